@@ -0,0 +1,100 @@
+// Example plugin gauge: system uptime from /proc/uptime, loaded through the shared-library plugin
+// interface. Build and install it with:
+//
+//     cargo build --release --example plugin_gauge
+//     cp target/release/examples/libplugin_gauge.so ~/.config/grelier/plugins/
+//
+// then enable it with `grelier -s grelier.gauges=clock,uptime`.
+use std::ffi::{c_char, c_void};
+use std::fs;
+
+/// Mirror of grelier's `GrelierGaugeOutput`.
+#[repr(C)]
+pub struct GrelierGaugeOutput {
+    text: [c_char; 32],
+    attention: u32,
+    info: [c_char; 1024],
+}
+
+/// Mirror of grelier's `GrelierGaugeDescriptor`, ABI version 1.
+#[repr(C)]
+pub struct GrelierGaugeDescriptor {
+    abi_version: u32,
+    id: *const c_char,
+    description: *const c_char,
+    icon: *const c_char,
+    interval_ms: u32,
+    create: Option<unsafe extern "C" fn() -> *mut c_void>,
+    poll: Option<unsafe extern "C" fn(*mut c_void, *mut GrelierGaugeOutput) -> i32>,
+    destroy: Option<unsafe extern "C" fn(*mut c_void)>,
+}
+
+/// The descriptor only holds pointers to static strings, so sharing it between threads is sound.
+struct Descriptor(GrelierGaugeDescriptor);
+
+unsafe impl Sync for Descriptor {}
+
+static DESCRIPTOR: Descriptor = Descriptor(GrelierGaugeDescriptor {
+    abi_version: 1,
+    id: c"uptime".as_ptr(),
+    description: c"Example plugin gauge showing system uptime.".as_ptr(),
+    // Null selects grelier's default plugin icon.
+    icon: std::ptr::null(),
+    interval_ms: 60_000,
+    create: None,
+    poll: Some(poll),
+    destroy: None,
+});
+
+/// Copy `text` into a fixed buffer, truncating so the terminating NUL always fits.
+fn fill(buffer: &mut [c_char], text: &str) {
+    let len = text.len().min(buffer.len() - 1);
+    for (slot, byte) in buffer.iter_mut().zip(&text.as_bytes()[..len]) {
+        *slot = *byte as c_char;
+    }
+    buffer[len] = 0;
+}
+
+/// Seconds since boot, from the first field of /proc/uptime.
+fn uptime_secs() -> Option<u64> {
+    let uptime = fs::read_to_string("/proc/uptime").ok()?;
+    let secs: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+    Some(secs as u64)
+}
+
+/// Compact bar text, such as "45m", "7h", or "12d".
+fn label(secs: u64) -> String {
+    match secs {
+        0..3600 => format!("{}m", secs / 60),
+        3600..86_400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+unsafe extern "C" fn poll(_state: *mut c_void, output: *mut GrelierGaugeOutput) -> i32 {
+    // SAFETY: grelier passes a valid, exclusive output buffer for the duration of the call.
+    let Some(output) = (unsafe { output.as_mut() }) else {
+        return 1;
+    };
+    let Some(secs) = uptime_secs() else {
+        fill(&mut output.info, "Cannot read /proc/uptime");
+        return 1;
+    };
+    let info = format!(
+        "Up {} days, {} hours, {} minutes",
+        secs / 86_400,
+        secs % 86_400 / 3600,
+        secs % 3600 / 60
+    );
+    fill(&mut output.text, &label(secs));
+    fill(&mut output.info, &info);
+    // A machine that has not been rebooted in a month is probably missing kernel updates.
+    output.attention = u32::from(secs >= 30 * 86_400);
+    0
+}
+
+/// Plugin entry point looked up by grelier when it loads the library.
+#[unsafe(no_mangle)]
+pub extern "C" fn grelier_gauge_v1() -> *const GrelierGaugeDescriptor {
+    &DESCRIPTOR.0
+}
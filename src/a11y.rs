@@ -0,0 +1,606 @@
+// Accessible names, roles, and values for what the bar shows, plus live announcements when a
+// gauge enters danger attention. iced 0.14 and iced_layershell have no AccessKit integration, so
+// the bar runs its own AccessKit adapter: workspace buttons and gauges are published on the
+// AT-SPI bus for Orca, and the same labels go out over the control socket (`label` fields and the
+// `announce` event topic). Open menus are published with their entries, and element bounds are
+// measured from the bar's layout so screen readers can point at what they describe.
+use crate::bar::{GaugeDialog, GaugeDialogWindow};
+use crate::compositor::WorkspaceInfo;
+use crate::panels::gauges::gauge::{
+    GaugeActionItem, GaugeDisplay, GaugeMenuItem, GaugeModel, GaugeValue, GaugeValueAttention,
+};
+use accesskit::{
+    ActionHandler, ActionRequest, ActivationHandler, DeactivationHandler, Live, Node, NodeId, Rect,
+    TreeId, TreeInfo, TreeUpdate,
+};
+use iced::widget::Id;
+use iced::{Rectangle, Size, Task, window};
+use iced_core::widget::Operation;
+use iced_core::widget::operation::Outcome;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// What an element is to assistive technology.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// A workspace button.
+    Button,
+    /// A gauge showing a reading.
+    Meter,
+    /// A menu entry without a check state.
+    MenuItem,
+    /// A menu entry that can be selected, such as an output device.
+    CheckMenuItem,
+}
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Button => "button",
+            Role::Meter => "meter",
+            Role::MenuItem => "menu item",
+            Role::CheckMenuItem => "check menu item",
+        }
+    }
+
+    fn accesskit(self) -> accesskit::Role {
+        match self {
+            Role::Button => accesskit::Role::Button,
+            Role::Meter => accesskit::Role::Meter,
+            Role::MenuItem => accesskit::Role::MenuItem,
+            Role::CheckMenuItem => accesskit::Role::MenuItemCheckBox,
+        }
+    }
+}
+
+/// Spoken name for a gauge id: `disk_usage` reads as "Disk usage".
+fn gauge_name(id: &str) -> String {
+    let spaced = id.replace('_', " ");
+    let mut chars = spaced.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn attention_suffix(attention: GaugeValueAttention) -> &'static str {
+    match attention {
+        GaugeValueAttention::Nominal => "",
+        GaugeValueAttention::Warning => ", warning",
+        GaugeValueAttention::Danger => ", danger",
+    }
+}
+
+/// Spoken value of a gauge. Icon-only values fall back to the first line of the gauge's info
+/// dialog, which describes the same reading in words.
+fn gauge_value(model: &GaugeModel) -> String {
+    let info_line = || {
+        model
+            .interactions
+            .left_click
+            .info
+            .as_ref()
+            .and_then(|info| info.lines.first().cloned())
+    };
+    match &model.display {
+        GaugeDisplay::Value { value, attention } => {
+            let value = match value {
+                GaugeValue::Text(text) => Some(text.clone()),
+                GaugeValue::Svg(_) => info_line(),
+            };
+            format!(
+                "{}{}",
+                value.unwrap_or_else(|| "no reading".to_string()),
+                attention_suffix(*attention)
+            )
+        }
+        GaugeDisplay::Graph { samples, attention } => {
+            let latest = samples
+                .last()
+                .map(|sample| format!("{:.0}%", sample * 100.0))
+                .or_else(info_line)
+                .unwrap_or_else(|| "no reading".to_string());
+            format!("{latest}{}", attention_suffix(*attention))
+        }
+        GaugeDisplay::Empty => info_line().unwrap_or_else(|| "no reading".to_string()),
+        GaugeDisplay::Error => "unavailable".to_string(),
+    }
+}
+
+/// Role and label for a gauge, such as "Battery: 42%, warning".
+pub fn gauge_label(model: &GaugeModel) -> (Role, String) {
+    (
+        Role::Meter,
+        format!("{}: {}", gauge_name(model.id), gauge_value(model)),
+    )
+}
+
+/// Role and label for a workspace button, such as "Workspace 2, focused".
+pub fn workspace_label(workspace: &WorkspaceInfo) -> (Role, String) {
+    let mut label = format!("Workspace {}", workspace.label);
+    if workspace.focused {
+        label.push_str(", focused");
+    }
+    if workspace.urgent {
+        label.push_str(", urgent");
+    }
+    (Role::Button, label)
+}
+
+/// Role and label for a gauge menu entry; entries that can be selected read their state.
+pub fn menu_item_label(item: &GaugeMenuItem) -> (Role, String) {
+    if item.selected {
+        (Role::CheckMenuItem, format!("{}, selected", item.label))
+    } else {
+        (Role::MenuItem, item.label.clone())
+    }
+}
+
+/// Role and label for an action dialog entry, which shows only an icon: `lock_screen` reads as
+/// "Lock screen".
+pub fn action_item_label(item: &GaugeActionItem) -> (Role, String) {
+    (Role::MenuItem, gauge_name(&item.id))
+}
+
+/// Widget id of a workspace button, used to measure its bounds on the bar.
+pub fn workspace_id(workspace: &WorkspaceInfo) -> Id {
+    Id::from(format!("a11y-workspace-{}", workspace.name))
+}
+
+/// Widget id of a gauge, used to measure its bounds on the bar.
+pub fn gauge_id(id: &str) -> Id {
+    Id::from(format!("a11y-gauge-{id}"))
+}
+
+/// Remembers each gauge's attention so only transitions into danger are announced.
+#[derive(Debug, Default)]
+pub struct Announcer {
+    last: HashMap<&'static str, GaugeValueAttention>,
+}
+
+fn attention(display: &GaugeDisplay) -> GaugeValueAttention {
+    match display {
+        GaugeDisplay::Value { attention, .. } | GaugeDisplay::Graph { attention, .. } => *attention,
+        GaugeDisplay::Empty | GaugeDisplay::Error => GaugeValueAttention::Nominal,
+    }
+}
+
+impl Announcer {
+    /// The text to announce when `model` has just entered danger attention.
+    pub fn observe(&mut self, model: &GaugeModel) -> Option<String> {
+        let current = attention(&model.display);
+        let previous = self.last.insert(model.id, current);
+        (current == GaugeValueAttention::Danger && previous != Some(current))
+            .then(|| gauge_label(model).1)
+    }
+}
+
+/// Announcer shared by every bar of this process.
+pub fn announcer() -> &'static Mutex<Announcer> {
+    static ANNOUNCER: OnceLock<Mutex<Announcer>> = OnceLock::new();
+    ANNOUNCER.get_or_init(|| Mutex::new(Announcer::default()))
+}
+
+const ROOT_ID: NodeId = NodeId(0);
+const STATUS_ID: NodeId = NodeId(1);
+/// Ids of the remaining nodes start here, in bar order.
+const FIRST_ELEMENT_ID: u64 = 2;
+
+/// A workspace button or gauge on the bar.
+#[derive(Debug, Clone, PartialEq)]
+struct TreeElement {
+    id: Id,
+    role: Role,
+    label: String,
+    /// Position on the bar surface, once the layout has been measured.
+    bounds: Option<Rect>,
+}
+
+/// An open gauge menu or action dialog and its entries.
+#[derive(Debug, Clone, PartialEq)]
+struct TreeMenu {
+    label: String,
+    items: Vec<(Role, String)>,
+}
+
+/// What the AccessKit tree shows: the bar's elements, open menus, and the latest announcement.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct TreeState {
+    elements: Vec<TreeElement>,
+    menus: Vec<TreeMenu>,
+    /// Size of the bar surface, which the root window node covers.
+    window: Option<Rect>,
+    announcement: String,
+}
+
+fn rect(bounds: Rectangle) -> Rect {
+    Rect::new(
+        f64::from(bounds.x),
+        f64::from(bounds.y),
+        f64::from(bounds.x + bounds.width),
+        f64::from(bounds.y + bounds.height),
+    )
+}
+
+impl TreeState {
+    /// A full tree: a window holding the workspace buttons and gauges, then a menu per open
+    /// dialog, then a live status node that speaks announcements.
+    fn update(&self) -> TreeUpdate {
+        let mut root = Node::new(accesskit::Role::Window);
+        root.set_label("grelier bar");
+        if let Some(window) = self.window {
+            root.set_bounds(window);
+        }
+        let mut status = Node::new(accesskit::Role::Status);
+        status.set_live(Live::Assertive);
+        status.set_label(self.announcement.as_str());
+
+        let mut nodes = Vec::with_capacity(self.elements.len() + self.menus.len() + 2);
+        let mut children = Vec::with_capacity(self.elements.len() + self.menus.len() + 1);
+        let mut ids = (FIRST_ELEMENT_ID..).map(NodeId);
+        for element in &self.elements {
+            let id = ids.next().expect("node ids");
+            let mut node = Node::new(element.role.accesskit());
+            node.set_label(element.label.as_str());
+            if let Some(bounds) = element.bounds {
+                node.set_bounds(bounds);
+            }
+            nodes.push((id, node));
+            children.push(id);
+        }
+        for menu in &self.menus {
+            let menu_id = ids.next().expect("node ids");
+            let mut node = Node::new(accesskit::Role::Menu);
+            node.set_label(menu.label.as_str());
+            let mut items = Vec::with_capacity(menu.items.len());
+            for (role, label) in &menu.items {
+                let id = ids.next().expect("node ids");
+                let mut item = Node::new(role.accesskit());
+                item.set_label(label.as_str());
+                nodes.push((id, item));
+                items.push(id);
+            }
+            node.set_children(items);
+            nodes.push((menu_id, node));
+            children.push(menu_id);
+        }
+        children.push(STATUS_ID);
+        root.set_children(children);
+        nodes.push((STATUS_ID, status));
+        nodes.push((ROOT_ID, root));
+        TreeUpdate {
+            nodes,
+            tree: Some(TreeInfo::new(ROOT_ID)),
+            tree_id: TreeId::ROOT,
+            focus: ROOT_ID,
+        }
+    }
+}
+
+fn tree_state() -> &'static Mutex<TreeState> {
+    static STATE: OnceLock<Mutex<TreeState>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(TreeState::default()))
+}
+
+fn adapter() -> &'static OnceLock<Mutex<accesskit_unix::Adapter>> {
+    static ADAPTER: OnceLock<Mutex<accesskit_unix::Adapter>> = OnceLock::new();
+    &ADAPTER
+}
+
+/// Set while a screen reader is reading the tree.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Whether a screen reader is connected; layout is only measured for one.
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Builds the first tree when a screen reader connects.
+struct Activation;
+
+impl ActivationHandler for Activation {
+    fn request_initial_tree(&mut self) -> Option<TreeUpdate> {
+        ACTIVE.store(true, Ordering::Relaxed);
+        tree_state().lock().ok().map(|state| state.update())
+    }
+}
+
+/// The tree is read-only: workspace buttons are still clicked through the bar itself.
+struct Actions;
+
+impl ActionHandler for Actions {
+    fn do_action(&mut self, _request: ActionRequest) {}
+}
+
+struct Deactivation;
+
+impl DeactivationHandler for Deactivation {
+    fn deactivate_accessibility(&mut self) {
+        ACTIVE.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Register the bar on the AT-SPI bus. Until a screen reader is running this costs nothing
+/// beyond the adapter's idle thread.
+pub fn start() {
+    adapter().get_or_init(|| {
+        Mutex::new(accesskit_unix::Adapter::new(
+            Activation,
+            Actions,
+            Deactivation,
+        ))
+    });
+}
+
+/// Apply `change` to the tree state and push the tree to the adapter if anything changed.
+fn update_tree(change: impl FnOnce(&mut TreeState)) {
+    let Ok(mut state) = tree_state().lock() else {
+        return;
+    };
+    let previous = state.clone();
+    change(&mut state);
+    if *state == previous {
+        return;
+    }
+    if let Some(adapter) = adapter().get()
+        && let Ok(mut adapter) = adapter.lock()
+    {
+        if state.window != previous.window
+            && let Some(window) = state.window
+        {
+            // A layer surface cannot learn its position on screen, so the window is placed at
+            // the origin and element bounds stay relative to the bar.
+            adapter.set_root_window_bounds(window, window);
+        }
+        adapter.update_if_active(|| state.update());
+    }
+}
+
+/// The menu shown by an open gauge dialog, if it lists entries.
+fn dialog_menu(window: &GaugeDialogWindow) -> Option<TreeMenu> {
+    let (title, items) = match &window.dialog {
+        GaugeDialog::Menu(menu) => (
+            &menu.title,
+            menu.items.iter().map(menu_item_label).collect(),
+        ),
+        GaugeDialog::Action(dialog) => (
+            &dialog.title,
+            dialog.items.iter().map(action_item_label).collect(),
+        ),
+        GaugeDialog::Info(_) | GaugeDialog::Prompt(_) => return None,
+    };
+    let label = if title.is_empty() {
+        gauge_name(&window.gauge_id)
+    } else {
+        title.clone()
+    };
+    Some(TreeMenu { label, items })
+}
+
+/// Publish the current workspace buttons, gauges, and open menus to the AccessKit tree.
+/// Returns true when the bar's elements changed, so their bounds should be measured again.
+pub fn publish_elements(
+    workspaces: &[WorkspaceInfo],
+    gauges: &[GaugeModel],
+    dialogs: &HashMap<window::Id, GaugeDialogWindow>,
+) -> bool {
+    let labels: Vec<(Id, (Role, String))> = workspaces
+        .iter()
+        .map(|workspace| (workspace_id(workspace), workspace_label(workspace)))
+        .chain(
+            gauges
+                .iter()
+                .map(|gauge| (gauge_id(gauge.id), gauge_label(gauge))),
+        )
+        .collect();
+    let mut windows: Vec<_> = dialogs.iter().collect();
+    windows.sort_by_key(|(id, _)| **id);
+    let menus: Vec<TreeMenu> = windows
+        .into_iter()
+        .filter_map(|(_, window)| dialog_menu(window))
+        .collect();
+    let mut changed = false;
+    update_tree(|state| {
+        changed = !state
+            .elements
+            .iter()
+            .map(|element| (&element.id, element.role, &element.label))
+            .eq(labels.iter().map(|(id, (role, label))| (id, *role, label)));
+        let mut previous: HashMap<Id, Option<Rect>> = state
+            .elements
+            .drain(..)
+            .map(|element| (element.id, element.bounds))
+            .collect();
+        state.elements = labels
+            .into_iter()
+            .map(|(id, (role, label))| TreeElement {
+                bounds: previous.remove(&id).flatten(),
+                id,
+                role,
+                label,
+            })
+            .collect();
+        state.menus = menus;
+    });
+    changed
+}
+
+/// Size of the bar surface, which the tree's root window covers.
+pub fn set_window_size(size: Size) {
+    update_tree(|state| {
+        state.window = Some(rect(Rectangle::with_size(size)));
+    });
+}
+
+/// Collects the layout bounds of the bar's workspace buttons and gauges by widget id.
+struct ElementBounds {
+    ids: Vec<Id>,
+    found: Vec<(Id, Rectangle)>,
+}
+
+impl Operation<Vec<(Id, Rectangle)>> for ElementBounds {
+    fn traverse(&mut self, operate: &mut dyn FnMut(&mut dyn Operation<Vec<(Id, Rectangle)>>)) {
+        operate(self);
+    }
+
+    fn container(&mut self, id: Option<&Id>, bounds: Rectangle) {
+        if let Some(id) = id
+            && self.ids.contains(id)
+        {
+            self.found.push((id.clone(), bounds));
+        }
+    }
+
+    fn finish(&self) -> Outcome<Vec<(Id, Rectangle)>> {
+        Outcome::Some(self.found.clone())
+    }
+}
+
+/// Measure where the published elements sit on the bar.
+pub fn measure_bounds() -> Task<Vec<(Id, Rectangle)>> {
+    let ids = tree_state()
+        .lock()
+        .map(|state| {
+            state
+                .elements
+                .iter()
+                .map(|element| element.id.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    iced::advanced::widget::operate(ElementBounds {
+        ids,
+        found: Vec::new(),
+    })
+}
+
+/// Record measured element bounds in the tree.
+pub fn set_bounds(bounds: Vec<(Id, Rectangle)>) {
+    let bounds: HashMap<Id, Rectangle> = bounds.into_iter().collect();
+    update_tree(|state| {
+        for element in &mut state.elements {
+            element.bounds = bounds.get(&element.id).copied().map(rect);
+        }
+    });
+}
+
+/// Speak `text` through the tree's live status node.
+pub fn announce(text: &str) {
+    // Screen readers speak a live node when its label changes, so clear it first; otherwise
+    // the same alert raised twice in a row would be spoken only once.
+    update_tree(|state| state.announcement.clear());
+    update_tree(|state| state.announcement = text.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icon::svg_asset;
+    use crate::panels::gauges::gauge::GaugeInteractionModel;
+
+    fn disk_usage(text: &str, attention: GaugeValueAttention) -> GaugeModel {
+        GaugeModel {
+            id: "disk_usage",
+            icon: svg_asset("ratio-0.svg"),
+            display: GaugeDisplay::Value {
+                value: GaugeValue::Text(text.to_string()),
+                attention,
+            },
+            interactions: GaugeInteractionModel::default(),
+        }
+    }
+
+    #[test]
+    fn labels_gauges_and_announces_only_entering_danger() {
+        assert_eq!(
+            gauge_label(&disk_usage("42%", GaugeValueAttention::Warning)),
+            (Role::Meter, "Disk usage: 42%, warning".to_string())
+        );
+        let item = GaugeMenuItem {
+            id: "hdmi".to_string(),
+            label: "HDMI".to_string(),
+            selected: true,
+            exec: None,
+        };
+        assert_eq!(
+            menu_item_label(&item),
+            (Role::CheckMenuItem, "HDMI, selected".to_string())
+        );
+
+        let mut announcer = Announcer::default();
+        assert_eq!(
+            announcer.observe(&disk_usage("12%", GaugeValueAttention::Nominal)),
+            None
+        );
+        assert_eq!(
+            announcer.observe(&disk_usage("4%", GaugeValueAttention::Danger)),
+            Some("Disk usage: 4%, danger".to_string())
+        );
+        assert_eq!(
+            announcer.observe(&disk_usage("3%", GaugeValueAttention::Danger)),
+            None
+        );
+    }
+
+    #[test]
+    fn tree_lists_elements_then_the_live_status() {
+        let element = |id: &'static str, (role, label), bounds| TreeElement {
+            id: Id::new(id),
+            role,
+            label,
+            bounds,
+        };
+        let gauge_bounds = Rect::new(0.0, 40.0, 28.0, 64.0);
+        let state = TreeState {
+            elements: vec![
+                element(
+                    "ws",
+                    (Role::Button, "Workspace 1, focused".to_string()),
+                    None,
+                ),
+                element(
+                    "disk",
+                    gauge_label(&disk_usage("42%", GaugeValueAttention::Nominal)),
+                    Some(gauge_bounds),
+                ),
+            ],
+            menus: Vec::new(),
+            window: None,
+            announcement: String::new(),
+        };
+        let update = state.update();
+        let (_, root) = update.nodes.last().expect("root node");
+        assert_eq!(root.children(), [NodeId(2), NodeId(3), STATUS_ID]);
+        let (_, gauge) = &update.nodes[1];
+        assert_eq!(gauge.role(), accesskit::Role::Meter);
+        assert_eq!(gauge.label(), Some("Disk usage: 42%"));
+        assert_eq!(gauge.bounds(), Some(gauge_bounds));
+    }
+
+    #[test]
+    fn open_menus_follow_the_bar_elements() {
+        let state = TreeState {
+            menus: vec![TreeMenu {
+                label: "Output".to_string(),
+                items: vec![
+                    (Role::CheckMenuItem, "HDMI, selected".to_string()),
+                    (Role::MenuItem, "Speakers".to_string()),
+                ],
+            }],
+            window: Some(Rect::new(0.0, 0.0, 28.0, 1080.0)),
+            ..TreeState::default()
+        };
+        let update = state.update();
+        let (_, root) = update.nodes.last().expect("root node");
+        assert_eq!(root.children(), [NodeId(2), STATUS_ID]);
+        assert_eq!(root.bounds(), Some(Rect::new(0.0, 0.0, 28.0, 1080.0)));
+        let (menu_id, menu) = &update.nodes[2];
+        assert_eq!(*menu_id, NodeId(2));
+        assert_eq!(menu.role(), accesskit::Role::Menu);
+        assert_eq!(menu.children(), [NodeId(3), NodeId(4)]);
+        let (_, item) = &update.nodes[0];
+        assert_eq!(item.role(), accesskit::Role::MenuItemCheckBox);
+        assert_eq!(item.label(), Some("HDMI, selected"));
+    }
+}
@@ -2,6 +2,28 @@ use crate::bar::AppIconCache;
 use elbey_cache::{AppDescriptor, Cache};
 use freedesktop_desktop_entry::desktop_entries;
 use locale_config::Locale;
+use std::collections::HashMap;
+
+/// Setting holding the `;`-separated desktop-entry categories shown by the top apps panel.
+pub const TOP_APPS_CATEGORIES_KEY: &str = "grelier.app.top_apps.categories";
+
+/// Main categories from the freedesktop menu specification, offered in the filter menu.
+pub const MAIN_CATEGORIES: &[&str] = &[
+    "AudioVideo",
+    "Development",
+    "Education",
+    "Game",
+    "Graphics",
+    "Network",
+    "Office",
+    "Science",
+    "Settings",
+    "System",
+    "Utility",
+];
+
+/// How many ranked apps to consider when a category filter drops some of them.
+const FILTERED_POOL_SIZE: usize = 256;
 
 pub fn load_desktop_apps() -> Vec<AppDescriptor> {
     let locales: Vec<String> = Locale::user_default()
@@ -14,6 +36,100 @@ pub fn load_desktop_apps() -> Vec<AppDescriptor> {
         .collect()
 }
 
+/// A desktop entry action, such as a browser's "New Private Window".
+#[derive(Debug, Clone)]
+pub struct AppAction {
+    pub name: String,
+    /// Command line with field codes removed.
+    pub argv: Vec<String>,
+}
+
+/// Desktop entry actions of every installed app, keyed by app id.
+pub fn load_app_actions() -> HashMap<String, Vec<AppAction>> {
+    let locales: Vec<String> = Locale::user_default()
+        .tags()
+        .map(|(_, tag)| tag.to_string())
+        .collect();
+    desktop_entries(&locales)
+        .into_iter()
+        .filter_map(|entry| {
+            let actions: Vec<AppAction> = entry
+                .actions()?
+                .into_iter()
+                .filter(|action| !action.is_empty())
+                .filter_map(|action| {
+                    Some(AppAction {
+                        name: entry.action_name(action, &locales)?.into_owned(),
+                        argv: entry.parse_exec_action(action).ok()?,
+                    })
+                })
+                .collect();
+            (!actions.is_empty()).then(|| (entry.appid.clone(), actions))
+        })
+        .collect()
+}
+
+/// Desktop-entry categories for every installed app, keyed by app id.
+pub fn load_app_categories() -> HashMap<String, Vec<String>> {
+    let locales: Vec<String> = Locale::user_default()
+        .tags()
+        .map(|(_, tag)| tag.to_string())
+        .collect();
+    desktop_entries(&locales)
+        .into_iter()
+        .map(|entry| {
+            let categories = entry
+                .categories()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|category| !category.is_empty())
+                .map(ToString::to_string)
+                .collect();
+            (entry.appid.clone(), categories)
+        })
+        .collect()
+}
+
+/// Parse a category filter such as `Development;Network` (commas are accepted too).
+pub fn parse_categories(raw: &str) -> Vec<String> {
+    raw.split([';', ','])
+        .map(str::trim)
+        .filter(|category| !category.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Number of ranked apps to load so that `count` remain after filtering.
+pub fn top_apps_pool_size(count: usize, filter: &[String]) -> usize {
+    if filter.is_empty() || count == 0 {
+        count
+    } else {
+        count.max(FILTERED_POOL_SIZE)
+    }
+}
+
+/// Keep the first `count` apps carrying any category in `filter`; an empty filter keeps all.
+pub fn filter_top_apps(
+    apps: Vec<AppDescriptor>,
+    filter: &[String],
+    categories: &HashMap<String, Vec<String>>,
+    count: usize,
+) -> Vec<AppDescriptor> {
+    apps.into_iter()
+        .filter(|app| {
+            filter.is_empty()
+                || categories.get(&app.appid).is_some_and(|app_categories| {
+                    app_categories.iter().any(|category| {
+                        filter
+                            .iter()
+                            .any(|wanted| wanted.eq_ignore_ascii_case(category))
+                    })
+                })
+        })
+        .take(count)
+        .collect()
+}
+
 pub fn load_cached_apps_from_cache(
     cache: &mut Cache,
     top_count: usize,
@@ -44,3 +160,51 @@ pub fn load_cached_apps_from_cache(
 
     (apps, app_icons, top_apps)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(appid: &str) -> AppDescriptor {
+        AppDescriptor {
+            appid: appid.to_string(),
+            title: appid.to_string(),
+            lower_title: appid.to_string(),
+            exec: None,
+            exec_count: 1,
+            icon_name: None,
+            icon_path: None,
+            icon_handle: elbey_cache::IconHandle::NotLoaded,
+        }
+    }
+
+    #[test]
+    fn filters_top_apps_by_category() {
+        let categories = HashMap::from([
+            ("code".to_string(), vec!["Development".to_string()]),
+            (
+                "firefox".to_string(),
+                vec!["Network".to_string(), "WebBrowser".to_string()],
+            ),
+            ("gimp".to_string(), vec!["Graphics".to_string()]),
+        ]);
+        let apps = || vec![app("code"), app("firefox"), app("gimp"), app("unknown")];
+        let ids = |apps: Vec<AppDescriptor>| -> Vec<String> {
+            apps.into_iter().map(|app| app.appid).collect()
+        };
+
+        let filter = parse_categories(" development; Network ,");
+        assert_eq!(filter, vec!["development", "Network"]);
+        assert_eq!(
+            ids(filter_top_apps(apps(), &filter, &categories, 6)),
+            vec!["code", "firefox"]
+        );
+        assert_eq!(
+            ids(filter_top_apps(apps(), &filter, &categories, 1)),
+            vec!["code"]
+        );
+        assert_eq!(ids(filter_top_apps(apps(), &[], &categories, 3)).len(), 3);
+        assert_eq!(top_apps_pool_size(6, &[]), 6);
+        assert_eq!(top_apps_pool_size(6, &filter), FILTERED_POOL_SIZE);
+    }
+}
@@ -0,0 +1,173 @@
+// Autohide: the bar rests mostly off-screen and slides out when the pointer reaches its edge.
+// The surface keeps its full width; a negative layer-shell margin on the anchored edge pushes
+// all but a `peek`-pixel strip off-screen, and that strip is the hot zone the pointer hits.
+// Consumes Settings: grelier.bar.autohide, grelier.bar.autohide.*.
+use crate::settings::Settings;
+use std::time::Duration;
+
+pub const AUTOHIDE_KEY: &str = "grelier.bar.autohide";
+pub const PEEK_KEY: &str = "grelier.bar.autohide.peek";
+pub const DELAY_KEY: &str = "grelier.bar.autohide.delay_ms";
+/// Time between slide animation frames.
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+/// Frames a full slide takes.
+const SLIDE_FRAMES: i32 = 8;
+
+/// Slide state of an autohiding bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Autohide {
+    /// Full bar width in pixels.
+    width: i32,
+    /// Pixels left on screen while hidden.
+    peek: i32,
+    /// Anchored to the left edge; the margin goes on that side.
+    left: bool,
+    /// How far the bar is pushed off-screen; 0 when fully shown.
+    offset: i32,
+    /// Whether the bar is sliding (or has slid) out.
+    revealed: bool,
+    /// Sliding toward the target; the bar subscribes to frame ticks while this is set.
+    animating: bool,
+    /// Bumped on every reveal and pointer leave so only the latest hide timer applies.
+    pub hide_generation: u64,
+    /// Delay after the pointer leaves before the bar slides back.
+    pub hide_delay: Duration,
+}
+
+impl Autohide {
+    /// Autohide state starting hidden, or `None` when `grelier.bar.autohide` is off.
+    pub fn from_settings(settings: &Settings, width: u32, left: bool) -> Option<Self> {
+        settings.get_bool_or(AUTOHIDE_KEY, false).then(|| {
+            Self::new(
+                width,
+                settings.get_parsed_or(PEEK_KEY, 2u32),
+                left,
+                Duration::from_millis(settings.get_parsed_or(DELAY_KEY, 500u64)),
+            )
+        })
+    }
+
+    fn new(width: u32, peek: u32, left: bool, hide_delay: Duration) -> Self {
+        let width = width as i32;
+        let mut autohide = Self {
+            width,
+            peek: (peek as i32).clamp(1, width.max(1)),
+            left,
+            offset: 0,
+            revealed: false,
+            animating: false,
+            hide_generation: 0,
+            hide_delay,
+        };
+        autohide.offset = autohide.hidden_offset();
+        autohide
+    }
+
+    fn hidden_offset(&self) -> i32 {
+        (self.width - self.peek).max(0)
+    }
+
+    fn target(&self) -> i32 {
+        if self.revealed {
+            0
+        } else {
+            self.hidden_offset()
+        }
+    }
+
+    /// Layer-shell margin `base` with the anchored edge replaced by the slide offset.
+    pub fn apply_to(&self, base: (i32, i32, i32, i32)) -> (i32, i32, i32, i32) {
+        let (top, right, bottom, left) = base;
+        if self.left {
+            (top, right, bottom, -self.offset)
+        } else {
+            (top, -self.offset, bottom, left)
+        }
+    }
+
+    /// Only the peek strip is reserved, so windows never sit under the hot zone and do not
+    /// reflow while the bar slides over them.
+    pub fn exclusive_zone(&self) -> i32 {
+        self.peek
+    }
+
+    /// Whether the slide still needs frames.
+    pub fn is_animating(&self) -> bool {
+        self.animating
+    }
+
+    /// Slide out; returns whether a slide has started.
+    pub fn reveal(&mut self) -> bool {
+        self.hide_generation = self.hide_generation.wrapping_add(1);
+        self.revealed = true;
+        self.start()
+    }
+
+    /// Slide back; returns whether a slide has started.
+    pub fn hide(&mut self) -> bool {
+        self.revealed = false;
+        self.start()
+    }
+
+    fn start(&mut self) -> bool {
+        if self.animating || self.offset == self.target() {
+            return false;
+        }
+        self.animating = true;
+        true
+    }
+
+    /// Jump straight to the target, for when animations are off.
+    pub fn finish(&mut self) {
+        self.offset = self.target();
+        self.animating = false;
+    }
+
+    /// Advance one frame toward the target; returns whether another frame is needed.
+    pub fn step(&mut self) -> bool {
+        let target = self.target();
+        let stride = (self.hidden_offset() + SLIDE_FRAMES - 1) / SLIDE_FRAMES;
+        self.offset = if self.offset < target {
+            (self.offset + stride.max(1)).min(target)
+        } else {
+            (self.offset - stride.max(1)).max(target)
+        };
+        self.animating = self.offset != target;
+        self.animating
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NO_MARGIN: (i32, i32, i32, i32) = (0, 0, 0, 0);
+
+    #[test]
+    fn slides_between_peek_strip_and_full_width() {
+        let delay = Duration::from_millis(500);
+        let mut autohide = Autohide::new(28, 4, true, delay);
+        assert_eq!(autohide.apply_to(NO_MARGIN), (0, 0, 0, -24));
+        assert_eq!(autohide.exclusive_zone(), 4);
+
+        assert!(autohide.reveal());
+        assert!(!autohide.reveal(), "a running slide is not restarted");
+        let mut frames = 1;
+        while autohide.step() {
+            frames += 1;
+        }
+        assert_eq!(frames, SLIDE_FRAMES);
+        assert_eq!(autohide.apply_to(NO_MARGIN), (0, 0, 0, 0));
+
+        assert!(autohide.hide());
+        autohide.step();
+        assert_eq!(autohide.apply_to(NO_MARGIN), (0, 0, 0, -3));
+
+        assert_eq!(
+            Autohide::new(28, 4, false, delay).apply_to(NO_MARGIN),
+            (0, -24, 0, 0)
+        );
+        assert_eq!(autohide.apply_to((8, 6, 8, 6)), (8, 6, 8, -3));
+        assert_eq!(Autohide::new(28, 0, true, delay).exclusive_zone(), 1);
+    }
+}
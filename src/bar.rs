@@ -1,26 +1,54 @@
 // Bar application state, update handling, and view composition for panels.
-// Consumes Settings: grelier.bar.width, grelier.bar.border.*.
-use std::collections::{HashMap, HashSet};
+// Consumes Settings: grelier.bar.width, grelier.bar.border.*, grelier.bar.lock.*,
+// grelier.bar.margin.*, grelier.bar.radius.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use crate::compositor::{FocusedWindow, WorkspaceApps, WorkspaceInfo};
 use crate::dialog::action::{action_view, dialog_dimensions as action_dialog_dimensions};
 use crate::dialog::info::{InfoDialog, dialog_dimensions as info_dialog_dimensions, info_view};
 use crate::dialog::menu::{dialog_dimensions as menu_dialog_dimensions, menu_view};
-use crate::panels::gauges::gauge::{GaugeActionDialog, GaugeInput, GaugeMenu, GaugeModel};
+use crate::dialog::prompt::{
+    PromptDialog, dialog_dimensions as prompt_dialog_dimensions, prompt_view,
+};
+use crate::dialog::toast::{self, Toast, ToastWindow, toast_view};
+use crate::icon::QuantityAtlasCache;
+use crate::panels::gauges::gauge::{
+    GaugeActionDialog, GaugeInput, GaugeMenu, GaugeModel, GaugeUpdate,
+};
 use crate::panels::panel_registry;
 use crate::settings;
-use crate::sway_workspace::{WorkspaceApps, WorkspaceInfo};
+use crate::window_tracker::WindowTracker;
 use elbey_cache::{AppDescriptor, FALLBACK_ICON_HANDLE, IconHandle};
 use iced::alignment;
+use iced::widget::canvas::{self, Canvas};
 use iced::widget::image::Image;
-use iced::widget::svg::Svg;
-use iced::widget::{Column, Row, Space, Stack, container, mouse_area, rule};
-use iced::{Color, Element, Length, Task, Theme, mouse, window};
+use iced::widget::svg::{self, Svg};
+use iced::widget::{Column, Row, Space, Stack, container, mouse_area, rule, text};
+use iced::{Border, Color, Element, Length, Point, Rectangle, Size, Task, Theme, mouse, window};
+use iced_anim::transition::Easing;
 use iced_layershell::actions::IcedNewPopupSettings;
+use iced_layershell::reexport::Anchor;
 use iced_layershell::to_layer_message;
 
 const CLICK_FILTER_WINDOW: Duration = Duration::from_millis(250);
+pub const LOCKED_KEY: &str = "grelier.bar.lock.locked";
+pub const LOCK_LONG_PRESS_KEY: &str = "grelier.bar.lock.long_press_ms";
+const LOCK_INDICATOR_SIZE: f32 = 10.0;
+pub const ALIGN_KEY: &str = "grelier.bar.align";
+pub const HOVER_HIGHLIGHT_KEY: &str = "grelier.bar.hover_highlight";
+pub const ANIMATIONS_KEY: &str = "grelier.bar.animations";
+/// Length of an "instant" transition; a zero duration would divide by zero in the easing.
+const INSTANT_TRANSITION: Duration = Duration::from_millis(1);
+pub const SCALE_KEY: &str = "grelier.bar.scale";
+pub const MARGIN_TOP_KEY: &str = "grelier.bar.margin.top";
+pub const MARGIN_RIGHT_KEY: &str = "grelier.bar.margin.right";
+pub const MARGIN_BOTTOM_KEY: &str = "grelier.bar.margin.bottom";
+pub const MARGIN_LEFT_KEY: &str = "grelier.bar.margin.left";
+pub const RADIUS_KEY: &str = "grelier.bar.radius";
+const HOVER_HIGHLIGHT_ALPHA: f32 = 0.12;
+const HOVER_HIGHLIGHT_RADIUS: f32 = 4.0;
 
 /// Application-level messages for the bar, panels, and dialogs.
 #[to_layer_message(multi)]
@@ -33,6 +61,23 @@ pub enum Message {
         apps: Vec<WorkspaceApps>,
     },
     WorkspaceClicked(String),
+    /// Debounce timer for workspace auto-naming; stale generations are ignored.
+    WorkspaceAutoName(u64),
+    /// Left button pressed on a workspace; starts a possible drag.
+    WorkspacePressed(String),
+    /// Left button released on a workspace: a click, or a drop when dragged elsewhere.
+    WorkspaceReleased(String),
+    /// Wheel over the workspace list: focus the next (`true`) or previous workspace.
+    WorkspaceScrolled {
+        next: bool,
+    },
+    /// Pointer left the workspace list; drops any focus-history preview without focusing it.
+    WorkspaceHistoryCancelled,
+    /// Middle/right click or double click on a workspace, mapped to an action in settings.
+    WorkspaceInput {
+        name: String,
+        input: crate::panels::ws_panel::WorkspaceInput,
+    },
     WorkspaceAppClicked {
         con_id: i64,
         app_id: String,
@@ -40,8 +85,77 @@ pub enum Message {
     TopAppClicked {
         app_id: String,
     },
+    /// The busy marker for a top app launch started at `started` has run out.
+    TopAppLaunchTimeout {
+        app_id: String,
+        started: Instant,
+    },
+    /// Middle-click on a taskbar icon: close that window.
+    TaskbarCloseRequested(i64),
+    /// Right-click on a taskbar icon: open the move-to-workspace menu.
+    TaskbarMenuRequested {
+        con_id: i64,
+        app_id: String,
+    },
+    /// Workspace picked from the taskbar menu for the window it was opened on.
+    TaskbarMoveSelected(String),
+    /// Pointer entered a clickable item; over a workspace this also moves the drop target.
+    HoverEnter(HoverTarget),
+    HoverExit(HoverTarget),
+    /// The focused window changed; `None` when no window has focus.
+    FocusedWindow(Option<FocusedWindow>),
+    /// Hide timer for the autohiding bar; stale generations are ignored.
+    AutohideHide(u64),
+    /// Next frame of the autohide slide animation.
+    AutohideFrame,
+    /// Advance the scrolling title of the focused window by one character.
+    FocusedWindowTick,
+    /// Next pulse of the gauge loading placeholders.
+    GaugeLoadingTick,
+    /// Click on the overview panel button: open or close the workspace overview.
+    OverviewToggled,
+    /// Escape or a click on the overview backdrop.
+    OverviewClosed,
+    /// Workspace card picked in the overview.
+    OverviewWorkspaceSelected(String),
+    /// Window picked in the overview, by container id.
+    OverviewWindowSelected(i64),
+    /// Right-click on the top apps panel: open the category filter menu.
+    TopAppsMenuRequested,
+    TopAppsCategorySelected(String),
+    /// Right-click on the bar background: open the theme menu, which also lists gauge diagnostics.
+    ThemeMenuRequested,
+    ThemeSelected(String),
+    /// Show the gauge diagnostics dialog.
+    GaugeDiagnosticsRequested,
+    /// The pywal colors file changed while the Pywal theme is active.
+    ThemeFileChanged,
+    /// The style file was edited, created, or removed.
+    StyleFileChanged,
+    /// The laptop lid opened or closed.
+    LidChanged {
+        closed: bool,
+    },
+    /// A battery, backlight, or network adapter was added or removed.
+    HardwareChanged,
+    /// A gauge raised a transient toast.
+    Toast(Toast),
+    /// A gauge asked for a text prompt, such as a passphrase.
+    PromptRequested {
+        gauge_id: String,
+        dialog: PromptDialog,
+    },
+    /// A gauge asked for a menu, such as a confirmation.
+    MenuRequested {
+        gauge_id: String,
+        menu: GaugeMenu,
+    },
+    /// A toast timed out or was clicked away.
+    ToastExpired(iced::window::Id),
     BackgroundClicked,
-    GaugeBatch(Vec<GaugeModel>),
+    /// Pointer released over the bar background; ends a lock/unlock long-press.
+    BackgroundReleased,
+    GaugeBatch(Vec<GaugeUpdate>),
     GaugeClicked {
         id: String,
         input: GaugeInput,
@@ -68,6 +182,18 @@ pub enum Message {
         window: iced::window::Id,
         value: u8,
     },
+    PromptChanged {
+        window: iced::window::Id,
+        value: String,
+    },
+    /// Typing or cursor movement in a multi-line prompt.
+    PromptEdited {
+        window: iced::window::Id,
+        action: iced::widget::text_editor::Action,
+    },
+    PromptSubmitted {
+        window: iced::window::Id,
+    },
     WindowFocusChanged {
         focused: bool,
     },
@@ -75,8 +201,29 @@ pub enum Message {
     WindowEvent(iced::window::Id, iced::window::Event),
     MenuDismissed(iced::window::Id),
     WindowClosed(iced::window::Id),
+    /// Layout bounds of the bar's accessible elements, by widget id.
+    A11yBoundsMeasured(Vec<(iced::widget::Id, Rectangle)>),
+    /// Output scale the compositor reported for a surface.
+    WindowScaleChanged {
+        window: iced::window::Id,
+        scale: f32,
+    },
     CacheRefreshed(Result<(Vec<AppDescriptor>, Vec<AppDescriptor>), String>),
     OutputChanged,
+    /// A runtime source (e.g. the compositor IPC) failed and the bar is running degraded.
+    Degraded {
+        source: String,
+        message: String,
+    },
+    /// Left-click on the error banner: clear degraded state and restart subscriptions.
+    BannerRetry,
+    /// Right-click on the error banner: show the degraded-state details.
+    BannerDetails,
+    /// Request received on the control socket; answer on `reply` with one JSON line.
+    Ipc {
+        command: crate::ipc::IpcCommand,
+        reply: crate::ipc::IpcReply,
+    },
     IcedEvent(iced::Event),
 }
 
@@ -91,6 +238,16 @@ pub(crate) fn close_window_task(window: window::Id) -> Task<Message> {
     ])
 }
 
+/// Deliver `message` once `delay` has elapsed.
+pub(crate) fn delayed_task(delay: Duration, message: Message) -> Task<Message> {
+    let (tx, rx) = iced::futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let _ = tx.send(());
+    });
+    Task::perform(rx, move |_| message.clone())
+}
+
 /// Simple container wrapper for a panel element.
 pub struct Panel<'a> {
     content: Element<'a, Message>,
@@ -118,6 +275,75 @@ pub(crate) fn lerp_color(from: Color, to: Color, t: f32) -> Color {
     }
 }
 
+/// Whether bar transitions animate; a settings profile can switch them off on battery.
+pub(crate) fn animations_enabled() -> bool {
+    settings::settings().get_bool_or(ANIMATIONS_KEY, true)
+}
+
+/// `easing`, or an instant change when animations are off.
+pub(crate) fn bar_easing(easing: Easing) -> Easing {
+    if animations_enabled() {
+        easing
+    } else {
+        easing.with_duration(INSTANT_TRANSITION)
+    }
+}
+
+/// Sparkline bars never shrink below this, so idle samples stay visible.
+const MIN_SPARKLINE_BAR_HEIGHT: f32 = 1.0;
+
+/// Gap between neighbouring sparkline bars.
+const SPARKLINE_BAR_GAP: f32 = 1.0;
+
+/// Canvas program drawing one bottom-aligned bar per sample.
+struct Sparkline<C> {
+    samples: Vec<f32>,
+    color: C,
+}
+
+impl<M, C: Fn(&Theme) -> Color> canvas::Program<M> for Sparkline<C> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &iced::Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let count = self.samples.len() as f32;
+        let slot = bounds.width / count.max(1.0);
+        let bar_width = (slot - SPARKLINE_BAR_GAP).max(1.0);
+        let color = (self.color)(theme);
+        for (index, sample) in self.samples.iter().enumerate() {
+            let bar_height = (sample.clamp(0.0, 1.0) * bounds.height).max(MIN_SPARKLINE_BAR_HEIGHT);
+            frame.fill_rectangle(
+                Point::new(index as f32 * slot, bounds.height - bar_height),
+                Size::new(bar_width, bar_height),
+                color,
+            );
+        }
+        vec![frame.into_geometry()]
+    }
+}
+
+/// Bar chart of `samples` (oldest first, 0..=1) filling the available width at `height`.
+pub(crate) fn sparkline<'a, M: 'a>(
+    samples: &[f32],
+    height: f32,
+    color: impl Fn(&Theme) -> Color + Copy + 'a,
+) -> Element<'a, M> {
+    Canvas::new(Sparkline {
+        samples: samples.to_vec(),
+        color,
+    })
+    .width(Length::Fill)
+    .height(Length::Fixed(height))
+    .into()
+}
+
 pub(crate) fn app_icon_view(handle: &IconHandle, size: f32) -> Element<'_, Message> {
     match handle {
         IconHandle::Raster(handle) => Image::new(handle.clone())
@@ -167,32 +393,267 @@ impl std::str::FromStr for Orientation {
     }
 }
 
+/// How panels are distributed along the bar's long axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BarAlign {
+    Start,
+    Center,
+    End,
+    #[default]
+    SpaceBetween,
+}
+
+impl BarAlign {
+    /// Where flexible space goes: `(before the first panel, between panels, after the last)`.
+    pub fn spacers(self) -> (bool, bool, bool) {
+        match self {
+            BarAlign::Start => (false, false, true),
+            BarAlign::Center => (true, false, true),
+            BarAlign::End => (true, false, false),
+            BarAlign::SpaceBetween => (false, true, false),
+        }
+    }
+}
+
+impl std::str::FromStr for BarAlign {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "start" => Ok(BarAlign::Start),
+            "center" => Ok(BarAlign::Center),
+            "end" => Ok(BarAlign::End),
+            "space-between" => Ok(BarAlign::SpaceBetween),
+            other => Err(format!(
+                "Invalid {ALIGN_KEY} '{other}', expected 'start', 'center', 'end', or 'space-between'",
+            )),
+        }
+    }
+}
+
+/// Gaps between the bar and the screen edges, so the bar can float away from its edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BarMargin {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+impl BarMargin {
+    pub fn from_settings(settings: &settings::Settings) -> Self {
+        Self {
+            top: settings.get_parsed_or(MARGIN_TOP_KEY, 0),
+            right: settings.get_parsed_or(MARGIN_RIGHT_KEY, 0),
+            bottom: settings.get_parsed_or(MARGIN_BOTTOM_KEY, 0),
+            left: settings.get_parsed_or(MARGIN_LEFT_KEY, 0),
+        }
+    }
+
+    /// Anchor for a bar on the `orientation` edge. Top and bottom margins only apply to a
+    /// surface anchored to those edges, so the bar is stretched between them when either is set.
+    pub fn anchor(&self, orientation: Orientation) -> Anchor {
+        let edge = match orientation {
+            Orientation::Left => Anchor::Left,
+            Orientation::Right => Anchor::Right,
+        };
+        if self.top != 0 || self.bottom != 0 {
+            edge | Anchor::Top | Anchor::Bottom
+        } else {
+            edge
+        }
+    }
+
+    /// Layer-shell margin `(top, right, bottom, left)`. While autohiding, the slide owns the
+    /// anchored edge so the peek strip stays on the screen edge.
+    pub fn layer_margin(
+        &self,
+        autohide: Option<&crate::autohide::Autohide>,
+    ) -> (i32, i32, i32, i32) {
+        let margin = (self.top, self.right, self.bottom, self.left);
+        autohide.map_or(margin, |autohide| autohide.apply_to(margin))
+    }
+}
+
+/// Corner radius of the bar background; above zero the window itself is transparent so the
+/// rounded corners show the desktop.
+pub fn corner_radius() -> f32 {
+    settings::settings()
+        .get_parsed_or(RADIUS_KEY, 0.0f32)
+        .max(0.0)
+}
+
+/// Device pixel scale used to snap borders and paddings: from the compositor, or fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PixelScale {
+    #[default]
+    Auto,
+    Fixed(f32),
+}
+
+impl std::str::FromStr for PixelScale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        if value.eq_ignore_ascii_case("auto") {
+            return Ok(PixelScale::Auto);
+        }
+        match value.parse::<f32>() {
+            Ok(scale) if scale.is_finite() && scale > 0.0 => Ok(PixelScale::Fixed(scale)),
+            _ => Err(format!(
+                "Invalid {SCALE_KEY} '{value}', expected 'auto' or a positive number"
+            )),
+        }
+    }
+}
+
+/// Scale to snap to: the configured value, else the scale of the window's own output, which is
+/// 1.0 until the compositor has reported it.
+pub fn resolve_device_scale(setting: PixelScale, surface: Option<f32>) -> f32 {
+    match setting {
+        PixelScale::Fixed(scale) => scale,
+        PixelScale::Auto => surface.unwrap_or(1.0),
+    }
+}
+
+/// The configured scale setting; invalid values fall back to `auto`.
+pub fn configured_scale() -> PixelScale {
+    settings::settings()
+        .get_or(SCALE_KEY, "auto")
+        .parse::<PixelScale>()
+        .unwrap_or_default()
+}
+
+/// Round a logical length to whole device pixels, keeping non-zero lengths at least one pixel.
+pub fn snap_at(logical: f32, scale: f32) -> f32 {
+    if logical <= 0.0 || scale <= 0.0 {
+        return logical;
+    }
+    (logical * scale).round().max(1.0) / scale
+}
+
+/// Clickable bar item under the pointer, tracked for hover feedback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HoverTarget {
+    Workspace(String),
+    /// Window icon on a workspace, by container id.
+    WorkspaceApp(i64),
+    TopApp(String),
+    Gauge(String),
+    /// The overview panel button.
+    Overview,
+}
+
+/// Track the pointer over a clickable item and overlay a subtle highlight while `hovered`.
+pub(crate) fn hoverable<'a>(
+    content: impl Into<Element<'a, Message>>,
+    target: HoverTarget,
+    hovered: bool,
+) -> Element<'a, Message> {
+    // The overlay is always present so toggling hover never reshapes the widget tree.
+    let overlay = container(Space::new())
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(move |theme: &Theme| container::Style {
+            background: hovered.then(|| {
+                Color {
+                    a: HOVER_HIGHLIGHT_ALPHA,
+                    ..theme.palette().text
+                }
+                .into()
+            }),
+            border: Border::default().rounded(HOVER_HIGHLIGHT_RADIUS),
+            ..container::Style::default()
+        });
+    mouse_area(Stack::new().push(content).push(overlay))
+        .on_enter(Message::HoverEnter(target.clone()))
+        .on_exit(Message::HoverExit(target))
+        .into()
+}
+
+/// Workspace being dragged in the workspace panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceDrag {
+    pub source: String,
+    /// Workspace under the pointer, when it differs from the source.
+    pub target: Option<String>,
+}
+
 /// Runtime state for the bar, including panels, dialogs, and cache.
 #[derive(Clone)]
 pub struct BarState {
     pub workspaces: Vec<WorkspaceInfo>,
-    pub workspace_apps: HashMap<String, Vec<crate::sway_workspace::WorkspaceApp>>,
+    pub workspace_apps: HashMap<String, Vec<crate::compositor::WorkspaceApp>>,
     pub top_apps: Vec<AppDescriptor>,
+    /// Top apps launched from the panel that have not opened a window yet.
+    pub launching: HashMap<String, crate::panels::top_apps_panel::PendingLaunch>,
+    /// Desktop-entry categories by app id; loaded only once a category filter is used.
+    pub app_categories: HashMap<String, Vec<String>>,
     pub app_icons: AppIconCache,
     pub gauges: Vec<GaugeModel>,
     /// Precomputed position index from the configured gauge order; avoids a per-frame allocation.
     pub gauge_order_index: HashMap<String, usize>,
     pub bar_theme: Theme,
     pub themed_svg_cache: Arc<Mutex<HashMap<String, iced::widget::svg::Handle>>>,
+    pub quantity_atlases: QuantityAtlasCache,
     pub current_workspace: Option<String>,
     pub previous_workspace: Option<String>,
+    /// Recently focused workspaces, most recent (the focused one) first.
+    pub workspace_history: Vec<String>,
+    /// Position in `workspace_history` previewed by modifier+scroll; focused on modifier release.
+    pub workspace_history_preview: Option<usize>,
+    /// Keyboard modifiers as last reported to the bar.
+    pub modifiers: iced::keyboard::Modifiers,
+    pub workspace_drag: Option<WorkspaceDrag>,
+    /// Last workspace click release, for double-click detection.
+    pub last_workspace_release: Option<(String, Instant)>,
+    pub focused_window: Option<FocusedWindow>,
+    /// Window the taskbar move-to-workspace menu was opened for.
+    pub taskbar_menu_con: Option<i64>,
+    /// Clickable item under the pointer, shared by all panels.
+    pub hovered: Option<HoverTarget>,
+    /// Marquee scroll position of the focused window title, in characters.
+    pub focused_title_offset: usize,
+    /// Bumped on every workspace update so only the last pending auto-name run applies.
+    pub auto_name_generation: u64,
     pub dialog_windows: HashMap<window::Id, GaugeDialogWindow>,
+    /// Info dialogs pinned with shift-click. Kept apart from `dialog_windows` so clicks, focus
+    /// loss, and other dialogs leave them open; they keep refreshing until unpinned.
+    pub pinned_dialogs: HashMap<window::Id, GaugeDialogWindow>,
+    /// Toast popups; kept apart from gauge dialogs so opening one does not close the other.
+    pub toast_windows: HashMap<window::Id, ToastWindow>,
     pub last_cursor: Option<iced::Point>,
-    pub closing_dialogs: HashSet<window::Id>,
     pub gauge_dialog_anchor: HashMap<String, i32>,
-    pub primary_window: Option<window::Id>,
-    pub pending_primary_window: bool,
-    pub bar_windows: HashSet<window::Id>,
+    /// Bar windows, the primary bar, closing windows, and output-change bookkeeping.
+    pub windows: WindowTracker,
     pub last_click_at: Option<Instant>,
     pub last_dialog_opened_at: Option<Instant>,
-    pub last_output_change_at: Option<Instant>,
-    pub last_bar_window_opened_at: Option<Instant>,
-    pub last_outputs: Option<Vec<OutputSnapshot>>,
+    /// Output named with `--on-monitor`, if any.
+    pub target_monitor: Option<String>,
+    pub lid_closed: bool,
+    /// Lid closed with an external monitor active; a bar pinned to the internal panel is closed.
+    pub clamshell: bool,
+    /// Degraded-state messages keyed by source, shown in the error banner.
+    pub degraded: BTreeMap<String, String>,
+    /// Bumped on banner retry so panel subscriptions are recreated.
+    pub subscription_generation: u64,
+    /// While set, all pointer input except the unlock long-press is ignored.
+    pub locked: bool,
+    /// When the current background press started, for long-press detection.
+    pub background_pressed_at: Option<Instant>,
+    /// Slide state when `grelier.bar.autohide` is on.
+    pub autohide: Option<crate::autohide::Autohide>,
+    /// Gauges still showing their loading placeholder.
+    pub gauge_loading: crate::gauge_loading::GaugeLoading,
+    /// Configured gauges left off the bar because their hardware is missing.
+    pub missing_hardware: BTreeSet<String>,
+    /// Full-screen workspace overview surface, while open.
+    pub overview: Option<window::Id>,
+    /// Reply to a `quit` request, sent once every bar surface has closed.
+    pub quit_reply: Option<crate::ipc::IpcReply>,
+    /// Output scale of each open surface, as reported by the compositor.
+    pub window_scales: HashMap<window::Id, f32>,
 }
 
 impl Default for BarState {
@@ -201,34 +662,57 @@ impl Default for BarState {
             workspaces: Vec::new(),
             workspace_apps: HashMap::new(),
             top_apps: Vec::new(),
+            launching: HashMap::new(),
+            app_categories: HashMap::new(),
             app_icons: AppIconCache::default(),
             gauges: Vec::new(),
             gauge_order_index: HashMap::new(),
             bar_theme: Theme::Nord,
             themed_svg_cache: Arc::new(Mutex::new(HashMap::new())),
+            quantity_atlases: Arc::new(Mutex::new(HashMap::new())),
             current_workspace: None,
             previous_workspace: None,
+            workspace_history: Vec::new(),
+            workspace_history_preview: None,
+            modifiers: iced::keyboard::Modifiers::default(),
+            workspace_drag: None,
+            last_workspace_release: None,
+            focused_window: None,
+            taskbar_menu_con: None,
+            hovered: None,
+            focused_title_offset: 0,
+            auto_name_generation: 0,
             dialog_windows: HashMap::new(),
+            pinned_dialogs: HashMap::new(),
+            toast_windows: HashMap::new(),
             last_cursor: None,
-            closing_dialogs: HashSet::new(),
             gauge_dialog_anchor: HashMap::new(),
-            primary_window: None,
-            pending_primary_window: false,
-            bar_windows: HashSet::new(),
+            windows: WindowTracker::default(),
             last_click_at: None,
             last_dialog_opened_at: None,
-            last_output_change_at: None,
-            last_bar_window_opened_at: None,
-            last_outputs: None,
+            target_monitor: None,
+            lid_closed: false,
+            clamshell: false,
+            degraded: BTreeMap::new(),
+            subscription_generation: 0,
+            locked: false,
+            background_pressed_at: None,
+            autohide: None,
+            gauge_loading: crate::gauge_loading::GaugeLoading::default(),
+            missing_hardware: BTreeSet::new(),
+            overview: None,
+            quit_reply: None,
+            window_scales: HashMap::new(),
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct OutputSnapshot {
     pub name: String,
     pub active: bool,
     pub rect: (i32, i32, i32, i32),
+    pub scale: f64,
 }
 
 /// Lookup cache for app icon handles by app id or title.
@@ -275,6 +759,7 @@ pub enum GaugeDialog {
     Menu(GaugeMenu),
     Action(GaugeActionDialog),
     Info(InfoDialog),
+    Prompt(PromptDialog),
 }
 
 /// Tracking info for an open gauge dialog window.
@@ -306,8 +791,15 @@ impl BarState {
         }
     }
 
+    /// Whether `target` is under the pointer and should show hover feedback.
+    pub fn is_hovered(&self, target: &HoverTarget) -> bool {
+        !self.locked
+            && self.hovered.as_ref() == Some(target)
+            && settings::settings().get_bool_or(HOVER_HIGHLIGHT_KEY, true)
+    }
+
     pub fn namespace() -> String {
-        env!("CARGO_PKG_NAME").to_string()
+        crate::session_kind::kind().namespace(env!("CARGO_PKG_NAME"))
     }
 
     pub fn open_menu(
@@ -350,6 +842,22 @@ impl BarState {
         )
     }
 
+    /// Open a text prompt; its input is focused once the window opens.
+    pub fn open_prompt_dialog(
+        &mut self,
+        gauge_id: &str,
+        dialog: PromptDialog,
+        anchor_y: Option<i32>,
+    ) -> Task<Message> {
+        let (width, height) = prompt_dialog_dimensions(&dialog);
+        self.open_dialog_window(
+            gauge_id,
+            GaugeDialog::Prompt(dialog),
+            anchor_y,
+            (width, height),
+        )
+    }
+
     fn open_dialog_window(
         &mut self,
         gauge_id: &str,
@@ -358,7 +866,35 @@ impl BarState {
         size: (u32, u32),
     ) -> Task<Message> {
         let mut tasks = vec![self.close_dialogs()];
+        let (window, task) = self.open_popup(gauge_id, &dialog, anchor_y, size);
+        let initial_slider = if let GaugeDialog::Menu(menu) = &dialog {
+            menu.slider.as_ref().map(|s| s.value)
+        } else {
+            None
+        };
+        self.dialog_windows.insert(
+            window,
+            GaugeDialogWindow {
+                gauge_id: gauge_id.to_string(),
+                dialog,
+                hovered_item: None,
+                slider_value: initial_slider,
+            },
+        );
+        self.last_dialog_opened_at = Some(Instant::now());
+        tasks.push(task);
+
+        Task::batch(tasks)
+    }
 
+    /// Open a popup beside the bar for `gauge_id`'s dialog, centered on its anchor.
+    fn open_popup(
+        &mut self,
+        gauge_id: &str,
+        dialog: &GaugeDialog,
+        anchor_y: Option<i32>,
+        size: (u32, u32),
+    ) -> (window::Id, Task<Message>) {
         let (width, height) = size;
         let bar_width = settings::settings().get_parsed_or("grelier.bar.width", 28u32) as i32;
         let anchor_y = anchor_y
@@ -383,32 +919,193 @@ impl BarState {
         let (window, task) = Message::popup_open(settings);
         self.gauge_dialog_anchor
             .insert(gauge_id.to_string(), anchor_y);
-        let initial_slider = if let GaugeDialog::Menu(menu) = &dialog {
-            menu.slider.as_ref().map(|s| s.value)
-        } else {
-            None
+        crate::ipc::publish(crate::ipc::EventTopic::Dialog, || {
+            crate::snapshot::dialog_json(gauge_id, dialog)
+        });
+        (window, task)
+    }
+
+    /// Pin `dialog` for `gauge_id`, or unpin it if that gauge already has a pinned dialog.
+    pub fn toggle_pinned_info(
+        &mut self,
+        gauge_id: &str,
+        dialog: Option<InfoDialog>,
+        anchor_y: Option<i32>,
+    ) -> Task<Message> {
+        let pinned = self
+            .pinned_dialogs
+            .iter()
+            .find(|(_, pinned)| pinned.gauge_id == gauge_id)
+            .map(|(id, _)| *id);
+        if let Some(window) = pinned {
+            self.pinned_dialogs.remove(&window);
+            self.windows.mark_closing([window]);
+            return close_window_task(window);
+        }
+        let Some(dialog) = dialog else {
+            return Task::none();
         };
-        self.dialog_windows.insert(
+        let size = info_dialog_dimensions(&dialog);
+        let dialog = GaugeDialog::Info(dialog);
+        let (window, task) = self.open_popup(gauge_id, &dialog, anchor_y, size);
+        self.pinned_dialogs.insert(
             window,
             GaugeDialogWindow {
                 gauge_id: gauge_id.to_string(),
                 dialog,
                 hovered_item: None,
-                slider_value: initial_slider,
+                slider_value: None,
             },
         );
-        self.last_dialog_opened_at = Some(Instant::now());
-        tasks.push(task);
+        task
+    }
+
+    /// Close every pinned dialog, for when the bar they belong to goes away.
+    pub fn close_pinned_dialogs(&mut self) -> Task<Message> {
+        let ids: Vec<window::Id> = self.pinned_dialogs.drain().map(|(id, _)| id).collect();
+        self.windows.mark_closing(ids.iter().copied());
+        Task::batch(ids.into_iter().map(close_window_task))
+    }
+
+    /// Info dialog describing every active degraded-state message.
+    pub fn degraded_info_dialog(&self) -> InfoDialog {
+        let mut lines: Vec<String> = self
+            .degraded
+            .iter()
+            .map(|(source, message)| format!("{source}: {message}"))
+            .collect();
+        lines.push("Left-click the banner to retry.".to_string());
+        InfoDialog {
+            title: "Degraded".to_string(),
+            lines,
+            graph: None,
+        }
+    }
+
+    fn degraded_banner(&self) -> Option<Element<'_, Message>> {
+        if self.degraded.is_empty() {
+            return None;
+        }
+        let strip = container(
+            text("!")
+                .size(12)
+                .width(Length::Fill)
+                .align_x(alignment::Horizontal::Center),
+        )
+        .width(Length::Fill)
+        .padding([2, 0])
+        .style(|theme: &Theme| {
+            let palette = theme.palette();
+            container::Style {
+                background: Some(palette.danger.into()),
+                text_color: Some(palette.background),
+                ..container::Style::default()
+            }
+        });
+        Some(
+            mouse_area(strip)
+                .on_press(Message::BannerRetry)
+                .on_right_press(Message::BannerDetails)
+                .interaction(mouse::Interaction::Pointer)
+                .into(),
+        )
+    }
 
+    /// Show a toast at the top of the screen beside the bar, replacing any current toast.
+    pub fn open_toast(&mut self, toast: Toast) -> Task<Message> {
+        let ids: Vec<window::Id> = self.toast_windows.drain().map(|(id, _)| id).collect();
+        self.windows.mark_closing(ids.iter().copied());
+        let mut tasks: Vec<Task<Message>> = ids.into_iter().map(close_window_task).collect();
+
+        let toast_window = ToastWindow::new(&toast);
+        let bar_width = settings::settings().get_parsed_or("grelier.bar.width", 28u32) as i32;
+        let (window, task) = Message::popup_open(IcedNewPopupSettings {
+            size: toast_window.dimensions(),
+            position: (bar_width, 0),
+        });
+        self.toast_windows.insert(window, toast_window);
+        tasks.push(task);
+        tasks.push(toast::expire_task(window, toast.timeout));
         Task::batch(tasks)
     }
 
     pub fn close_dialogs(&mut self) -> Task<Message> {
         let ids: Vec<window::Id> = self.dialog_windows.drain().map(|(id, _)| id).collect();
-        self.closing_dialogs.extend(&ids);
+        self.windows.mark_closing(ids.iter().copied());
         Task::batch(ids.into_iter().map(close_window_task))
     }
 
+    /// Open the workspace overview, or close it when it is already open.
+    pub fn toggle_overview(&mut self) -> Task<Message> {
+        if self.overview.is_some() {
+            return self.close_overview();
+        }
+        let window = window::Id::unique();
+        self.overview = Some(window);
+        Task::batch([
+            self.close_dialogs(),
+            Task::done(Message::NewLayerShell {
+                settings: crate::overview::layer_settings(),
+                id: window,
+            }),
+        ])
+    }
+
+    pub fn close_overview(&mut self) -> Task<Message> {
+        let Some(window) = self.overview.take() else {
+            return Task::none();
+        };
+        self.windows.mark_closing([window]);
+        close_window_task(window)
+    }
+
+    /// Lock or unlock the bar, persisting the state so a restart stays locked.
+    pub fn set_locked(&mut self, locked: bool) -> Task<Message> {
+        self.background_pressed_at = None;
+        if self.locked == locked {
+            return Task::none();
+        }
+        self.locked = locked;
+        settings::settings().update(LOCKED_KEY, if locked { "true" } else { "false" });
+        if locked {
+            self.close_dialogs()
+        } else {
+            Task::none()
+        }
+    }
+
+    /// End a background press; true if it was held for at least `long_press`.
+    pub(crate) fn finish_background_press(&mut self, now: Instant, long_press: Duration) -> bool {
+        let Some(pressed_at) = self.background_pressed_at.take() else {
+            return false;
+        };
+        !long_press.is_zero() && now.saturating_duration_since(pressed_at) >= long_press
+    }
+
+    fn lock_indicator(&self) -> Option<Element<'_, Message>> {
+        if !self.locked {
+            return None;
+        }
+        let icon = Svg::new(svg::Handle::from_path(
+            std::path::Path::new(crate::icon::ASSETS_DIR).join("lock.svg"),
+        ))
+        .width(Length::Fixed(LOCK_INDICATOR_SIZE))
+        .height(Length::Fixed(LOCK_INDICATOR_SIZE))
+        .style(|theme: &Theme, _status| svg::Style {
+            color: Some(Color {
+                a: 0.5,
+                ..theme.palette().text
+            }),
+        });
+        Some(
+            container(icon)
+                .width(Length::Fill)
+                .padding([2, 0])
+                .align_x(alignment::Horizontal::Center)
+                .into(),
+        )
+    }
+
     pub fn allow_click(&mut self) -> bool {
         self.allow_click_at(Instant::now())
     }
@@ -429,11 +1126,23 @@ impl BarState {
         true
     }
 
+    /// Device pixel scale to snap `window`'s borders and paddings to.
+    pub fn device_scale(&self, window: window::Id) -> f32 {
+        resolve_device_scale(configured_scale(), self.window_scales.get(&window).copied())
+    }
+
     pub fn view<'a>(&'a self, window: window::Id) -> Element<'a, Message> {
         let settings = settings::settings();
+        let scale = self.device_scale(window);
         let border_blend = settings.get_bool_or("grelier.bar.border.blend", true);
-        let border_line_width = settings.get_parsed_or("grelier.bar.border.line_width", 1.0);
-        let border_column_width = settings.get_parsed_or("grelier.bar.border.column_width", 3.0);
+        let border_line_width = snap_at(
+            settings.get_parsed_or("grelier.bar.border.line_width", 1.0),
+            scale,
+        );
+        let border_column_width = snap_at(
+            settings.get_parsed_or("grelier.bar.border.column_width", 3.0),
+            scale,
+        );
         let border_mix_1 = settings.get_parsed_or("grelier.bar.border.mix_1", 0.2);
         let border_mix_2 = settings.get_parsed_or("grelier.bar.border.mix_2", 0.6);
         let border_mix_3 = settings.get_parsed_or("grelier.bar.border.mix_3", 1.0);
@@ -441,12 +1150,26 @@ impl BarState {
         let border_alpha_2 = settings.get_parsed_or("grelier.bar.border.alpha_2", 0.7);
         let border_alpha_3 = settings.get_parsed_or("grelier.bar.border.alpha_3", 0.9);
 
+        if self.overview == Some(window) {
+            return crate::overview::view(self);
+        }
+        if let Some(toast) = self.toast_windows.get(&window) {
+            return toast_view(toast, window, scale);
+        }
+        if let Some(GaugeDialogWindow {
+            dialog: GaugeDialog::Info(dialog),
+            ..
+        }) = self.pinned_dialogs.get(&window)
+        {
+            return info_view(dialog, scale);
+        }
         if let Some(dialog_window) = self.dialog_windows.get(&window) {
             let gauge_id = dialog_window.gauge_id.clone();
             let window_id = window;
             return match &dialog_window.dialog {
                 GaugeDialog::Menu(menu) => menu_view(
                     menu,
+                    scale,
                     dialog_window.hovered_item.as_deref(),
                     dialog_window.slider_value,
                     move |item_id| Message::MenuItemSelected {
@@ -468,16 +1191,29 @@ impl BarState {
                     },
                 ),
                 GaugeDialog::Action(dialog) => {
-                    action_view(dialog, move |item_id| Message::ActionItemSelected {
+                    action_view(dialog, scale, move |item_id| Message::ActionItemSelected {
                         window: window_id,
                         gauge_id: gauge_id.clone(),
                         item_id,
                     })
                 }
-                GaugeDialog::Info(dialog) => info_view(dialog),
+                GaugeDialog::Info(dialog) => info_view(dialog, scale),
+                GaugeDialog::Prompt(dialog) => prompt_view(
+                    dialog,
+                    scale,
+                    move |value| Message::PromptChanged {
+                        window: window_id,
+                        value,
+                    },
+                    move |action| Message::PromptEdited {
+                        window: window_id,
+                        action,
+                    },
+                    Message::PromptSubmitted { window: window_id },
+                ),
             };
         }
-        if self.closing_dialogs.contains(&window) {
+        if self.windows.is_closing(window) {
             return container(Space::new()).into();
         }
 
@@ -486,44 +1222,75 @@ impl BarState {
         );
 
         let mut layout = Column::new().width(Length::Fill).height(Length::Fill);
+        if let Some(indicator) = self.lock_indicator() {
+            layout = layout.push(indicator);
+        }
+        if let Some(banner) = self.degraded_banner() {
+            layout = layout.push(banner);
+        }
+        // Validated at startup; a bad value set at runtime falls back to the default.
+        let (leading, between, trailing) = settings
+            .get(ALIGN_KEY)
+            .and_then(|align| align.parse::<BarAlign>().ok())
+            .unwrap_or_default()
+            .spacers();
+        if leading {
+            layout = layout.push(Space::new().height(Length::Fill));
+        }
         let mut iter = panel_order.iter().peekable();
         while let Some(panel_id) = iter.next() {
             let Some(spec) = panel_registry::find(panel_id) else {
                 continue;
             };
-            let panel = (spec.view)(self);
+            let panel = (spec.view)(panel_registry::PanelViewContext { state: self, scale });
             layout = layout.push(panel.view());
-            if iter.peek().is_some() {
+            if between && iter.peek().is_some() {
                 layout = layout.push(Space::new().height(Length::Fill));
             }
         }
+        if trailing {
+            layout = layout.push(Space::new().height(Length::Fill));
+        }
 
+        let radius = corner_radius();
+        let border_color = move |theme: &Theme, mix: f32, alpha: f32| {
+            let background = theme.palette().background;
+            let blended = if border_blend && mix != 0.0 {
+                lerp_color(background, Color::BLACK, mix)
+            } else {
+                background
+            };
+            Color {
+                a: alpha,
+                ..blended
+            }
+        };
         let filled = container(layout)
             .width(Length::Fill)
             .height(Length::Fill)
-            .style(|theme: &Theme| container::Style {
+            .style(move |theme: &Theme| container::Style {
                 background: Some(theme.palette().background.into()),
+                // A rounded bar is outlined all the way round instead of the edge column.
+                border: if radius > 0.0 {
+                    Border {
+                        color: border_color(theme, border_mix_3, border_alpha_3),
+                        width: border_line_width,
+                        radius: radius.into(),
+                    }
+                } else {
+                    Border::default()
+                },
                 ..container::Style::default()
-            });
+            })
+            .clip(radius > 0.0);
 
         let border = container({
             let line = |mix: f32, alpha: f32| {
-                rule::vertical(border_line_width).style(move |theme: &Theme| {
-                    let background = theme.palette().background;
-                    let blended = if border_blend && mix != 0.0 {
-                        lerp_color(background, Color::BLACK, mix)
-                    } else {
-                        background
-                    };
-                    rule::Style {
-                        color: Color {
-                            a: alpha,
-                            ..blended
-                        },
-                        radius: 0.0.into(),
-                        fill_mode: rule::FillMode::Full,
-                        snap: true,
-                    }
+                rule::vertical(border_line_width).style(move |theme: &Theme| rule::Style {
+                    color: border_color(theme, mix, alpha),
+                    radius: 0.0.into(),
+                    fill_mode: rule::FillMode::Full,
+                    snap: true,
                 })
             };
             let line1 = line(border_mix_1, border_alpha_1);
@@ -542,15 +1309,29 @@ impl BarState {
         .height(Length::Fill)
         .align_x(alignment::Horizontal::Right);
 
-        let layered = Stack::new()
+        let mut layered = Stack::new()
             .width(Length::Fill)
             .height(Length::Fill)
-            .push(filled)
-            .push(border);
+            .push(filled);
+        if radius <= 0.0 {
+            layered = layered.push(border);
+        }
+        if self.locked {
+            // Swallow all input above the panels; only the long-press reaches `update`.
+            layered = layered.push(
+                mouse_area(Space::new().width(Length::Fill).height(Length::Fill))
+                    .on_press(Message::BackgroundClicked)
+                    .on_release(Message::BackgroundReleased)
+                    .on_right_press(Message::Noop)
+                    .on_middle_press(Message::Noop)
+                    .on_scroll(|_| Message::Noop),
+            );
+        }
 
         mouse_area(layered)
             .on_press(Message::BackgroundClicked)
-            .on_right_press(Message::BackgroundClicked)
+            .on_release(Message::BackgroundReleased)
+            .on_right_press(Message::ThemeMenuRequested)
             .interaction(mouse::Interaction::None)
             .into()
     }
@@ -560,6 +1341,76 @@ impl BarState {
 mod tests {
     use super::*;
 
+    #[test]
+    fn only_long_background_press_toggles_lock() {
+        let mut state = BarState::default();
+        let long_press = Duration::from_millis(1500);
+        let start = Instant::now();
+
+        assert!(!state.finish_background_press(start, long_press));
+        state.background_pressed_at = Some(start);
+        assert!(!state.finish_background_press(start + Duration::from_millis(300), long_press));
+        assert!(state.background_pressed_at.is_none());
+        state.background_pressed_at = Some(start);
+        assert!(state.finish_background_press(start + long_press, long_press));
+        state.background_pressed_at = Some(start);
+        assert!(!state.finish_background_press(start + long_press, Duration::ZERO));
+    }
+
+    #[test]
+    fn vertical_margins_stretch_the_bar_between_top_and_bottom() {
+        let side_only = BarMargin {
+            left: 8,
+            ..BarMargin::default()
+        };
+        assert_eq!(side_only.anchor(Orientation::Left), Anchor::Left);
+        assert_eq!(side_only.layer_margin(None), (0, 0, 0, 8));
+
+        let floating = BarMargin {
+            top: 8,
+            right: 6,
+            bottom: 8,
+            left: 0,
+        };
+        assert_eq!(
+            floating.anchor(Orientation::Right),
+            Anchor::Right | Anchor::Top | Anchor::Bottom
+        );
+        assert_eq!(floating.layer_margin(None), (8, 6, 8, 0));
+    }
+
+    #[test]
+    fn parses_bar_alignment() {
+        assert_eq!("Center".parse::<BarAlign>(), Ok(BarAlign::Center));
+        assert_eq!(
+            "space-between".parse::<BarAlign>(),
+            Ok(BarAlign::SpaceBetween)
+        );
+        assert_eq!(BarAlign::Start.spacers(), (false, false, true));
+        assert_eq!(BarAlign::End.spacers(), (true, false, false));
+        assert!("middle".parse::<BarAlign>().is_err());
+    }
+
+    #[test]
+    fn snaps_lengths_to_device_pixels() {
+        // 1px at 1.25x is 1.25 device pixels; snapping draws exactly one.
+        assert_eq!(snap_at(1.0, 1.25), 0.8);
+        assert_eq!(snap_at(3.0, 1.5), 5.0 / 1.5);
+        assert_eq!(snap_at(2.0, 1.0), 2.0);
+        assert_eq!(snap_at(0.0, 1.25), 0.0);
+        assert_eq!(snap_at(0.2, 1.5), 1.0 / 1.5);
+
+        assert_eq!(resolve_device_scale(PixelScale::Auto, Some(1.25)), 1.25);
+        assert_eq!(resolve_device_scale(PixelScale::Auto, None), 1.0);
+        assert_eq!(
+            resolve_device_scale(PixelScale::Fixed(2.0), Some(1.25)),
+            2.0
+        );
+        assert_eq!("auto".parse::<PixelScale>(), Ok(PixelScale::Auto));
+        assert_eq!("1.5".parse::<PixelScale>(), Ok(PixelScale::Fixed(1.5)));
+        assert!("0".parse::<PixelScale>().is_err());
+    }
+
     #[test]
     fn panel_order_filters_duplicates() {
         let order = panel_registry::panel_order_from_setting("gauges,workspaces,gauges,top_apps");
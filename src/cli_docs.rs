@@ -0,0 +1,385 @@
+// Shell completions and the man page, generated from the argh help text plus the gauge,
+// theme, and setting registries so they always match the running build.
+use std::fmt::Write as _;
+
+use crate::settings::SettingSpec;
+
+/// Shell targeted by `--generate-completions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl std::str::FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            other => Err(format!(
+                "Invalid shell '{other}', expected 'bash', 'zsh', or 'fish'"
+            )),
+        }
+    }
+}
+
+/// One command-line option as described by the argh help text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliOption {
+    pub short: Option<char>,
+    pub long: String,
+    /// Help text with wrapped lines joined.
+    pub help: String,
+    /// Whether the option is followed by a value.
+    pub takes_value: bool,
+    /// Whether the option may be given more than once.
+    pub repeated: bool,
+}
+
+/// What a value-taking option accepts, for completion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ValueHint {
+    Words(Vec<String>),
+    File,
+    Directory,
+    Any,
+}
+
+/// Registry data woven into the generated artifacts.
+pub struct CliData<'a> {
+    pub options: Vec<CliOption>,
+    /// Gauge ids with their descriptions, sorted by id.
+    pub gauges: Vec<(&'static str, &'static str)>,
+    pub themes: Vec<String>,
+    pub settings: &'a [SettingSpec],
+}
+
+/// Parse argh's `--help` output: value-taking and repeated options come from the usage
+/// line, short and long names and help text from the options list.
+pub fn parse_help(help: &str) -> Vec<CliOption> {
+    let usage = help
+        .lines()
+        .find(|line| line.starts_with("Usage:"))
+        .unwrap_or_default();
+    let mut options: Vec<CliOption> = Vec::new();
+    let mut in_options = false;
+    for line in help.lines() {
+        if line.trim_end() == "Options:" {
+            in_options = true;
+            continue;
+        }
+        if !in_options || line.trim().is_empty() {
+            continue;
+        }
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if indent > 2 {
+            if let Some(option) = options.last_mut() {
+                // Long option names push the whole help text onto the next line.
+                if !option.help.is_empty() {
+                    option.help.push(' ');
+                }
+                option.help.push_str(trimmed.trim());
+            }
+            continue;
+        }
+        let (names, help) = match trimmed.split_once("  ") {
+            Some((names, help)) => (names, help.trim()),
+            None => (trimmed, ""),
+        };
+        let mut short = None;
+        let mut long = None;
+        for name in names.split(',').map(str::trim) {
+            if let Some(name) = name.strip_prefix("--") {
+                long = Some(name.to_string());
+            } else if let Some(name) = name.strip_prefix('-') {
+                short = name.chars().next();
+            }
+        }
+        let Some(long) = long else {
+            continue;
+        };
+        if long == "help" {
+            continue;
+        }
+        let flag = short.map_or_else(|| format!("--{long}"), |short| format!("-{short}"));
+        let value_marker = format!("[{flag} <");
+        let takes_value = usage.contains(&value_marker);
+        let repeated = usage
+            .split(&value_marker)
+            .nth(1)
+            .and_then(|rest| rest.split('>').next())
+            .is_some_and(|value| value.ends_with("..."));
+        options.push(CliOption {
+            short,
+            long,
+            help: help.to_string(),
+            takes_value,
+            repeated,
+        });
+    }
+    options
+}
+
+impl CliData<'_> {
+    /// Candidate values for `-s`: every setting key, plus complete pairs for the theme.
+    fn setting_words(&self) -> Vec<String> {
+        let mut words: Vec<String> = self
+            .settings
+            .iter()
+            .map(|spec| format!("{}=", spec.key))
+            .collect();
+        words.extend(
+            self.themes
+                .iter()
+                .map(|theme| format!("{}={theme}", crate::theme::THEME_KEY)),
+        );
+        words.extend(
+            self.gauges
+                .iter()
+                .map(|(id, _)| format!("grelier.gauges={id}")),
+        );
+        words.retain(|word| !word.contains(char::is_whitespace));
+        words
+    }
+
+    fn value_hint(&self, long: &str) -> ValueHint {
+        let words =
+            |values: &[&str]| ValueHint::Words(values.iter().map(|v| v.to_string()).collect());
+        match long {
+            "settings" => ValueHint::Words(self.setting_words()),
+            "config" => ValueHint::File,
+            "snapshot" => ValueHint::Directory,
+            "new-gauge-dir" => ValueHint::Directory,
+            "lock" => words(&["on", "off", "toggle"]),
+            "events" => words(&["all", "workspace", "gauge", "dialog"]),
+            "generate-completions" => words(&["bash", "zsh", "fish"]),
+            _ => ValueHint::Any,
+        }
+    }
+
+    pub fn completions(&self, shell: Shell) -> String {
+        match shell {
+            Shell::Bash => self.bash(),
+            Shell::Zsh => self.zsh(),
+            Shell::Fish => self.fish(),
+        }
+    }
+
+    fn bash(&self) -> String {
+        let mut out = String::from("# bash completion for grelier\n_grelier() {\n");
+        out.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+        out.push_str("    local prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n");
+        out.push_str("    case \"$prev\" in\n");
+        for option in self.options.iter().filter(|option| option.takes_value) {
+            let pattern = match option.short {
+                Some(short) => format!("-{short}|--{}", option.long),
+                None => format!("--{}", option.long),
+            };
+            let reply = match self.value_hint(&option.long) {
+                ValueHint::Words(words) => {
+                    let nospace = if option.long == "settings" {
+                        " compopt -o nospace;"
+                    } else {
+                        ""
+                    };
+                    format!(
+                        "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"));{nospace}",
+                        words.join(" ")
+                    )
+                }
+                ValueHint::File => "COMPREPLY=($(compgen -f -- \"$cur\"));".to_string(),
+                ValueHint::Directory => "COMPREPLY=($(compgen -d -- \"$cur\"));".to_string(),
+                ValueHint::Any => "COMPREPLY=();".to_string(),
+            };
+            let _ = writeln!(out, "        {pattern}) {reply} return ;;");
+        }
+        out.push_str("    esac\n");
+        let flags: Vec<String> = self
+            .options
+            .iter()
+            .flat_map(|option| {
+                option
+                    .short
+                    .map(|short| format!("-{short}"))
+                    .into_iter()
+                    .chain([format!("--{}", option.long)])
+            })
+            .chain(["--help".to_string()])
+            .collect();
+        let _ = writeln!(
+            out,
+            "    COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+            flags.join(" ")
+        );
+        out.push_str("}\ncomplete -F _grelier grelier\n");
+        out
+    }
+
+    fn zsh(&self) -> String {
+        let escape = |text: &str| {
+            text.replace('\'', "'\\''")
+                .replace('[', "\\[")
+                .replace(']', "\\]")
+                .replace(':', "\\:")
+        };
+        let mut out = String::from("#compdef grelier\n\n_arguments \\\n");
+        for option in &self.options {
+            let action = if option.takes_value {
+                match self.value_hint(&option.long) {
+                    ValueHint::Words(words) => format!(":{}:({})", option.long, words.join(" ")),
+                    ValueHint::File => format!(":{}:_files", option.long),
+                    ValueHint::Directory => format!(":{}:_files -/", option.long),
+                    ValueHint::Any => format!(":{}: ", option.long),
+                }
+            } else {
+                String::new()
+            };
+            let repeat = if option.repeated { "*" } else { "" };
+            let help = escape(&option.help);
+            let mut names = vec![format!("--{}", option.long)];
+            if let Some(short) = option.short {
+                names.insert(0, format!("-{short}"));
+            }
+            for name in names {
+                let _ = writeln!(out, "  '{repeat}{name}[{help}]{action}' \\");
+            }
+        }
+        out.push_str("  '--help[display usage information]'\n");
+        out
+    }
+
+    fn fish(&self) -> String {
+        let quote = |text: &str| format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"));
+        let mut out = String::from("# fish completion for grelier\ncomplete -c grelier -f\n");
+        for option in &self.options {
+            let mut line = String::from("complete -c grelier");
+            if let Some(short) = option.short {
+                let _ = write!(line, " -s {short}");
+            }
+            let _ = write!(line, " -l {} -d {}", option.long, quote(&option.help));
+            if option.takes_value {
+                match self.value_hint(&option.long) {
+                    ValueHint::Words(words) => {
+                        let _ = write!(line, " -x -a {}", quote(&words.join(" ")));
+                    }
+                    ValueHint::File | ValueHint::Directory => line.push_str(" -r -F"),
+                    ValueHint::Any => line.push_str(" -x"),
+                }
+            }
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Man page in roff, with the gauge, theme, and setting lists appended.
+    pub fn man_page(&self) -> String {
+        let escape = |text: &str| text.replace('\\', "\\\\").replace('-', "\\-");
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            ".TH GRELIER 1 \"\" \"grelier {}\"",
+            env!("CARGO_PKG_VERSION")
+        );
+        out.push_str(
+            ".SH NAME\ngrelier \\- vertical status bar for Sway and other Wayland compositors\n",
+        );
+        out.push_str(".SH SYNOPSIS\n.B grelier\n[\\fIOPTIONS\\fR]\n");
+        out.push_str(
+            ".SH DESCRIPTION\n\
+             grelier renders a bar of workspaces, launchers, and gauges along one edge of each \
+             monitor. Settings are read from the settings file and can be overridden with \
+             \\fB\\-s\\fR \\fIkey\\fR=\\fIvalue\\fR.\n",
+        );
+        out.push_str(".SH OPTIONS\n");
+        for option in &self.options {
+            out.push_str(".TP\n.B ");
+            if let Some(short) = option.short {
+                let _ = write!(out, "\\-{short}, ");
+            }
+            let _ = write!(out, "\\-\\-{}", escape(&option.long));
+            if option.takes_value {
+                let _ = write!(out, " \\fI{}\\fR", escape(&option.long));
+            }
+            let _ = writeln!(out, "\n{}", escape(&option.help));
+        }
+        out.push_str(".SH GAUGES\nEnable gauges by listing their ids in \\fBgrelier.gauges\\fR.\n");
+        for (id, description) in &self.gauges {
+            let _ = writeln!(out, ".TP\n.B {}\n{}", escape(id), escape(description));
+        }
+        out.push_str(".SH THEMES\nSelect a theme with \\fBgrelier.bar.theme\\fR.\n.PP\n");
+        let _ = writeln!(out, "{}", escape(&self.themes.join(", ")));
+        out.push_str(".SH SETTINGS\n");
+        for spec in self.settings {
+            let default = if spec.default.is_empty() {
+                "(empty)".to_string()
+            } else {
+                escape(spec.default)
+            };
+            let _ = writeln!(out, ".TP\n.B {}\nDefault: {default}", escape(spec.key));
+        }
+        out.push_str(
+            ".SH FILES\n.TP\n.I ~/.config/grelier/\n\
+             Settings file, style.toml, user themes, and plugins.\n",
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELP: &str = "Usage: grelier [-s <settings...>] [--list-themes] [--lock <lock>]\n\n\
+        Grelier command line argument spec\n\n\
+        Options:\n  \
+        -s, --settings    setting override; repeat for multiple pairs (key=value or\n                    \
+        key:value)\n  \
+        --list-themes     list available themes and exit\n  \
+        --lock            lock (on), unlock (off), or toggle input\n  \
+        --help, help      display usage information\n";
+
+    #[test]
+    fn parses_help_and_weaves_registry_into_completions() {
+        let options = parse_help(HELP);
+        assert_eq!(options.len(), 3);
+        assert_eq!(options[0].short, Some('s'));
+        assert_eq!(
+            options[0].help,
+            "setting override; repeat for multiple pairs (key=value or key:value)"
+        );
+        assert!(options[0].takes_value && options[0].repeated);
+        assert!(!options[1].takes_value);
+        assert!(options[2].takes_value && !options[2].repeated);
+
+        let settings = [SettingSpec {
+            key: "grelier.bar.width",
+            default: "28",
+        }];
+        let data = CliData {
+            options,
+            gauges: vec![("cpu", "CPU gauge")],
+            themes: vec!["Nord".to_string()],
+            settings: &settings,
+        };
+        let bash = data.completions(Shell::Bash);
+        assert!(bash.contains("-s|--settings) COMPREPLY=($(compgen -W \"grelier.bar.width= grelier.bar.theme=Nord grelier.gauges=cpu\""));
+        assert!(bash.contains("--lock) COMPREPLY=($(compgen -W \"on off toggle\""));
+        assert!(
+            data.completions(Shell::Zsh)
+                .contains("'*-s[setting override; repeat")
+        );
+        assert!(data.completions(Shell::Fish).contains(
+            "-l lock -d 'lock (on), unlock (off), or toggle input' -x -a 'on off toggle'"
+        ));
+        let man = data.man_page();
+        assert!(man.contains(".B cpu\nCPU gauge"));
+        assert!(man.contains(".B \\-\\-list\\-themes\n"));
+        assert!("tcsh".parse::<Shell>().is_err());
+    }
+}
@@ -0,0 +1,389 @@
+// Hyprland IPC backend for workspace state, focus, and event subscriptions.
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+use super::{
+    CompositorBackend, CompositorEvent, EventIter, FocusedWindow, OutputInfo, Rect, WorkspaceApp,
+    WorkspaceApps, WorkspaceInfo,
+};
+use serde_json::Value;
+
+/// Backend talking to Hyprland over its command (`.socket.sock`) and event (`.socket2.sock`) sockets.
+pub struct HyprlandBackend {
+    socket_dir: Option<PathBuf>,
+}
+
+impl HyprlandBackend {
+    pub fn from_env() -> Self {
+        let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok();
+        let runtime_dir = std::env::var("XDG_RUNTIME_DIR").ok();
+        Self {
+            socket_dir: signature.map(|signature| socket_dir(runtime_dir.as_deref(), &signature)),
+        }
+    }
+
+    fn socket(&self, name: &str) -> Result<PathBuf, String> {
+        self.socket_dir
+            .as_ref()
+            .map(|dir| dir.join(name))
+            .ok_or_else(|| "HYPRLAND_INSTANCE_SIGNATURE is not set".to_string())
+    }
+
+    /// Send one command and return the raw reply; Hyprland closes the socket after replying.
+    fn command(&self, command: &str) -> Result<String, String> {
+        let path = self.socket(".socket.sock")?;
+        let mut stream = UnixStream::connect(&path)
+            .map_err(|err| format!("failed to connect to {}: {err}", path.display()))?;
+        stream
+            .write_all(command.as_bytes())
+            .map_err(|err| format!("failed to send command: {err}"))?;
+        let mut reply = String::new();
+        stream
+            .read_to_string(&mut reply)
+            .map_err(|err| format!("failed to read reply: {err}"))?;
+        Ok(reply)
+    }
+
+    fn query(&self, what: &str) -> Result<Value, String> {
+        let reply = self.command(&format!("j/{what}"))?;
+        serde_json::from_str(&reply).map_err(|err| format!("invalid {what} reply: {err}"))
+    }
+
+    fn dispatch(&self, dispatcher: &str) -> Result<(), String> {
+        let reply = self.command(&format!("dispatch {dispatcher}"))?;
+        if reply.trim() == "ok" {
+            Ok(())
+        } else {
+            Err(reply.trim().to_string())
+        }
+    }
+}
+
+/// Resolve the instance socket directory, preferring the modern runtime-dir location.
+fn socket_dir(runtime_dir: Option<&str>, signature: &str) -> PathBuf {
+    if let Some(runtime_dir) = runtime_dir.filter(|dir| !dir.trim().is_empty()) {
+        let dir = Path::new(runtime_dir).join("hypr").join(signature);
+        if dir.exists() {
+            return dir;
+        }
+    }
+    Path::new("/tmp/hypr").join(signature)
+}
+
+fn int_field(value: &Value, key: &str) -> i64 {
+    value.get(key).and_then(Value::as_i64).unwrap_or_default()
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn parse_outputs(monitors: &Value) -> Vec<OutputInfo> {
+    let mut out: Vec<OutputInfo> = monitors
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|monitor| {
+            let scale = monitor
+                .get("scale")
+                .and_then(Value::as_f64)
+                .filter(|scale| *scale > 0.0)
+                .unwrap_or(1.0);
+            let logical = |key: &str| (int_field(monitor, key) as f64 / scale).round() as i32;
+            OutputInfo {
+                name: str_field(monitor, "name").to_string(),
+                active: !monitor
+                    .get("disabled")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+                make: str_field(monitor, "make").to_string(),
+                model: str_field(monitor, "model").to_string(),
+                rect: (
+                    int_field(monitor, "x") as i32,
+                    int_field(monitor, "y") as i32,
+                    logical("width"),
+                    logical("height"),
+                ),
+                scale,
+            }
+        })
+        .collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+fn parse_workspaces(
+    workspaces: &Value,
+    active_id: i64,
+    outputs: &[OutputInfo],
+) -> Vec<WorkspaceInfo> {
+    let mut out: Vec<(i64, WorkspaceInfo)> = workspaces
+        .as_array()
+        .into_iter()
+        .flatten()
+        // Negative ids are special workspaces (scratchpads), which sway also hides.
+        .filter(|ws| int_field(ws, "id") > 0)
+        .map(|ws| {
+            let id = int_field(ws, "id");
+            let monitor = str_field(ws, "monitor");
+            let (_, y, _, height) = outputs
+                .iter()
+                .find(|output| output.name == monitor)
+                .map(|output| output.rect)
+                .unwrap_or_default();
+            let info = WorkspaceInfo {
+                num: id as i32,
+                name: str_field(ws, "name").to_string(),
+                label: str_field(ws, "name").to_string(),
+                focused: id == active_id,
+                // Hyprland reports urgency only as transient `urgent>>` events.
+                urgent: false,
+                rect: Rect { y, height },
+            };
+            (id, info)
+        })
+        .collect();
+    out.sort_by(|a, b| (a.0, &a.1.name).cmp(&(b.0, &b.1.name)));
+    out.into_iter().map(|(_, info)| info).collect()
+}
+
+fn parse_workspace_apps(workspaces: &Value, clients: &Value) -> Vec<WorkspaceApps> {
+    let mut by_id: HashMap<i64, WorkspaceApps> = workspaces
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|ws| int_field(ws, "id") > 0)
+        .map(|ws| {
+            (
+                int_field(ws, "id"),
+                WorkspaceApps {
+                    name: str_field(ws, "name").to_string(),
+                    apps: Vec::new(),
+                },
+            )
+        })
+        .collect();
+
+    for client in clients.as_array().into_iter().flatten() {
+        let workspace_id = client
+            .get("workspace")
+            .map(|ws| int_field(ws, "id"))
+            .unwrap_or_default();
+        let Some(entry) = by_id.get_mut(&workspace_id) else {
+            continue;
+        };
+        let app_id = [str_field(client, "class"), str_field(client, "title")]
+            .into_iter()
+            .find(|value| !value.is_empty());
+        let Some(app_id) = app_id else {
+            continue;
+        };
+        entry.apps.push(WorkspaceApp {
+            app_id: app_id.to_string(),
+            con_id: parse_address(str_field(client, "address")).unwrap_or_default(),
+        });
+    }
+
+    let mut out: Vec<WorkspaceApps> = by_id.into_values().collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+/// Client addresses are hex strings such as `0x55d0c8a3e2f0`.
+fn parse_address(address: &str) -> Option<i64> {
+    i64::from_str_radix(address.trim_start_matches("0x"), 16).ok()
+}
+
+/// Map one `.socket2.sock` line (`event>>data`) to a compositor event, if it is relevant.
+fn classify_event(line: &str, outputs_only: bool) -> Option<CompositorEvent> {
+    let (name, _) = line.split_once(">>")?;
+    match name {
+        "monitoradded" | "monitoraddedv2" | "monitorremoved" | "monitorremovedv2" => {
+            Some(CompositorEvent::Outputs)
+        }
+        _ if outputs_only => None,
+        "workspace" | "workspacev2" | "focusedmon" | "focusedmonv2" | "createworkspace"
+        | "createworkspacev2" | "destroyworkspace" | "destroyworkspacev2" | "moveworkspace"
+        | "moveworkspacev2" | "renameworkspace" | "openwindow" | "closewindow" | "movewindow"
+        | "movewindowv2" | "urgent" | "activewindow" | "activewindowv2" => {
+            Some(CompositorEvent::Workspaces)
+        }
+        _ => None,
+    }
+}
+
+impl CompositorBackend for HyprlandBackend {
+    fn name(&self) -> &'static str {
+        "hyprland"
+    }
+
+    fn fetch_workspaces(&self) -> Result<Vec<WorkspaceInfo>, String> {
+        let outputs = parse_outputs(&self.query("monitors all")?);
+        let active_id = int_field(&self.query("activeworkspace")?, "id");
+        Ok(parse_workspaces(
+            &self.query("workspaces")?,
+            active_id,
+            &outputs,
+        ))
+    }
+
+    fn fetch_workspace_apps(&self) -> Result<Vec<WorkspaceApps>, String> {
+        Ok(parse_workspace_apps(
+            &self.query("workspaces")?,
+            &self.query("clients")?,
+        ))
+    }
+
+    fn fetch_outputs(&self) -> Result<Vec<OutputInfo>, String> {
+        Ok(parse_outputs(&self.query("monitors all")?))
+    }
+
+    fn fetch_focused_app(&self) -> Result<Option<String>, String> {
+        let window = self.query("activewindow")?;
+        Ok(["class", "initialClass"]
+            .iter()
+            .find_map(|field| window.get(field).and_then(Value::as_str))
+            .filter(|class| !class.is_empty())
+            .map(ToString::to_string))
+    }
+
+    fn fetch_focused_window(&self) -> Result<Option<FocusedWindow>, String> {
+        let window = self.query("activewindow")?;
+        let app_id = ["class", "initialClass"]
+            .iter()
+            .map(|field| str_field(&window, field))
+            .find(|class| !class.is_empty());
+        Ok(app_id.map(|app_id| FocusedWindow {
+            app_id: app_id.to_string(),
+            title: str_field(&window, "title").to_string(),
+        }))
+    }
+
+    fn focus_workspace(&self, name: &str) -> Result<(), String> {
+        let workspaces = self.query("workspaces")?;
+        let id = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|ws| str_field(ws, "name") == name)
+            .map(|ws| int_field(ws, "id"))
+            .ok_or_else(|| format!("no workspace named \"{name}\""))?;
+        self.dispatch(&format!("workspace {id}"))
+    }
+
+    fn rename_workspace(&self, name: &str, new_name: &str) -> Result<(), String> {
+        let workspaces = self.query("workspaces")?;
+        let id = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|ws| str_field(ws, "name") == name)
+            .map(|ws| int_field(ws, "id"))
+            .ok_or_else(|| format!("no workspace named \"{name}\""))?;
+        self.dispatch(&format!("renameworkspace {id} {new_name}"))
+    }
+
+    fn move_workspace(&self, _name: &str, _target: &str) -> Result<(), String> {
+        Err("hyprland does not support reordering workspaces".to_string())
+    }
+
+    fn focus_con_id(&self, con_id: i64) -> Result<(), String> {
+        self.dispatch(&format!("focuswindow address:0x{con_id:x}"))
+    }
+
+    fn close_con_id(&self, con_id: i64) -> Result<(), String> {
+        self.dispatch(&format!("closewindow address:0x{con_id:x}"))
+    }
+
+    fn move_con_to_workspace(&self, con_id: i64, workspace: &str) -> Result<(), String> {
+        let workspaces = self.query("workspaces")?;
+        let id = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|ws| str_field(ws, "name") == workspace)
+            .map(|ws| int_field(ws, "id"))
+            .ok_or_else(|| format!("no workspace named \"{workspace}\""))?;
+        self.dispatch(&format!("movetoworkspacesilent {id},address:0x{con_id:x}"))
+    }
+
+    fn move_focused_to_workspace(&self, workspace: &str) -> Result<(), String> {
+        let workspaces = self.query("workspaces")?;
+        let id = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|ws| str_field(ws, "name") == workspace)
+            .map(|ws| int_field(ws, "id"))
+            .ok_or_else(|| format!("no workspace named \"{workspace}\""))?;
+        self.dispatch(&format!("movetoworkspacesilent {id}"))
+    }
+
+    fn launch_app(&self, app_id: &str) -> Result<(), String> {
+        let escaped = app_id.replace('"', "\\\"");
+        self.dispatch(&format!("exec gtk-launch \"{escaped}\""))
+    }
+
+    fn subscribe(&self, outputs_only: bool) -> Result<EventIter, String> {
+        let path = self.socket(".socket2.sock")?;
+        let stream = UnixStream::connect(&path)
+            .map_err(|err| format!("failed to connect to {}: {err}", path.display()))?;
+        Ok(Box::new(BufReader::new(stream).lines().filter_map(
+            move |line| match line {
+                Ok(line) => classify_event(&line, outputs_only).map(Ok),
+                Err(err) => Some(Err(err.to_string())),
+            },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_workspaces_and_clients_from_ipc_json() {
+        let outputs = parse_outputs(&json!([
+            { "name": "eDP-1", "make": "BOE", "model": "0x095F", "x": 0, "y": 0, "width": 2880, "height": 1800, "scale": 2.0, "disabled": false }
+        ]));
+        assert_eq!(outputs[0].rect, (0, 0, 1440, 900));
+
+        let workspaces = json!([
+            { "id": 2, "name": "2", "monitor": "eDP-1" },
+            { "id": -98, "name": "special:scratch", "monitor": "eDP-1" },
+            { "id": 1, "name": "1", "monitor": "eDP-1" }
+        ]);
+        let info = parse_workspaces(&workspaces, 2, &outputs);
+        let names: Vec<&str> = info.iter().map(|ws| ws.name.as_str()).collect();
+        assert_eq!(names, vec!["1", "2"]);
+        assert!(!info[0].focused && info[1].focused);
+        assert_eq!(info[0].rect.height, 900);
+
+        let clients = json!([
+            { "address": "0x5a", "class": "kitty", "title": "~", "workspace": { "id": 1, "name": "1" } },
+            { "address": "0x5b", "class": "", "title": "Picture-in-Picture", "workspace": { "id": 2, "name": "2" } }
+        ]);
+        let apps = parse_workspace_apps(&workspaces, &clients);
+        assert_eq!(apps.len(), 2);
+        assert_eq!(apps[0].apps[0].app_id, "kitty");
+        assert_eq!(apps[0].apps[0].con_id, 0x5a);
+        assert_eq!(apps[1].apps[0].app_id, "Picture-in-Picture");
+    }
+
+    #[test]
+    fn classifies_event_socket_lines() {
+        assert_eq!(
+            classify_event("monitoradded>>HDMI-A-1", true),
+            Some(CompositorEvent::Outputs)
+        );
+        assert_eq!(classify_event("workspace>>3", true), None);
+        assert_eq!(
+            classify_event("openwindow>>5a,1,kitty,~", false),
+            Some(CompositorEvent::Workspaces)
+        );
+        assert_eq!(classify_event("activelayout>>kbd,us", false), None);
+    }
+}
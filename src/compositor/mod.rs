@@ -0,0 +1,501 @@
+// Compositor backend abstraction for workspace/output queries, focus actions, and events.
+// Consumes Settings: grelier.compositor.
+pub mod hyprland;
+pub mod niri;
+pub mod sway;
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::bar::Message;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceInfo {
+    pub num: i32,
+    /// Identity used in compositor requests.
+    pub name: String,
+    /// Name shown to the user; differs from `name` where that is an internal id.
+    pub label: String,
+    pub focused: bool,
+    pub urgent: bool,
+    pub rect: Rect,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceApps {
+    pub name: String,
+    pub apps: Vec<WorkspaceApp>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceApp {
+    pub app_id: String,
+    pub con_id: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rect {
+    pub y: i32,
+    pub height: i32,
+}
+
+/// The window that currently has keyboard focus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FocusedWindow {
+    pub app_id: String,
+    pub title: String,
+}
+
+/// Compositor-neutral description of a display output.
+#[derive(Debug, Clone)]
+pub struct OutputInfo {
+    pub name: String,
+    pub active: bool,
+    pub make: String,
+    pub model: String,
+    /// Logical output rectangle as `(x, y, width, height)`.
+    pub rect: (i32, i32, i32, i32),
+    /// Device pixels per logical pixel; fractional on scaled outputs.
+    pub scale: f64,
+}
+
+/// Output display features toggled through the compositor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFeature {
+    /// Variable refresh rate (VRR).
+    AdaptiveSync,
+    Hdr,
+}
+
+impl OutputFeature {
+    pub const ALL: [OutputFeature; 2] = [OutputFeature::AdaptiveSync, OutputFeature::Hdr];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OutputFeature::AdaptiveSync => "Adaptive sync",
+            OutputFeature::Hdr => "HDR",
+        }
+    }
+
+    /// Name used in sway `output` commands.
+    pub fn key(self) -> &'static str {
+        match self {
+            OutputFeature::AdaptiveSync => "adaptive_sync",
+            OutputFeature::Hdr => "hdr",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|feature| feature.key() == key)
+    }
+}
+
+/// Feature state of one active output; `None` where the compositor cannot toggle it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputFeatures {
+    pub name: String,
+    pub adaptive_sync: Option<bool>,
+    pub hdr: Option<bool>,
+}
+
+impl OutputFeatures {
+    pub fn get(&self, feature: OutputFeature) -> Option<bool> {
+        match feature {
+            OutputFeature::AdaptiveSync => self.adaptive_sync,
+            OutputFeature::Hdr => self.hdr,
+        }
+    }
+}
+
+/// Change notifications emitted by a compositor event stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompositorEvent {
+    /// Workspace or window state changed.
+    Workspaces,
+    /// Output configuration changed.
+    Outputs,
+}
+
+/// Blocking iterator of compositor events; ends when the connection closes.
+pub type EventIter = Box<dyn Iterator<Item = Result<CompositorEvent, String>> + Send>;
+
+/// Interface implemented by each supported compositor IPC.
+pub trait CompositorBackend: Send + Sync {
+    /// Short backend name used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Fetch workspaces in display order.
+    fn fetch_workspaces(&self) -> Result<Vec<WorkspaceInfo>, String>;
+
+    /// Fetch the apps on each workspace, keyed by workspace name.
+    fn fetch_workspace_apps(&self) -> Result<Vec<WorkspaceApps>, String>;
+
+    /// Fetch the current outputs.
+    fn fetch_outputs(&self) -> Result<Vec<OutputInfo>, String>;
+
+    /// App id (or window class) of the focused window, if a window has focus.
+    fn fetch_focused_app(&self) -> Result<Option<String>, String>;
+
+    /// App id and title of the focused window, if a window has focus.
+    fn fetch_focused_window(&self) -> Result<Option<FocusedWindow>, String>;
+
+    /// Focus the workspace with the given name.
+    fn focus_workspace(&self, name: &str) -> Result<(), String>;
+
+    /// Rename a workspace.
+    fn rename_workspace(&self, name: &str, new_name: &str) -> Result<(), String>;
+
+    /// Move the workspace named `name` to the position currently held by `target`.
+    fn move_workspace(&self, name: &str, target: &str) -> Result<(), String>;
+
+    /// Focus the window/container with the given compositor id.
+    fn focus_con_id(&self, con_id: i64) -> Result<(), String>;
+
+    /// Ask the window/container with the given compositor id to close.
+    fn close_con_id(&self, con_id: i64) -> Result<(), String>;
+
+    /// Move the window/container with the given compositor id to the named workspace.
+    fn move_con_to_workspace(&self, con_id: i64, workspace: &str) -> Result<(), String>;
+
+    /// Move the focused window to the named workspace without following it.
+    fn move_focused_to_workspace(&self, workspace: &str) -> Result<(), String>;
+
+    /// Launch an application using the desktop app id.
+    fn launch_app(&self, app_id: &str) -> Result<(), String>;
+
+    /// Open an event stream. When `outputs_only` is set, only output changes are reported.
+    fn subscribe(&self, outputs_only: bool) -> Result<EventIter, String>;
+
+    /// Layout rectangles `(x, y, width, height)` of the windows currently on screen.
+    fn fetch_visible_windows(&self) -> Result<Vec<(i32, i32, i32, i32)>, String> {
+        Err(format!("{} cannot list window geometry", self.name()))
+    }
+
+    /// Adaptive sync and HDR state of each active output.
+    fn fetch_output_features(&self) -> Result<Vec<OutputFeatures>, String> {
+        Err(format!("{} cannot toggle output features", self.name()))
+    }
+
+    /// Turn an output feature on or off.
+    fn set_output_feature(
+        &self,
+        _output: &str,
+        _feature: OutputFeature,
+        _enabled: bool,
+    ) -> Result<(), String> {
+        Err(format!("{} cannot toggle output features", self.name()))
+    }
+}
+
+/// Supported compositor backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompositorKind {
+    Sway,
+    Niri,
+    Hyprland,
+}
+
+/// How long startup waits for the compositor IPC to answer with active outputs.
+const STARTUP_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+const STARTUP_RETRY_INTERVAL: Duration = Duration::from_millis(250);
+/// Attempts made to open the workspace event stream before giving up.
+const SUBSCRIBE_ATTEMPTS: u32 = 8;
+
+static BACKEND: OnceLock<Box<dyn CompositorBackend>> = OnceLock::new();
+
+/// Compositor IPC environment variables used for auto-detection.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CompositorEnv<'a> {
+    pub sway_socket: Option<&'a str>,
+    pub niri_socket: Option<&'a str>,
+    pub hyprland_signature: Option<&'a str>,
+}
+
+/// Resolve the `grelier.compositor` setting value, falling back to environment detection.
+pub fn resolve_kind(setting: &str, env: CompositorEnv<'_>) -> Result<CompositorKind, String> {
+    match setting.trim().to_ascii_lowercase().as_str() {
+        "sway" => Ok(CompositorKind::Sway),
+        "niri" => Ok(CompositorKind::Niri),
+        "hyprland" => Ok(CompositorKind::Hyprland),
+        "" | "auto" => {
+            let set = |value: Option<&str>| value.is_some_and(|v| !v.trim().is_empty());
+            if set(env.sway_socket) {
+                Ok(CompositorKind::Sway)
+            } else if set(env.niri_socket) {
+                Ok(CompositorKind::Niri)
+            } else if set(env.hyprland_signature) {
+                Ok(CompositorKind::Hyprland)
+            } else {
+                Ok(CompositorKind::Sway)
+            }
+        }
+        other => Err(format!(
+            "Invalid compositor '{other}', expected 'auto', 'sway', 'niri', or 'hyprland'"
+        )),
+    }
+}
+
+fn detect_kind(setting: &str) -> Result<CompositorKind, String> {
+    let sway_socket = std::env::var("SWAYSOCK").ok();
+    let niri_socket = std::env::var("NIRI_SOCKET").ok();
+    let hyprland_signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok();
+    resolve_kind(
+        setting,
+        CompositorEnv {
+            sway_socket: sway_socket.as_deref(),
+            niri_socket: niri_socket.as_deref(),
+            hyprland_signature: hyprland_signature.as_deref(),
+        },
+    )
+}
+
+fn create_backend(kind: CompositorKind) -> Box<dyn CompositorBackend> {
+    match kind {
+        CompositorKind::Sway => Box::new(sway::SwayBackend),
+        CompositorKind::Niri => Box::new(niri::NiriBackend::from_env()),
+        CompositorKind::Hyprland => Box::new(hyprland::HyprlandBackend::from_env()),
+    }
+}
+
+/// Select the backend from the `grelier.compositor` setting value.
+///
+/// Must be called before the first call to [`backend`] to take effect.
+pub fn init(setting: &str) -> Result<(), String> {
+    let kind = detect_kind(setting)?;
+    let selected = BACKEND.get_or_init(|| create_backend(kind));
+    log::info!("Using {} compositor backend", selected.name());
+    Ok(())
+}
+
+/// Active compositor backend, auto-detected if [`init`] was not called.
+pub fn backend() -> &'static dyn CompositorBackend {
+    BACKEND
+        .get_or_init(|| create_backend(detect_kind("auto").unwrap_or(CompositorKind::Sway)))
+        .as_ref()
+}
+
+/// Call `attempt` until it succeeds or `timeout` elapses, sleeping `interval` between tries.
+///
+/// Returns the last error on timeout. The first failure and eventual recovery are logged so
+/// slow compositor startups are visible without flooding the log.
+fn retry_until<T>(
+    what: &str,
+    timeout: Duration,
+    interval: Duration,
+    mut attempt: impl FnMut() -> Result<T, String>,
+) -> Result<T, String> {
+    let started = Instant::now();
+    let mut attempts = 0u32;
+    loop {
+        attempts += 1;
+        match attempt() {
+            Ok(value) => {
+                if attempts > 1 {
+                    log::info!("{what} ready after {attempts} attempts");
+                }
+                return Ok(value);
+            }
+            Err(err) => {
+                if attempts == 1 {
+                    log::info!("Waiting for {what}: {err}");
+                }
+                if started.elapsed() + interval > timeout {
+                    return Err(err);
+                }
+                std::thread::sleep(interval);
+            }
+        }
+    }
+}
+
+/// Block until the compositor IPC reports at least one active output.
+///
+/// When grelier is started by the compositor's own autostart, the IPC socket or output list
+/// may not be ready yet. This waits for a bounded time and only logs on timeout so startup
+/// continues either way.
+pub fn wait_until_ready() {
+    let backend = backend();
+    let what = format!("{} IPC", backend.name());
+    let result = retry_until(&what, STARTUP_WAIT_TIMEOUT, STARTUP_RETRY_INTERVAL, || {
+        let outputs = backend.fetch_outputs()?;
+        if outputs.iter().any(|output| output.active) {
+            Ok(())
+        } else {
+            Err("no active outputs reported yet".to_string())
+        }
+    });
+    if let Err(err) = result {
+        log::warn!(
+            "{what} not ready after {}s, continuing anyway: {err}",
+            STARTUP_WAIT_TIMEOUT.as_secs()
+        );
+    }
+}
+
+/// Source key used for compositor failures in the bar's degraded-state banner.
+pub const DEGRADED_SOURCE: &str = "compositor";
+
+/// Workspace/window event subscription; a new `generation` restarts the stream.
+pub fn workspace_subscription(generation: u64) -> Subscription<Message> {
+    Subscription::run_with(generation, |_| workspace_stream())
+}
+
+/// Output-only event subscription; a new `generation` restarts the stream.
+pub fn output_subscription(generation: u64) -> Subscription<Message> {
+    Subscription::run_with(generation, |_| output_stream())
+}
+
+fn degraded(message: String) -> Message {
+    Message::Degraded {
+        source: DEGRADED_SOURCE.to_string(),
+        message,
+    }
+}
+
+fn workspace_stream() -> impl iced::futures::Stream<Item = Message> {
+    let (mut sender, receiver) = mpsc::channel(16);
+
+    std::thread::spawn(move || {
+        let backend = backend();
+        let send_workspaces = |sender: &mut mpsc::Sender<Message>| match backend.fetch_workspaces()
+        {
+            Ok(workspaces) => {
+                let apps = match backend.fetch_workspace_apps() {
+                    Ok(apps) => apps,
+                    Err(err) => {
+                        log::error!("Failed to fetch workspace app names: {err}");
+                        Vec::new()
+                    }
+                };
+                let _ = sender.try_send(Message::Workspaces { workspaces, apps });
+            }
+            Err(err) => {
+                log::error!("Failed to fetch workspaces: {err}");
+                let _ = sender.try_send(degraded(format!("workspace query failed: {err}")));
+            }
+        };
+
+        send_workspaces(&mut sender);
+
+        let stream = match retry_until(
+            "workspace event stream",
+            STARTUP_RETRY_INTERVAL * SUBSCRIBE_ATTEMPTS,
+            STARTUP_RETRY_INTERVAL,
+            || backend.subscribe(false),
+        ) {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::error!("Failed to subscribe to workspace events: {err}");
+                let _ = sender.try_send(degraded(format!("event stream unavailable: {err}")));
+                return;
+            }
+        };
+
+        for event in stream {
+            match event {
+                Ok(CompositorEvent::Workspaces) => send_workspaces(&mut sender),
+                Ok(CompositorEvent::Outputs) => {
+                    let _ = sender.try_send(Message::OutputChanged);
+                    send_workspaces(&mut sender);
+                }
+                Err(err) => {
+                    log::error!("Workspace event stream error: {err}");
+                    break;
+                }
+            }
+        }
+        let _ = sender.try_send(degraded("event stream closed".to_string()));
+    });
+
+    receiver
+}
+
+fn output_stream() -> impl iced::futures::Stream<Item = Message> {
+    let (mut sender, receiver) = mpsc::channel(16);
+
+    std::thread::spawn(move || {
+        let stream = match retry_until(
+            "output event stream",
+            STARTUP_RETRY_INTERVAL * SUBSCRIBE_ATTEMPTS,
+            STARTUP_RETRY_INTERVAL,
+            || backend().subscribe(true),
+        ) {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::error!("Failed to subscribe to output events: {err}");
+                let _ = sender.try_send(degraded(format!("output stream unavailable: {err}")));
+                return;
+            }
+        };
+
+        for event in stream {
+            match event {
+                Ok(CompositorEvent::Outputs) => {
+                    let _ = sender.try_send(Message::OutputChanged);
+                }
+                Ok(CompositorEvent::Workspaces) => {}
+                Err(err) => {
+                    log::error!("Output event stream error: {err}");
+                    break;
+                }
+            }
+        }
+        let _ = sender.try_send(degraded("output stream closed".to_string()));
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_compositor_from_setting_and_environment() {
+        let niri = CompositorEnv {
+            niri_socket: Some("/run/niri.sock"),
+            ..CompositorEnv::default()
+        };
+        let sway = CompositorEnv {
+            sway_socket: Some("/run/sway.sock"),
+            ..CompositorEnv::default()
+        };
+        let hyprland = CompositorEnv {
+            hyprland_signature: Some("abc123"),
+            ..CompositorEnv::default()
+        };
+        assert_eq!(resolve_kind("auto", niri), Ok(CompositorKind::Niri));
+        assert_eq!(resolve_kind("auto", sway), Ok(CompositorKind::Sway));
+        assert_eq!(resolve_kind("auto", hyprland), Ok(CompositorKind::Hyprland));
+        assert_eq!(
+            resolve_kind("auto", CompositorEnv::default()),
+            Ok(CompositorKind::Sway)
+        );
+        assert_eq!(resolve_kind("Niri", sway), Ok(CompositorKind::Niri));
+        assert!(resolve_kind("weston", sway).is_err());
+    }
+
+    #[test]
+    fn retry_until_stops_on_success_or_timeout() {
+        let mut calls = 0;
+        let result = retry_until("test", Duration::from_secs(1), Duration::ZERO, || {
+            calls += 1;
+            if calls < 3 {
+                Err("not yet".to_string())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result, Ok(3));
+
+        let result: Result<(), String> = retry_until(
+            "test",
+            Duration::from_millis(5),
+            Duration::from_millis(2),
+            || Err("down".to_string()),
+        );
+        assert_eq!(result, Err("down".to_string()));
+    }
+}
@@ -0,0 +1,536 @@
+// Niri IPC backend for workspace state, focus, and event subscriptions.
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+use super::{
+    CompositorBackend, CompositorEvent, EventIter, FocusedWindow, OutputInfo, Rect, WorkspaceApp,
+    WorkspaceApps, WorkspaceInfo,
+};
+use serde_json::{Value, json};
+
+/// Backend talking to niri over the JSON socket named by `NIRI_SOCKET`.
+pub struct NiriBackend {
+    socket: Option<PathBuf>,
+}
+
+impl NiriBackend {
+    pub fn from_env() -> Self {
+        Self {
+            socket: std::env::var_os("NIRI_SOCKET").map(PathBuf::from),
+        }
+    }
+
+    fn connect(&self, request: &Value) -> Result<BufReader<UnixStream>, String> {
+        let path = self
+            .socket
+            .as_ref()
+            .ok_or_else(|| "NIRI_SOCKET is not set".to_string())?;
+        let mut stream = UnixStream::connect(path)
+            .map_err(|err| format!("failed to connect to {}: {err}", path.display()))?;
+        let mut payload = request.to_string();
+        payload.push('\n');
+        stream
+            .write_all(payload.as_bytes())
+            .map_err(|err| format!("failed to send request: {err}"))?;
+        Ok(BufReader::new(stream))
+    }
+
+    /// Send one request and return the `Ok` payload of the reply.
+    fn request(&self, request: Value) -> Result<Value, String> {
+        let mut reader = self.connect(&request)?;
+        read_reply(&mut reader)
+    }
+
+    fn action(&self, action: Value) -> Result<(), String> {
+        self.request(json!({ "Action": action })).map(|_| ())
+    }
+
+    fn outputs(&self) -> Result<Value, String> {
+        self.request(json!("Outputs"))
+            .map(|reply| reply.get("Outputs").cloned().unwrap_or(Value::Null))
+    }
+
+    fn workspaces(&self) -> Result<Value, String> {
+        self.request(json!("Workspaces"))
+            .map(|reply| reply.get("Workspaces").cloned().unwrap_or(Value::Null))
+    }
+}
+
+fn read_reply(reader: &mut impl BufRead) -> Result<Value, String> {
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|err| format!("failed to read reply: {err}"))?;
+    let reply: Value =
+        serde_json::from_str(&line).map_err(|err| format!("invalid reply: {err}"))?;
+    if let Some(err) = reply.get("Err") {
+        return Err(err.as_str().unwrap_or("unknown niri error").to_string());
+    }
+    reply
+        .get("Ok")
+        .cloned()
+        .ok_or_else(|| "reply is missing an Ok payload".to_string())
+}
+
+/// Identity of a workspace. Unnamed workspaces are numbered per output, so their `idx` repeats
+/// across monitors and the niri id stands in for the name.
+fn workspace_name(workspace: &Value) -> String {
+    match workspace.get("name").and_then(Value::as_str) {
+        Some(name) => name.to_string(),
+        None => format!(
+            "#{}",
+            workspace
+                .get("id")
+                .and_then(Value::as_u64)
+                .unwrap_or_default()
+        ),
+    }
+}
+
+/// Name shown for a workspace: its name, or its index on its output.
+fn workspace_label(workspace: &Value) -> String {
+    match workspace.get("name").and_then(Value::as_str) {
+        Some(name) => name.to_string(),
+        None => workspace_idx(workspace).to_string(),
+    }
+}
+
+fn workspace_idx(workspace: &Value) -> i32 {
+    workspace.get("idx").and_then(Value::as_i64).unwrap_or(0) as i32
+}
+
+fn output_rect(output: &Value) -> Option<(i32, i32, i32, i32)> {
+    let logical = output.get("logical").filter(|value| !value.is_null())?;
+    let field = |key: &str| logical.get(key).and_then(Value::as_i64).unwrap_or(0) as i32;
+    Some((field("x"), field("y"), field("width"), field("height")))
+}
+
+fn parse_outputs(outputs: &Value) -> Vec<OutputInfo> {
+    let Some(outputs) = outputs.as_object() else {
+        return Vec::new();
+    };
+    let text = |output: &Value, key: &str| {
+        output
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let mut out: Vec<OutputInfo> = outputs
+        .iter()
+        .map(|(name, output)| {
+            let rect = output_rect(output);
+            OutputInfo {
+                name: name.clone(),
+                active: rect.is_some(),
+                make: text(output, "make"),
+                model: text(output, "model"),
+                rect: rect.unwrap_or_default(),
+                scale: output
+                    .get("logical")
+                    .and_then(|logical| logical.get("scale"))
+                    .and_then(Value::as_f64)
+                    .filter(|scale| *scale > 0.0)
+                    .unwrap_or(1.0),
+            }
+        })
+        .collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+fn parse_workspaces(workspaces: &Value, outputs: &[OutputInfo]) -> Vec<WorkspaceInfo> {
+    let Some(workspaces) = workspaces.as_array() else {
+        return Vec::new();
+    };
+    let mut sorted: Vec<&Value> = workspaces.iter().collect();
+    sorted.sort_by_key(|ws| {
+        (
+            ws.get("output")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            workspace_idx(ws),
+        )
+    });
+
+    sorted
+        .into_iter()
+        .map(|ws| {
+            let output = ws.get("output").and_then(Value::as_str);
+            let (_, y, _, height) = outputs
+                .iter()
+                .find(|info| Some(info.name.as_str()) == output)
+                .map(|info| info.rect)
+                .unwrap_or_default();
+            let flag = |key: &str| ws.get(key).and_then(Value::as_bool).unwrap_or(false);
+            WorkspaceInfo {
+                num: workspace_idx(ws),
+                name: workspace_name(ws),
+                label: workspace_label(ws),
+                focused: flag("is_focused"),
+                urgent: flag("is_urgent"),
+                rect: Rect { y, height },
+            }
+        })
+        .collect()
+}
+
+fn parse_workspace_apps(workspaces: &Value, windows: &Value) -> Vec<WorkspaceApps> {
+    let Some(workspaces) = workspaces.as_array() else {
+        return Vec::new();
+    };
+    let mut by_id: HashMap<u64, WorkspaceApps> = HashMap::new();
+    for ws in workspaces {
+        if let Some(id) = ws.get("id").and_then(Value::as_u64) {
+            by_id.insert(
+                id,
+                WorkspaceApps {
+                    name: workspace_name(ws),
+                    apps: Vec::new(),
+                },
+            );
+        }
+    }
+
+    for window in windows.as_array().into_iter().flatten() {
+        let Some(entry) = window
+            .get("workspace_id")
+            .and_then(Value::as_u64)
+            .and_then(|id| by_id.get_mut(&id))
+        else {
+            continue;
+        };
+        let Some(app_id) = window
+            .get("app_id")
+            .and_then(Value::as_str)
+            .or_else(|| window.get("title").and_then(Value::as_str))
+        else {
+            continue;
+        };
+        entry.apps.push(WorkspaceApp {
+            app_id: app_id.to_string(),
+            con_id: window.get("id").and_then(Value::as_i64).unwrap_or_default(),
+        });
+    }
+
+    let mut out: Vec<WorkspaceApps> = by_id.into_values().collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+/// What an output change is judged by: name, activity, layout, and scale.
+type OutputState = (String, bool, (i32, i32, i32, i32), f64);
+
+fn output_state(outputs: &[OutputInfo]) -> Vec<OutputState> {
+    outputs
+        .iter()
+        .map(|output| {
+            (
+                output.name.clone(),
+                output.active,
+                output.rect,
+                output.scale,
+            )
+        })
+        .collect()
+}
+
+/// Last seen outputs, to tell output hotplug apart from other workspace changes.
+struct OutputTracker {
+    backend: NiriBackend,
+    known: Vec<OutputState>,
+}
+
+impl OutputTracker {
+    fn new(backend: NiriBackend) -> Self {
+        let known = backend
+            .outputs()
+            .map(|outputs| output_state(&parse_outputs(&outputs)))
+            .unwrap_or_default();
+        Self { backend, known }
+    }
+
+    fn changed(&mut self) -> bool {
+        let current = match self.backend.outputs() {
+            Ok(outputs) => output_state(&parse_outputs(&outputs)),
+            Err(err) => {
+                log::warn!("niri: failed to query outputs: {err}");
+                return false;
+            }
+        };
+        let changed = current != self.known;
+        self.known = current;
+        changed
+    }
+}
+
+/// Map one event-stream line to compositor events. Niri has no dedicated output event; output
+/// hotplug re-announces all workspaces, so `outputs_changed` is asked on `WorkspacesChanged`.
+fn classify_event(
+    line: &str,
+    outputs_only: bool,
+    outputs_changed: impl FnOnce() -> bool,
+) -> Vec<CompositorEvent> {
+    let Ok(event) = serde_json::from_str::<Value>(line) else {
+        return Vec::new();
+    };
+    let Some(name) = event.as_object().and_then(|event| event.keys().next()) else {
+        return Vec::new();
+    };
+    let mut events = Vec::new();
+    if name == "WorkspacesChanged" && outputs_changed() {
+        events.push(CompositorEvent::Outputs);
+    }
+    if !outputs_only && (name.starts_with("Workspace") || name.starts_with("Window")) {
+        events.push(CompositorEvent::Workspaces);
+    }
+    events
+}
+
+impl CompositorBackend for NiriBackend {
+    fn name(&self) -> &'static str {
+        "niri"
+    }
+
+    fn fetch_workspaces(&self) -> Result<Vec<WorkspaceInfo>, String> {
+        let outputs = parse_outputs(&self.outputs()?);
+        Ok(parse_workspaces(&self.workspaces()?, &outputs))
+    }
+
+    fn fetch_workspace_apps(&self) -> Result<Vec<WorkspaceApps>, String> {
+        let workspaces = self.workspaces()?;
+        let windows = self
+            .request(json!("Windows"))?
+            .get("Windows")
+            .cloned()
+            .unwrap_or(Value::Null);
+        Ok(parse_workspace_apps(&workspaces, &windows))
+    }
+
+    fn fetch_outputs(&self) -> Result<Vec<OutputInfo>, String> {
+        Ok(parse_outputs(&self.outputs()?))
+    }
+
+    fn fetch_focused_app(&self) -> Result<Option<String>, String> {
+        let reply = self.request(json!("FocusedWindow"))?;
+        Ok(reply
+            .get("FocusedWindow")
+            .and_then(|window| window.get("app_id"))
+            .and_then(Value::as_str)
+            .filter(|app_id| !app_id.is_empty())
+            .map(ToString::to_string))
+    }
+
+    fn fetch_focused_window(&self) -> Result<Option<FocusedWindow>, String> {
+        let reply = self.request(json!("FocusedWindow"))?;
+        let Some(window) = reply
+            .get("FocusedWindow")
+            .filter(|window| !window.is_null())
+        else {
+            return Ok(None);
+        };
+        let field = |key: &str| {
+            window
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        Ok(Some(FocusedWindow {
+            app_id: field("app_id"),
+            title: field("title"),
+        }))
+    }
+
+    fn focus_workspace(&self, name: &str) -> Result<(), String> {
+        let workspaces = self.workspaces()?;
+        let id = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|ws| workspace_name(ws) == name)
+            .and_then(|ws| ws.get("id").and_then(Value::as_u64))
+            .ok_or_else(|| format!("no workspace named \"{name}\""))?;
+        self.action(json!({ "FocusWorkspace": { "reference": { "Id": id } } }))
+    }
+
+    fn rename_workspace(&self, _name: &str, _new_name: &str) -> Result<(), String> {
+        // Named niri workspaces persist when empty, so renaming would change their lifetime.
+        Err("niri workspaces are not renamed by grelier".to_string())
+    }
+
+    fn move_workspace(&self, name: &str, target: &str) -> Result<(), String> {
+        let workspaces = self.workspaces()?;
+        let find = |name: &str| {
+            workspaces
+                .as_array()
+                .into_iter()
+                .flatten()
+                .find(|ws| workspace_name(ws) == name)
+                .ok_or_else(|| format!("no workspace named \"{name}\""))
+        };
+        let (source, target) = (find(name)?, find(target)?);
+        if source.get("output") != target.get("output") {
+            return Err("workspaces can only be reordered within one output".to_string());
+        }
+        let id = source.get("id").and_then(Value::as_u64);
+        self.action(json!({
+            "MoveWorkspaceToIndex": { "index": workspace_idx(target), "reference": { "Id": id } }
+        }))
+    }
+
+    fn focus_con_id(&self, con_id: i64) -> Result<(), String> {
+        self.action(json!({ "FocusWindow": { "id": con_id } }))
+    }
+
+    fn close_con_id(&self, con_id: i64) -> Result<(), String> {
+        self.action(json!({ "CloseWindow": { "id": con_id } }))
+    }
+
+    fn move_con_to_workspace(&self, con_id: i64, workspace: &str) -> Result<(), String> {
+        let workspaces = self.workspaces()?;
+        let id = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|ws| workspace_name(ws) == workspace)
+            .and_then(|ws| ws.get("id").and_then(Value::as_u64))
+            .ok_or_else(|| format!("no workspace named \"{workspace}\""))?;
+        self.action(json!({
+            "MoveWindowToWorkspace": {
+                "window_id": con_id,
+                "reference": { "Id": id },
+                "focus": false
+            }
+        }))
+    }
+
+    fn move_focused_to_workspace(&self, workspace: &str) -> Result<(), String> {
+        let workspaces = self.workspaces()?;
+        let id = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|ws| workspace_name(ws) == workspace)
+            .and_then(|ws| ws.get("id").and_then(Value::as_u64))
+            .ok_or_else(|| format!("no workspace named \"{workspace}\""))?;
+        // A null window id moves the focused window.
+        self.action(json!({
+            "MoveWindowToWorkspace": {
+                "window_id": null,
+                "reference": { "Id": id },
+                "focus": false
+            }
+        }))
+    }
+
+    fn launch_app(&self, app_id: &str) -> Result<(), String> {
+        self.action(json!({ "Spawn": { "command": ["gtk-launch", app_id] } }))
+    }
+
+    fn subscribe(&self, outputs_only: bool) -> Result<EventIter, String> {
+        let mut tracker = OutputTracker::new(Self {
+            socket: self.socket.clone(),
+        });
+        let mut reader = self.connect(&json!("EventStream"))?;
+        read_reply(&mut reader)?;
+        Ok(Box::new(reader.lines().flat_map(move |line| {
+            match line {
+                Ok(line) => classify_event(&line, outputs_only, || tracker.changed())
+                    .into_iter()
+                    .map(Ok)
+                    .collect(),
+                Err(err) => vec![Err(err.to_string())],
+            }
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_workspaces_and_apps_from_ipc_json() {
+        let outputs = parse_outputs(&json!({
+            "DP-1": { "make": "Dell", "model": "U2720Q", "logical": { "x": 0, "y": 0, "width": 2560, "height": 1440 } },
+            "HDMI-A-1": { "make": "", "model": "", "logical": null }
+        }));
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs[0].active);
+        assert!(!outputs[1].active);
+
+        let workspaces = json!([
+            { "id": 7, "idx": 2, "name": "web", "output": "DP-1", "is_urgent": false, "is_focused": true },
+            { "id": 3, "idx": 1, "name": null, "output": "DP-1", "is_urgent": true, "is_focused": false }
+        ]);
+        let info = parse_workspaces(&workspaces, &outputs);
+        let names: Vec<&str> = info.iter().map(|ws| ws.name.as_str()).collect();
+        assert_eq!(names, vec!["#3", "web"]);
+        let labels: Vec<&str> = info.iter().map(|ws| ws.label.as_str()).collect();
+        assert_eq!(labels, vec!["1", "web"]);
+        assert!(info[0].urgent && info[1].focused);
+        assert_eq!(info[1].rect.height, 1440);
+
+        let windows = json!([
+            { "id": 11, "app_id": "firefox", "title": "Home", "workspace_id": 7 },
+            { "id": 12, "app_id": null, "title": "scratch", "workspace_id": 3 }
+        ]);
+        let apps = parse_workspace_apps(&workspaces, &windows);
+        assert_eq!(apps[0].name, "#3");
+        assert_eq!(apps[0].apps[0].app_id, "scratch");
+        assert_eq!(apps[1].apps[0].con_id, 11);
+    }
+
+    #[test]
+    fn unnamed_workspaces_on_each_output_keep_distinct_names() {
+        let outputs = parse_outputs(&json!({
+            "DP-1": { "logical": { "x": 0, "y": 0, "width": 1920, "height": 1080 } },
+            "DP-2": { "logical": { "x": 1920, "y": 0, "width": 1920, "height": 1080 } }
+        }));
+        let workspaces = json!([
+            { "id": 1, "idx": 1, "name": null, "output": "DP-1" },
+            { "id": 2, "idx": 1, "name": null, "output": "DP-2" }
+        ]);
+        let info = parse_workspaces(&workspaces, &outputs);
+        assert_eq!(info[0].label, info[1].label);
+        assert_ne!(info[0].name, info[1].name);
+        let target = workspaces
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|ws| workspace_name(ws) == info[1].name)
+            .and_then(|ws| ws.get("id"))
+            .and_then(Value::as_u64);
+        assert_eq!(target, Some(2));
+    }
+
+    #[test]
+    fn classifies_event_stream_lines() {
+        let changed = r#"{"WorkspacesChanged":{"workspaces":[]}}"#;
+        let focus = r#"{"WindowFocusChanged":{"id":4}}"#;
+        let layout = r#"{"KeyboardLayoutSwitched":{"idx":0}}"#;
+        assert_eq!(
+            classify_event(changed, true, || true),
+            vec![CompositorEvent::Outputs]
+        );
+        assert_eq!(classify_event(changed, true, || false), Vec::new());
+        assert_eq!(
+            classify_event(changed, false, || true),
+            vec![CompositorEvent::Outputs, CompositorEvent::Workspaces]
+        );
+        assert_eq!(
+            classify_event(changed, false, || false),
+            vec![CompositorEvent::Workspaces]
+        );
+        assert_eq!(classify_event(focus, true, || true), Vec::new());
+        assert_eq!(
+            classify_event(focus, false, || true),
+            vec![CompositorEvent::Workspaces]
+        );
+        assert_eq!(classify_event(layout, false, || true), Vec::new());
+    }
+}
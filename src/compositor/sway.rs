@@ -0,0 +1,665 @@
+// Sway IPC backend for workspace state, focus, and subscriptions.
+use std::cell::RefCell;
+
+use super::{
+    CompositorBackend, CompositorEvent, EventIter, FocusedWindow, OutputFeature, OutputFeatures,
+    OutputInfo, Rect, WorkspaceApp, WorkspaceApps, WorkspaceInfo,
+};
+use swayipc::{Connection, Error, EventStream, EventType, Node, NodeType, Workspace};
+use swayipc::{EnabledOrDisabled, Event};
+
+#[cfg(test)]
+type SwayConnection = FakeConnection;
+#[cfg(not(test))]
+type SwayConnection = Connection;
+
+thread_local! {
+    static COMMAND_CONN: RefCell<Option<SwayConnection>> = const { RefCell::new(None) };
+}
+
+/// Fetch and sort the current Sway workspaces.
+fn fetch_workspaces() -> Result<Vec<Workspace>, Error> {
+    with_command_conn(|conn| {
+        let mut workspaces = conn.get_workspaces()?;
+        workspaces.sort_by(|a, b| (a.num, &a.name).cmp(&(b.num, &b.name)));
+        Ok(workspaces)
+    })
+}
+
+fn fetch_workspace_apps() -> Result<Vec<WorkspaceApps>, Error> {
+    with_command_conn(|conn| {
+        let tree = conn.get_tree()?;
+        Ok(workspace_apps(&tree))
+    })
+}
+
+fn fetch_focused_app() -> Result<Option<String>, Error> {
+    with_command_conn(|conn| {
+        let tree = conn.get_tree()?;
+        Ok(focused_app(&tree))
+    })
+}
+
+fn focused_app(node: &Node) -> Option<String> {
+    if node.focused {
+        return app_name(node).filter(|_| node.node_type != NodeType::Workspace);
+    }
+    node.nodes
+        .iter()
+        .chain(node.floating_nodes.iter())
+        .find_map(focused_app)
+}
+
+fn fetch_focused_window() -> Result<Option<FocusedWindow>, Error> {
+    with_command_conn(|conn| {
+        let tree = conn.get_tree()?;
+        Ok(focused_window(&tree))
+    })
+}
+
+fn focused_window(node: &Node) -> Option<FocusedWindow> {
+    if node.focused {
+        if node.node_type == NodeType::Workspace {
+            return None;
+        }
+        return app_name(node).map(|app_id| FocusedWindow {
+            app_id,
+            title: node.name.clone().unwrap_or_default(),
+        });
+    }
+    node.nodes
+        .iter()
+        .chain(node.floating_nodes.iter())
+        .find_map(focused_window)
+}
+
+fn fetch_visible_windows() -> Result<Vec<(i32, i32, i32, i32)>, Error> {
+    with_command_conn(|conn| {
+        let tree = conn.get_tree()?;
+        let mut out = Vec::new();
+        collect_visible_windows(&tree, &mut out);
+        Ok(out)
+    })
+}
+
+fn collect_visible_windows(node: &Node, out: &mut Vec<(i32, i32, i32, i32)>) {
+    if node.visible == Some(true) {
+        let rect = &node.rect;
+        out.push((rect.x, rect.y, rect.width, rect.height));
+    }
+    for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+        collect_visible_windows(child, out);
+    }
+}
+
+/// Fetch the current Sway outputs.
+fn fetch_outputs() -> Result<Vec<swayipc::Output>, Error> {
+    with_command_conn(|conn| conn.get_outputs())
+}
+
+/// Sway added `output <name> hdr` in 1.11.
+const HDR_SINCE: (i32, i32) = (1, 11);
+
+fn output_features(outputs: Vec<swayipc::Output>, hdr_supported: bool) -> Vec<OutputFeatures> {
+    outputs
+        .into_iter()
+        .filter(|output| output.active)
+        .map(|output| OutputFeatures {
+            adaptive_sync: output
+                .adaptive_sync_status
+                .map(|status| status == EnabledOrDisabled::Enabled),
+            hdr: hdr_supported.then_some(output.hdr),
+            name: output.name,
+        })
+        .collect()
+}
+
+fn fetch_output_features() -> Result<Vec<OutputFeatures>, Error> {
+    with_command_conn(|conn| {
+        let version = conn.get_version()?;
+        let hdr_supported = (version.major, version.minor) >= HDR_SINCE;
+        Ok(output_features(conn.get_outputs()?, hdr_supported))
+    })
+}
+
+fn set_output_feature(output: &str, feature: OutputFeature, enabled: bool) -> Result<(), String> {
+    let escaped = output.replace('"', "\\\"");
+    let state = if enabled { "on" } else { "off" };
+    with_command_conn(|conn| {
+        conn.run_command(format!("output \"{escaped}\" {} {state}", feature.key()))
+    })
+    .map_err(|err| err.to_string())?
+    .into_iter()
+    .collect::<Result<(), Error>>()
+    .map_err(|err| err.to_string())
+}
+
+/// Subscribe to workspace-related events.
+fn subscribe_workspace_events() -> Result<EventStream, Error> {
+    Connection::new()?.subscribe([EventType::Workspace, EventType::Window, EventType::Output])
+}
+
+/// Subscribe to output-only events.
+fn subscribe_output_events() -> Result<EventStream, Error> {
+    Connection::new()?.subscribe([EventType::Output])
+}
+
+/// Focus the workspace with the given name.
+fn focus_workspace(name: &str) -> Result<(), Error> {
+    with_command_conn(|conn| {
+        let cmd = format!("workspace \"{}\"", name.replace('"', "\\\""));
+        conn.run_command(cmd)?;
+        Ok(())
+    })
+}
+
+fn rename_workspace(name: &str, new_name: &str) -> Result<(), Error> {
+    with_command_conn(|conn| {
+        let quote = |name: &str| format!("\"{}\"", name.replace('"', "\\\""));
+        conn.run_command(format!(
+            "rename workspace {} to {}",
+            quote(name),
+            quote(new_name)
+        ))?;
+        Ok(())
+    })
+}
+
+/// Leading workspace number replaced by `num`, keeping any `:label` suffix.
+fn with_number(name: &str, num: i32) -> String {
+    let rest = name.trim_start_matches(|c: char| c.is_ascii_digit());
+    format!("{num}{rest}")
+}
+
+/// Renames that move `workspaces[from]` to index `to` by swapping numbers around.
+///
+/// Sway orders workspaces by number, so the numbers held by the affected range are handed
+/// out again in the new order. Every workspace in that range must be numbered.
+fn reorder_renames(
+    workspaces: &[(i32, String)],
+    from: usize,
+    to: usize,
+) -> Result<Vec<(String, String)>, String> {
+    let (start, end) = (from.min(to), from.max(to));
+    let range = workspaces
+        .get(start..=end)
+        .ok_or_else(|| "workspace index out of range".to_string())?;
+    if let Some((_, name)) = range.iter().find(|(num, _)| *num < 0) {
+        return Err(format!("workspace \"{name}\" has no number to reorder by"));
+    }
+    let mut reordered: Vec<&(i32, String)> = range.iter().collect();
+    let moved = reordered.remove(from - start);
+    reordered.insert(to - start, moved);
+    Ok(range
+        .iter()
+        .zip(reordered)
+        .filter_map(|((num, _), (_, name))| {
+            let renamed = with_number(name, *num);
+            (renamed != *name).then(|| (name.clone(), renamed))
+        })
+        .collect())
+}
+
+/// Move a workspace to another's position by renumbering the workspaces in between.
+fn move_workspace(name: &str, target: &str) -> Result<(), String> {
+    let workspaces: Vec<(i32, String)> = fetch_workspaces()
+        .map_err(|err| err.to_string())?
+        .into_iter()
+        .map(|ws| (ws.num, ws.name))
+        .collect();
+    let position = |name: &str| {
+        workspaces
+            .iter()
+            .position(|(_, ws)| ws == name)
+            .ok_or_else(|| format!("no workspace named \"{name}\""))
+    };
+    let renames = reorder_renames(&workspaces, position(name)?, position(target)?)?;
+    if renames.is_empty() {
+        return Ok(());
+    }
+    // Rename through temporary names first so no step collides with an existing workspace.
+    let quote = |name: &str| format!("\"{}\"", name.replace('"', "\\\""));
+    let temp = |index: usize| format!("grelier-reorder-{index}");
+    let commands: Vec<String> =
+        renames
+            .iter()
+            .enumerate()
+            .map(|(index, (old, _))| format!("rename workspace {} to {}", quote(old), temp(index)))
+            .chain(renames.iter().enumerate().map(|(index, (_, new))| {
+                format!("rename workspace {} to {}", temp(index), quote(new))
+            }))
+            .collect();
+    with_command_conn(|conn| {
+        conn.run_command(commands.join("; "))?;
+        Ok(())
+    })
+    .map_err(|err| err.to_string())
+}
+
+/// Focus the container with the given Sway con_id.
+fn focus_con_id(con_id: i64) -> Result<(), Error> {
+    with_command_conn(|conn| {
+        let cmd = format!("[con_id={con_id}] focus");
+        let _ = conn.run_command(cmd)?;
+        Ok(())
+    })
+}
+
+fn close_con_id(con_id: i64) -> Result<(), Error> {
+    with_command_conn(|conn| {
+        conn.run_command(format!("[con_id={con_id}] kill"))?;
+        Ok(())
+    })
+}
+
+fn move_con_to_workspace(con_id: i64, workspace: &str) -> Result<(), Error> {
+    with_command_conn(|conn| {
+        let escaped = workspace.replace('"', "\\\"");
+        conn.run_command(format!(
+            "[con_id={con_id}] move container to workspace \"{escaped}\""
+        ))?;
+        Ok(())
+    })
+}
+
+fn move_focused_to_workspace(workspace: &str) -> Result<(), Error> {
+    with_command_conn(|conn| {
+        let escaped = workspace.replace('"', "\\\"");
+        conn.run_command(format!("move container to workspace \"{escaped}\""))?;
+        Ok(())
+    })
+}
+
+/// Launch an application using the desktop app id.
+fn launch_app(app_id: &str) -> Result<(), Error> {
+    with_command_conn(|conn| {
+        let escaped = app_id.replace('"', "\\\"");
+        let cmd = format!("exec gtk-launch \"{escaped}\"");
+        conn.run_command(cmd)?;
+        Ok(())
+    })
+}
+
+fn with_command_conn<R>(
+    f: impl FnOnce(&mut SwayConnection) -> Result<R, Error>,
+) -> Result<R, Error> {
+    COMMAND_CONN.with(|cell| {
+        if cell.borrow().is_none() {
+            *cell.borrow_mut() = Some(SwayConnection::new()?);
+        }
+
+        // Connection is guaranteed to exist after initialization above.
+        let mut conn_ref = cell.borrow_mut();
+        let conn = conn_ref.as_mut().expect("connection initialized");
+        f(conn)
+    })
+}
+
+fn to_workspace_info(ws: swayipc::Workspace) -> WorkspaceInfo {
+    let rect = Rect {
+        y: ws.rect.y,
+        height: ws.rect.height,
+    };
+
+    WorkspaceInfo {
+        num: ws.num,
+        label: ws.name.clone(),
+        name: ws.name,
+        focused: ws.focused,
+        urgent: ws.urgent,
+        rect,
+    }
+}
+
+fn to_output_info(output: swayipc::Output) -> OutputInfo {
+    OutputInfo {
+        name: output.name,
+        active: output.active,
+        make: output.make,
+        model: output.model,
+        rect: (
+            output.rect.x,
+            output.rect.y,
+            output.rect.width,
+            output.rect.height,
+        ),
+        // Sway reports -1 for disabled outputs.
+        scale: output.scale.filter(|scale| *scale > 0.0).unwrap_or(1.0),
+    }
+}
+
+fn workspace_apps(root: &Node) -> Vec<WorkspaceApps> {
+    let mut out = Vec::new();
+    collect_workspace_apps(root, &mut out);
+    out
+}
+
+fn collect_workspace_apps(node: &Node, out: &mut Vec<WorkspaceApps>) {
+    if node.node_type == NodeType::Workspace {
+        let name = node
+            .name
+            .clone()
+            .or_else(|| node.num.map(|num| num.to_string()))
+            .unwrap_or_else(|| "<unnamed>".to_string());
+        if name == "__i3_scratch" {
+            return;
+        }
+        let mut apps = Vec::new();
+        for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+            collect_app_names(child, &mut apps);
+        }
+        out.push(WorkspaceApps { name, apps });
+    }
+
+    for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+        collect_workspace_apps(child, out);
+    }
+}
+
+fn collect_app_names(node: &Node, out: &mut Vec<WorkspaceApp>) {
+    if let Some(name) = app_name(node) {
+        out.push(WorkspaceApp {
+            app_id: name,
+            con_id: node.id,
+        });
+    }
+
+    for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+        collect_app_names(child, out);
+    }
+}
+
+fn app_name(node: &Node) -> Option<String> {
+    if let Some(app_id) = &node.app_id {
+        return Some(app_id.clone());
+    }
+
+    let props = node.window_properties.as_ref()?;
+    if let Some(class) = &props.class {
+        Some(class.clone())
+    } else if let Some(instance) = &props.instance {
+        Some(instance.clone())
+    } else {
+        props.title.clone()
+    }
+}
+
+/// Backend talking to sway over its i3-compatible IPC socket.
+pub struct SwayBackend;
+
+impl CompositorBackend for SwayBackend {
+    fn name(&self) -> &'static str {
+        "sway"
+    }
+
+    fn fetch_workspaces(&self) -> Result<Vec<WorkspaceInfo>, String> {
+        fetch_workspaces()
+            .map(|workspaces| workspaces.into_iter().map(to_workspace_info).collect())
+            .map_err(|err| err.to_string())
+    }
+
+    fn fetch_workspace_apps(&self) -> Result<Vec<WorkspaceApps>, String> {
+        fetch_workspace_apps().map_err(|err| err.to_string())
+    }
+
+    fn fetch_outputs(&self) -> Result<Vec<OutputInfo>, String> {
+        fetch_outputs()
+            .map(|outputs| outputs.into_iter().map(to_output_info).collect())
+            .map_err(|err| err.to_string())
+    }
+
+    fn fetch_focused_app(&self) -> Result<Option<String>, String> {
+        fetch_focused_app().map_err(|err| err.to_string())
+    }
+
+    fn fetch_focused_window(&self) -> Result<Option<FocusedWindow>, String> {
+        fetch_focused_window().map_err(|err| err.to_string())
+    }
+
+    fn focus_workspace(&self, name: &str) -> Result<(), String> {
+        focus_workspace(name).map_err(|err| err.to_string())
+    }
+
+    fn rename_workspace(&self, name: &str, new_name: &str) -> Result<(), String> {
+        rename_workspace(name, new_name).map_err(|err| err.to_string())
+    }
+
+    fn move_workspace(&self, name: &str, target: &str) -> Result<(), String> {
+        move_workspace(name, target)
+    }
+
+    fn focus_con_id(&self, con_id: i64) -> Result<(), String> {
+        focus_con_id(con_id).map_err(|err| err.to_string())
+    }
+
+    fn close_con_id(&self, con_id: i64) -> Result<(), String> {
+        close_con_id(con_id).map_err(|err| err.to_string())
+    }
+
+    fn move_con_to_workspace(&self, con_id: i64, workspace: &str) -> Result<(), String> {
+        move_con_to_workspace(con_id, workspace).map_err(|err| err.to_string())
+    }
+
+    fn move_focused_to_workspace(&self, workspace: &str) -> Result<(), String> {
+        move_focused_to_workspace(workspace).map_err(|err| err.to_string())
+    }
+
+    fn launch_app(&self, app_id: &str) -> Result<(), String> {
+        launch_app(app_id).map_err(|err| err.to_string())
+    }
+
+    fn subscribe(&self, outputs_only: bool) -> Result<EventIter, String> {
+        let stream = if outputs_only {
+            subscribe_output_events()
+        } else {
+            subscribe_workspace_events()
+        }
+        .map_err(|err| err.to_string())?;
+        Ok(Box::new(stream.filter_map(|event| match event {
+            Ok(Event::Workspace(_)) | Ok(Event::Window(_)) => Some(Ok(CompositorEvent::Workspaces)),
+            Ok(Event::Output(_)) => Some(Ok(CompositorEvent::Outputs)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err.to_string())),
+        })))
+    }
+
+    fn fetch_visible_windows(&self) -> Result<Vec<(i32, i32, i32, i32)>, String> {
+        fetch_visible_windows().map_err(|err| err.to_string())
+    }
+
+    fn fetch_output_features(&self) -> Result<Vec<OutputFeatures>, String> {
+        fetch_output_features().map_err(|err| err.to_string())
+    }
+
+    fn set_output_feature(
+        &self,
+        output: &str,
+        feature: OutputFeature,
+        enabled: bool,
+    ) -> Result<(), String> {
+        set_output_feature(output, feature, enabled)
+    }
+}
+
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(test)]
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(test)]
+static LOG: OnceLock<Mutex<Vec<(usize, &'static str)>>> = OnceLock::new();
+
+#[cfg(test)]
+fn log_call(id: usize, name: &'static str) {
+    let log = LOG.get_or_init(|| Mutex::new(Vec::new()));
+    log.lock().unwrap().push((id, name));
+}
+
+#[cfg(test)]
+fn take_log() -> Vec<(usize, &'static str)> {
+    let log = LOG.get_or_init(|| Mutex::new(Vec::new()));
+    let mut lock = log.lock().unwrap();
+    let out = lock.clone();
+    lock.clear();
+    out
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+struct FakeConnection {
+    id: usize,
+}
+
+#[cfg(test)]
+impl FakeConnection {
+    fn new() -> Result<Self, Error> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        Ok(Self {
+            id: NEXT.fetch_add(1, Ordering::SeqCst),
+        })
+    }
+
+    fn get_workspaces(&mut self) -> Result<Vec<Workspace>, Error> {
+        log_call(self.id, "get_workspaces");
+        Ok(Vec::new())
+    }
+
+    fn run_command<T: AsRef<str>>(&mut self, _payload: T) -> Result<Vec<Result<(), Error>>, Error> {
+        log_call(self.id, "run_command");
+        Ok(vec![Ok(())])
+    }
+
+    fn get_tree(&mut self) -> Result<swayipc::Node, Error> {
+        Ok(empty_node())
+    }
+
+    fn get_outputs(&mut self) -> Result<Vec<swayipc::Output>, Error> {
+        log_call(self.id, "get_outputs");
+        Ok(Vec::new())
+    }
+
+    fn get_version(&mut self) -> Result<swayipc::Version, Error> {
+        log_call(self.id, "get_version");
+        Ok(serde_json::from_value(serde_json::json!({
+            "major": 1,
+            "minor": 11,
+            "patch": 0,
+            "human_readable": "1.11",
+            "loaded_config_file_name": ""
+        }))
+        .expect("swayipc version should deserialize"))
+    }
+}
+
+#[cfg(test)]
+fn empty_node() -> swayipc::Node {
+    let rect = serde_json::json!({
+        "x": 0,
+        "y": 0,
+        "width": 0,
+        "height": 0
+    });
+
+    serde_json::from_value(serde_json::json!({
+        "id": 0,
+        "name": null,
+        "type": "root",
+        "border": "none",
+        "current_border_width": 0,
+        "layout": "splith",
+        "orientation": "none",
+        "percent": null,
+        "rect": rect,
+        "window_rect": rect,
+        "deco_rect": rect,
+        "geometry": rect,
+        "urgent": false,
+        "focused": false,
+        "focus": [],
+        "floating": null,
+        "nodes": [],
+        "floating_nodes": [],
+        "sticky": false,
+        "representation": null,
+        "fullscreen_mode": null,
+        "scratchpad_state": null,
+        "app_id": null,
+        "pid": null,
+        "window": null,
+        "num": null,
+        "window_properties": null,
+        "marks": [],
+        "inhibit_idle": null,
+        "idle_inhibitors": null,
+        "sandbox_engine": null,
+        "sandbox_app_id": null,
+        "sandbox_instance_id": null,
+        "tag": null,
+        "shell": null,
+        "foreign_toplevel_identifier": null,
+        "visible": null,
+        "output": null
+    }))
+    .expect("empty swayipc node should deserialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reorder_renumbers_the_affected_range() {
+        let workspaces: Vec<(i32, String)> = vec![
+            (1, "1".to_string()),
+            (2, "2:web".to_string()),
+            (3, "3".to_string()),
+            (5, "5:mail".to_string()),
+        ];
+        let renames = reorder_renames(&workspaces, 3, 1).expect("plan");
+        assert_eq!(
+            renames,
+            vec![
+                ("5:mail".to_string(), "2:mail".to_string()),
+                ("2:web".to_string(), "3:web".to_string()),
+                ("3".to_string(), "5".to_string()),
+            ]
+        );
+        let forward = reorder_renames(&workspaces, 0, 1).expect("plan");
+        assert_eq!(
+            forward,
+            vec![
+                ("2:web".to_string(), "1:web".to_string()),
+                ("1".to_string(), "2".to_string()),
+            ]
+        );
+        let unnumbered = vec![(1, "1".to_string()), (-1, "scratch".to_string())];
+        assert!(reorder_renames(&unnumbered, 0, 1).is_err());
+    }
+
+    #[test]
+    fn reuses_single_connection_for_fetch_and_focus() {
+        // Ensure clean log before starting.
+        let _ = take_log();
+
+        fetch_workspaces().expect("fetch succeeds");
+        focus_workspace("1").expect("focus succeeds");
+
+        let log = take_log();
+        assert!(
+            !log.is_empty(),
+            "expected calls to be recorded; got empty log"
+        );
+
+        let ids: Vec<usize> = log.iter().map(|(id, _)| *id).collect();
+        assert!(
+            ids.windows(2).all(|w| w[0] == w[1]),
+            "expected same connection id, got {ids:?}"
+        );
+
+        let calls: Vec<&str> = log.iter().map(|(_, name)| *name).collect();
+        assert!(
+            calls.contains(&"get_workspaces") && calls.contains(&"run_command"),
+            "expected both fetch and focus calls; got {calls:?}"
+        );
+    }
+}
@@ -1,12 +1,16 @@
 // Action dialog sizing and rendering for gauge popup dialogs.
 // Consumes Settings: grelier.dialog.*, grelier.action_dialog.*.
 use crate::dialog::common::{self, BorderSettings};
-use crate::panels::gauges::gauge::{GaugeActionDialog, GaugeActionItem};
+use crate::panels::gauges::gauge::{
+    ActionSelectAction, GaugeActionDialog, GaugeActionItem, GaugeMenu, GaugeMenuItem,
+    MenuSelectAction,
+};
 use crate::settings;
 use iced::alignment;
 use iced::widget::svg::{self, Svg};
 use iced::widget::{Container, Row, button, container};
 use iced::{Element, Length, Theme};
+use std::sync::Arc;
 
 const DEFAULT_ICON_SIZE: u32 = 20;
 const DEFAULT_BUTTON_PADDING_Y: u32 = 2;
@@ -16,6 +20,7 @@ const DEFAULT_BORDER_PADDING_Y: u32 = 4;
 const DEFAULT_BORDER_PADDING_X: u32 = 2;
 const DEFAULT_MIN_WIDTH: u32 = 0;
 const DEFAULT_MAX_WIDTH: u32 = 4096;
+const CONFIRM_ITEM: &str = "confirm";
 
 struct ActionDialogSettings {
     min_width: u32,
@@ -77,17 +82,18 @@ pub fn dialog_dimensions(dialog: &GaugeActionDialog) -> (u32, u32) {
 
 pub fn action_view<'a, Message: Clone + 'a>(
     dialog: &'a GaugeActionDialog,
+    scale: f32,
     on_select: impl Fn(String) -> Message + 'a,
 ) -> Element<'a, Message> {
     let cfg = ActionDialogSettings::load();
-    let border_settings = BorderSettings::load();
+    let border_settings = BorderSettings::load(scale);
 
     let mut buttons = Row::new()
         .width(Length::Shrink)
         .align_y(alignment::Vertical::Center)
         .spacing(cfg.item_spacing_x);
 
-    for GaugeActionItem { id, icon } in &dialog.items {
+    for GaugeActionItem { id, icon, .. } in &dialog.items {
         let item_id = id.clone();
         let icon = Svg::new(icon.clone())
             .width(Length::Fixed(cfg.icon_size as f32))
@@ -132,3 +138,26 @@ pub fn action_view<'a, Message: Clone + 'a>(
 
     common::stack_with_border(content, border_settings, common::popup_border_sides())
 }
+
+/// Confirm/cancel menu shown before running an action item that has a confirmation prompt.
+pub fn confirm_menu(prompt: String, item_id: String, action: ActionSelectAction) -> GaugeMenu {
+    let on_select: MenuSelectAction = Arc::new(move |choice: String| {
+        if choice == CONFIRM_ITEM {
+            action(item_id.clone());
+        }
+    });
+    GaugeMenu {
+        title: prompt,
+        items: [(CONFIRM_ITEM, "Confirm"), ("cancel", "Cancel")]
+            .into_iter()
+            .map(|(id, label)| GaugeMenuItem {
+                id: id.to_string(),
+                label: label.to_string(),
+                selected: false,
+                exec: None,
+            })
+            .collect(),
+        on_select: Some(on_select),
+        slider: None,
+    }
+}
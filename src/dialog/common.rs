@@ -4,6 +4,7 @@ use iced::widget::{Column, Row, Stack, Text, container, rule, text};
 use iced::{Color, Element, Font, Length, Theme};
 
 use crate::settings;
+use crate::style;
 
 /// Default alignment when the dialog title alignment setting is missing/invalid.
 const DEFAULT_TITLE_ALIGN: &str = "center";
@@ -42,12 +43,19 @@ pub struct BorderSettings {
 }
 
 impl BorderSettings {
-    pub fn load() -> Self {
+    /// Border settings with widths snapped to whole device pixels at `scale`.
+    pub fn load(scale: f32) -> Self {
         let settings = settings::settings();
         Self {
             blend: settings.get_bool_or("grelier.bar.border.blend", true),
-            line_width: settings.get_parsed_or("grelier.bar.border.line_width", 1.0),
-            column_width: settings.get_parsed_or("grelier.bar.border.column_width", 3.0),
+            line_width: crate::bar::snap_at(
+                settings.get_parsed_or("grelier.bar.border.line_width", 1.0),
+                scale,
+            ),
+            column_width: crate::bar::snap_at(
+                settings.get_parsed_or("grelier.bar.border.column_width", 3.0),
+                scale,
+            ),
             mix_1: settings.get_parsed_or("grelier.bar.border.mix_1", 0.2),
             mix_2: settings.get_parsed_or("grelier.bar.border.mix_2", 0.6),
             mix_3: settings.get_parsed_or("grelier.bar.border.mix_3", 1.0),
@@ -215,24 +223,38 @@ pub fn stack_with_border<'a, Message: 'a>(
     stack.into()
 }
 
+/// Font for dialog text: the style file's `dialog.font`, else the default.
+pub fn dialog_font() -> Font {
+    style::current()
+        .font("dialog.font")
+        .unwrap_or(Font::DEFAULT)
+}
+
 pub fn dialog_title<'a, Message: 'a>(title: &'a str, font_size: u32) -> Element<'a, Message> {
+    let style = style::current();
+    let title_text = style.color("dialog.title_text");
+    let title_background = style.color("dialog.title_background");
     container(
         Text::new(title)
             .size(font_size)
             .width(Length::Fill)
             .align_x(title_alignment())
-            .style(|theme: &Theme| text::Style {
-                color: Some(theme.extended_palette().background.base.color),
+            .style(move |theme: &Theme| text::Style {
+                color: Some(title_text.unwrap_or(theme.extended_palette().background.base.color)),
             })
             .font(Font {
                 weight: Weight::Bold,
-                ..Font::DEFAULT
+                ..dialog_font()
             }),
     )
     .padding([0, 6])
     .width(Length::Fill)
-    .style(|theme: &Theme| container::Style {
-        background: Some(theme.extended_palette().primary.base.color.into()),
+    .style(move |theme: &Theme| container::Style {
+        background: Some(
+            title_background
+                .unwrap_or(theme.extended_palette().primary.base.color)
+                .into(),
+        ),
         ..container::Style::default()
     })
     .into()
@@ -243,12 +265,20 @@ pub fn dialog_surface<'a, Message: 'a>(
     padding_y: u16,
     padding_x: u16,
 ) -> Element<'a, Message> {
+    let style = style::current();
+    let background = style.color("dialog.background");
+    let text_color = style.color("dialog.text");
     container(content)
         .padding([padding_y, padding_x])
         .width(Length::Fill)
         .height(Length::Fill)
-        .style(|theme: &Theme| container::Style {
-            background: Some(theme.extended_palette().background.base.color.into()),
+        .style(move |theme: &Theme| container::Style {
+            background: Some(
+                background
+                    .unwrap_or(theme.extended_palette().background.base.color)
+                    .into(),
+            ),
+            text_color,
             ..container::Style::default()
         })
         .into()
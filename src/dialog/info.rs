@@ -1,9 +1,10 @@
 // Info dialog sizing and rendering for gauge popup dialogs.
 // Consumes Settings: grelier.dialog.*, grelier.info_dialog.*.
+use crate::bar::sparkline;
 use crate::dialog::common::{self, BorderSettings};
 use crate::settings;
-use iced::widget::{Column, Space, Text};
-use iced::{Element, Length};
+use iced::widget::{Column, Space, Text, container, scrollable};
+use iced::{Element, Length, Theme};
 
 const DEFAULT_HEADER_FONT_SIZE: u32 = 14;
 const DEFAULT_BODY_FONT_SIZE: u32 = 12;
@@ -11,16 +12,20 @@ const DEFAULT_CHAR_WIDTH: u32 = 6;
 const DEFAULT_MAX_CHARS_PER_LINE: u32 = 60;
 const DEFAULT_MIN_WIDTH: u32 = 0;
 const DEFAULT_MAX_WIDTH: u32 = 840;
+/// Taller dialogs scroll their lines instead of growing off-screen.
+const DEFAULT_MAX_HEIGHT: u32 = 560;
 const DEFAULT_HEADER_SPACING: u32 = 4;
 const DEFAULT_HEADER_BOTTOM_SPACING: u32 = 4;
 const DEFAULT_LINE_SPACING: u32 = 6;
 const DEFAULT_CONTAINER_PADDING_Y: u32 = 10;
 const DEFAULT_CONTAINER_PADDING_X: u32 = 10;
 const DEFAULT_BOTTOM_PADDING_EXTRA: u32 = 4;
+const DEFAULT_GRAPH_HEIGHT: u32 = 32;
 
 struct InfoDialogSettings {
     min_width: u32,
     max_width: u32,
+    max_height: u32,
     char_width: u32,
     max_chars_per_line: u32,
     header_font_size: u32,
@@ -31,6 +36,7 @@ struct InfoDialogSettings {
     container_padding_y: u32,
     container_padding_x: u32,
     bottom_padding_extra: u32,
+    graph_height: u32,
 }
 
 impl InfoDialogSettings {
@@ -39,6 +45,8 @@ impl InfoDialogSettings {
         Self {
             min_width: settings.get_parsed_or("grelier.info_dialog.min_width", DEFAULT_MIN_WIDTH),
             max_width: settings.get_parsed_or("grelier.info_dialog.max_width", DEFAULT_MAX_WIDTH),
+            max_height: settings
+                .get_parsed_or("grelier.info_dialog.max_height", DEFAULT_MAX_HEIGHT),
             char_width: settings
                 .get_parsed_or("grelier.info_dialog.char_width", DEFAULT_CHAR_WIDTH),
             max_chars_per_line: settings.get_parsed_or(
@@ -69,6 +77,8 @@ impl InfoDialogSettings {
                 "grelier.info_dialog.bottom_padding_extra",
                 DEFAULT_BOTTOM_PADDING_EXTRA,
             ),
+            graph_height: settings
+                .get_parsed_or("grelier.info_dialog.graph_height", DEFAULT_GRAPH_HEIGHT),
         }
     }
 }
@@ -77,6 +87,8 @@ impl InfoDialogSettings {
 pub struct InfoDialog {
     pub title: String,
     pub lines: Vec<String>,
+    /// Optional history graph drawn under the lines: samples oldest first, scaled to 0..=1.
+    pub graph: Option<Vec<f32>>,
 }
 
 /// Calculate a reasonable window size for an info dialog based on line count and length.
@@ -121,20 +133,27 @@ pub fn dialog_dimensions(dialog: &InfoDialog) -> (u32, u32) {
         + dialog_cfg
             .line_spacing
             .saturating_mul(dialog.lines.len().saturating_sub(1) as u32);
+    let graph_height = if dialog.graph.is_some() {
+        dialog_cfg.graph_height + dialog_cfg.line_spacing
+    } else {
+        0
+    };
     let safety_height = (dialog_cfg.body_font_size as f32 * 0.6).ceil() as u32;
     let height = header_height
         + dialog_cfg.header_bottom_spacing
         + body_height
+        + graph_height
         + dialog_cfg.container_padding_y * 2
         + dialog_cfg.bottom_padding_extra
         + safety_height;
 
-    (width, height)
+    (width, height.min(dialog_cfg.max_height.max(header_height)))
 }
 
-pub fn info_view<'a, Message: 'a>(dialog: &'a InfoDialog) -> Element<'a, Message> {
+/// Bar-chart sparkline: one bottom-aligned bar per sample, filling the dialog width.
+pub fn info_view<'a, Message: 'a>(dialog: &'a InfoDialog, scale: f32) -> Element<'a, Message> {
     let dialog_cfg = InfoDialogSettings::load();
-    let border_settings = BorderSettings::load();
+    let border_settings = BorderSettings::load(scale);
 
     let header = Column::new()
         .width(Length::Fill)
@@ -145,7 +164,7 @@ pub fn info_view<'a, Message: 'a>(dialog: &'a InfoDialog) -> Element<'a, Message
         ))
         .push(Space::new().height(Length::Fixed(dialog_cfg.header_bottom_spacing as f32)));
 
-    let lines = dialog.lines.iter().fold(
+    let mut lines = dialog.lines.iter().fold(
         Column::new()
             .width(Length::Fill)
             .spacing(dialog_cfg.line_spacing),
@@ -153,10 +172,25 @@ pub fn info_view<'a, Message: 'a>(dialog: &'a InfoDialog) -> Element<'a, Message
             col.push(
                 Text::new(line.as_str())
                     .size(dialog_cfg.body_font_size)
+                    .font(common::dialog_font())
                     .width(Length::Fill),
             )
         },
     );
+    if let Some(samples) = dialog.graph.as_deref() {
+        lines = lines.push(
+            container(sparkline(
+                samples,
+                dialog_cfg.graph_height as f32,
+                |theme| theme.extended_palette().primary.base.color,
+            ))
+            .width(Length::Fill)
+            .style(|theme: &Theme| container::Style {
+                background: Some(theme.extended_palette().background.weak.color.into()),
+                ..container::Style::default()
+            }),
+        );
+    }
 
     let content = common::dialog_surface(
         Column::new()
@@ -164,7 +198,7 @@ pub fn info_view<'a, Message: 'a>(dialog: &'a InfoDialog) -> Element<'a, Message
             .height(Length::Fill)
             .spacing(dialog_cfg.header_spacing)
             .push(header)
-            .push(lines)
+            .push(scrollable(lines).height(Length::Fill))
             .push(Space::new().height(Length::Fixed(dialog_cfg.bottom_padding_extra as f32))),
         dialog_cfg.container_padding_y as u16,
         dialog_cfg.container_padding_x as u16,
@@ -1,15 +1,21 @@
 // Menu sizing and rendering for gauge popup dialogs.
+// Gauges can ask for a menu from their callbacks with `request`, for example a confirmation.
 // Consumes Settings: grelier.dialog.*, grelier.menu_dialog.*.
+use crate::bar::Message as BarMessage;
 use crate::dialog::common::{self, BorderSettings};
 use crate::icon::svg_asset;
 use crate::panels::gauges::gauge::{GaugeMenu, GaugeMenuItem};
 use crate::settings;
+use iced::Subscription;
 use iced::alignment;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
 use iced::widget::slider;
 use iced::widget::svg::{self, Svg};
 use iced::widget::text::LineHeight;
-use iced::widget::{Column, Row, Slider, Space, Text, button, container, mouse_area};
+use iced::widget::{Column, Row, Slider, Space, Text, button, container, mouse_area, scrollable};
 use iced::{Element, Length, Pixels, Theme};
+use std::sync::{Mutex, OnceLock};
 
 const DEFAULT_HEADER_FONT_SIZE: u32 = 14;
 const DEFAULT_ITEM_FONT_SIZE: u32 = 12;
@@ -27,6 +33,44 @@ const DEFAULT_INDICATOR_SPACING: u32 = 10;
 const DEFAULT_BUTTON_PADDING_X: u32 = 6;
 const DEFAULT_CONTAINER_PADDING_X: u32 = 10;
 const DEFAULT_SLIDER_HEIGHT: u32 = 24;
+const DEFAULT_MAX_VISIBLE_ITEMS: u32 = 12;
+
+struct MenuQueue {
+    tx: UnboundedSender<(String, GaugeMenu)>,
+    /// Taken by the bar's subscription.
+    rx: Mutex<Option<UnboundedReceiver<(String, GaugeMenu)>>>,
+}
+
+fn queue() -> &'static MenuQueue {
+    static QUEUE: OnceLock<MenuQueue> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::unbounded();
+        MenuQueue {
+            tx,
+            rx: Mutex::new(Some(rx)),
+        }
+    })
+}
+
+/// Ask the bar to open `menu` beside the gauge `gauge_id`. Safe to call from menu callbacks
+/// and gauge threads, which cannot open windows themselves.
+pub fn request(gauge_id: &str, menu: GaugeMenu) {
+    let _ = queue().tx.unbounded_send((gauge_id.to_string(), menu));
+}
+
+fn menu_stream() -> impl iced::futures::Stream<Item = BarMessage> {
+    let rx = queue().rx.lock().ok().and_then(|mut rx| rx.take());
+    iced::futures::stream::unfold(rx, |rx| async move {
+        let mut rx = rx?;
+        let (gauge_id, menu) = rx.next().await?;
+        Some((BarMessage::MenuRequested { gauge_id, menu }, Some(rx)))
+    })
+}
+
+/// Deliver menus requested by gauges to the bar for the lifetime of the process.
+pub fn subscription() -> Subscription<BarMessage> {
+    Subscription::run(menu_stream)
+}
 
 struct MenuDialogSettings {
     min_width: u32,
@@ -45,6 +89,7 @@ struct MenuDialogSettings {
     button_padding_x: u32,
     container_padding_x: u32,
     slider_height: u32,
+    max_visible_items: u32,
 }
 
 impl MenuDialogSettings {
@@ -95,6 +140,10 @@ impl MenuDialogSettings {
             ),
             slider_height: settings
                 .get_parsed_or("grelier.menu_dialog.slider_height", DEFAULT_SLIDER_HEIGHT),
+            max_visible_items: settings.get_parsed_or(
+                "grelier.menu_dialog.max_visible_items",
+                DEFAULT_MAX_VISIBLE_ITEMS,
+            ),
         }
     }
 }
@@ -113,7 +162,8 @@ pub fn dialog_dimensions(menu: &GaugeMenu) -> (u32, u32) {
     let width =
         (max_label_chars * cfg.char_width + cfg.label_padding).clamp(cfg.min_width, cfg.max_width);
 
-    let rows = menu.items.len().max(1) as u32;
+    // Longer menus scroll instead of growing past the visible row limit.
+    let rows = (menu.items.len() as u32).clamp(1, cfg.max_visible_items.max(1));
     let header_line_height = LineHeight::default()
         .to_absolute(Pixels(cfg.header_font_size as f32))
         .0;
@@ -141,8 +191,10 @@ pub fn dialog_dimensions(menu: &GaugeMenu) -> (u32, u32) {
     (width, height)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn menu_view<'a, Message: Clone + 'a>(
     menu: &'a GaugeMenu,
+    scale: f32,
     hovered_item: Option<&'a str>,
     slider_value: Option<u8>,
     on_select: impl Fn(String) -> Message + 'a,
@@ -150,7 +202,7 @@ pub fn menu_view<'a, Message: Clone + 'a>(
     on_hover_exit: impl Fn(String) -> Message + 'a,
     on_slider_change: impl Fn(u8) -> Message + 'a,
 ) -> Element<'a, Message> {
-    let border_settings = BorderSettings::load();
+    let border_settings = BorderSettings::load(scale);
     let cfg = MenuDialogSettings::load();
     let checked_icon = svg_asset("option-checked.svg");
     let empty_icon = svg_asset("option-empty.svg");
@@ -205,6 +257,7 @@ pub fn menu_view<'a, Message: Clone + 'a>(
         id,
         label,
         selected,
+        ..
     } in &menu.items
     {
         let is_hovered = hovered_item.is_some_and(|hovered| hovered == id.as_str());
@@ -239,7 +292,8 @@ pub fn menu_view<'a, Message: Clone + 'a>(
             .push(
                 Text::new(label.as_str())
                     .width(Length::Shrink)
-                    .size(cfg.item_font_size),
+                    .size(cfg.item_font_size)
+                    .font(common::dialog_font()),
             )
             .push(Space::new().width(Length::Fill));
 
@@ -268,7 +322,7 @@ pub fn menu_view<'a, Message: Clone + 'a>(
         );
     }
 
-    body = body.push(list.spacing(cfg.list_spacing));
+    body = body.push(scrollable(list.spacing(cfg.list_spacing)).height(Length::Fill));
     let content = common::dialog_surface(
         body.height(Length::Fill),
         cfg.container_padding_y as u16,
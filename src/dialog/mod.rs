@@ -2,3 +2,5 @@ pub mod action;
 pub mod common;
 pub mod info;
 pub mod menu;
+pub mod prompt;
+pub mod toast;
@@ -0,0 +1,236 @@
+// Text prompt dialog: a single-line field, such as the workspace rename prompt, or a text area
+// with a Save button for several lines. Gauges can ask for one from their callbacks with
+// `request`, for example a Wi-Fi passphrase.
+// Consumes Settings: grelier.dialog.*, grelier.prompt_dialog.*.
+use crate::bar::Message;
+use crate::dialog::common::{self, BorderSettings};
+use crate::settings;
+use iced::Subscription;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use iced::widget::{Column, button, text, text_editor, text_input};
+use iced::{Element, Length, alignment};
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+const DEFAULT_HEADER_FONT_SIZE: u32 = 14;
+const DEFAULT_INPUT_FONT_SIZE: u32 = 13;
+const DEFAULT_WIDTH: u32 = 240;
+const DEFAULT_SPACING: u32 = 8;
+const DEFAULT_CONTAINER_PADDING_Y: u32 = 10;
+const DEFAULT_CONTAINER_PADDING_X: u32 = 10;
+const DEFAULT_EDITOR_LINES: u32 = 8;
+const INPUT_PADDING: u32 = 5;
+/// Widget id used to focus the input as soon as the prompt opens.
+pub const INPUT_ID: &str = "grelier-prompt-input";
+
+/// Callback receiving the submitted text.
+pub type PromptSubmitAction = Arc<dyn Fn(String) + Send + Sync>;
+
+/// A titled text field; Enter submits, Escape or clicking away cancels.
+#[derive(Clone)]
+pub struct PromptDialog {
+    pub title: String,
+    /// Current contents of the field, starting with the initial value.
+    pub value: String,
+    pub placeholder: String,
+    /// Mask the typed characters, for passphrases.
+    pub secret: bool,
+    /// Edit several lines in a text area instead; Enter starts a new line and Save submits.
+    pub editor: Option<text_editor::Content>,
+    pub on_submit: Option<PromptSubmitAction>,
+}
+
+impl PromptDialog {
+    /// A text area holding `value`.
+    pub fn multiline(
+        title: impl Into<String>,
+        value: &str,
+        on_submit: Option<PromptSubmitAction>,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            value: String::new(),
+            placeholder: String::new(),
+            secret: false,
+            editor: Some(text_editor::Content::with_text(value)),
+            on_submit,
+        }
+    }
+
+    /// The text to submit: the text area's contents, or the field's value.
+    pub fn text(&self) -> String {
+        self.editor
+            .as_ref()
+            .map_or_else(|| self.value.clone(), text_editor::Content::text)
+    }
+}
+
+impl fmt::Debug for PromptDialog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PromptDialog")
+            .field("title", &self.title)
+            .field("value", &if self.secret { "<secret>" } else { &self.value })
+            .field("placeholder", &self.placeholder)
+            .field(
+                "editor",
+                &self.editor.as_ref().map(text_editor::Content::text),
+            )
+            .field("on_submit", &self.on_submit.as_ref().map(|_| "<set>"))
+            .finish()
+    }
+}
+
+struct PromptQueue {
+    tx: UnboundedSender<(String, PromptDialog)>,
+    /// Taken by the bar's subscription.
+    rx: Mutex<Option<UnboundedReceiver<(String, PromptDialog)>>>,
+}
+
+fn queue() -> &'static PromptQueue {
+    static QUEUE: OnceLock<PromptQueue> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::unbounded();
+        PromptQueue {
+            tx,
+            rx: Mutex::new(Some(rx)),
+        }
+    })
+}
+
+/// Ask the bar to open `dialog` beside the gauge `gauge_id`. Safe to call from menu callbacks
+/// and gauge threads, which cannot open windows themselves.
+pub fn request(gauge_id: &str, dialog: PromptDialog) {
+    let _ = queue().tx.unbounded_send((gauge_id.to_string(), dialog));
+}
+
+fn prompt_stream() -> impl iced::futures::Stream<Item = Message> {
+    let rx = queue().rx.lock().ok().and_then(|mut rx| rx.take());
+    iced::futures::stream::unfold(rx, |rx| async move {
+        let mut rx = rx?;
+        let (gauge_id, dialog) = rx.next().await?;
+        Some((Message::PromptRequested { gauge_id, dialog }, Some(rx)))
+    })
+}
+
+/// Deliver prompts requested by gauges to the bar for the lifetime of the process.
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(prompt_stream)
+}
+
+struct PromptDialogSettings {
+    width: u32,
+    editor_lines: u32,
+    header_font_size: u32,
+    input_font_size: u32,
+    spacing: u32,
+    container_padding_y: u32,
+    container_padding_x: u32,
+}
+
+impl PromptDialogSettings {
+    fn load() -> Self {
+        let settings = settings::settings();
+        Self {
+            width: settings.get_parsed_or("grelier.prompt_dialog.width", DEFAULT_WIDTH),
+            editor_lines: settings
+                .get_parsed_or("grelier.prompt_dialog.editor_lines", DEFAULT_EDITOR_LINES),
+            header_font_size: settings
+                .get_parsed_or("grelier.dialog.header.font_size", DEFAULT_HEADER_FONT_SIZE),
+            input_font_size: settings
+                .get_parsed_or("grelier.prompt_dialog.font_size", DEFAULT_INPUT_FONT_SIZE),
+            spacing: settings.get_parsed_or("grelier.prompt_dialog.spacing", DEFAULT_SPACING),
+            container_padding_y: settings.get_parsed_or(
+                "grelier.dialog.container.padding_y",
+                DEFAULT_CONTAINER_PADDING_Y,
+            ),
+            container_padding_x: settings.get_parsed_or(
+                "grelier.dialog.container.padding_x",
+                DEFAULT_CONTAINER_PADDING_X,
+            ),
+        }
+    }
+}
+
+/// Window size for a prompt: a title row and one input row, or a text area and a Save button.
+pub fn dialog_dimensions(dialog: &PromptDialog) -> (u32, u32) {
+    let cfg = PromptDialogSettings::load();
+    let header_height = (cfg.header_font_size as f32 * 1.2).ceil() as u32;
+    let line_height = (cfg.input_font_size as f32 * 1.3).ceil() as u32;
+    let input_height = line_height + INPUT_PADDING * 2;
+    let body_height = if dialog.editor.is_some() {
+        line_height * cfg.editor_lines.max(1) + INPUT_PADDING * 2 + cfg.spacing + input_height
+    } else {
+        input_height
+    };
+    let height = header_height + cfg.spacing + body_height + cfg.container_padding_y * 2;
+    (cfg.width, height)
+}
+
+pub fn prompt_view<'a, Message: Clone + 'a>(
+    dialog: &'a PromptDialog,
+    scale: f32,
+    on_input: impl Fn(String) -> Message + 'a,
+    on_edit: impl Fn(text_editor::Action) -> Message + 'a,
+    on_submit: Message,
+) -> Element<'a, Message> {
+    let cfg = PromptDialogSettings::load();
+    let mut body =
+        Column::new()
+            .width(Length::Fill)
+            .spacing(cfg.spacing)
+            .push(common::dialog_title(
+                dialog.title.as_str(),
+                cfg.header_font_size,
+            ));
+    if let Some(editor) = &dialog.editor {
+        let line_height = (cfg.input_font_size as f32 * 1.3).ceil() as u32;
+        let area = text_editor(editor)
+            .id(INPUT_ID)
+            .placeholder(dialog.placeholder.as_str())
+            .on_action(on_edit)
+            .font(common::dialog_font())
+            .size(cfg.input_font_size)
+            .padding(INPUT_PADDING as u16)
+            .height(Length::Fixed(
+                (line_height * cfg.editor_lines.max(1) + INPUT_PADDING * 2) as f32,
+            ));
+        let save = button(
+            text("Save")
+                .font(common::dialog_font())
+                .size(cfg.input_font_size),
+        )
+        .padding(INPUT_PADDING as u16)
+        .on_press(on_submit);
+        body = body.push(area).push(
+            Column::new()
+                .width(Length::Fill)
+                .align_x(alignment::Horizontal::Right)
+                .push(save),
+        );
+    } else {
+        body = body.push(
+            text_input(&dialog.placeholder, &dialog.value)
+                .id(INPUT_ID)
+                .on_input(on_input)
+                .on_submit(on_submit)
+                .secure(dialog.secret)
+                .font(common::dialog_font())
+                .size(cfg.input_font_size)
+                .padding(INPUT_PADDING as u16)
+                .width(Length::Fill),
+        );
+    }
+
+    let content = common::dialog_surface(
+        body,
+        cfg.container_padding_y as u16,
+        cfg.container_padding_x as u16,
+    );
+
+    common::stack_with_border(
+        content,
+        BorderSettings::load(scale),
+        common::popup_border_sides(),
+    )
+}
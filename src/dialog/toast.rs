@@ -0,0 +1,178 @@
+// Transient toasts any gauge can raise, shown in a popup beside the bar until they time out.
+// Independent of the freedesktop notification server; gauges call `show` from any thread.
+use crate::bar::{Message, delayed_task};
+use crate::dialog::info::{self, InfoDialog, info_view};
+use crate::panels::gauges::dnd;
+use crate::panels::gauges::gauge::GaugeValueAttention;
+use iced::futures::StreamExt;
+use iced::futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use iced::widget::{Column, Space, container, mouse_area};
+use iced::{Element, Length, Subscription, Task, Theme, mouse, window};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// How long a toast stays up when the caller does not pick a timeout.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(6);
+const ATTENTION_STRIP_HEIGHT: f32 = 4.0;
+
+/// A short message with a title, body, severity, and display duration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toast {
+    pub title: String,
+    pub body: String,
+    pub attention: GaugeValueAttention,
+    pub timeout: Duration,
+}
+
+impl Toast {
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            body: body.into(),
+            attention: GaugeValueAttention::Nominal,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    pub fn with_attention(mut self, attention: GaugeValueAttention) -> Self {
+        self.attention = attention;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Info dialog content used to size and render the toast.
+    pub fn info(&self) -> InfoDialog {
+        InfoDialog {
+            title: self.title.clone(),
+            lines: self.body.lines().map(ToString::to_string).collect(),
+            graph: None,
+        }
+    }
+}
+
+struct ToastQueue {
+    tx: UnboundedSender<Toast>,
+    /// Taken by the bar's subscription; toasts raised before it starts wait in the channel.
+    rx: Mutex<Option<UnboundedReceiver<Toast>>>,
+}
+
+fn queue() -> &'static ToastQueue {
+    static QUEUE: OnceLock<ToastQueue> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::unbounded();
+        ToastQueue {
+            tx,
+            rx: Mutex::new(Some(rx)),
+        }
+    })
+}
+
+/// Queue a toast for display. Safe to call from gauge threads; never blocks.
+///
+/// While Do Not Disturb is on, only danger toasts are shown; the rest are just logged.
+pub fn show(toast: Toast) {
+    log::info!("toast: {}: {}", toast.title, toast.body);
+    if dnd::dnd_enabled() && toast.attention != GaugeValueAttention::Danger {
+        return;
+    }
+    let _ = queue().tx.unbounded_send(toast);
+}
+
+fn toast_stream() -> impl iced::futures::Stream<Item = Message> {
+    let rx = queue().rx.lock().ok().and_then(|mut rx| rx.take());
+    iced::futures::stream::unfold(rx, |rx| async move {
+        let mut rx = rx?;
+        let toast = rx.next().await?;
+        Some((Message::Toast(toast), Some(rx)))
+    })
+}
+
+/// Deliver queued toasts to the bar for the lifetime of the process.
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(toast_stream)
+}
+
+/// Emit `Message::ToastExpired(window)` once `timeout` has elapsed.
+pub fn expire_task(window: window::Id, timeout: Duration) -> Task<Message> {
+    delayed_task(timeout, Message::ToastExpired(window))
+}
+
+/// A toast currently shown in its own popup window.
+#[derive(Debug, Clone)]
+pub struct ToastWindow {
+    pub info: InfoDialog,
+    pub attention: GaugeValueAttention,
+}
+
+impl ToastWindow {
+    pub fn new(toast: &Toast) -> Self {
+        Self {
+            info: toast.info(),
+            attention: toast.attention,
+        }
+    }
+
+    /// Popup size: the info dialog plus the attention strip, if any.
+    pub fn dimensions(&self) -> (u32, u32) {
+        let (width, height) = info::dialog_dimensions(&self.info);
+        if self.attention == GaugeValueAttention::Nominal {
+            (width, height)
+        } else {
+            (width, height + ATTENTION_STRIP_HEIGHT as u32)
+        }
+    }
+}
+
+/// Toast popup: the info dialog layout topped with an attention strip; click to dismiss.
+pub fn toast_view(toast: &ToastWindow, window: window::Id, scale: f32) -> Element<'_, Message> {
+    let attention = toast.attention;
+    let mut content = Column::new().width(Length::Fill).height(Length::Fill);
+    if attention != GaugeValueAttention::Nominal {
+        content = content.push(
+            container(Space::new())
+                .width(Length::Fill)
+                .height(Length::Fixed(ATTENTION_STRIP_HEIGHT))
+                .style(move |theme: &Theme| {
+                    let palette = theme.palette();
+                    let color = match attention {
+                        GaugeValueAttention::Danger => palette.danger,
+                        _ => palette.warning,
+                    };
+                    container::Style {
+                        background: Some(color.into()),
+                        ..container::Style::default()
+                    }
+                }),
+        );
+    }
+    mouse_area(content.push(info_view(&toast.info, scale)))
+        .on_press(Message::ToastExpired(window))
+        .interaction(mouse::Interaction::Pointer)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_toasts_with_defaults() {
+        let toast = Toast::new("Battery low", "12% remaining\nPlug in soon")
+            .with_attention(GaugeValueAttention::Danger);
+        assert_eq!(toast.timeout, DEFAULT_TIMEOUT);
+        assert_eq!(toast.attention, GaugeValueAttention::Danger);
+        let info = toast.info();
+        assert_eq!(info.title, "Battery low");
+        assert_eq!(info.lines, vec!["12% remaining", "Plug in soon"]);
+        assert_eq!(
+            Toast::new("a", "b")
+                .with_timeout(Duration::from_secs(1))
+                .timeout,
+            Duration::from_secs(1)
+        );
+    }
+}
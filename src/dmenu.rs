@@ -0,0 +1,526 @@
+// dmenu-compatible chooser: `grelier --dmenu` reads one item per line from stdin, lets the
+// user fuzzy-filter and pick one in a bar-styled dialog, and prints the choice to stdout.
+// The app launcher reuses the same chooser with launch counts as ranking weights.
+// Consumes Settings: grelier.dialog.*, grelier.menu_dialog.item_font_size,
+// grelier.prompt_dialog.*, grelier.bar.theme.
+use crate::dialog::common::{self, BorderSettings};
+use crate::settings;
+use iced::widget::text::Span;
+use iced::widget::{Column, button, rich_text, scrollable, span, text_input};
+use iced::{Element, Font, Length, Task, Theme, event, keyboard};
+use iced_layershell::application;
+use iced_layershell::reexport::{Anchor, KeyboardInteractivity, Layer};
+use iced_layershell::settings::{LayerShellSettings, Settings as LayerShellAppSettings, StartMode};
+use iced_layershell::to_layer_message;
+use std::io::{self, BufRead};
+use std::sync::{Arc, Mutex};
+
+/// Widget id of the filter field, focused when the chooser opens.
+const INPUT_ID: &str = "grelier-dmenu-input";
+const DEFAULT_WIDTH: u32 = 420;
+const DEFAULT_HEADER_FONT_SIZE: u32 = 14;
+const DEFAULT_ITEM_FONT_SIZE: u32 = 12;
+const DEFAULT_SPACING: u32 = 8;
+const DEFAULT_CONTAINER_PADDING: u32 = 10;
+const VISIBLE_ITEMS: u32 = 12;
+const ROW_PADDING: u16 = 4;
+/// Score for each matched character.
+const SCORE_MATCH: i32 = 16;
+/// Bonus for a match at the start of a word, after a separator or a lower-to-upper case change.
+const BONUS_BOUNDARY: i32 = 10;
+/// Bonus for a match right after the previous one.
+const BONUS_CONSECUTIVE: i32 = 8;
+/// Score per unit of `ln(1 + weight)`, so frequent items win among similar matches.
+const WEIGHT_SCALE: f32 = 6.0;
+
+/// Read the items to choose from: one per line, blank lines skipped.
+pub fn read_items(input: impl BufRead) -> io::Result<Vec<String>> {
+    let mut items = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            items.push(line);
+        }
+    }
+    Ok(items)
+}
+
+/// What the user picked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Choice {
+    /// Index of the chosen item.
+    Item(usize),
+    /// Typed text that matched nothing.
+    Typed(String),
+}
+
+/// An item matching the current query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Match {
+    index: usize,
+    /// Character positions of the matched query characters, for highlighting.
+    positions: Vec<usize>,
+}
+
+fn is_boundary(previous: Option<char>, current: char) -> bool {
+    match previous {
+        None => true,
+        Some(previous) => {
+            !previous.is_alphanumeric() || (previous.is_lowercase() && current.is_uppercase())
+        }
+    }
+}
+
+/// Fuzzy-match `query` against `candidate`, ignoring case: every query character must appear
+/// in order. Among the possible alignments the shortest window ending at the first full match
+/// is used, scored for word starts and runs of adjacent characters, less the gaps between them.
+fn fuzzy_match(query: &str, candidate: &str) -> Option<(i32, Vec<usize>)> {
+    let fold = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold)
+        .collect();
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    let chars: Vec<char> = candidate.chars().collect();
+    let folded: Vec<char> = chars.iter().copied().map(fold).collect();
+
+    // Forward pass to the end of the first full match, then back to the latest start.
+    let mut next = 0;
+    let mut end = None;
+    for (position, &c) in folded.iter().enumerate() {
+        if c == query[next] {
+            next += 1;
+            if next == query.len() {
+                end = Some(position);
+                break;
+            }
+        }
+    }
+    let end = end?;
+    let mut positions = Vec::with_capacity(query.len());
+    for position in (0..=end).rev() {
+        if positions.len() < query.len()
+            && folded[position] == query[query.len() - 1 - positions.len()]
+        {
+            positions.push(position);
+        }
+    }
+    positions.reverse();
+    let start = positions[0];
+
+    let mut score = 0;
+    for (i, &position) in positions.iter().enumerate() {
+        score += SCORE_MATCH;
+        let previous = position.checked_sub(1).map(|p| chars[p]);
+        if is_boundary(previous, chars[position]) {
+            score += BONUS_BOUNDARY;
+        }
+        if i > 0 && positions[i - 1] + 1 == position {
+            score += BONUS_CONSECUTIVE;
+        }
+    }
+    let gaps = (end - start + 1 - positions.len()) as i32;
+    score -= gaps + (start as i32).min(BONUS_BOUNDARY);
+    Some((score, positions))
+}
+
+/// Items matching `query`, best first; `weights` (such as launch counts) boost the ranking and
+/// order the full list when the query is empty. Ties keep input order.
+fn matching(items: &[String], weights: &[u32], query: &str) -> Vec<Match> {
+    let boost = |index: usize| {
+        let weight = weights.get(index).copied().unwrap_or(0);
+        ((weight as f32).ln_1p() * WEIGHT_SCALE).round() as i32
+    };
+    let mut scored: Vec<(i32, Match)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let (score, positions) = fuzzy_match(query, item)?;
+            Some((score + boost(index), Match { index, positions }))
+        })
+        .collect();
+    scored.sort_by(|(a, a_match), (b, b_match)| b.cmp(a).then(a_match.index.cmp(&b_match.index)));
+    scored.into_iter().map(|(_, found)| found).collect()
+}
+
+/// `item` split into spans, with the characters at `positions` in `highlight`.
+fn highlighted_spans<'a>(
+    item: &'a str,
+    positions: &[usize],
+    highlight: iced::Color,
+) -> Vec<Span<'a, ()>> {
+    let mut spans = Vec::new();
+    let mut run_start = 0;
+    let mut run_matched = false;
+    for (position, (offset, _)) in item.char_indices().enumerate() {
+        let matched = positions.contains(&position);
+        if matched != run_matched {
+            if offset > run_start {
+                spans.push(styled_span(
+                    &item[run_start..offset],
+                    run_matched,
+                    highlight,
+                ));
+            }
+            run_start = offset;
+            run_matched = matched;
+        }
+    }
+    if run_start < item.len() {
+        spans.push(styled_span(&item[run_start..], run_matched, highlight));
+    }
+    spans
+}
+
+fn styled_span(text: &str, matched: bool, highlight: iced::Color) -> Span<'_, ()> {
+    let part = span(text);
+    if matched {
+        part.color(highlight).underline(true)
+    } else {
+        part
+    }
+}
+
+struct Chooser {
+    title: String,
+    items: Vec<String>,
+    /// Ranking weight per item; empty when items are unweighted.
+    weights: Vec<u32>,
+    query: String,
+    /// Items shown for the current query, best first.
+    matches: Vec<Match>,
+    /// Highlighted position in `matches`.
+    highlighted: usize,
+    theme: Theme,
+    /// Where the choice is left for [`run`] once the window closes.
+    choice: Arc<Mutex<Option<Choice>>>,
+}
+
+impl Chooser {
+    fn new(
+        title: String,
+        items: Vec<String>,
+        weights: Vec<u32>,
+        theme: Theme,
+        choice: Arc<Mutex<Option<Choice>>>,
+    ) -> Self {
+        let matches = matching(&items, &weights, "");
+        Self {
+            title,
+            items,
+            weights,
+            query: String::new(),
+            matches,
+            highlighted: 0,
+            theme,
+            choice,
+        }
+    }
+
+    fn set_query(&mut self, query: String) {
+        self.matches = matching(&self.items, &self.weights, &query);
+        self.query = query;
+        self.highlighted = 0;
+    }
+
+    fn move_highlight(&mut self, down: bool) {
+        let last = self.matches.len().saturating_sub(1);
+        self.highlighted = if down {
+            (self.highlighted + 1).min(last)
+        } else {
+            self.highlighted.saturating_sub(1)
+        };
+    }
+
+    /// The highlighted item, or the typed text when nothing matches, as dmenu does.
+    fn submission(&self) -> Option<Choice> {
+        match self.matches.get(self.highlighted) {
+            Some(found) => Some(Choice::Item(found.index)),
+            None => (!self.query.is_empty()).then(|| Choice::Typed(self.query.clone())),
+        }
+    }
+
+    fn finish(&self, choice: Option<Choice>) -> Task<Message> {
+        if let Ok(mut slot) = self.choice.lock() {
+            *slot = choice;
+        }
+        iced::exit()
+    }
+}
+
+#[to_layer_message]
+#[derive(Debug, Clone)]
+enum Message {
+    QueryChanged(String),
+    Submit,
+    Picked(usize),
+    Highlight { down: bool },
+    Cancel,
+}
+
+fn update(chooser: &mut Chooser, message: Message) -> Task<Message> {
+    match message {
+        Message::QueryChanged(query) => chooser.set_query(query),
+        Message::Submit => return chooser.finish(chooser.submission()),
+        Message::Picked(index) => return chooser.finish(Some(Choice::Item(index))),
+        Message::Highlight { down } => chooser.move_highlight(down),
+        Message::Cancel => return chooser.finish(None),
+        _ => {}
+    }
+    Task::none()
+}
+
+fn key_message(
+    event: iced::Event,
+    _status: event::Status,
+    _id: iced::window::Id,
+) -> Option<Message> {
+    let iced::Event::Keyboard(keyboard::Event::KeyPressed {
+        key: keyboard::Key::Named(key),
+        ..
+    }) = event
+    else {
+        return None;
+    };
+    match key {
+        keyboard::key::Named::Escape => Some(Message::Cancel),
+        keyboard::key::Named::ArrowUp => Some(Message::Highlight { down: false }),
+        keyboard::key::Named::ArrowDown | keyboard::key::Named::Tab => {
+            Some(Message::Highlight { down: true })
+        }
+        _ => None,
+    }
+}
+
+struct ChooserSettings {
+    width: u32,
+    header_font_size: u32,
+    item_font_size: u32,
+    spacing: u32,
+    padding: u32,
+}
+
+impl ChooserSettings {
+    fn load() -> Self {
+        let settings = settings::settings();
+        Self {
+            width: settings.get_parsed_or("grelier.prompt_dialog.width", DEFAULT_WIDTH),
+            header_font_size: settings
+                .get_parsed_or("grelier.dialog.header.font_size", DEFAULT_HEADER_FONT_SIZE),
+            item_font_size: settings
+                .get_parsed_or("grelier.menu_dialog.item_font_size", DEFAULT_ITEM_FONT_SIZE),
+            spacing: settings.get_parsed_or("grelier.prompt_dialog.spacing", DEFAULT_SPACING),
+            padding: settings.get_parsed_or(
+                "grelier.dialog.container.padding_y",
+                DEFAULT_CONTAINER_PADDING,
+            ),
+        }
+    }
+
+    /// Window size: title, filter field, and a fixed number of visible rows.
+    fn dimensions(&self) -> (u32, u32) {
+        let line = |size: u32| (size as f32 * 1.3).ceil() as u32;
+        let row = line(self.item_font_size) + u32::from(ROW_PADDING) * 2;
+        let height = line(self.header_font_size)
+            + (row + self.spacing) * 2
+            + row * VISIBLE_ITEMS
+            + self.padding * 2;
+        (self.width, height)
+    }
+}
+
+fn view(chooser: &Chooser) -> Element<'_, Message> {
+    let cfg = ChooserSettings::load();
+    let input = text_input("Filter", &chooser.query)
+        .id(INPUT_ID)
+        .on_input(Message::QueryChanged)
+        .on_submit(Message::Submit)
+        .font(common::dialog_font())
+        .size(cfg.item_font_size)
+        .padding(ROW_PADDING)
+        .width(Length::Fill);
+
+    let mut list = Column::new().width(Length::Fill);
+    let match_color = chooser.theme.palette().primary;
+    for (position, found) in chooser.matches.iter().enumerate() {
+        let highlighted = position == chooser.highlighted;
+        let index = found.index;
+        list = list.push(
+            button(
+                rich_text(highlighted_spans(
+                    chooser.items[index].as_str(),
+                    &found.positions,
+                    match_color,
+                ))
+                .size(cfg.item_font_size)
+                .font(common::dialog_font()),
+            )
+            .padding([ROW_PADDING, ROW_PADDING + 2])
+            .width(Length::Fill)
+            .style(move |theme: &Theme, status| {
+                let highlight = theme.extended_palette().primary.weak.color;
+                let background = (highlighted
+                    || matches!(status, button::Status::Hovered | button::Status::Pressed))
+                .then(|| highlight.into());
+                button::Style {
+                    background,
+                    text_color: theme.palette().text,
+                    ..button::Style::default()
+                }
+            })
+            .on_press(Message::Picked(index)),
+        );
+    }
+
+    let content = common::dialog_surface(
+        Column::new()
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .spacing(cfg.spacing)
+            .push(common::dialog_title(
+                chooser.title.as_str(),
+                cfg.header_font_size,
+            ))
+            .push(input)
+            .push(scrollable(list).height(Length::Fill)),
+        cfg.padding as u16,
+        cfg.padding as u16,
+    );
+    common::stack_with_border(
+        content,
+        BorderSettings::load(crate::bar::resolve_device_scale(
+            crate::bar::configured_scale(),
+            None,
+        )),
+        common::popup_border_sides(),
+    )
+}
+
+/// Show the chooser centered on the active output and block until it closes. `weights` rank
+/// items (empty for none). Returns `None` when the user cancelled.
+pub fn run(
+    title: String,
+    items: Vec<String>,
+    weights: Vec<u32>,
+    theme: Theme,
+) -> Result<Option<Choice>, String> {
+    let choice = Arc::new(Mutex::new(None));
+    let (width, height) = ChooserSettings::load().dimensions();
+    let boot_choice = Arc::clone(&choice);
+    application(
+        move || {
+            (
+                Chooser::new(
+                    title.clone(),
+                    items.clone(),
+                    weights.clone(),
+                    theme.clone(),
+                    Arc::clone(&boot_choice),
+                ),
+                iced::widget::operation::focus(INPUT_ID),
+            )
+        },
+        || "grelier-dmenu".to_string(),
+        update,
+        view,
+    )
+    .theme(|chooser: &Chooser| chooser.theme.clone())
+    .subscription(|_| event::listen_with(key_message))
+    .settings(LayerShellAppSettings {
+        layer_settings: LayerShellSettings {
+            size: Some((width, height)),
+            exclusive_zone: 0,
+            anchor: Anchor::empty(),
+            layer: Layer::Overlay,
+            margin: (0, 0, 0, 0),
+            keyboard_interactivity: KeyboardInteractivity::Exclusive,
+            start_mode: StartMode::Active,
+            events_transparent: false,
+        },
+        antialiasing: true,
+        default_font: Font::MONOSPACE,
+        ..LayerShellAppSettings::default()
+    })
+    .run()
+    .map_err(|err| format!("dmenu chooser failed: {err}"))?;
+    let choice = choice.lock().map_err(|err| err.to_string())?.take();
+    Ok(choice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_items_and_falls_back_to_typed_text() {
+        let items = read_items("eduroam\n\nHome WiFi\nhome-5g\n".as_bytes()).expect("items");
+        assert_eq!(items, vec!["eduroam", "Home WiFi", "home-5g"]);
+
+        let mut chooser = Chooser::new(
+            "Networks".to_string(),
+            items,
+            Vec::new(),
+            Theme::Dark,
+            Arc::new(Mutex::new(None)),
+        );
+        let indices = |chooser: &Chooser| -> Vec<usize> {
+            chooser.matches.iter().map(|found| found.index).collect()
+        };
+        chooser.set_query("HOME".to_string());
+        assert_eq!(indices(&chooser), vec![1, 2]);
+        chooser.move_highlight(true);
+        chooser.move_highlight(true);
+        assert_eq!(chooser.submission(), Some(Choice::Item(2)));
+        chooser.move_highlight(false);
+        assert_eq!(chooser.submission(), Some(Choice::Item(1)));
+
+        chooser.set_query("guest".to_string());
+        assert!(chooser.matches.is_empty());
+        assert_eq!(
+            chooser.submission(),
+            Some(Choice::Typed("guest".to_string()))
+        );
+        chooser.set_query(String::new());
+        assert_eq!(chooser.submission(), Some(Choice::Item(0)));
+    }
+
+    #[test]
+    fn fuzzy_ranks_word_starts_and_launch_counts() {
+        let items: Vec<String> = [
+            "Firefox",
+            "GNU Image Manipulation Program",
+            "Files",
+            "gimp-2.10",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        let (_, positions) = fuzzy_match("gimp", "GNU Image Manipulation Program").expect("match");
+        assert_eq!(positions, vec![0, 4, 10, 14]);
+        assert_eq!(fuzzy_match("xz", "Firefox"), None);
+
+        let ranked = |weights: &[u32], query: &str| -> Vec<usize> {
+            matching(&items, weights, query)
+                .iter()
+                .map(|found| found.index)
+                .collect()
+        };
+        // A contiguous match beats one spread across words.
+        assert_eq!(ranked(&[], "gimp"), vec![3, 1]);
+        assert_eq!(ranked(&[], "fi"), vec![0, 2]);
+        assert_eq!(ranked(&[0, 0, 40, 0], "fi"), vec![2, 0]);
+        assert_eq!(ranked(&[1, 0, 9, 0], ""), vec![2, 0, 1, 3]);
+
+        let spans = highlighted_spans("Files", &[0, 1], iced::Color::WHITE);
+        assert_eq!(
+            spans
+                .iter()
+                .map(|part| part.text.as_ref())
+                .collect::<Vec<_>>(),
+            vec!["Fi", "les"]
+        );
+    }
+}
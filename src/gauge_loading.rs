@@ -0,0 +1,111 @@
+// Loading placeholders: every configured gauge shows a pulsing icon in its slot until its
+// first model arrives, so slow gauges (weather, updates) do not pop in and shift the column.
+// Gauges still silent after the timeout show the standard error display instead.
+// Consumes Settings: grelier.gauge.ui.loading_timeout_secs.
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{GaugeDisplay, GaugeInteractionModel, GaugeModel};
+use crate::panels::gauges::gauge_registry;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+pub const LOADING_TIMEOUT_KEY: &str = "grelier.gauge.ui.loading_timeout_secs";
+/// Time for one half of the pulse, dim to bright or back.
+pub const PULSE_INTERVAL: Duration = Duration::from_millis(700);
+
+/// Gauges that have not reported yet.
+#[derive(Debug, Clone)]
+pub struct GaugeLoading {
+    pending: HashSet<&'static str>,
+    deadline: Instant,
+    /// Flips on every tick; the view animates toward it.
+    pub pulse: bool,
+}
+
+impl Default for GaugeLoading {
+    fn default() -> Self {
+        Self {
+            pending: HashSet::new(),
+            deadline: Instant::now(),
+            pulse: false,
+        }
+    }
+}
+
+impl GaugeLoading {
+    /// Track `ids` from now, returning the placeholder models to show meanwhile. Ids without
+    /// a registered gauge are left out.
+    pub fn start<'a>(
+        ids: impl IntoIterator<Item = &'a String>,
+        now: Instant,
+        timeout: Duration,
+    ) -> (Self, Vec<GaugeModel>) {
+        let pending: HashSet<&'static str> = ids
+            .into_iter()
+            .filter_map(|id| gauge_registry::find(id).map(|spec| spec.id))
+            .collect();
+        let placeholders = pending.iter().map(|id| placeholder(id)).collect();
+        let loading = Self {
+            pending,
+            deadline: now + timeout,
+            pulse: false,
+        };
+        (loading, placeholders)
+    }
+
+    pub fn is_loading(&self, id: &str) -> bool {
+        self.pending.contains(id)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// The first model for `id` arrived.
+    pub fn arrived(&mut self, id: &str) {
+        self.pending.remove(id);
+    }
+
+    /// Advance the pulse and return the gauges that ran out of time, which stop loading.
+    pub fn tick(&mut self, now: Instant) -> Vec<&'static str> {
+        self.pulse = !self.pulse;
+        if now < self.deadline {
+            return Vec::new();
+        }
+        self.pending.drain().collect()
+    }
+}
+
+fn placeholder(id: &'static str) -> GaugeModel {
+    GaugeModel {
+        id,
+        icon: svg_asset("gauge-loading.svg"),
+        display: GaugeDisplay::Empty,
+        interactions: GaugeInteractionModel::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_gauges_pulse_until_they_report_or_time_out() {
+        let start = Instant::now();
+        let ids = [
+            "clock".to_string(),
+            "cpu".to_string(),
+            "no_such".to_string(),
+        ];
+        let (mut loading, placeholders) = GaugeLoading::start(&ids, start, Duration::from_secs(10));
+        assert_eq!(placeholders.len(), 2, "unknown ids get no placeholder");
+        assert!(loading.is_loading("cpu"));
+
+        loading.arrived("clock");
+        assert!(!loading.is_loading("clock"));
+        assert!(loading.tick(start + Duration::from_secs(1)).is_empty());
+        assert!(loading.pulse);
+
+        assert_eq!(loading.tick(start + Duration::from_secs(10)), vec!["cpu"]);
+        assert!(loading.is_done());
+    }
+}
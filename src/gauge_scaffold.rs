@@ -0,0 +1,201 @@
+// `--new-gauge NAME`: writes a gauge module skeleton with settings, a `Gauge` implementation,
+// registry entry, and a test, ready to be dropped into src/panels/gauges.
+use crate::panels::gauges::gauge_registry;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const ID_MARKER: &str = "__ID__";
+const TYPE_MARKER: &str = "__TYPE__";
+
+const TEMPLATE: &str = r#"// __ID__ gauge: describe what the gauge shows and where the reading comes from.
+// Consumes Settings: grelier.gauge.__ID__.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use std::time::{Duration, Instant};
+
+settings::typed_settings! {
+    struct __TYPE__Settings {
+        warning_threshold: u64 = ("grelier.gauge.__ID__.warning_threshold", "80", 0..=100),
+        poll_interval_secs: u64 = ("grelier.gauge.__ID__.poll_interval_secs", "5", 1..=3600),
+    }
+}
+
+/// Take one reading. Replace with the real source, such as a file under /sys or /proc.
+fn read_value() -> Result<u64, String> {
+    Ok(42)
+}
+
+fn attention_for(value: u64, warning_threshold: u64) -> GaugeValueAttention {
+    if value >= warning_threshold {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+/// Gauge showing the latest reading.
+struct __TYPE__Gauge {
+    settings: __TYPE__Settings,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for __TYPE__Gauge {
+    fn id(&self) -> &'static str {
+        "__ID__"
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    // Runs on the shared gauge worker thread and must return quickly. Blocking work belongs
+    // in an event source added from `register`, which wakes the gauge with its ready notify.
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + Duration::from_secs(self.settings.poll_interval_secs);
+
+        let (display, lines) = match read_value() {
+            Ok(value) => (
+                GaugeDisplay::Value {
+                    value: GaugeValue::Text(value.to_string()),
+                    attention: attention_for(value, self.settings.warning_threshold),
+                },
+                vec![format!("Reading: {value}")],
+            ),
+            Err(err) => (GaugeDisplay::Error, vec![err]),
+        };
+
+        Some(GaugeModel {
+            id: "__ID__",
+            icon: svg_asset("plugin.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "__ID__".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    Box::new(__TYPE__Gauge {
+        settings: __TYPE__Settings::load(),
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    __TYPE__Settings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "__ID__",
+        description: "Describe the __ID__ gauge for --list-gauges.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_at_the_threshold() {
+        assert_eq!(attention_for(79, 80), GaugeValueAttention::Nominal);
+        assert_eq!(attention_for(80, 80), GaugeValueAttention::Warning);
+    }
+}
+"#;
+
+/// Gauge ids are used in setting keys and module names: lowercase ASCII, digits, and `_`.
+fn validate_id(id: &str) -> Result<(), String> {
+    let valid = id.starts_with(|c: char| c.is_ascii_lowercase())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid gauge name '{id}': use lowercase letters, digits, and '_', starting with a letter"
+        ));
+    }
+    if gauge_registry::find(id).is_some() {
+        return Err(format!("A gauge named '{id}' already exists"));
+    }
+    Ok(())
+}
+
+/// `disk_io` becomes `DiskIo`.
+fn type_name(id: &str) -> String {
+    id.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn render(id: &str) -> String {
+    TEMPLATE
+        .replace(TYPE_MARKER, &type_name(id))
+        .replace(ID_MARKER, id)
+}
+
+/// Write `<dir>/<id>.rs`, refusing to replace an existing file.
+pub fn write(id: &str, dir: &Path) -> Result<PathBuf, String> {
+    validate_id(id)?;
+    let path = dir.join(format!("{id}.rs"));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|err| format!("Cannot create {}: {err}", path.display()))?;
+    file.write_all(render(id).as_bytes())
+        .map_err(|err| format!("Cannot write {}: {err}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_a_module_for_valid_names_only() {
+        assert_eq!(type_name("disk_io"), "DiskIo");
+        assert_eq!(type_name("uptime2"), "Uptime2");
+        let module = render("disk_io");
+        assert!(module.contains("struct DiskIoGauge {"));
+        assert!(module.contains("\"grelier.gauge.disk_io.poll_interval_secs\""));
+        assert!(!module.contains(ID_MARKER) && !module.contains(TYPE_MARKER));
+
+        assert!(validate_id("disk_io").is_ok());
+        assert!(validate_id("Disk").is_err());
+        assert!(validate_id("2fast").is_err());
+        assert!(validate_id("disk-io").is_err());
+        assert_eq!(
+            validate_id("clock"),
+            Err("A gauge named 'clock' already exists".to_string())
+        );
+    }
+}
@@ -1,8 +1,15 @@
 // SVG asset helpers and quantity icon selection for gauges.
-use iced::{Color, widget::svg};
+// SVG templates are loaded once. Quantity icons are rasterized per theme gradient into
+// memory-mapped atlases when the theme changes, and gauges draw views into those.
+use iced::Color;
+use iced::widget::{image, svg};
+use iced_core::Bytes;
 use iced_core::svg::Data;
+use resvg::{tiny_skia, usvg};
 use std::collections::HashMap;
 use std::path::Path;
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex, OnceLock};
 
 /// Absolute path to the bundled asset directory (e.g. SVG icons).
 pub const ASSETS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets");
@@ -23,7 +30,7 @@ pub fn themed_svg_handle_cached(
         return Some(existing.clone());
     }
 
-    let template = svg_template_from_handle(handle)?;
+    let template = svg_template(handle)?;
     let svg_data = svg_with_gradient_stops(&template, &start_hex, &end_hex);
     let themed = svg::Handle::from_memory(svg_data);
     if let Ok(mut map) = cache.lock() {
@@ -39,6 +46,180 @@ fn svg_template_from_handle(handle: &svg::Handle) -> Option<String> {
     }
 }
 
+/// SVG source for `handle`, read from disk only the first time it is themed.
+fn svg_template(handle: &svg::Handle) -> Option<Arc<str>> {
+    static TEMPLATES: OnceLock<Mutex<HashMap<u64, Arc<str>>>> = OnceLock::new();
+    let templates = TEMPLATES.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Ok(map) = templates.lock()
+        && let Some(template) = map.get(&handle.id())
+    {
+        return Some(template.clone());
+    }
+    let template: Arc<str> = svg_template_from_handle(handle)?.into();
+    if let Ok(mut map) = templates.lock() {
+        map.insert(handle.id(), template.clone());
+    }
+    Some(template)
+}
+
+/// Anonymous memory mapping holding the pixels of one atlas.
+struct MappedPixels {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: the mapping is owned by this value alone and is only written before it is shared.
+unsafe impl Send for MappedPixels {}
+unsafe impl Sync for MappedPixels {}
+
+impl MappedPixels {
+    /// Map `len` zeroed bytes.
+    fn new(len: usize) -> Result<Self, String> {
+        // SAFETY: a fresh private anonymous mapping; no existing memory is touched.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(format!("mmap: {}", std::io::Error::last_os_error()));
+        }
+        let ptr = NonNull::new(ptr.cast()).ok_or("mmap returned null")?;
+        Ok(Self { ptr, len })
+    }
+}
+
+impl AsRef<[u8]> for MappedPixels {
+    fn as_ref(&self) -> &[u8] {
+        // SAFETY: the mapping is `len` bytes long and lives as long as `self`.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsMut<[u8]> for MappedPixels {
+    fn as_mut(&mut self) -> &mut [u8] {
+        // SAFETY: as above, and `&mut self` makes this the only reference.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for MappedPixels {
+    fn drop(&mut self) {
+        // SAFETY: unmaps exactly the region mapped in `new`; no slices of it outlive `self`.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), self.len);
+        }
+    }
+}
+
+/// Every quantity icon in one gradient, rasterized at one pixel size into a single
+/// memory-mapped image. Icons are stacked top to bottom, so each one is a contiguous run
+/// of rows and its handle is a view into the shared pixels rather than a copy.
+#[derive(Clone)]
+pub struct QuantityAtlas {
+    icons: [image::Handle; QUANTITY_STEPS],
+}
+
+/// Atlases by gradient and pixel size, rebuilt when the theme changes.
+pub type QuantityAtlasCache = Arc<Mutex<HashMap<String, QuantityAtlas>>>;
+
+impl QuantityAtlas {
+    /// Rasterize every quantity icon themed from `start` to `end`, `size` pixels square.
+    pub fn render(start: Color, end: Color, size: u32) -> Result<Self, String> {
+        if size == 0 {
+            return Err("quantity atlas: icon size is zero".to_string());
+        }
+        let icon_len = size as usize * size as usize * 4;
+        let mut pixels = MappedPixels::new(icon_len * QUANTITY_STEPS)
+            .map_err(|err| format!("quantity atlas: {err}"))?;
+        let start_hex = color_to_hex(start);
+        let end_hex = color_to_hex(end);
+        for (handle, icon) in quantity_icons()
+            .iter()
+            .zip(pixels.as_mut().chunks_exact_mut(icon_len))
+        {
+            let template = svg_template(handle)
+                .ok_or_else(|| format!("quantity atlas: cannot read {handle:?}"))?;
+            let svg = svg_with_gradient_stops(&template, &start_hex, &end_hex);
+            rasterize(&svg, size, icon)?;
+        }
+        let pixels = Bytes::from_owner(pixels);
+        Ok(Self {
+            icons: std::array::from_fn(|step| {
+                let rows = pixels.slice(step * icon_len..(step + 1) * icon_len);
+                image::Handle::from_rgba(size, size, rows)
+            }),
+        })
+    }
+
+    /// Handle for quantity step `step`, as returned by `quantity_step`.
+    pub fn icon(&self, step: usize) -> Option<image::Handle> {
+        self.icons.get(step).cloned()
+    }
+}
+
+/// Draw `svg` scaled to fit `size` pixels square into `pixels`, as premultiplied RGBA like
+/// iced's own SVG rasterizer produces.
+fn rasterize(svg: &[u8], size: u32, pixels: &mut [u8]) -> Result<(), String> {
+    let tree = usvg::Tree::from_data(svg, &usvg::Options::default())
+        .map_err(|err| format!("quantity atlas: {err}"))?;
+    let mut pixmap = tiny_skia::PixmapMut::from_bytes(pixels, size, size)
+        .ok_or("quantity atlas: pixel buffer does not match the icon size")?;
+    let tree_size = tree.size();
+    let scale = size as f32 / tree_size.width().max(tree_size.height());
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap,
+    );
+    Ok(())
+}
+
+fn atlas_key(start: Color, end: Color, size: u32) -> String {
+    format!("{}:{}:{size}", color_to_hex(start), color_to_hex(end))
+}
+
+/// The atlas for `start`..`end` at `size` pixels, rendered into `cache` on first use.
+pub fn quantity_atlas_cached(
+    cache: &QuantityAtlasCache,
+    start: Color,
+    end: Color,
+    size: u32,
+) -> Option<QuantityAtlas> {
+    let key = atlas_key(start, end, size);
+    if let Ok(map) = cache.lock()
+        && let Some(atlas) = map.get(&key)
+    {
+        return Some(atlas.clone());
+    }
+    let atlas = QuantityAtlas::render(start, end, size)
+        .inspect_err(|err| log::warn!("{err}"))
+        .ok()?;
+    if let Ok(mut map) = cache.lock() {
+        map.insert(key, atlas.clone());
+    }
+    Some(atlas)
+}
+
+/// Render the atlases for each `(start, end)` gradient at each pixel size ahead of time, so
+/// gauge updates only look up ready handles.
+pub fn prerender_quantity_atlases(
+    cache: &QuantityAtlasCache,
+    gradients: &[(Color, Color)],
+    sizes: &[u32],
+) {
+    for (start, end) in gradients {
+        for size in sizes {
+            quantity_atlas_cached(cache, *start, *end, *size);
+        }
+    }
+}
+
 fn svg_with_gradient_stops(template: &str, start_hex: &str, end_hex: &str) -> Vec<u8> {
     template
         .replacen(
@@ -108,6 +289,50 @@ mod svg_tests {
         assert_eq!(cache_len_after_third, 2);
     }
 
+    #[test]
+    fn quantity_atlas_serves_views_into_one_mapping_per_gradient_and_size() {
+        let cache: QuantityAtlasCache = Arc::new(Mutex::new(HashMap::new()));
+        let gradients = [
+            (
+                Color::from_rgb(0.1, 0.2, 0.3),
+                Color::from_rgb(0.7, 0.6, 0.5),
+            ),
+            (Color::BLACK, Color::WHITE),
+        ];
+        prerender_quantity_atlases(&cache, &gradients, &[16, 24]);
+        assert_eq!(cache.lock().unwrap().len(), 4);
+
+        let (start, end) = gradients[1];
+        let atlas = quantity_atlas_cached(&cache, start, end, 16).unwrap();
+        assert_eq!(cache.lock().unwrap().len(), 4);
+
+        let pixels = |step: usize| match atlas.icon(step).unwrap() {
+            image::Handle::Rgba {
+                width,
+                height,
+                pixels,
+                ..
+            } => {
+                assert_eq!((width, height), (16, 16));
+                pixels
+            }
+            other => panic!("expected RGBA pixels, got {other:?}"),
+        };
+        let empty = pixels(quantity_step(&icon_quantity(0.0)).unwrap());
+        let full = pixels(quantity_step(&icon_quantity(1.0)).unwrap());
+        let icon_len = 16 * 16 * 4;
+        assert_eq!(empty.len(), icon_len);
+        // The last icon starts right after the first seven in the same mapping.
+        assert_eq!(
+            full.as_ptr() as usize - empty.as_ptr() as usize,
+            (QUANTITY_STEPS - 1) * icon_len
+        );
+        let opaque = |pixels: &Bytes| pixels.chunks(4).filter(|pixel| pixel[3] > 0).count();
+        assert!(opaque(&full) > opaque(&empty));
+        assert!(atlas.icon(QUANTITY_STEPS).is_none());
+        assert_eq!(quantity_step(&svg_asset("bell.svg")), None);
+    }
+
     #[test]
     fn svg_gradient_replacement_updates_stops_and_styling() {
         let template = r#"
@@ -141,10 +366,23 @@ mod svg_tests {
 /// ratio-0.svg through ratio-7.svg are the icons returned.
 pub fn icon_quantity(value: f32) -> svg::Handle {
     let clamped = value.clamp(0.0, 1.0);
-    let index = (clamped * 7.0).round() as u8;
-    let icon_name = format!("ratio-{index}.svg");
+    let index = (clamped * (QUANTITY_STEPS - 1) as f32).round() as usize;
+    quantity_icons()[index].clone()
+}
+
+/// Which quantity icon `handle` is, if it came from `icon_quantity`.
+pub fn quantity_step(handle: &svg::Handle) -> Option<usize> {
+    quantity_icons()
+        .iter()
+        .position(|icon| icon.id() == handle.id())
+}
+
+const QUANTITY_STEPS: usize = 8;
 
-    svg_asset(&icon_name)
+/// The ratio icons, built once and shared by every gauge.
+fn quantity_icons() -> &'static [svg::Handle; QUANTITY_STEPS] {
+    static ICONS: OnceLock<[svg::Handle; QUANTITY_STEPS]> = OnceLock::new();
+    ICONS.get_or_init(|| std::array::from_fn(|index| svg_asset(&format!("ratio-{index}.svg"))))
 }
 
 #[cfg(test)]
@@ -0,0 +1,541 @@
+// Per-process control socket for commands sent by `grelier --snapshot`, `grelier --lock`, and similar tools.
+// Clients can also `subscribe` to a stream of newline-delimited JSON state-change events.
+use crate::bar::Message;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, executor};
+use serde_json::Value;
+use std::ffi::CString;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc as std_mpsc;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+const SOCKET_PREFIX: &str = "grelier-";
+const SOCKET_SUFFIX: &str = ".sock";
+/// Screenshots go through the renderer, so allow a few frames before giving up.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+/// A subscriber that stops reading is dropped rather than stalling the others.
+const EVENT_WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+/// How long `--replace` waits for old instances to exit.
+const REPLACE_TIMEOUT: Duration = Duration::from_secs(5);
+const REPLACE_POLL: Duration = Duration::from_millis(50);
+
+/// Command received on the control socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpcCommand {
+    /// Write a PNG of every bar/dialog window and a JSON state dump into `dir`.
+    Snapshot { dir: PathBuf },
+    /// Change the bar lock that makes every bar ignore pointer input.
+    Lock(LockAction),
+    /// Keep the connection open and stream events on these topics; empty means all.
+    Subscribe { topics: Vec<EventTopic> },
+    /// Open or close the workspace overview.
+    Overview,
+    /// Report each gauge's run counters, used by `grelier --doctor`.
+    GaugeStats,
+    /// Close every bar and exit, used by `grelier --replace`.
+    Quit,
+}
+
+/// Kinds of state change streamed to subscribers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventTopic {
+    /// The focused workspace changed.
+    Workspace,
+    /// A gauge's displayed value changed.
+    Gauge,
+    /// A gauge dialog opened.
+    Dialog,
+    /// A gauge entered danger attention; carries text for a screen reader to speak.
+    Announce,
+}
+
+impl std::str::FromStr for EventTopic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "workspace" => Ok(EventTopic::Workspace),
+            "gauge" => Ok(EventTopic::Gauge),
+            "dialog" => Ok(EventTopic::Dialog),
+            "announce" => Ok(EventTopic::Announce),
+            other => Err(format!(
+                "Invalid event topic '{other}', expected 'workspace', 'gauge', 'dialog', or \
+                 'announce'"
+            )),
+        }
+    }
+}
+
+impl EventTopic {
+    fn as_str(self) -> &'static str {
+        match self {
+            EventTopic::Workspace => "workspace",
+            EventTopic::Gauge => "gauge",
+            EventTopic::Dialog => "dialog",
+            EventTopic::Announce => "announce",
+        }
+    }
+}
+
+/// Parse a comma-separated topic list such as `gauge,dialog`.
+pub fn parse_topics(raw: &str) -> Result<Vec<EventTopic>, String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|topic| !topic.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Requested change to the bar lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockAction {
+    On,
+    Off,
+    Toggle,
+}
+
+impl std::str::FromStr for LockAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "on" => Ok(LockAction::On),
+            "off" => Ok(LockAction::Off),
+            "toggle" => Ok(LockAction::Toggle),
+            other => Err(format!(
+                "Invalid lock action '{other}', expected 'on', 'off', or 'toggle'"
+            )),
+        }
+    }
+}
+
+impl LockAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            LockAction::On => "on",
+            LockAction::Off => "off",
+            LockAction::Toggle => "toggle",
+        }
+    }
+
+    /// Lock state after applying this action to `locked`.
+    pub fn apply(self, locked: bool) -> bool {
+        match self {
+            LockAction::On => true,
+            LockAction::Off => false,
+            LockAction::Toggle => !locked,
+        }
+    }
+}
+
+impl IpcCommand {
+    /// Parse one request line such as `snapshot /tmp/grelier-report`.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (verb, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match verb {
+            "snapshot" => {
+                let dir = rest.trim();
+                if dir.is_empty() {
+                    return Err("snapshot requires a target directory".to_string());
+                }
+                Ok(IpcCommand::Snapshot {
+                    dir: PathBuf::from(dir),
+                })
+            }
+            "lock" => rest.parse().map(IpcCommand::Lock),
+            "subscribe" => parse_topics(rest).map(|topics| IpcCommand::Subscribe { topics }),
+            "overview" => Ok(IpcCommand::Overview),
+            "gauge-stats" => Ok(IpcCommand::GaugeStats),
+            "quit" => Ok(IpcCommand::Quit),
+            "" => Err("empty command".to_string()),
+            other => Err(format!("unknown command '{other}'")),
+        }
+    }
+
+    fn to_line(&self) -> String {
+        match self {
+            IpcCommand::Snapshot { dir } => format!("snapshot {}", dir.display()),
+            IpcCommand::Lock(action) => format!("lock {}", action.as_str()),
+            IpcCommand::Overview => "overview".to_string(),
+            IpcCommand::GaugeStats => "gauge-stats".to_string(),
+            IpcCommand::Quit => "quit".to_string(),
+            IpcCommand::Subscribe { topics } => {
+                let topics: Vec<&str> = topics.iter().map(|topic| topic.as_str()).collect();
+                format!("subscribe {}", topics.join(","))
+                    .trim_end()
+                    .to_string()
+            }
+        }
+    }
+}
+
+/// Channel the update loop uses to answer the connected client with one JSON line.
+pub type IpcReply = std_mpsc::Sender<String>;
+
+fn runtime_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+fn socket_path_in(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("{SOCKET_PREFIX}{pid}{SOCKET_SUFFIX}"))
+}
+
+fn socket_pid(path: &Path) -> Option<u32> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix(SOCKET_PREFIX)?
+        .strip_suffix(SOCKET_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+/// Remove this process's control socket, so it is not mistaken for a live instance.
+pub fn remove_socket() {
+    let _ = std::fs::remove_file(socket_path_in(&runtime_dir(), std::process::id()));
+}
+
+/// Socket path as a C string, prepared up front for the termination handler.
+static SOCKET_CPATH: OnceLock<CString> = OnceLock::new();
+
+extern "C" fn on_termination(signal: libc::c_int) {
+    if let Some(path) = SOCKET_CPATH.get() {
+        // SAFETY: unlink, signal, and raise are async-signal-safe, and the path was built before
+        // the handler was installed.
+        unsafe {
+            libc::unlink(path.as_ptr());
+        }
+    }
+    // SAFETY: restoring the default action and re-raising ends the process as the signal asks.
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    }
+}
+
+/// Remove the control socket when the process is ended by SIGTERM, SIGINT, or SIGHUP.
+pub fn remove_socket_on_termination() {
+    let path = socket_path_in(&runtime_dir(), std::process::id());
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return;
+    };
+    if SOCKET_CPATH.set(path).is_err() {
+        return;
+    }
+    // SAFETY: the handler only calls async-signal-safe functions.
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_termination as *const () as usize;
+        libc::sigemptyset(&mut action.sa_mask);
+        for signal in [libc::SIGTERM, libc::SIGINT, libc::SIGHUP] {
+            if libc::sigaction(signal, &action, std::ptr::null_mut()) != 0 {
+                log::warn!("Failed to install the control socket cleanup for signal {signal}");
+            }
+        }
+    }
+}
+
+/// Control sockets of every grelier instance; one process serves the bars of all its monitors.
+fn instance_sockets(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut sockets: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| socket_pid(path).is_some())
+        .collect();
+    sockets.sort();
+    sockets
+}
+
+/// Send `command` to every running instance and return each reply keyed by socket path.
+pub fn send_to_all(command: &IpcCommand) -> Result<Vec<(PathBuf, String)>, String> {
+    let mut replies = Vec::new();
+    for path in instance_sockets(&runtime_dir()) {
+        let Ok(mut stream) = UnixStream::connect(&path) else {
+            // Left behind by an instance that did not exit cleanly.
+            continue;
+        };
+        let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT + Duration::from_secs(1)));
+        stream
+            .write_all(format!("{}\n", command.to_line()).as_bytes())
+            .map_err(|err| format!("failed to send to {}: {err}", path.display()))?;
+        let mut reply = String::new();
+        BufReader::new(stream)
+            .read_line(&mut reply)
+            .map_err(|err| format!("failed to read from {}: {err}", path.display()))?;
+        replies.push((path, reply.trim().to_string()));
+    }
+    if replies.is_empty() {
+        return Err("no running grelier instance found".to_string());
+    }
+    Ok(replies)
+}
+
+/// Whether `pid` is a live process; zombies waiting to be reaped count as exited.
+fn process_running(pid: u32) -> bool {
+    let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) else {
+        return false;
+    };
+    // The state follows the command name, which is parenthesized and may contain spaces.
+    stat.rsplit_once(')')
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .is_some_and(|state| state != "Z" && state != "X")
+}
+
+/// Ask every running instance to quit and wait until they are gone. An instance answers only
+/// after its bar surfaces have closed; then its control socket and process must disappear.
+/// Succeeds when nothing is running.
+pub fn replace_running() -> Result<(), String> {
+    let replies = match send_to_all(&IpcCommand::Quit) {
+        Ok(replies) => replies,
+        Err(_) => return Ok(()),
+    };
+    let instances: Vec<(PathBuf, u32)> = replies
+        .into_iter()
+        .filter_map(|(path, _)| socket_pid(&path).map(|pid| (path, pid)))
+        .filter(|(_, pid)| *pid != std::process::id())
+        .collect();
+    let deadline = std::time::Instant::now() + REPLACE_TIMEOUT;
+    loop {
+        let running: Vec<u32> = instances
+            .iter()
+            .filter(|(path, pid)| UnixStream::connect(path).is_ok() || process_running(*pid))
+            .map(|(_, pid)| *pid)
+            .collect();
+        if running.is_empty() {
+            return Ok(());
+        }
+        if std::time::Instant::now() >= deadline {
+            return Err(format!(
+                "grelier instance {} did not exit within {}s",
+                running
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                REPLACE_TIMEOUT.as_secs()
+            ));
+        }
+        std::thread::sleep(REPLACE_POLL);
+    }
+}
+
+/// Stream events from the first running instance to stdout until it exits.
+pub fn print_events(topics: Vec<EventTopic>) -> Result<(), String> {
+    let path = instance_sockets(&runtime_dir())
+        .into_iter()
+        .find(|path| UnixStream::connect(path).is_ok())
+        .ok_or_else(|| "no running grelier instance found".to_string())?;
+    let mut stream = UnixStream::connect(&path)
+        .map_err(|err| format!("failed to connect to {}: {err}", path.display()))?;
+    stream
+        .write_all(format!("{}\n", IpcCommand::Subscribe { topics }.to_line()).as_bytes())
+        .map_err(|err| format!("failed to send to {}: {err}", path.display()))?;
+    let mut stdout = std::io::stdout().lock();
+    for line in BufReader::new(stream).lines() {
+        let line = line.map_err(|err| format!("failed to read from {}: {err}", path.display()))?;
+        if writeln!(stdout, "{line}")
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+    Ok(())
+}
+
+struct Subscriber {
+    stream: UnixStream,
+    topics: Vec<EventTopic>,
+}
+
+impl Subscriber {
+    fn wants(&self, topic: EventTopic) -> bool {
+        self.topics.is_empty() || self.topics.contains(&topic)
+    }
+}
+
+fn subscribers() -> &'static Mutex<Vec<Subscriber>> {
+    static SUBSCRIBERS: OnceLock<Mutex<Vec<Subscriber>>> = OnceLock::new();
+    SUBSCRIBERS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Send an event to every subscriber of `topic`, dropping those that went away.
+/// `event` is only built when someone is listening.
+pub fn publish(topic: EventTopic, event: impl FnOnce() -> Value) {
+    let Ok(mut subscribers) = subscribers().lock() else {
+        return;
+    };
+    if !subscribers.iter().any(|subscriber| subscriber.wants(topic)) {
+        return;
+    }
+    let mut event = event();
+    if let Some(fields) = event.as_object_mut() {
+        fields.insert("event".to_string(), Value::from(topic.as_str()));
+    }
+    let line = format!("{event}\n");
+    subscribers.retain_mut(|subscriber| {
+        !subscriber.wants(topic) || subscriber.stream.write_all(line.as_bytes()).is_ok()
+    });
+}
+
+fn error_reply(message: &str) -> String {
+    serde_json::json!({ "ok": false, "error": message }).to_string()
+}
+
+fn handle_client(stream: UnixStream, sender: &mut mpsc::Sender<Message>) {
+    // A client that connects and never sends a command only ties up its own thread.
+    let _ = stream.set_read_timeout(Some(REPLY_TIMEOUT));
+    let mut line = String::new();
+    if BufReader::new(&stream).read_line(&mut line).is_err() {
+        return;
+    }
+    let reply = match IpcCommand::parse(&line) {
+        Ok(IpcCommand::Subscribe { topics }) => {
+            let mut stream = stream;
+            let _ = stream.set_write_timeout(Some(EVENT_WRITE_TIMEOUT));
+            let reply = serde_json::json!({
+                "ok": true,
+                "subscribed": topics.iter().map(|topic| topic.as_str()).collect::<Vec<_>>(),
+            });
+            if stream.write_all(format!("{reply}\n").as_bytes()).is_ok()
+                && let Ok(mut subscribers) = subscribers().lock()
+            {
+                subscribers.push(Subscriber { stream, topics });
+            }
+            return;
+        }
+        Ok(command) => {
+            let (reply_tx, reply_rx) = std_mpsc::channel();
+            let message = Message::Ipc {
+                command,
+                reply: reply_tx,
+            };
+            if executor::block_on(sender.send(message)).is_err() {
+                return;
+            }
+            reply_rx
+                .recv_timeout(REPLY_TIMEOUT)
+                .unwrap_or_else(|_| error_reply("timed out waiting for the bar"))
+        }
+        Err(err) => error_reply(&err),
+    };
+    let mut stream = stream;
+    let _ = stream.write_all(format!("{reply}\n").as_bytes());
+}
+
+fn ipc_stream() -> impl iced::futures::Stream<Item = Message> {
+    let (sender, receiver) = mpsc::channel(4);
+
+    std::thread::spawn(move || {
+        let path = socket_path_in(&runtime_dir(), std::process::id());
+        let _ = std::fs::remove_file(&path);
+        let listener = match UnixListener::bind(&path) {
+            Ok(listener) => listener,
+            Err(err) => {
+                log::warn!("Failed to bind control socket {}: {err}", path.display());
+                return;
+            }
+        };
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    // Each client gets its own thread, so a slow one cannot hold up the rest.
+                    let mut sender = sender.clone();
+                    std::thread::spawn(move || handle_client(stream, &mut sender));
+                }
+                Err(err) => log::warn!("Control socket accept failed: {err}"),
+            }
+        }
+    });
+
+    receiver
+}
+
+/// Serve the control socket for the lifetime of the bar.
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(ipc_stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_snapshot_command() {
+        assert_eq!(
+            IpcCommand::parse("snapshot /tmp/report\n"),
+            Ok(IpcCommand::Snapshot {
+                dir: PathBuf::from("/tmp/report")
+            })
+        );
+        assert!(IpcCommand::parse("snapshot").is_err());
+        assert!(IpcCommand::parse("reload").is_err());
+
+        let lock = IpcCommand::Lock(LockAction::Toggle);
+        assert_eq!(IpcCommand::parse(&lock.to_line()), Ok(lock));
+        assert!(IpcCommand::parse("lock sideways").is_err());
+        assert!(LockAction::Toggle.apply(false));
+        assert!(LockAction::On.apply(true));
+
+        let subscribe = IpcCommand::parse("subscribe gauge, dialog").expect("subscribe");
+        assert_eq!(
+            subscribe,
+            IpcCommand::Subscribe {
+                topics: vec![EventTopic::Gauge, EventTopic::Dialog]
+            }
+        );
+        assert_eq!(IpcCommand::parse(&subscribe.to_line()), Ok(subscribe));
+        assert_eq!(
+            IpcCommand::parse("subscribe"),
+            Ok(IpcCommand::Subscribe { topics: Vec::new() })
+        );
+        assert!(IpcCommand::parse("subscribe clicks").is_err());
+        assert_eq!(IpcCommand::parse("quit"), Ok(IpcCommand::Quit));
+        assert_eq!(IpcCommand::parse("overview"), Ok(IpcCommand::Overview));
+        assert_eq!(
+            IpcCommand::parse(&IpcCommand::GaugeStats.to_line()),
+            Ok(IpcCommand::GaugeStats)
+        );
+    }
+
+    #[test]
+    fn finds_instance_sockets_by_name() {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("grelier_ipc_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("create dir");
+        for name in ["grelier-12.sock", "grelier-x.sock", "other-3.sock"] {
+            std::fs::write(dir.join(name), "").expect("write file");
+        }
+
+        assert_eq!(instance_sockets(&dir), vec![socket_path_in(&dir, 12)]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn zombie_processes_count_as_exited() {
+        assert!(process_running(std::process::id()));
+        let mut child = std::process::Command::new("true")
+            .spawn()
+            .expect("spawn true");
+        // Until it is reaped, the exited child stays behind as a zombie.
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while process_running(child.id()) && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!process_running(child.id()));
+        let _ = child.wait();
+    }
+}
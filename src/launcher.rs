@@ -0,0 +1,193 @@
+// App launcher: `grelier --launcher` lists installed applications in the fuzzy chooser, ranked
+// by how often each was launched, and starts the chosen one through the compositor. Desktop
+// entry actions follow their app, and recently used files from `recently-used.xbel` come last.
+use crate::apps::{self, AppAction};
+use crate::compositor;
+use crate::dmenu::{self, Choice};
+use elbey_cache::{AppDescriptor, Cache};
+use iced::Theme;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Most recently used files offered by the launcher.
+const RECENT_FILES_LIMIT: usize = 30;
+
+/// Something the launcher can start.
+enum Entry {
+    App(usize),
+    Action(usize, AppAction),
+    File(PathBuf),
+}
+
+/// Show the launcher and start the chosen entry. Returns whether anything was launched.
+pub fn run(title: String, theme: Theme) -> Result<bool, String> {
+    let mut cache = Cache::new(apps::load_desktop_apps);
+    let apps = cache.load_apps();
+    let mut actions = apps::load_app_actions();
+
+    let mut entries = Vec::new();
+    let mut titles = Vec::new();
+    let mut weights = Vec::new();
+    for (index, app) in apps.iter().enumerate() {
+        let weight = u32::try_from(app.exec_count).unwrap_or(u32::MAX);
+        entries.push(Entry::App(index));
+        titles.push(app.title.clone());
+        weights.push(weight);
+        for action in actions.remove(&app.appid).unwrap_or_default() {
+            titles.push(format!("{}: {}", app.title, action.name));
+            weights.push(weight);
+            entries.push(Entry::Action(index, action));
+        }
+    }
+    for path in recent_files(&recently_used_path()) {
+        titles.push(file_title(&path));
+        weights.push(0);
+        entries.push(Entry::File(path));
+    }
+
+    let Some(Choice::Item(index)) = dmenu::run(title, titles, weights, theme)? else {
+        return Ok(false);
+    };
+    match &entries[index] {
+        Entry::App(app) => {
+            compositor::backend().launch_app(&apps[*app].appid)?;
+            record_launch(&mut cache, &apps[*app]);
+        }
+        Entry::Action(app, action) => {
+            spawn(&action.argv)?;
+            record_launch(&mut cache, &apps[*app]);
+        }
+        Entry::File(path) => spawn(&["xdg-open".to_string(), path.display().to_string()])?,
+    }
+    Ok(true)
+}
+
+fn record_launch(cache: &mut Cache, app: &AppDescriptor) {
+    if let Err(err) = cache.record_launch(app) {
+        log::warn!("Failed to update app cache for \"{}\": {err}", app.appid);
+    }
+}
+
+/// Start `argv` detached from the launcher, which exits right after.
+fn spawn(argv: &[String]) -> Result<(), String> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| "empty command".to_string())?;
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("failed to run {program}: {err}"))
+}
+
+fn recently_used_path() -> PathBuf {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("recently-used.xbel")
+}
+
+/// "report.pdf (~/Documents)", so files with the same name can be told apart.
+fn file_title(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let dir = path
+        .parent()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    let dir = match std::env::var("HOME") {
+        Ok(home) if !home.is_empty() && dir.starts_with(&home) => {
+            format!("~{}", &dir[home.len()..])
+        }
+        _ => dir,
+    };
+    format!("{name} ({dir})")
+}
+
+/// Existing local files in the recently-used list, most recently modified first.
+fn recent_files(path: &Path) -> Vec<PathBuf> {
+    let Ok(xbel) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    parse_xbel(&xbel)
+        .into_iter()
+        .filter(|path| path.exists())
+        .take(RECENT_FILES_LIMIT)
+        .collect()
+}
+
+/// Local file bookmarks in an XBEL document, most recently modified first.
+fn parse_xbel(xbel: &str) -> Vec<PathBuf> {
+    let mut bookmarks: Vec<(String, PathBuf)> = xbel
+        .split("<bookmark ")
+        .skip(1)
+        .filter_map(|rest| {
+            let tag = &rest[..rest.find('>')?];
+            let href = attribute(tag, "href")?;
+            let path = percent_decode(href.strip_prefix("file://")?)?;
+            let modified = attribute(tag, "modified").unwrap_or_default();
+            Some((modified.to_string(), PathBuf::from(path)))
+        })
+        .collect();
+    // Timestamps are ISO 8601 in UTC, so they sort as strings.
+    bookmarks.sort_by(|a, b| b.0.cmp(&a.0));
+    bookmarks.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Value of attribute `name` in the attribute list of a start tag.
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!("{name}=\"");
+    let start = attributes
+        .match_indices(&needle)
+        .map(|(index, _)| index)
+        .find(|&index| index == 0 || attributes[..index].ends_with(char::is_whitespace))?
+        + needle.len();
+    let len = attributes[start..].find('"')?;
+    Some(&attributes[start..start + len])
+}
+
+fn percent_decode(raw: &str) -> Option<String> {
+    let raw = raw.replace("&amp;", "&").replace("&apos;", "'");
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut iter = raw.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_recent_local_files_newest_first() {
+        let xbel = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0">
+  <bookmark href="file:///home/sam/Notes/old.txt" added="2026-01-01T10:00:00Z" modified="2026-01-01T10:00:00Z" visited="2026-01-01T10:00:00Z">
+    <info/>
+  </bookmark>
+  <bookmark href="https://example.com/page" added="2026-03-01T10:00:00Z" modified="2026-03-01T10:00:00Z" visited="2026-03-01T10:00:00Z"/>
+  <bookmark href="file:///home/sam/Tax%20%26%20Bills/2026.pdf" added="2026-02-01T10:00:00Z" modified="2026-02-01T10:00:00Z" visited="2026-02-01T10:00:00Z"/>
+</xbel>"#;
+        assert_eq!(
+            parse_xbel(xbel),
+            vec![
+                PathBuf::from("/home/sam/Tax & Bills/2026.pdf"),
+                PathBuf::from("/home/sam/Notes/old.txt"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+// Laptop lid state and clamshell detection (lid closed while an external monitor is active).
+// Lid changes come from logind, which follows the udev lid switch; /proc/acpi is polled
+// when logind is unavailable.
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use iced::Subscription;
+use iced::futures::SinkExt;
+use iced::futures::channel::mpsc;
+use iced::futures::executor;
+use zbus::blocking::{Connection, Proxy};
+
+use crate::bar::{Message, OutputSnapshot};
+use crate::panels::gauges::ddc_common;
+
+const ACPI_LID_ROOT: &str = "/proc/acpi/button/lid";
+const LOGIND_SERVICE: &str = "org.freedesktop.login1";
+const LOGIND_PATH: &str = "/org/freedesktop/login1";
+const LOGIND_IFACE: &str = "org.freedesktop.login1.Manager";
+/// Poll cadence for the /proc/acpi fallback.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static CLAMSHELL: AtomicBool = AtomicBool::new(false);
+
+/// Whether the bar is currently in clamshell mode; read by gauges tied to the internal panel.
+pub fn clamshell_active() -> bool {
+    CLAMSHELL.load(Ordering::Relaxed)
+}
+
+pub fn set_clamshell(active: bool) {
+    CLAMSHELL.store(active, Ordering::Relaxed);
+}
+
+/// Built-in panels use eDP, LVDS, or DSI connectors.
+pub fn is_internal_output(name: &str) -> bool {
+    ddc_common::INTERNAL_CONNECTORS
+        .iter()
+        .any(|internal| name.starts_with(internal))
+}
+
+/// Lid closed with at least one external output still active.
+pub fn is_clamshell(lid_closed: bool, outputs: &[OutputSnapshot]) -> bool {
+    lid_closed
+        && outputs
+            .iter()
+            .any(|output| output.active && !is_internal_output(&output.name))
+}
+
+/// Parse an ACPI lid `state` file: `state:      closed`.
+fn parse_acpi_state(contents: &str) -> Option<bool> {
+    match contents.split_once(':')?.1.trim() {
+        "closed" => Some(true),
+        "open" => Some(false),
+        _ => None,
+    }
+}
+
+/// Lid state from /proc/acpi; `None` when the machine has no lid there.
+fn read_acpi_lid(root: &Path) -> Option<bool> {
+    fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .find_map(|entry| parse_acpi_state(&fs::read_to_string(entry.path().join("state")).ok()?))
+}
+
+fn send(sender: &mut mpsc::Sender<Message>, closed: bool) -> bool {
+    executor::block_on(sender.send(Message::LidChanged { closed })).is_ok()
+}
+
+/// Follow logind's `LidClosed` property; returns `Err` when logind cannot be used.
+fn watch_logind(sender: &mut mpsc::Sender<Message>) -> Result<(), String> {
+    let connection = Connection::system().map_err(|err| format!("system bus: {err}"))?;
+    let proxy = Proxy::new(&connection, LOGIND_SERVICE, LOGIND_PATH, LOGIND_IFACE)
+        .map_err(|err| format!("logind proxy: {err}"))?;
+    if !proxy.get_property::<bool>("LidPresent").unwrap_or(false) {
+        log::debug!("No lid switch reported by logind");
+        return Ok(());
+    }
+    // The first item carries the current value.
+    for change in proxy.receive_property_changed::<bool>("LidClosed") {
+        let Ok(closed) = change.get() else {
+            continue;
+        };
+        if !send(sender, closed) {
+            return Ok(());
+        }
+    }
+    Err("logind property stream ended".to_string())
+}
+
+fn poll_acpi(sender: &mut mpsc::Sender<Message>) {
+    let root = Path::new(ACPI_LID_ROOT);
+    let mut last = None;
+    while !sender.is_closed() {
+        let Some(closed) = read_acpi_lid(root) else {
+            return;
+        };
+        if last != Some(closed) {
+            last = Some(closed);
+            if !send(sender, closed) {
+                return;
+            }
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn lid_stream() -> impl iced::futures::Stream<Item = Message> {
+    let (mut sender, receiver) = mpsc::channel(1);
+    std::thread::spawn(move || {
+        if let Err(err) = watch_logind(&mut sender) {
+            log::debug!("Lid events via logind unavailable ({err}); polling {ACPI_LID_ROOT}");
+            poll_acpi(&mut sender);
+        }
+    });
+    receiver
+}
+
+/// Lid open/close events.
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(lid_stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(name: &str, active: bool) -> OutputSnapshot {
+        OutputSnapshot {
+            name: name.to_string(),
+            active,
+            rect: (0, 0, 1920, 1080),
+            scale: 1.0,
+        }
+    }
+
+    #[test]
+    fn detects_clamshell_from_lid_and_outputs() {
+        assert_eq!(parse_acpi_state("state:      closed\n"), Some(true));
+        assert_eq!(parse_acpi_state("state:      open\n"), Some(false));
+        assert_eq!(parse_acpi_state("garbage"), None);
+
+        let docked = [output("eDP-1", true), output("DP-2", true)];
+        assert!(is_clamshell(true, &docked));
+        assert!(!is_clamshell(false, &docked));
+        assert!(!is_clamshell(true, &[output("eDP-1", true)]));
+        assert!(!is_clamshell(true, &[output("HDMI-A-1", false)]));
+    }
+}
@@ -1,14 +1,29 @@
 // Entry point wiring CLI args, settings initialization, and gauge subscriptions for the bar.
+mod a11y;
 mod apps;
+mod autohide;
 mod bar;
+mod cli_docs;
+mod compositor;
 mod dialog;
+mod dmenu;
+mod gauge_loading;
+mod gauge_scaffold;
 mod icon;
+mod ipc;
+mod launcher;
+mod lid;
 mod monitor;
+mod overview;
 mod panels;
+mod session_kind;
 mod settings;
 mod settings_storage;
-mod sway_workspace;
+mod snapshot;
+mod style;
 mod theme;
+mod units;
+mod window_tracker;
 
 use argh::FromArgs;
 use iced::Font;
@@ -17,27 +32,40 @@ use iced::{Subscription, event, mouse, window};
 
 use iced_layershell::daemon;
 use iced_layershell::reexport::{
-    Anchor, KeyboardInteractivity, Layer, NewLayerShellSettings, OutputOption,
+    KeyboardInteractivity, Layer, NewLayerShellSettings, OutputOption,
 };
 use iced_layershell::settings::{LayerShellSettings, Settings as LayerShellAppSettings, StartMode};
 
 use crate::bar::Orientation;
 use crate::bar::{
-    AppIconCache, BarState, GaugeDialog, GaugeDialogWindow, Message, close_window_task,
+    AppIconCache, BarState, GaugeDialog, GaugeDialogWindow, HoverTarget, Message, OutputSnapshot,
+    close_window_task,
+};
+use crate::dialog::toast::Toast;
+use crate::panels::gauges::gauge::{
+    GaugeClick, GaugeDisplay, GaugeInput, GaugeMenu, GaugeModel, GaugePointerInteraction,
+    GaugeRedraw, GaugeUpdate,
+};
+use crate::panels::gauges::gauge_bindings::{self, GaugeBinding};
+use crate::panels::gauges::{
+    gauge_dependencies, gauge_diagnostics, gauge_hardware, gauge_registry, gauge_tuning, plugin,
 };
-use crate::panels::gauges::gauge::{GaugeClick, GaugeInput, GaugeModel, GaugePointerInteraction};
-use crate::panels::gauges::gauge_registry;
 use crate::panels::panel_registry;
+use crate::panels::taskbar_panel;
+use crate::panels::top_apps_panel;
+use crate::window_tracker::BarClosed;
 use elbey_cache::Cache;
 use log::{error, info, warn};
 use std::io::Write;
 use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 const DEFAULT_ORIENTATION: &str = "left";
 const DEFAULT_THEME: &str = "Nord";
 const DIALOG_UNFOCUS_SUPPRESSION_WINDOW: Duration = Duration::from_millis(250);
-const OUTPUT_REOPEN_SUPPRESSION_WINDOW: Duration = Duration::from_millis(750);
+/// How long the toast showing a tuned gauge interval stays up.
+const TUNE_TOAST_TIMEOUT: Duration = Duration::from_secs(2);
 
 struct StderrLogger;
 
@@ -110,6 +138,16 @@ fn exit_with_error(message: impl std::fmt::Display) -> ! {
     std::process::exit(1);
 }
 
+/// Theme named by `grelier.bar.theme`; an unknown name is fatal at startup.
+fn configured_theme(settings_store: &settings::Settings) -> iced::Theme {
+    match settings_store.get(theme::THEME_KEY) {
+        Some(name) => {
+            theme::theme_for_name(&name, settings_store).unwrap_or_else(|err| exit_with_error(err))
+        }
+        None => theme::DEFAULT_THEME,
+    }
+}
+
 fn ensure_layershell_environment() -> Result<(), String> {
     let session_type = std::env::var("XDG_SESSION_TYPE")
         .ok()
@@ -157,6 +195,11 @@ fn ensure_layershell_environment() -> Result<(), String> {
     Ok(())
 }
 
+/// Ask the compositor for the scale of the output `window` is on.
+fn query_window_scale(window: window::Id) -> Task<Message> {
+    window::scale_factor(window).map(move |scale| Message::WindowScaleChanged { window, scale })
+}
+
 fn set_input_region_task(window: window::Id, size: iced::Size) -> Task<Message> {
     if size.width <= 0.0 || size.height <= 0.0 {
         return Task::none();
@@ -206,6 +249,88 @@ struct Args {
     /// limit bar to one monitor by name
     #[argh(option, long = "on-monitor")]
     on_monitor: Option<String>,
+
+    /// write screenshots and a state dump of running bars into a directory and exit
+    #[argh(option, long = "snapshot")]
+    snapshot: Option<std::path::PathBuf>,
+
+    /// lock (on), unlock (off), or toggle input on running bars and exit
+    #[argh(option, long = "lock")]
+    lock: Option<ipc::LockAction>,
+
+    /// open or close the workspace overview on running bars and exit
+    #[argh(switch)]
+    overview: bool,
+
+    /// check optional services and tools used by gauges and exit
+    #[argh(switch)]
+    doctor: bool,
+
+    /// ask a running bar to exit and take its place
+    #[argh(switch)]
+    replace: bool,
+
+    /// print state-change events from a running bar as JSON lines; optionally a comma-separated list of workspace, gauge, dialog, announce
+    #[argh(option, long = "events")]
+    events: Option<String>,
+
+    /// print a completion script for bash, zsh, or fish and exit
+    #[argh(option, long = "generate-completions")]
+    generate_completions: Option<cli_docs::Shell>,
+
+    /// print the man page (roff) and exit
+    #[argh(switch)]
+    generate_man: bool,
+
+    /// pick one line from stdin in a chooser dialog, print it, and exit, like dmenu
+    #[argh(switch)]
+    dmenu: bool,
+
+    /// title of the --dmenu or --launcher chooser
+    #[argh(option, short = 'p', long = "prompt")]
+    prompt: Option<String>,
+
+    /// pick an installed application in a chooser dialog, launch it, and exit
+    #[argh(switch)]
+    launcher: bool,
+
+    /// write a skeleton module for a new gauge named NAME and exit
+    #[argh(option, long = "new-gauge")]
+    new_gauge: Option<String>,
+
+    /// directory for the --new-gauge module; defaults to the current directory
+    #[argh(option, long = "new-gauge-dir")]
+    new_gauge_dir: Option<std::path::PathBuf>,
+}
+
+/// Options, gauges, themes, and settings for the generated completions and man page.
+fn print_cli_docs(shell: Option<cli_docs::Shell>) {
+    let help = match Args::from_args(&["grelier"], &["--help"]) {
+        Err(early_exit) => early_exit.output,
+        Ok(_) => String::new(),
+    };
+    let base_setting_specs = settings::base_setting_specs(
+        gauge_registry::default_gauges(),
+        panel_registry::default_panels(),
+        DEFAULT_ORIENTATION,
+        DEFAULT_THEME,
+    );
+    let setting_specs =
+        gauge_registry::collect_settings(&panel_registry::collect_settings(&base_setting_specs));
+    let mut gauges: Vec<(&'static str, &'static str)> = gauge_registry::all()
+        .map(|spec| (spec.id, spec.description))
+        .collect();
+    gauges.sort();
+    let data = cli_docs::CliData {
+        options: cli_docs::parse_help(&help),
+        gauges,
+        themes: theme::theme_names(),
+        settings: &setting_specs,
+    };
+    match shell {
+        Some(shell) => print!("{}", data.completions(shell)),
+        None => print!("{}", data.man_page()),
+    }
 }
 
 fn main() -> Result<(), iced_layershell::Error> {
@@ -218,30 +343,99 @@ fn main() -> Result<(), iced_layershell::Error> {
         return Ok(());
     }
 
+    plugin::load_plugins(&plugin::plugin_dir());
+
     if args.list_gauges {
         gauge_registry::list_gauges();
         return Ok(());
     }
 
+    if let Some(name) = args.new_gauge {
+        let dir = args
+            .new_gauge_dir
+            .unwrap_or_else(|| std::path::PathBuf::from("."));
+        match gauge_scaffold::write(&name, &dir) {
+            Ok(path) => {
+                println!("Wrote {}", path.display());
+                println!(
+                    "Move it into src/panels/gauges and add `pub mod {name};` to src/panels/gauges/mod.rs."
+                );
+            }
+            Err(err) => exit_with_error(err),
+        }
+        return Ok(());
+    }
+
     if args.list_panels {
         panel_registry::list_panels();
         return Ok(());
     }
 
-    if args.list_monitors {
-        if let Err(err) = monitor::list_monitors() {
-            exit_with_error(err);
+    if args.generate_man || args.generate_completions.is_some() {
+        print_cli_docs(args.generate_completions);
+        return Ok(());
+    }
+
+    if let Some(dir) = args.snapshot {
+        let dir = std::path::absolute(&dir).unwrap_or(dir);
+        match ipc::send_to_all(&ipc::IpcCommand::Snapshot { dir }) {
+            Ok(replies) => {
+                for (socket, reply) in replies {
+                    println!("{}: {reply}", socket.display());
+                }
+            }
+            Err(err) => exit_with_error(err),
         }
         return Ok(());
     }
 
-    let monitor_name = monitor::normalize_monitor_selection(args.on_monitor.as_deref())
-        .unwrap_or_else(|err| exit_with_error(err));
+    if let Some(action) = args.lock {
+        match ipc::send_to_all(&ipc::IpcCommand::Lock(action)) {
+            Ok(replies) => {
+                for (socket, reply) in replies {
+                    println!("{}: {reply}", socket.display());
+                }
+            }
+            Err(err) => exit_with_error(err),
+        }
+        return Ok(());
+    }
+
+    if args.overview {
+        match ipc::send_to_all(&ipc::IpcCommand::Overview) {
+            Ok(replies) => {
+                for (socket, reply) in replies {
+                    println!("{}: {reply}", socket.display());
+                }
+            }
+            Err(err) => exit_with_error(err),
+        }
+        return Ok(());
+    }
+
+    if let Some(topics) = args.events {
+        // `--events all` (or an empty list) streams every topic.
+        let topics = if topics.trim() == "all" {
+            Ok(Vec::new())
+        } else {
+            ipc::parse_topics(&topics)
+        };
+        if let Err(err) = topics.and_then(ipc::print_events) {
+            exit_with_error(err);
+        }
+        return Ok(());
+    }
 
     if let Err(err) = ensure_layershell_environment() {
         exit_with_error(err);
     }
 
+    if args.replace
+        && let Err(err) = ipc::replace_running()
+    {
+        exit_with_error(err);
+    }
+
     let default_gauges = gauge_registry::default_gauges();
     let default_panels = panel_registry::default_panels();
     let base_setting_specs = settings::base_setting_specs(
@@ -277,9 +471,16 @@ fn main() -> Result<(), iced_layershell::Error> {
     let panel_setting_specs = panel_registry::collect_settings(&base_setting_specs);
     let all_setting_specs = gauge_registry::collect_settings(&panel_setting_specs);
     settings_store.ensure_defaults(&all_setting_specs);
+    // The monitor name is checked against the compositor's outputs further down.
+    if let Some(output) = args.on_monitor.as_deref().map(str::trim) {
+        let count = settings_store.apply_output_overrides(output);
+        if count > 0 {
+            info!("Applied {count} settings for output {output}");
+        }
+    }
 
     let gauges_setting = settings_store.get_or("grelier.gauges", default_gauges);
-    let gauges: Vec<String> = gauges_setting
+    let mut gauges: Vec<String> = gauges_setting
         .split(',')
         .map(str::trim)
         .filter(|s| !s.is_empty())
@@ -292,12 +493,109 @@ fn main() -> Result<(), iced_layershell::Error> {
         return Ok(());
     }
 
+    if args.doctor {
+        println!("Gauges:");
+        let disabled = gauge_diagnostics::doctor(&gauges);
+        println!("\nDependencies:");
+        let missing = gauge_dependencies::doctor(&gauges);
+        if missing > 0 {
+            exit_with_error(format!(
+                "Enabled gauges are missing {missing} optional dependencies"
+            ));
+        }
+        if disabled > 0 {
+            exit_with_error(format!(
+                "{disabled} gauges were disabled for running slowly"
+            ));
+        }
+        return Ok(());
+    }
+
+    if args.dmenu {
+        let items = dmenu::read_items(std::io::stdin().lock())
+            .unwrap_or_else(|err| exit_with_error(format!("Cannot read items from stdin: {err}")));
+        if let Err(err) = style::reload() {
+            warn!("Ignoring style file: {err}");
+        }
+        let title = args.prompt.unwrap_or_else(|| "Select".to_string());
+        match dmenu::run(
+            title,
+            items.clone(),
+            Vec::new(),
+            configured_theme(settings_store),
+        ) {
+            Ok(Some(dmenu::Choice::Item(index))) => println!("{}", items[index]),
+            Ok(Some(dmenu::Choice::Typed(text))) => println!("{text}"),
+            // dmenu exits with an error status when nothing was chosen.
+            Ok(None) => std::process::exit(1),
+            Err(err) => exit_with_error(err),
+        }
+        return Ok(());
+    }
+
+    if let Err(err) = compositor::init(&settings_store.get_or("grelier.compositor", "auto")) {
+        exit_with_error(err);
+    }
+
+    if args.list_monitors {
+        if let Err(err) = monitor::list_monitors() {
+            exit_with_error(err);
+        }
+        return Ok(());
+    }
+
+    compositor::wait_until_ready();
+
+    if args.launcher {
+        if let Err(err) = style::reload() {
+            warn!("Ignoring style file: {err}");
+        }
+        let title = args.prompt.unwrap_or_else(|| "Launch".to_string());
+        match launcher::run(title, configured_theme(settings_store)) {
+            Ok(true) => return Ok(()),
+            Ok(false) => std::process::exit(1),
+            Err(err) => exit_with_error(err),
+        }
+    }
+
+    let session = session_kind::init(
+        settings_store,
+        &monitor::snapshot_outputs().unwrap_or_default(),
+    )
+    .unwrap_or_else(|err| exit_with_error(err));
+    if session != session_kind::SessionKind::Local {
+        info!("Running in a {} session", session.name());
+        gauges.retain(|id| {
+            let skip = session.skips(id);
+            if skip {
+                info!(
+                    "Skipping gauge '{id}': not available in a {} session",
+                    session.name()
+                );
+            }
+            !skip
+        });
+    }
+
+    let missing_hardware = gauge_hardware::missing(&gauges);
+    for id in &missing_hardware {
+        if let Some(hardware) = gauge_hardware::requirement(id) {
+            info!("Hiding gauge '{id}': no {} found", hardware.name());
+        }
+    }
+
+    let monitor_name = monitor::normalize_monitor_selection(args.on_monitor.as_deref())
+        .unwrap_or_else(|err| exit_with_error(err));
+
     if let Err(err) = gauge_registry::validate_settings(settings_store) {
         exit_with_error(err);
     }
     if let Err(err) = panel_registry::validate_settings(settings_store) {
         exit_with_error(err);
     }
+    if let Err(err) = units::validate_settings(settings_store) {
+        exit_with_error(err);
+    }
 
     let mut known_settings = std::collections::HashSet::new();
     for spec in &all_setting_specs {
@@ -315,11 +613,28 @@ fn main() -> Result<(), iced_layershell::Error> {
             exit_with_error(err);
         });
 
-    let anchor = match orientation_setting {
-        Orientation::Left => Anchor::Left,
-        Orientation::Right => Anchor::Right,
-    };
+    if let Err(err) = settings_store
+        .get_or(bar::ALIGN_KEY, "space-between")
+        .parse::<bar::BarAlign>()
+    {
+        exit_with_error(err);
+    }
+    if let Err(err) = settings_store
+        .get_or(bar::SCALE_KEY, "auto")
+        .parse::<bar::PixelScale>()
+    {
+        exit_with_error(err);
+    }
 
+    let bar_margin = bar::BarMargin::from_settings(settings_store);
+    let anchor = bar_margin.anchor(orientation_setting);
+    let autohide = autohide::Autohide::from_settings(
+        settings_store,
+        bar_width,
+        orientation_setting == Orientation::Left,
+    );
+
+    let target_monitor = monitor_name.clone();
     let start_mode = match monitor_name {
         Some(name) => StartMode::TargetScreen(name),
         None => StartMode::AllScreens,
@@ -328,10 +643,10 @@ fn main() -> Result<(), iced_layershell::Error> {
     let settings = LayerShellAppSettings {
         layer_settings: LayerShellSettings {
             size: Some((bar_width, 0)),
-            exclusive_zone: bar_width as i32,
+            exclusive_zone: autohide.map_or(bar_width as i32, |autohide| autohide.exclusive_zone()),
             anchor,
             layer: Layer::Top,
-            margin: (0, 0, 0, 0),
+            margin: bar_margin.layer_margin(autohide.as_ref()),
             keyboard_interactivity: KeyboardInteractivity::OnDemand,
             start_mode,
             events_transparent: false,
@@ -341,23 +656,10 @@ fn main() -> Result<(), iced_layershell::Error> {
         ..LayerShellAppSettings::default()
     };
 
-    let theme = match settings_store.get("grelier.bar.theme") {
-        Some(name) if theme::is_custom_theme_name(&name) => {
-            theme::custom_theme_from_settings(settings_store).unwrap_or_else(|err| {
-                exit_with_error(err);
-            })
-        }
-        Some(name) => match theme::parse_theme(&name) {
-            Some(theme) => theme,
-            None => {
-                exit_with_error(format!(
-                    "Unknown theme '{name}'. Valid themes: {}",
-                    theme::VALID_THEME_NAMES.join(", ")
-                ));
-            }
-        },
-        None => theme::DEFAULT_THEME,
-    };
+    let theme = configured_theme(settings_store);
+    if let Err(err) = style::reload() {
+        warn!("Ignoring style file: {err}");
+    }
 
     let gauge_order = gauges;
     let gauges_for_subscription = gauge_order.clone();
@@ -365,21 +667,23 @@ fn main() -> Result<(), iced_layershell::Error> {
     let panel_bootstrap = panel_registry::bootstrap_for_setting(&panels_setting, settings_store);
     let workspace_app_icons = panel_bootstrap.workspace_app_icons;
     let top_apps_count = panel_bootstrap.top_apps_count;
+    let top_apps_pool = top_apps_panel::pool_size(top_apps_count);
 
-    let theme_for_state = theme.clone();
+    a11y::start();
+    ipc::remove_socket_on_termination();
     let run_result = daemon(
         move || {
             let mut icon_cache = Cache::new(apps::load_desktop_apps);
             let (mut apps, app_icons, top_apps) = apps::load_cached_apps_from_cache(
                 &mut icon_cache,
-                top_apps_count,
+                top_apps_pool,
                 workspace_app_icons,
             );
             let refresh_task = if workspace_app_icons || top_apps_count > 0 {
                 Task::perform(
                     async move {
                         let top_apps = icon_cache
-                            .refresh_with_top(&mut apps, top_apps_count)
+                            .refresh_with_top(&mut apps, top_apps_pool)
                             .map_err(|err| err.to_string())?;
                         Ok((apps, top_apps))
                     },
@@ -393,22 +697,50 @@ fn main() -> Result<(), iced_layershell::Error> {
                     let mut state = BarState::with_gauge_order_and_icons(
                         gauge_order.clone(),
                         app_icons,
-                        top_apps,
+                        Vec::new(),
+                    );
+                    top_apps_panel::set_top_apps(&mut state, top_apps);
+                    state.bar_theme = theme.clone();
+                    panels::gauge_panel::rebuild_themed_icons(&state);
+                    state.locked = settings::settings().get_bool_or(bar::LOCKED_KEY, false);
+                    state.target_monitor = target_monitor.clone();
+                    state.autohide = autohide;
+                    let timeout = settings::settings()
+                        .get_parsed_or(gauge_loading::LOADING_TIMEOUT_KEY, 15u64);
+                    state.missing_hardware = missing_hardware.clone();
+                    let (loading, placeholders) = gauge_loading::GaugeLoading::start(
+                        gauge_order
+                            .iter()
+                            .filter(|id| !missing_hardware.contains(*id)),
+                        Instant::now(),
+                        Duration::from_secs(timeout),
                     );
-                    state.bar_theme = theme_for_state.clone();
+                    state.gauges = placeholders;
+                    state.gauge_loading = loading;
                     state
                 },
-                refresh_task,
+                Task::batch([
+                    refresh_task,
+                    bar::delayed_task(gauge_loading::PULSE_INTERVAL, Message::GaugeLoadingTick),
+                ]),
             )
         },
         BarState::namespace,
         update,
         BarState::view,
     )
-    .theme(theme)
+    .theme(|state: &BarState, _| state.bar_theme.clone())
+    .style(|_: &BarState, theme: &iced::Theme| {
+        let mut style = iced::theme::Base::base(theme);
+        if bar::corner_radius() > 0.0 {
+            style.background_color = iced::Color::TRANSPARENT;
+        }
+        style
+    })
     .subscription(move |state| app_subscription(state, &gauges_for_subscription))
     .settings(settings)
     .run();
+    ipc::remove_socket();
 
     match &run_result {
         Ok(()) => info!("Exiting normally after bar run completed."),
@@ -417,7 +749,7 @@ fn main() -> Result<(), iced_layershell::Error> {
     run_result
 }
 
-fn app_subscription(_state: &BarState, gauges: &[String]) -> Subscription<Message> {
+fn app_subscription(state: &BarState, gauges: &[String]) -> Subscription<Message> {
     let default_panels = panel_registry::default_panels();
     let panels_setting = settings::settings().get_or("grelier.panels", default_panels);
     let mut subs = vec![
@@ -425,25 +757,123 @@ fn app_subscription(_state: &BarState, gauges: &[String]) -> Subscription<Messag
         window::open_events().map(Message::WindowOpened),
         window::events().map(|(id, event)| Message::WindowEvent(id, event)),
         window::close_events().map(Message::WindowClosed),
+        ipc::subscription(),
+        dialog::toast::subscription(),
+        dialog::prompt::subscription(),
+        dialog::menu::subscription(),
+        style::subscription(),
     ];
+    if state
+        .autohide
+        .as_ref()
+        .is_some_and(autohide::Autohide::is_animating)
+    {
+        subs.push(iced::time::every(autohide::FRAME_INTERVAL).map(|_| Message::AutohideFrame));
+    }
+    if session_kind::kind().owns_hardware() {
+        subs.push(lid::subscription());
+        if gauge_hardware::watches(gauges) {
+            subs.push(gauge_hardware::subscription());
+        }
+    }
+    if theme::is_pywal_theme_name(&settings::settings().get_or(theme::THEME_KEY, "")) {
+        subs.push(theme::pywal_subscription());
+    }
+    // Changing the gauge set restarts the gauge worker with the new one.
+    let present: Vec<String> = gauges
+        .iter()
+        .filter(|id| !state.missing_hardware.contains(*id))
+        .cloned()
+        .collect();
     subs.extend(panel_registry::subscriptions_for_setting(
         &panels_setting,
-        gauges,
+        &present,
+        state.subscription_generation,
     ));
     Subscription::batch(subs)
 }
 
+/// Dispatch the action configured for `input` on workspace `name`.
+fn run_workspace_action(
+    state: &mut BarState,
+    name: String,
+    input: panels::ws_panel::WorkspaceInput,
+) -> Task<Message> {
+    use panels::ws_panel::WorkspaceAction;
+    let result = match panels::ws_panel::action_for(input) {
+        WorkspaceAction::None => return Task::none(),
+        WorkspaceAction::Focus => compositor::backend().focus_workspace(&name),
+        WorkspaceAction::MoveFocusedHere => compositor::backend().move_focused_to_workspace(&name),
+        WorkspaceAction::Rename => {
+            let old_name = name.clone();
+            let dialog = dialog::prompt::PromptDialog {
+                title: "Rename Workspace".to_string(),
+                value: name,
+                placeholder: "Workspace name".to_string(),
+                secret: false,
+                editor: None,
+                on_submit: Some(Arc::new(move |new_name: String| {
+                    if let Err(err) = compositor::backend().rename_workspace(&old_name, &new_name) {
+                        error!(
+                            "Failed to rename workspace \"{old_name}\" to \"{new_name}\": {err}"
+                        );
+                    }
+                })),
+            };
+            return state.open_prompt_dialog("workspaces", dialog, None);
+        }
+    };
+    if let Err(err) = result {
+        error!("Workspace action on \"{name}\" failed: {err}");
+    }
+    Task::none()
+}
+
 fn update(state: &mut BarState, message: Message) -> Task<Message> {
+    // Workspace app icons and resizes move elements without changing any accessible label.
+    let relayout = matches!(
+        message,
+        Message::Workspaces { .. } | Message::WindowEvent(_, iced::window::Event::Resized(_))
+    );
+    let task = handle_message(state, message);
+    // Dialogs open and close from many messages, so the tree is republished after each one; it
+    // only reaches the screen reader when something changed.
+    let changed = a11y::publish_elements(&state.workspaces, &state.gauges, &state.dialog_windows);
+    if (changed || relayout) && a11y::is_active() {
+        return Task::batch([task, measure_a11y_bounds()]);
+    }
+    task
+}
+
+/// Measure where the bar's accessible elements sit once the next layout is done.
+fn measure_a11y_bounds() -> Task<Message> {
+    a11y::measure_bounds().map(Message::A11yBoundsMeasured)
+}
+
+fn handle_message(state: &mut BarState, message: Message) -> Task<Message> {
     let is_click_message = matches!(
         message,
         Message::WorkspaceClicked(_)
+            | Message::WorkspaceScrolled { .. }
+            | Message::WorkspaceInput { .. }
             | Message::WorkspaceAppClicked { .. }
             | Message::TopAppClicked { .. }
+            | Message::TopAppsMenuRequested
+            | Message::TaskbarCloseRequested(_)
+            | Message::TaskbarMenuRequested { .. }
+            | Message::ThemeMenuRequested
+            | Message::OverviewToggled
             | Message::BackgroundClicked
             | Message::GaugeClicked { .. }
             | Message::MenuItemSelected { .. }
             | Message::ActionItemSelected { .. }
+            | Message::BannerRetry
+            | Message::BannerDetails
     );
+    // A locked bar only listens for the unlock long-press on its background.
+    if is_click_message && state.locked && !matches!(message, Message::BackgroundClicked) {
+        return Task::none();
+    }
     if is_click_message && !state.allow_click() {
         return Task::none();
     }
@@ -452,24 +882,192 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
         Message::Workspaces { workspaces, apps } => {
             panels::ws_panel::update_workspace_focus(state, &workspaces);
             state.workspaces = workspaces;
+            state.degraded.remove(compositor::DEGRADED_SOURCE);
             state.workspace_apps = apps
                 .into_iter()
                 .map(|entry| (entry.name, entry.apps))
                 .collect();
+            top_apps_panel::settle_launches(state);
+            let settings = settings::settings();
+            if settings.get_bool_or(panels::ws_panel::AUTO_NAME_KEY, false) {
+                // Wait for window churn to settle before renaming.
+                state.auto_name_generation = state.auto_name_generation.wrapping_add(1);
+                let delay = settings.get_parsed_or(panels::ws_panel::AUTO_NAME_DELAY_KEY, 1000u64);
+                return bar::delayed_task(
+                    Duration::from_millis(delay),
+                    Message::WorkspaceAutoName(state.auto_name_generation),
+                );
+            }
+        }
+        Message::FocusedWindow(window) => {
+            state.focused_window = window;
+            state.focused_title_offset = 0;
+        }
+        Message::FocusedWindowTick => {
+            state.focused_title_offset = state.focused_title_offset.wrapping_add(1);
+        }
+        Message::GaugeLoadingTick => {
+            for id in state.gauge_loading.tick(Instant::now()) {
+                if let Some(gauge) = state.gauges.iter_mut().find(|gauge| gauge.id == id) {
+                    gauge.display = GaugeDisplay::Error;
+                }
+            }
+            if !state.gauge_loading.is_done() {
+                return bar::delayed_task(gauge_loading::PULSE_INTERVAL, Message::GaugeLoadingTick);
+            }
+        }
+        Message::WorkspaceAutoName(generation) => {
+            if generation != state.auto_name_generation {
+                return Task::none();
+            }
+            let blocklist: Vec<String> = settings::settings()
+                .get_or(panels::ws_panel::AUTO_NAME_BLOCKLIST_KEY, "")
+                .split(',')
+                .map(str::trim)
+                .filter(|app_id| !app_id.is_empty())
+                .map(ToString::to_string)
+                .collect();
+            let renames = panels::ws_panel::auto_name_renames(
+                &state.workspaces,
+                &state.workspace_apps,
+                &blocklist,
+            );
+            for (name, new_name) in renames {
+                if let Err(err) = compositor::backend().rename_workspace(&name, &new_name) {
+                    warn!("Failed to rename workspace \"{name}\" to \"{new_name}\": {err}");
+                    break;
+                }
+            }
+        }
+        Message::Degraded { source, message } => {
+            state.degraded.insert(source, message);
+        }
+        Message::BannerRetry => {
+            info!("Retrying degraded sources: {:?}", state.degraded.keys());
+            state.degraded.clear();
+            state.subscription_generation = state.subscription_generation.wrapping_add(1);
+            return state.close_dialogs();
+        }
+        Message::BannerDetails => {
+            if !state.dialog_windows.is_empty() {
+                return state.close_dialogs();
+            }
+            let dialog = state.degraded_info_dialog();
+            return state.open_info_dialog("banner", dialog, Some(0));
         }
         Message::WorkspaceClicked(name) => {
             if !state.dialog_windows.is_empty() {
                 return state.close_dialogs();
             }
-            if let Err(err) = sway_workspace::focus_workspace(&name) {
+            if let Err(err) = compositor::backend().focus_workspace(&name) {
+                error!("Failed to focus workspace \"{name}\": {err}");
+            }
+        }
+        Message::WorkspacePressed(name) => {
+            if !state.locked {
+                state.workspace_drag = Some(bar::WorkspaceDrag {
+                    source: name,
+                    target: None,
+                });
+            }
+        }
+        Message::HoverEnter(target) => {
+            if let (HoverTarget::Workspace(name), Some(drag)) =
+                (&target, state.workspace_drag.as_mut())
+            {
+                drag.target = (&drag.source != name).then(|| name.clone());
+            }
+            state.hovered = Some(target);
+        }
+        Message::HoverExit(target) => {
+            // Enter for the next item can arrive before exit for the previous one.
+            if state.hovered.as_ref() == Some(&target) {
+                state.hovered = None;
+            }
+        }
+        Message::WorkspaceReleased(name) => match state.workspace_drag.take() {
+            Some(drag) if drag.source != name => {
+                if let Err(err) = compositor::backend().move_workspace(&drag.source, &name) {
+                    error!(
+                        "Failed to move workspace \"{}\" to \"{name}\": {err}",
+                        drag.source
+                    );
+                }
+            }
+            _ => {
+                if state.locked {
+                    return Task::none();
+                }
+                // The second release of a double click would be dropped by the click filter,
+                // so it is recognized here instead.
+                let now = Instant::now();
+                let window = Duration::from_millis(
+                    settings::settings()
+                        .get_parsed_or(panels::ws_panel::DOUBLE_CLICK_MS_KEY, 400u64),
+                );
+                let double_click = panels::ws_panel::is_double_click(
+                    state.last_workspace_release.as_ref(),
+                    &name,
+                    now,
+                    window,
+                );
+                let input = panels::ws_panel::WorkspaceInput::Double;
+                if double_click
+                    && panels::ws_panel::action_for(input)
+                        != panels::ws_panel::WorkspaceAction::None
+                {
+                    state.last_workspace_release = None;
+                    return run_workspace_action(state, name, input);
+                }
+                state.last_workspace_release = Some((name.clone(), now));
+                return Task::done(Message::WorkspaceClicked(name));
+            }
+        },
+        Message::WorkspaceHistoryCancelled => {
+            state.workspace_history_preview = None;
+        }
+        Message::WorkspaceInput { name, input } => {
+            return run_workspace_action(state, name, input);
+        }
+        Message::WorkspaceScrolled { next } => {
+            if panels::ws_panel::history_modifier().held(state.modifiers) {
+                state.workspace_history_preview = panels::ws_panel::history_step(
+                    state.workspace_history.len(),
+                    state.workspace_history_preview,
+                    next,
+                );
+                return Task::none();
+            }
+            let wrap = settings::settings().get_bool_or(panels::ws_panel::SCROLL_WRAP_KEY, true);
+            if let Some(name) = panels::ws_panel::scroll_target(&state.workspaces, next, wrap)
+                && let Err(err) = compositor::backend().focus_workspace(name)
+            {
+                error!("Failed to focus workspace \"{name}\": {err}");
+            }
+        }
+        Message::OverviewToggled => {
+            return state.toggle_overview();
+        }
+        Message::OverviewClosed => {
+            return state.close_overview();
+        }
+        Message::OverviewWorkspaceSelected(name) => {
+            if let Err(err) = compositor::backend().focus_workspace(&name) {
                 error!("Failed to focus workspace \"{name}\": {err}");
             }
+            return state.close_overview();
+        }
+        Message::OverviewWindowSelected(con_id) => {
+            if let Err(err) = compositor::backend().focus_con_id(con_id) {
+                error!("Failed to focus window (con_id {con_id}): {err}");
+            }
+            return state.close_overview();
         }
         Message::WorkspaceAppClicked { con_id, app_id } => {
             if !state.dialog_windows.is_empty() {
                 return state.close_dialogs();
             }
-            if let Err(err) = sway_workspace::focus_con_id(con_id) {
+            if let Err(err) = compositor::backend().focus_con_id(con_id) {
                 error!("Failed to focus app \"{app_id}\" (con_id {con_id}): {err}");
             }
         }
@@ -477,45 +1075,279 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
             if !state.dialog_windows.is_empty() {
                 return state.close_dialogs();
             }
-            if let Err(err) = sway_workspace::launch_app(&app_id) {
+            if let Err(err) = compositor::backend().launch_app(&app_id) {
                 error!("Failed to launch app \"{app_id}\": {err}");
                 return Task::none();
             }
+            let busy = top_apps_panel::begin_launch(state, &app_id);
             if let Some(app) = state.top_apps.iter().find(|app| app.appid == app_id) {
                 let mut cache = Cache::new(apps::load_desktop_apps);
                 if let Err(err) = cache.record_launch(app) {
                     error!("Failed to update app cache for \"{app_id}\": {err}");
                 }
-                let top_apps_count =
-                    settings::settings().get_parsed_or("grelier.app.top_apps.count", 6usize);
-                state.top_apps = cache.top_apps(top_apps_count).unwrap_or_default();
+                top_apps_panel::reload_top_apps(state, &mut cache);
+            }
+            return busy;
+        }
+        Message::TopAppLaunchTimeout { app_id, started } => {
+            top_apps_panel::expire_launch(state, &app_id, started);
+        }
+        Message::TopAppsMenuRequested => {
+            if !state.dialog_windows.is_empty() {
+                return state.close_dialogs();
+            }
+            let menu = top_apps_panel::category_menu(state);
+            let anchor_y = state.last_cursor.map(|p| p.y.round() as i32);
+            return state.open_menu(top_apps_panel::MENU_ID, menu, anchor_y);
+        }
+        Message::TaskbarCloseRequested(con_id) => {
+            if !state.dialog_windows.is_empty() {
+                return state.close_dialogs();
+            }
+            if let Err(err) = compositor::backend().close_con_id(con_id) {
+                error!("Failed to close window (con_id {con_id}): {err}");
+            }
+        }
+        Message::TaskbarMenuRequested { con_id, app_id } => {
+            if !state.dialog_windows.is_empty() {
+                return state.close_dialogs();
+            }
+            let menu = taskbar_panel::move_menu(&state.workspaces, &app_id);
+            if menu.items.is_empty() {
+                return Task::none();
+            }
+            state.taskbar_menu_con = Some(con_id);
+            let anchor_y = state.last_cursor.map(|p| p.y.round() as i32);
+            return state.open_menu(taskbar_panel::MENU_ID, menu, anchor_y);
+        }
+        Message::TaskbarMoveSelected(workspace) => {
+            if let Some(con_id) = state.taskbar_menu_con.take()
+                && let Err(err) = compositor::backend().move_con_to_workspace(con_id, &workspace)
+            {
+                error!("Failed to move window (con_id {con_id}) to \"{workspace}\": {err}");
+            }
+        }
+        Message::TopAppsCategorySelected(category) => {
+            top_apps_panel::select_category(state, &category);
+        }
+        Message::ThemeMenuRequested => {
+            if !state.dialog_windows.is_empty() {
+                return state.close_dialogs();
+            }
+            let current = settings::settings().get_or(theme::THEME_KEY, "");
+            let mut menu = theme::theme_menu(&current);
+            menu.items.push(gauge_diagnostics::menu_item());
+            let anchor_y = state.last_cursor.map(|p| p.y.round() as i32);
+            return state.open_menu(theme::MENU_ID, menu, anchor_y);
+        }
+        Message::GaugeDiagnosticsRequested => {
+            let dialog = gauge_diagnostics::info_dialog();
+            return state.open_info_dialog(gauge_diagnostics::MENU_ITEM, dialog, Some(0));
+        }
+        Message::ThemeSelected(name) => {
+            let settings = settings::settings();
+            match theme::theme_for_name(&name, settings) {
+                Ok(theme) => {
+                    info!("Switching theme to {name}");
+                    state.bar_theme = theme;
+                    panels::gauge_panel::rebuild_themed_icons(state);
+                    settings.update(theme::THEME_KEY, &name);
+                }
+                Err(err) => warn!("Cannot switch theme: {err}"),
+            }
+        }
+        Message::ThemeFileChanged => {
+            let path = theme::pywal_colors_path(settings::settings());
+            match theme::pywal_theme(&path) {
+                Ok(theme) => {
+                    info!("Reloaded pywal colors from {}", path.display());
+                    state.bar_theme = theme;
+                    panels::gauge_panel::rebuild_themed_icons(state);
+                }
+                Err(err) => warn!("Cannot reload pywal colors: {err}"),
+            }
+        }
+        Message::StyleFileChanged => match style::reload() {
+            Ok(()) => info!("Reloaded {}", style::style_path().display()),
+            Err(err) => warn!("Cannot reload style file: {err}"),
+        },
+        Message::Toast(toast) => return state.open_toast(toast),
+        Message::PromptRequested { gauge_id, dialog } => {
+            let anchor_y = state
+                .gauge_dialog_anchor
+                .get(&gauge_id)
+                .copied()
+                .or_else(|| panels::gauge_panel::anchor_y(state));
+            return state.open_prompt_dialog(&gauge_id, dialog, anchor_y);
+        }
+        Message::MenuRequested { gauge_id, menu } => {
+            return state.open_menu(&gauge_id, menu, None);
+        }
+        Message::ToastExpired(window) => {
+            if state.toast_windows.remove(&window).is_some() {
+                state.windows.mark_closing([window]);
+                return close_window_task(window);
+            }
+        }
+        Message::IcedEvent(iced::Event::Mouse(
+            event @ (mouse::Event::ButtonReleased(mouse::Button::Left) | mouse::Event::CursorLeft),
+        )) => {
+            // Released off any workspace or left the bar: cancel the drag.
+            state.workspace_drag = None;
+            if event == mouse::Event::CursorLeft {
+                state.hovered = None;
+                if let Some(autohide) = &mut state.autohide {
+                    autohide.hide_generation = autohide.hide_generation.wrapping_add(1);
+                    return bar::delayed_task(
+                        autohide.hide_delay,
+                        Message::AutohideHide(autohide.hide_generation),
+                    );
+                }
             }
         }
         Message::IcedEvent(iced::Event::Mouse(mouse::Event::CursorMoved { position })) => {
             state.last_cursor = Some(position);
+            if let Some(autohide) = &mut state.autohide
+                && autohide.reveal()
+            {
+                return Task::done(Message::AutohideFrame);
+            }
+        }
+        Message::AutohideHide(generation) => {
+            if let Some(autohide) = &mut state.autohide
+                && autohide.hide_generation == generation
+            {
+                // Stay out while a dialog is open; check again after another delay.
+                if !state.dialog_windows.is_empty() {
+                    return bar::delayed_task(
+                        autohide.hide_delay,
+                        Message::AutohideHide(generation),
+                    );
+                }
+                if autohide.hide() {
+                    return Task::done(Message::AutohideFrame);
+                }
+            }
+        }
+        Message::AutohideFrame => {
+            let Some(autohide) = &mut state.autohide else {
+                return Task::none();
+            };
+            // Later frames come from the frame subscription while the slide is animating.
+            if bar::animations_enabled() {
+                autohide.step();
+            } else {
+                autohide.finish();
+            }
+            let margin =
+                bar::BarMargin::from_settings(settings::settings()).layer_margin(Some(autohide));
+            return Task::batch(
+                state
+                    .windows
+                    .bars()
+                    .map(|id| Task::done(Message::MarginChange { id, margin })),
+            );
         }
         Message::BackgroundClicked => {
+            state.background_pressed_at = Some(Instant::now());
             if !state.dialog_windows.is_empty() {
                 return state.close_dialogs();
             }
         }
+        Message::BackgroundReleased => {
+            state.workspace_drag = None;
+            let long_press = Duration::from_millis(
+                settings::settings().get_parsed_or(bar::LOCK_LONG_PRESS_KEY, 1500u64),
+            );
+            if state.finish_background_press(Instant::now(), long_press) {
+                let locked = !state.locked;
+                info!(
+                    "Bar {} by long-press",
+                    if locked { "locked" } else { "unlocked" }
+                );
+                return state.set_locked(locked);
+            }
+        }
+        Message::IcedEvent(iced::Event::Keyboard(iced::keyboard::Event::ModifiersChanged(
+            modifiers,
+        ))) => {
+            state.modifiers = modifiers;
+            if !panels::ws_panel::history_modifier().held(modifiers)
+                && let Some(name) = panels::ws_panel::commit_history_preview(state)
+                && let Err(err) = compositor::backend().focus_workspace(&name)
+            {
+                error!("Failed to focus workspace \"{name}\": {err}");
+            }
+        }
         Message::IcedEvent(iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
             key: iced::keyboard::Key::Named(iced::keyboard::key::Named::Escape),
             ..
         })) => {
+            if state.overview.is_some() {
+                return state.close_overview();
+            }
             if !state.dialog_windows.is_empty() {
                 return state.close_dialogs();
             }
         }
         Message::GaugeBatch(batch) => {
-            apply_gauge_batch(&mut state.gauges, &mut state.dialog_windows, batch);
+            for update in &batch {
+                state.gauge_loading.arrived(update.model.id);
+            }
+            apply_gauge_batch(
+                &mut state.gauges,
+                [&mut state.dialog_windows, &mut state.pinned_dialogs],
+                batch,
+            );
         }
-        Message::GaugeClicked { id, input } => {
+        Message::GaugeClicked { id, mut input } => {
             // If any dialog is open, any click just dismisses it.
             if !state.dialog_windows.is_empty() {
                 return state.close_dialogs();
             }
 
+            // Scrolling with the tune modifier held steps the gauge's interval instead.
+            if matches!(input, GaugeInput::ScrollUp | GaugeInput::ScrollDown)
+                && gauge_tuning::tune_modifier().held(state.modifiers)
+                && let Some((tunable, value)) =
+                    gauge_tuning::tune(&id, input == GaugeInput::ScrollUp)
+            {
+                return state.open_toast(
+                    Toast::new(tunable.label, format!("{value} s"))
+                        .with_timeout(TUNE_TOAST_TIMEOUT),
+                );
+            }
+
+            // Shift-click pins the info dialog, or unpins it.
+            if input == GaugeInput::Button(mouse::Button::Left) && state.modifiers.shift() {
+                let info = state
+                    .gauges
+                    .iter()
+                    .find(|g| g.id == id)
+                    .and_then(|gauge| gauge.interactions.left_click.info.clone());
+                let anchor_y = state
+                    .gauge_dialog_anchor
+                    .get(&id)
+                    .copied()
+                    .or_else(|| panels::gauge_panel::anchor_y(state));
+                return state.toggle_pinned_info(&id, info, anchor_y);
+            }
+
+            // User bindings take precedence over the gauge's own handling.
+            match gauge_bindings::binding_for(&id, input) {
+                Some(GaugeBinding::Exec(command)) => {
+                    gauge_bindings::spawn_command(&command);
+                    return Task::none();
+                }
+                Some(GaugeBinding::Volume(delta)) => {
+                    panels::gauges::audio_out::adjust_default_sink(delta);
+                    return Task::none();
+                }
+                Some(GaugeBinding::Disabled) => return Task::none(),
+                Some(GaugeBinding::Input(remapped)) => input = remapped,
+                None => {}
+            }
+
             let interaction = state
                 .gauges
                 .iter()
@@ -548,8 +1380,21 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
                 return state.open_action_dialog(&id, dialog, anchor_y);
             }
 
+            let mut menu = interaction.menu;
+            if matches!(input, GaugeInput::Button(iced::mouse::Button::Right)) {
+                let user_items = gauge_bindings::user_menu_items(&id, &settings::settings().all());
+                if !user_items.is_empty() {
+                    let menu = menu.get_or_insert_with(|| GaugeMenu {
+                        title: id.clone(),
+                        items: Vec::new(),
+                        on_select: None,
+                        slider: None,
+                    });
+                    menu.items.extend(user_items);
+                }
+            }
             if matches!(input, GaugeInput::Button(iced::mouse::Button::Right))
-                && let Some(menu) = interaction.menu
+                && let Some(menu) = menu
             {
                 let anchor_y = state
                     .gauge_dialog_anchor
@@ -559,8 +1404,10 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
                 return state.open_menu(&id, menu, anchor_y);
             }
 
-            if matches!(input, GaugeInput::Button(iced::mouse::Button::Left))
-                && let Some(dialog) = interaction.info
+            if matches!(
+                input,
+                GaugeInput::Button(iced::mouse::Button::Left | iced::mouse::Button::Middle)
+            ) && let Some(dialog) = interaction.info
             {
                 let anchor_y = state
                     .gauge_dialog_anchor
@@ -577,22 +1424,101 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
             }
         }
         Message::Noop => {}
+        Message::Ipc { command, reply } => match command {
+            ipc::IpcCommand::Snapshot { dir } => {
+                return snapshot::snapshot_task(state, dir, reply);
+            }
+            ipc::IpcCommand::Lock(action) => {
+                let task = state.set_locked(action.apply(state.locked));
+                let _ = reply
+                    .send(serde_json::json!({ "ok": true, "locked": state.locked }).to_string());
+                return task;
+            }
+            ipc::IpcCommand::Overview => {
+                let _ = reply.send(serde_json::json!({ "ok": true }).to_string());
+                return state.toggle_overview();
+            }
+            ipc::IpcCommand::GaugeStats => {
+                let _ = reply.send(gauge_diagnostics::stats_json().to_string());
+            }
+            ipc::IpcCommand::Quit => {
+                info!("Exiting on request from another instance.");
+                // Reply only once the surfaces are gone, so the new instance never overlaps them.
+                state.quit_reply = Some(reply);
+                let bar_windows = state.windows.close_bars(false);
+                let tasks: Vec<Task<Message>> =
+                    [state.close_dialogs(), state.close_pinned_dialogs()]
+                        .into_iter()
+                        .chain(bar_windows.into_iter().map(close_window_task))
+                        .collect();
+                if state.windows.all_closed() {
+                    return finish_quit(state);
+                }
+                return Task::batch(tasks);
+            }
+            // Subscriptions are served on the socket thread and never reach the bar.
+            ipc::IpcCommand::Subscribe { .. } => {}
+        },
         Message::MenuItemSelected {
             window,
             gauge_id,
             item_id,
         } => {
+            let (exec, dialog_on_select) =
+                match state.dialog_windows.get(&window).map(|d| &d.dialog) {
+                    Some(GaugeDialog::Menu(menu)) => (
+                        menu.items
+                            .iter()
+                            .find(|item| item.id == item_id)
+                            .and_then(|item| item.exec.clone()),
+                        menu.on_select.clone(),
+                    ),
+                    _ => (None, None),
+                };
             // Close the selected window and any other open dialogs.
             state.dialog_windows.remove(&window);
-            state.closing_dialogs.remove(&window);
+            state.toast_windows.remove(&window);
+            state.windows.forget_closing(window);
             let close_others = state.close_dialogs();
-            if let Some(menu) = state
-                .gauges
-                .iter()
-                .find(|g| g.id == gauge_id)
-                .and_then(|g| g.interactions.right_click.menu.as_ref())
-                .and_then(|menu| menu.on_select.clone())
-            {
+            if let Some(command) = exec {
+                gauge_bindings::spawn_command(&command);
+                return Task::batch([close_others, close_window_task(window)]);
+            }
+            if gauge_id == top_apps_panel::MENU_ID {
+                return Task::batch([
+                    close_others,
+                    close_window_task(window),
+                    Task::done(Message::TopAppsCategorySelected(item_id)),
+                ]);
+            }
+            if gauge_id == taskbar_panel::MENU_ID {
+                return Task::batch([
+                    close_others,
+                    close_window_task(window),
+                    Task::done(Message::TaskbarMoveSelected(item_id)),
+                ]);
+            }
+            if gauge_id == theme::MENU_ID {
+                let selected = if item_id == gauge_diagnostics::MENU_ITEM {
+                    Message::GaugeDiagnosticsRequested
+                } else {
+                    Message::ThemeSelected(item_id)
+                };
+                return Task::batch([
+                    close_others,
+                    close_window_task(window),
+                    Task::done(selected),
+                ]);
+            }
+            // Menus built by the bar (such as confirmations) carry their own callback.
+            if let Some(menu) = dialog_on_select.or_else(|| {
+                state
+                    .gauges
+                    .iter()
+                    .find(|g| g.id == gauge_id)
+                    .and_then(|g| g.interactions.right_click.menu.as_ref())
+                    .and_then(|menu| menu.on_select.clone())
+            }) {
                 menu(item_id);
             }
             return Task::batch([close_others, close_window_task(window)]);
@@ -602,10 +1528,25 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
             gauge_id,
             item_id,
         } => {
+            let (exec, prompt) = match state.dialog_windows.get(&window).map(|d| &d.dialog) {
+                Some(GaugeDialog::Action(actions)) => (
+                    actions
+                        .items
+                        .iter()
+                        .find(|item| item.id == item_id)
+                        .and_then(|item| item.exec.clone()),
+                    actions.confirm.and_then(|confirm| confirm(&item_id)),
+                ),
+                _ => (None, None),
+            };
             // Close the selected window and any other open dialogs.
             state.dialog_windows.remove(&window);
-            state.closing_dialogs.remove(&window);
+            state.windows.forget_closing(window);
             let close_others = state.close_dialogs();
+            if let Some(command) = exec {
+                gauge_bindings::spawn_command(&command);
+                return Task::batch([close_others, Task::done(Message::RemoveWindow(window))]);
+            }
             if let Some(action) = state
                 .gauges
                 .iter()
@@ -613,10 +1554,54 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
                 .and_then(|g| g.interactions.right_click.action_dialog.as_ref())
                 .and_then(|dialog| dialog.on_select.clone())
             {
+                if let Some(prompt) = prompt {
+                    let menu = dialog::action::confirm_menu(prompt, item_id, action);
+                    let open_confirm = state.open_menu(&gauge_id, menu, None);
+                    return Task::batch([
+                        close_others,
+                        Task::done(Message::RemoveWindow(window)),
+                        open_confirm,
+                    ]);
+                }
                 action(item_id.clone());
             }
             return Task::batch([close_others, Task::done(Message::RemoveWindow(window))]);
         }
+        Message::PromptChanged { window, value } => {
+            if let Some(dialog_window) = state.dialog_windows.get_mut(&window)
+                && let GaugeDialog::Prompt(prompt) = &mut dialog_window.dialog
+            {
+                prompt.value = value;
+            }
+        }
+        Message::PromptEdited { window, action } => {
+            if let Some(dialog_window) = state.dialog_windows.get_mut(&window)
+                && let GaugeDialog::Prompt(prompt) = &mut dialog_window.dialog
+                && let Some(editor) = &mut prompt.editor
+            {
+                editor.perform(action);
+            }
+        }
+        Message::PromptSubmitted { window } => {
+            if let Some(dialog_window) = state.dialog_windows.get(&window)
+                && let GaugeDialog::Prompt(prompt) = &dialog_window.dialog
+            {
+                // Passphrases are passed as typed; other prompts are trimmed.
+                let text = prompt.text();
+                let value = if prompt.secret {
+                    text.as_str()
+                } else {
+                    text.trim()
+                };
+                if value.trim().is_empty() {
+                    return Task::none();
+                }
+                if let Some(on_submit) = &prompt.on_submit {
+                    on_submit(value.to_string());
+                }
+            }
+            return state.close_dialogs();
+        }
         Message::MenuSliderChanged { window, value } => {
             if let Some(dialog_window) = state.dialog_windows.get_mut(&window) {
                 dialog_window.slider_value = Some(value);
@@ -649,10 +1634,27 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
             if let Some(task) = track_bar_window(state, window) {
                 return task;
             }
+            if let Some(dialog_window) = state.dialog_windows.get(&window)
+                && matches!(dialog_window.dialog, GaugeDialog::Prompt(_))
+            {
+                return iced::widget::operation::focus(dialog::prompt::INPUT_ID);
+            }
         }
         Message::WindowEvent(window, event) => {
+            if let iced::window::Event::Opened { size, .. } | iced::window::Event::Resized(size) =
+                event
+                && state.windows.bars().any(|bar| bar == window)
+            {
+                a11y::set_window_size(size);
+            }
             if let iced::window::Event::Opened { size, .. } = event {
-                let mut tasks = vec![set_input_region_task(window, size)];
+                let mut tasks = vec![
+                    set_input_region_task(window, size),
+                    query_window_scale(window),
+                ];
+                if a11y::is_active() {
+                    tasks.push(measure_a11y_bounds());
+                }
                 if let Some(task) = track_bar_window(state, window) {
                     tasks.push(task);
                 }
@@ -666,7 +1668,7 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
         }
         Message::MenuDismissed(window) => {
             state.dialog_windows.remove(&window);
-            state.closing_dialogs.remove(&window);
+            state.windows.forget_closing(window);
             return close_window_task(window);
         }
         Message::CacheRefreshed(result) => match result {
@@ -679,31 +1681,32 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
                 } else {
                     AppIconCache::default()
                 };
-                state.top_apps = top_apps;
+                top_apps_panel::set_top_apps(state, top_apps);
             }
             Err(err) => {
                 error!("Failed to refresh icon cache: {err}");
             }
         },
+        Message::WindowScaleChanged { window, scale } => {
+            state.window_scales.insert(window, scale);
+        }
+        Message::A11yBoundsMeasured(bounds) => a11y::set_bounds(bounds),
         Message::WindowClosed(window) => {
-            let is_primary = state
-                .primary_window
-                .is_some_and(|primary| primary == window);
+            state.window_scales.remove(&window);
             state.dialog_windows.remove(&window);
-            state.closing_dialogs.remove(&window);
-            state.bar_windows.remove(&window);
-            if is_primary {
-                if let Some(next_primary) = state.bar_windows.iter().copied().next() {
-                    state.primary_window = Some(next_primary);
-                    state.pending_primary_window = false;
-                    return Task::none();
+            state.pinned_dialogs.remove(&window);
+            if state.quit_reply.is_some() {
+                state.windows.window_closed(window);
+                if state.windows.all_closed() {
+                    return finish_quit(state);
                 }
-                state.primary_window = None;
-                state.pending_primary_window = true;
-                let mut tasks = vec![state.close_dialogs()];
+                return Task::none();
+            }
+            if state.windows.window_closed(window) == BarClosed::Reopen {
+                let mut tasks = vec![state.close_dialogs(), state.close_pinned_dialogs()];
                 let id = window::Id::unique();
                 let task = Task::done(Message::NewLayerShell {
-                    settings: layershell_reopen_settings(),
+                    settings: layershell_reopen_settings(state.autohide.as_ref()),
                     id,
                 });
                 tasks.push(Task::done(Message::ForgetLastOutput));
@@ -711,67 +1714,45 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
                 return Task::batch(tasks);
             }
         }
-        Message::OutputChanged => {
-            if let Some(snapshot) = monitor::snapshot_outputs() {
-                if !monitor::has_active_outputs(&snapshot) {
-                    state.last_outputs = Some(snapshot);
-                    return Task::none();
-                }
-                match state.last_outputs.as_ref() {
-                    None => {
-                        state.last_outputs = Some(snapshot);
-                        return Task::none();
-                    }
-                    Some(prev) => {
-                        if !monitor::has_active_outputs(prev) {
-                            state.last_outputs = Some(snapshot);
-                            return Task::none();
-                        }
-                        if monitor::outputs_equal(prev, &snapshot) {
-                            state.last_outputs = Some(snapshot);
-                            return Task::none();
-                        }
-                        state.last_outputs = Some(snapshot);
-                    }
-                }
-            }
-            let now = Instant::now();
-            let reopened_since_last_output = state
-                .last_output_change_at
-                .and_then(|last| state.last_bar_window_opened_at.map(|opened| opened > last))
-                .unwrap_or(false);
-            if reopened_since_last_output {
-                state.last_output_change_at = Some(now);
-                return Task::none();
-            }
-            let recent_open = state
-                .last_bar_window_opened_at
-                .and_then(|last| now.checked_duration_since(last))
-                .is_some_and(|elapsed| elapsed < OUTPUT_REOPEN_SUPPRESSION_WINDOW);
-            if recent_open {
-                return Task::none();
-            }
-            let recently_handled = state
-                .last_output_change_at
-                .and_then(|last| now.checked_duration_since(last))
-                .is_some_and(|elapsed| elapsed < OUTPUT_REOPEN_SUPPRESSION_WINDOW);
-            if recently_handled {
-                return Task::none();
+        Message::LidChanged { closed } => {
+            state.lid_closed = closed;
+            let outputs = monitor::snapshot_outputs()
+                .or_else(|| state.windows.last_outputs().map(<[_]>::to_vec))
+                .unwrap_or_default();
+            return update_clamshell(state, &outputs).unwrap_or_else(Task::none);
+        }
+        Message::HardwareChanged => {
+            let configured: Vec<String> = state.gauge_order_index.keys().cloned().collect();
+            let missing = gauge_hardware::missing(&configured);
+            for id in missing.difference(&state.missing_hardware) {
+                info!("Hiding gauge '{id}': its hardware was removed");
             }
-            if state.bar_windows.len() > 1 {
-                state.last_output_change_at = Some(now);
-                return Task::none();
+            for id in state.missing_hardware.difference(&missing) {
+                info!("Showing gauge '{id}': its hardware was added");
             }
-            if state.pending_primary_window && state.primary_window.is_none() {
-                return Task::none();
+            state.gauges.retain(|gauge| !missing.contains(gauge.id));
+            state.missing_hardware = missing;
+        }
+        Message::OutputChanged => {
+            let snapshot = monitor::snapshot_outputs();
+            // Output scales may have changed under open surfaces.
+            let rescale = Task::batch(
+                state
+                    .window_scales
+                    .keys()
+                    .map(|window| query_window_scale(*window))
+                    .collect::<Vec<_>>(),
+            );
+            if let Some(snapshot) = &snapshot
+                && let Some(task) = update_clamshell(state, snapshot)
+            {
+                state.windows.record_outputs(snapshot.clone());
+                return Task::batch([rescale, task]);
             }
-            if state.primary_window.is_none() {
-                return Task::none();
+            if state.windows.output_changed(snapshot, Instant::now()) {
+                return Task::batch([rescale, reopen_primary_window(state)]);
             }
-            state.last_output_change_at = Some(now);
-            // After resume/hotplug, the existing surface can go blank. Recreate the
-            // primary window while ensuring we do not leave duplicates behind.
-            return reopen_primary_window(state);
+            return rescale;
         }
         Message::IcedEvent(iced::Event::Window(iced::window::Event::Unfocused)) => {
             return Task::done(Message::WindowFocusChanged { focused: false });
@@ -806,23 +1787,16 @@ fn update(state: &mut BarState, message: Message) -> Task<Message> {
 }
 
 fn track_bar_window(state: &mut BarState, window: window::Id) -> Option<Task<Message>> {
-    if state.dialog_windows.contains_key(&window) || state.closing_dialogs.contains(&window) {
-        return None;
-    }
-
-    let inserted = state.bar_windows.insert(window);
-    if inserted {
-        state.last_bar_window_opened_at = Some(Instant::now());
-    }
-    if state.primary_window.is_none() {
-        state.primary_window = Some(window);
-        state.pending_primary_window = false;
+    if !state.dialog_windows.contains_key(&window)
+        && !state.pinned_dialogs.contains_key(&window)
+        && state.overview != Some(window)
+    {
+        state.windows.bar_opened(window, Instant::now());
     }
-
     None
 }
 
-fn layershell_reopen_settings() -> NewLayerShellSettings {
+fn layershell_reopen_settings(autohide: Option<&autohide::Autohide>) -> NewLayerShellSettings {
     let settings = settings::settings();
     let bar_width = settings.get_parsed_or("grelier.bar.width", 28u32);
     let orientation_raw = settings.get_or("grelier.bar.orientation", DEFAULT_ORIENTATION);
@@ -833,17 +1807,16 @@ fn layershell_reopen_settings() -> NewLayerShellSettings {
             Orientation::Left
         }
     };
-    let anchor = match orientation {
-        Orientation::Left => Anchor::Left,
-        Orientation::Right => Anchor::Right,
-    };
+    let bar_margin = bar::BarMargin::from_settings(settings);
 
     NewLayerShellSettings {
         size: Some((bar_width, 0)),
         layer: Layer::Top,
-        anchor,
-        exclusive_zone: Some(bar_width as i32),
-        margin: Some((0, 0, 0, 0)),
+        anchor: bar_margin.anchor(orientation),
+        exclusive_zone: Some(
+            autohide.map_or(bar_width as i32, |autohide| autohide.exclusive_zone()),
+        ),
+        margin: Some(bar_margin.layer_margin(autohide)),
         keyboard_interactivity: KeyboardInteractivity::OnDemand,
         output_option: OutputOption::None,
         events_transparent: false,
@@ -851,20 +1824,51 @@ fn layershell_reopen_settings() -> NewLayerShellSettings {
     }
 }
 
+/// Track clamshell mode. A bar pinned to the internal panel with `--on-monitor` is closed
+/// while the lid is shut and reopened when it opens; returns that task on a transition.
+fn update_clamshell(state: &mut BarState, outputs: &[OutputSnapshot]) -> Option<Task<Message>> {
+    let active = lid::is_clamshell(state.lid_closed, outputs);
+    if active == state.clamshell {
+        return None;
+    }
+    state.clamshell = active;
+    lid::set_clamshell(active);
+    info!(
+        "Clamshell mode {}",
+        if active { "engaged" } else { "released" }
+    );
+    let pinned_internal = state
+        .target_monitor
+        .as_deref()
+        .is_some_and(lid::is_internal_output);
+    if !pinned_internal {
+        return None;
+    }
+    if active {
+        let bar_windows = state.windows.close_bars(false);
+        return Some(Task::batch(
+            [state.close_dialogs(), state.close_pinned_dialogs()]
+                .into_iter()
+                .chain(bar_windows.into_iter().map(close_window_task)),
+        ));
+    }
+    state.windows.request_bar();
+    Some(Task::done(Message::NewLayerShell {
+        settings: layershell_reopen_settings(state.autohide.as_ref()),
+        id: window::Id::unique(),
+    }))
+}
+
 fn reopen_primary_window(state: &mut BarState) -> Task<Message> {
-    state.pending_primary_window = true;
-    state.primary_window = None;
-    let closing_bar_windows: Vec<window::Id> = state.bar_windows.drain().collect();
-    state
-        .closing_dialogs
-        .extend(closing_bar_windows.iter().copied());
+    let closing_bar_windows = state.windows.close_bars(true);
 
     Task::batch(
-        std::iter::once(state.close_dialogs())
+        [state.close_dialogs(), state.close_pinned_dialogs()]
+            .into_iter()
             .chain(closing_bar_windows.into_iter().map(close_window_task))
             .chain(std::iter::once(Task::done(Message::ForgetLastOutput)))
             .chain(std::iter::once(Task::done(Message::NewLayerShell {
-                settings: layershell_reopen_settings(),
+                settings: layershell_reopen_settings(state.autohide.as_ref()),
                 id: window::Id::unique(),
             }))),
     )
@@ -888,7 +1892,7 @@ fn handle_window_focus_change(state: &mut BarState, focused: bool) -> Task<Messa
     // Close the first tracked dialog on a real unfocus transition.
     if let Some(window) = state.dialog_windows.keys().copied().next() {
         state.dialog_windows.remove(&window);
-        state.closing_dialogs.insert(window);
+        state.windows.mark_closing([window]);
         return close_window_task(window);
     }
 
@@ -904,14 +1908,67 @@ fn update_gauge(gauges: &mut Vec<GaugeModel>, new: GaugeModel) {
     }
 }
 
+/// Apply only the parts flagged in `update.redraw`, so unchanged icon handles and
+/// interactions stay as they were and the view reuses their cached renders.
+fn merge_gauge_update(gauges: &mut Vec<GaugeModel>, update: GaugeUpdate) {
+    let GaugeUpdate { model, redraw } = update;
+    let existing = match gauges.iter().position(|g| g.id == model.id) {
+        Some(idx) if redraw != GaugeRedraw::ALL => &mut gauges[idx],
+        _ => return update_gauge(gauges, model),
+    };
+    if redraw.icon {
+        existing.icon = model.icon;
+    }
+    if redraw.value {
+        existing.display = model.display;
+    }
+    if redraw.interactions {
+        existing.interactions = model.interactions;
+    }
+}
+
+/// Answer the pending `quit` request and exit, once every surface has closed.
+fn finish_quit(state: &mut BarState) -> Task<Message> {
+    if let Some(reply) = state.quit_reply.take() {
+        let _ = reply.send(serde_json::json!({ "ok": true }).to_string());
+    }
+    ipc::remove_socket();
+    iced::exit()
+}
+
+/// Merge gauge updates, refreshing open and pinned info dialogs in `dialog_maps`.
 fn apply_gauge_batch(
     gauges: &mut Vec<GaugeModel>,
-    dialog_windows: &mut std::collections::HashMap<window::Id, GaugeDialogWindow>,
-    batch: Vec<GaugeModel>,
+    mut dialog_maps: [&mut std::collections::HashMap<window::Id, GaugeDialogWindow>; 2],
+    batch: Vec<GaugeUpdate>,
 ) {
-    for gauge in batch {
-        refresh_info_dialogs(dialog_windows, &gauge);
-        update_gauge(gauges, gauge);
+    for update in batch {
+        if update.redraw.interactions {
+            for dialog_windows in dialog_maps.iter_mut() {
+                refresh_info_dialogs(dialog_windows, &update.model);
+            }
+        }
+        if update.redraw.value {
+            ipc::publish(ipc::EventTopic::Gauge, || {
+                serde_json::json!({
+                    "id": update.model.id,
+                    "display": snapshot::display_json(&update.model.display),
+                    "label": a11y::gauge_label(&update.model).1,
+                })
+            });
+            let announcement = a11y::announcer()
+                .lock()
+                .ok()
+                .and_then(|mut announcer| announcer.observe(&update.model));
+            if let Some(text) = announcement {
+                a11y::announce(&text);
+                ipc::publish(
+                    ipc::EventTopic::Announce,
+                    || serde_json::json!({ "gauge": update.model.id, "text": text }),
+                );
+            }
+        }
+        merge_gauge_update(gauges, update);
     }
 }
 
@@ -919,14 +1976,20 @@ fn refresh_info_dialogs(
     dialog_windows: &mut std::collections::HashMap<window::Id, GaugeDialogWindow>,
     gauge: &GaugeModel,
 ) {
-    let Some(info) = gauge.interactions.left_click.info.as_ref() else {
-        return;
-    };
-
+    let left = gauge.interactions.left_click.info.as_ref();
+    let middle = gauge.interactions.middle_click.info.as_ref();
     for dialog_window in dialog_windows.values_mut() {
-        if dialog_window.gauge_id == gauge.id
-            && let GaugeDialog::Info(dialog) = &mut dialog_window.dialog
-        {
+        if dialog_window.gauge_id != gauge.id {
+            continue;
+        }
+        let GaugeDialog::Info(dialog) = &mut dialog_window.dialog else {
+            continue;
+        };
+        // A middle-click dialog is told apart from the left-click one by its title.
+        let info = middle
+            .filter(|middle| middle.title == dialog.title)
+            .or(left);
+        if let Some(info) = info {
             *dialog = info.clone();
         }
     }
@@ -935,11 +1998,13 @@ fn refresh_info_dialogs(
 #[cfg(test)]
 mod tests {
     use crate::bar::{GaugeDialog, GaugeDialogWindow};
+    use crate::dialog::info::InfoDialog;
     use crate::panels::gauges::gauge::{
         GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugePointerInteraction, GaugeValue,
         GaugeValueAttention,
     };
     use crate::settings_storage::SettingsStorage;
+    use crate::window_tracker::Primary;
     use std::sync::Arc;
     use std::sync::Mutex;
     use std::sync::atomic::{AtomicBool, Ordering};
@@ -1028,6 +2093,27 @@ mod tests {
         assert_eq!(gauges.len(), 2, "different id should append");
     }
 
+    #[test]
+    fn banner_retry_clears_degraded_state_and_restarts_subscriptions() {
+        let mut state = BarState::default();
+        let _ = update(
+            &mut state,
+            Message::Degraded {
+                source: compositor::DEGRADED_SOURCE.to_string(),
+                message: "event stream closed".to_string(),
+            },
+        );
+        assert_eq!(state.degraded.len(), 1);
+        assert_eq!(
+            state.degraded_info_dialog().lines[0],
+            "compositor: event stream closed"
+        );
+
+        let _ = update(&mut state, Message::BannerRetry);
+        assert!(state.degraded.is_empty());
+        assert_eq!(state.subscription_generation, 1);
+    }
+
     #[test]
     fn left_click_closes_open_dialog_without_invoking_callback() {
         let mut state = BarState::default();
@@ -1081,7 +2167,7 @@ mod tests {
             "menu windows should be cleared"
         );
         assert!(
-            state.closing_dialogs.contains(&window),
+            state.windows.is_closing(window),
             "window should be marked for closing"
         );
         assert!(
@@ -1128,7 +2214,7 @@ mod tests {
             "any click should close existing menu"
         );
         assert!(
-            state.closing_dialogs.contains(&window),
+            state.windows.is_closing(window),
             "window should be marked for closing"
         );
         assert!(
@@ -1212,11 +2298,11 @@ mod tests {
         );
         assert!(state.dialog_windows.is_empty(), "menus should be cleared");
         assert!(
-            state.closing_dialogs.contains(&other_window),
+            state.windows.is_closing(other_window),
             "other menus should be marked for closing"
         );
         assert!(
-            !state.closing_dialogs.contains(&window),
+            !state.windows.is_closing(window),
             "selected window is closed directly"
         );
         assert!(task.units() > 0, "menu selection returns a close task");
@@ -1240,7 +2326,7 @@ mod tests {
                 slider_value: None,
             },
         );
-        state.closing_dialogs.insert(window);
+        state.windows.mark_closing([window]);
 
         let _ = update(&mut state, Message::MenuDismissed(window));
 
@@ -1249,7 +2335,7 @@ mod tests {
             "menu should be removed"
         );
         assert!(
-            !state.closing_dialogs.contains(&window),
+            !state.windows.is_closing(window),
             "closing set should be cleared"
         );
     }
@@ -1315,32 +2401,93 @@ mod tests {
             "dialog windows should be cleared on any click"
         );
         assert!(
-            state.closing_dialogs.contains(&window),
+            state.windows.is_closing(window),
             "existing dialog should be marked for closing"
         );
         assert!(task.units() > 0, "closing task should be returned");
     }
 
+    #[test]
+    fn pinned_info_dialog_stays_open_until_shift_clicked() {
+        let mut state = BarState::default();
+        let gauge = |line: &str| GaugeModel {
+            id: "cpu",
+            icon: test_icon(),
+            display: GaugeDisplay::Empty,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "CPU".to_string(),
+                        lines: vec![line.to_string()],
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        };
+        state.gauges.push(gauge("10%"));
+        let pinned = window::Id::unique();
+        state.pinned_dialogs.insert(
+            pinned,
+            GaugeDialogWindow {
+                gauge_id: "cpu".to_string(),
+                dialog: GaugeDialog::Info(gauge("10%").interactions.left_click.info.expect("info")),
+                hovered_item: None,
+                slider_value: None,
+            },
+        );
+        let _ = track_bar_window(&mut state, pinned);
+        assert!(
+            state.windows.bars().next().is_none(),
+            "the pinned popup is not a bar"
+        );
+
+        // Focus loss and other dialogs leave it open, and updates still reach it.
+        let _ = update(&mut state, Message::WindowFocusChanged { focused: false });
+        let _ = state.close_dialogs();
+        let _ = update(
+            &mut state,
+            Message::GaugeBatch(vec![GaugeUpdate {
+                model: gauge("85%"),
+                redraw: GaugeRedraw::ALL,
+            }]),
+        );
+        match state.pinned_dialogs.get(&pinned).map(|d| &d.dialog) {
+            Some(GaugeDialog::Info(info)) => assert_eq!(info.lines, vec!["85%"]),
+            _ => panic!("pinned info dialog should remain"),
+        }
+
+        state.modifiers = iced::keyboard::Modifiers::SHIFT;
+        let task = update(
+            &mut state,
+            Message::GaugeClicked {
+                id: "cpu".to_string(),
+                input: GaugeInput::Button(mouse::Button::Left),
+            },
+        );
+        assert!(state.pinned_dialogs.is_empty());
+        assert!(state.windows.is_closing(pinned));
+        assert!(task.units() > 0, "unpinning closes the popup");
+    }
+
     #[test]
     fn track_bar_window_keeps_existing_primary_and_preserves_windows() {
         let mut state = BarState::default();
         let old_primary = window::Id::unique();
         let new_primary = window::Id::unique();
-        state.primary_window = Some(old_primary);
-        state.bar_windows.insert(old_primary);
+        state.windows.bar_opened(old_primary, Instant::now());
 
         let task = track_bar_window(&mut state, new_primary);
 
         assert!(task.is_none(), "tracking bars should not queue closes");
-        assert_eq!(state.primary_window, Some(old_primary));
-        assert!(state.closing_dialogs.is_empty());
-        assert_eq!(
-            state.bar_windows.len(),
-            2,
-            "both windows should remain tracked"
-        );
-        assert!(state.bar_windows.contains(&old_primary));
-        assert!(state.bar_windows.contains(&new_primary));
+        assert_eq!(state.windows.primary(), Primary::Open(old_primary));
+        assert!(!state.windows.is_closing(old_primary));
+        assert!(!state.windows.is_closing(new_primary));
+        let bars: Vec<window::Id> = state.windows.bars().collect();
+        assert_eq!(bars.len(), 2, "both windows should remain tracked");
+        assert!(bars.contains(&old_primary));
+        assert!(bars.contains(&new_primary));
     }
 
     #[test]
@@ -1348,9 +2495,8 @@ mod tests {
         let mut state = BarState::default();
         let old_primary = window::Id::unique();
         let other = window::Id::unique();
-        state.primary_window = Some(old_primary);
-        state.bar_windows.insert(old_primary);
-        state.bar_windows.insert(other);
+        state.windows.bar_opened(old_primary, Instant::now());
+        state.windows.bar_opened(other, Instant::now());
 
         let task = update(&mut state, Message::WindowClosed(old_primary));
 
@@ -1359,10 +2505,35 @@ mod tests {
             0,
             "closing one bar should not reopen when another remains"
         );
-        assert_eq!(state.primary_window, Some(other));
-        assert!(!state.pending_primary_window);
-        assert_eq!(state.bar_windows.len(), 1);
-        assert!(state.bar_windows.contains(&other));
+        assert_eq!(state.windows.primary(), Primary::Open(other));
+        assert_eq!(state.windows.bars().collect::<Vec<_>>(), vec![other]);
+    }
+
+    #[test]
+    fn hover_tracks_latest_item_and_drop_target() {
+        let mut state = BarState::default();
+        let gauge = HoverTarget::Gauge("clock".to_string());
+        let _ = update(&mut state, Message::HoverEnter(gauge.clone()));
+        let _ = update(
+            &mut state,
+            Message::HoverEnter(HoverTarget::TopApp("foot".into())),
+        );
+        // A late exit from the previous item must not clear the new one.
+        let _ = update(&mut state, Message::HoverExit(gauge));
+        assert_eq!(state.hovered, Some(HoverTarget::TopApp("foot".into())));
+
+        state.workspace_drag = Some(bar::WorkspaceDrag {
+            source: "1".to_string(),
+            target: None,
+        });
+        let _ = update(
+            &mut state,
+            Message::HoverEnter(HoverTarget::Workspace("2".into())),
+        );
+        assert_eq!(
+            state.workspace_drag.and_then(|drag| drag.target),
+            Some("2".to_string())
+        );
     }
 
     fn assert_text_value(model: &GaugeModel, expected: &str) {
@@ -1,5 +1,5 @@
 use crate::bar::OutputSnapshot;
-use crate::sway_workspace;
+use crate::compositor;
 use log::error;
 use std::collections::HashSet;
 
@@ -14,14 +14,16 @@ pub fn normalize_monitor_selection(raw: Option<&str>) -> Result<Option<String>,
     }
     if monitor_name.contains(',') {
         return Err(
-            "--on-monitor accepts exactly one monitor name. Use --list-monitors to inspect names."
+            "--on-monitor accepts exactly one monitor name. Omit it to show a bar on every \
+             monitor from a single process; use --list-monitors to inspect names."
                 .to_string(),
         );
     }
     let monitor_name = monitor_name.to_string();
 
-    let outputs =
-        sway_workspace::fetch_outputs().map_err(|err| format!("Failed to query outputs: {err}"))?;
+    let outputs = compositor::backend()
+        .fetch_outputs()
+        .map_err(|err| format!("Failed to query outputs: {err}"))?;
     let known: HashSet<String> = outputs.into_iter().map(|output| output.name).collect();
 
     if !known.contains(&monitor_name) {
@@ -40,8 +42,9 @@ pub fn normalize_monitor_selection(raw: Option<&str>) -> Result<Option<String>,
 }
 
 pub fn list_monitors() -> Result<(), String> {
-    let outputs =
-        sway_workspace::fetch_outputs().map_err(|err| format!("Failed to query outputs: {err}"))?;
+    let outputs = compositor::backend()
+        .fetch_outputs()
+        .map_err(|err| format!("Failed to query outputs: {err}"))?;
     if outputs.is_empty() {
         println!("No outputs detected.");
         return Ok(());
@@ -63,19 +66,15 @@ pub fn list_monitors() -> Result<(), String> {
 }
 
 pub fn snapshot_outputs() -> Option<Vec<OutputSnapshot>> {
-    match sway_workspace::fetch_outputs() {
+    match compositor::backend().fetch_outputs() {
         Ok(outputs) => Some(
             outputs
                 .into_iter()
                 .map(|output| OutputSnapshot {
                     name: output.name,
                     active: output.active,
-                    rect: (
-                        output.rect.x,
-                        output.rect.y,
-                        output.rect.width,
-                        output.rect.height,
-                    ),
+                    rect: output.rect,
+                    scale: output.scale,
                 })
                 .collect(),
         ),
@@ -0,0 +1,185 @@
+// Workspace overview: a full-screen overlay with a card for each workspace listing its windows.
+// Clicking a card focuses that workspace and clicking a window focuses the window; Escape or a
+// click on the backdrop closes it. Opened from the `overview` panel or `grelier --overview`.
+// Consumes Settings: grelier.overview.*.
+use crate::bar::{BarState, HoverTarget, Message, app_icon_view, hoverable};
+use crate::compositor::WorkspaceInfo;
+use crate::settings;
+use elbey_cache::FALLBACK_ICON_HANDLE;
+use iced::alignment;
+use iced::font::Weight;
+use iced::widget::{Column, Row, Text, container, mouse_area};
+use iced::{Border, Color, Element, Font, Length, Theme, mouse};
+use iced_layershell::reexport::{
+    Anchor, KeyboardInteractivity, Layer, NewLayerShellSettings, OutputOption,
+};
+
+pub const COLUMNS_KEY: &str = "grelier.overview.columns";
+pub const CARD_WIDTH_KEY: &str = "grelier.overview.card_width";
+pub const ICON_SIZE_KEY: &str = "grelier.overview.icon_size";
+pub const BUTTON_SIZE_KEY: &str = "grelier.overview.button_size";
+const CARD_SPACING: u32 = 16;
+const CARD_PADDING: u16 = 12;
+const CARD_RADIUS: f32 = 8.0;
+const BACKDROP_ALPHA: f32 = 0.75;
+
+/// Columns for `count` cards: the smallest near-square grid, capped at `max_columns`.
+pub fn grid_columns(count: usize, max_columns: usize) -> usize {
+    let mut columns = 1;
+    while columns * columns < count {
+        columns += 1;
+    }
+    columns.min(max_columns.max(1))
+}
+
+/// Layer surface covering the whole output above other windows, taking the keyboard so
+/// Escape reaches it.
+pub fn layer_settings() -> NewLayerShellSettings {
+    NewLayerShellSettings {
+        size: Some((0, 0)),
+        layer: Layer::Overlay,
+        anchor: Anchor::Top | Anchor::Bottom | Anchor::Left | Anchor::Right,
+        exclusive_zone: Some(-1),
+        margin: None,
+        keyboard_interactivity: KeyboardInteractivity::Exclusive,
+        output_option: OutputOption::None,
+        events_transparent: false,
+        namespace: Some(format!("{}-overview", BarState::namespace())),
+    }
+}
+
+fn card<'a>(
+    state: &'a BarState,
+    workspace: &'a WorkspaceInfo,
+    width: f32,
+    icon_size: f32,
+) -> Element<'a, Message> {
+    let apps = state
+        .workspace_apps
+        .get(&workspace.name)
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    let mut title = Text::new(workspace.label.clone()).size(18);
+    if workspace.focused {
+        title = title.font(Font {
+            weight: Weight::Bold,
+            ..Font::DEFAULT
+        });
+    }
+
+    let windows = if apps.is_empty() {
+        Column::new().push(Text::new("No windows").size(13).style(|theme: &Theme| {
+            iced::widget::text::Style {
+                color: Some(Color {
+                    a: 0.6,
+                    ..theme.palette().text
+                }),
+            }
+        }))
+    } else {
+        apps.iter().fold(Column::new().spacing(4), |column, app| {
+            let handle = state
+                .app_icons
+                .icon_for(&app.app_id)
+                .unwrap_or(&FALLBACK_ICON_HANDLE);
+            let target = HoverTarget::WorkspaceApp(app.con_id);
+            let hovered = state.is_hovered(&target);
+            let entry = Row::new()
+                .spacing(8)
+                .align_y(alignment::Vertical::Center)
+                .push(app_icon_view(handle, icon_size))
+                .push(Text::new(app.app_id.clone()).size(14))
+                .width(Length::Fill);
+            column.push(
+                mouse_area(hoverable(entry, target, hovered))
+                    .on_press(Message::OverviewWindowSelected(app.con_id))
+                    .interaction(mouse::Interaction::Pointer),
+            )
+        })
+    };
+
+    let focused = workspace.focused;
+    let urgent = workspace.urgent;
+    let body = container(Column::new().spacing(8).push(title).push(windows))
+        .padding(CARD_PADDING)
+        .width(Length::Fixed(width))
+        .style(move |theme: &Theme| {
+            let palette = theme.extended_palette();
+            let border_color = if urgent {
+                palette.danger.base.color
+            } else if focused {
+                palette.primary.base.color
+            } else {
+                palette.background.strong.color
+            };
+            container::Style {
+                background: Some(palette.background.base.color.into()),
+                border: Border::default()
+                    .rounded(CARD_RADIUS)
+                    .width(if focused || urgent { 2.0 } else { 1.0 })
+                    .color(border_color),
+                text_color: Some(palette.background.base.text),
+                ..container::Style::default()
+            }
+        });
+    mouse_area(body)
+        .on_press(Message::OverviewWorkspaceSelected(workspace.name.clone()))
+        .interaction(mouse::Interaction::Pointer)
+        .into()
+}
+
+/// The overlay: workspace cards in a centered grid over a dimmed backdrop.
+pub fn view(state: &BarState) -> Element<'_, Message> {
+    let settings = settings::settings();
+    let max_columns = settings.get_parsed_or(COLUMNS_KEY, 4usize);
+    let card_width = settings.get_parsed_or(CARD_WIDTH_KEY, 260.0_f32);
+    let icon_size = settings.get_parsed_or(ICON_SIZE_KEY, 24.0_f32);
+    let columns = grid_columns(state.workspaces.len(), max_columns);
+
+    let grid = state.workspaces.chunks(columns).fold(
+        Column::new()
+            .spacing(CARD_SPACING)
+            .align_x(alignment::Horizontal::Center),
+        |grid, row| {
+            grid.push(
+                row.iter()
+                    .fold(Row::new().spacing(CARD_SPACING), |row, workspace| {
+                        row.push(card(state, workspace, card_width, icon_size))
+                    }),
+            )
+        },
+    );
+    let backdrop = container(grid)
+        .center(Length::Fill)
+        .padding(32)
+        .style(|theme: &Theme| container::Style {
+            background: Some(
+                Color {
+                    a: BACKDROP_ALPHA,
+                    ..theme.palette().background
+                }
+                .into(),
+            ),
+            ..container::Style::default()
+        });
+    // Cards capture their own presses, so this only sees clicks on the backdrop.
+    mouse_area(backdrop)
+        .on_press(Message::OverviewClosed)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lays_out_a_near_square_grid() {
+        assert_eq!(grid_columns(0, 4), 1);
+        assert_eq!(grid_columns(1, 4), 1);
+        assert_eq!(grid_columns(4, 4), 2);
+        assert_eq!(grid_columns(5, 4), 3);
+        assert_eq!(grid_columns(10, 4), 4);
+        assert_eq!(grid_columns(30, 4), 4);
+        assert_eq!(grid_columns(3, 0), 1);
+    }
+}
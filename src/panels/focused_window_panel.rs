@@ -0,0 +1,222 @@
+// Focused window panel: app icon plus the window title stacked one character per line.
+// Consumes Settings: grelier.focused_window.*.
+use crate::bar::{Message, Panel, app_icon_view};
+use crate::compositor::{self, CompositorEvent, FocusedWindow};
+use crate::panels::panel_registry::{
+    PanelActivation, PanelSpec, PanelSubscriptionContext, PanelViewContext,
+};
+use crate::settings;
+use elbey_cache::FALLBACK_ICON_HANDLE;
+use iced::alignment;
+use iced::futures::channel::mpsc;
+use iced::widget::{Column, container, text};
+use iced::{Length, Subscription};
+use std::sync::mpsc as sync_mpsc;
+use std::time::Duration;
+
+const MAX_CHARS_KEY: &str = "grelier.focused_window.max_chars";
+const MARQUEE_KEY: &str = "grelier.focused_window.marquee";
+const MARQUEE_MS_KEY: &str = "grelier.focused_window.marquee_ms";
+const ICON_SIZE_KEY: &str = "grelier.focused_window.icon_size";
+const TEXT_SIZE_KEY: &str = "grelier.focused_window.text_size";
+/// Blank characters between the end of a scrolling title and its restart.
+const MARQUEE_GAP: usize = 3;
+
+fn max_chars() -> usize {
+    settings::settings()
+        .get_parsed_or(MAX_CHARS_KEY, 12usize)
+        .max(2)
+}
+
+fn marquee_enabled() -> bool {
+    settings::settings().get_bool_or(MARQUEE_KEY, true)
+}
+
+/// Title text as shown in the bar: at most `max_chars` characters, one per line.
+///
+/// Longer titles scroll by `offset` characters when `marquee` is set, else end in an ellipsis.
+pub fn visible_title(title: &str, max_chars: usize, offset: usize, marquee: bool) -> String {
+    let chars: Vec<char> = title.trim().chars().collect();
+    let shown: Vec<char> = if chars.len() <= max_chars {
+        chars
+    } else if marquee {
+        let cycle = chars.len() + MARQUEE_GAP;
+        (0..max_chars)
+            .map(|idx| chars.get((offset + idx) % cycle).copied().unwrap_or(' '))
+            .collect()
+    } else {
+        chars[..max_chars - 1]
+            .iter()
+            .copied()
+            .chain(std::iter::once('…'))
+            .collect()
+    };
+    shown
+        .iter()
+        .map(char::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn view<'a>(context: PanelViewContext<'a>) -> Panel<'a> {
+    let state = context.state;
+    let settings = settings::settings();
+    let icon_size = settings.get_parsed_or(ICON_SIZE_KEY, 20.0_f32);
+    let text_size = settings.get_parsed_or(TEXT_SIZE_KEY, 12.0_f32);
+
+    let mut column = Column::new()
+        .spacing(4)
+        .align_x(alignment::Horizontal::Center)
+        .width(Length::Fill);
+    if let Some(window) = &state.focused_window {
+        let handle = state
+            .app_icons
+            .icon_for(&window.app_id)
+            .unwrap_or(&FALLBACK_ICON_HANDLE);
+        column = column.push(app_icon_view(handle, icon_size));
+        let title = visible_title(
+            &window.title,
+            max_chars(),
+            state.focused_title_offset,
+            marquee_enabled(),
+        );
+        if !title.is_empty() {
+            column = column.push(
+                text(title)
+                    .size(text_size)
+                    .width(Length::Fill)
+                    .align_x(text::Alignment::Center),
+            );
+        }
+    }
+
+    Panel::new(
+        container(column)
+            .padding([2, 2])
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Center),
+    )
+}
+
+/// Focused-window updates from the compositor, plus marquee ticks while the title overflows.
+fn focused_window_stream() -> impl iced::futures::Stream<Item = Message> {
+    let (mut sender, receiver) = mpsc::channel(16);
+
+    std::thread::spawn(move || {
+        let backend = compositor::backend();
+        let (changed_tx, changed_rx) = sync_mpsc::channel::<()>();
+        let _ = changed_tx.send(());
+        std::thread::spawn(move || {
+            let events = match backend.subscribe(false) {
+                Ok(events) => events,
+                Err(err) => {
+                    log::error!("Failed to subscribe to focused window events: {err}");
+                    return;
+                }
+            };
+            for event in events {
+                match event {
+                    Ok(CompositorEvent::Workspaces) => {
+                        if changed_tx.send(()).is_err() {
+                            return;
+                        }
+                    }
+                    Ok(CompositorEvent::Outputs) => {}
+                    Err(err) => {
+                        log::error!("Focused window event stream error: {err}");
+                        return;
+                    }
+                }
+            }
+        });
+
+        let max_chars = max_chars();
+        let marquee = marquee_enabled();
+        let tick = Duration::from_millis(
+            settings::settings()
+                .get_parsed_or(MARQUEE_MS_KEY, 400u64)
+                .max(50),
+        );
+        let mut current: Option<FocusedWindow> = None;
+        loop {
+            let scrolling = marquee
+                && current
+                    .as_ref()
+                    .is_some_and(|window| window.title.trim().chars().count() > max_chars);
+            let changed = if scrolling {
+                match changed_rx.recv_timeout(tick) {
+                    Ok(()) => true,
+                    Err(sync_mpsc::RecvTimeoutError::Timeout) => false,
+                    Err(sync_mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            } else if changed_rx.recv().is_ok() {
+                true
+            } else {
+                return;
+            };
+
+            if !changed {
+                if sender.try_send(Message::FocusedWindowTick).is_err() && sender.is_closed() {
+                    return;
+                }
+                continue;
+            }
+            let window = match backend.fetch_focused_window() {
+                Ok(window) => window,
+                Err(err) => {
+                    log::warn!("Failed to fetch focused window: {err}");
+                    continue;
+                }
+            };
+            if window != current {
+                current = window.clone();
+                if sender.try_send(Message::FocusedWindow(window)).is_err() && sender.is_closed() {
+                    return;
+                }
+            }
+        }
+    });
+
+    receiver
+}
+
+fn panel_settings() -> &'static [crate::settings::SettingSpec] {
+    crate::settings::NO_SETTINGS
+}
+
+fn panel_subscription(context: PanelSubscriptionContext<'_>) -> Option<Subscription<Message>> {
+    (context.activation == PanelActivation::Active)
+        .then(|| Subscription::run_with(context.generation, |_| focused_window_stream()))
+}
+
+inventory::submit! {
+    PanelSpec {
+        id: "focused_window",
+        description: "Focused window panel showing the app icon and a scrolling window title.",
+        default_enabled: false,
+        settings: panel_settings,
+        view,
+        subscription: Some(panel_subscription),
+        bootstrap: None,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_or_scrolls_long_titles() {
+        assert_eq!(visible_title("  vim  ", 12, 5, true), "v\ni\nm");
+        assert_eq!(visible_title("Firefox", 4, 0, false), "F\ni\nr\n…");
+        assert_eq!(visible_title("Firefox", 4, 0, true), "F\ni\nr\ne");
+        assert_eq!(visible_title("Firefox", 4, 5, true), "o\nx\n \n ");
+        assert_eq!(visible_title("Firefox", 4, 9, true), " \nF\ni\nr");
+        // The cycle is the title plus the gap, so offsets wrap back to the start.
+        assert_eq!(
+            visible_title("Firefox", 4, 10, true),
+            visible_title("Firefox", 4, 0, true)
+        );
+    }
+}
@@ -1,14 +1,24 @@
 use std::collections::HashMap;
 
-use crate::bar::{BarState, Message, Panel, lerp_color};
-use crate::icon::{svg_asset, themed_svg_handle_cached};
+use crate::bar::{
+    BarState, HoverTarget, Message, Panel, bar_easing, configured_scale, hoverable, lerp_color,
+    resolve_device_scale, snap_at, sparkline,
+};
+use crate::gauge_loading;
+use crate::icon::{
+    prerender_quantity_atlases, quantity_atlas_cached, quantity_step, svg_asset,
+    themed_svg_handle_cached,
+};
 use crate::panels::gauges::gauge::{
     GaugeDisplay, GaugeInput, GaugeModel, GaugeValue, GaugeValueAttention,
 };
 use crate::panels::gauges::gauge_work_manager;
-use crate::panels::panel_registry::{PanelActivation, PanelSpec, PanelSubscriptionContext};
+use crate::panels::panel_registry::{
+    PanelActivation, PanelSpec, PanelSubscriptionContext, PanelViewContext,
+};
 use crate::settings;
 use iced::alignment;
+use iced::widget::image::Image;
 use iced::widget::svg::{self, Svg};
 use iced::widget::text;
 use iced::widget::{Column, Space, container, mouse_area};
@@ -94,6 +104,41 @@ fn attention_gradient_colors_at_level(level: f32, theme: &Theme) -> (Color, Colo
     }
 }
 
+fn value_icon_size() -> f32 {
+    settings::settings().get_parsed_or("grelier.gauge.ui.value_icon_size", 20.0)
+}
+
+/// Pixel size of a quantity icon drawn `logical` units wide at `scale`.
+fn atlas_size(logical: f32, scale: f32) -> u32 {
+    (logical * scale).round().max(1.0) as u32
+}
+
+/// Drop themed icons from the previous theme and rasterize the quantity icon atlases for
+/// each attention gradient of the current one, at the scale of every open window.
+pub fn rebuild_themed_icons(state: &BarState) {
+    if let Ok(mut cache) = state.themed_svg_cache.lock() {
+        cache.clear();
+    }
+    if let Ok(mut atlases) = state.quantity_atlases.lock() {
+        atlases.clear();
+    }
+    let gradients =
+        [0.0, 1.0, 2.0].map(|level| attention_gradient_colors_at_level(level, &state.bar_theme));
+    let icon_size = value_icon_size();
+    let mut sizes: Vec<u32> = state
+        .window_scales
+        .keys()
+        .map(|window| atlas_size(icon_size, state.device_scale(*window)))
+        .collect();
+    if sizes.is_empty() {
+        let scale = resolve_device_scale(configured_scale(), None);
+        sizes.push(atlas_size(icon_size, scale));
+    }
+    sizes.sort_unstable();
+    sizes.dedup();
+    prerender_quantity_atlases(&state.quantity_atlases, &gradients, &sizes);
+}
+
 fn quantize_attention_level(level: f32) -> f32 {
     if level < 0.5 {
         0.0
@@ -135,17 +180,40 @@ pub fn ordered_gauges<'a>(
     ordered.into_iter().map(|(_, gauge)| gauge).collect()
 }
 
-pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
+pub fn view<'a>(context: PanelViewContext<'a>) -> Panel<'a> {
+    let state = context.state;
     let settings = settings::settings();
-    let gauge_padding_x = settings.get_parsed_or("grelier.gauge.ui.padding_x", 2u16);
-    let gauge_padding_y = settings.get_parsed_or("grelier.gauge.ui.padding_y", 2u16);
-    let gauge_spacing = settings.get_parsed_or("grelier.gauge.ui.spacing", 14u32);
-    let gauge_icon_size = settings.get_parsed_or("grelier.gauge.ui.icon_size", 20.0);
-    let gauge_value_icon_size = settings.get_parsed_or("grelier.gauge.ui.value_icon_size", 20.0);
+    let style = crate::style::current();
+    let gauge_padding_x = snap_at(
+        style
+            .number("gauge.padding_x")
+            .unwrap_or_else(|| settings.get_parsed_or("grelier.gauge.ui.padding_x", 2.0)),
+        context.scale,
+    );
+    let gauge_padding_y = snap_at(
+        style
+            .number("gauge.padding_y")
+            .unwrap_or_else(|| settings.get_parsed_or("grelier.gauge.ui.padding_y", 2.0)),
+        context.scale,
+    );
+    let gauge_spacing = style
+        .number("gauge.spacing")
+        .map(|spacing| spacing as u32)
+        .unwrap_or_else(|| settings.get_parsed_or("grelier.gauge.ui.spacing", 14u32));
+    let gauge_icon_size = style
+        .number("gauge.icon_size")
+        .unwrap_or_else(|| settings.get_parsed_or("grelier.gauge.ui.icon_size", 20.0));
+    let icon_color = style.color("gauge.icon");
+    let text_color = style.color("gauge.text");
+    let text_size = style.number("gauge.font_size");
+    let text_font = style.font("gauge.font");
+    let gauge_value_icon_size = value_icon_size();
     let gauge_icon_value_spacing =
         settings.get_parsed_or("grelier.gauge.ui.icon_value_spacing", 0.0);
     let bar_theme = state.bar_theme.clone();
     let svg_cache = state.themed_svg_cache.clone();
+    let atlases = state.quantity_atlases.clone();
+    let atlas_size = atlas_size(gauge_value_icon_size, context.scale);
 
     let ordered = ordered_gauges(&state.gauges, &state.gauge_order_index);
     let ratio_inner_full_icon = svg_asset("ratio-inner-full.svg");
@@ -164,6 +232,7 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
             let dialog_open = state
                 .dialog_windows
                 .values()
+                .chain(state.pinned_dialogs.values())
                 .any(|window| window.gauge_id == gauge.id);
 
             let mut gauge_column = Column::new()
@@ -174,12 +243,38 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
             let icon_handle = gauge.icon.clone();
             let icon_bar_theme = bar_theme.clone();
             let icon_svg_cache = svg_cache.clone();
-            let icon_box: Element<'_, Message> =
+            let icon_box: Element<'_, Message> = if state.gauge_loading.is_loading(gauge.id) {
+                let pulse = if state.gauge_loading.pulse { 1.0 } else { 0.0 };
+                AnimationBuilder::new(pulse, move |t: f32| {
+                    let theme = &icon_bar_theme;
+                    let (base_start, base_end) = icon_color
+                        .map(|color| (color, color))
+                        .unwrap_or_else(|| nominal_gradient_colors(theme));
+                    // Fade toward the background between pulses.
+                    let faded = 0.75 - 0.5 * t;
+                    let background = theme.palette().background;
+                    themed_svg_element(
+                        icon_svg_cache.clone(),
+                        icon_handle.clone(),
+                        lerp_color(base_start, background, faded),
+                        lerp_color(base_end, background, faded),
+                        gauge_icon_size,
+                        Some(lerp_color(base_end, background, faded)),
+                    )
+                })
+                .animation(bar_easing(
+                    Easing::EASE_IN_OUT.with_duration(gauge_loading::PULSE_INTERVAL),
+                ))
+                .into()
+            } else {
                 AnimationBuilder::new(if dialog_open { 1.0 } else { 0.0 }, move |t| {
                     let icon_view: Element<'_, Message> = {
                         let theme = &icon_bar_theme;
-                        let (base_start, base_end) = nominal_gradient_colors(theme);
-                        let base_fallback = attention_color(attention, theme);
+                        let (base_start, base_end) = icon_color
+                            .map(|color| (color, color))
+                            .unwrap_or_else(|| nominal_gradient_colors(theme));
+                        let base_fallback =
+                            icon_color.unwrap_or_else(|| attention_color(attention, theme));
                         let selected_foreground = theme.palette().background;
                         let start = lerp_color(base_start, selected_foreground, t);
                         let end = lerp_color(base_end, selected_foreground, t);
@@ -207,8 +302,9 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                         })
                         .into()
                 })
-                .animation(Easing::EASE_IN_OUT.very_quick())
-                .into();
+                .animation(bar_easing(Easing::EASE_IN_OUT.very_quick()))
+                .into()
+            };
             let centered_icon: Element<'_, Message> = container(icon_box)
                 .width(Length::Fill)
                 .align_x(alignment::Horizontal::Center)
@@ -228,15 +324,26 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                         let attention_level = attention_level(*attention);
                         let value = value.clone();
                         AnimationBuilder::new(attention_level, move |level| {
-                            text::Text::new(value.clone())
+                            let mut value = text::Text::new(value.clone())
                                 .width(Length::Fill)
                                 .align_x(text::Alignment::Center)
                                 .style(move |theme: &Theme| text::Style {
-                                    color: Some(attention_color_at_level(level, theme)),
-                                })
-                                .into()
+                                    // The style color only replaces the nominal color, so
+                                    // warnings still stand out.
+                                    color: Some(match text_color {
+                                        Some(color) if level <= 0.0 => color,
+                                        _ => attention_color_at_level(level, theme),
+                                    }),
+                                });
+                            if let Some(size) = text_size {
+                                value = value.size(size);
+                            }
+                            if let Some(font) = text_font {
+                                value = value.font(font);
+                            }
+                            value.into()
                         })
-                        .animation(Easing::EASE_IN_OUT.very_quick())
+                        .animation(bar_easing(Easing::EASE_IN_OUT.very_quick()))
                         .into()
                     }
                     GaugeDisplay::Value {
@@ -247,10 +354,21 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                         let handle = handle.clone();
                         let bar_theme = bar_theme.clone();
                         let svg_cache = svg_cache.clone();
+                        let atlases = atlases.clone();
                         AnimationBuilder::new(attention_level, move |level| {
                             let theme = &bar_theme;
                             let quantized = quantize_attention_level(level);
                             let (start, end) = attention_gradient_colors_at_level(quantized, theme);
+                            // Quantity icons come pre-rasterized from the atlas for this gradient.
+                            let atlas_icon = quantity_step(&handle).and_then(|step| {
+                                quantity_atlas_cached(&atlases, start, end, atlas_size)?.icon(step)
+                            });
+                            if let Some(icon) = atlas_icon {
+                                return Image::new(icon)
+                                    .width(Length::Fixed(gauge_value_icon_size))
+                                    .height(Length::Fixed(gauge_value_icon_size))
+                                    .into();
+                            }
                             let fallback = attention_color_at_level(quantized, theme);
                             themed_svg_element(
                                 svg_cache.clone(),
@@ -261,7 +379,21 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                                 Some(fallback),
                             )
                         })
-                        .animation(Easing::EASE_IN_OUT.very_quick())
+                        .animation(bar_easing(Easing::EASE_IN_OUT.very_quick()))
+                        .into()
+                    }
+                    GaugeDisplay::Graph { samples, attention } => {
+                        let attention_level = attention_level(*attention);
+                        let samples = samples.clone();
+                        AnimationBuilder::new(attention_level, move |level| {
+                            let level = quantize_attention_level(level);
+                            container(sparkline(&samples, gauge_value_icon_size, move |theme| {
+                                attention_color_at_level(level, theme)
+                            }))
+                            .width(Length::Fixed(gauge_value_icon_size))
+                            .into()
+                        })
+                        .animation(bar_easing(Easing::EASE_IN_OUT.very_quick()))
                         .into()
                     }
                     GaugeDisplay::Error => {
@@ -283,7 +415,7 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                                 Some(fallback),
                             )
                         })
-                        .animation(Easing::EASE_IN_OUT.very_quick())
+                        .animation(bar_easing(Easing::EASE_IN_OUT.very_quick()))
                         .into()
                     }
                     GaugeDisplay::Empty => Space::new().into(),
@@ -298,14 +430,21 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                 None
             };
 
+            let a11y_id = crate::a11y::gauge_id(gauge.id);
             let gauge_id = gauge.id.to_string();
-            let gauge_element: Element<'_, Message> = mouse_area({
-                let mut column = gauge_column.align_x(alignment::Horizontal::Center);
-                if let Some(value) = centered_value {
-                    column = column.push(value);
-                }
-                column.width(Length::Fill)
-            })
+            let target = HoverTarget::Gauge(gauge_id.clone());
+            let hovered = state.is_hovered(&target);
+            let gauge_element: Element<'_, Message> = mouse_area(hoverable(
+                {
+                    let mut column = gauge_column.align_x(alignment::Horizontal::Center);
+                    if let Some(value) = centered_value {
+                        column = column.push(value);
+                    }
+                    column.width(Length::Fill)
+                },
+                target,
+                hovered,
+            ))
             .on_press(Message::GaugeClicked {
                 id: gauge_id.clone(),
                 input: GaugeInput::Button(mouse::Button::Left),
@@ -328,7 +467,7 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
             .interaction(mouse::Interaction::Pointer)
             .into();
 
-            col.push(gauge_element)
+            col.push(container(gauge_element).id(a11y_id))
         },
     );
 
@@ -412,6 +551,7 @@ inventory::submit! {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use super::*;
 
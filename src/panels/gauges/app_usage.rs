@@ -0,0 +1,337 @@
+// Opt-in focused-app time tracking used by the clock gauge's usage dialog.
+// Data stays in a local JSON file and can be paused or purged from the clock menu.
+use crate::compositor;
+use crate::panels::gauges::gauge::{GaugeEventSource, GaugeReadyNotify, GaugeStop};
+use chrono::Local;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the in-progress focus span is accrued and saved without a focus change.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+/// Delay before re-subscribing after the compositor event stream ends.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Seconds per app per local date (`YYYY-MM-DD`).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UsageStore {
+    days: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+impl UsageStore {
+    fn from_json(value: &Value) -> Self {
+        let days = value
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(date, apps)| {
+                let apps = apps
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(app, secs)| Some((app.clone(), secs.as_u64()?)))
+                    .collect();
+                (date.clone(), apps)
+            })
+            .collect();
+        Self { days }
+    }
+
+    fn to_json(&self) -> Value {
+        Value::Object(
+            self.days
+                .iter()
+                .map(|(date, apps)| {
+                    let apps: Map<String, Value> = apps
+                        .iter()
+                        .map(|(app, secs)| (app.clone(), Value::from(*secs)))
+                        .collect();
+                    (date.clone(), Value::Object(apps))
+                })
+                .collect(),
+        )
+    }
+
+    fn add(&mut self, date: &str, app: &str, secs: u64) {
+        if secs == 0 {
+            return;
+        }
+        *self
+            .days
+            .entry(date.to_string())
+            .or_default()
+            .entry(app.to_string())
+            .or_default() += secs;
+    }
+
+    /// Drop all but the newest `keep_days` dates.
+    fn prune(&mut self, keep_days: usize) {
+        while self.days.len() > keep_days {
+            self.days.pop_first();
+        }
+    }
+
+    /// Apps used on `date`, longest first.
+    pub fn top_apps(&self, date: &str, limit: usize) -> Vec<(String, u64)> {
+        let mut apps: Vec<(String, u64)> = self
+            .days
+            .get(date)
+            .into_iter()
+            .flatten()
+            .map(|(app, secs)| (app.clone(), *secs))
+            .collect();
+        apps.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        apps.truncate(limit);
+        apps
+    }
+}
+
+/// Tracker state shared between the focus monitor and the clock gauge.
+#[derive(Debug)]
+pub struct AppUsage {
+    path: PathBuf,
+    retention_days: usize,
+    store: UsageStore,
+    /// Focused app and when its current span started.
+    current: Option<(String, Instant)>,
+    paused: bool,
+}
+
+impl AppUsage {
+    pub fn load(path: PathBuf, retention_days: usize, paused: bool) -> Self {
+        let store = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+            .map(|value| UsageStore::from_json(&value))
+            .unwrap_or_default();
+        Self {
+            path,
+            retention_days,
+            store,
+            current: None,
+            paused,
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+            .unwrap_or_else(std::env::temp_dir);
+        data_home.join("grelier").join("app-usage.json")
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Close the running span, crediting its time to `date`, and start tracking `app`.
+    fn focus_changed(&mut self, app: Option<String>, now: Instant, date: &str) {
+        self.accrue(now, date);
+        self.current = if self.paused {
+            None
+        } else {
+            app.map(|app| (app, now))
+        };
+    }
+
+    fn accrue(&mut self, now: Instant, date: &str) {
+        if let Some((app, started)) = self.current.as_mut() {
+            let secs = now.saturating_duration_since(*started).as_secs();
+            self.store.add(date, app, secs);
+            *started += Duration::from_secs(secs);
+        }
+    }
+
+    fn save(&mut self) {
+        self.store.prune(self.retention_days);
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(err) = fs::write(&self.path, self.store.to_json().to_string()) {
+            log::warn!("app usage: failed to save {}: {err}", self.path.display());
+        }
+    }
+
+    /// Stop or resume tracking; time spent paused is never recorded.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.accrue(Instant::now(), &today());
+        self.paused = paused;
+        self.current = None;
+        self.save();
+    }
+
+    /// Forget all recorded usage and delete the store file.
+    pub fn purge(&mut self) {
+        self.store = UsageStore::default();
+        self.current = self.current.take().map(|(app, _)| (app, Instant::now()));
+        if let Err(err) = fs::remove_file(&self.path)
+            && err.kind() != std::io::ErrorKind::NotFound
+        {
+            log::warn!("app usage: failed to delete {}: {err}", self.path.display());
+        }
+    }
+
+    /// Summary lines for today's top apps, including the span still in progress.
+    pub fn today_lines(&self, limit: usize) -> Vec<String> {
+        if self.paused {
+            return vec!["Tracking paused".to_string()];
+        }
+        let date = today();
+        let mut store = self.store.clone();
+        if let Some((app, started)) = &self.current {
+            store.add(&date, app, started.elapsed().as_secs());
+        }
+        let apps = store.top_apps(&date, limit);
+        if apps.is_empty() {
+            return vec!["No app usage recorded today".to_string()];
+        }
+        apps.into_iter()
+            .map(|(app, secs)| format!("{app}: {}", format_duration(secs)))
+            .collect()
+    }
+}
+
+fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+fn format_duration(secs: u64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+/// Event source that follows compositor focus changes and credits time to the focused app.
+pub struct AppUsageMonitor {
+    pub usage: Arc<Mutex<AppUsage>>,
+}
+
+fn forward_focus_events(tx: mpsc::Sender<()>) {
+    loop {
+        match compositor::backend().subscribe(false) {
+            Ok(events) => {
+                for event in events {
+                    if event.is_err() {
+                        break;
+                    }
+                    if tx.send(()).is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(err) => log::warn!("app usage: failed to subscribe to compositor: {err}"),
+        }
+        thread::sleep(RESUBSCRIBE_DELAY);
+    }
+}
+
+impl GaugeEventSource for AppUsageMonitor {
+    fn run(self: Box<Self>, _notify: GaugeReadyNotify, stop: GaugeStop) {
+        let (tx, rx) = mpsc::channel();
+        // Pick up the window that already has focus at startup.
+        let _ = tx.send(());
+        thread::spawn(move || forward_focus_events(tx));
+
+        let mut last_app: Option<String> = None;
+        let mut last_flush = Instant::now();
+        loop {
+            let event = stop.recv_timeout(&rx, FLUSH_INTERVAL);
+            if matches!(event, Err(mpsc::RecvTimeoutError::Disconnected)) {
+                // Keep the time accrued since the last flush.
+                if let Ok(mut usage) = self.usage.lock() {
+                    usage.accrue(Instant::now(), &today());
+                    usage.save();
+                }
+                return;
+            }
+            let focused = match event {
+                Ok(()) => compositor::backend().fetch_focused_app().unwrap_or(None),
+                Err(_) => last_app.clone(),
+            };
+            let Ok(mut usage) = self.usage.lock() else {
+                return;
+            };
+            let now = Instant::now();
+            let date = today();
+            let resumed = usage.current.is_none() && !usage.paused;
+            if focused != last_app || resumed {
+                usage.focus_changed(focused.clone(), now, &date);
+                last_app = focused;
+            } else {
+                usage.accrue(now, &date);
+            }
+            if now.duration_since(last_flush) >= FLUSH_INTERVAL {
+                usage.save();
+                last_flush = now;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accrues_focus_spans_and_ignores_paused_time() {
+        let path = std::env::temp_dir().join(format!(
+            "grelier_app_usage_test_{}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let mut usage = AppUsage::load(path.clone(), 30, false);
+        let start = Instant::now();
+        let date = "2026-01-02";
+
+        usage.focus_changed(Some("firefox".to_string()), start, date);
+        usage.focus_changed(
+            Some("foot".to_string()),
+            start + Duration::from_secs(600),
+            date,
+        );
+        usage.accrue(start + Duration::from_secs(660), date);
+        usage.focus_changed(None, start + Duration::from_secs(720), date);
+        assert_eq!(
+            usage.store.top_apps(date, 5),
+            vec![("firefox".to_string(), 600), ("foot".to_string(), 120)]
+        );
+
+        usage.paused = true;
+        usage.focus_changed(Some("foot".to_string()), start, date);
+        usage.focus_changed(None, start + Duration::from_secs(900), date);
+        assert_eq!(usage.store.top_apps(date, 5)[1].1, 120);
+
+        usage.save();
+        let reloaded = AppUsage::load(path.clone(), 30, false);
+        assert_eq!(reloaded.store, usage.store);
+        usage.purge();
+        assert!(!path.exists());
+        assert!(usage.store.top_apps(date, 5).is_empty());
+    }
+
+    #[test]
+    fn prunes_oldest_days() {
+        let mut store = UsageStore::default();
+        for day in ["2026-01-01", "2026-01-03", "2026-01-02"] {
+            store.add(day, "foot", 60);
+        }
+        store.prune(2);
+        assert_eq!(
+            store.days.keys().collect::<Vec<_>>(),
+            vec!["2026-01-02", "2026-01-03"]
+        );
+        assert_eq!(format_duration(3725), "1h 02m");
+    }
+}
@@ -1,15 +1,18 @@
-// PulseAudio input volume gauge with mute/adjust actions and device menu.
-// Consumes Settings: grelier.gauge.audio_in.step_percent.
+// PulseAudio input volume gauge with mute/adjust actions and device menu. Source outputs are
+// watched too, so the gauge turns to warning while any application records from a microphone.
+// Consumes Settings: grelier.gauge.audio_in.step_percent, grelier.gauge.audio_in.recording_ignore.
 use crate::dialog::info::InfoDialog;
 use crate::icon::{icon_quantity, svg_asset};
-use crate::panels::gauges::gauge::{Gauge, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar};
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar, GaugeStop,
+};
 use crate::panels::gauges::gauge::{
     GaugeClick, GaugeClickAction, GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem,
     GaugeMenuSlider, GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
 };
+use crate::panels::gauges::gauge_dependencies;
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
+use crate::settings::{self, SettingSpec};
 use libpulse_binding as pulse;
 use pulse::callbacks::ListResult;
 use pulse::context::subscribe::{Facility, InterestMaskSet};
@@ -28,12 +31,18 @@ use std::time::{Duration, Instant};
 const IDLE_WAIT: Duration = Duration::from_millis(25);
 #[cfg(not(test))]
 const IDLE_WAIT: Duration = Duration::from_millis(250);
-const DEFAULT_STEP_PERCENT: i8 = 5;
+settings::typed_settings! {
+    struct AudioInSettings {
+        step_percent: i8 = ("grelier.gauge.audio_in.step_percent", "5", -100..=100),
+        recording_ignore: String = ("grelier.gauge.audio_in.recording_ignore", "pavucontrol"),
+    }
+}
 const IDLE_RUN_INTERVAL_SECS: u64 = 300;
 const MENU_REFRESH_INTERVAL_SECS: u64 = 15;
 const MAX_LABEL_CHARS: usize = 92;
 
-fn format_level(percent: Option<u8>) -> GaugeDisplay {
+/// Warning attention while an application records, so an unexpected recording stands out.
+fn format_level(percent: Option<u8>, recording: bool) -> GaugeDisplay {
     match percent {
         Some(value) => {
             let ratio = if value == 0 {
@@ -43,7 +52,11 @@ fn format_level(percent: Option<u8>) -> GaugeDisplay {
             };
             GaugeDisplay::Value {
                 value: GaugeValue::Svg(icon_quantity(ratio)),
-                attention: GaugeValueAttention::Nominal,
+                attention: if recording {
+                    GaugeValueAttention::Warning
+                } else {
+                    GaugeValueAttention::Nominal
+                },
             }
         }
         None => GaugeDisplay::Error,
@@ -63,6 +76,7 @@ struct AudioInSignature {
     muted: Option<bool>,
     connected: bool,
     device_label: Option<String>,
+    recording: Vec<String>,
     menu: Vec<(String, bool)>,
 }
 
@@ -70,6 +84,7 @@ fn signature_for_snapshot(
     status: Option<SourceStatus>,
     connected: bool,
     device_label: Option<&str>,
+    recording: &[String],
     menu_items: &[GaugeMenuItem],
 ) -> AudioInSignature {
     AudioInSignature {
@@ -77,6 +92,7 @@ fn signature_for_snapshot(
         muted: status.map(|s| s.muted),
         connected,
         device_label: device_label.map(ToString::to_string),
+        recording: recording.to_vec(),
         menu: menu_items
             .iter()
             .map(|item| (item.id.clone(), item.selected))
@@ -84,6 +100,41 @@ fn signature_for_snapshot(
     }
 }
 
+/// A stream recording from a source, as reported by the server.
+#[derive(Debug, Clone)]
+struct SourceOutputEntry {
+    /// `application.name`, falling back to the stream name.
+    app: String,
+    /// `application.process.binary`, if the client set it.
+    binary: Option<String>,
+    /// Paused streams capture nothing.
+    corked: bool,
+    /// Recording from a sink monitor (what is playing) rather than a microphone.
+    from_monitor: bool,
+}
+
+/// Applications recording from a microphone, sorted and listed once each. Entries in
+/// `ignore` match the application name or binary, ignoring case.
+fn recording_apps(outputs: &[SourceOutputEntry], ignore: &[String]) -> Vec<String> {
+    let ignored = |output: &SourceOutputEntry| {
+        ignore.iter().any(|name| {
+            output.app.eq_ignore_ascii_case(name)
+                || output
+                    .binary
+                    .as_deref()
+                    .is_some_and(|binary| binary.eq_ignore_ascii_case(name))
+        })
+    };
+    let mut apps: Vec<String> = outputs
+        .iter()
+        .filter(|output| !output.corked && !output.from_monitor && !ignored(output))
+        .map(|output| output.app.clone())
+        .collect();
+    apps.sort();
+    apps.dedup();
+    apps
+}
+
 #[derive(Clone)]
 struct SourceMenuEntry {
     name: String,
@@ -268,6 +319,85 @@ fn collect_sources(mainloop: &mut Mainloop, context: &Context) -> Option<Vec<Sou
     Some(entries)
 }
 
+fn monitor_source_indices(mainloop: &mut Mainloop, context: &Context) -> Option<Vec<u32>> {
+    let indices = Rc::new(RefCell::new(Vec::new()));
+    let done = Rc::new(Cell::new(false));
+
+    {
+        let indices = Rc::clone(&indices);
+        let done = Rc::clone(&done);
+        context
+            .introspect()
+            .get_source_info_list(move |result| match result {
+                ListResult::Item(info) => {
+                    if info.monitor_of_sink.is_some() {
+                        indices.borrow_mut().push(info.index);
+                    }
+                }
+                ListResult::End | ListResult::Error => done.set(true),
+            });
+    }
+
+    while !done.get() {
+        iterate(mainloop)?;
+        if matches!(
+            context.get_state(),
+            ContextState::Failed | ContextState::Terminated
+        ) {
+            return None;
+        }
+    }
+
+    Some(indices.borrow().clone())
+}
+
+fn collect_source_outputs(
+    mainloop: &mut Mainloop,
+    context: &Context,
+) -> Option<Vec<SourceOutputEntry>> {
+    let monitors = monitor_source_indices(mainloop, context)?;
+    let outputs = Rc::new(RefCell::new(Vec::new()));
+    let done = Rc::new(Cell::new(false));
+
+    {
+        let outputs = Rc::clone(&outputs);
+        let done = Rc::clone(&done);
+        context
+            .introspect()
+            .get_source_output_info_list(move |result| match result {
+                ListResult::Item(info) => {
+                    let app = info
+                        .proplist
+                        .get_str(pulse::proplist::properties::APPLICATION_NAME)
+                        .or_else(|| info.name.as_ref().map(|name| name.to_string()))
+                        .unwrap_or_else(|| "Unknown".to_string());
+                    outputs.borrow_mut().push(SourceOutputEntry {
+                        app,
+                        binary: info
+                            .proplist
+                            .get_str(pulse::proplist::properties::APPLICATION_PROCESS_BINARY),
+                        corked: info.corked,
+                        from_monitor: monitors.contains(&info.source),
+                    });
+                }
+                ListResult::End | ListResult::Error => done.set(true),
+            });
+    }
+
+    while !done.get() {
+        iterate(mainloop)?;
+        if matches!(
+            context.get_state(),
+            ContextState::Failed | ContextState::Terminated
+        ) {
+            return None;
+        }
+    }
+
+    let entries = outputs.borrow().clone();
+    Some(entries)
+}
+
 fn sources_to_menu_items(
     entries: &[SourceMenuEntry],
     default_source: Option<&str>,
@@ -288,6 +418,7 @@ fn sources_to_menu_items(
                 id: entry.name.clone(),
                 label,
                 selected: default_source.map(|d| d == entry.name).unwrap_or(false),
+                exec: None,
             }
         })
         .collect()
@@ -384,6 +515,8 @@ struct AudioInSnapshot {
     status: Option<SourceStatus>,
     menu_items: Option<Vec<GaugeMenuItem>>,
     device_label: Option<String>,
+    /// Applications currently recording from a microphone.
+    recording: Vec<String>,
     connected: bool,
 }
 
@@ -393,6 +526,7 @@ impl AudioInSnapshot {
             status: None,
             menu_items: None,
             device_label: None,
+            recording: Vec::new(),
             connected: false,
         }
     }
@@ -403,6 +537,7 @@ fn snapshot_audio_in_from_context(
     context: &Context,
     now: Instant,
     menu_cache: &mut AudioInMenuCache,
+    recording_ignore: &[String],
 ) -> AudioInSnapshot {
     let source = default_source_name(mainloop, context);
     // Rebuild source menu infrequently unless the default source changed.
@@ -435,10 +570,15 @@ fn snapshot_audio_in_from_context(
             .unwrap_or_else(|| device_label_for_source(None, name))
     });
 
+    let recording = collect_source_outputs(mainloop, context)
+        .map(|outputs| recording_apps(&outputs, recording_ignore))
+        .unwrap_or_default();
+
     AudioInSnapshot {
         status,
         menu_items: menu_cache.menu_items.clone(),
         device_label,
+        recording,
         connected: true,
     }
 }
@@ -446,7 +586,9 @@ fn snapshot_audio_in_from_context(
 fn run_audio_in_worker(
     command_rx: mpsc::Receiver<InputCommand>,
     snapshot_tx: mpsc::Sender<AudioInSnapshot>,
+    recording_ignore: &[String],
     ready_notify: GaugeReadyNotify,
+    stop: &GaugeStop,
 ) {
     let mut mainloop = match Mainloop::new() {
         Some(mainloop) => mainloop,
@@ -476,12 +618,18 @@ fn run_audio_in_worker(
     context.set_subscribe_callback(Some(Box::new({
         let refresh_needed = Rc::clone(&refresh_needed);
         move |facility, _operation, _index| {
-            if matches!(facility, Some(Facility::Source) | Some(Facility::Server)) {
+            if matches!(
+                facility,
+                Some(Facility::Source) | Some(Facility::SourceOutput) | Some(Facility::Server)
+            ) {
                 refresh_needed.set(true);
             }
         }
     })));
-    context.subscribe(InterestMaskSet::SOURCE | InterestMaskSet::SERVER, |_| {});
+    context.subscribe(
+        InterestMaskSet::SOURCE | InterestMaskSet::SOURCE_OUTPUT | InterestMaskSet::SERVER,
+        |_| {},
+    );
     let mut menu_cache = AudioInMenuCache {
         menu_items: None,
         source_labels: HashMap::new(),
@@ -490,7 +638,7 @@ fn run_audio_in_worker(
     };
     let mut last_signature: Option<AudioInSignature> = None;
 
-    loop {
+    while !stop.is_stopped() {
         while let Ok(command) = command_rx.try_recv() {
             if apply_input_command(command, &mut mainloop, &mut context).is_none() {
                 let _ = snapshot_tx.send(AudioInSnapshot::disconnected());
@@ -506,12 +654,14 @@ fn run_audio_in_worker(
                 &context,
                 Instant::now(),
                 &mut menu_cache,
+                recording_ignore,
             );
             let empty_menu = Vec::new();
             let signature = signature_for_snapshot(
                 snapshot.status,
                 snapshot.connected,
                 snapshot.device_label.as_deref(),
+                &snapshot.recording,
                 snapshot.menu_items.as_deref().unwrap_or(&empty_menu),
             );
             // Coalesce unchanged snapshots before waking the scheduler.
@@ -555,11 +705,19 @@ fn run_audio_in_worker(
 struct AudioInEventSource {
     command_rx: mpsc::Receiver<InputCommand>,
     snapshot_tx: mpsc::Sender<AudioInSnapshot>,
+    /// Applications not reported as recording, such as volume meters.
+    recording_ignore: Vec<String>,
 }
 
 impl GaugeEventSource for AudioInEventSource {
-    fn run(self: Box<Self>, notify: GaugeReadyNotify) {
-        run_audio_in_worker(self.command_rx, self.snapshot_tx, notify);
+    fn run(self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
+        run_audio_in_worker(
+            self.command_rx,
+            self.snapshot_tx,
+            &self.recording_ignore,
+            notify,
+            &stop,
+        );
     }
 }
 
@@ -614,6 +772,7 @@ impl Gauge for AudioInGauge {
             status,
             snapshot.connected,
             Some(&device_label),
+            &snapshot.recording,
             &menu_snapshot,
         );
         if self.last_signature.as_ref() == Some(&signature) {
@@ -660,21 +819,36 @@ impl Gauge for AudioInGauge {
             .unwrap_or_else(|| svg_asset("microphone.svg"));
         self.next_deadline = now + Duration::from_secs(IDLE_RUN_INTERVAL_SECS);
 
+        let mut lines = vec![
+            device_label,
+            match status {
+                Some(status) => format!("Level: {}%", status.percent),
+                None => "Level: N/A".to_string(),
+            },
+        ];
+        if snapshot.recording.is_empty() {
+            lines.push("No apps recording".to_string());
+        } else {
+            lines.push("Recording:".to_string());
+            lines.extend(snapshot.recording.iter().map(|app| format!("  {app}")));
+        }
+        if status.is_none() {
+            lines.extend(gauge_dependencies::missing_hints("audio_in"));
+        }
+
         Some(crate::panels::gauges::gauge::GaugeModel {
             id: "audio_in",
             icon,
-            display: format_level(status.map(|status| status.percent)),
+            display: format_level(
+                status.map(|status| status.percent),
+                !snapshot.recording.is_empty(),
+            ),
             interactions: GaugeInteractionModel {
                 left_click: GaugePointerInteraction {
                     info: Some(InfoDialog {
                         title: "Audio In".to_string(),
-                        lines: vec![
-                            device_label,
-                            match status {
-                                Some(status) => format!("Level: {}%", status.percent),
-                                None => "Level: N/A".to_string(),
-                            },
-                        ],
+                        lines,
+                        graph: None,
                     }),
                     ..GaugePointerInteraction::default()
                 },
@@ -708,10 +882,10 @@ impl Gauge for AudioInGauge {
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
-    let mut step_percent = settings::settings()
-        .get_parsed_or("grelier.gauge.audio_in.step_percent", DEFAULT_STEP_PERCENT);
+    let settings = AudioInSettings::load();
+    let mut step_percent = settings.step_percent;
     if step_percent == 0 {
-        step_percent = DEFAULT_STEP_PERCENT;
+        step_percent = AudioInSettings::default().step_percent;
     }
     let (command_tx, command_rx) = mpsc::channel::<InputCommand>();
     let (snapshot_tx, snapshot_rx) = mpsc::channel::<AudioInSnapshot>();
@@ -723,6 +897,13 @@ pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
         event_source: Some(AudioInEventSource {
             command_rx,
             snapshot_tx,
+            recording_ignore: settings
+                .recording_ignore
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(ToString::to_string)
+                .collect(),
         }),
         last_signature: None,
         next_deadline: now,
@@ -730,11 +911,7 @@ pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[SettingSpec {
-        key: "grelier.gauge.audio_in.step_percent",
-        default: "5",
-    }];
-    SETTINGS
+    AudioInSettings::SPECS
 }
 
 inventory::submit! {
@@ -782,7 +959,7 @@ mod tests {
 
     #[test]
     fn level_uses_ratio_icon() {
-        match format_level(Some(50)) {
+        match format_level(Some(50), false) {
             GaugeDisplay::Value {
                 value: GaugeValue::Svg(handle),
                 attention,
@@ -796,7 +973,41 @@ mod tests {
 
     #[test]
     fn level_is_none_on_missing_status() {
-        assert!(matches!(format_level(None), GaugeDisplay::Error));
+        assert!(matches!(format_level(None, true), GaugeDisplay::Error));
+    }
+
+    #[test]
+    fn recording_apps_skip_paused_monitor_and_ignored_streams() {
+        let output = |app: &str, binary: Option<&str>, corked, from_monitor| SourceOutputEntry {
+            app: app.to_string(),
+            binary: binary.map(ToString::to_string),
+            corked,
+            from_monitor,
+        };
+        let outputs = vec![
+            output("Firefox", Some("firefox"), false, false),
+            output("OBS", Some("obs"), false, true),
+            output("Zoom", None, true, false),
+            output(
+                "PulseAudio Volume Control",
+                Some("pavucontrol"),
+                false,
+                false,
+            ),
+            output("Firefox", Some("firefox"), false, false),
+            output("Audacity", None, false, false),
+        ];
+        assert_eq!(
+            recording_apps(&outputs, &["PAVUCONTROL".to_string()]),
+            vec!["Audacity", "Firefox"]
+        );
+        assert!(matches!(
+            format_level(Some(40), true),
+            GaugeDisplay::Value {
+                attention: GaugeValueAttention::Warning,
+                ..
+            }
+        ));
     }
 
     #[test]
@@ -836,16 +1047,19 @@ mod tests {
             id: "source-a".to_string(),
             label: "Source A".to_string(),
             selected: true,
+            exec: None,
         }];
         let status = Some(SourceStatus {
             percent: 42,
             muted: false,
             channels: 2,
         });
-        let a = signature_for_snapshot(status, true, Some("Mic"), &items);
-        let b = signature_for_snapshot(status, true, Some("Mic"), &items);
-        let c = signature_for_snapshot(status, true, Some("Webcam Mic"), &items);
+        let a = signature_for_snapshot(status, true, Some("Mic"), &[], &items);
+        let b = signature_for_snapshot(status, true, Some("Mic"), &[], &items);
+        let c = signature_for_snapshot(status, true, Some("Webcam Mic"), &[], &items);
+        let d = signature_for_snapshot(status, true, Some("Mic"), &["Zoom".to_string()], &items);
         assert_eq!(a, b);
         assert_ne!(a, c);
+        assert_ne!(a, d);
     }
 }
@@ -1,15 +1,18 @@
 // PulseAudio output volume gauge with mute/adjust actions and device menu.
-// Consumes Settings: grelier.gauge.audio_out.step_percent.
+// Consumes Settings: grelier.gauge.audio_out.*.
 use crate::dialog::info::InfoDialog;
+use crate::dialog::toast::{self, Toast};
 use crate::icon::{icon_quantity, svg_asset};
-use crate::panels::gauges::gauge::{Gauge, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar};
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar, GaugeStop,
+};
 use crate::panels::gauges::gauge::{
     GaugeClick, GaugeClickAction, GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem,
     GaugeMenuSlider, GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
 };
+use crate::panels::gauges::gauge_dependencies;
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
+use crate::settings::{self, SettingSpec};
 use libpulse_binding as pulse;
 use pulse::callbacks::ListResult;
 use pulse::context::subscribe::{Facility, InterestMaskSet};
@@ -28,7 +31,13 @@ use std::time::{Duration, Instant};
 const IDLE_WAIT: Duration = Duration::from_millis(25);
 #[cfg(not(test))]
 const IDLE_WAIT: Duration = Duration::from_millis(250);
-const DEFAULT_STEP_PERCENT: i8 = 5;
+settings::typed_settings! {
+    struct AudioOutSettings {
+        step_percent: i8 = ("grelier.gauge.audio_out.step_percent", "5", -100..=100),
+        max_percent: u8 = ("grelier.gauge.audio_out.max_percent", "100", 1..=150),
+        warn_above_max: bool = ("grelier.gauge.audio_out.warn_above_max", "true"),
+    }
+}
 const IDLE_RUN_INTERVAL_SECS: u64 = 300;
 const MENU_REFRESH_INTERVAL_SECS: u64 = 15;
 const MAX_LABEL_CHARS: usize = 92;
@@ -53,6 +62,8 @@ fn format_level(percent: Option<u8>) -> GaugeDisplay {
 #[derive(Clone, Copy)]
 struct SinkStatus {
     percent: u8,
+    /// Unclamped level, which can exceed 100 when another client boosts the sink.
+    actual_percent: u32,
     muted: bool,
     channels: u8,
 }
@@ -60,6 +71,7 @@ struct SinkStatus {
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct AudioOutSignature {
     percent: Option<u8>,
+    actual_percent: Option<u32>,
     muted: Option<bool>,
     connected: bool,
     device_label: Option<String>,
@@ -74,6 +86,7 @@ fn signature_for_snapshot(
 ) -> AudioOutSignature {
     AudioOutSignature {
         percent: status.map(|s| s.percent),
+        actual_percent: status.map(|s| s.actual_percent),
         muted: status.map(|s| s.muted),
         connected,
         device_label: device_label.map(ToString::to_string),
@@ -91,8 +104,21 @@ struct SinkMenuEntry {
 }
 
 fn percent_from_volume(volume: Volume) -> u8 {
-    let percent = (volume.0 as f64 * 100.0 / Volume::NORMAL.0 as f64).round();
-    percent.clamp(0.0, 99.0) as u8
+    actual_percent_from_volume(volume).min(99) as u8
+}
+
+fn actual_percent_from_volume(volume: Volume) -> u32 {
+    (volume.0 as f64 * 100.0 / Volume::NORMAL.0 as f64).round() as u32
+}
+
+/// Highest level the gauge itself sets; above 100 only when `max_percent` allows a boost.
+fn capped_percent(percent: u8, max_percent: u8) -> u8 {
+    percent.min(max_percent)
+}
+
+/// The sink sits above the cap, which only happens through another client.
+fn above_cap(status: &SinkStatus, max_percent: u8) -> bool {
+    status.actual_percent > u32::from(max_percent)
 }
 
 fn iterate(mainloop: &mut Mainloop) -> Option<()> {
@@ -180,6 +206,7 @@ fn read_sink_status(
                     let channels = info.volume.len();
                     *status.borrow_mut() = Some(SinkStatus {
                         percent,
+                        actual_percent: actual_percent_from_volume(avg),
                         muted,
                         channels,
                     });
@@ -282,6 +309,7 @@ fn sinks_to_menu_items(
                 id: entry.name.clone(),
                 label,
                 selected: default_sink.map(|d| d == entry.name).unwrap_or(false),
+                exec: None,
             }
         })
         .collect()
@@ -319,6 +347,7 @@ struct AudioOutMenuCache {
 
 fn apply_output_command(
     command: SoundCommand,
+    max_percent: u8,
     mainloop: &mut Mainloop,
     context: &mut Context,
 ) -> Option<()> {
@@ -344,7 +373,9 @@ fn apply_output_command(
                 && let Some(status) = read_sink_status(mainloop, context, &sink)
                 && status.channels > 0
             {
-                let new_percent = status.percent.saturating_add_signed(delta).clamp(0, 99);
+                // Step from the real level, which can sit above 100 when boosts are allowed.
+                let current = u8::try_from(status.actual_percent).unwrap_or(u8::MAX);
+                let new_percent = capped_percent(current.saturating_add_signed(delta), max_percent);
                 let mut volumes = ChannelVolumes::default();
                 volumes.set(status.channels, volume_from_percent(new_percent));
                 let operation = context.introspect().set_sink_volume_by_name(
@@ -361,7 +392,10 @@ fn apply_output_command(
                 && status.channels > 0
             {
                 let mut volumes = ChannelVolumes::default();
-                volumes.set(status.channels, volume_from_percent(percent.clamp(0, 99)));
+                volumes.set(
+                    status.channels,
+                    volume_from_percent(capped_percent(percent, max_percent)),
+                );
                 let operation = context.introspect().set_sink_volume_by_name(
                     &sink,
                     &volumes,
@@ -437,34 +471,45 @@ fn snapshot_audio_out_from_context(
     }
 }
 
+/// A mainloop and a context connected to the PulseAudio server.
+fn connect_context(name: &str) -> Option<(Mainloop, Context)> {
+    let mut mainloop = Mainloop::new()?;
+    let mut context = Context::new(&mainloop, name)?;
+    context.connect(None, FlagSet::NOFLAGS, None).ok()?;
+    wait_for_context_ready(&mut mainloop, &context)?;
+    Some((mainloop, context))
+}
+
+/// Step the default sink by `delta` percent for a `volume+N` binding, honouring `max_percent`.
+/// Runs on its own thread and connection so the UI thread never waits on the server.
+pub fn adjust_default_sink(delta: i8) {
+    let max_percent = AudioOutSettings::load().max_percent;
+    std::thread::spawn(move || {
+        let Some((mut mainloop, mut context)) = connect_context("grelier-volume-binding") else {
+            log::warn!("volume binding: cannot connect to the PulseAudio server");
+            return;
+        };
+        let _ = apply_output_command(
+            SoundCommand::AdjustVolume(delta),
+            max_percent,
+            &mut mainloop,
+            &mut context,
+        );
+    });
+}
+
 fn run_audio_out_worker(
+    max_percent: u8,
     command_rx: mpsc::Receiver<SoundCommand>,
     snapshot_tx: mpsc::Sender<AudioOutSnapshot>,
     ready_notify: GaugeReadyNotify,
+    stop: &GaugeStop,
 ) {
-    let mut mainloop = match Mainloop::new() {
-        Some(mainloop) => mainloop,
-        None => {
-            let _ = snapshot_tx.send(AudioOutSnapshot::disconnected());
-            ready_notify("audio_out");
-            return;
-        }
-    };
-    let mut context = match Context::new(&mainloop, "grelier-audio-out") {
-        Some(context) => context,
-        None => {
-            let _ = snapshot_tx.send(AudioOutSnapshot::disconnected());
-            ready_notify("audio_out");
-            return;
-        }
-    };
-    if context.connect(None, FlagSet::NOFLAGS, None).is_err()
-        || wait_for_context_ready(&mut mainloop, &context).is_none()
-    {
+    let Some((mut mainloop, mut context)) = connect_context("grelier-audio-out") else {
         let _ = snapshot_tx.send(AudioOutSnapshot::disconnected());
         ready_notify("audio_out");
         return;
-    }
+    };
 
     let refresh_needed = Rc::new(Cell::new(true));
     context.set_subscribe_callback(Some(Box::new({
@@ -484,9 +529,9 @@ fn run_audio_out_worker(
     };
     let mut last_signature: Option<AudioOutSignature> = None;
 
-    loop {
+    while !stop.is_stopped() {
         while let Ok(command) = command_rx.try_recv() {
-            if apply_output_command(command, &mut mainloop, &mut context).is_none() {
+            if apply_output_command(command, max_percent, &mut mainloop, &mut context).is_none() {
                 let _ = snapshot_tx.send(AudioOutSnapshot::disconnected());
                 ready_notify("audio_out");
                 return;
@@ -533,7 +578,8 @@ fn run_audio_out_worker(
 
         match recv_with_idle_wait(&command_rx) {
             Ok(command) => {
-                if apply_output_command(command, &mut mainloop, &mut context).is_none() {
+                if apply_output_command(command, max_percent, &mut mainloop, &mut context).is_none()
+                {
                     let _ = snapshot_tx.send(AudioOutSnapshot::disconnected());
                     ready_notify("audio_out");
                     return;
@@ -547,13 +593,20 @@ fn run_audio_out_worker(
 }
 
 struct AudioOutEventSource {
+    max_percent: u8,
     command_rx: mpsc::Receiver<SoundCommand>,
     snapshot_tx: mpsc::Sender<AudioOutSnapshot>,
 }
 
 impl GaugeEventSource for AudioOutEventSource {
-    fn run(self: Box<Self>, notify: GaugeReadyNotify) {
-        run_audio_out_worker(self.command_rx, self.snapshot_tx, notify);
+    fn run(self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
+        run_audio_out_worker(
+            self.max_percent,
+            self.command_rx,
+            self.snapshot_tx,
+            notify,
+            &stop,
+        );
     }
 }
 
@@ -561,6 +614,12 @@ impl GaugeEventSource for AudioOutEventSource {
 struct AudioOutGauge {
     /// Volume adjustment delta applied for each scroll/click step.
     step_percent: i8,
+    /// Ceiling for levels set from the gauge.
+    max_percent: u8,
+    /// Raise a toast when another client pushes the level above `max_percent`.
+    warn_above_max: bool,
+    /// A toast was raised for the current excursion above the cap.
+    warned: bool,
     /// Channel used by UI actions to send output commands.
     command_tx: mpsc::Sender<SoundCommand>,
     /// Receives the latest audio-output snapshot from the event source.
@@ -654,21 +713,49 @@ impl Gauge for AudioOutGauge {
             .unwrap_or_else(|| svg_asset("speaker.svg"));
         self.next_deadline = now + Duration::from_secs(IDLE_RUN_INTERVAL_SECS);
 
+        let mut lines = vec![
+            device_label.clone(),
+            match status {
+                Some(status) => format!("Level: {}%", status.percent),
+                None => "Level: N/A".to_string(),
+            },
+        ];
+        if status.is_none() {
+            lines.extend(gauge_dependencies::missing_hints("audio_out"));
+        }
+
+        let mut display = format_level(status.map(|status| status.percent));
+        let boosted = status.filter(|status| above_cap(status, self.max_percent));
+        if let Some(status) = boosted {
+            lines.push(format!(
+                "Boosted to {}%, above the {}% cap",
+                status.actual_percent, self.max_percent
+            ));
+            if let GaugeDisplay::Value { attention, .. } = &mut display {
+                *attention = GaugeValueAttention::Warning;
+            }
+            if self.warn_above_max && !self.warned {
+                toast::show(
+                    Toast::new(
+                        "Volume above cap",
+                        format!("{device_label} is at {}%", status.actual_percent),
+                    )
+                    .with_attention(GaugeValueAttention::Warning),
+                );
+            }
+        }
+        self.warned = boosted.is_some();
+
         Some(crate::panels::gauges::gauge::GaugeModel {
             id: "audio_out",
             icon,
-            display: format_level(status.map(|status| status.percent)),
+            display,
             interactions: GaugeInteractionModel {
                 left_click: GaugePointerInteraction {
                     info: Some(InfoDialog {
                         title: "Audio Out".to_string(),
-                        lines: vec![
-                            device_label,
-                            match status {
-                                Some(status) => format!("Level: {}%", status.percent),
-                                None => "Level: N/A".to_string(),
-                            },
-                        ],
+                        lines,
+                        graph: None,
                     }),
                     ..GaugePointerInteraction::default()
                 },
@@ -702,19 +789,23 @@ impl Gauge for AudioOutGauge {
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
-    let mut step_percent = settings::settings()
-        .get_parsed_or("grelier.gauge.audio_out.step_percent", DEFAULT_STEP_PERCENT);
+    let settings = AudioOutSettings::load();
+    let mut step_percent = settings.step_percent;
     if step_percent == 0 {
-        step_percent = DEFAULT_STEP_PERCENT;
+        step_percent = AudioOutSettings::default().step_percent;
     }
     let (command_tx, command_rx) = mpsc::channel::<SoundCommand>();
     let (snapshot_tx, snapshot_rx) = mpsc::channel::<AudioOutSnapshot>();
 
     Box::new(AudioOutGauge {
         step_percent,
+        max_percent: settings.max_percent,
+        warn_above_max: settings.warn_above_max,
+        warned: false,
         command_tx,
         snapshot_rx,
         event_source: Some(AudioOutEventSource {
+            max_percent: settings.max_percent,
             command_rx,
             snapshot_tx,
         }),
@@ -724,11 +815,7 @@ pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[SettingSpec {
-        key: "grelier.gauge.audio_out.step_percent",
-        default: "5",
-    }];
-    SETTINGS
+    AudioOutSettings::SPECS
 }
 
 inventory::submit! {
@@ -803,6 +890,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cap_limits_gauge_changes_and_flags_external_boosts() {
+        assert_eq!(capped_percent(95, 80), 80);
+        assert_eq!(capped_percent(60, 80), 60);
+        assert_eq!(capped_percent(99, 100), 99);
+        assert_eq!(capped_percent(140, 150), 140);
+
+        let boosted = SinkStatus {
+            percent: percent_from_volume(Volume(Volume::NORMAL.0 / 2 * 3)),
+            actual_percent: actual_percent_from_volume(Volume(Volume::NORMAL.0 / 2 * 3)),
+            muted: false,
+            channels: 2,
+        };
+        assert_eq!(boosted.percent, 99);
+        assert_eq!(boosted.actual_percent, 150);
+        assert!(above_cap(&boosted, 100));
+        assert!(!above_cap(
+            &SinkStatus {
+                actual_percent: 100,
+                ..boosted
+            },
+            100
+        ));
+    }
+
     #[test]
     fn idle_wait_blocks_when_no_command_is_available() {
         let (_tx, rx) = mpsc::channel::<SoundCommand>();
@@ -823,9 +935,11 @@ mod tests {
             id: "sink-a".to_string(),
             label: "Sink A".to_string(),
             selected: true,
+            exec: None,
         }];
         let status = Some(SinkStatus {
             percent: 55,
+            actual_percent: 55,
             muted: false,
             channels: 2,
         });
@@ -1,26 +1,44 @@
-// Battery gauge driven by udev power_supply events and snapshots.
+// Battery gauge driven by udev power_supply events and snapshots, sampling power draw for a
+// live graph in its info dialog. Several system batteries are shown as one combined charge,
+// with a per-battery breakdown in the dialog.
 use crate::dialog::info::InfoDialog;
+use crate::dialog::toast::{self, Toast};
 use crate::icon::{icon_quantity, svg_asset};
-use crate::panels::gauges::gauge::{Gauge, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar};
+use crate::panels::gauges::battery_profile;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar, GaugeStop,
+};
 use crate::panels::gauges::gauge::{
     GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel,
     GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
 };
+use crate::panels::gauges::gauge_dependencies;
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
+use crate::panels::gauges::net_common::RateHistory;
+use crate::panels::gauges::proc_common::{self, ProcessCpuTracker};
+use crate::session_kind;
+use crate::settings::{self, SettingSpec};
 use battery::State as BatteryState;
 use battery::units::{energy::watt_hour, time::second};
 use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::os::fd::AsRawFd;
+use std::path::Path;
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use zbus::blocking::{Connection, Proxy};
 use zbus::zvariant::OwnedValue;
 
-const DEFAULT_WARNING_PERCENT: u8 = 49;
-const DEFAULT_DANGER_PERCENT: u8 = 19;
+settings::typed_settings! {
+    struct BatterySettings {
+        warning_percent: u8 = ("grelier.gauge.battery.warning_percent", "49", 0..=100),
+        danger_percent: u8 = ("grelier.gauge.battery.danger_percent", "19", 0..=100),
+        power_sample_secs: u64 = ("grelier.gauge.battery.power_sample_secs", "5", 1..=60),
+        power_history_secs: u64 = ("grelier.gauge.battery.power_history_secs", "300", 10..=3600),
+        top_processes: usize = ("grelier.gauge.battery.top_processes", "8", 0..=32),
+    }
+}
 const VALUE_ICON_SUCCESS_THRESHOLD: u8 = 50;
 const VALUE_ICON_WARNING_THRESHOLD: u8 = 10;
 const IDLE_RUN_INTERVAL_SECS: u64 = 300;
@@ -44,7 +62,7 @@ struct PowerProfilesSnapshot {
 struct BatteryEventSource;
 
 impl GaugeEventSource for BatteryEventSource {
-    fn run(self: Box<Self>, notify: GaugeReadyNotify) {
+    fn run(self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
         let monitor = match udev::MonitorBuilder::new()
             .and_then(|builder| builder.match_subsystem("power_supply"))
             .and_then(|builder| builder.listen())
@@ -56,8 +74,26 @@ impl GaugeEventSource for BatteryEventSource {
             }
         };
 
-        for _event in monitor.iter() {
-            notify("battery");
+        // The monitor socket is nonblocking, so wait for events before draining them.
+        let mut pollfd = libc::pollfd {
+            fd: monitor.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = GaugeStop::CHECK_INTERVAL.as_millis() as libc::c_int;
+        while !stop.is_stopped() {
+            // SAFETY: `pollfd` is one valid entry for the duration of the call.
+            if unsafe { libc::poll(&mut pollfd, 1, timeout) } < 0 {
+                let err = std::io::Error::last_os_error();
+                if err.raw_os_error() == Some(libc::EINTR) {
+                    continue;
+                }
+                log::error!("battery gauge: udev monitor failed: {err}");
+                return;
+            }
+            if monitor.iter().count() > 0 {
+                notify("battery");
+            }
         }
     }
 }
@@ -69,6 +105,13 @@ fn is_battery(dev: &udev::Device) -> bool {
         .unwrap_or(false)
 }
 
+/// Batteries powering the machine; peripherals such as mice report a `Device` scope.
+fn is_system_battery(dev: &udev::Device) -> bool {
+    is_battery(dev)
+        && !property_str(dev, "POWER_SUPPLY_SCOPE")
+            .is_some_and(|scope| scope.eq_ignore_ascii_case("Device"))
+}
+
 fn is_mains(dev: &udev::Device) -> bool {
     dev.property_value("POWER_SUPPLY_TYPE")
         .and_then(|v| v.to_str())
@@ -89,6 +132,7 @@ fn mains_online(dev: &udev::Device) -> Option<bool> {
 fn snapshot_model(
     warning_percent: u8,
     danger_percent: u8,
+    power: &mut PowerDraw,
     info_state: &Arc<Mutex<InfoDialog>>,
     manager: Option<&battery::Manager>,
     menu_select: Option<&MenuSelectAction>,
@@ -114,38 +158,64 @@ fn snapshot_model(
         }
     };
 
-    let mut battery_dev: Option<udev::Device> = None;
+    let mut battery_devs: Vec<udev::Device> = Vec::new();
     let mut ac_online: Option<bool> = None;
 
     for dev in devices {
-        if battery_dev.is_none() && is_battery(&dev) {
-            battery_dev = Some(dev);
+        if is_system_battery(&dev) {
+            battery_devs.push(dev);
             continue;
         }
         if ac_online.is_none() && is_mains(&dev) {
             ac_online = mains_online(&dev);
         }
-        if battery_dev.is_some() && ac_online.is_some() {
-            break;
-        }
     }
 
-    if let Some(dev) = battery_dev {
-        update_info_state(info_state, battery_info_dialog(&dev, manager));
-        let status = property_str(&dev, "POWER_SUPPLY_STATUS");
+    if let Some(dev) = battery_devs.first() {
+        let (status, display, rate, dialog) = if battery_devs.len() > 1 {
+            let readings: Vec<BatteryReading> =
+                battery_devs.iter().map(BatteryReading::from_udev).collect();
+            let status = combined_status(&readings);
+            let capacity = combined_percent(&readings).map(|percent| percent.to_string());
+            (
+                status.clone(),
+                battery_value_from_strings(
+                    capacity.as_deref(),
+                    status.as_deref(),
+                    warning_percent,
+                    danger_percent,
+                ),
+                combined_rate_watts(&readings),
+                combined_info_dialog(&readings, status.as_deref()),
+            )
+        } else {
+            (
+                property_str(dev, "POWER_SUPPLY_STATUS"),
+                battery_value(dev, warning_percent, danger_percent),
+                discharge_rate_watts_from_udev(dev),
+                battery_info_dialog(dev, manager),
+            )
+        };
+        update_info_state(info_state, dialog);
         if ac_online.is_none() {
             ac_online = ac_online_from_status(status.as_deref());
         }
-        if let Some(display) = battery_value(&dev, warning_percent, danger_percent) {
+        battery_profile::power_source_changed(ac_online);
+        power.record(rate);
+        if let Some(display) = display {
             let icon = svg_asset(power_icon_for_status(status.as_deref(), ac_online));
             let menu = menu_select.and_then(|select| power_profile_menu(select.clone()));
+            let mut info = info_with_hints(info_state, menu.is_none());
+            if let Some(info) = &mut info {
+                power.annotate(info);
+            }
             return Some(GaugeModel {
                 id: "battery",
                 icon,
                 display,
                 interactions: GaugeInteractionModel {
                     left_click: GaugePointerInteraction {
-                        info: info_state.lock().ok().map(|info| info.clone()),
+                        info,
                         ..GaugePointerInteraction::default()
                     },
                     right_click: GaugePointerInteraction {
@@ -168,6 +238,7 @@ fn snapshot_model(
                 "ETA: Unknown".to_string(),
                 "Discharge rate: Unknown".to_string(),
             ],
+            graph: None,
         },
     );
     let menu = menu_select.and_then(|select| power_profile_menu(select.clone()));
@@ -177,7 +248,7 @@ fn snapshot_model(
         display: GaugeDisplay::Error,
         interactions: GaugeInteractionModel {
             left_click: GaugePointerInteraction {
-                info: info_state.lock().ok().map(|info| info.clone()),
+                info: info_with_hints(info_state, menu.is_none()),
                 ..GaugePointerInteraction::default()
             },
             right_click: GaugePointerInteraction {
@@ -189,6 +260,16 @@ fn snapshot_model(
     })
 }
 
+/// Current info dialog, explaining the missing power mode menu when there is none.
+fn info_with_hints(info_state: &Mutex<InfoDialog>, menu_missing: bool) -> Option<InfoDialog> {
+    let mut info = info_state.lock().ok()?.clone();
+    if menu_missing {
+        info.lines
+            .extend(gauge_dependencies::missing_hints("battery"));
+    }
+    Some(info)
+}
+
 fn battery_value(
     dev: &udev::Device,
     warning_percent: u8,
@@ -285,6 +366,7 @@ fn battery_info_dialog_from_manager(manager: &battery::Manager) -> Option<InfoDi
             format!("Current: {}", format_quantity(Some(current), Some("Wh"))),
             eta_line,
         ],
+        graph: None,
     })
 }
 
@@ -306,6 +388,7 @@ fn battery_info_dialog_from_udev(dev: &udev::Device, status: Option<&str>) -> In
             format!("Current: {}", format_quantity(current, unit)),
             eta_line,
         ],
+        graph: None,
     }
 }
 
@@ -341,6 +424,159 @@ fn battery_charge_values(dev: &udev::Device) -> (Option<f64>, Option<f64>, Optio
     (None, None, None)
 }
 
+/// Charge of one system battery, read once so several can be combined.
+#[derive(Debug, Clone, PartialEq)]
+struct BatteryReading {
+    /// Kernel name, such as `BAT0`.
+    name: String,
+    capacity: Option<u8>,
+    status: Option<String>,
+    /// Current charge, in `unit`.
+    now: Option<f64>,
+    /// Last full charge, in `unit`.
+    full: Option<f64>,
+    /// Charge when new, in `unit`.
+    full_design: Option<f64>,
+    unit: Option<&'static str>,
+    rate_watts: Option<f64>,
+}
+
+impl BatteryReading {
+    fn from_udev(dev: &udev::Device) -> Self {
+        let (full, now, unit) = battery_charge_values(dev);
+        let design_key = if unit == Some("Ah") {
+            "CHARGE_FULL_DESIGN"
+        } else {
+            "ENERGY_FULL_DESIGN"
+        };
+        Self {
+            name: dev.sysname().to_string_lossy().into_owned(),
+            capacity: property_str(dev, "POWER_SUPPLY_CAPACITY")
+                .or_else(|| property_str(dev, "CAPACITY"))
+                .and_then(|value| value.trim().parse().ok()),
+            status: property_str(dev, "POWER_SUPPLY_STATUS"),
+            now,
+            full,
+            full_design: property_num(dev, &format!("POWER_SUPPLY_{design_key}"))
+                .or_else(|| property_num(dev, design_key))
+                .map(|value| value / 1_000_000.0),
+            unit,
+            rate_watts: discharge_rate_watts_from_udev(dev),
+        }
+    }
+
+    /// Last full charge as a share of the design capacity.
+    fn health_percent(&self) -> Option<f64> {
+        match (self.full, self.full_design) {
+            (Some(full), Some(design)) if design > 0.0 => Some(full / design * 100.0),
+            _ => None,
+        }
+    }
+
+    /// One dialog line, such as "BAT1: 82%, Discharging, health 91%".
+    fn breakdown_line(&self) -> String {
+        let mut line = format!(
+            "{}: {}",
+            self.name,
+            self.capacity
+                .map_or_else(|| "Unknown".to_string(), |percent| format!("{percent}%"))
+        );
+        if let Some(status) = &self.status {
+            line.push_str(&format!(", {status}"));
+        }
+        if let Some(health) = self.health_percent() {
+            line.push_str(&format!(", health {health:.0}%"));
+        }
+        line
+    }
+}
+
+/// Combined charge: stored energy over total capacity when every battery reports it in the
+/// same unit, so a small battery counts for less, or else the mean of their percentages.
+fn combined_percent(readings: &[BatteryReading]) -> Option<u8> {
+    let unit = readings.first()?.unit;
+    let by_charge = readings
+        .iter()
+        .map(|reading| match (reading.now, reading.full) {
+            (Some(now), Some(full)) if reading.unit == unit => Some((now, full)),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    if let Some(charges) = by_charge {
+        let (now, full) = charges
+            .iter()
+            .fold((0.0, 0.0), |(now, full), (n, f)| (now + n, full + f));
+        if full > 0.0 {
+            return Some((now / full * 100.0).round().clamp(0.0, 100.0) as u8);
+        }
+    }
+    let percents: Vec<u32> = readings
+        .iter()
+        .filter_map(|reading| reading.capacity.map(u32::from))
+        .collect();
+    (!percents.is_empty()).then(|| (percents.iter().sum::<u32>() / percents.len() as u32) as u8)
+}
+
+/// Charging while any battery charges and discharging while any discharges, since firmware
+/// typically drains or fills the batteries one at a time.
+fn combined_status(readings: &[BatteryReading]) -> Option<String> {
+    let has = |wanted: &str| {
+        readings.iter().any(|reading| {
+            reading
+                .status
+                .as_deref()
+                .is_some_and(|status| status.eq_ignore_ascii_case(wanted))
+        })
+    };
+    if has("Charging") {
+        return Some("Charging".to_string());
+    }
+    if has("Discharging") {
+        return Some("Discharging".to_string());
+    }
+    readings.iter().find_map(|reading| reading.status.clone())
+}
+
+fn combined_rate_watts(readings: &[BatteryReading]) -> Option<f64> {
+    readings
+        .iter()
+        .filter_map(|reading| reading.rate_watts.map(f64::abs))
+        .reduce(|total, rate| total + rate)
+}
+
+/// Totals across all batteries, followed by one line per battery.
+fn combined_info_dialog(readings: &[BatteryReading], status: Option<&str>) -> InfoDialog {
+    let unit = readings.first().and_then(|reading| reading.unit);
+    let sum = |value: fn(&BatteryReading) -> Option<f64>| {
+        readings
+            .iter()
+            .filter(|reading| reading.unit == unit)
+            .filter_map(value)
+            .reduce(|total, value| total + value)
+    };
+    let total = sum(|reading| reading.full);
+    let current = sum(|reading| reading.now);
+    let rate = combined_rate_watts(readings);
+    let current_wh = current.filter(|_| unit == Some("Wh"));
+    let eta_line = match estimate_time_to_empty_seconds(status, current_wh, rate) {
+        Some(seconds) => format!("ETA: {}", format_duration(seconds)),
+        None if is_charging_status(status) => "ETA: Charging".to_string(),
+        None => "ETA: Unknown".to_string(),
+    };
+    let mut lines = vec![
+        format!("Total: {}", format_quantity(total, unit)),
+        format!("Current: {}", format_quantity(current, unit)),
+        eta_line,
+        discharge_rate_line(status, rate),
+    ];
+    lines.extend(readings.iter().map(BatteryReading::breakdown_line));
+    InfoDialog {
+        title: format!("Battery ({} batteries)", readings.len()),
+        lines,
+        graph: None,
+    }
+}
+
 fn discharge_rate_watts_from_udev(dev: &udev::Device) -> Option<f64> {
     let power = property_num(dev, "POWER_SUPPLY_POWER_NOW")
         .or_else(|| property_num(dev, "POWER_NOW"))
@@ -378,6 +614,104 @@ fn discharge_rate_line(status: Option<&str>, rate_watts: Option<f64>) -> String
     }
 }
 
+/// Recent power draw, sampled every run while a battery is present.
+struct PowerDraw {
+    history: RateHistory,
+    /// Most recent reading in watts; `None` when the battery reports no power or current.
+    latest: Option<f64>,
+    /// Span covered by a full history, for the info dialog label.
+    window: Duration,
+    /// Time between samples.
+    sample_interval: Duration,
+}
+
+impl PowerDraw {
+    fn new(sample_interval: Duration, window: Duration) -> Self {
+        let samples = (window.as_secs() / sample_interval.as_secs().max(1)).max(2) as usize;
+        Self {
+            history: RateHistory::new(samples),
+            latest: None,
+            window,
+            sample_interval,
+        }
+    }
+
+    /// Record a reading; charging and discharging both count as draw through the battery.
+    fn record(&mut self, watts: Option<f64>) {
+        self.latest = watts.map(f64::abs);
+        if let Some(watts) = self.latest {
+            self.history.push(watts);
+        }
+    }
+
+    /// Add the power line and the draw graph to the battery info dialog.
+    fn annotate(&self, info: &mut InfoDialog) {
+        let Some(latest) = self.latest else {
+            return;
+        };
+        info.lines.push(format!(
+            "Power: {}, peak {} in {}",
+            format_quantity(Some(latest), Some("W")),
+            format_quantity(Some(self.history.peak()), Some("W")),
+            format_duration(self.window.as_secs())
+        ));
+        info.graph = Some(self.history.graph());
+    }
+}
+
+/// Share of the power draw each app is estimated to cause, from its share of CPU time since
+/// the previous sample. Processes with the same name are counted as one app.
+fn power_use_lines(busiest: &[(String, u64)], watts: Option<f64>, limit: usize) -> Vec<String> {
+    let total: u64 = busiest.iter().map(|(_, ticks)| ticks).sum();
+    if total == 0 {
+        return vec!["No CPU activity since the last sample".to_string()];
+    }
+    let mut by_app: Vec<(&str, u64)> = Vec::new();
+    for (name, ticks) in busiest {
+        match by_app.iter_mut().find(|(app, _)| app == name) {
+            Some((_, app_ticks)) => *app_ticks += ticks,
+            None => by_app.push((name, *ticks)),
+        }
+    }
+    by_app.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    by_app
+        .into_iter()
+        .take(limit)
+        .map(|(app, ticks)| {
+            let share = ticks as f64 / total as f64;
+            match watts {
+                Some(watts) => format!(
+                    "{app}: ~{} ({:.0}%)",
+                    format_quantity(Some(watts * share), Some("W")),
+                    share * 100.0
+                ),
+                None => format!("{app}: {:.0}% of CPU time", share * 100.0),
+            }
+        })
+        .collect()
+}
+
+/// Apps using the most energy, estimated from CPU time, for the middle-click dialog.
+struct AppPowerUse {
+    processes: ProcessCpuTracker,
+    limit: usize,
+}
+
+impl AppPowerUse {
+    fn dialog(&mut self, watts: Option<f64>) -> Option<InfoDialog> {
+        if self.limit == 0 {
+            return None;
+        }
+        let processes = proc_common::read_processes(Path::new(proc_common::PROC_ROOT));
+        let busiest = self.processes.sample(&processes);
+        Some(InfoDialog {
+            title: "Battery power use".to_string(),
+            lines: power_use_lines(&busiest, watts, self.limit),
+            graph: None,
+        })
+    }
+}
+
 fn time_to_empty_seconds(dev: &udev::Device) -> Option<u64> {
     property_num(dev, "POWER_SUPPLY_TIME_TO_EMPTY_NOW")
         .or_else(|| property_num(dev, "TIME_TO_EMPTY_NOW"))
@@ -533,6 +867,7 @@ fn power_profile_menu(on_select: MenuSelectAction) -> Option<GaugeMenu> {
             id: profile.clone(),
             label: power_profile_label(profile),
             selected: profile == &snapshot.active,
+            exec: None,
         })
         .collect();
     items.sort_by(|a, b| a.label.cmp(&b.label));
@@ -705,10 +1040,36 @@ struct BatteryGauge {
     event_source: Option<BatteryEventSource>,
     /// Shared info dialog state updated by the battery event source.
     info_state: Arc<Mutex<InfoDialog>>,
+    /// Attention shown on the previous run; a toast is raised when it escalates.
+    last_attention: GaugeValueAttention,
+    /// Power draw samples graphed in the info dialog.
+    power: PowerDraw,
+    /// Per-app estimate shown on middle-click.
+    app_power: AppPowerUse,
     /// Scheduler deadline for the next run.
     next_deadline: Instant,
 }
 
+/// Toast for a battery level that just crossed into warning or danger.
+fn escalation_toast(previous: GaugeValueAttention, current: GaugeValueAttention) -> Option<Toast> {
+    if current <= previous {
+        return None;
+    }
+    match current {
+        GaugeValueAttention::Nominal => None,
+        GaugeValueAttention::Warning => {
+            Some(Toast::new("Battery low", "Consider plugging in soon.").with_attention(current))
+        }
+        GaugeValueAttention::Danger => Some(
+            Toast::new(
+                "Battery critically low",
+                "Plug in now to avoid losing work.",
+            )
+            .with_attention(current),
+        ),
+    }
+}
+
 impl Gauge for BatteryGauge {
     fn id(&self) -> &'static str {
         "battery"
@@ -746,36 +1107,53 @@ impl Gauge for BatteryGauge {
         });
 
         let manager = battery::Manager::new().ok();
-        self.next_deadline = now + Duration::from_secs(IDLE_RUN_INTERVAL_SECS);
 
-        snapshot_model(
+        let mut model = snapshot_model(
             self.warning_percent,
             self.danger_percent,
+            &mut self.power,
             &self.info_state,
             manager.as_ref(),
             Some(&menu_select),
-        )
+        );
+        if let Some(model) = &mut model {
+            model.interactions.middle_click.info = self.app_power.dialog(self.power.latest);
+        }
+        // Keep sampling for the graph while there is a battery reporting its draw.
+        self.next_deadline = now
+            + if self.power.latest.is_some() {
+                self.power.sample_interval
+            } else {
+                Duration::from_secs(IDLE_RUN_INTERVAL_SECS)
+            };
+        if let Some(GaugeModel {
+            display: GaugeDisplay::Value { attention, .. },
+            ..
+        }) = &model
+        {
+            if let Some(toast) = escalation_toast(self.last_attention, *attention) {
+                toast::show(toast);
+            }
+            self.last_attention = *attention;
+        }
+        model
     }
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
-    let warning_percent = settings::settings().get_parsed_or(
-        "grelier.gauge.battery.warning_percent",
-        DEFAULT_WARNING_PERCENT,
-    );
-    let danger_percent = settings::settings().get_parsed_or(
-        "grelier.gauge.battery.danger_percent",
-        DEFAULT_DANGER_PERCENT,
-    );
+    let settings = BatterySettings::load();
     let (command_tx, command_rx) = mpsc::channel::<BatteryCommand>();
 
     Box::new(BatteryGauge {
-        warning_percent,
-        danger_percent,
+        warning_percent: settings.warning_percent,
+        danger_percent: settings.danger_percent,
         command_tx,
         command_rx,
         ready_notify: None,
-        event_source: Some(BatteryEventSource),
+        // Outside the machine's own seat, udev events are not ours; polling still works.
+        event_source: session_kind::kind()
+            .owns_hardware()
+            .then_some(BatteryEventSource),
         info_state: Arc::new(Mutex::new(InfoDialog {
             title: "Battery".to_string(),
             lines: vec![
@@ -784,23 +1162,23 @@ pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
                 "ETA: Unknown".to_string(),
                 "Discharge rate: Unknown".to_string(),
             ],
+            graph: None,
         })),
+        last_attention: GaugeValueAttention::Nominal,
+        power: PowerDraw::new(
+            Duration::from_secs(settings.power_sample_secs),
+            Duration::from_secs(settings.power_history_secs),
+        ),
+        app_power: AppPowerUse {
+            processes: ProcessCpuTracker::default(),
+            limit: settings.top_processes,
+        },
         next_deadline: now,
     })
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[
-        SettingSpec {
-            key: "grelier.gauge.battery.warning_percent",
-            default: "49",
-        },
-        SettingSpec {
-            key: "grelier.gauge.battery.danger_percent",
-            default: "19",
-        },
-    ];
-    SETTINGS
+    BatterySettings::SPECS
 }
 
 inventory::submit! {
@@ -874,13 +1252,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn power_draw_graphs_recent_samples_against_the_peak() {
+        let mut power = PowerDraw::new(Duration::from_secs(5), Duration::from_secs(300));
+        power.record(Some(-6.0));
+        power.record(Some(12.0));
+        power.record(None);
+        let mut info = InfoDialog {
+            title: "Battery".to_string(),
+            lines: Vec::new(),
+            graph: None,
+        };
+        power.annotate(&mut info);
+        assert!(info.lines.is_empty(), "no line without a current reading");
+
+        power.record(Some(3.0));
+        power.annotate(&mut info);
+        assert_eq!(info.lines, vec!["Power: 3.00 W, peak 12.0 W in 5m"]);
+        assert_eq!(info.graph, Some(vec![0.5, 1.0, 0.25]));
+    }
+
     #[test]
     fn battery_value_formats_fallback_text() {
         let display = battery_value_from_strings(
             Some("abc"),
             Some("Discharging"),
-            DEFAULT_WARNING_PERCENT,
-            DEFAULT_DANGER_PERCENT,
+            BatterySettings::default().warning_percent,
+            BatterySettings::default().danger_percent,
         )
         .expect("value present");
         match display {
@@ -895,13 +1293,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn power_use_splits_draw_by_app_cpu_share() {
+        let busiest = vec![
+            ("firefox".to_string(), 30),
+            ("Web Content".to_string(), 40),
+            ("firefox".to_string(), 10),
+            ("sway".to_string(), 20),
+        ];
+        assert_eq!(
+            power_use_lines(&busiest, Some(9.0), 2),
+            vec!["Web Content: ~3.60 W (40%)", "firefox: ~3.60 W (40%)"]
+        );
+        assert_eq!(
+            power_use_lines(&busiest[3..], None, 5),
+            vec!["sway: 100% of CPU time"]
+        );
+        assert_eq!(
+            power_use_lines(&[], Some(9.0), 5),
+            vec!["No CPU activity since the last sample"]
+        );
+    }
+
+    #[test]
+    fn combines_batteries_weighted_by_capacity() {
+        let reading = |name: &str, capacity, status: &str, now, full| BatteryReading {
+            name: name.to_string(),
+            capacity: Some(capacity),
+            status: Some(status.to_string()),
+            now: Some(now),
+            full: Some(full),
+            full_design: Some(full * 1.25),
+            unit: Some("Wh"),
+            rate_watts: Some(-5.0),
+        };
+        let internal = reading("BAT0", 100, "Full", 24.0, 24.0);
+        let external = reading("BAT1", 25, "Discharging", 18.0, 72.0);
+        let readings = vec![internal.clone(), external];
+        assert_eq!(combined_percent(&readings), Some(44));
+        assert_eq!(combined_status(&readings).as_deref(), Some("Discharging"));
+        assert_eq!(combined_rate_watts(&readings), Some(10.0));
+
+        let dialog = combined_info_dialog(&readings, Some("Discharging"));
+        assert_eq!(dialog.title, "Battery (2 batteries)");
+        assert_eq!(
+            dialog.lines,
+            vec![
+                "Total: 96.0 Wh",
+                "Current: 42.0 Wh",
+                "ETA: 4h 12m",
+                "Discharge rate: 10.0 W",
+                "BAT0: 100%, Full, health 80%",
+                "BAT1: 25%, Discharging, health 80%",
+            ]
+        );
+
+        let unknown = BatteryReading {
+            now: None,
+            capacity: Some(50),
+            ..internal
+        };
+        assert_eq!(
+            combined_percent(&[unknown, reading("BAT1", 20, "Full", 1.0, 2.0)]),
+            Some(35)
+        );
+    }
+
+    #[test]
+    fn toasts_only_when_attention_escalates() {
+        use GaugeValueAttention::{Danger, Nominal, Warning};
+        assert_eq!(
+            escalation_toast(Nominal, Warning).map(|toast| toast.attention),
+            Some(Warning)
+        );
+        assert_eq!(
+            escalation_toast(Warning, Danger).map(|toast| toast.title),
+            Some("Battery critically low".to_string())
+        );
+        assert!(escalation_toast(Danger, Warning).is_none());
+        assert!(escalation_toast(Warning, Warning).is_none());
+    }
+
     #[test]
     fn battery_value_uses_icon_when_numeric() {
         let display = battery_value_from_strings(
             Some("50"),
             Some("Charging"),
-            DEFAULT_WARNING_PERCENT,
-            DEFAULT_DANGER_PERCENT,
+            BatterySettings::default().warning_percent,
+            BatterySettings::default().danger_percent,
         )
         .expect("value present");
         match display {
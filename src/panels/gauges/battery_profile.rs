@@ -0,0 +1,45 @@
+// Low-power settings profile: the battery gauge reports the power source, and while the machine
+// runs on battery the profile named by `grelier.profile.on_battery` is switched on.
+use crate::panels::gauges::gauge_tuning;
+use crate::settings;
+
+pub const ON_BATTERY_KEY: &str = "grelier.profile.on_battery";
+
+/// The profile to run with: the configured one on battery, none on AC. An empty name turns
+/// switching off.
+fn profile_for(ac_online: bool, on_battery: &str) -> Option<&str> {
+    let name = on_battery.trim();
+    (!ac_online && !name.is_empty()).then_some(name)
+}
+
+/// Apply the profile for the current power source. An unknown source leaves things as they are.
+pub fn power_source_changed(ac_online: Option<bool>) {
+    let Some(ac_online) = ac_online else {
+        return;
+    };
+    let store = settings::settings();
+    let on_battery = store.get_or(ON_BATTERY_KEY, "battery");
+    let profile = profile_for(ac_online, &on_battery);
+    if !store.set_profile(profile) {
+        return;
+    }
+    // Gauges load their settings once, so tunable intervals are handed over live.
+    gauge_tuning::reload();
+    match profile {
+        Some(name) => log::info!("On battery: switched to settings profile '{name}'"),
+        None => log::info!("On AC: switched settings profile off"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_profile_only_on_battery() {
+        assert_eq!(profile_for(false, "battery"), Some("battery"));
+        assert_eq!(profile_for(false, " quiet "), Some("quiet"));
+        assert_eq!(profile_for(true, "battery"), None);
+        assert_eq!(profile_for(false, ""), None);
+    }
+}
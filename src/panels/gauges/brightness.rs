@@ -1,25 +1,39 @@
-// Backlight brightness gauge with scroll adjustments via sysfs.
-// Consumes Settings: grelier.gauge.brightness.step_percent, grelier.gauge.brightness.refresh_interval_secs.
+// Brightness gauge with scroll adjustments via backlight sysfs and DDC/CI for external monitors.
+// The backlight is left out in clamshell mode, since the internal panel is off.
+// Consumes Settings: grelier.gauge.brightness.*.
 use crate::dialog::info::InfoDialog;
 use crate::icon::{icon_quantity, svg_asset};
-use crate::panels::gauges::gauge::{Gauge, GaugeReadyNotify};
+use crate::lid;
+use crate::panels::gauges::ddc_common::{self, DdcDisplay, VCP_BRIGHTNESS};
 use crate::panels::gauges::gauge::{
-    GaugeClick, GaugeClickAction, GaugeDisplay, GaugeInput, GaugeInteractionModel, GaugeValue,
-    GaugeValueAttention,
+    Gauge, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar, GaugeStop,
 };
+use crate::panels::gauges::gauge::{
+    GaugeClick, GaugeClickAction, GaugeDisplay, GaugeInput, GaugeInteractionModel, GaugeMenu,
+    GaugeMenuItem, GaugeValue, GaugeValueAttention, MenuSelectAction,
+};
+use crate::panels::gauges::gauge_dependencies;
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
+use crate::settings::{self, SettingSpec};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use std::sync::mpsc::{self};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-const DEFAULT_STEP_PERCENT: i8 = 5;
-const DEFAULT_REFRESH_INTERVAL_SECS: u64 = 2;
+settings::typed_settings! {
+    struct BrightnessSettings {
+        step_percent: i8 = ("grelier.gauge.brightness.step_percent", "5", -100..=100),
+        refresh_interval_secs: u64 = ("grelier.gauge.brightness.refresh_interval_secs", "2", 1..=3600),
+        ddc: bool = ("grelier.gauge.brightness.ddc", "true"),
+        ddc_refresh_interval_secs: u64 = ("grelier.gauge.brightness.ddc_refresh_interval_secs", "30", 5..=3600),
+        device: String = ("grelier.gauge.brightness.device", ""),
+    }
+}
 const ABS_MAX_PERCENT: u8 = 100;
+const DEVICE_KEY: &str = "grelier.gauge.brightness.device";
 const SYS_BACKLIGHT: &str = "/sys/class/backlight";
 
 fn brightness_value(percent: Option<u8>) -> GaugeDisplay {
@@ -108,21 +122,183 @@ impl Backlight {
     }
 
     fn adjust_percent(&self, delta: i8) -> io::Result<u8> {
-        let current = self.percent()? as i16;
-        let next = (current + delta as i16).clamp(0, ABS_MAX_PERCENT as i16) as u8;
+        let next = adjusted_percent(self.percent()?, delta);
         self.set_percent(next)?;
         Ok(next)
     }
 }
 
+fn adjusted_percent(current: u8, delta: i8) -> u8 {
+    (current as i16 + delta as i16).clamp(0, ABS_MAX_PERCENT as i16) as u8
+}
+
 enum BrightnessCommand {
     Adjust(i8),
+    /// Make the device with this id the one scrolling adjusts.
+    Select(String),
+}
+
+/// Latest `(current, max)` brightness per DDC/CI connector; `None` when the monitor did not answer.
+type DdcReadings = Arc<Mutex<BTreeMap<String, Option<(u16, u16)>>>>;
+
+/// Adjustment request handed to the DDC/CI thread.
+struct DdcAdjust {
+    connector: String,
+    delta: i8,
+}
+
+/// Event source that owns all DDC/CI traffic, which is too slow for the gauge's run loop.
+struct DdcWorker {
+    readings: DdcReadings,
+    adjust_rx: mpsc::Receiver<DdcAdjust>,
+    /// How often monitors are re-discovered and re-read.
+    refresh_interval: Duration,
+}
+
+impl DdcWorker {
+    fn publish(&self, readings: BTreeMap<String, Option<(u16, u16)>>) {
+        if let Ok(mut shared) = self.readings.lock() {
+            *shared = readings;
+        }
+    }
+
+    fn adjust(&self, display: &DdcDisplay, delta: i8) -> io::Result<(u16, u16)> {
+        let cached = self
+            .readings
+            .lock()
+            .ok()
+            .and_then(|readings| readings.get(&display.connector).copied().flatten());
+        let (current, max) = match cached {
+            Some(reading) => reading,
+            None => display.get_vcp(VCP_BRIGHTNESS)?,
+        };
+        let next = raw_from_percent(
+            adjusted_percent(percent_from_raw(current as u32, max as u32), delta),
+            max as u32,
+        ) as u16;
+        display.set_vcp(VCP_BRIGHTNESS, next)?;
+        Ok((next, max))
+    }
+}
+
+impl GaugeEventSource for DdcWorker {
+    fn run(self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
+        let mut displays: Vec<DdcDisplay> = Vec::new();
+        let mut next_scan = Instant::now();
+        loop {
+            let now = Instant::now();
+            if now >= next_scan {
+                displays = ddc_common::discover(Path::new(ddc_common::SYS_DRM));
+                let readings = displays
+                    .iter()
+                    .map(|display| {
+                        let reading = display
+                            .get_vcp(VCP_BRIGHTNESS)
+                            .inspect_err(|err| {
+                                log::debug!(
+                                    "brightness gauge: DDC/CI read from {}: {err}",
+                                    display.connector
+                                )
+                            })
+                            .ok();
+                        (display.connector.clone(), reading)
+                    })
+                    .collect();
+                self.publish(readings);
+                notify("brightness");
+                next_scan = now + self.refresh_interval;
+            }
+
+            match stop.recv_timeout(&self.adjust_rx, next_scan.saturating_duration_since(now)) {
+                Ok(DdcAdjust { connector, delta }) => {
+                    let Some(display) = displays.iter().find(|d| d.connector == connector) else {
+                        continue;
+                    };
+                    let reading = match self.adjust(display, delta) {
+                        Ok(reading) => Some(reading),
+                        Err(err) => {
+                            log::error!(
+                                "brightness gauge: failed to adjust {connector} over DDC/CI: {err}"
+                            );
+                            None
+                        }
+                    };
+                    if let Ok(mut readings) = self.readings.lock() {
+                        readings.insert(connector, reading);
+                    }
+                    notify("brightness");
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+}
+
+/// A display whose brightness the gauge can show and adjust.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BrightnessDevice {
+    /// Backlight name or DRM connector; also the menu item id.
+    id: String,
+    percent: Option<u8>,
+    /// Whether the device is driven over DDC/CI rather than the backlight.
+    ddc: bool,
+}
+
+/// The configured device, falling back to the first one (the backlight when present).
+fn selected_device<'a>(
+    devices: &'a [BrightnessDevice],
+    selected: &str,
+) -> Option<&'a BrightnessDevice> {
+    devices
+        .iter()
+        .find(|device| device.id == selected)
+        .or_else(|| devices.first())
+}
+
+fn device_menu(
+    devices: &[BrightnessDevice],
+    current: Option<&BrightnessDevice>,
+    on_select: MenuSelectAction,
+) -> Option<GaugeMenu> {
+    if devices.len() < 2 {
+        return None;
+    }
+    Some(GaugeMenu {
+        title: "Scroll Adjusts".to_string(),
+        items: devices
+            .iter()
+            .map(|device| GaugeMenuItem {
+                id: device.id.clone(),
+                label: device.id.clone(),
+                selected: current.is_some_and(|current| current.id == device.id),
+                exec: None,
+            })
+            .collect(),
+        on_select: Some(on_select),
+        slider: None,
+    })
+}
+
+fn percent_label(percent: Option<u8>) -> String {
+    match percent {
+        Some(value) => format!("{value}%"),
+        None => "N/A".to_string(),
+    }
 }
 
 /// Gauge that reads and adjusts display backlight brightness.
 struct BrightnessGauge {
     /// Cached backlight controller; re-discovered when unavailable.
     backlight: Option<Backlight>,
+    /// Id of the device scrolling adjusts; empty for the default.
+    selected: String,
+    /// External monitor readings published by the DDC/CI worker.
+    ddc_readings: DdcReadings,
+    /// Sender for DDC/CI adjustments; `None` when DDC/CI is disabled.
+    ddc_tx: Option<mpsc::Sender<DdcAdjust>>,
+    /// DDC/CI worker handed to the work manager on registration.
+    ddc_worker: Option<DdcWorker>,
     /// Brightness adjustment delta applied for each scroll/click step.
     step_percent: i8,
     /// Poll cadence for brightness reads and model refresh.
@@ -146,20 +322,37 @@ impl Gauge for BrightnessGauge {
         self.ready_notify = Some(notify);
     }
 
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(worker) = self.ddc_worker.take() {
+            registrar.add_event_source(Box::new(worker));
+        }
+    }
+
     fn next_deadline(&self) -> Instant {
         self.next_deadline
     }
 
     fn run_once(&mut self, now: Instant) -> Option<crate::panels::gauges::gauge::GaugeModel> {
-        while let Ok(BrightnessCommand::Adjust(delta)) = self.command_rx.try_recv() {
-            if self.backlight.is_none() {
-                self.backlight = Backlight::discover();
-            }
-            if let Some(ref ctl) = self.backlight
-                && let Err(err) = ctl.adjust_percent(delta)
-            {
-                log::error!("brightness gauge: failed to adjust brightness: {err}");
-                self.backlight = None;
+        while let Ok(command) = self.command_rx.try_recv() {
+            match command {
+                BrightnessCommand::Select(id) => {
+                    settings::settings().update(DEVICE_KEY, &id);
+                    self.selected = id;
+                }
+                BrightnessCommand::Adjust(delta) => {
+                    let devices = self.devices();
+                    match selected_device(&devices, &self.selected) {
+                        Some(device) if device.ddc => {
+                            if let Some(ddc_tx) = &self.ddc_tx {
+                                let _ = ddc_tx.send(DdcAdjust {
+                                    connector: device.id.clone(),
+                                    delta,
+                                });
+                            }
+                        }
+                        _ => self.adjust_backlight(delta),
+                    }
+                }
             }
         }
 
@@ -180,7 +373,49 @@ impl Gauge for BrightnessGauge {
             None
         };
 
-        let device_name = self.backlight.as_ref().map(|ctl| ctl.name.clone());
+        let mut devices = self.devices();
+        if let Some(ctl) = &self.backlight
+            && let Some(device) = devices.iter_mut().find(|device| device.id == ctl.name)
+        {
+            device.percent = percent;
+        }
+        let clamshell = lid::clamshell_active();
+        let current = selected_device(&devices, &self.selected);
+        let percent = current.and_then(|device| device.percent);
+        let display = if clamshell && devices.is_empty() {
+            GaugeDisplay::Empty
+        } else {
+            brightness_value(percent)
+        };
+        let mut lines = vec![
+            current
+                .map(|device| device.id.clone())
+                .unwrap_or_else(|| "No backlight device".to_string()),
+            format!("Brightness: {}", percent_label(percent)),
+        ];
+        lines.extend(
+            devices
+                .iter()
+                .filter(|device| current.is_none_or(|current| current.id != device.id))
+                .map(|device| format!("{}: {}", device.id, percent_label(device.percent))),
+        );
+        if clamshell {
+            lines.push("Lid closed: internal display off".to_string());
+        } else if devices.is_empty() {
+            lines.extend(gauge_dependencies::missing_hints("brightness"));
+        }
+        let menu_select: MenuSelectAction = {
+            let command_tx = self.command_tx.clone();
+            let ready_notify = self.ready_notify.clone();
+            Arc::new(move |id: String| {
+                let _ = command_tx.send(BrightnessCommand::Select(id));
+                if let Some(ready_notify) = &ready_notify {
+                    ready_notify("brightness");
+                }
+            })
+        };
+        let menu = device_menu(&devices, current, menu_select);
+
         let step_percent = self.step_percent;
         let command_tx = self.command_tx.clone();
         let ready_notify = self.ready_notify.clone();
@@ -205,21 +440,20 @@ impl Gauge for BrightnessGauge {
         Some(crate::panels::gauges::gauge::GaugeModel {
             id: "brightness",
             icon: svg_asset("brightness.svg"),
-            display: brightness_value(percent),
+            display,
             interactions: GaugeInteractionModel {
                 left_click: crate::panels::gauges::gauge::GaugePointerInteraction {
                     info: Some(InfoDialog {
                         title: "Brightness".to_string(),
-                        lines: vec![
-                            device_name.unwrap_or_else(|| "No backlight device".to_string()),
-                            match percent {
-                                Some(value) => format!("Brightness: {value}%"),
-                                None => "Brightness: N/A".to_string(),
-                            },
-                        ],
+                        lines,
+                        graph: None,
                     }),
                     ..crate::panels::gauges::gauge::GaugePointerInteraction::default()
                 },
+                right_click: crate::panels::gauges::gauge::GaugePointerInteraction {
+                    menu,
+                    ..crate::panels::gauges::gauge::GaugePointerInteraction::default()
+                },
                 scroll: crate::panels::gauges::gauge::GaugePointerInteraction {
                     on_input: Some(on_click),
                     ..crate::panels::gauges::gauge::GaugePointerInteraction::default()
@@ -230,23 +464,73 @@ impl Gauge for BrightnessGauge {
     }
 }
 
+impl BrightnessGauge {
+    /// The backlight, if any and not in clamshell mode, followed by external monitors found over DDC/CI.
+    fn devices(&self) -> Vec<BrightnessDevice> {
+        let mut devices: Vec<BrightnessDevice> = self
+            .backlight
+            .iter()
+            .filter(|_| !lid::clamshell_active())
+            .map(|ctl| BrightnessDevice {
+                id: ctl.name.clone(),
+                percent: None,
+                ddc: false,
+            })
+            .collect();
+        if let Ok(readings) = self.ddc_readings.lock() {
+            devices.extend(
+                readings
+                    .iter()
+                    .map(|(connector, reading)| BrightnessDevice {
+                        id: connector.clone(),
+                        percent: reading
+                            .map(|(current, max)| percent_from_raw(current as u32, max as u32)),
+                        ddc: true,
+                    }),
+            );
+        }
+        devices
+    }
+
+    fn adjust_backlight(&mut self, delta: i8) {
+        if self.backlight.is_none() {
+            self.backlight = Backlight::discover();
+        }
+        if let Some(ref ctl) = self.backlight
+            && let Err(err) = ctl.adjust_percent(delta)
+        {
+            log::error!("brightness gauge: failed to adjust brightness: {err}");
+            self.backlight = None;
+        }
+    }
+}
+
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
-    let mut step_percent = settings::settings().get_parsed_or(
-        "grelier.gauge.brightness.step_percent",
-        DEFAULT_STEP_PERCENT,
-    );
-    if step_percent == 0 {
-        step_percent = DEFAULT_STEP_PERCENT;
+    let mut settings = BrightnessSettings::load();
+    if settings.step_percent == 0 {
+        settings.step_percent = BrightnessSettings::default().step_percent;
     }
-    let refresh_interval_secs = settings::settings().get_parsed_or(
-        "grelier.gauge.brightness.refresh_interval_secs",
-        DEFAULT_REFRESH_INTERVAL_SECS,
-    );
     let (command_tx, command_rx) = mpsc::channel::<BrightnessCommand>();
+    let ddc_readings: DdcReadings = Arc::default();
+    let (ddc_tx, ddc_worker) = if settings.ddc {
+        let (adjust_tx, adjust_rx) = mpsc::channel();
+        let worker = DdcWorker {
+            readings: Arc::clone(&ddc_readings),
+            adjust_rx,
+            refresh_interval: Duration::from_secs(settings.ddc_refresh_interval_secs),
+        };
+        (Some(adjust_tx), Some(worker))
+    } else {
+        (None, None)
+    };
     Box::new(BrightnessGauge {
         backlight: None,
-        step_percent,
-        refresh_interval: Duration::from_secs(refresh_interval_secs),
+        selected: settings.device.trim().to_string(),
+        ddc_readings,
+        ddc_tx,
+        ddc_worker,
+        step_percent: settings.step_percent,
+        refresh_interval: Duration::from_secs(settings.refresh_interval_secs),
         command_tx,
         command_rx,
         ready_notify: None,
@@ -255,23 +539,13 @@ pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[
-        SettingSpec {
-            key: "grelier.gauge.brightness.step_percent",
-            default: "5",
-        },
-        SettingSpec {
-            key: "grelier.gauge.brightness.refresh_interval_secs",
-            default: "2",
-        },
-    ];
-    SETTINGS
+    BrightnessSettings::SPECS
 }
 
 inventory::submit! {
     GaugeSpec {
         id: "brightness",
-        description: "Brightness gauge controlling backlight percent level.",
+        description: "Brightness gauge controlling backlight and external monitor (DDC/CI) brightness.",
         default_enabled: false,
         settings,
         create: create_gauge,
@@ -308,6 +582,29 @@ mod tests {
         assert_eq!(percent_from_raw(500, 0), 0);
     }
 
+    #[test]
+    fn selects_configured_device_or_first() {
+        let device = |id: &str, ddc| BrightnessDevice {
+            id: id.to_string(),
+            percent: None,
+            ddc,
+        };
+        let devices = vec![device("intel_backlight", false), device("DP-1", true)];
+        assert_eq!(selected_device(&devices, "DP-1").map(|d| d.ddc), Some(true));
+        assert_eq!(
+            selected_device(&devices, "").map(|d| d.id.as_str()),
+            Some("intel_backlight")
+        );
+        assert!(selected_device(&[], "DP-1").is_none());
+
+        let menu = device_menu(&devices, devices.get(1), Arc::new(|_| {})).expect("menu");
+        let selected: Vec<bool> = menu.items.iter().map(|item| item.selected).collect();
+        assert_eq!(selected, vec![false, true]);
+        assert!(device_menu(&devices[..1], devices.first(), Arc::new(|_| {})).is_none());
+        assert_eq!(adjusted_percent(98, 5), 100);
+        assert_eq!(adjusted_percent(3, -5), 0);
+    }
+
     #[test]
     fn raw_from_percent_rounds() {
         assert_eq!(raw_from_percent(0, 100), 0);
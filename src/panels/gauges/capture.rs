@@ -0,0 +1,529 @@
+// Screenshot and screen recording gauge built on grim, slurp, and wf-recorder.
+// Consumes Settings: grelier.gauge.capture.*.
+use crate::compositor;
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    ActionSelectAction, GaugeActionDialog, GaugeActionItem, GaugeClickAction, GaugeDisplay,
+    GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue, GaugeValueAttention,
+};
+use crate::panels::gauges::gauge::{Gauge, GaugeReadyNotify};
+use crate::panels::gauges::gauge_dependencies;
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use chrono::Local;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Refresh cadence while recording, so the elapsed time ticks.
+const RECORDING_INTERVAL: Duration = Duration::from_secs(1);
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+/// Lets the action dialog close before a full-screen grab, so it is not in the shot.
+const DIALOG_CLOSE_DELAY: Duration = Duration::from_millis(300);
+
+settings::typed_settings! {
+    struct CaptureSettings {
+        screenshot_dir: String = ("grelier.gauge.capture.screenshot_dir", "~/Pictures"),
+        recording_dir: String = ("grelier.gauge.capture.recording_dir", "~/Videos"),
+        clipboard: bool = ("grelier.gauge.capture.clipboard", "true"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptureAction {
+    Region,
+    Window,
+    Screen,
+    Record,
+    Stop,
+}
+
+impl CaptureAction {
+    const ALL: [CaptureAction; 5] = [
+        Self::Region,
+        Self::Window,
+        Self::Screen,
+        Self::Record,
+        Self::Stop,
+    ];
+
+    fn item_id(self) -> &'static str {
+        match self {
+            Self::Region => "region",
+            Self::Window => "window",
+            Self::Screen => "screen",
+            Self::Record => "record",
+            Self::Stop => "stop",
+        }
+    }
+
+    fn from_item_id(item_id: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.item_id() == item_id)
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Region => "capture-region.svg",
+            Self::Window => "capture-window.svg",
+            Self::Screen => "capture-screen.svg",
+            Self::Record => "record.svg",
+            Self::Stop => "stop.svg",
+        }
+    }
+}
+
+/// Actions offered in the dialog; an active recording swaps "record" for "stop".
+fn dialog_actions(recording: bool) -> Vec<CaptureAction> {
+    CaptureAction::ALL
+        .into_iter()
+        .filter(|action| match action {
+            CaptureAction::Record => !recording,
+            CaptureAction::Stop => recording,
+            _ => true,
+        })
+        .collect()
+}
+
+fn expand_home(dir: &str) -> PathBuf {
+    match dir.trim().strip_prefix("~/") {
+        Some(rest) => std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_default()
+            .join(rest),
+        None => PathBuf::from(dir.trim()),
+    }
+}
+
+fn capture_path(dir: &Path, prefix: &str, stamp: &str, extension: &str) -> PathBuf {
+    dir.join(format!("{prefix}-{stamp}.{extension}"))
+}
+
+fn timestamp() -> String {
+    Local::now().format("%Y%m%d-%H%M%S").to_string()
+}
+
+/// Predefined boxes for `slurp -r`, one `x,y wxh` per line.
+fn slurp_boxes(rects: &[(i32, i32, i32, i32)]) -> String {
+    rects
+        .iter()
+        .map(|(x, y, width, height)| format!("{x},{y} {width}x{height}\n"))
+        .collect()
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Ask slurp for a geometry; `None` when the selection was cancelled.
+fn slurp(args: &[&str], boxes: Option<&str>) -> Result<Option<String>, String> {
+    let mut child = Command::new("slurp")
+        .args(args)
+        .stdin(if boxes.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("failed to run slurp: {err}"))?;
+    if let (Some(boxes), Some(mut stdin)) = (boxes, child.stdin.take()) {
+        stdin
+            .write_all(boxes.as_bytes())
+            .map_err(|err| format!("failed to write to slurp: {err}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("failed to wait for slurp: {err}"))?;
+    let geometry = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((output.status.success() && !geometry.is_empty()).then_some(geometry))
+}
+
+fn copy_image(path: &Path) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let status = Command::new("wl-copy")
+        .args(["--type", "image/png"])
+        .stdin(Stdio::from(file))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|err| format!("failed to run wl-copy: {err}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("wl-copy exited with {status}"))
+    }
+}
+
+/// Take a screenshot; `Ok(None)` when the user cancelled the selection.
+fn screenshot(
+    action: CaptureAction,
+    dir: &Path,
+    clipboard: bool,
+) -> Result<Option<PathBuf>, String> {
+    let geometry = match action {
+        CaptureAction::Region => {
+            let Some(geometry) = slurp(&[], None)? else {
+                return Ok(None);
+            };
+            Some(geometry)
+        }
+        CaptureAction::Window => {
+            let windows = compositor::backend().fetch_visible_windows()?;
+            let Some(geometry) = slurp(&["-r"], Some(&slurp_boxes(&windows)))? else {
+                return Ok(None);
+            };
+            Some(geometry)
+        }
+        CaptureAction::Screen => {
+            thread::sleep(DIALOG_CLOSE_DELAY);
+            None
+        }
+        CaptureAction::Record | CaptureAction::Stop => {
+            return Err(format!("{action:?} is not a screenshot"));
+        }
+    };
+
+    fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    let path = capture_path(dir, "screenshot", &timestamp(), "png");
+    let mut grim = Command::new("grim");
+    if let Some(geometry) = &geometry {
+        grim.arg("-g").arg(geometry);
+    }
+    let status = grim
+        .arg(&path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|err| format!("failed to run grim: {err}"))?;
+    if !status.success() {
+        return Err(format!("grim exited with {status}"));
+    }
+    if clipboard && let Err(err) = copy_image(&path) {
+        log::warn!("capture gauge: {err}");
+    }
+    Ok(Some(path))
+}
+
+/// A running wf-recorder process.
+struct Recording {
+    child: Child,
+    path: PathBuf,
+    started: Instant,
+}
+
+/// Let the user pick an output or region, then start recording it; `Ok(None)` when cancelled.
+fn start_recording(dir: &Path) -> Result<Option<Recording>, String> {
+    let Some(geometry) = slurp(&["-o"], None)? else {
+        return Ok(None);
+    };
+    fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    let path = capture_path(dir, "recording", &timestamp(), "mp4");
+    let child = Command::new("wf-recorder")
+        .arg("-g")
+        .arg(&geometry)
+        .arg("-f")
+        .arg(&path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("failed to run wf-recorder: {err}"))?;
+    Ok(Some(Recording {
+        child,
+        path,
+        started: Instant::now(),
+    }))
+}
+
+/// Interrupt wf-recorder so it finalizes the file, then reap it.
+fn stop_recording(mut recording: Recording) -> PathBuf {
+    // SAFETY: kill only sends a signal to the child we spawned.
+    unsafe {
+        libc::kill(recording.child.id() as libc::pid_t, libc::SIGINT);
+    }
+    if let Err(err) = recording.child.wait() {
+        log::warn!("capture gauge: failed to wait for wf-recorder: {err}");
+    }
+    recording.path
+}
+
+/// State shared between the gauge and the threads running its actions.
+#[derive(Clone)]
+struct CaptureContext {
+    screenshot_dir: PathBuf,
+    recording_dir: PathBuf,
+    clipboard: bool,
+    recording: Arc<Mutex<Option<Recording>>>,
+    /// Most recently saved file, shown in the info dialog.
+    last_saved: Arc<Mutex<Option<PathBuf>>>,
+    /// Notifier used to request an immediate scheduler wake-up after actions.
+    ready_notify: Option<GaugeReadyNotify>,
+}
+
+impl CaptureContext {
+    fn notify(&self) {
+        if let Some(ready_notify) = &self.ready_notify {
+            ready_notify("capture");
+        }
+    }
+
+    fn saved(&self, path: PathBuf) {
+        log::info!("capture gauge: saved {}", path.display());
+        if let Ok(mut last_saved) = self.last_saved.lock() {
+            *last_saved = Some(path);
+        }
+        self.notify();
+    }
+
+    /// Run `action` to completion; slurp and grim block, so callers use a worker thread.
+    fn perform(&self, action: CaptureAction) {
+        let result = match action {
+            CaptureAction::Record => {
+                if self.is_recording() {
+                    return;
+                }
+                start_recording(&self.recording_dir).map(|recording| {
+                    if let Some(recording) = recording
+                        && let Ok(mut slot) = self.recording.lock()
+                    {
+                        *slot = Some(recording);
+                        self.notify();
+                    }
+                })
+            }
+            CaptureAction::Stop => {
+                let recording = self.recording.lock().ok().and_then(|mut slot| slot.take());
+                if let Some(recording) = recording {
+                    self.saved(stop_recording(recording));
+                }
+                Ok(())
+            }
+            _ => screenshot(action, &self.screenshot_dir, self.clipboard).map(|path| {
+                if let Some(path) = path {
+                    self.saved(path);
+                }
+            }),
+        };
+        if let Err(err) = result {
+            log::error!("capture gauge: {err}");
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.recording
+            .lock()
+            .map(|slot| slot.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Elapsed time of the active recording, after reaping one that exited on its own.
+    fn recording_elapsed(&self) -> Option<Duration> {
+        let mut slot = self.recording.lock().ok()?;
+        let recording = slot.as_mut()?;
+        if let Ok(Some(status)) = recording.child.try_wait() {
+            log::warn!("capture gauge: wf-recorder exited with {status}");
+            let path = slot.take()?.path;
+            drop(slot);
+            self.saved(path);
+            return None;
+        }
+        Some(recording.started.elapsed())
+    }
+
+    fn spawn(&self, action: CaptureAction) {
+        let context = self.clone();
+        thread::spawn(move || context.perform(action));
+    }
+}
+
+fn capture_action_dialog(context: &CaptureContext, recording: bool) -> GaugeActionDialog {
+    let context = context.clone();
+    let on_select: ActionSelectAction = Arc::new(move |item_id: String| {
+        let Some(action) = CaptureAction::from_item_id(&item_id) else {
+            log::warn!("capture gauge: unknown action '{item_id}'");
+            return;
+        };
+        context.spawn(action);
+    });
+
+    GaugeActionDialog {
+        title: "Capture".to_string(),
+        items: dialog_actions(recording)
+            .into_iter()
+            .map(|action| GaugeActionItem {
+                id: action.item_id().to_string(),
+                icon: svg_asset(action.icon()),
+                exec: None,
+            })
+            .collect(),
+        on_select: Some(on_select),
+        confirm: None,
+    }
+}
+
+/// Gauge offering screenshots and screen recording, showing the elapsed time while recording.
+struct CaptureGauge {
+    context: CaptureContext,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for CaptureGauge {
+    fn id(&self) -> &'static str {
+        "capture"
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.context.ready_notify = Some(notify);
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        let elapsed = self.context.recording_elapsed();
+        let recording = elapsed.is_some();
+        self.next_deadline = now
+            + if recording {
+                RECORDING_INTERVAL
+            } else {
+                IDLE_INTERVAL
+            };
+
+        let (icon, display, left_click) = match elapsed {
+            Some(elapsed) => {
+                let context = self.context.clone();
+                let on_click: GaugeClickAction =
+                    Arc::new(move |_| context.spawn(CaptureAction::Stop));
+                (
+                    svg_asset("record.svg"),
+                    GaugeDisplay::Value {
+                        value: GaugeValue::Text(format_elapsed(elapsed)),
+                        attention: GaugeValueAttention::Danger,
+                    },
+                    GaugePointerInteraction {
+                        on_input: Some(on_click),
+                        ..GaugePointerInteraction::default()
+                    },
+                )
+            }
+            None => {
+                let mut lines = vec![match self
+                    .context
+                    .last_saved
+                    .lock()
+                    .ok()
+                    .and_then(|last| last.clone())
+                {
+                    Some(path) => format!("Last capture: {}", path.display()),
+                    None => "No captures yet".to_string(),
+                }];
+                lines.extend(gauge_dependencies::missing_hints("capture"));
+                (
+                    svg_asset("camera.svg"),
+                    GaugeDisplay::Empty,
+                    GaugePointerInteraction {
+                        info: Some(InfoDialog {
+                            title: "Capture".to_string(),
+                            lines,
+                            graph: None,
+                        }),
+                        ..GaugePointerInteraction::default()
+                    },
+                )
+            }
+        };
+
+        Some(GaugeModel {
+            id: "capture",
+            icon,
+            display,
+            interactions: GaugeInteractionModel {
+                left_click,
+                right_click: GaugePointerInteraction {
+                    action_dialog: Some(capture_action_dialog(&self.context, recording)),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = CaptureSettings::load();
+    Box::new(CaptureGauge {
+        context: CaptureContext {
+            screenshot_dir: expand_home(&settings.screenshot_dir),
+            recording_dir: expand_home(&settings.recording_dir),
+            clipboard: settings.clipboard,
+            recording: Arc::new(Mutex::new(None)),
+            last_saved: Arc::new(Mutex::new(None)),
+            ready_notify: None,
+        },
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    CaptureSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "capture",
+        description: "Screenshots of a region, window, or the whole screen, and screen recording.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swaps_record_for_stop_and_formats_capture_inputs() {
+        assert_eq!(
+            dialog_actions(false),
+            vec![
+                CaptureAction::Region,
+                CaptureAction::Window,
+                CaptureAction::Screen,
+                CaptureAction::Record,
+            ]
+        );
+        assert_eq!(dialog_actions(true).last(), Some(&CaptureAction::Stop));
+        for action in CaptureAction::ALL {
+            assert_eq!(CaptureAction::from_item_id(action.item_id()), Some(action));
+        }
+
+        assert_eq!(
+            slurp_boxes(&[(0, 0, 960, 1080), (960, 20, 960, 1060)]),
+            "0,0 960x1080\n960,20 960x1060\n"
+        );
+        assert_eq!(format_elapsed(Duration::from_secs(754)), "12:34");
+        assert_eq!(
+            capture_path(
+                Path::new("/tmp/shots"),
+                "screenshot",
+                "20261016-120000",
+                "png"
+            ),
+            PathBuf::from("/tmp/shots/screenshot-20261016-120000.png")
+        );
+    }
+}
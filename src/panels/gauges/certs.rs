@@ -0,0 +1,437 @@
+// Certificate expiry gauge: days until the nearest TLS certificate expires, for configured
+// endpoints and certificate files. Certificates are read with `openssl`.
+// Consumes Settings: grelier.gauge.certs.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    AsyncGaugeEventSource, Gauge, GaugeDisplay, GaugeFuture, GaugeInteractionModel, GaugeModel,
+    GaugePointerInteraction, GaugeRegistrar, GaugeValue, GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec, Settings};
+use async_process::{Command, Stdio};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures_lite::AsyncWriteExt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const TARGETS_KEY: &str = "grelier.gauge.certs.targets";
+const DEFAULT_PORT: u16 = 443;
+/// Push-driven gauge; the poller wakes it when it has checked every certificate.
+const FALLBACK_INTERVAL: Duration = Duration::from_secs(3600);
+/// A round of checks still running after this long is cancelled.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(60);
+const SECS_PER_DAY: i64 = 86_400;
+
+settings::typed_settings! {
+    struct CertsSettings {
+        targets: String = ("grelier.gauge.certs.targets", ""),
+        warning_days: i64 = ("grelier.gauge.certs.warning_days", "30", 0..=3650),
+        danger_days: i64 = ("grelier.gauge.certs.danger_days", "7", 0..=3650),
+        poll_interval_secs: u64 = ("grelier.gauge.certs.poll_interval_secs", "21600", 60..=604_800),
+    }
+}
+
+/// Where a certificate comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CertTarget {
+    /// A TLS server, checked with a handshake.
+    Endpoint { host: String, port: u16 },
+    /// A PEM file on disk.
+    File(String),
+}
+
+impl CertTarget {
+    /// Parse one entry: an absolute path, `host`, or `host:port`.
+    fn parse(entry: &str) -> Result<Self, String> {
+        let entry = entry.trim();
+        if entry.starts_with('/') {
+            return Ok(CertTarget::File(entry.to_string()));
+        }
+        let (host, port) = match entry.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>()
+                    .map_err(|_| format!("Invalid port in certificate target '{entry}'"))?,
+            ),
+            None => (entry, DEFAULT_PORT),
+        };
+        if host.is_empty() || host.contains(char::is_whitespace) {
+            return Err(format!("Invalid certificate target '{entry}'"));
+        }
+        Ok(CertTarget::Endpoint {
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    fn label(&self) -> String {
+        match self {
+            CertTarget::Endpoint { host, port } if *port == DEFAULT_PORT => host.clone(),
+            CertTarget::Endpoint { host, port } => format!("{host}:{port}"),
+            CertTarget::File(path) => path.clone(),
+        }
+    }
+}
+
+fn parse_targets(targets: &str) -> Result<Vec<CertTarget>, String> {
+    targets
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(CertTarget::parse)
+        .collect()
+}
+
+/// Expiry of one monitored certificate, or why it could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CertStatus {
+    label: String,
+    not_after: Result<DateTime<Utc>, String>,
+}
+
+/// Read the `notAfter=Jan  1 00:00:00 2027 GMT` line of `openssl x509 -enddate`.
+fn parse_enddate(output: &str) -> Option<DateTime<Utc>> {
+    let value = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("notAfter="))?;
+    NaiveDateTime::parse_from_str(value.trim(), "%b %e %H:%M:%S %Y GMT")
+        .ok()
+        .map(|date| date.and_utc())
+}
+
+/// Run `openssl x509 -noout -enddate` on a certificate file, or on PEM text fed to stdin.
+async fn read_enddate(file: Option<&str>, pem: Option<&[u8]>) -> Result<DateTime<Utc>, String> {
+    let mut command = Command::new("openssl");
+    command.args(["x509", "-noout", "-enddate"]);
+    if let Some(file) = file {
+        command.args(["-in", file]);
+    }
+    let mut child = command
+        .stdin(if pem.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| format!("failed to run openssl: {err}"))?;
+    if let (Some(pem), Some(mut stdin)) = (pem, child.stdin.take()) {
+        stdin
+            .write_all(pem)
+            .await
+            .map_err(|err| format!("failed to run openssl: {err}"))?;
+    }
+    let output = child
+        .output()
+        .await
+        .map_err(|err| format!("failed to run openssl: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr
+            .lines()
+            .next()
+            .unwrap_or("unreadable certificate")
+            .to_string());
+    }
+    parse_enddate(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| "no expiry date in certificate".to_string())
+}
+
+async fn check(target: &CertTarget) -> Result<DateTime<Utc>, String> {
+    match target {
+        CertTarget::File(path) => read_enddate(Some(path), None).await,
+        CertTarget::Endpoint { host, port } => {
+            // With stdin closed, s_client prints the server certificate and hangs up.
+            let output = Command::new("openssl")
+                .args(["s_client", "-connect", &format!("{host}:{port}")])
+                .args(["-servername", host])
+                .stdin(Stdio::null())
+                .kill_on_drop(true)
+                .output()
+                .await
+                .map_err(|err| format!("failed to run openssl: {err}"))?;
+            if !String::from_utf8_lossy(&output.stdout).contains("-----BEGIN CERTIFICATE-----") {
+                return Err("no certificate from server".to_string());
+            }
+            read_enddate(None, Some(&output.stdout)).await
+        }
+    }
+}
+
+/// Whole days from `now` until `not_after`, negative once expired.
+fn days_left(not_after: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+    (not_after - now).num_seconds().div_euclid(SECS_PER_DAY)
+}
+
+fn attention_for(days: i64, warning_days: i64, danger_days: i64) -> GaugeValueAttention {
+    if days <= danger_days {
+        GaugeValueAttention::Danger
+    } else if days <= warning_days {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+/// Dialog lines, soonest expiry first and unreadable certificates last.
+fn status_lines(statuses: &[CertStatus], now: DateTime<Utc>) -> Vec<String> {
+    let mut sorted: Vec<&CertStatus> = statuses.iter().collect();
+    sorted.sort_by_key(|status| status.not_after.as_ref().ok().copied());
+    sorted.sort_by_key(|status| status.not_after.is_err());
+    sorted
+        .into_iter()
+        .map(|status| match &status.not_after {
+            Ok(not_after) => {
+                let date = not_after.format("%Y-%m-%d");
+                match days_left(*not_after, now) {
+                    days if days < 0 => {
+                        format!("{}: expired {date} ({} days ago)", status.label, -days)
+                    }
+                    days => format!("{}: expires {date} (in {days} days)", status.label),
+                }
+            }
+            Err(err) => format!("{}: {err}", status.label),
+        })
+        .collect()
+}
+
+type SharedStatuses = Arc<Mutex<Option<Vec<CertStatus>>>>;
+
+/// Async event source checking every target on the shared gauge executor.
+struct CertPoller {
+    targets: Vec<CertTarget>,
+    interval: Duration,
+    statuses: SharedStatuses,
+}
+
+impl AsyncGaugeEventSource for CertPoller {
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn timeout(&self) -> Duration {
+        CHECK_TIMEOUT
+    }
+
+    fn poll(&mut self) -> GaugeFuture<'_, bool> {
+        Box::pin(async move {
+            let mut next = Vec::with_capacity(self.targets.len());
+            for target in &self.targets {
+                let not_after = check(target).await;
+                if let Err(err) = &not_after {
+                    log::warn!("certs gauge: {}: {err}", target.label());
+                }
+                next.push(CertStatus {
+                    label: target.label(),
+                    not_after,
+                });
+            }
+            match self.statuses.lock() {
+                Ok(mut statuses) => *statuses = Some(next),
+                Err(_) => return false,
+            }
+            true
+        })
+    }
+}
+
+/// Gauge showing the days left on the certificate that expires first.
+struct CertsGauge {
+    settings: CertsSettings,
+    /// Latest check results; `None` until the first round completes.
+    statuses: SharedStatuses,
+    /// Poller handed to the work manager on registration; `None` when misconfigured.
+    poller: Option<CertPoller>,
+    /// Configuration problem shown instead of certificates.
+    config_error: Option<String>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for CertsGauge {
+    fn id(&self) -> &'static str {
+        "certs"
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(poller) = self.poller.take() {
+            registrar.add_async_event_source(Box::new(poller));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + FALLBACK_INTERVAL;
+
+        let latest = self
+            .statuses
+            .lock()
+            .ok()
+            .and_then(|statuses| statuses.clone());
+        let today = Utc::now();
+        let (display, lines) = match (&self.config_error, latest) {
+            (Some(err), _) => (GaugeDisplay::Error, vec![err.clone()]),
+            (None, None) => (
+                GaugeDisplay::Empty,
+                vec!["Checking certificates…".to_string()],
+            ),
+            (None, Some(statuses)) => {
+                let nearest = statuses
+                    .iter()
+                    .filter_map(|status| status.not_after.as_ref().ok())
+                    .min()
+                    .map(|not_after| days_left(*not_after, today));
+                let display = match nearest {
+                    Some(days) => GaugeDisplay::Value {
+                        value: GaugeValue::Text(if days < 0 {
+                            "exp".to_string()
+                        } else {
+                            format!("{days}d")
+                        }),
+                        attention: attention_for(
+                            days,
+                            self.settings.warning_days,
+                            self.settings.danger_days,
+                        ),
+                    },
+                    None => GaugeDisplay::Error,
+                };
+                (display, status_lines(&statuses, today))
+            }
+        };
+
+        Some(GaugeModel {
+            id: "certs",
+            icon: svg_asset("certificate.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Certificates".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = CertsSettings::load();
+    let statuses: SharedStatuses = Arc::new(Mutex::new(None));
+    let (poller, config_error) = match parse_targets(&settings.targets) {
+        Ok(targets) if targets.is_empty() => (
+            None,
+            Some(format!("No certificates configured in {TARGETS_KEY}")),
+        ),
+        Ok(targets) => (
+            Some(CertPoller {
+                targets,
+                interval: Duration::from_secs(settings.poll_interval_secs),
+                statuses: Arc::clone(&statuses),
+            }),
+            None,
+        ),
+        Err(err) => {
+            log::warn!("certs gauge: {err}");
+            (None, Some(err))
+        }
+    };
+    Box::new(CertsGauge {
+        settings,
+        statuses,
+        poller,
+        config_error,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    CertsSettings::SPECS
+}
+
+fn validate(settings: &Settings) -> Result<(), String> {
+    parse_targets(&settings.get_or(TARGETS_KEY, "")).map(|_| ())
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "certs",
+        description: "Certificate expiry gauge showing days until the nearest TLS certificate expires.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: Some(validate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> DateTime<Utc> {
+        parse_enddate(&format!("notAfter={value}")).expect("date")
+    }
+
+    #[test]
+    fn parses_targets_and_orders_expiry_dates() {
+        assert_eq!(
+            parse_targets("example.org, git.example.org:8443,/etc/ssl/mail.pem,"),
+            Ok(vec![
+                CertTarget::Endpoint {
+                    host: "example.org".to_string(),
+                    port: 443,
+                },
+                CertTarget::Endpoint {
+                    host: "git.example.org".to_string(),
+                    port: 8443,
+                },
+                CertTarget::File("/etc/ssl/mail.pem".to_string()),
+            ])
+        );
+        assert!(parse_targets("example.org:https").is_err());
+
+        let now = date("Oct 16 12:00:00 2026 GMT");
+        assert_eq!(
+            date("Jan  1 00:00:00 2027 GMT")
+                .format("%Y-%m-%d")
+                .to_string(),
+            "2027-01-01"
+        );
+        assert_eq!(days_left(date("Oct 23 13:00:00 2026 GMT"), now), 7);
+        assert_eq!(days_left(date("Oct 16 11:00:00 2026 GMT"), now), -1);
+        assert_eq!(attention_for(7, 30, 7), GaugeValueAttention::Danger);
+        assert_eq!(attention_for(20, 30, 7), GaugeValueAttention::Warning);
+
+        let statuses = [
+            CertStatus {
+                label: "broken.example".to_string(),
+                not_after: Err("no certificate from server".to_string()),
+            },
+            CertStatus {
+                label: "later.example".to_string(),
+                not_after: Ok(date("Jan  1 00:00:00 2027 GMT")),
+            },
+            CertStatus {
+                label: "old.example".to_string(),
+                not_after: Ok(date("Oct  1 12:00:00 2026 GMT")),
+            },
+        ];
+        assert_eq!(
+            status_lines(&statuses, now),
+            vec![
+                "old.example: expired 2026-10-01 (15 days ago)",
+                "later.example: expires 2027-01-01 (in 76 days)",
+                "broken.example: no certificate from server",
+            ]
+        );
+    }
+}
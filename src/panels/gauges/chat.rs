@@ -0,0 +1,499 @@
+// Chat gauge showing unread and highlight counts from a pluggable backend (Matrix sync API).
+// Consumes Settings: grelier.gauge.chat.*.
+use crate::compositor;
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeClick, GaugeClickAction, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar,
+    GaugeStop,
+};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec, Settings};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const BACKEND_KEY: &str = "grelier.gauge.chat.backend";
+/// Server-side long-poll timeout for Matrix `/sync`.
+const MATRIX_SYNC_TIMEOUT_MS: u64 = 30_000;
+/// Keep the initial sync small: room names and unread counts only.
+const MATRIX_SYNC_FILTER: &str = r#"{"presence":{"not_types":["*"]},"account_data":{"not_types":["*"]},"room":{"timeline":{"limit":1},"state":{"types":["m.room.name","m.room.canonical_alias"],"lazy_load_members":true},"ephemeral":{"not_types":["*"]},"account_data":{"not_types":["*"]}}}"#;
+const INFO_DIALOG_ROOMS: usize = 12;
+
+settings::typed_settings! {
+    struct ChatSettings {
+        backend: String = ("grelier.gauge.chat.backend", "matrix"),
+        matrix_homeserver: String = ("grelier.gauge.chat.matrix_homeserver", ""),
+        matrix_token_file: String = ("grelier.gauge.chat.matrix_token_file", ""),
+        client_app_id: String = ("grelier.gauge.chat.client_app_id", "Element"),
+        client_desktop_id: String = ("grelier.gauge.chat.client_desktop_id", "element-desktop"),
+        retry_interval_secs: u64 = ("grelier.gauge.chat.retry_interval_secs", "30", 1..=3600),
+    }
+}
+
+/// Unread state for one joined room.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct RoomUnread {
+    name: String,
+    notifications: u64,
+    highlights: u64,
+}
+
+/// A chat service that can report unread counts.
+trait ChatBackend: Send {
+    /// Wait for the next change and return unread state for all rooms.
+    fn poll(&mut self) -> Result<Vec<RoomUnread>, String>;
+}
+
+fn create_backend(settings: &ChatSettings) -> Result<Box<dyn ChatBackend>, String> {
+    match settings.backend.trim() {
+        "matrix" => Ok(Box::new(MatrixBackend::new(
+            settings.matrix_homeserver.trim(),
+            token_path(&settings.matrix_token_file),
+        ))),
+        other => Err(format!("Unknown chat backend '{other}', expected 'matrix'")),
+    }
+}
+
+fn validate(settings: &Settings) -> Result<(), String> {
+    match settings.get(BACKEND_KEY) {
+        Some(backend) if backend.trim() != "matrix" => Err(format!(
+            "Unknown chat backend '{}', expected 'matrix'",
+            backend.trim()
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn token_path(configured: &str) -> PathBuf {
+    if !configured.trim().is_empty() {
+        return PathBuf::from(configured.trim());
+    }
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default()
+        .join("grelier")
+        .join("matrix-token")
+}
+
+/// Matrix client-server `/sync` long-poll via `curl`.
+struct MatrixBackend {
+    homeserver: String,
+    token_path: PathBuf,
+    since: Option<String>,
+    rooms: BTreeMap<String, RoomUnread>,
+}
+
+impl MatrixBackend {
+    fn new(homeserver: &str, token_path: PathBuf) -> Self {
+        Self {
+            homeserver: homeserver.trim_end_matches('/').to_string(),
+            token_path,
+            since: None,
+            rooms: BTreeMap::new(),
+        }
+    }
+
+    /// Merge one `/sync` response into the room table.
+    fn apply_sync(&mut self, sync: &Value) {
+        if let Some(next_batch) = sync["next_batch"].as_str() {
+            self.since = Some(next_batch.to_string());
+        }
+        if let Some(left) = sync["rooms"]["leave"].as_object() {
+            for room_id in left.keys() {
+                self.rooms.remove(room_id);
+            }
+        }
+        let Some(joined) = sync["rooms"]["join"].as_object() else {
+            return;
+        };
+        for (room_id, room) in joined {
+            let entry = self
+                .rooms
+                .entry(room_id.clone())
+                .or_insert_with(|| RoomUnread {
+                    name: room_id.clone(),
+                    ..RoomUnread::default()
+                });
+            if let Some(name) = room_name(room) {
+                entry.name = name;
+            }
+            let counts = &room["unread_notifications"];
+            if let Some(count) = counts["notification_count"].as_u64() {
+                entry.notifications = count;
+            }
+            if let Some(count) = counts["highlight_count"].as_u64() {
+                entry.highlights = count;
+            }
+        }
+    }
+}
+
+/// Name from `m.room.name`, then the canonical alias, then the first DM hero.
+fn room_name(room: &Value) -> Option<String> {
+    let events = ["state", "timeline"]
+        .iter()
+        .filter_map(|section| room[section]["events"].as_array())
+        .flatten();
+    let mut alias = None;
+    let mut name = None;
+    for event in events {
+        match event["type"].as_str() {
+            Some("m.room.name") => name = event["content"]["name"].as_str(),
+            Some("m.room.canonical_alias") => alias = event["content"]["alias"].as_str(),
+            _ => {}
+        }
+    }
+    name.or(alias)
+        .or_else(|| room["summary"]["m.heroes"][0].as_str())
+        .filter(|name| !name.is_empty())
+        .map(ToString::to_string)
+}
+
+impl ChatBackend for MatrixBackend {
+    fn poll(&mut self) -> Result<Vec<RoomUnread>, String> {
+        if self.homeserver.is_empty() {
+            return Err("No Matrix homeserver configured".to_string());
+        }
+        let token = fs::read_to_string(&self.token_path)
+            .map(|token| token.trim().to_string())
+            .map_err(|err| format!("{}: {err}", self.token_path.display()))?;
+
+        let mut command = Command::new("curl");
+        command
+            .args(["-sS", "--fail", "--get", "--max-time"])
+            .arg((MATRIX_SYNC_TIMEOUT_MS / 1000 + 30).to_string())
+            // Read the auth header from stdin so the token never shows up in `ps`.
+            .args(["-H", "@-"])
+            .args(["--data-urlencode", &format!("filter={MATRIX_SYNC_FILTER}")]);
+        // The initial sync returns immediately; later ones wait for new events.
+        match &self.since {
+            Some(since) => command
+                .args(["--data-urlencode", &format!("since={since}")])
+                .args(["--data", &format!("timeout={MATRIX_SYNC_TIMEOUT_MS}")]),
+            None => command.args(["--data", "timeout=0"]),
+        };
+        let mut child = command
+            .arg(format!("{}/_matrix/client/v3/sync", self.homeserver))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("failed to run curl: {err}"))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "Authorization: Bearer {token}")
+                .map_err(|err| format!("failed to write to curl: {err}"))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|err| format!("curl failed: {err}"))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        let sync: Value = serde_json::from_slice(&output.stdout)
+            .map_err(|err| format!("invalid sync response: {err}"))?;
+        self.apply_sync(&sync);
+        Ok(self.rooms.values().cloned().collect())
+    }
+}
+
+/// Latest result from the backend thread.
+#[derive(Debug, Default)]
+struct ChatState {
+    rooms: Vec<RoomUnread>,
+    error: Option<String>,
+}
+
+/// Event source that long-polls the backend and wakes the gauge on each update.
+struct ChatMonitor {
+    backend: Box<dyn ChatBackend>,
+    retry_interval: Duration,
+    state: Arc<Mutex<ChatState>>,
+}
+
+impl GaugeEventSource for ChatMonitor {
+    fn run(mut self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
+        // Each poll returns within the server's long-poll timeout, so stops are seen by then.
+        while !stop.is_stopped() {
+            let result = self.backend.poll();
+            let failed = result.is_err();
+            let changed = match self.state.lock() {
+                Ok(mut state) => {
+                    let before = (state.rooms.clone(), state.error.clone());
+                    match result {
+                        Ok(rooms) => {
+                            state.rooms = rooms;
+                            state.error = None;
+                        }
+                        Err(err) => {
+                            log::warn!("chat gauge: {err}");
+                            state.error = Some(err);
+                        }
+                    }
+                    before != (state.rooms.clone(), state.error.clone())
+                }
+                Err(_) => return,
+            };
+            if changed {
+                notify("chat");
+            }
+            if failed && stop.sleep(self.retry_interval) {
+                return;
+            }
+        }
+    }
+}
+
+fn totals(rooms: &[RoomUnread]) -> (u64, u64) {
+    rooms.iter().fold((0, 0), |(unread, highlights), room| {
+        (unread + room.notifications, highlights + room.highlights)
+    })
+}
+
+/// Rooms with mentions first, then other unread rooms.
+fn info_lines(rooms: &[RoomUnread]) -> Vec<String> {
+    let mut unread: Vec<&RoomUnread> = rooms.iter().filter(|r| r.notifications > 0).collect();
+    if unread.is_empty() {
+        return vec!["No unread messages".to_string()];
+    }
+    unread.sort_by(|a, b| {
+        b.highlights
+            .cmp(&a.highlights)
+            .then_with(|| b.notifications.cmp(&a.notifications))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    let mut lines: Vec<String> = unread
+        .iter()
+        .take(INFO_DIALOG_ROOMS)
+        .map(|room| {
+            if room.highlights > 0 {
+                format!(
+                    "{}: {} unread, {} mentions",
+                    room.name, room.notifications, room.highlights
+                )
+            } else {
+                format!("{}: {} unread", room.name, room.notifications)
+            }
+        })
+        .collect();
+    if unread.len() > INFO_DIALOG_ROOMS {
+        lines.push(format!("…and {} more", unread.len() - INFO_DIALOG_ROOMS));
+    }
+    lines
+}
+
+/// Focus a running client window, or launch the client if none is open.
+fn focus_or_launch(app_id: &str, desktop_id: &str) {
+    let backend = compositor::backend();
+    let window = backend.fetch_workspace_apps().ok().and_then(|workspaces| {
+        workspaces
+            .into_iter()
+            .flat_map(|workspace| workspace.apps)
+            .find(|app| app.app_id.eq_ignore_ascii_case(app_id))
+    });
+    let result = match window {
+        Some(window) => backend.focus_con_id(window.con_id),
+        None if !desktop_id.is_empty() => backend.launch_app(desktop_id),
+        None => Ok(()),
+    };
+    if let Err(err) = result {
+        log::error!("chat gauge: failed to open chat client: {err}");
+    }
+}
+
+/// Gauge showing total unread chat messages, highlighting mentions.
+struct ChatGauge {
+    state: Arc<Mutex<ChatState>>,
+    client_app_id: String,
+    client_desktop_id: String,
+    /// Backend poller handed to the work manager on registration.
+    monitor: Option<ChatMonitor>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for ChatGauge {
+    fn id(&self) -> &'static str {
+        "chat"
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(monitor) = self.monitor.take() {
+            registrar.add_event_source(Box::new(monitor));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        // Updates are pushed by the backend thread; the deadline is only a fallback refresh.
+        self.next_deadline = now + Duration::from_secs(3600);
+        let state = self.state.lock().ok()?;
+
+        let (display, lines) = match &state.error {
+            Some(err) => (GaugeDisplay::Error, vec![err.clone()]),
+            None => {
+                let (unread, highlights) = totals(&state.rooms);
+                let attention = if highlights > 0 {
+                    GaugeValueAttention::Warning
+                } else {
+                    GaugeValueAttention::Nominal
+                };
+                (
+                    GaugeDisplay::Value {
+                        value: GaugeValue::Text(unread.to_string()),
+                        attention,
+                    },
+                    info_lines(&state.rooms),
+                )
+            }
+        };
+
+        let on_click: GaugeClickAction = {
+            let app_id = self.client_app_id.clone();
+            let desktop_id = self.client_desktop_id.clone();
+            Arc::new(move |_click: GaugeClick| focus_or_launch(&app_id, &desktop_id))
+        };
+
+        Some(GaugeModel {
+            id: "chat",
+            icon: svg_asset("chat.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Chat".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                right_click: GaugePointerInteraction {
+                    on_input: Some(on_click),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = ChatSettings::load();
+    let state = Arc::new(Mutex::new(ChatState::default()));
+    let monitor = match create_backend(&settings) {
+        Ok(backend) => Some(ChatMonitor {
+            backend,
+            retry_interval: Duration::from_secs(settings.retry_interval_secs),
+            state: Arc::clone(&state),
+        }),
+        Err(err) => {
+            if let Ok(mut state) = state.lock() {
+                state.error = Some(err);
+            }
+            None
+        }
+    };
+
+    Box::new(ChatGauge {
+        state,
+        client_app_id: settings.client_app_id.trim().to_string(),
+        client_desktop_id: settings.client_desktop_id.trim().to_string(),
+        monitor,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    ChatSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "chat",
+        description: "Chat gauge showing unread and mention counts from Matrix.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: Some(validate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn matrix_sync_updates_rooms_incrementally() {
+        let mut backend = MatrixBackend::new("https://example.org/", PathBuf::new());
+        backend.apply_sync(&json!({
+            "next_batch": "s1",
+            "rooms": { "join": {
+                "!a:example.org": {
+                    "state": { "events": [
+                        { "type": "m.room.canonical_alias", "content": { "alias": "#rust:example.org" } }
+                    ] },
+                    "unread_notifications": { "notification_count": 4, "highlight_count": 1 }
+                },
+                "!b:example.org": {
+                    "summary": { "m.heroes": ["@alice:example.org"] },
+                    "unread_notifications": { "notification_count": 2, "highlight_count": 0 }
+                }
+            } }
+        }));
+        backend.apply_sync(&json!({
+            "next_batch": "s2",
+            "rooms": {
+                "join": { "!a:example.org": {
+                    "timeline": { "events": [
+                        { "type": "m.room.name", "content": { "name": "Rust" } }
+                    ] },
+                    "unread_notifications": { "notification_count": 0, "highlight_count": 0 }
+                } },
+                "leave": { "!b:example.org": {} }
+            }
+        }));
+
+        assert_eq!(backend.homeserver, "https://example.org");
+        assert_eq!(backend.since.as_deref(), Some("s2"));
+        let rooms: Vec<RoomUnread> = backend.rooms.values().cloned().collect();
+        assert_eq!(
+            rooms,
+            vec![RoomUnread {
+                name: "Rust".to_string(),
+                notifications: 0,
+                highlights: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn info_lists_mentions_first() {
+        let room = |name: &str, notifications, highlights| RoomUnread {
+            name: name.to_string(),
+            notifications,
+            highlights,
+        };
+        let rooms = vec![room("busy", 40, 0), room("quiet", 0, 0), room("team", 3, 2)];
+        assert_eq!(totals(&rooms), (43, 2));
+        assert_eq!(
+            info_lines(&rooms),
+            vec!["team: 3 unread, 2 mentions", "busy: 40 unread"]
+        );
+        assert_eq!(info_lines(&[]), vec!["No unread messages"]);
+    }
+}
@@ -1,21 +1,24 @@
 // Clock gauge stream with hour format toggling and optional text/seconds display.
-// Consumes Settings: grelier.gauge.clock.hourformat, grelier.gauge.clock.showseconds, grelier.gauge.clock.show_text.
+// Consumes Settings: grelier.gauge.clock.*.
+// Optionally hosts the opt-in app usage tracker (see app_usage.rs).
 use chrono::Local;
 use chrono::Timelike;
 use iced::mouse;
 use iced::widget::svg;
 use std::f32::consts::PI;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::panels::gauges::gauge::{Gauge, GaugeReadyNotify};
+use crate::dialog::info::InfoDialog;
+use crate::panels::gauges::app_usage::{AppUsage, AppUsageMonitor};
+use crate::panels::gauges::gauge::{Gauge, GaugeReadyNotify, GaugeRegistrar};
 use crate::panels::gauges::gauge::{
-    GaugeClick, GaugeClickAction, GaugeDisplay, GaugeInteractionModel, GaugeModel,
-    GaugePointerInteraction, GaugeValue, GaugeValueAttention,
+    GaugeClick, GaugeClickAction, GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem,
+    GaugeModel, GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
 };
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
+use crate::settings::{self, SettingSpec};
 
 #[derive(Debug, Clone, Copy, Default)]
 enum HourFormat {
@@ -40,16 +43,69 @@ impl HourFormat {
     }
 }
 
-fn hour_format_from_setting() -> HourFormat {
-    let value = settings::settings().get_or("grelier.gauge.clock.hourformat", "24");
-    match value.as_str() {
-        "24" => HourFormat::TwentyFour,
-        "12" => HourFormat::Twelve,
-        other => {
-            panic!(
-                "Invalid setting 'grelier.gauge.clock.hourformat': expected 12 or 24, got '{other}'"
-            )
+impl FromStr for HourFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "24" => Ok(HourFormat::TwentyFour),
+            "12" => Ok(HourFormat::Twelve),
+            other => Err(format!("expected 12 or 24, got '{other}'")),
+        }
+    }
+}
+
+settings::typed_settings! {
+    struct ClockSettings {
+        show_seconds: bool = ("grelier.gauge.clock.showseconds", "false"),
+        hour_format: HourFormat = ("grelier.gauge.clock.hourformat", "24"),
+        show_text: bool = ("grelier.gauge.clock.show_text", "true"),
+        usage_tracking: bool = ("grelier.gauge.clock.usage_tracking", "false"),
+        usage_paused: bool = ("grelier.gauge.clock.usage_paused", "false"),
+        usage_retention_days: usize = ("grelier.gauge.clock.usage_retention_days", "30", 1..=3650),
+    }
+}
+
+const USAGE_PAUSED_KEY: &str = "grelier.gauge.clock.usage_paused";
+const USAGE_SUMMARY_LIMIT: usize = 8;
+
+/// Privacy controls for the app usage tracker.
+fn usage_menu(paused: bool, on_select: MenuSelectAction) -> GaugeMenu {
+    let (toggle_id, toggle_label) = if paused {
+        ("resume", "Resume tracking")
+    } else {
+        ("pause", "Pause tracking")
+    };
+    GaugeMenu {
+        title: "App Usage".to_string(),
+        items: vec![
+            GaugeMenuItem {
+                id: toggle_id.to_string(),
+                label: toggle_label.to_string(),
+                selected: false,
+                exec: None,
+            },
+            GaugeMenuItem {
+                id: "purge".to_string(),
+                label: "Purge history".to_string(),
+                selected: false,
+                exec: None,
+            },
+        ],
+        on_select: Some(on_select),
+        slider: None,
+    }
+}
+
+fn apply_usage_action(usage: &mut AppUsage, action: &str) {
+    match action {
+        "pause" | "resume" => {
+            let paused = action == "pause";
+            usage.set_paused(paused);
+            settings::settings().update(USAGE_PAUSED_KEY, if paused { "true" } else { "false" });
         }
+        "purge" => usage.purge(),
+        other => log::warn!("clock gauge: unknown usage action '{other}'"),
     }
 }
 
@@ -148,6 +204,10 @@ struct ClockGauge {
     icon_state: Option<ClockIconState>,
     /// Notifier used to request an immediate scheduler wake-up after toggles.
     ready_notify: Option<GaugeReadyNotify>,
+    /// App usage tracker state, present only when tracking is enabled.
+    usage: Option<Arc<Mutex<AppUsage>>>,
+    /// Focus monitor handed to the work manager on registration.
+    usage_monitor: Option<AppUsageMonitor>,
     /// Scheduler deadline for the next run.
     next_deadline: Instant,
 }
@@ -161,6 +221,12 @@ impl Gauge for ClockGauge {
         self.ready_notify = Some(notify);
     }
 
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(monitor) = self.usage_monitor.take() {
+            registrar.add_event_source(Box::new(monitor));
+        }
+    }
+
     fn next_deadline(&self) -> Instant {
         self.next_deadline
     }
@@ -236,11 +302,47 @@ impl Gauge for ClockGauge {
         };
         self.next_deadline = now + duration_until_window_boundary(interval);
 
+        let (left_click, middle_click) = match &self.usage {
+            Some(usage) => {
+                let (lines, paused) = usage
+                    .lock()
+                    .map(|usage| (usage.today_lines(USAGE_SUMMARY_LIMIT), usage.paused()))
+                    .unwrap_or_default();
+                let usage = Arc::clone(usage);
+                let ready_notify = self.ready_notify.clone();
+                let on_select: MenuSelectAction = Arc::new(move |action: String| {
+                    if let Ok(mut usage) = usage.lock() {
+                        apply_usage_action(&mut usage, &action);
+                    }
+                    if let Some(ready_notify) = &ready_notify {
+                        ready_notify("clock");
+                    }
+                });
+                (
+                    GaugePointerInteraction {
+                        info: Some(InfoDialog {
+                            title: "App Usage Today".to_string(),
+                            lines,
+                            graph: None,
+                        }),
+                        ..GaugePointerInteraction::default()
+                    },
+                    GaugePointerInteraction {
+                        menu: Some(usage_menu(paused, on_select)),
+                        ..GaugePointerInteraction::default()
+                    },
+                )
+            }
+            None => Default::default(),
+        };
+
         Some(GaugeModel {
             id: "clock",
             icon,
             display,
             interactions: GaugeInteractionModel {
+                left_click,
+                middle_click,
                 right_click: GaugePointerInteraction {
                     on_input: Some(on_click),
                     ..GaugePointerInteraction::default()
@@ -252,32 +354,31 @@ impl Gauge for ClockGauge {
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = ClockSettings::load();
+    let usage = settings.usage_tracking.then(|| {
+        Arc::new(Mutex::new(AppUsage::load(
+            AppUsage::default_path(),
+            settings.usage_retention_days,
+            settings.usage_paused,
+        )))
+    });
+    let usage_monitor = usage.as_ref().map(|usage| AppUsageMonitor {
+        usage: Arc::clone(usage),
+    });
     Box::new(ClockGauge {
-        show_seconds: settings::settings().get_bool_or("grelier.gauge.clock.showseconds", false),
-        show_text: settings::settings().get_bool_or("grelier.gauge.clock.show_text", true),
-        format_state: Arc::new(Mutex::new(hour_format_from_setting())),
+        show_seconds: settings.show_seconds,
+        show_text: settings.show_text,
+        format_state: Arc::new(Mutex::new(settings.hour_format)),
         icon_state: None,
         ready_notify: None,
+        usage,
+        usage_monitor,
         next_deadline: now,
     })
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[
-        SettingSpec {
-            key: "grelier.gauge.clock.showseconds",
-            default: "false",
-        },
-        SettingSpec {
-            key: "grelier.gauge.clock.hourformat",
-            default: "24",
-        },
-        SettingSpec {
-            key: "grelier.gauge.clock.show_text",
-            default: "true",
-        },
-    ];
-    SETTINGS
+    ClockSettings::SPECS
 }
 
 inventory::submit! {
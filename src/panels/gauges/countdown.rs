@@ -0,0 +1,256 @@
+// Countdown gauge showing days until the nearest configured event.
+// Consumes Settings: grelier.gauge.countdown.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec, Settings};
+use chrono::{Datelike, Local, NaiveDate};
+use std::time::{Duration, Instant};
+
+const EVENTS_KEY: &str = "grelier.gauge.countdown.events";
+
+settings::typed_settings! {
+    struct CountdownSettings {
+        events: String = ("grelier.gauge.countdown.events", ""),
+        warning_days: i64 = ("grelier.gauge.countdown.warning_days", "7", 0..=366),
+    }
+}
+
+/// When an event happens: once on a date, or every year on a month/day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventDate {
+    Once(NaiveDate),
+    Yearly { month: u32, day: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CountdownEvent {
+    name: String,
+    date: EventDate,
+}
+
+impl CountdownEvent {
+    /// Next date the event falls on, counting today; one-off events keep their date once passed.
+    fn next_date(&self, today: NaiveDate) -> NaiveDate {
+        match self.date {
+            EventDate::Once(date) => date,
+            EventDate::Yearly { month, day } => {
+                let in_year = |year: i32| {
+                    // Feb 29 birthdays fall on Feb 28 in common years.
+                    NaiveDate::from_ymd_opt(year, month, day)
+                        .or_else(|| NaiveDate::from_ymd_opt(year, month, day - 1))
+                };
+                match in_year(today.year()) {
+                    Some(date) if date >= today => date,
+                    _ => in_year(today.year() + 1).unwrap_or(today),
+                }
+            }
+        }
+    }
+}
+
+/// Parse `Name=YYYY-MM-DD` (one-off) and `Name=MM-DD` (yearly) entries separated by commas.
+fn parse_events(value: &str) -> Result<Vec<CountdownEvent>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, date) = entry
+                .rsplit_once('=')
+                .ok_or_else(|| format!("Invalid event '{entry}', expected Name=YYYY-MM-DD"))?;
+            let name = name.trim();
+            let date = date.trim();
+            if name.is_empty() {
+                return Err(format!("Event '{entry}' has no name"));
+            }
+            let date = if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                EventDate::Once(date)
+            } else {
+                // Validate against a leap year so Feb 29 is accepted.
+                let date = NaiveDate::parse_from_str(&format!("2000-{date}"), "%Y-%m-%d").map_err(
+                    |_| {
+                        format!(
+                            "Invalid date '{date}' for event '{name}', expected YYYY-MM-DD or MM-DD"
+                        )
+                    },
+                )?;
+                EventDate::Yearly {
+                    month: date.month(),
+                    day: date.day(),
+                }
+            };
+            Ok(CountdownEvent {
+                name: name.to_string(),
+                date,
+            })
+        })
+        .collect()
+}
+
+/// Upcoming events with days remaining, nearest first, followed by passed one-off events.
+fn schedule(events: &[CountdownEvent], today: NaiveDate) -> Vec<(i64, &CountdownEvent)> {
+    let mut scheduled: Vec<(i64, &CountdownEvent)> = events
+        .iter()
+        .map(|event| ((event.next_date(today) - today).num_days(), event))
+        .collect();
+    scheduled.sort_by_key(|(days, event)| (*days < 0, days.abs(), event.name.clone()));
+    scheduled
+}
+
+fn describe(days: i64, event: &CountdownEvent, today: NaiveDate) -> String {
+    let date = event.next_date(today).format("%Y-%m-%d");
+    let when = match days {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        days if days > 0 => format!("in {days} days"),
+        -1 => "1 day ago".to_string(),
+        days => format!("{} days ago", -days),
+    };
+    format!("{date}  {}: {when}", event.name)
+}
+
+/// Time left until the next local midnight, when the day counts change.
+fn until_midnight() -> Duration {
+    let now = Local::now();
+    now.date_naive()
+        .succ_opt()
+        .and_then(|tomorrow| tomorrow.and_hms_opt(0, 0, 1))
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .and_then(|midnight| (midnight - now).to_std().ok())
+        .unwrap_or(Duration::from_secs(3600))
+}
+
+/// Gauge counting down to the nearest configured event.
+struct CountdownGauge {
+    events: Vec<CountdownEvent>,
+    /// Days before an event at which the gauge switches to warning attention.
+    warning_days: i64,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for CountdownGauge {
+    fn id(&self) -> &'static str {
+        "countdown"
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + until_midnight();
+        let today = Local::now().date_naive();
+        let scheduled = schedule(&self.events, today);
+
+        let display = match scheduled.first() {
+            Some((days, _)) if *days >= 0 => GaugeDisplay::Value {
+                value: GaugeValue::Text(days.to_string()),
+                attention: if *days <= self.warning_days {
+                    GaugeValueAttention::Warning
+                } else {
+                    GaugeValueAttention::Nominal
+                },
+            },
+            _ => GaugeDisplay::Empty,
+        };
+        let lines = if scheduled.is_empty() {
+            vec![format!("No events configured in {EVENTS_KEY}")]
+        } else {
+            scheduled
+                .iter()
+                .map(|(days, event)| describe(*days, event, today))
+                .collect()
+        };
+
+        Some(GaugeModel {
+            id: "countdown",
+            icon: svg_asset("hourglass.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Countdown".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = CountdownSettings::load();
+    let events = parse_events(&settings.events).unwrap_or_else(|err| {
+        log::warn!("countdown gauge: {err}");
+        Vec::new()
+    });
+    Box::new(CountdownGauge {
+        events,
+        warning_days: settings.warning_days,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    CountdownSettings::SPECS
+}
+
+fn validate(settings: &Settings) -> Result<(), String> {
+    parse_events(&settings.get_or(EVENTS_KEY, "")).map(|_| ())
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "countdown",
+        description: "Countdown gauge showing days until the nearest configured event.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: Some(validate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).expect("valid date")
+    }
+
+    #[test]
+    fn parses_one_off_and_yearly_events() {
+        let events = parse_events("Launch=2026-11-02, Ada's birthday=02-29,").expect("parse");
+        assert_eq!(events[0].date, EventDate::Once(date(2026, 11, 2)));
+        assert_eq!(events[1].date, EventDate::Yearly { month: 2, day: 29 });
+        assert_eq!(events[1].next_date(date(2027, 3, 1)), date(2028, 2, 29));
+        assert_eq!(events[1].next_date(date(2026, 3, 1)), date(2027, 2, 28));
+        assert!(parse_events("Launch").is_err());
+        assert!(parse_events("Launch=13-01").is_err());
+    }
+
+    #[test]
+    fn schedules_nearest_upcoming_first() {
+        let events = parse_events("Old=2026-01-01,Later=2026-12-24,Soon=10-20").expect("parse");
+        let today = date(2026, 10, 16);
+        let scheduled = schedule(&events, today);
+        let order: Vec<(i64, &str)> = scheduled
+            .iter()
+            .map(|(days, event)| (*days, event.name.as_str()))
+            .collect();
+        assert_eq!(order, vec![(4, "Soon"), (69, "Later"), (-288, "Old")]);
+        assert_eq!(
+            describe(4, scheduled[0].1, today),
+            "2026-10-20  Soon: in 4 days"
+        );
+    }
+}
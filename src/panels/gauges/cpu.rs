@@ -1,34 +1,42 @@
 // CPU utilization gauge with adaptive polling and quantity icons.
+// The info dialog breaks usage down per core and lists the busiest processes.
 // Consumes Settings: grelier.gauge.cpu.*.
 use crate::dialog::info::InfoDialog;
 use crate::icon::{icon_quantity, svg_asset};
 use crate::panels::gauges::gauge::Gauge;
 use crate::panels::gauges::gauge::{
-    GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
-    GaugeValueAttention,
+    GaugeDisplay, GaugeHistory, GaugeInteractionModel, GaugeModel, GaugePointerInteraction,
+    GaugeValue, GaugeValueAttention,
 };
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
-use std::fs::File;
+use crate::panels::gauges::proc_common::{self, ProcessCpuTracker};
+use crate::settings::{self, SettingSpec};
+use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
+use std::path::Path;
 use std::time::{Duration, Instant};
 
-const DEFAULT_WARNING_THRESHOLD: f32 = 0.90;
-const DEFAULT_DANGER_THRESHOLD: f32 = 1.0;
-const DEFAULT_FAST_THRESHOLD: f32 = 0.50;
-const DEFAULT_FAST_INTERVAL_SECS: u64 = 1;
-const DEFAULT_SLOW_INTERVAL_SECS: u64 = 4;
-const DEFAULT_CALM_TICKS: u8 = 4;
+settings::typed_settings! {
+    struct CpuSettings {
+        warning_threshold: f32 = ("grelier.gauge.cpu.warning_threshold", "0.75", 0.0..=1.0),
+        danger_threshold: f32 = ("grelier.gauge.cpu.danger_threshold", "0.90", 0.0..=1.0),
+        fast_threshold: f32 = ("grelier.gauge.cpu.fast_threshold", "0.50", 0.0..=1.0),
+        calm_ticks: u8 = ("grelier.gauge.cpu.calm_ticks", "4"),
+        fast_interval_secs: u64 = ("grelier.gauge.cpu.fast_interval_secs", "1", 1..=3600),
+        slow_interval_secs: u64 = ("grelier.gauge.cpu.slow_interval_secs", "4", 1..=3600),
+        graph: bool = ("grelier.gauge.cpu.graph", "false"),
+        top_processes: usize = ("grelier.gauge.cpu.top_processes", "5", 0..=20),
+    }
+}
 
 #[derive(Clone, Copy)]
-struct CpuTime {
+pub(crate) struct CpuTime {
     idle: u64,
     non_idle: u64,
 }
 
 impl CpuTime {
-    fn utilization_since(&self, previous: Self) -> f32 {
+    pub(crate) fn utilization_since(&self, previous: Self) -> f32 {
         let total_now = self.idle.saturating_add(self.non_idle);
         let total_prev = previous.idle.saturating_add(previous.non_idle);
         let total_delta = total_now.saturating_sub(total_prev);
@@ -41,15 +49,20 @@ impl CpuTime {
     }
 }
 
-fn read_cpu_time() -> Option<CpuTime> {
-    let file = File::open("/proc/stat").ok()?;
-    let mut lines = BufReader::new(file).lines();
-    let line = lines.next()?.ok()?;
-
-    if !line.starts_with("cpu ") {
-        return None;
+impl CpuTime {
+    fn total(&self) -> u64 {
+        self.idle.saturating_add(self.non_idle)
     }
+}
+
+/// Aggregate and per-core times from one read of `/proc/stat`.
+pub(crate) struct CpuStat {
+    pub(crate) total: CpuTime,
+    /// One entry per `cpuN` line, in order.
+    cores: Vec<CpuTime>,
+}
 
+fn parse_cpu_line(line: &str) -> Option<CpuTime> {
     let values: Vec<u64> = line
         .split_whitespace()
         .skip(1)
@@ -70,6 +83,53 @@ fn read_cpu_time() -> Option<CpuTime> {
     Some(CpuTime { idle, non_idle })
 }
 
+fn parse_stat(contents: &str) -> Option<CpuStat> {
+    let mut lines = contents.lines();
+    let first = lines.next()?;
+    if !first.starts_with("cpu ") {
+        return None;
+    }
+    let total = parse_cpu_line(first)?;
+    let cores = lines
+        .take_while(|line| line.starts_with("cpu"))
+        .filter_map(parse_cpu_line)
+        .collect();
+    Some(CpuStat { total, cores })
+}
+
+pub(crate) fn read_cpu_stat() -> Option<CpuStat> {
+    parse_stat(&fs::read_to_string("/proc/stat").ok()?)
+}
+
+/// Utilization of each core since the previous sample; empty until there is one.
+fn core_utilization(current: &[CpuTime], previous: &[CpuTime]) -> Vec<f32> {
+    if current.len() != previous.len() {
+        return Vec::new();
+    }
+    current
+        .iter()
+        .zip(previous)
+        .map(|(current, previous)| current.utilization_since(*previous))
+        .collect()
+}
+
+/// Dialog lines for the busiest processes, as a share of total CPU capacity.
+fn process_lines(busiest: &[(String, u64)], total_ticks: u64, limit: usize) -> Vec<String> {
+    if total_ticks == 0 {
+        return Vec::new();
+    }
+    busiest
+        .iter()
+        .take(limit)
+        .map(|(name, ticks)| {
+            format!(
+                "{name}: {:.1}%",
+                (*ticks as f32 / total_ticks as f32 * 100.0).clamp(0.0, 100.0)
+            )
+        })
+        .collect()
+}
+
 fn read_cpu_model() -> Option<String> {
     let file = File::open("/proc/cpuinfo").ok()?;
     for line in BufReader::new(file).lines() {
@@ -120,6 +180,20 @@ struct CpuState {
 }
 
 impl CpuState {
+    fn new(settings: &CpuSettings) -> Self {
+        Self {
+            previous: None,
+            fast_interval: false,
+            below_threshold_streak: 0,
+            fast_threshold: settings.fast_threshold,
+            calm_ticks: settings.calm_ticks,
+            fast_interval_duration: Duration::from_secs(settings.fast_interval_secs),
+            slow_interval_duration: Duration::from_secs(settings.slow_interval_secs),
+            warning_threshold: settings.warning_threshold,
+            danger_threshold: settings.danger_threshold,
+        }
+    }
+
     fn update_interval_state(&mut self, utilization: f32) {
         if utilization > self.fast_threshold {
             self.fast_interval = true;
@@ -163,6 +237,14 @@ struct CpuGauge {
     state: CpuState,
     /// Human-readable CPU model shown in the info dialog.
     cpu_model: String,
+    /// Recent utilization drawn as a sparkline when `grelier.gauge.cpu.graph` is on.
+    history: Option<GaugeHistory>,
+    /// Per-core times from the previous run.
+    previous_cores: Vec<CpuTime>,
+    /// Per-process CPU time from the previous run.
+    processes: ProcessCpuTracker,
+    /// Number of processes listed in the info dialog.
+    top_processes: usize,
     /// Scheduler deadline for the next run.
     next_deadline: Instant,
 }
@@ -177,12 +259,27 @@ impl Gauge for CpuGauge {
     }
 
     fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
-        let (display, load_line) = match read_cpu_time() {
+        let mut sample = None;
+        let mut cores = Vec::new();
+        let mut total_ticks = 0;
+        let stat = read_cpu_stat();
+        if let Some(stat) = stat.as_ref() {
+            cores = core_utilization(&stat.cores, &self.previous_cores);
+            total_ticks = stat.total.total().saturating_sub(
+                self.state
+                    .previous
+                    .map(|previous| previous.total())
+                    .unwrap_or_default(),
+            );
+            self.previous_cores = stat.cores.clone();
+        }
+        let (display, load_line) = match stat.map(|stat| stat.total) {
             Some(current) => match self.state.previous {
                 Some(previous) => {
                     let utilization = current.utilization_since(previous);
                     self.state.previous = Some(current);
                     self.state.update_interval_state(utilization);
+                    sample = Some(utilization);
                     (
                         cpu_value(
                             Some(utilization),
@@ -214,6 +311,24 @@ impl Gauge for CpuGauge {
         };
 
         self.next_deadline = now + self.state.interval();
+        let display = match self.history.as_mut() {
+            Some(history) => history.record(sample, display),
+            None => display,
+        };
+
+        let mut lines = vec![self.cpu_model.clone(), load_line];
+        if let Some((busiest, usage)) = cores.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)) {
+            lines.push(format!(
+                "Cores: {} (busiest cpu{busiest} at {:.0}%)",
+                cores.len(),
+                usage * 100.0
+            ));
+        }
+        if self.top_processes > 0 {
+            let processes = proc_common::read_processes(Path::new(proc_common::PROC_ROOT));
+            let busiest = self.processes.sample(&processes);
+            lines.extend(process_lines(&busiest, total_ticks, self.top_processes));
+        }
 
         Some(GaugeModel {
             id: "cpu",
@@ -223,7 +338,8 @@ impl Gauge for CpuGauge {
                 left_click: GaugePointerInteraction {
                     info: Some(InfoDialog {
                         title: "CPU".to_string(),
-                        lines: vec![self.cpu_model.clone(), load_line],
+                        lines,
+                        graph: (!cores.is_empty()).then_some(cores),
                     }),
                     ..GaugePointerInteraction::default()
                 },
@@ -234,72 +350,20 @@ impl Gauge for CpuGauge {
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
-    let warning_threshold = settings::settings().get_parsed_or(
-        "grelier.gauge.cpu.warning_threshold",
-        DEFAULT_WARNING_THRESHOLD,
-    );
-    let danger_threshold = settings::settings().get_parsed_or(
-        "grelier.gauge.cpu.danger_threshold",
-        DEFAULT_DANGER_THRESHOLD,
-    );
-    let fast_threshold = settings::settings()
-        .get_parsed_or("grelier.gauge.cpu.fast_threshold", DEFAULT_FAST_THRESHOLD);
-    let calm_ticks =
-        settings::settings().get_parsed_or("grelier.gauge.cpu.calm_ticks", DEFAULT_CALM_TICKS);
-    let fast_interval_secs = settings::settings().get_parsed_or(
-        "grelier.gauge.cpu.fast_interval_secs",
-        DEFAULT_FAST_INTERVAL_SECS,
-    );
-    let slow_interval_secs = settings::settings().get_parsed_or(
-        "grelier.gauge.cpu.slow_interval_secs",
-        DEFAULT_SLOW_INTERVAL_SECS,
-    );
-
+    let settings = CpuSettings::load();
     Box::new(CpuGauge {
-        state: CpuState {
-            previous: None,
-            fast_interval: false,
-            below_threshold_streak: 0,
-            fast_threshold,
-            calm_ticks,
-            fast_interval_duration: Duration::from_secs(fast_interval_secs),
-            slow_interval_duration: Duration::from_secs(slow_interval_secs),
-            warning_threshold,
-            danger_threshold,
-        },
+        state: CpuState::new(&settings),
         cpu_model: read_cpu_model().unwrap_or_else(|| "Unknown CPU".to_string()),
+        history: settings.graph.then(GaugeHistory::from_settings),
+        previous_cores: Vec::new(),
+        processes: ProcessCpuTracker::default(),
+        top_processes: settings.top_processes,
         next_deadline: now,
     })
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[
-        SettingSpec {
-            key: "grelier.gauge.cpu.warning_threshold",
-            default: "0.75",
-        },
-        SettingSpec {
-            key: "grelier.gauge.cpu.danger_threshold",
-            default: "0.90",
-        },
-        SettingSpec {
-            key: "grelier.gauge.cpu.fast_threshold",
-            default: "0.50",
-        },
-        SettingSpec {
-            key: "grelier.gauge.cpu.calm_ticks",
-            default: "4",
-        },
-        SettingSpec {
-            key: "grelier.gauge.cpu.fast_interval_secs",
-            default: "1",
-        },
-        SettingSpec {
-            key: "grelier.gauge.cpu.slow_interval_secs",
-            default: "4",
-        },
-    ];
-    SETTINGS
+    CpuSettings::SPECS
 }
 
 inventory::submit! {
@@ -319,23 +383,14 @@ mod tests {
 
     #[test]
     fn cpu_interval_speeds_up_and_recovers() {
-        let mut state = CpuState {
-            fast_threshold: DEFAULT_FAST_THRESHOLD,
-            calm_ticks: DEFAULT_CALM_TICKS,
-            fast_interval_duration: Duration::from_secs(DEFAULT_FAST_INTERVAL_SECS),
-            slow_interval_duration: Duration::from_secs(DEFAULT_SLOW_INTERVAL_SECS),
-            warning_threshold: DEFAULT_WARNING_THRESHOLD,
-            danger_threshold: DEFAULT_DANGER_THRESHOLD,
-            previous: None,
-            fast_interval: false,
-            below_threshold_streak: 0,
-        };
+        let defaults = CpuSettings::default();
+        let mut state = CpuState::new(&defaults);
 
         // Jump to fast interval when utilization crosses threshold.
         state.update_interval_state(0.6);
         assert_eq!(
             state.interval(),
-            Duration::from_secs(DEFAULT_FAST_INTERVAL_SECS)
+            Duration::from_secs(defaults.fast_interval_secs)
         );
 
         // Stay fast for several below-threshold ticks.
@@ -343,7 +398,7 @@ mod tests {
             state.update_interval_state(0.4);
             assert_eq!(
                 state.interval(),
-                Duration::from_secs(DEFAULT_FAST_INTERVAL_SECS)
+                Duration::from_secs(defaults.fast_interval_secs)
             );
         }
 
@@ -351,14 +406,46 @@ mod tests {
         state.update_interval_state(0.4);
         assert_eq!(
             state.interval(),
-            Duration::from_secs(DEFAULT_SLOW_INTERVAL_SECS)
+            Duration::from_secs(defaults.slow_interval_secs)
+        );
+    }
+
+    #[test]
+    fn parses_cores_and_formats_busiest_processes() {
+        let stat = parse_stat(
+            "cpu  10 0 10 70 10 0 0 0 0 0\n\
+             cpu0 5 0 5 30 10 0 0 0 0 0\n\
+             cpu1 5 0 5 40 0 0 0 0 0 0\n\
+             intr 12345\n",
+        )
+        .expect("stat");
+        assert_eq!(stat.cores.len(), 2);
+        let later = [
+            CpuTime {
+                idle: 50,
+                non_idle: 50,
+            },
+            CpuTime {
+                idle: 40,
+                non_idle: 10,
+            },
+        ];
+        assert_eq!(core_utilization(&later, &stat.cores), vec![0.8, 0.0]);
+        assert!(core_utilization(&later[..1], &stat.cores).is_empty());
+
+        let busiest = vec![("firefox".to_string(), 30), ("sway".to_string(), 5)];
+        assert_eq!(
+            process_lines(&busiest, 200, 1),
+            vec!["firefox: 15.0%".to_string()]
         );
+        assert!(process_lines(&busiest, 0, 5).is_empty());
     }
 
     #[test]
     fn returns_none_on_missing_utilization() {
+        let defaults = CpuSettings::default();
         assert!(matches!(
-            super::cpu_value(None, DEFAULT_WARNING_THRESHOLD, DEFAULT_DANGER_THRESHOLD),
+            super::cpu_value(None, defaults.warning_threshold, defaults.danger_threshold),
             GaugeDisplay::Error
         ));
     }
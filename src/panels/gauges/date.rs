@@ -9,13 +9,17 @@ use crate::panels::gauges::gauge::{
     GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugeValue, GaugeValueAttention,
 };
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
+use crate::settings::{self, SettingSpec};
 
 const SECS_PER_DAY: u64 = 86_400;
 const DAY_LENGTH: Duration = Duration::from_secs(SECS_PER_DAY);
-const DEFAULT_MONTH_FORMAT: &str = "%m";
-const DEFAULT_DAY_FORMAT: &str = "%d";
+
+settings::typed_settings! {
+    struct DateSettings {
+        month_format: String = ("grelier.gauge.date.month_format", "%m"),
+        day_format: String = ("grelier.gauge.date.day_format", "%d"),
+    }
+}
 
 fn day_rollover_delay() -> Duration {
     let now = SystemTime::now();
@@ -78,29 +82,16 @@ impl Gauge for DateGauge {
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
-    let month_format =
-        settings::settings().get_or("grelier.gauge.date.month_format", DEFAULT_MONTH_FORMAT);
-    let day_format =
-        settings::settings().get_or("grelier.gauge.date.day_format", DEFAULT_DAY_FORMAT);
+    let settings = DateSettings::load();
     Box::new(DateGauge {
-        month_format,
-        day_format,
+        month_format: settings.month_format,
+        day_format: settings.day_format,
         next_deadline: now,
     })
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[
-        SettingSpec {
-            key: "grelier.gauge.date.month_format",
-            default: DEFAULT_MONTH_FORMAT,
-        },
-        SettingSpec {
-            key: "grelier.gauge.date.day_format",
-            default: DEFAULT_DAY_FORMAT,
-        },
-    ];
-    SETTINGS
+    DateSettings::SPECS
 }
 
 inventory::submit! {
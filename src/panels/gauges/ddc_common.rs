@@ -0,0 +1,200 @@
+// DDC/CI access to external monitors over /dev/i2c-*, in the style of ddcutil.
+// Requires the i2c-dev module and read/write access to the monitor's i2c device.
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+pub const SYS_DRM: &str = "/sys/class/drm";
+/// VCP feature code for luminance.
+pub const VCP_BRIGHTNESS: u8 = 0x10;
+/// `I2C_SLAVE` ioctl from linux/i2c-dev.h.
+const I2C_SLAVE: u64 = 0x0703;
+/// 7-bit i2c address monitors answer DDC/CI on.
+const DDC_ADDRESS: u64 = 0x37;
+/// Destination address byte (0x37 << 1) folded into request checksums.
+const DDC_DEST: u8 = 0x6e;
+/// Virtual host address folded into reply checksums.
+const DDC_HOST: u8 = 0x50;
+/// Monitors need time to prepare a reply and to apply a change.
+const REPLY_DELAY: Duration = Duration::from_millis(40);
+const SET_DELAY: Duration = Duration::from_millis(50);
+const REPLY_LEN: usize = 11;
+const ATTEMPTS: usize = 3;
+/// Connectors for built-in panels, which are driven through the backlight instead.
+pub const INTERNAL_CONNECTORS: [&str; 3] = ["eDP", "LVDS", "DSI"];
+
+/// An external monitor reachable over DDC/CI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DdcDisplay {
+    /// DRM connector name, such as `DP-1`.
+    pub connector: String,
+    /// i2c character device, such as `/dev/i2c-5`.
+    pub device: PathBuf,
+}
+
+fn checksum(seed: u8, bytes: &[u8]) -> u8 {
+    bytes.iter().fold(seed, |acc, byte| acc ^ byte)
+}
+
+/// "Get VCP feature" request for `code`.
+pub fn get_vcp_request(code: u8) -> [u8; 5] {
+    let mut request = [0x51, 0x82, 0x01, code, 0];
+    request[4] = checksum(DDC_DEST, &request[..4]);
+    request
+}
+
+/// "Set VCP feature" request writing `value` to `code`.
+pub fn set_vcp_request(code: u8, value: u16) -> [u8; 7] {
+    let [high, low] = value.to_be_bytes();
+    let mut request = [0x51, 0x84, 0x03, code, high, low, 0];
+    request[6] = checksum(DDC_DEST, &request[..6]);
+    request
+}
+
+/// Parse a "Get VCP feature" reply into `(current, max)`.
+pub fn parse_vcp_reply(code: u8, reply: &[u8]) -> Result<(u16, u16), String> {
+    let reply = reply
+        .get(..REPLY_LEN)
+        .ok_or_else(|| "short DDC reply".to_string())?;
+    if reply[0] != DDC_DEST || reply[1] != 0x88 || reply[2] != 0x02 {
+        return Err("malformed DDC reply".to_string());
+    }
+    if checksum(DDC_HOST, &reply[..REPLY_LEN - 1]) != reply[REPLY_LEN - 1] {
+        return Err("DDC reply checksum mismatch".to_string());
+    }
+    if reply[3] != 0 {
+        return Err(format!("monitor does not support VCP feature {code:#04x}"));
+    }
+    if reply[4] != code {
+        return Err(format!("DDC reply for feature {:#04x}", reply[4]));
+    }
+    let max = u16::from_be_bytes([reply[6], reply[7]]);
+    let current = u16::from_be_bytes([reply[8], reply[9]]);
+    Ok((current, max))
+}
+
+impl DdcDisplay {
+    fn open(&self) -> io::Result<File> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.device)?;
+        // SAFETY: I2C_SLAVE takes the target address by value and only affects this descriptor.
+        let result = unsafe { libc::ioctl(file.as_raw_fd(), I2C_SLAVE as _, DDC_ADDRESS) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(file)
+    }
+
+    /// Read `(current, max)` for a VCP feature, retrying the flaky bus a few times.
+    pub fn get_vcp(&self, code: u8) -> io::Result<(u16, u16)> {
+        let mut file = self.open()?;
+        let mut last_err = io::Error::other("no DDC reply");
+        for _ in 0..ATTEMPTS {
+            file.write_all(&get_vcp_request(code))?;
+            thread::sleep(REPLY_DELAY);
+            let mut reply = [0u8; REPLY_LEN];
+            if let Err(err) = file.read_exact(&mut reply) {
+                last_err = err;
+                continue;
+            }
+            match parse_vcp_reply(code, &reply) {
+                Ok(value) => return Ok(value),
+                Err(err) => last_err = io::Error::new(io::ErrorKind::InvalidData, err),
+            }
+        }
+        Err(last_err)
+    }
+
+    pub fn set_vcp(&self, code: u8, value: u16) -> io::Result<()> {
+        let mut file = self.open()?;
+        file.write_all(&set_vcp_request(code, value))?;
+        thread::sleep(SET_DELAY);
+        Ok(())
+    }
+}
+
+/// i2c device for a DRM connector directory, from its `ddc` link or an `i2c-N` child (DP AUX).
+fn connector_bus(connector_dir: &Path) -> Option<PathBuf> {
+    let bus = fs::read_link(connector_dir.join("ddc"))
+        .ok()
+        .and_then(|target| target.file_name().map(|name| name.to_owned()))
+        .or_else(|| {
+            fs::read_dir(connector_dir)
+                .ok()?
+                .flatten()
+                .find_map(|entry| {
+                    let name = entry.file_name();
+                    name.to_string_lossy().starts_with("i2c-").then_some(name)
+                })
+        })?;
+    Some(Path::new("/dev").join(bus))
+}
+
+/// Connected external monitors under `drm_root`, sorted by connector name.
+pub fn discover(drm_root: &Path) -> Vec<DdcDisplay> {
+    let Ok(entries) = fs::read_dir(drm_root) else {
+        return Vec::new();
+    };
+    let mut displays: Vec<DdcDisplay> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Connector entries are named `card<N>-<connector>`.
+            let (card, connector) = name.split_once('-')?;
+            if !card.starts_with("card")
+                || INTERNAL_CONNECTORS
+                    .iter()
+                    .any(|internal| connector.starts_with(internal))
+            {
+                return None;
+            }
+            let path = entry.path();
+            let status = fs::read_to_string(path.join("status")).ok()?;
+            if status.trim() != "connected" {
+                return None;
+            }
+            Some(DdcDisplay {
+                connector: connector.to_string(),
+                device: connector_bus(&path)?,
+            })
+        })
+        .collect();
+    displays.sort_by(|a, b| a.connector.cmp(&b.connector));
+    displays
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_requests_and_parses_replies() {
+        assert_eq!(
+            get_vcp_request(VCP_BRIGHTNESS),
+            [0x51, 0x82, 0x01, 0x10, 0xac]
+        );
+        assert_eq!(
+            set_vcp_request(VCP_BRIGHTNESS, 70),
+            [0x51, 0x84, 0x03, 0x10, 0x00, 0x46, 0xee]
+        );
+
+        let mut reply = [
+            0x6e, 0x88, 0x02, 0x00, 0x10, 0x00, 0x00, 0x64, 0x00, 0x32, 0,
+        ];
+        reply[10] = checksum(DDC_HOST, &reply[..10]);
+        assert_eq!(parse_vcp_reply(VCP_BRIGHTNESS, &reply), Ok((50, 100)));
+
+        let mut unsupported = reply;
+        unsupported[3] = 0x01;
+        unsupported[10] = checksum(DDC_HOST, &unsupported[..10]);
+        assert!(parse_vcp_reply(VCP_BRIGHTNESS, &unsupported).is_err());
+        reply[10] ^= 0xff;
+        assert!(parse_vcp_reply(VCP_BRIGHTNESS, &reply).is_err());
+        assert!(parse_vcp_reply(VCP_BRIGHTNESS, &reply[..4]).is_err());
+    }
+}
@@ -8,8 +8,9 @@ use crate::panels::gauges::gauge::{
     GaugeValueAttention,
 };
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
+use crate::panels::gauges::gauge_tuning;
+use crate::settings::{self, SettingSpec};
+use crate::units::Units;
 use std::cmp::Ordering;
 use std::ffi::CString;
 use std::fs;
@@ -17,10 +18,14 @@ use std::mem::MaybeUninit;
 use std::os::raw::{c_char, c_int, c_ulong};
 use std::time::{Duration, Instant};
 
-const DEFAULT_ROOT_PATH: &str = "/";
-const DEFAULT_POLL_INTERVAL_SECS: u64 = 60;
-const DEFAULT_WARNING_THRESHOLD: f32 = 0.85;
-const DEFAULT_DANGER_THRESHOLD: f32 = 0.95;
+settings::typed_settings! {
+    struct DiskSettings {
+        path: String = ("grelier.gauge.disk.path", "/"),
+        poll_interval_secs: u64 = ("grelier.gauge.disk.poll_interval_secs", "60", 1..=86400),
+        warning_threshold: f32 = ("grelier.gauge.disk.warning_threshold", "0.85", 0.0..=1.0),
+        danger_threshold: f32 = ("grelier.gauge.disk.danger_threshold", "0.95", 0.0..=1.0),
+    }
+}
 
 #[repr(C)]
 #[derive(Clone, Copy)]
@@ -44,12 +49,12 @@ unsafe extern "C" {
 }
 
 #[derive(Clone, Copy)]
-struct DiskUsage {
-    used: u64,
-    total: u64,
+pub(crate) struct DiskUsage {
+    pub(crate) used: u64,
+    pub(crate) total: u64,
 }
 
-fn disk_usage(path: &str) -> Option<DiskUsage> {
+pub(crate) fn disk_usage(path: &str) -> Option<DiskUsage> {
     let c_path = CString::new(path).ok()?;
 
     let mut stats = MaybeUninit::<Statvfs>::uninit();
@@ -117,23 +122,6 @@ fn path_matches_mount(path: &str, mount_point: &str) -> bool {
     matches!(path.as_bytes().get(mount_point.len()), Some(b'/') | None)
 }
 
-fn format_bytes(bytes: u64) -> String {
-    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
-    let mut value = bytes as f64;
-    let mut unit = 0usize;
-    while value >= 1024.0 && unit < UNITS.len() - 1 {
-        value /= 1024.0;
-        unit += 1;
-    }
-    if unit == 0 {
-        format!("{:.0} {}", value, UNITS[unit])
-    } else if value < 10.0 {
-        format!("{:.1} {}", value, UNITS[unit])
-    } else {
-        format!("{:.0} {}", value, UNITS[unit])
-    }
-}
-
 fn attention_for(
     utilization: f32,
     warning_threshold: f32,
@@ -172,6 +160,8 @@ struct DiskGauge {
     danger_threshold: f32,
     /// Poll cadence for filesystem usage sampling.
     poll_interval: Duration,
+    /// Unit preferences for the info dialog.
+    units: Units,
     /// Scheduler deadline for the next run.
     next_deadline: Instant,
 }
@@ -201,13 +191,17 @@ impl Gauge for DiskGauge {
         let (total_line, used_line) = usage
             .map(|usage| {
                 (
-                    format!("Total: {}", format_bytes(usage.total)),
-                    format!("Used: {}", format_bytes(usage.used)),
+                    format!("Total: {}", self.units.format_bytes(usage.total)),
+                    format!("Used: {}", self.units.format_bytes(usage.used)),
                 )
             })
             .unwrap_or_else(|| ("Total: N/A".to_string(), "Used: N/A".to_string()));
 
-        self.next_deadline = now + self.poll_interval;
+        self.next_deadline = now
+            + Duration::from_secs(gauge_tuning::live_secs(
+                "disk",
+                self.poll_interval.as_secs(),
+            ));
 
         Some(GaugeModel {
             id: "disk",
@@ -218,6 +212,7 @@ impl Gauge for DiskGauge {
                     info: Some(InfoDialog {
                         title: "Disk".to_string(),
                         lines: vec![device, total_line, used_line],
+                        graph: None,
                     }),
                     ..GaugePointerInteraction::default()
                 },
@@ -228,49 +223,19 @@ impl Gauge for DiskGauge {
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
-    let path = settings::settings().get_or("grelier.gauge.disk.path", DEFAULT_ROOT_PATH);
-    let poll_interval_secs = settings::settings().get_parsed_or(
-        "grelier.gauge.disk.poll_interval_secs",
-        DEFAULT_POLL_INTERVAL_SECS,
-    );
-    let warning_threshold = settings::settings().get_parsed_or(
-        "grelier.gauge.disk.warning_threshold",
-        DEFAULT_WARNING_THRESHOLD,
-    );
-    let danger_threshold = settings::settings().get_parsed_or(
-        "grelier.gauge.disk.danger_threshold",
-        DEFAULT_DANGER_THRESHOLD,
-    );
-
+    let settings = DiskSettings::load();
     Box::new(DiskGauge {
-        path,
-        warning_threshold,
-        danger_threshold,
-        poll_interval: Duration::from_secs(poll_interval_secs),
+        poll_interval: Duration::from_secs(settings.poll_interval_secs),
+        warning_threshold: settings.warning_threshold,
+        danger_threshold: settings.danger_threshold,
+        path: settings.path,
+        units: Units::load(),
         next_deadline: now,
     })
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[
-        SettingSpec {
-            key: "grelier.gauge.disk.path",
-            default: DEFAULT_ROOT_PATH,
-        },
-        SettingSpec {
-            key: "grelier.gauge.disk.poll_interval_secs",
-            default: "60",
-        },
-        SettingSpec {
-            key: "grelier.gauge.disk.warning_threshold",
-            default: "0.85",
-        },
-        SettingSpec {
-            key: "grelier.gauge.disk.danger_threshold",
-            default: "0.95",
-        },
-    ];
-    SETTINGS
+    DiskSettings::SPECS
 }
 
 inventory::submit! {
@@ -290,8 +255,9 @@ mod tests {
 
     #[test]
     fn returns_none_on_missing_utilization() {
+        let defaults = DiskSettings::default();
         assert!(matches!(
-            disk_value(None, DEFAULT_WARNING_THRESHOLD, DEFAULT_DANGER_THRESHOLD),
+            disk_value(None, defaults.warning_threshold, defaults.danger_threshold),
             GaugeDisplay::Error
         ));
     }
@@ -0,0 +1,342 @@
+// Display feature gauge: per-output adaptive sync and HDR toggles through the compositor,
+// plus the ThinkPad privacy screen when present.
+// Consumes Settings: grelier.gauge.display_features.*.
+use crate::compositor::{self, OutputFeature, OutputFeatures};
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{Gauge, GaugeReadyNotify};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel,
+    GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant};
+
+/// thinkpad_acpi's privacy screen (ePrivacy) switch.
+const LCDSHADOW_PATH: &str = "/proc/acpi/ibm/lcdshadow";
+const PRIVACY_ITEM: &str = "privacy";
+
+settings::typed_settings! {
+    struct DisplayFeaturesSettings {
+        poll_interval_secs: u64 = ("grelier.gauge.display_features.poll_interval_secs", "10", 1..=3600),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FeatureCommand {
+    /// Flip `feature` on the named output.
+    Toggle(String, OutputFeature),
+    TogglePrivacy,
+}
+
+/// Menu item id for one output feature: `<output>/<feature>`.
+fn item_id(output: &str, feature: OutputFeature) -> String {
+    format!("{output}/{}", feature.key())
+}
+
+fn parse_item(id: &str) -> Option<FeatureCommand> {
+    if id == PRIVACY_ITEM {
+        return Some(FeatureCommand::TogglePrivacy);
+    }
+    let (output, feature) = id.rsplit_once('/')?;
+    Some(FeatureCommand::Toggle(
+        output.to_string(),
+        OutputFeature::from_key(feature)?,
+    ))
+}
+
+/// Parse `/proc/acpi/ibm/lcdshadow`: `status:\t\t1`. Negative values mean no privacy screen.
+fn parse_lcdshadow(contents: &str) -> Option<bool> {
+    let status = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("status:"))?
+        .trim()
+        .parse::<i32>()
+        .ok()?;
+    (status >= 0).then_some(status > 0)
+}
+
+fn read_privacy(path: &Path) -> Option<bool> {
+    parse_lcdshadow(&fs::read_to_string(path).ok()?)
+}
+
+fn write_privacy(path: &Path, enabled: bool) -> Result<(), String> {
+    fs::write(path, if enabled { "1" } else { "0" })
+        .map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn feature_menu(
+    outputs: &[OutputFeatures],
+    privacy: Option<bool>,
+    on_select: MenuSelectAction,
+) -> Option<GaugeMenu> {
+    let mut items: Vec<GaugeMenuItem> = outputs
+        .iter()
+        .flat_map(|output| {
+            OutputFeature::ALL.into_iter().filter_map(|feature| {
+                Some(GaugeMenuItem {
+                    id: item_id(&output.name, feature),
+                    label: format!("{}: {}", output.name, feature.label()),
+                    selected: output.get(feature)?,
+                    exec: None,
+                })
+            })
+        })
+        .collect();
+    if let Some(enabled) = privacy {
+        items.push(GaugeMenuItem {
+            id: PRIVACY_ITEM.to_string(),
+            label: "Privacy screen".to_string(),
+            selected: enabled,
+            exec: None,
+        });
+    }
+    (!items.is_empty()).then(|| GaugeMenu {
+        title: "Display Features".to_string(),
+        items,
+        on_select: Some(on_select),
+        slider: None,
+    })
+}
+
+fn feature_lines(outputs: &[OutputFeatures], privacy: Option<bool>) -> Vec<String> {
+    let state = |value: Option<bool>| match value {
+        Some(true) => "on",
+        Some(false) => "off",
+        None => "unsupported",
+    };
+    let mut lines: Vec<String> = outputs
+        .iter()
+        .map(|output| {
+            format!(
+                "{}: VRR {}, HDR {}",
+                output.name,
+                state(output.adaptive_sync),
+                state(output.hdr)
+            )
+        })
+        .collect();
+    if let Some(enabled) = privacy {
+        lines.push(format!("Privacy screen: {}", state(Some(enabled))));
+    }
+    lines
+}
+
+fn any_enabled(outputs: &[OutputFeatures], privacy: Option<bool>) -> bool {
+    privacy == Some(true)
+        || outputs.iter().any(|output| {
+            OutputFeature::ALL
+                .into_iter()
+                .any(|feature| output.get(feature) == Some(true))
+        })
+}
+
+/// Gauge listing each output's adaptive sync and HDR state, toggled from its menu.
+struct DisplayFeaturesGauge {
+    poll_interval: Duration,
+    /// Sender cloned into menu callbacks to queue toggles.
+    command_tx: mpsc::Sender<FeatureCommand>,
+    /// Receiver drained on each run to apply queued toggles.
+    command_rx: mpsc::Receiver<FeatureCommand>,
+    /// Notifier used to request an immediate scheduler wake-up after actions.
+    ready_notify: Option<GaugeReadyNotify>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl DisplayFeaturesGauge {
+    fn apply(&self, command: FeatureCommand, outputs: &[OutputFeatures], privacy: Option<bool>) {
+        let result = match &command {
+            FeatureCommand::Toggle(name, feature) => {
+                let current = outputs
+                    .iter()
+                    .find(|output| &output.name == name)
+                    .and_then(|output| output.get(*feature));
+                match current {
+                    Some(enabled) => {
+                        compositor::backend().set_output_feature(name, *feature, !enabled)
+                    }
+                    None => Err(format!("{name} does not support {}", feature.label())),
+                }
+            }
+            FeatureCommand::TogglePrivacy => match privacy {
+                Some(enabled) => write_privacy(Path::new(LCDSHADOW_PATH), !enabled),
+                None => Err("no privacy screen".to_string()),
+            },
+        };
+        if let Err(err) = result {
+            log::warn!("display_features gauge: {command:?} failed: {err}");
+        }
+    }
+
+    fn menu_action(&self) -> MenuSelectAction {
+        let command_tx = self.command_tx.clone();
+        let ready_notify = self.ready_notify.clone();
+        Arc::new(move |id: String| {
+            if let Some(command) = parse_item(&id) {
+                let _ = command_tx.send(command);
+                if let Some(ready_notify) = &ready_notify {
+                    ready_notify("display_features");
+                }
+            }
+        })
+    }
+}
+
+impl Gauge for DisplayFeaturesGauge {
+    fn id(&self) -> &'static str {
+        "display_features"
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + self.poll_interval;
+
+        let privacy_path = Path::new(LCDSHADOW_PATH);
+        let mut outputs = compositor::backend().fetch_output_features();
+        let mut privacy = read_privacy(privacy_path);
+        let mut toggled = false;
+        while let Ok(command) = self.command_rx.try_recv() {
+            self.apply(command, outputs.as_deref().unwrap_or_default(), privacy);
+            toggled = true;
+        }
+        if toggled {
+            outputs = compositor::backend().fetch_output_features();
+            privacy = read_privacy(privacy_path);
+        }
+
+        let (display, lines, menu) = match outputs {
+            Ok(outputs) => (
+                GaugeDisplay::Value {
+                    value: GaugeValue::Text(
+                        if any_enabled(&outputs, privacy) {
+                            "on"
+                        } else {
+                            "off"
+                        }
+                        .to_string(),
+                    ),
+                    attention: GaugeValueAttention::Nominal,
+                },
+                feature_lines(&outputs, privacy),
+                feature_menu(&outputs, privacy, self.menu_action()),
+            ),
+            Err(err) => (GaugeDisplay::Error, vec![err], None),
+        };
+
+        Some(GaugeModel {
+            id: "display_features",
+            icon: svg_asset("display.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Display Features".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                right_click: GaugePointerInteraction {
+                    menu,
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = DisplayFeaturesSettings::load();
+    let (command_tx, command_rx) = mpsc::channel();
+    Box::new(DisplayFeaturesGauge {
+        poll_interval: Duration::from_secs(settings.poll_interval_secs),
+        command_tx,
+        command_rx,
+        ready_notify: None,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    DisplayFeaturesSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "display_features",
+        description: "Display features gauge toggling adaptive sync (VRR) and HDR per output, and the privacy screen.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu_reflects_per_output_state_and_round_trips_ids() {
+        let outputs = vec![
+            OutputFeatures {
+                name: "DP-1".to_string(),
+                adaptive_sync: Some(true),
+                hdr: Some(false),
+            },
+            OutputFeatures {
+                name: "eDP-1".to_string(),
+                adaptive_sync: Some(false),
+                hdr: None,
+            },
+        ];
+        let menu = feature_menu(&outputs, Some(false), Arc::new(|_| {})).expect("menu");
+        assert_eq!(
+            menu.items
+                .iter()
+                .map(|item| (item.label.as_str(), item.selected))
+                .collect::<Vec<_>>(),
+            vec![
+                ("DP-1: Adaptive sync", true),
+                ("DP-1: HDR", false),
+                ("eDP-1: Adaptive sync", false),
+                ("Privacy screen", false),
+            ]
+        );
+        assert_eq!(
+            parse_item(&menu.items[1].id),
+            Some(FeatureCommand::Toggle(
+                "DP-1".to_string(),
+                OutputFeature::Hdr
+            ))
+        );
+        assert_eq!(
+            parse_item(PRIVACY_ITEM),
+            Some(FeatureCommand::TogglePrivacy)
+        );
+        assert_eq!(
+            feature_lines(&outputs, None)[1],
+            "eDP-1: VRR off, HDR unsupported"
+        );
+        assert!(any_enabled(&outputs, None));
+
+        assert_eq!(
+            parse_lcdshadow("status:\t\t1\ncommands:\t0, 1\n"),
+            Some(true)
+        );
+        assert_eq!(parse_lcdshadow("status:\t\t-1\n"), None);
+    }
+}
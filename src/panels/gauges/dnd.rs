@@ -0,0 +1,310 @@
+// Do Not Disturb gauge: a toggle that quiets notification attention and toasts.
+// Serves org.grelier.DoNotDisturb on the session bus so other tools can read or flip the state.
+// Consumes Settings: grelier.gauge.dnd.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    AsyncGaugeEventSource, Gauge, GaugeClick, GaugeClickAction, GaugeFuture, GaugeReadyNotify,
+    GaugeRegistrar,
+};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use futures_lite::{StreamExt, future};
+use iced::futures::channel::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zbus::object_server::{InterfaceRef, SignalContext};
+
+const BUS_NAME: &str = "org.grelier.DoNotDisturb";
+const OBJECT_PATH: &str = "/org/grelier/DoNotDisturb";
+const ENABLED_KEY: &str = "grelier.gauge.dnd.enabled";
+
+settings::typed_settings! {
+    struct DndSettings {
+        enabled: bool = ("grelier.gauge.dnd.enabled", "false"),
+    }
+}
+
+/// Process-wide Do Not Disturb state, read by the notification gauge and toasts.
+static DND_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether Do Not Disturb is on. Always false when the `dnd` gauge is not running.
+pub fn dnd_enabled() -> bool {
+    DND_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Store and persist a new state, then refresh the gauges that display it.
+fn apply_state(enabled: bool, notify: &GaugeReadyNotify) {
+    if DND_ENABLED.swap(enabled, Ordering::Relaxed) == enabled {
+        return;
+    }
+    log::info!(
+        "do not disturb {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    settings::settings().update(ENABLED_KEY, if enabled { "true" } else { "false" });
+    notify("dnd");
+    notify("notifications");
+}
+
+/// D-Bus object exposing the state as a read/write property.
+struct DndServer {
+    ready_notify: GaugeReadyNotify,
+}
+
+#[zbus::interface(name = "org.grelier.DoNotDisturb")]
+impl DndServer {
+    #[zbus(property)]
+    fn enabled(&self) -> bool {
+        dnd_enabled()
+    }
+
+    #[zbus(property)]
+    fn set_enabled(&mut self, enabled: bool) {
+        apply_state(enabled, &self.ready_notify);
+    }
+
+    async fn toggle(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<bool> {
+        let enabled = !dnd_enabled();
+        apply_state(enabled, &self.ready_notify);
+        self.enabled_changed(&ctxt).await?;
+        Ok(enabled)
+    }
+}
+
+/// Async event source that owns the bus connection and applies toggles from the bar.
+struct DndService {
+    toggle_rx: mpsc::UnboundedReceiver<()>,
+    /// Set when the bus name could not be acquired; the toggle still works locally.
+    error: Arc<Mutex<Option<String>>>,
+    ready_notify: Option<GaugeReadyNotify>,
+    /// Served interface, used to announce toggles; `None` when the bus is unavailable.
+    iface: Option<InterfaceRef<DndServer>>,
+    /// Whether the first poll already tried to acquire the bus name.
+    served: bool,
+}
+
+impl DndService {
+    async fn serve(notify: GaugeReadyNotify) -> zbus::Result<InterfaceRef<DndServer>> {
+        let server = DndServer {
+            ready_notify: notify,
+        };
+        let connection = zbus::connection::Builder::session()?
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, server)?
+            .build()
+            .await?;
+        connection
+            .object_server()
+            .interface::<_, DndServer>(OBJECT_PATH)
+            .await
+    }
+}
+
+impl AsyncGaugeEventSource for DndService {
+    fn interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Each poll waits for the next toggle, however long that takes.
+    fn timeout(&self) -> Duration {
+        Duration::MAX
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn poll(&mut self) -> GaugeFuture<'_, bool> {
+        Box::pin(async move {
+            let Some(notify) = self.ready_notify.clone() else {
+                return future::pending().await;
+            };
+            if !self.served {
+                self.served = true;
+                match Self::serve(notify.clone()).await {
+                    Ok(iface) => self.iface = Some(iface),
+                    Err(err) => {
+                        log::warn!("dnd gauge: failed to acquire {BUS_NAME}: {err}");
+                        if let Ok(mut error) = self.error.lock() {
+                            *error = Some(err.to_string());
+                        }
+                        return true;
+                    }
+                }
+            }
+            // The gauge is gone once its sender closes; idle until the task is cancelled.
+            if self.toggle_rx.next().await.is_none() {
+                return future::pending().await;
+            }
+            apply_state(!dnd_enabled(), &notify);
+            if let Some(iface) = &self.iface {
+                let server = iface.get().await;
+                if let Err(err) = server.enabled_changed(iface.signal_context()).await {
+                    log::warn!("dnd gauge: failed to emit PropertiesChanged: {err}");
+                }
+            }
+            // `apply_state` already woke the gauges that show the state.
+            false
+        })
+    }
+}
+
+fn dnd_model(enabled: bool, error: Option<&str>, on_click: GaugeClickAction) -> GaugeModel {
+    let mut lines = vec![
+        if enabled {
+            "Do Not Disturb: on".to_string()
+        } else {
+            "Do Not Disturb: off".to_string()
+        },
+        "Notifications stay quiet and only critical toasts are shown while on.".to_string(),
+    ];
+    let attention = match error {
+        Some(err) => {
+            lines.push(format!("D-Bus service unavailable: {err}"));
+            GaugeValueAttention::Warning
+        }
+        None => {
+            lines.push(format!("D-Bus: {BUS_NAME} {OBJECT_PATH}"));
+            GaugeValueAttention::Nominal
+        }
+    };
+
+    GaugeModel {
+        id: "dnd",
+        icon: svg_asset(if enabled { "bell-off.svg" } else { "bell.svg" }),
+        display: GaugeDisplay::Value {
+            value: GaugeValue::Text(if enabled { "on" } else { "off" }.to_string()),
+            attention,
+        },
+        interactions: GaugeInteractionModel {
+            left_click: GaugePointerInteraction {
+                on_input: Some(on_click),
+                ..GaugePointerInteraction::default()
+            },
+            right_click: GaugePointerInteraction {
+                info: Some(InfoDialog {
+                    title: "Do Not Disturb".to_string(),
+                    lines,
+                    graph: None,
+                }),
+                ..GaugePointerInteraction::default()
+            },
+            ..GaugeInteractionModel::default()
+        },
+    }
+}
+
+/// Gauge showing and toggling Do Not Disturb.
+struct DndGauge {
+    /// Sender used by clicks to request a toggle from the service.
+    toggle_tx: mpsc::UnboundedSender<()>,
+    /// Bus error reported by the service, if any.
+    error: Arc<Mutex<Option<String>>>,
+    /// Service handed to the work manager on registration.
+    service: Option<DndService>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for DndGauge {
+    fn id(&self) -> &'static str {
+        "dnd"
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(service) = self.service.take() {
+            registrar.add_async_event_source(Box::new(service));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        // Toggles notify the work manager directly; the deadline is only a fallback refresh.
+        self.next_deadline = now + Duration::from_secs(3600);
+        let error = self.error.lock().ok()?.clone();
+        let toggle_tx = self.toggle_tx.clone();
+        let on_click: GaugeClickAction = Arc::new(move |_click: GaugeClick| {
+            let _ = toggle_tx.unbounded_send(());
+        });
+        Some(dnd_model(dnd_enabled(), error.as_deref(), on_click))
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = DndSettings::load();
+    DND_ENABLED.store(settings.enabled, Ordering::Relaxed);
+    let error = Arc::new(Mutex::new(None));
+    let (toggle_tx, toggle_rx) = mpsc::unbounded();
+
+    Box::new(DndGauge {
+        toggle_tx,
+        error: Arc::clone(&error),
+        service: Some(DndService {
+            toggle_rx,
+            error,
+            ready_notify: None,
+            iface: None,
+            served: false,
+        }),
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    DndSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "dnd",
+        description: "Do Not Disturb toggle that quiets notification attention and toasts.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_reflects_state_and_bus_errors() {
+        let on_click: GaugeClickAction = Arc::new(|_| {});
+        let model = dnd_model(true, None, on_click.clone());
+        assert_eq!(model.icon, svg_asset("bell-off.svg"));
+        assert!(matches!(
+            model.display,
+            GaugeDisplay::Value {
+                value: GaugeValue::Text(ref text),
+                attention: GaugeValueAttention::Nominal,
+            } if text == "on"
+        ));
+        assert!(model.interactions.left_click.on_input.is_some());
+
+        let model = dnd_model(false, Some("name taken"), on_click);
+        assert!(matches!(
+            model.display,
+            GaugeDisplay::Value {
+                attention: GaugeValueAttention::Warning,
+                ..
+            }
+        ));
+        let info = model.interactions.right_click.info.expect("info");
+        assert_eq!(info.lines[0], "Do Not Disturb: off");
+        assert!(info.lines.iter().any(|line| line.contains("name taken")));
+    }
+}
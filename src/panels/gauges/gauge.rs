@@ -1,14 +1,18 @@
 // Gauge models, menus, and interaction payloads.
 use iced::mouse;
 use iced::widget::svg;
+use std::collections::VecDeque;
 use std::fmt;
-use std::sync::Arc;
-use std::time::Instant;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, mpsc};
+use std::time::{Duration, Instant};
 
 use crate::dialog::info::InfoDialog;
+use crate::settings;
 
 /// Severity level used when rendering gauge values.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub enum GaugeValueAttention {
     #[default]
     Nominal,
@@ -30,22 +34,91 @@ pub enum GaugeDisplay {
         value: GaugeValue,
         attention: GaugeValueAttention,
     },
+    /// Small time-series chart drawn in the value area: samples oldest first, scaled to 0..=1.
+    Graph {
+        samples: Vec<f32>,
+        attention: GaugeValueAttention,
+    },
     Empty,
     Error,
 }
 
+pub const GRAPH_SAMPLES_KEY: &str = "grelier.gauge.ui.graph_samples";
+const DEFAULT_GRAPH_SAMPLES: usize = 10;
+
+/// Number of samples a bar sparkline shows.
+pub fn graph_samples() -> usize {
+    settings::settings()
+        .get_parsed_or(GRAPH_SAMPLES_KEY, DEFAULT_GRAPH_SAMPLES)
+        .max(1)
+}
+
+/// Ring buffer of recent samples for [`GaugeDisplay::Graph`].
+#[derive(Debug, Clone)]
+pub struct GaugeHistory {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl GaugeHistory {
+    /// Sized by `grelier.gauge.ui.graph_samples`.
+    pub fn from_settings() -> Self {
+        Self::new(graph_samples())
+    }
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Append a sample, clamped to 0..=1, dropping the oldest once full.
+    pub fn push(&mut self, sample: f32) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample.clamp(0.0, 1.0));
+    }
+
+    pub fn samples(&self) -> Vec<f32> {
+        self.samples.iter().copied().collect()
+    }
+
+    /// Record `sample` and turn a value `display` into a graph with the same attention.
+    /// Errors and empty displays pass through, so a failed read still shows as one.
+    pub fn record(&mut self, sample: Option<f32>, display: GaugeDisplay) -> GaugeDisplay {
+        if let Some(sample) = sample {
+            self.push(sample);
+        }
+        match display {
+            GaugeDisplay::Value { attention, .. } | GaugeDisplay::Graph { attention, .. } => {
+                GaugeDisplay::Graph {
+                    samples: self.samples(),
+                    attention,
+                }
+            }
+            other => other,
+        }
+    }
+}
+
 /// One selectable entry in a gauge menu.
 #[derive(Debug, Clone)]
 pub struct GaugeMenuItem {
     pub id: String,
     pub label: String,
     pub selected: bool,
+    /// Shell command spawned detached when chosen, instead of calling `on_select`.
+    pub exec: Option<String>,
 }
 
 /// Callback invoked when a gauge menu item is selected.
 pub type MenuSelectAction = Arc<dyn Fn(String) + Send + Sync>;
 /// Callback invoked when a gauge action item is selected.
 pub type ActionSelectAction = MenuSelectAction;
+/// Confirmation prompt for an action item id; `None` runs the item without asking.
+pub type ActionConfirmPrompt = fn(&str) -> Option<String>;
 /// Callback invoked when a gauge menu slider value changes.
 pub type MenuSliderAction = Arc<dyn Fn(u8) + Send + Sync>;
 
@@ -68,11 +141,24 @@ pub struct GaugeMenu {
     pub slider: Option<GaugeMenuSlider>,
 }
 
+impl fmt::Debug for GaugeMenu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GaugeMenu")
+            .field("title", &self.title)
+            .field("items", &self.items)
+            .field("on_select", &self.on_select.as_ref().map(|_| "<set>"))
+            .field("slider", &self.slider.as_ref().map(|slider| slider.value))
+            .finish()
+    }
+}
+
 /// One action entry shown in a gauge action dialog.
 #[derive(Debug, Clone)]
 pub struct GaugeActionItem {
     pub id: String,
     pub icon: svg::Handle,
+    /// Shell command spawned detached when chosen, instead of calling `on_select`.
+    pub exec: Option<String>,
 }
 
 /// Action dialog model shown for a gauge.
@@ -81,6 +167,8 @@ pub struct GaugeActionDialog {
     pub title: String,
     pub items: Vec<GaugeActionItem>,
     pub on_select: Option<ActionSelectAction>,
+    /// When set, items with a prompt open a confirm/cancel menu before `on_select` runs.
+    pub confirm: Option<ActionConfirmPrompt>,
 }
 
 /// Interaction capabilities for one pointer input type.
@@ -150,6 +238,34 @@ pub struct GaugeModel {
     pub interactions: GaugeInteractionModel,
 }
 
+/// Which parts of a gauge differ from the model it replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GaugeRedraw {
+    pub icon: bool,
+    pub value: bool,
+    pub interactions: bool,
+}
+
+impl GaugeRedraw {
+    /// Every part changed, as for a gauge's first model.
+    pub const ALL: GaugeRedraw = GaugeRedraw {
+        icon: true,
+        value: true,
+        interactions: true,
+    };
+
+    pub fn is_empty(self) -> bool {
+        !(self.icon || self.value || self.interactions)
+    }
+}
+
+/// A model emitted by the work manager, with hints for which parts need redrawing.
+#[derive(Debug, Clone)]
+pub struct GaugeUpdate {
+    pub model: GaugeModel,
+    pub redraw: GaugeRedraw,
+}
+
 impl fmt::Debug for GaugeModel {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("GaugeModel")
@@ -162,7 +278,7 @@ impl fmt::Debug for GaugeModel {
 }
 
 /// Supported user input events for a gauge.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GaugeInput {
     Button(mouse::Button),
     ScrollUp,
@@ -200,14 +316,97 @@ pub enum RunOutcome {
     ModelChanged(Box<GaugeModel>),
 }
 
+/// Stop signal for a blocking event source, set when the work manager cancels the gauge's
+/// event sources or shuts down.
+#[derive(Clone, Default)]
+pub struct GaugeStop(Arc<(Mutex<bool>, Condvar)>);
+
+impl GaugeStop {
+    /// Longest a source should block between checks of `is_stopped`.
+    pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn stop(&self) {
+        let (stopped, wake) = &*self.0;
+        if let Ok(mut stopped) = stopped.lock() {
+            *stopped = true;
+        }
+        wake.notify_all();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.0.lock().map_or(true, |stopped| *stopped)
+    }
+
+    /// Sleep for `duration`, waking early once stopped. Returns whether the source should stop.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let (stopped, wake) = &*self.0;
+        let Ok(guard) = stopped.lock() else {
+            return true;
+        };
+        wake.wait_timeout_while(guard, duration, |stopped| !*stopped)
+            .map_or(true, |(stopped, _)| *stopped)
+    }
+
+    /// `rx.recv_timeout(timeout)` that gives up early once stopped, reporting `Disconnected`
+    /// so sources handle a stop like their gauge going away.
+    pub fn recv_timeout<T>(
+        &self,
+        rx: &mpsc::Receiver<T>,
+        timeout: Duration,
+    ) -> Result<T, mpsc::RecvTimeoutError> {
+        let started = Instant::now();
+        loop {
+            if self.is_stopped() {
+                return Err(mpsc::RecvTimeoutError::Disconnected);
+            }
+            let remaining = timeout.saturating_sub(started.elapsed());
+            match rx.recv_timeout(remaining.min(Self::CHECK_INTERVAL)) {
+                Err(mpsc::RecvTimeoutError::Timeout) if !remaining.is_zero() => {}
+                other => return other,
+            }
+        }
+    }
+}
+
 /// Source of external gauge events owned by the work manager.
+///
+/// Runs on a thread of its own; prefer `AsyncGaugeEventSource` for IO the executor can wait on.
 pub trait GaugeEventSource: Send + 'static {
-    fn run(self: Box<Self>, notify: GaugeReadyNotify);
+    /// Wake the gauge through `notify` until `stop` is set. Sources check `stop` between
+    /// blocking waits, so each wait should be short; `GaugeStop::sleep` and
+    /// `GaugeStop::recv_timeout` return as soon as it is set.
+    fn run(self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop);
+}
+
+/// Boxed future polled on the work manager's shared async executor.
+pub type GaugeFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// IO-bound event source run as a task on the work manager's shared executor
+/// instead of on a thread of its own.
+///
+/// The manager calls `poll` every `interval`, drops (cancels) any call that runs past
+/// `timeout`, and wakes the gauge when a call resolves to `true`. Its tasks are cancelled
+/// when the gauge is disabled.
+pub trait AsyncGaugeEventSource: Send + 'static {
+    /// Delay between the end of one `poll` and the start of the next.
+    fn interval(&self) -> Duration;
+
+    /// Longest a single `poll` may run before it is cancelled.
+    fn timeout(&self) -> Duration;
+
+    /// Do one round of IO; resolve to `true` when the gauge should run.
+    fn poll(&mut self) -> GaugeFuture<'_, bool>;
+
+    /// Receive the gauge's ready callback before the first `poll`, for sources that also
+    /// wake the gauge from elsewhere, such as D-Bus method handlers.
+    fn bind_ready_notify(&mut self, _notify: GaugeReadyNotify) {}
 }
 
 /// Registration interface for manager-owned scheduling/event wiring.
 pub trait GaugeRegistrar {
     fn add_event_source(&mut self, source: Box<dyn GaugeEventSource>);
+
+    fn add_async_event_source(&mut self, source: Box<dyn AsyncGaugeEventSource>);
 }
 
 /// Runtime contract implemented by every gauge.
@@ -251,3 +450,29 @@ pub trait Gauge: Send + 'static {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_newest_samples_and_graphs_values() {
+        let mut history = GaugeHistory::new(2);
+        let value = GaugeDisplay::Value {
+            value: GaugeValue::Text("50".to_string()),
+            attention: GaugeValueAttention::Warning,
+        };
+        history.record(Some(0.2), value.clone());
+        history.record(Some(1.5), value.clone());
+        let display = history.record(Some(0.5), value);
+        assert!(matches!(
+            display,
+            GaugeDisplay::Graph { ref samples, attention: GaugeValueAttention::Warning }
+                if samples == &vec![1.0, 0.5]
+        ));
+        assert!(matches!(
+            history.record(None, GaugeDisplay::Error),
+            GaugeDisplay::Error
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+// User-configured gauge click/scroll bindings, checked before a gauge's built-in handlers,
+// and user-defined entries appended to a gauge's right-click menu.
+// Consumes Settings: grelier.gauge.<id>.on_{left,middle,right}_click, on_scroll_{up,down},
+// grelier.gauge.<id>.menu.<n>.{label,exec}.
+use crate::panels::gauges::gauge::{GaugeInput, GaugeMenuItem};
+use crate::settings;
+use iced::mouse;
+use std::collections::BTreeMap;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// What a bound input does instead of the gauge's built-in handling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GaugeBinding {
+    /// Run a shell command.
+    Exec(String),
+    /// Handle the input as a different input on the same gauge.
+    Input(GaugeInput),
+    /// Step the default audio sink by this many percent, up to the audio_out `max_percent`.
+    Volume(i8),
+    /// Ignore the input.
+    Disabled,
+}
+
+/// Settings key holding the binding for `input` on `gauge_id`.
+pub fn binding_key(gauge_id: &str, input: GaugeInput) -> Option<String> {
+    let name = match input {
+        GaugeInput::Button(mouse::Button::Left) => "on_left_click",
+        GaugeInput::Button(mouse::Button::Middle) => "on_middle_click",
+        GaugeInput::Button(mouse::Button::Right) => "on_right_click",
+        GaugeInput::Button(_) => return None,
+        GaugeInput::ScrollUp => "on_scroll_up",
+        GaugeInput::ScrollDown => "on_scroll_down",
+    };
+    Some(format!("grelier.gauge.{gauge_id}.{name}"))
+}
+
+/// Parse `exec <command>`, an input name (`left_click`, `scroll_up`, ...), `none`,
+/// or the `volume+N`/`volume-N` shorthand for the default audio sink.
+pub fn parse_binding(value: &str) -> Result<GaugeBinding, String> {
+    let value = value.trim();
+    if let Some(command) = value.strip_prefix("exec ") {
+        let command = command.trim();
+        if command.is_empty() {
+            return Err("exec binding has no command".to_string());
+        }
+        return Ok(GaugeBinding::Exec(command.to_string()));
+    }
+    if let Some(step) = value.strip_prefix("volume") {
+        let negative = step.starts_with('-');
+        let amount = step.strip_prefix(['+', '-']).unwrap_or_default();
+        if let Ok(amount) = amount.parse::<i8>()
+            && amount >= 0
+        {
+            return Ok(GaugeBinding::Volume(if negative {
+                -amount
+            } else {
+                amount
+            }));
+        }
+        return Err(format!(
+            "invalid volume binding '{value}', expected volume+N or volume-N"
+        ));
+    }
+    match value {
+        "none" => Ok(GaugeBinding::Disabled),
+        "left_click" => Ok(GaugeBinding::Input(GaugeInput::Button(mouse::Button::Left))),
+        "middle_click" => Ok(GaugeBinding::Input(GaugeInput::Button(
+            mouse::Button::Middle,
+        ))),
+        "right_click" => Ok(GaugeBinding::Input(GaugeInput::Button(
+            mouse::Button::Right,
+        ))),
+        "scroll_up" => Ok(GaugeBinding::Input(GaugeInput::ScrollUp)),
+        "scroll_down" => Ok(GaugeBinding::Input(GaugeInput::ScrollDown)),
+        other => Err(format!(
+            "unknown binding '{other}', expected 'exec <command>', an input name, 'none', or volume+N"
+        )),
+    }
+}
+
+/// Configured binding for `input` on `gauge_id`; invalid values are logged and ignored.
+pub fn binding_for(gauge_id: &str, input: GaugeInput) -> Option<GaugeBinding> {
+    let key = binding_key(gauge_id, input)?;
+    let value = settings::settings().get(&key)?;
+    match parse_binding(&value) {
+        Ok(binding) => Some(binding),
+        Err(err) => {
+            log::warn!("{key}: {err}");
+            None
+        }
+    }
+}
+
+/// Menu entries configured as `grelier.gauge.<id>.menu.<n>.label` and `.exec`, ordered by `n`.
+///
+/// Entries missing either key are skipped.
+pub fn user_menu_items(gauge_id: &str, all: &BTreeMap<String, String>) -> Vec<GaugeMenuItem> {
+    let prefix = format!("grelier.gauge.{gauge_id}.menu.");
+    let mut entries: BTreeMap<u32, (Option<String>, Option<String>)> = BTreeMap::new();
+    for (key, value) in all.range(prefix.clone()..) {
+        let Some(rest) = key.strip_prefix(&prefix) else {
+            break;
+        };
+        let Some((index, field)) = rest.split_once('.') else {
+            continue;
+        };
+        let Ok(index) = index.parse::<u32>() else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        let entry = entries.entry(index).or_default();
+        match field {
+            "label" => entry.0 = Some(value.to_string()),
+            "exec" => entry.1 = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    entries
+        .into_iter()
+        .filter_map(|(index, entry)| match entry {
+            (Some(label), Some(exec)) => Some(GaugeMenuItem {
+                id: format!("user-menu-{index}"),
+                label,
+                selected: false,
+                exec: Some(exec),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Run a bound or click command without waiting for it, in its own session so it outlives the bar.
+pub fn spawn_command(command: &str) {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // SAFETY: setsid is async-signal-safe and touches no state shared with the parent.
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    let spawned = cmd.spawn();
+    match spawned {
+        // Reap the child without blocking the UI thread.
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(err) => log::error!("gauge command: failed to run '{command}': {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bindings() {
+        assert_eq!(
+            binding_key("audio_out", GaugeInput::Button(mouse::Button::Middle)).as_deref(),
+            Some("grelier.gauge.audio_out.on_middle_click")
+        );
+        assert_eq!(
+            binding_key("clock", GaugeInput::Button(mouse::Button::Back)),
+            None
+        );
+        assert_eq!(
+            parse_binding("exec pavucontrol"),
+            Ok(GaugeBinding::Exec("pavucontrol".to_string()))
+        );
+        assert_eq!(parse_binding("volume+10"), Ok(GaugeBinding::Volume(10)));
+        assert_eq!(parse_binding("volume-5"), Ok(GaugeBinding::Volume(-5)));
+        assert_eq!(
+            parse_binding(" scroll_up "),
+            Ok(GaugeBinding::Input(GaugeInput::ScrollUp))
+        );
+        assert_eq!(parse_binding("none"), Ok(GaugeBinding::Disabled));
+        assert!(parse_binding("volume*3").is_err());
+        assert!(parse_binding("exec ").is_err());
+        assert!(parse_binding("launch rockets").is_err());
+    }
+
+    #[test]
+    fn collects_user_menu_entries_in_order() {
+        let all = BTreeMap::from(
+            [
+                ("grelier.gauge.audio_out.menu.10.label", "Mixer"),
+                ("grelier.gauge.audio_out.menu.10.exec", "pavucontrol"),
+                ("grelier.gauge.audio_out.menu.2.label", "Settings"),
+                (
+                    "grelier.gauge.audio_out.menu.2.exec",
+                    "gnome-control-center sound",
+                ),
+                ("grelier.gauge.audio_out.menu.3.label", "No command"),
+                ("grelier.gauge.audio_outx.menu.1.label", "Other gauge"),
+                ("grelier.gauge.audio_outx.menu.1.exec", "true"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+        let items = user_menu_items("audio_out", &all);
+        let labels: Vec<(&str, Option<&str>)> = items
+            .iter()
+            .map(|item| (item.label.as_str(), item.exec.as_deref()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("Settings", Some("gnome-control-center sound")),
+                ("Mixer", Some("pavucontrol")),
+            ]
+        );
+    }
+}
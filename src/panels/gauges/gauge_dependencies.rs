@@ -0,0 +1,272 @@
+// Optional system services and tools that gauges rely on, with availability probes.
+// Gauges use this to explain missing backends in their info dialogs; `--doctor` reports the same checks.
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use zbus::blocking::Connection;
+use zbus::blocking::fdo::DBusProxy;
+use zbus::names::BusName;
+
+/// How long a probe result is reused before gauges check again.
+const PROBE_TTL: Duration = Duration::from_secs(60);
+
+/// How to tell whether a dependency is present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Probe {
+    /// A name owned, or activatable, on the system bus.
+    SystemBus(&'static str),
+    /// An executable on `PATH`.
+    Command(&'static str),
+    /// A file or directory that exists.
+    Path(&'static str),
+    /// A path under `$XDG_RUNTIME_DIR`.
+    RuntimePath(&'static str),
+}
+
+/// One optional dependency and the gauges that use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dependency {
+    /// Service or package name shown to the user.
+    pub name: &'static str,
+    /// What the gauges lose without it.
+    pub purpose: &'static str,
+    /// Gauge ids that rely on it.
+    pub gauges: &'static [&'static str],
+    pub probe: Probe,
+}
+
+impl Dependency {
+    /// Run the probe now.
+    pub fn available(&self) -> bool {
+        match self.probe {
+            Probe::SystemBus(name) => system_bus_has(name),
+            Probe::Command(command) => command_on_path(command, env::var_os("PATH").as_deref()),
+            Probe::Path(path) => Path::new(path).exists(),
+            Probe::RuntimePath(path) => env::var_os("XDG_RUNTIME_DIR")
+                .is_some_and(|dir| Path::new(&dir).join(path).exists()),
+        }
+    }
+
+    /// One-line explanation shown when the dependency is missing.
+    pub fn hint(&self) -> String {
+        format!("Missing {}: needed for {}", self.name, self.purpose)
+    }
+}
+
+pub const DEPENDENCIES: &[Dependency] = &[
+    Dependency {
+        name: "power-profiles-daemon",
+        purpose: "switching power modes",
+        gauges: &["battery"],
+        probe: Probe::SystemBus("net.hadess.PowerProfiles"),
+    },
+    Dependency {
+        name: "the i2c-dev kernel module",
+        purpose: "external monitor brightness over DDC/CI",
+        gauges: &["brightness"],
+        probe: Probe::Path("/sys/class/i2c-dev"),
+    },
+    Dependency {
+        name: "NetworkManager",
+        purpose: "listing and connecting networks",
+        gauges: &["wifi", "vpn"],
+        probe: Probe::SystemBus("org.freedesktop.NetworkManager"),
+    },
+    Dependency {
+        name: "systemd-logind or elogind",
+        purpose: "session actions and suspend inhibitors",
+        gauges: &["session", "inhibit"],
+        probe: Probe::SystemBus("org.freedesktop.login1"),
+    },
+    Dependency {
+        name: "bootctl (systemd-boot)",
+        purpose: "rebooting into another boot entry",
+        gauges: &["session"],
+        probe: Probe::Command("bootctl"),
+    },
+    Dependency {
+        name: "nvidia-smi (nvidia-utils)",
+        purpose: "NVIDIA GPU statistics",
+        gauges: &["gpu"],
+        probe: Probe::Command("nvidia-smi"),
+    },
+    Dependency {
+        name: "PulseAudio or pipewire-pulse",
+        purpose: "audio levels and device switching",
+        gauges: &["audio_in", "audio_out"],
+        probe: Probe::RuntimePath("pulse/native"),
+    },
+    Dependency {
+        name: "curl",
+        purpose: "web requests",
+        gauges: &["chat", "ip", "weather_alerts"],
+        probe: Probe::Command("curl"),
+    },
+    Dependency {
+        name: "ip (iproute2)",
+        purpose: "listing interface addresses",
+        gauges: &["ip"],
+        probe: Probe::Command("ip"),
+    },
+    Dependency {
+        name: "wl-copy (wl-clipboard)",
+        purpose: "copying addresses and screenshots to the clipboard",
+        gauges: &["ip", "capture"],
+        probe: Probe::Command("wl-copy"),
+    },
+    Dependency {
+        name: "a kernel with CONFIG_PSI",
+        purpose: "pressure stall figures",
+        gauges: &["pressure"],
+        probe: Probe::Path("/proc/pressure"),
+    },
+    Dependency {
+        name: "KDE Connect",
+        purpose: "phone battery, notifications, and remote actions",
+        gauges: &["kdeconnect"],
+        probe: Probe::Command("kdeconnect-cli"),
+    },
+    Dependency {
+        name: "grim",
+        purpose: "taking screenshots",
+        gauges: &["capture"],
+        probe: Probe::Command("grim"),
+    },
+    Dependency {
+        name: "slurp",
+        purpose: "selecting a region, window, or output to capture",
+        gauges: &["capture"],
+        probe: Probe::Command("slurp"),
+    },
+    Dependency {
+        name: "wf-recorder",
+        purpose: "screen recording",
+        gauges: &["capture"],
+        probe: Probe::Command("wf-recorder"),
+    },
+    Dependency {
+        name: "tailscale",
+        purpose: "tailnet status and exit node switching",
+        gauges: &["tailscale"],
+        probe: Probe::Command("tailscale"),
+    },
+    Dependency {
+        name: "journalctl",
+        purpose: "following error messages in the systemd journal",
+        gauges: &["journal"],
+        probe: Probe::Command("journalctl"),
+    },
+];
+
+fn system_bus_has(name: &str) -> bool {
+    let Ok(bus_name) = BusName::try_from(name) else {
+        return false;
+    };
+    let Ok(connection) = Connection::system() else {
+        return false;
+    };
+    let Ok(proxy) = DBusProxy::new(&connection) else {
+        return false;
+    };
+    if proxy.name_has_owner(bus_name).unwrap_or(false) {
+        return true;
+    }
+    proxy
+        .list_activatable_names()
+        .is_ok_and(|names| names.iter().any(|activatable| activatable.as_str() == name))
+}
+
+fn command_on_path(command: &str, path: Option<&std::ffi::OsStr>) -> bool {
+    path.is_some_and(|path| env::split_paths(path).any(|dir| dir.join(command).is_file()))
+}
+
+/// Dependencies used by `gauge_id`.
+pub fn dependencies_for(gauge_id: &str) -> impl Iterator<Item = &'static Dependency> + '_ {
+    DEPENDENCIES
+        .iter()
+        .filter(move |dependency| dependency.gauges.contains(&gauge_id))
+}
+
+/// Probe result for `dependency`, reused for `PROBE_TTL` so gauges can ask on every run.
+fn cached_available(dependency: &'static Dependency) -> bool {
+    static CACHE: OnceLock<Mutex<HashMap<&'static str, (Instant, bool)>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let now = Instant::now();
+    if let Ok(cache) = cache.lock()
+        && let Some((checked, available)) = cache.get(dependency.name)
+        && now.duration_since(*checked) < PROBE_TTL
+    {
+        return *available;
+    }
+    let available = dependency.available();
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(dependency.name, (now, available));
+    }
+    available
+}
+
+/// Hint lines for each of `gauge_id`'s dependencies that is missing.
+pub fn missing_hints(gauge_id: &str) -> Vec<String> {
+    dependencies_for(gauge_id)
+        .filter(|dependency| !cached_available(dependency))
+        .map(Dependency::hint)
+        .collect()
+}
+
+/// Print every dependency with its status for `--doctor`; returns the count missing for `enabled` gauges.
+pub fn doctor(enabled: &[String]) -> usize {
+    let mut missing = 0;
+    for dependency in DEPENDENCIES {
+        let used = dependency
+            .gauges
+            .iter()
+            .any(|gauge| enabled.iter().any(|id| id == gauge));
+        let available = dependency.available();
+        if used && !available {
+            missing += 1;
+        }
+        let status = match (available, used) {
+            (true, _) => "ok",
+            (false, true) => "MISSING",
+            (false, false) => "missing",
+        };
+        let note = if used {
+            ""
+        } else {
+            " (no enabled gauge uses it)"
+        };
+        println!(
+            "{status:<8} {} [{}]: {}{note}",
+            dependency.name,
+            dependency.gauges.join(", "),
+            dependency.purpose
+        );
+    }
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_gauge_dependencies_and_commands() {
+        let names: Vec<&str> = dependencies_for("ip").map(|d| d.name).collect();
+        assert_eq!(
+            names,
+            vec!["curl", "ip (iproute2)", "wl-copy (wl-clipboard)"]
+        );
+        assert_eq!(
+            dependencies_for("battery").next().map(Dependency::hint),
+            Some("Missing power-profiles-daemon: needed for switching power modes".to_string())
+        );
+        assert_eq!(dependencies_for("clock").count(), 0);
+
+        let path = env::var_os("PATH");
+        assert!(command_on_path("sh", path.as_deref()));
+        assert!(!command_on_path("grelier-no-such-tool", path.as_deref()));
+        assert!(!command_on_path("sh", None));
+    }
+}
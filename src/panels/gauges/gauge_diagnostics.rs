@@ -0,0 +1,199 @@
+// Gauge health: run counters the work manager publishes after every scheduling cycle, shown in a
+// dialog from the bar's context menu and served to `--doctor` over the control socket.
+use crate::dialog::info::InfoDialog;
+use crate::ipc::{self, IpcCommand};
+use crate::panels::gauges::gauge::GaugeMenuItem;
+use crate::panels::gauges::gauge_dependencies;
+use crate::panels::gauges::gauge_hardware;
+use serde_json::{Value, json};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Menu item id of the diagnostics entry in the bar's context menu.
+pub const MENU_ITEM: &str = "grelier:gauge_diagnostics";
+
+/// Runtime counters of one gauge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GaugeStats {
+    pub id: String,
+    /// Disabled after too many slow runs in a row.
+    pub dead: bool,
+    /// Total number of times the gauge has been run.
+    pub run_count: u64,
+    /// Consecutive runs over the time limit.
+    pub strike_count: u8,
+    /// How long the most recent run took.
+    pub last_run: Duration,
+}
+
+impl GaugeStats {
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "dead": self.dead,
+            "runs": self.run_count,
+            "strikes": self.strike_count,
+            "last_run_us": u64::try_from(self.last_run.as_micros()).unwrap_or(u64::MAX),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            id: value.get("id")?.as_str()?.to_string(),
+            dead: value.get("dead")?.as_bool()?,
+            run_count: value.get("runs")?.as_u64()?,
+            strike_count: u8::try_from(value.get("strikes")?.as_u64()?).ok()?,
+            last_run: Duration::from_micros(value.get("last_run_us")?.as_u64()?),
+        })
+    }
+
+    /// One-line summary, such as "120 runs, last 0.4 ms".
+    fn summary(&self) -> String {
+        if self.dead {
+            return format!("disabled for slow runs ({} runs)", self.run_count);
+        }
+        let mut summary = format!(
+            "{} runs, last {:.1} ms",
+            self.run_count,
+            self.last_run.as_secs_f64() * 1000.0
+        );
+        if self.strike_count > 0 {
+            summary.push_str(&format!(", {} slow in a row", self.strike_count));
+        }
+        summary
+    }
+}
+
+static LATEST: Mutex<Vec<GaugeStats>> = Mutex::new(Vec::new());
+
+/// Replace the published counters; called by the gauge worker thread.
+pub fn publish(stats: Vec<GaugeStats>) {
+    if let Ok(mut latest) = LATEST.lock() {
+        *latest = stats;
+    }
+}
+
+/// Counters from the most recent scheduling cycle.
+pub fn latest() -> Vec<GaugeStats> {
+    LATEST
+        .lock()
+        .map(|latest| latest.clone())
+        .unwrap_or_default()
+}
+
+/// Reply to `IpcCommand::GaugeStats`.
+pub fn stats_json() -> Value {
+    json!({
+        "ok": true,
+        "gauges": latest().iter().map(GaugeStats::to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Entry appended to the bar's context menu.
+pub fn menu_item() -> GaugeMenuItem {
+    GaugeMenuItem {
+        id: MENU_ITEM.to_string(),
+        label: "Gauge diagnostics".to_string(),
+        selected: false,
+        exec: None,
+    }
+}
+
+pub fn info_dialog() -> InfoDialog {
+    let stats = latest();
+    let lines = if stats.is_empty() {
+        vec!["No gauges are running".to_string()]
+    } else {
+        stats
+            .iter()
+            .map(|stats| format!("{}: {}", stats.id, stats.summary()))
+            .collect()
+    };
+    InfoDialog {
+        title: "Gauge diagnostics".to_string(),
+        lines,
+        graph: None,
+    }
+}
+
+/// Counters from every running instance, or an explanation of why there are none.
+fn running_stats() -> Result<Vec<GaugeStats>, String> {
+    let replies = ipc::send_to_all(&IpcCommand::GaugeStats)?;
+    let mut stats = Vec::new();
+    for (_, reply) in replies {
+        let reply: Value = serde_json::from_str(&reply).map_err(|err| err.to_string())?;
+        if let Some(gauges) = reply.get("gauges").and_then(Value::as_array) {
+            stats.extend(gauges.iter().filter_map(GaugeStats::from_json));
+        }
+    }
+    Ok(stats)
+}
+
+/// `--doctor` line for one gauge: its counters, then each probe as `name ok` or `name missing`.
+fn gauge_line(id: &str, stats: Option<&GaugeStats>, probes: &[(&str, bool)]) -> String {
+    let state = stats.map_or_else(|| "not running".to_string(), GaugeStats::summary);
+    let mut line = format!("{id}: {state}");
+    if !probes.is_empty() {
+        let probes: Vec<String> = probes
+            .iter()
+            .map(|(name, ok)| format!("{name} {}", if *ok { "ok" } else { "missing" }))
+            .collect();
+        line.push_str(&format!("; {}", probes.join(", ")));
+    }
+    line
+}
+
+/// Print run counters from the running bar and probe results for each of `enabled`.
+/// Returns the number of gauges that were disabled for running slowly.
+pub fn doctor(enabled: &[String]) -> usize {
+    let running = running_stats().unwrap_or_else(|err| {
+        println!("Gauge run counters unavailable: {err}");
+        Vec::new()
+    });
+    for id in enabled {
+        let mut probes: Vec<(&str, bool)> = gauge_dependencies::dependencies_for(id)
+            .map(|dependency| (dependency.name, dependency.available()))
+            .collect();
+        if let Some(hardware) = gauge_hardware::requirement(id) {
+            probes.push((hardware.name(), hardware.is_present()));
+        }
+        let stats = running.iter().find(|stats| stats.id == *id);
+        println!("{}", gauge_line(id, stats, &probes));
+    }
+    running.iter().filter(|stats| stats.dead).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_counters_and_round_trips_them() {
+        let clock = GaugeStats {
+            id: "clock".to_string(),
+            dead: false,
+            run_count: 120,
+            strike_count: 1,
+            last_run: Duration::from_micros(400),
+        };
+        assert_eq!(clock.summary(), "120 runs, last 0.4 ms, 1 slow in a row");
+        assert_eq!(GaugeStats::from_json(&clock.to_json()), Some(clock.clone()));
+
+        let wifi = GaugeStats {
+            id: "wifi".to_string(),
+            dead: true,
+            run_count: 9,
+            strike_count: 3,
+            last_run: Duration::from_millis(80),
+        };
+        assert_eq!(
+            gauge_line(
+                "wifi",
+                Some(&wifi),
+                &[("NetworkManager", true), ("iw", false)]
+            ),
+            "wifi: disabled for slow runs (9 runs); NetworkManager ok, iw missing"
+        );
+        assert_eq!(gauge_line("disk", None, &[]), "disk: not running");
+    }
+}
@@ -0,0 +1,257 @@
+// Shared async executor for gauge event sources, with per-poll timeouts and cancellation.
+// Consumes Settings: grelier.gauge.work.event_runtime.
+use crate::panels::gauges::gauge::{
+    AsyncGaugeEventSource, GaugeEventSource, GaugeFuture, GaugeReadyNotify, GaugeStop,
+};
+use crate::settings;
+use async_executor::{Executor, Task};
+use async_io::Timer;
+use futures_lite::future;
+use iced::futures::channel::oneshot;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+pub const EVENT_RUNTIME_KEY: &str = "grelier.gauge.work.event_runtime";
+
+/// Where blocking `GaugeEventSource`s run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventRuntime {
+    /// One dedicated thread per event source.
+    Threads,
+    /// Adapted onto the shared executor's blocking pool, next to the async sources.
+    Async,
+}
+
+impl EventRuntime {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim() {
+            "threads" => Ok(EventRuntime::Threads),
+            "async" => Ok(EventRuntime::Async),
+            other => Err(format!(
+                "Invalid {EVENT_RUNTIME_KEY} '{other}', expected 'threads' or 'async'"
+            )),
+        }
+    }
+
+    pub fn from_settings() -> Self {
+        let value = settings::settings().get_or(EVENT_RUNTIME_KEY, "async");
+        Self::parse(&value).unwrap_or_else(|err| {
+            log::warn!("{err}");
+            EventRuntime::Async
+        })
+    }
+}
+
+/// Run `work`, or give up with `None` once `timeout` has passed. Giving up drops `work`,
+/// which cancels whatever it was waiting on.
+pub async fn with_timeout<T>(work: GaugeFuture<'_, T>, timeout: Duration) -> Option<T> {
+    future::or(async { Some(work.await) }, async {
+        Timer::after(timeout).await;
+        None
+    })
+    .await
+}
+
+async fn drive(
+    id: &'static str,
+    mut source: Box<dyn AsyncGaugeEventSource>,
+    notify: GaugeReadyNotify,
+) {
+    loop {
+        let timeout = source.timeout();
+        match with_timeout(source.poll(), timeout).await {
+            Some(true) => notify(id),
+            Some(false) => {}
+            None => log::warn!("gauge '{id}': event poll cancelled after {timeout:?}"),
+        }
+        Timer::after(source.interval()).await;
+    }
+}
+
+/// One executor thread shared by every gauge's async event sources.
+///
+/// Tasks are kept per gauge id; dropping them cancels them at their next await point.
+/// Dropping the executor stops its thread and every blocking source it runs.
+pub struct GaugeExecutor {
+    executor: Arc<Executor<'static>>,
+    tasks: HashMap<&'static str, Vec<Task<()>>>,
+    /// Stop signals of the blocking sources, which dropping their task only detaches.
+    stops: HashMap<&'static str, Vec<GaugeStop>>,
+    /// Dropped with the executor, which ends the executor thread's run loop.
+    _stop: oneshot::Sender<()>,
+}
+
+impl GaugeExecutor {
+    pub fn spawn() -> Self {
+        let executor = Arc::new(Executor::new());
+        let (stop, stopped) = oneshot::channel::<()>();
+        let runner = Arc::clone(&executor);
+        let spawned = thread::Builder::new()
+            .name("gauge-executor".to_string())
+            .spawn(move || {
+                async_io::block_on(runner.run(async {
+                    let _ = stopped.await;
+                }))
+            });
+        if let Err(err) = spawned {
+            log::error!("failed to start gauge executor thread: {err}");
+        }
+        Self {
+            executor,
+            tasks: HashMap::new(),
+            stops: HashMap::new(),
+            _stop: stop,
+        }
+    }
+
+    pub fn spawn_source(
+        &mut self,
+        id: &'static str,
+        mut source: Box<dyn AsyncGaugeEventSource>,
+        notify: GaugeReadyNotify,
+    ) {
+        source.bind_ready_notify(notify.clone());
+        let task = self.executor.spawn(drive(id, source, notify));
+        self.tasks.entry(id).or_default().push(task);
+    }
+
+    /// Adapter for a blocking event source: it runs on the blocking pool and is tracked like
+    /// an async task. Cancelling it sets `stop`, which the source checks between waits.
+    pub fn spawn_blocking(
+        &mut self,
+        id: &'static str,
+        source: Box<dyn GaugeEventSource>,
+        notify: GaugeReadyNotify,
+        stop: GaugeStop,
+    ) {
+        let task = self.executor.spawn(blocking::unblock({
+            let stop = stop.clone();
+            move || source.run(notify, stop)
+        }));
+        self.tasks.entry(id).or_default().push(task);
+        self.stops.entry(id).or_default().push(stop);
+    }
+
+    /// Cancel every task belonging to `id` and stop its blocking sources.
+    pub fn cancel(&mut self, id: &str) {
+        if let Some(tasks) = self.tasks.remove(id) {
+            log::debug!("cancelling {} event task(s) for gauge '{id}'", tasks.len());
+        }
+        for stop in self.stops.remove(id).into_iter().flatten() {
+            stop.stop();
+        }
+    }
+}
+
+impl Drop for GaugeExecutor {
+    fn drop(&mut self) {
+        for stop in self.stops.values().flatten() {
+            stop.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    struct CountingSource {
+        polls: Arc<AtomicUsize>,
+    }
+
+    impl AsyncGaugeEventSource for CountingSource {
+        fn interval(&self) -> Duration {
+            Duration::from_millis(5)
+        }
+
+        fn timeout(&self) -> Duration {
+            Duration::from_secs(1)
+        }
+
+        fn poll(&mut self) -> GaugeFuture<'_, bool> {
+            let polls = Arc::clone(&self.polls);
+            Box::pin(async move {
+                polls.fetch_add(1, Ordering::SeqCst);
+                true
+            })
+        }
+    }
+
+    #[test]
+    fn times_out_slow_polls_and_cancels_gauge_tasks() {
+        let slow: GaugeFuture<'_, u8> = Box::pin(async {
+            Timer::after(Duration::from_secs(5)).await;
+            1
+        });
+        assert_eq!(
+            async_io::block_on(with_timeout(slow, Duration::from_millis(10))),
+            None
+        );
+        assert_eq!(
+            async_io::block_on(with_timeout(Box::pin(async { 2 }), Duration::from_secs(1))),
+            Some(2)
+        );
+        assert_eq!(EventRuntime::parse("async"), Ok(EventRuntime::Async));
+        assert!(EventRuntime::parse("tokio").is_err());
+
+        let polls = Arc::new(AtomicUsize::new(0));
+        let woken = Arc::new(Mutex::new(Vec::new()));
+        let notify: GaugeReadyNotify = {
+            let woken = Arc::clone(&woken);
+            Arc::new(move |id| woken.lock().unwrap().push(id))
+        };
+        let mut executor = GaugeExecutor::spawn();
+        executor.spawn_source(
+            "counter",
+            Box::new(CountingSource {
+                polls: Arc::clone(&polls),
+            }),
+            notify,
+        );
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while polls.load(Ordering::SeqCst) < 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(polls.load(Ordering::SeqCst) >= 2);
+        assert_eq!(woken.lock().unwrap().first(), Some(&"counter"));
+
+        executor.cancel("counter");
+        thread::sleep(Duration::from_millis(20));
+        let after_cancel = polls.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(40));
+        assert_eq!(polls.load(Ordering::SeqCst), after_cancel);
+    }
+
+    struct SleepingSource {
+        events: std::sync::mpsc::Sender<&'static str>,
+    }
+
+    impl GaugeEventSource for SleepingSource {
+        fn run(self: Box<Self>, _notify: GaugeReadyNotify, stop: GaugeStop) {
+            let _ = self.events.send("started");
+            while !stop.sleep(Duration::from_secs(3600)) {}
+            let _ = self.events.send("stopped");
+        }
+    }
+
+    #[test]
+    fn cancel_stops_blocking_sources() {
+        let (events, received) = std::sync::mpsc::channel();
+        let mut executor = GaugeExecutor::spawn();
+        executor.spawn_blocking(
+            "sleeper",
+            Box::new(SleepingSource { events }),
+            Arc::new(|_| {}),
+            GaugeStop::default(),
+        );
+        let wait = Duration::from_secs(2);
+        assert_eq!(received.recv_timeout(wait), Ok("started"));
+        executor.cancel("sleeper");
+        assert_eq!(received.recv_timeout(wait), Ok("stopped"));
+    }
+}
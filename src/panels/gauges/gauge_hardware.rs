@@ -0,0 +1,221 @@
+// Hardware prerequisites: gauges that need a battery, a backlight, or a network adapter are left
+// off the bar while that hardware is missing, and come and go as udev reports devices being
+// added and removed. Desktops without a battery then get no permanent error icon.
+// Consumes Settings: grelier.gauge.hide_missing_hardware.
+use crate::bar::Message;
+use crate::panels::gauges::wired;
+use crate::settings;
+use iced::Subscription;
+use iced::futures::channel::mpsc;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+pub const HIDE_MISSING_KEY: &str = "grelier.gauge.hide_missing_hardware";
+const SYS_CLASS: &str = "/sys/class";
+/// udev subsystems whose devices decide whether a prerequisite is met.
+const SUBSYSTEMS: &[&str] = &["power_supply", "backlight", "i2c-dev", "net"];
+
+/// Hardware a gauge cannot work without.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hardware {
+    /// A battery powering the machine, not one in a mouse or headset.
+    Battery,
+    /// A backlight, or an i2c bus for monitors controlled over DDC/CI.
+    Backlight,
+    WifiAdapter,
+    EthernetAdapter,
+}
+
+/// Gauges with a hardware prerequisite.
+pub const PREREQUISITES: &[(&str, Hardware)] = &[
+    ("battery", Hardware::Battery),
+    ("brightness", Hardware::Backlight),
+    ("wifi", Hardware::WifiAdapter),
+    ("wired", Hardware::EthernetAdapter),
+];
+
+fn class_entries(sys_class: &Path, class: &str) -> Vec<PathBuf> {
+    fs::read_dir(sys_class.join(class))
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default()
+}
+
+fn read_trimmed(path: &Path) -> String {
+    fs::read_to_string(path)
+        .map(|value| value.trim().to_string())
+        .unwrap_or_default()
+}
+
+impl Hardware {
+    pub fn name(self) -> &'static str {
+        match self {
+            Hardware::Battery => "battery",
+            Hardware::Backlight => "backlight or DDC/CI monitor",
+            Hardware::WifiAdapter => "Wi-Fi adapter",
+            Hardware::EthernetAdapter => "Ethernet adapter",
+        }
+    }
+
+    /// Whether the hardware is present right now.
+    pub fn is_present(self) -> bool {
+        self.present_in(Path::new(SYS_CLASS))
+    }
+
+    fn present_in(self, sys_class: &Path) -> bool {
+        match self {
+            Hardware::Battery => class_entries(sys_class, "power_supply")
+                .iter()
+                .any(|supply| {
+                    read_trimmed(&supply.join("type")) == "Battery"
+                        && read_trimmed(&supply.join("scope")) != "Device"
+                }),
+            Hardware::Backlight => {
+                !class_entries(sys_class, "backlight").is_empty()
+                    || !class_entries(sys_class, "i2c-dev").is_empty()
+            }
+            Hardware::WifiAdapter => class_entries(sys_class, "net")
+                .iter()
+                .any(|iface| iface.join("wireless").exists() || iface.join("phy80211").exists()),
+            Hardware::EthernetAdapter => {
+                !wired::wired_interfaces(&sys_class.join("net")).is_empty()
+            }
+        }
+    }
+}
+
+pub fn requirement(gauge_id: &str) -> Option<Hardware> {
+    PREREQUISITES
+        .iter()
+        .find(|(id, _)| *id == gauge_id)
+        .map(|(_, hardware)| *hardware)
+}
+
+fn missing_in<'a>(ids: impl IntoIterator<Item = &'a String>, sys_class: &Path) -> BTreeSet<String> {
+    ids.into_iter()
+        .filter(|id| requirement(id).is_some_and(|hardware| !hardware.present_in(sys_class)))
+        .cloned()
+        .collect()
+}
+
+/// Gauges among `ids` whose hardware is missing right now; none when the check is turned off.
+pub fn missing<'a>(ids: impl IntoIterator<Item = &'a String>) -> BTreeSet<String> {
+    if !settings::settings().get_bool_or(HIDE_MISSING_KEY, true) {
+        return BTreeSet::new();
+    }
+    missing_in(ids, Path::new(SYS_CLASS))
+}
+
+/// Whether any of `ids` can come and go with hardware, so udev is worth watching.
+pub fn watches(ids: &[String]) -> bool {
+    settings::settings().get_bool_or(HIDE_MISSING_KEY, true)
+        && ids.iter().any(|id| requirement(id).is_some())
+}
+
+/// Send `HardwareChanged` whenever a device in one of `SUBSYSTEMS` is added or removed.
+fn watch_udev(sender: &mut mpsc::Sender<Message>) -> Result<(), String> {
+    let mut builder = udev::MonitorBuilder::new().map_err(|err| format!("udev monitor: {err}"))?;
+    for subsystem in SUBSYSTEMS {
+        builder = builder
+            .match_subsystem(subsystem)
+            .map_err(|err| format!("udev monitor: {err}"))?;
+    }
+    let socket = builder
+        .listen()
+        .map_err(|err| format!("udev monitor: {err}"))?;
+    let mut pollfd = libc::pollfd {
+        fd: socket.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    while !sender.is_closed() {
+        // SAFETY: `pollfd` is one valid entry for the duration of the call.
+        if unsafe { libc::poll(&mut pollfd, 1, -1) } < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::EINTR) {
+                continue;
+            }
+            return Err(format!("udev monitor: {err}"));
+        }
+        // Batteries send change events every few seconds; only devices coming and going count.
+        let plugged = socket
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event.event_type(),
+                    udev::EventType::Add | udev::EventType::Remove
+                )
+            })
+            .count();
+        if plugged > 0 {
+            match sender.try_send(Message::HardwareChanged) {
+                Err(err) if err.is_disconnected() => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+fn hardware_stream() -> impl iced::futures::Stream<Item = Message> {
+    let (mut sender, receiver) = mpsc::channel(1);
+    std::thread::spawn(move || {
+        if let Err(err) = watch_udev(&mut sender) {
+            log::warn!("Gauges will not follow hardware changes: {err}");
+        }
+    });
+    receiver
+}
+
+/// Hardware hotplug events.
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(hardware_stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, value: &str) {
+        fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+        fs::write(path, value).expect("write file");
+    }
+
+    #[test]
+    fn gauges_without_their_hardware_are_missing() {
+        let sys_class = std::env::temp_dir().join("grelier_gauge_hardware_test");
+        let _ = fs::remove_dir_all(&sys_class);
+        write(&sys_class.join("power_supply/AC/type"), "Mains\n");
+        write(
+            &sys_class.join("power_supply/hidpp_battery_0/type"),
+            "Battery\n",
+        );
+        write(
+            &sys_class.join("power_supply/hidpp_battery_0/scope"),
+            "Device\n",
+        );
+        write(&sys_class.join("net/wlan0/type"), "1\n");
+        fs::create_dir_all(sys_class.join("net/wlan0/wireless")).expect("create dir");
+
+        let ids: Vec<String> = ["battery", "brightness", "clock", "wifi", "wired"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            missing_in(&ids, &sys_class),
+            BTreeSet::from(["battery", "brightness", "wired"].map(String::from))
+        );
+
+        write(&sys_class.join("power_supply/BAT0/type"), "Battery\n");
+        fs::create_dir_all(sys_class.join("i2c-dev/i2c-3")).expect("create dir");
+        assert_eq!(
+            missing_in(&ids, &sys_class),
+            BTreeSet::from(["wired".to_string()])
+        );
+        let _ = fs::remove_dir_all(&sys_class);
+
+        assert_eq!(requirement("wifi"), Some(Hardware::WifiAdapter));
+        assert_eq!(requirement("clock"), None);
+    }
+}
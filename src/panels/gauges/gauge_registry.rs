@@ -1,4 +1,5 @@
 use crate::panels::gauges::gauge::Gauge;
+use crate::panels::gauges::gauge_work_manager;
 use crate::settings::{SettingSpec, Settings};
 use std::sync::OnceLock;
 use std::time::Instant;
@@ -28,14 +29,35 @@ pub struct GaugeSpec {
 
 inventory::collect!(GaugeSpec);
 
-pub fn all() -> impl Iterator<Item = &'static GaugeSpec> {
-    inventory::iter::<GaugeSpec>.into_iter()
+/// Gauges discovered at runtime, such as shared-library plugins.
+static DYNAMIC_SPECS: OnceLock<Vec<GaugeSpec>> = OnceLock::new();
+
+/// Register runtime-discovered gauges next to the built-ins.
+///
+/// Must be called once, before the registry is first queried; ids that clash with
+/// an already registered gauge are dropped.
+pub fn register_dynamic(specs: Vec<GaugeSpec>) {
+    let mut accepted: Vec<GaugeSpec> = Vec::new();
+    for spec in specs {
+        if find(spec.id).is_some() || accepted.iter().any(|other| other.id == spec.id) {
+            log::warn!("Ignoring gauge '{}': id already registered", spec.id);
+            continue;
+        }
+        accepted.push(spec);
+    }
+    if DYNAMIC_SPECS.set(accepted).is_err() {
+        log::warn!("Dynamic gauges were already registered");
+    }
 }
 
-pub fn find(id: &str) -> Option<&'static GaugeSpec> {
+pub fn all() -> impl Iterator<Item = &'static GaugeSpec> {
     inventory::iter::<GaugeSpec>
         .into_iter()
-        .find(|spec| spec.id == id)
+        .chain(DYNAMIC_SPECS.get().into_iter().flatten())
+}
+
+pub fn find(id: &str) -> Option<&'static GaugeSpec> {
+    all().find(|spec| spec.id == id)
 }
 
 /// Construct a gauge runtime by id.
@@ -57,8 +79,25 @@ pub fn default_gauges() -> &'static str {
     })
 }
 
+/// Settings every gauge has through the shared worker: the worker's own, then one
+/// `min_emit_interval_ms` per gauge.
+fn worker_settings() -> &'static [SettingSpec] {
+    static SPECS: OnceLock<Vec<SettingSpec>> = OnceLock::new();
+    SPECS.get_or_init(|| {
+        let mut ids: Vec<&'static str> = all().map(|spec| spec.id).collect();
+        ids.sort();
+        let mut specs = gauge_work_manager::settings().to_vec();
+        specs.extend(ids.into_iter().map(|id| SettingSpec {
+            key: Box::leak(gauge_work_manager::min_emit_interval_key(id).into_boxed_str()),
+            default: gauge_work_manager::DEFAULT_MIN_EMIT_INTERVAL_MS,
+        }));
+        specs
+    })
+}
+
 pub fn collect_settings(base: &[SettingSpec]) -> Vec<SettingSpec> {
     let mut specs = base.to_vec();
+    specs.extend_from_slice(worker_settings());
     for spec in all() {
         specs.extend_from_slice((spec.settings)());
     }
@@ -66,7 +105,7 @@ pub fn collect_settings(base: &[SettingSpec]) -> Vec<SettingSpec> {
 }
 
 pub fn list_settings(base: &[SettingSpec]) {
-    for spec in base {
+    for spec in base.iter().chain(worker_settings()) {
         println!("{}:{}", spec.key, spec.default);
     }
     let mut gauges: Vec<&'static GaugeSpec> = all().collect();
@@ -94,3 +133,17 @@ pub fn validate_settings(settings: &Settings) -> Result<(), String> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_worker_settings_for_every_gauge() {
+        let specs = collect_settings(&[]);
+        let has = |key: &str| specs.iter().any(|spec| spec.key == key);
+        assert!(has("grelier.gauge.work.max_run_ms"));
+        assert!(has("grelier.gauge.work.retry_max_secs"));
+        assert!(has("grelier.gauge.clock.min_emit_interval_ms"));
+    }
+}
@@ -0,0 +1,175 @@
+// Live tuning of a gauge's main interval: scrolling over a gauge while holding the tune
+// modifier steps that setting, shows the new value in a toast, and saves it. Tuned gauges read
+// the value back through `live_secs`, so the change applies from their next run.
+use crate::panels::ws_panel::HistoryModifier;
+use crate::settings;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+pub const TUNE_MODIFIER_KEY: &str = "grelier.gauge.tune_modifier";
+
+/// A whole-second setting that can be tuned from the bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tunable {
+    /// Gauge ids the scroll applies to.
+    pub gauges: &'static [&'static str],
+    pub key: &'static str,
+    /// Name shown in the toast.
+    pub label: &'static str,
+    pub default: u64,
+    pub step: u64,
+    pub min: u64,
+    pub max: u64,
+}
+
+pub const TUNABLES: &[Tunable] = &[
+    Tunable {
+        gauges: &["net_down", "net_up"],
+        key: "grelier.gauge.net.slow_interval_secs",
+        label: "Network sampling interval",
+        default: 3,
+        step: 1,
+        min: 1,
+        max: 30,
+    },
+    Tunable {
+        gauges: &["disk"],
+        key: "grelier.gauge.disk.poll_interval_secs",
+        label: "Disk refresh interval",
+        default: 60,
+        step: 10,
+        min: 10,
+        max: 3600,
+    },
+    Tunable {
+        gauges: &["temp"],
+        key: "grelier.gauge.temp.poll_interval_secs",
+        label: "Temperature refresh interval",
+        default: 5,
+        step: 1,
+        min: 1,
+        max: 60,
+    },
+    Tunable {
+        gauges: &["gpu"],
+        key: "grelier.gauge.gpu.poll_interval_secs",
+        label: "GPU refresh interval",
+        default: 3,
+        step: 1,
+        min: 1,
+        max: 60,
+    },
+    Tunable {
+        gauges: &["pressure"],
+        key: "grelier.gauge.pressure.poll_interval_secs",
+        label: "Pressure refresh interval",
+        default: 5,
+        step: 1,
+        min: 1,
+        max: 60,
+    },
+    Tunable {
+        gauges: &["health"],
+        key: "grelier.gauge.health.poll_interval_secs",
+        label: "Health refresh interval",
+        default: 5,
+        step: 1,
+        min: 1,
+        max: 60,
+    },
+    Tunable {
+        gauges: &["wifi"],
+        key: "grelier.gauge.wifi.poll_interval_secs",
+        label: "Wi-Fi refresh interval",
+        default: 3,
+        step: 1,
+        min: 1,
+        max: 60,
+    },
+];
+
+impl Tunable {
+    /// The value one scroll step away from `current`, kept within the tunable's range.
+    pub fn stepped(&self, current: u64, up: bool) -> u64 {
+        let next = if up {
+            current.saturating_add(self.step)
+        } else {
+            current.saturating_sub(self.step)
+        };
+        next.clamp(self.min, self.max)
+    }
+}
+
+pub fn tunable_for(gauge_id: &str) -> Option<&'static Tunable> {
+    TUNABLES
+        .iter()
+        .find(|tunable| tunable.gauges.contains(&gauge_id))
+}
+
+/// Configured tune modifier; invalid values turn tuning off.
+pub fn tune_modifier() -> HistoryModifier {
+    HistoryModifier::parse(&settings::settings().get_or(TUNE_MODIFIER_KEY, "ctrl"))
+        .unwrap_or(HistoryModifier::None)
+}
+
+/// Values tuned this session, by setting key. Gauge threads read these instead of the settings
+/// store, which they only load when created.
+fn tuned() -> &'static Mutex<HashMap<&'static str, u64>> {
+    static TUNED: OnceLock<Mutex<HashMap<&'static str, u64>>> = OnceLock::new();
+    TUNED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The interval `gauge_id` should use: its tuned value if it was tuned, otherwise `loaded`.
+pub fn live_secs(gauge_id: &str, loaded: u64) -> u64 {
+    tunable_for(gauge_id)
+        .and_then(|tunable| {
+            let tuned = tuned().lock().ok()?;
+            tuned.get(tunable.key).copied()
+        })
+        .unwrap_or(loaded)
+}
+
+/// Pick up tunable values changed outside of tuning, such as by a settings profile.
+pub fn reload() {
+    let store = settings::settings();
+    if let Ok(mut tuned) = tuned().lock() {
+        for tunable in TUNABLES {
+            tuned.insert(
+                tunable.key,
+                store.get_parsed_or(tunable.key, tunable.default),
+            );
+        }
+    }
+}
+
+/// Step the tunable setting of `gauge_id` and save it. Returns the tunable and its new value,
+/// or `None` when the gauge has nothing to tune.
+pub fn tune(gauge_id: &str, up: bool) -> Option<(&'static Tunable, u64)> {
+    let tunable = tunable_for(gauge_id)?;
+    let current = live_secs(
+        gauge_id,
+        settings::settings().get_parsed_or(tunable.key, tunable.default),
+    );
+    let value = tunable.stepped(current, up);
+    if let Ok(mut tuned) = tuned().lock() {
+        tuned.insert(tunable.key, value);
+    }
+    settings::settings().update(tunable.key, &value.to_string());
+    Some((tunable, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_within_range_and_maps_shared_settings() {
+        let disk = tunable_for("disk").expect("disk is tunable");
+        assert_eq!(disk.stepped(60, true), 70);
+        assert_eq!(disk.stepped(15, false), 10);
+        assert_eq!(disk.stepped(3600, true), 3600);
+        assert_eq!(tunable_for("net_up"), tunable_for("net_down"));
+        assert_eq!(tunable_for("clock"), None);
+        assert_eq!(live_secs("clock", 7), 7);
+    }
+}
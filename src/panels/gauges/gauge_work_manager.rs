@@ -1,14 +1,19 @@
 // Gauge work-manager runtime and subscription adapter.
 use crate::bar::Message;
 use crate::dialog::info::InfoDialog;
+use crate::dialog::toast::{self, Toast};
 use crate::icon::svg_asset;
 use crate::panels::gauges::gauge::{
-    Gauge, GaugeActionDialog, GaugeDisplay, GaugeEventSource, GaugeInteractionModel, GaugeMenu,
-    GaugeModel, GaugePointerInteraction, GaugeReadyNotify, GaugeRegistrar, GaugeValue, GaugeWake,
-    RunOutcome,
+    AsyncGaugeEventSource, Gauge, GaugeActionDialog, GaugeDisplay, GaugeEventSource,
+    GaugeInteractionModel, GaugeMenu, GaugeModel, GaugePointerInteraction, GaugeReadyNotify,
+    GaugeRedraw, GaugeRegistrar, GaugeStop, GaugeUpdate, GaugeValue, GaugeValueAttention,
+    GaugeWake, RunOutcome,
 };
+use crate::panels::gauges::gauge_diagnostics::{self, GaugeStats};
+use crate::panels::gauges::gauge_executor::{EventRuntime, GaugeExecutor};
 use crate::panels::gauges::gauge_registry;
-use crate::settings;
+use crate::panels::gauges::run_watchdog::RunWatchdog;
+use crate::settings::{self, SettingSpec};
 use iced::Subscription;
 use iced::futures::channel::mpsc;
 use std::cmp::Reverse;
@@ -17,6 +22,34 @@ use std::sync::{Arc, Mutex, mpsc as sync_mpsc};
 use std::thread;
 use std::time::{Duration, Instant};
 
+settings::typed_settings! {
+    struct WorkSettings {
+        max_run_ms: u64 = ("grelier.gauge.work.max_run_ms", "40", 1..=60_000),
+        max_run_strikes: u8 = ("grelier.gauge.work.max_run_strikes", "3", 1..=100),
+        stack_samples: bool = ("grelier.gauge.work.stack_samples", "true"),
+        retry_initial_secs: u64 = ("grelier.gauge.work.retry_initial_secs", "30", 0..=86_400),
+        retry_max_secs: u64 = ("grelier.gauge.work.retry_max_secs", "1800", 1..=86_400),
+    }
+}
+
+/// Default of the per-gauge `grelier.gauge.<id>.min_emit_interval_ms`; 0 shows every change.
+pub const DEFAULT_MIN_EMIT_INTERVAL_MS: &str = "0";
+
+/// Settings of the shared gauge worker.
+pub fn settings() -> &'static [SettingSpec] {
+    WorkSettings::SPECS
+}
+
+pub fn min_emit_interval_key(gauge_id: &str) -> String {
+    format!("grelier.gauge.{gauge_id}.min_emit_interval_ms")
+}
+
+fn min_emit_interval_ms(gauge_id: &str) -> u64 {
+    let key = min_emit_interval_key(gauge_id);
+    let raw = settings::settings().get_or(&key, DEFAULT_MIN_EMIT_INTERVAL_MS);
+    settings::clamp_setting(&key, settings::parse_or_exit(&key, &raw), 0..=60_000)
+}
+
 type GaugeBatchMessageStream = Box<dyn iced::futures::Stream<Item = Message> + Send + Unpin>;
 
 /// Gauge subscription.
@@ -53,16 +86,32 @@ fn gauge_batch_stream_by_ids(ids: &Arc<[String]>) -> GaugeBatchMessageStream {
             gauge.bind_ready_notify(ready_notify.clone());
         }
 
-        let max_run_ms = settings::settings().get_parsed_or("grelier.gauge.work.max_run_ms", 40u64);
-        let max_run_strikes =
-            settings::settings().get_parsed_or("grelier.gauge.work.max_run_strikes", 3u8);
+        let work_settings = WorkSettings::load();
+        let max_run = Duration::from_millis(work_settings.max_run_ms);
         let mut manager = GaugeWorkManager::new(
             SystemClock,
-            Duration::from_millis(max_run_ms),
-            max_run_strikes,
+            max_run,
+            work_settings.max_run_strikes,
+            EventRuntime::from_settings(),
             ready_notify.clone(),
             gauges,
         );
+        if work_settings.stack_samples {
+            manager.set_watchdog(RunWatchdog::spawn(max_run));
+        }
+        let retry_initial_secs = work_settings.retry_initial_secs;
+        if retry_initial_secs > 0 {
+            manager.set_retry_policy(RetryPolicy {
+                initial: Duration::from_secs(retry_initial_secs),
+                max: Duration::from_secs(work_settings.retry_max_secs.max(retry_initial_secs)),
+            });
+        }
+        for id in ids.iter() {
+            let interval_ms = min_emit_interval_ms(id);
+            if interval_ms > 0 {
+                manager.set_min_emit_interval(id, Duration::from_millis(interval_ms));
+            }
+        }
 
         loop {
             let sleep_for = manager.next_wakeup_delay();
@@ -71,6 +120,11 @@ fn gauge_batch_stream_by_ids(ids: &Arc<[String]>) -> GaugeBatchMessageStream {
             if let Some(batch) = manager.step_once() {
                 let _ = sender.try_send(Message::GaugeBatch(batch));
             }
+            gauge_diagnostics::publish(manager.stats());
+            // The subscription is dropped when the gauge set changes; a new worker takes over.
+            if sender.is_closed() {
+                return;
+            }
         }
     });
 
@@ -154,6 +208,25 @@ impl Clock for FakeClock {
     }
 }
 
+/// When to give a gauge disabled for slow runs another chance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Wait before the first retry.
+    pub initial: Duration,
+    /// Longest wait; each failed retry doubles the previous one up to this.
+    pub max: Duration,
+}
+
+impl RetryPolicy {
+    /// Wait before the retry following the `deaths`th consecutive time the gauge was disabled.
+    fn backoff(&self, deaths: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(deaths.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GaugeStatus {
     Active,
@@ -199,17 +272,36 @@ struct GaugeRuntime {
     strike_count: u8,
     /// Total number of times the gauge has been run.
     run_count: u64,
+    /// How long the most recent run took.
+    last_run: Duration,
+    /// Times in a row the gauge was disabled without a fast run in between.
+    deaths: u32,
+    /// When a disabled gauge is next retried; `None` keeps it disabled.
+    retry_at: Option<Instant>,
+    /// Retried after being disabled; one slow run disables it again.
+    on_probation: bool,
+    /// Shortest time between emitted models; zero emits every change.
+    min_emit_interval: Duration,
+    /// When a model was last sent to the UI.
+    last_emit_at: Option<Instant>,
+    /// Latest change held back by `min_emit_interval`, sent once the interval has passed.
+    pending: Option<GaugeModel>,
 }
 
 #[derive(Default)]
 struct RegistrationCollector {
     event_sources: Vec<Box<dyn GaugeEventSource>>,
+    async_event_sources: Vec<Box<dyn AsyncGaugeEventSource>>,
 }
 
 impl GaugeRegistrar for RegistrationCollector {
     fn add_event_source(&mut self, source: Box<dyn GaugeEventSource>) {
         self.event_sources.push(source);
     }
+
+    fn add_async_event_source(&mut self, source: Box<dyn AsyncGaugeEventSource>) {
+        self.async_event_sources.push(source);
+    }
 }
 
 /// Deterministic scheduler used by runtime and unit tests.
@@ -220,6 +312,8 @@ pub struct GaugeWorkManager<C: Clock> {
     clock: C,
     max_run: Duration,
     max_run_strikes: u8,
+    // Retries disabled gauges with exponential backoff; without it they stay disabled.
+    retry_policy: Option<RetryPolicy>,
     runtimes: Vec<GaugeRuntime>,
     id_to_index: HashMap<&'static str, usize>,
     deadline_heap: BinaryHeap<Reverse<(Instant, usize, u64)>>,
@@ -227,32 +321,56 @@ pub struct GaugeWorkManager<C: Clock> {
     ready_set: BTreeSet<usize>,
     // Last model emitted to UI per gauge id; used to suppress redundant updates.
     last_emitted_models: HashMap<&'static str, GaugeModel>,
+    // Samples the worker stack during overlong runs; absent in unit tests.
+    watchdog: Option<RunWatchdog>,
+    // Runs async event sources, and blocking ones in `EventRuntime::Async`; started on first use.
+    executor: Option<GaugeExecutor>,
+    // Stop signals of blocking event sources on threads of their own (`EventRuntime::Threads`).
+    thread_stops: HashMap<&'static str, Vec<GaugeStop>>,
 }
 
 impl<C: Clock> GaugeWorkManager<C> {
     /// Build a scheduler with the provided gauges.
     ///
-    /// `max_run` and `max_run_strikes` control when slow gauges are transitioned to `Dead`.
+    /// `max_run` and `max_run_strikes` control when slow gauges are transitioned to `Dead`;
+    /// `event_runtime` selects where blocking event sources run.
     pub fn new(
         clock: C,
         max_run: Duration,
         max_run_strikes: u8,
+        event_runtime: EventRuntime,
         ready_notify: GaugeReadyNotify,
         gauges: Vec<Box<dyn Gauge>>,
     ) -> Self {
         let mut runtimes = Vec::new();
         let mut id_to_index = HashMap::new();
         let mut deadline_heap = BinaryHeap::new();
+        let mut executor: Option<GaugeExecutor> = None;
+        let mut thread_stops: HashMap<&'static str, Vec<GaugeStop>> = HashMap::new();
 
         for (idx, mut gauge) in gauges.into_iter().enumerate() {
+            let id = gauge.id();
             let mut registration = RegistrationCollector::default();
             gauge.register(&mut registration);
             for event_source in registration.event_sources {
                 let notify = ready_notify.clone();
-                thread::spawn(move || event_source.run(notify));
+                let stop = GaugeStop::default();
+                match event_runtime {
+                    EventRuntime::Threads => {
+                        thread_stops.entry(id).or_default().push(stop.clone());
+                        thread::spawn(move || event_source.run(notify, stop));
+                    }
+                    EventRuntime::Async => executor
+                        .get_or_insert_with(GaugeExecutor::spawn)
+                        .spawn_blocking(id, event_source, notify, stop),
+                }
+            }
+            for event_source in registration.async_event_sources {
+                executor
+                    .get_or_insert_with(GaugeExecutor::spawn)
+                    .spawn_source(id, event_source, ready_notify.clone());
             }
 
-            let id = gauge.id();
             let next_deadline = gauge.next_deadline();
             let runtime = GaugeRuntime {
                 gauge,
@@ -261,6 +379,13 @@ impl<C: Clock> GaugeWorkManager<C> {
                 generation: 0,
                 strike_count: 0,
                 run_count: 0,
+                last_run: Duration::ZERO,
+                deaths: 0,
+                retry_at: None,
+                on_probation: false,
+                min_emit_interval: Duration::ZERO,
+                last_emit_at: None,
+                pending: None,
             };
             id_to_index.insert(id, idx);
             deadline_heap.push(Reverse((next_deadline, idx, 0)));
@@ -271,12 +396,34 @@ impl<C: Clock> GaugeWorkManager<C> {
             clock,
             max_run,
             max_run_strikes: max_run_strikes.max(1),
+            retry_policy: None,
             runtimes,
             id_to_index,
             deadline_heap,
             ready_queue: VecDeque::new(),
             ready_set: BTreeSet::new(),
             last_emitted_models: HashMap::new(),
+            watchdog: None,
+            executor,
+            thread_stops,
+        }
+    }
+
+    /// Sample the calling thread's stack whenever a run exceeds `max_run`.
+    pub fn set_watchdog(&mut self, watchdog: RunWatchdog) {
+        self.watchdog = Some(watchdog);
+    }
+
+    /// Retry gauges disabled for slow runs according to `policy` instead of leaving them disabled.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = Some(policy);
+    }
+
+    /// Cap how often `gauge_id` updates the UI. Changes within `interval` of the last emitted
+    /// model are coalesced into the latest one unless they change the attention level.
+    pub fn set_min_emit_interval(&mut self, gauge_id: &str, interval: Duration) {
+        if let Some(&idx) = self.id_to_index.get(gauge_id) {
+            self.runtimes[idx].min_emit_interval = interval;
         }
     }
 
@@ -299,46 +446,80 @@ impl<C: Clock> GaugeWorkManager<C> {
         }
 
         let now = self.clock.now();
+        let flush_at = self.next_flush_at();
         // Use the heap head to avoid scanning every gauge on each loop iteration.
         while let Some(Reverse((deadline, idx, generation))) = self.deadline_heap.peek().copied() {
-            let runtime = &self.runtimes[idx];
-            if runtime.status == GaugeStatus::Dead
-                || runtime.generation != generation
-                || runtime.next_deadline != deadline
-            {
+            if !is_scheduled(&self.runtimes[idx], deadline, generation) {
                 let _ = self.deadline_heap.pop();
                 continue;
             }
-            return deadline.saturating_duration_since(now);
+            let wake_at = flush_at.map_or(deadline, |flush_at| flush_at.min(deadline));
+            return wake_at.saturating_duration_since(now);
         }
 
-        Duration::from_millis(250)
+        flush_at.map_or(Duration::from_millis(250), |flush_at| {
+            flush_at.saturating_duration_since(now)
+        })
+    }
+
+    /// Earliest time a held-back model is due to be sent.
+    fn next_flush_at(&self) -> Option<Instant> {
+        self.runtimes
+            .iter()
+            .filter(|runtime| runtime.pending.is_some())
+            .filter_map(|runtime| Some(runtime.last_emit_at? + runtime.min_emit_interval))
+            .min()
     }
 
     /// Run one scheduling cycle and return the emitted gauge update batch.
     ///
     /// Returns `None` when no gauge emitted a model in this cycle.
-    pub fn step_once(&mut self) -> Option<Vec<GaugeModel>> {
+    pub fn step_once(&mut self) -> Option<Vec<GaugeUpdate>> {
         let now = self.clock.now();
         let mut runnable = BTreeSet::new();
         let mut external_wake = BTreeSet::new();
 
-        // Pop all due heap entries, ignoring stale generations and dead gauges.
+        // Pop all due heap entries, ignoring stale generations; dead gauges are only due to retry.
         while let Some(Reverse((deadline, idx, generation))) = self.deadline_heap.peek().copied() {
             if deadline > now {
                 break;
             }
             let _ = self.deadline_heap.pop();
-            let runtime = &self.runtimes[idx];
-            if runtime.status == GaugeStatus::Dead {
+            let runtime = &mut self.runtimes[idx];
+            if !is_scheduled(runtime, deadline, generation) {
                 continue;
             }
-            if runtime.generation != generation || runtime.next_deadline != deadline {
-                continue;
+            if runtime.status == GaugeStatus::Dead {
+                // The retry deadline of a disabled gauge has come: run it again on probation.
+                log::info!(
+                    "Retrying gauge '{}' after it was disabled",
+                    runtime.gauge.id()
+                );
+                runtime.status = GaugeStatus::Active;
+                runtime.retry_at = None;
+                runtime.strike_count = 0;
+                runtime.on_probation = true;
             }
             runnable.insert(idx);
         }
 
+        // Send models held back by `min_emit_interval` whose interval has passed.
+        let mut updates = Vec::new();
+        for runtime in &mut self.runtimes {
+            let due = runtime
+                .last_emit_at
+                .is_none_or(|at| now.saturating_duration_since(at) >= runtime.min_emit_interval);
+            if due && let Some(model) = runtime.pending.take() {
+                emit_model(
+                    &mut self.last_emitted_models,
+                    runtime,
+                    model,
+                    now,
+                    &mut updates,
+                );
+            }
+        }
+
         // Merge explicit ready notifications; set+queue guarantees each gauge runs at most once/cycle.
         while let Some(idx) = self.ready_queue.pop_front() {
             self.ready_set.remove(&idx);
@@ -348,11 +529,6 @@ impl<C: Clock> GaugeWorkManager<C> {
             }
         }
 
-        if runnable.is_empty() {
-            return None;
-        }
-
-        let mut updates = Vec::new();
         for idx in runnable {
             let runtime = &mut self.runtimes[idx];
             if runtime.status == GaugeStatus::Dead {
@@ -365,35 +541,105 @@ impl<C: Clock> GaugeWorkManager<C> {
             } else {
                 GaugeWake::Timer
             };
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.begin(runtime.gauge.id());
+            }
             let run_outcome = runtime.gauge.run(wake, now);
+            if let Some(watchdog) = &self.watchdog {
+                watchdog.end();
+            }
             let elapsed = self.clock.now().saturating_duration_since(started);
             runtime.run_count = runtime.run_count.saturating_add(1);
+            runtime.last_run = elapsed;
 
             if elapsed > self.max_run {
                 runtime.strike_count = runtime.strike_count.saturating_add(1);
-                if runtime.strike_count >= self.max_run_strikes {
-                    // Emit one final model (turtle icon) and permanently unschedule this gauge.
+                if runtime.strike_count >= self.max_run_strikes || runtime.on_probation {
+                    // Emit one final model (turtle icon) and unschedule this gauge until its
+                    // retry, or for good without a retry policy.
                     runtime.status = GaugeStatus::Dead;
-                    updates.push(dead_gauge_model(runtime.gauge.id()));
+                    runtime.pending = None;
+                    runtime.on_probation = false;
+                    runtime.deaths = runtime.deaths.saturating_add(1);
+                    let id = runtime.gauge.id();
+                    let retry_in = self
+                        .retry_policy
+                        .map(|policy| policy.backoff(runtime.deaths));
+                    match retry_in {
+                        // Event sources keep running so the gauge has fresh input when retried.
+                        Some(retry_in) => {
+                            let retry_at = now + retry_in;
+                            runtime.retry_at = Some(retry_at);
+                            runtime.next_deadline = retry_at;
+                            runtime.generation = runtime.generation.wrapping_add(1);
+                            self.deadline_heap
+                                .push(Reverse((retry_at, idx, runtime.generation)));
+                        }
+                        None => {
+                            cancel_event_sources(&mut self.thread_stops, self.executor.as_mut(), id)
+                        }
+                    }
+                    let sample = self
+                        .watchdog
+                        .as_ref()
+                        .and_then(|watchdog| watchdog.take_sample(id));
+                    log::error!(
+                        "gauge '{id}' disabled after {} runs over {:?}; stack of last slow run:\n{}",
+                        runtime.strike_count,
+                        self.max_run,
+                        sample.as_deref().unwrap_or("(not captured)")
+                    );
+                    let body = match retry_in {
+                        Some(retry_in) => format!(
+                            "'{id}' was paused after repeated slow runs; retrying in {}s.",
+                            retry_in.as_secs()
+                        ),
+                        None => format!("'{id}' was disabled after repeated slow runs."),
+                    };
+                    toast::show(
+                        Toast::new("Gauge stopped", body)
+                            .with_attention(GaugeValueAttention::Danger)
+                            .with_timeout(Duration::from_secs(10)),
+                    );
+                    let model = dead_gauge_model(id);
+                    self.last_emitted_models.insert(id, model.clone());
+                    updates.push(GaugeUpdate {
+                        model,
+                        redraw: GaugeRedraw::ALL,
+                    });
                     continue;
                 }
             } else {
                 runtime.strike_count = 0;
+                // A fast run after a retry means the gauge has recovered.
+                if runtime.on_probation {
+                    runtime.on_probation = false;
+                    runtime.deaths = 0;
+                }
             }
 
             match run_outcome {
                 RunOutcome::NoChange => {}
                 RunOutcome::ModelChanged(model) => {
                     let model = *model;
-                    // Avoid pushing unchanged renders to UI when a gauge emits equivalent state.
-                    let should_emit = self
+                    let within_interval = runtime.last_emit_at.is_some_and(|at| {
+                        now.saturating_duration_since(at) < runtime.min_emit_interval
+                    });
+                    let same_attention = self
                         .last_emitted_models
                         .get(model.id)
-                        .map(|previous| !models_visually_equal(previous, &model))
-                        .unwrap_or(true);
-                    if should_emit {
-                        self.last_emitted_models.insert(model.id, model.clone());
-                        updates.push(model);
+                        .is_some_and(|previous| same_attention(&previous.display, &model.display));
+                    if within_interval && same_attention {
+                        runtime.pending = Some(model);
+                    } else {
+                        runtime.pending = None;
+                        emit_model(
+                            &mut self.last_emitted_models,
+                            runtime,
+                            model,
+                            now,
+                            &mut updates,
+                        );
                     }
                 }
             }
@@ -412,6 +658,20 @@ impl<C: Clock> GaugeWorkManager<C> {
         }
     }
 
+    /// Runtime counters of every gauge, for the diagnostics dialog and `--doctor`.
+    pub fn stats(&self) -> Vec<GaugeStats> {
+        self.runtimes
+            .iter()
+            .map(|runtime| GaugeStats {
+                id: runtime.gauge.id().to_string(),
+                dead: runtime.status == GaugeStatus::Dead,
+                run_count: runtime.run_count,
+                strike_count: runtime.strike_count,
+                last_run: runtime.last_run,
+            })
+            .collect()
+    }
+
     #[cfg(test)]
     pub fn snapshot(&self) -> ManagerSnapshot {
         ManagerSnapshot {
@@ -443,6 +703,67 @@ impl<C: Clock> GaugeWorkManager<C> {
     }
 }
 
+impl<C: Clock> Drop for GaugeWorkManager<C> {
+    fn drop(&mut self) {
+        // Blocking sources on their own threads would otherwise outlive the worker.
+        for stop in self.thread_stops.drain().flat_map(|(_, stops)| stops) {
+            stop.stop();
+        }
+    }
+}
+
+/// Stop every event source of `id`, whether on its own thread or on the executor.
+fn cancel_event_sources(
+    thread_stops: &mut HashMap<&'static str, Vec<GaugeStop>>,
+    executor: Option<&mut GaugeExecutor>,
+    id: &str,
+) {
+    for stop in thread_stops.remove(id).into_iter().flatten() {
+        stop.stop();
+    }
+    if let Some(executor) = executor {
+        executor.cancel(id);
+    }
+}
+
+/// Whether a heap entry still stands for `runtime`'s next run or, for a disabled gauge, its retry.
+fn is_scheduled(runtime: &GaugeRuntime, deadline: Instant, generation: u64) -> bool {
+    let live = runtime.status == GaugeStatus::Active || runtime.retry_at.is_some();
+    live && runtime.generation == generation && runtime.next_deadline == deadline
+}
+
+/// Queue `model` for the UI unless it matches the last emitted one.
+fn emit_model(
+    last_emitted_models: &mut HashMap<&'static str, GaugeModel>,
+    runtime: &mut GaugeRuntime,
+    model: GaugeModel,
+    now: Instant,
+    updates: &mut Vec<GaugeUpdate>,
+) {
+    // Avoid pushing unchanged renders to UI when a gauge emits equivalent state,
+    // and tell the view which parts changed so the rest can be kept as-is.
+    let redraw = last_emitted_models
+        .get(model.id)
+        .map(|previous| redraw_hint(previous, &model))
+        .unwrap_or(GaugeRedraw::ALL);
+    if !redraw.is_empty() {
+        runtime.last_emit_at = Some(now);
+        last_emitted_models.insert(model.id, model.clone());
+        updates.push(GaugeUpdate { model, redraw });
+    }
+}
+
+/// Whether two displays share a kind and attention level, so one may stand in for the other.
+fn same_attention(a: &GaugeDisplay, b: &GaugeDisplay) -> bool {
+    match (a, b) {
+        (
+            GaugeDisplay::Value { attention: a, .. } | GaugeDisplay::Graph { attention: a, .. },
+            GaugeDisplay::Value { attention: b, .. } | GaugeDisplay::Graph { attention: b, .. },
+        ) => a == b,
+        _ => std::mem::discriminant(a) == std::mem::discriminant(b),
+    }
+}
+
 fn dead_gauge_model(id: &'static str) -> GaugeModel {
     GaugeModel {
         id,
@@ -452,11 +773,16 @@ fn dead_gauge_model(id: &'static str) -> GaugeModel {
     }
 }
 
-fn models_visually_equal(a: &GaugeModel, b: &GaugeModel) -> bool {
-    if a.id != b.id || a.icon != b.icon || !display_equal(&a.display, &b.display) {
-        return false;
+/// Parts of `next` that differ visually from `previous`; empty when nothing needs redrawing.
+fn redraw_hint(previous: &GaugeModel, next: &GaugeModel) -> GaugeRedraw {
+    if previous.id != next.id {
+        return GaugeRedraw::ALL;
+    }
+    GaugeRedraw {
+        icon: previous.icon != next.icon,
+        value: !display_equal(&previous.display, &next.display),
+        interactions: !interactions_equal(&previous.interactions, &next.interactions),
     }
-    interactions_equal(&a.interactions, &b.interactions)
 }
 
 fn display_equal(a: &GaugeDisplay, b: &GaugeDisplay) -> bool {
@@ -471,6 +797,16 @@ fn display_equal(a: &GaugeDisplay, b: &GaugeDisplay) -> bool {
                 attention: ba,
             },
         ) => aa == ba && value_equal(av, bv),
+        (
+            GaugeDisplay::Graph {
+                samples: a_samples,
+                attention: aa,
+            },
+            GaugeDisplay::Graph {
+                samples: b_samples,
+                attention: ba,
+            },
+        ) => aa == ba && a_samples == b_samples,
         (GaugeDisplay::Empty, GaugeDisplay::Empty) => true,
         (GaugeDisplay::Error, GaugeDisplay::Error) => true,
         _ => false,
@@ -492,7 +828,10 @@ fn menu_equal(a: Option<&GaugeMenu>, b: Option<&GaugeMenu>) -> bool {
             a.title == b.title
                 && a.items.len() == b.items.len()
                 && a.items.iter().zip(&b.items).all(|(ai, bi)| {
-                    ai.id == bi.id && ai.label == bi.label && ai.selected == bi.selected
+                    ai.id == bi.id
+                        && ai.label == bi.label
+                        && ai.selected == bi.selected
+                        && ai.exec == bi.exec
                 })
         }
         _ => false,
@@ -508,7 +847,7 @@ fn action_dialog_equal(a: Option<&GaugeActionDialog>, b: Option<&GaugeActionDial
                 && a.items
                     .iter()
                     .zip(&b.items)
-                    .all(|(ai, bi)| ai.id == bi.id && ai.icon == bi.icon)
+                    .all(|(ai, bi)| ai.id == bi.id && ai.icon == bi.icon && ai.exec == bi.exec)
         }
         _ => false,
     }
@@ -601,6 +940,64 @@ mod tests {
         }
     }
 
+    /// Test gauge emitting a rising value with a given attention on each ready run.
+    struct CountingGauge {
+        count: u32,
+        attention: Arc<Mutex<GaugeValueAttention>>,
+        start: Instant,
+    }
+
+    impl Gauge for CountingGauge {
+        fn id(&self) -> &'static str {
+            "count"
+        }
+
+        fn next_deadline(&self) -> Instant {
+            self.start + Duration::from_secs(3600)
+        }
+
+        fn run_once(&mut self, _now: Instant) -> Option<GaugeModel> {
+            self.count += 1;
+            Some(GaugeModel {
+                id: "count",
+                icon: svg_asset("ratio-0.svg"),
+                display: GaugeDisplay::Value {
+                    value: GaugeValue::Text(self.count.to_string()),
+                    attention: *self.attention.lock().unwrap(),
+                },
+                interactions: GaugeInteractionModel::default(),
+            })
+        }
+    }
+
+    /// Test gauge whose run duration the test can change between runs.
+    struct FlakyGauge {
+        clock: FakeClock,
+        run_duration: Arc<Mutex<Duration>>,
+        next_deadline: Instant,
+    }
+
+    impl Gauge for FlakyGauge {
+        fn id(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn next_deadline(&self) -> Instant {
+            self.next_deadline
+        }
+
+        fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+            self.clock.advance(*self.run_duration.lock().unwrap());
+            self.next_deadline = now + Duration::from_secs(1);
+            Some(GaugeModel {
+                id: "flaky",
+                icon: svg_asset("ratio-0.svg"),
+                display: GaugeDisplay::Empty,
+                interactions: GaugeInteractionModel::default(),
+            })
+        }
+    }
+
     fn runtime<'a>(snapshot: &'a ManagerSnapshot, id: &str) -> &'a GaugeRuntimeSnapshot {
         snapshot
             .runtimes
@@ -622,6 +1019,7 @@ mod tests {
             manager_clock,
             Duration::from_millis(40),
             3,
+            EventRuntime::Threads,
             noop_notify(),
             vec![
                 Box::new(TestGauge::new(
@@ -647,7 +1045,7 @@ mod tests {
         clock.advance(Duration::from_millis(10));
         let batch = manager.step_once().expect("first due gauge should emit");
         assert_eq!(batch.len(), 1);
-        assert_eq!(batch[0].id, "g1");
+        assert_eq!(batch[0].model.id, "g1");
 
         let snapshot = manager.snapshot();
         assert!(snapshot.deadline_heap_len >= 2);
@@ -667,6 +1065,7 @@ mod tests {
             manager_clock,
             Duration::from_millis(40),
             3,
+            EventRuntime::Threads,
             noop_notify(),
             vec![Box::new(TestGauge::new(
                 "ready",
@@ -683,7 +1082,7 @@ mod tests {
         assert!(!manager.mark_ready("ready"));
         let batch = manager.step_once().expect("ready queue should run gauge");
         assert_eq!(batch.len(), 1);
-        assert_eq!(batch[0].id, "ready");
+        assert_eq!(batch[0].model.id, "ready");
 
         let snapshot = manager.snapshot();
         assert_eq!(runtime(&snapshot, "ready").run_count, 1);
@@ -698,6 +1097,7 @@ mod tests {
             manager_clock,
             Duration::from_millis(40),
             3,
+            EventRuntime::Threads,
             noop_notify(),
             vec![Box::new(TestGauge::new(
                 "dup",
@@ -726,6 +1126,7 @@ mod tests {
             manager_clock,
             Duration::from_millis(40),
             3,
+            EventRuntime::Threads,
             noop_notify(),
             vec![Box::new(TestGauge::new(
                 "same",
@@ -746,6 +1147,102 @@ mod tests {
         assert!(second.is_none());
     }
 
+    #[test]
+    fn min_emit_interval_coalesces_updates_with_unchanged_attention() {
+        let start = Instant::now();
+        let clock = FakeClock::new(start);
+        let attention = Arc::new(Mutex::new(GaugeValueAttention::Nominal));
+        let mut manager = GaugeWorkManager::new(
+            clock.clone(),
+            Duration::from_millis(40),
+            3,
+            EventRuntime::Threads,
+            noop_notify(),
+            vec![Box::new(CountingGauge {
+                count: 0,
+                attention: attention.clone(),
+                start,
+            })],
+        );
+        manager.set_min_emit_interval("count", Duration::from_secs(1));
+        let emitted_value = |batch: Option<Vec<GaugeUpdate>>| match batch
+            .expect("update should be emitted")[0]
+            .model
+            .display
+            .clone()
+        {
+            GaugeDisplay::Value {
+                value: GaugeValue::Text(text),
+                ..
+            } => text,
+            _ => panic!("expected a value"),
+        };
+
+        manager.mark_ready("count");
+        assert_eq!(emitted_value(manager.step_once()), "1");
+
+        // Changes within the interval are held, the latest replacing earlier ones.
+        for _ in 0..2 {
+            clock.advance(Duration::from_millis(100));
+            manager.mark_ready("count");
+            assert!(manager.step_once().is_none());
+        }
+        assert_eq!(manager.next_wakeup_delay(), Duration::from_millis(800));
+
+        clock.advance(Duration::from_millis(800));
+        assert_eq!(emitted_value(manager.step_once()), "3");
+
+        // A change of attention is shown at once.
+        clock.advance(Duration::from_millis(100));
+        *attention.lock().unwrap() = GaugeValueAttention::Danger;
+        manager.mark_ready("count");
+        assert_eq!(emitted_value(manager.step_once()), "4");
+    }
+
+    #[test]
+    fn redraw_hint_flags_only_changed_parts() {
+        let base = GaugeModel {
+            id: "cpu",
+            icon: svg_asset("ratio-0.svg"),
+            display: GaugeDisplay::Value {
+                value: GaugeValue::Text("10".to_string()),
+                attention: GaugeValueAttention::Nominal,
+            },
+            interactions: GaugeInteractionModel::default(),
+        };
+        assert!(redraw_hint(&base, &base.clone()).is_empty());
+
+        let mut value_changed = base.clone();
+        value_changed.display = GaugeDisplay::Value {
+            value: GaugeValue::Text("11".to_string()),
+            attention: GaugeValueAttention::Nominal,
+        };
+        assert_eq!(
+            redraw_hint(&base, &value_changed),
+            GaugeRedraw {
+                icon: false,
+                value: true,
+                interactions: false,
+            }
+        );
+
+        let mut icon_and_info = base.clone();
+        icon_and_info.icon = svg_asset("ratio-1.svg");
+        icon_and_info.interactions.left_click.info = Some(InfoDialog {
+            title: "CPU".to_string(),
+            lines: vec!["10%".to_string()],
+            graph: None,
+        });
+        assert_eq!(
+            redraw_hint(&base, &icon_and_info),
+            GaugeRedraw {
+                icon: true,
+                value: false,
+                interactions: true,
+            }
+        );
+    }
+
     #[test]
     fn timeout_strikes_transition_gauge_to_dead() {
         let start = Instant::now();
@@ -755,6 +1252,7 @@ mod tests {
             manager_clock,
             Duration::from_millis(40),
             2,
+            EventRuntime::Threads,
             noop_notify(),
             vec![Box::new(TestGauge::new(
                 "slow",
@@ -776,15 +1274,72 @@ mod tests {
             .step_once()
             .expect("dead transition should emit turtle model");
         assert_eq!(dead_batch.len(), 1);
-        assert_eq!(dead_batch[0].id, "slow");
-        assert_eq!(dead_batch[0].icon, svg_asset("turtle.svg"));
-        assert!(matches!(dead_batch[0].display, GaugeDisplay::Empty));
+        assert_eq!(dead_batch[0].model.id, "slow");
+        assert_eq!(dead_batch[0].model.icon, svg_asset("turtle.svg"));
+        assert!(matches!(dead_batch[0].model.display, GaugeDisplay::Empty));
         let second = manager.snapshot();
         assert_eq!(runtime(&second, "slow").status, GaugeStatus::Dead);
         assert_eq!(runtime(&second, "slow").strike_count, 2);
+        let stats = manager.stats();
+        assert!(stats[0].dead);
+        assert_eq!(stats[0].run_count, 2);
+        assert_eq!(stats[0].last_run, Duration::from_millis(50));
         assert!(!manager.mark_ready("slow"));
     }
 
+    #[test]
+    fn dead_gauges_are_retried_with_backoff() {
+        let start = Instant::now();
+        let clock = FakeClock::new(start);
+        let run_duration = Arc::new(Mutex::new(Duration::from_millis(50)));
+        let mut manager = GaugeWorkManager::new(
+            clock.clone(),
+            Duration::from_millis(40),
+            1,
+            EventRuntime::Threads,
+            noop_notify(),
+            vec![Box::new(FlakyGauge {
+                clock: clock.clone(),
+                run_duration: run_duration.clone(),
+                next_deadline: start,
+            })],
+        );
+        let policy = RetryPolicy {
+            initial: Duration::from_secs(10),
+            max: Duration::from_secs(25),
+        };
+        manager.set_retry_policy(policy);
+        assert_eq!(policy.backoff(3), Duration::from_secs(25));
+
+        let batch = manager.step_once().expect("turtle model");
+        assert_eq!(batch[0].model.icon, svg_asset("turtle.svg"));
+        assert_eq!(
+            runtime(&manager.snapshot(), "flaky").status,
+            GaugeStatus::Dead
+        );
+        assert!(!manager.mark_ready("flaky"));
+        assert_eq!(manager.next_wakeup_delay(), Duration::from_millis(9950));
+
+        // Still slow when retried: one slow run disables it again, for twice as long.
+        clock.advance(Duration::from_secs(10));
+        assert!(manager.step_once().is_some());
+        assert_eq!(
+            runtime(&manager.snapshot(), "flaky").status,
+            GaugeStatus::Dead
+        );
+        assert_eq!(manager.next_wakeup_delay(), Duration::from_millis(19_950));
+
+        // Fast again: the retry brings it back and replaces the turtle.
+        *run_duration.lock().unwrap() = Duration::from_millis(1);
+        clock.advance(Duration::from_secs(20));
+        let batch = manager.step_once().expect("recovered model");
+        assert_eq!(batch[0].model.icon, svg_asset("ratio-0.svg"));
+        let snapshot = manager.snapshot();
+        assert_eq!(runtime(&snapshot, "flaky").status, GaugeStatus::Active);
+        assert_eq!(runtime(&snapshot, "flaky").run_count, 3);
+        assert!(manager.mark_ready("flaky"));
+    }
+
     #[test]
     fn system_clock_produces_non_decreasing_instant() {
         let clock = SystemClock;
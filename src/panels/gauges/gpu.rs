@@ -0,0 +1,340 @@
+// GPU gauge reading utilization, VRAM, and temperature for amdgpu (sysfs) and NVIDIA (nvidia-smi).
+// Consumes Settings: grelier.gauge.gpu.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::{icon_quantity, svg_asset};
+use crate::panels::gauges::gauge::Gauge;
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_dependencies;
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::panels::gauges::gauge_tuning;
+use crate::settings::{self, SettingSpec};
+use crate::units::Units;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const SYS_DRM: &str = "/sys/class/drm";
+const NVIDIA_PROC: &str = "/proc/driver/nvidia";
+
+settings::typed_settings! {
+    struct GpuSettings {
+        warning_percent: u8 = ("grelier.gauge.gpu.warning_percent", "85", 0..=100),
+        danger_percent: u8 = ("grelier.gauge.gpu.danger_percent", "95", 0..=100),
+        vram_warning_percent: u8 = ("grelier.gauge.gpu.vram_warning_percent", "90", 0..=100),
+        temp_warning_celsius: f32 = ("grelier.gauge.gpu.temp_warning_celsius", "80"),
+        temp_danger_celsius: f32 = ("grelier.gauge.gpu.temp_danger_celsius", "90"),
+        poll_interval_secs: u64 = ("grelier.gauge.gpu.poll_interval_secs", "3", 1..=3600),
+    }
+}
+
+/// Stats for one GPU; fields the driver does not expose stay `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct GpuStats {
+    name: String,
+    busy_percent: Option<u8>,
+    vram_used: Option<u64>,
+    vram_total: Option<u64>,
+    temp_celsius: Option<f32>,
+}
+
+impl GpuStats {
+    fn vram_percent(&self) -> Option<u8> {
+        let (used, total) = (self.vram_used?, self.vram_total?);
+        (total > 0).then(|| ((used as f64 / total as f64) * 100.0).round().min(100.0) as u8)
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+/// First `temp1_input` under the device's hwmon directory, in °C.
+fn read_hwmon_temp(device: &Path) -> Option<f32> {
+    fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .flatten()
+        .find_map(|entry| read_u64(&entry.path().join("temp1_input")))
+        .map(|millidegrees| millidegrees as f32 / 1000.0)
+}
+
+/// amdgpu cards expose `gpu_busy_percent` and `mem_info_vram_*` on the PCI device.
+fn read_amdgpu(drm: &Path) -> Vec<GpuStats> {
+    let Ok(entries) = fs::read_dir(drm) else {
+        return Vec::new();
+    };
+    let mut cards: Vec<(String, GpuStats)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let card = entry.file_name().to_string_lossy().into_owned();
+            // Skip connector entries such as card0-DP-1.
+            if !card.starts_with("card") || card.contains('-') {
+                return None;
+            }
+            let device = entry.path().join("device");
+            let busy_percent = read_u64(&device.join("gpu_busy_percent"))?;
+            Some((
+                card.clone(),
+                GpuStats {
+                    name: format!("{card} (amdgpu)"),
+                    busy_percent: Some(busy_percent.min(100) as u8),
+                    vram_used: read_u64(&device.join("mem_info_vram_used")),
+                    vram_total: read_u64(&device.join("mem_info_vram_total")),
+                    temp_celsius: read_hwmon_temp(&device),
+                },
+            ))
+        })
+        .collect();
+    cards.sort_by(|a, b| a.0.cmp(&b.0));
+    cards.into_iter().map(|(_, stats)| stats).collect()
+}
+
+/// Parse `nvidia-smi --query-gpu=name,utilization.gpu,memory.used,memory.total,temperature.gpu
+/// --format=csv,noheader,nounits`; memory is reported in MiB.
+fn parse_nvidia_smi(output: &str) -> Vec<GpuStats> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [name, busy, used, total, temp] = fields.as_slice() else {
+                return None;
+            };
+            let mib = |value: &str| value.parse::<u64>().ok().map(|mib| mib * 1024 * 1024);
+            Some(GpuStats {
+                name: name.to_string(),
+                busy_percent: busy.parse::<u8>().ok().map(|busy| busy.min(100)),
+                vram_used: mib(used),
+                vram_total: mib(total),
+                temp_celsius: temp.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+fn read_nvidia() -> Vec<GpuStats> {
+    // Avoid spawning nvidia-smi every poll on machines without the driver.
+    if !Path::new(NVIDIA_PROC).exists() {
+        return Vec::new();
+    }
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=name,utilization.gpu,memory.used,memory.total,temperature.gpu",
+            "--format=csv,noheader,nounits",
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn gpu_attention(stats: &GpuStats, settings: &GpuSettings) -> GaugeValueAttention {
+    let busy = stats.busy_percent.unwrap_or(0);
+    let temp = stats.temp_celsius.unwrap_or(0.0);
+    let vram = stats.vram_percent().unwrap_or(0);
+    if busy >= settings.danger_percent || temp >= settings.temp_danger_celsius {
+        GaugeValueAttention::Danger
+    } else if busy >= settings.warning_percent
+        || temp >= settings.temp_warning_celsius
+        || vram >= settings.vram_warning_percent
+    {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+fn info_lines(gpus: &[GpuStats], units: &Units) -> Vec<String> {
+    let mut lines = Vec::new();
+    for gpu in gpus {
+        lines.push(gpu.name.clone());
+        if let Some(busy) = gpu.busy_percent {
+            lines.push(format!("  Busy: {busy}%"));
+        }
+        if let (Some(used), Some(total)) = (gpu.vram_used, gpu.vram_total) {
+            lines.push(format!(
+                "  VRAM: {} / {}",
+                units.format_bytes(used),
+                units.format_bytes(total)
+            ));
+        }
+        if let Some(temp) = gpu.temp_celsius {
+            lines.push(format!("  Temp: {}", units.format_temperature(temp, 0)));
+        }
+    }
+    lines
+}
+
+/// Gauge that shows the busiest GPU's utilization.
+struct GpuGauge {
+    /// Thresholds and poll cadence.
+    settings: GpuSettings,
+    /// Unit preferences for the info dialog.
+    units: Units,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for GpuGauge {
+    fn id(&self) -> &'static str {
+        "gpu"
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now
+            + Duration::from_secs(gauge_tuning::live_secs(
+                "gpu",
+                self.settings.poll_interval_secs,
+            ));
+
+        let mut gpus = read_amdgpu(Path::new(SYS_DRM));
+        gpus.extend(read_nvidia());
+
+        let busiest = gpus
+            .iter()
+            .max_by_key(|gpu| (gpu_attention(gpu, &self.settings), gpu.busy_percent));
+        let (display, lines) = match busiest {
+            Some(gpu) => (
+                GaugeDisplay::Value {
+                    value: GaugeValue::Svg(icon_quantity(
+                        gpu.busy_percent.unwrap_or(0) as f32 / 100.0,
+                    )),
+                    attention: gpu_attention(gpu, &self.settings),
+                },
+                info_lines(&gpus, &self.units),
+            ),
+            None => (GaugeDisplay::Error, {
+                let mut lines = vec!["No supported GPU found".to_string()];
+                lines.extend(gauge_dependencies::missing_hints("gpu"));
+                lines
+            }),
+        };
+
+        Some(GaugeModel {
+            id: "gpu",
+            icon: svg_asset("gpu.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "GPU".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    Box::new(GpuGauge {
+        settings: GpuSettings::load(),
+        units: Units::load(),
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    GpuSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "gpu",
+        description: "GPU gauge showing utilization, VRAM, and temperature for amdgpu and NVIDIA.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_drm(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("grelier_gpu_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+        fs::write(path, contents).expect("write file");
+    }
+
+    #[test]
+    fn reads_amdgpu_cards_from_sysfs() {
+        let drm = temp_drm("amd");
+        let device = drm.join("card1/device");
+        write(&device.join("gpu_busy_percent"), "42\n");
+        write(&device.join("mem_info_vram_used"), "1073741824\n");
+        write(&device.join("mem_info_vram_total"), "8589934592\n");
+        write(&device.join("hwmon/hwmon3/temp1_input"), "61000\n");
+        write(&drm.join("card1-DP-1/status"), "connected\n");
+        write(&drm.join("card0/device/vendor"), "0x8086\n");
+
+        let gpus = read_amdgpu(&drm);
+        assert_eq!(
+            gpus,
+            vec![GpuStats {
+                name: "card1 (amdgpu)".to_string(),
+                busy_percent: Some(42),
+                vram_used: Some(1 << 30),
+                vram_total: Some(8 << 30),
+                temp_celsius: Some(61.0),
+            }]
+        );
+        assert_eq!(gpus[0].vram_percent(), Some(13));
+
+        let _ = fs::remove_dir_all(&drm);
+    }
+
+    #[test]
+    fn parses_nvidia_smi_and_applies_thresholds() {
+        let gpus = parse_nvidia_smi("NVIDIA GeForce RTX 3070, 97, 2048, 8192, 71\nbad line\n");
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].busy_percent, Some(97));
+        assert_eq!(gpus[0].vram_used, Some(2048 * 1024 * 1024));
+
+        let settings = GpuSettings::default();
+        assert_eq!(
+            gpu_attention(&gpus[0], &settings),
+            GaugeValueAttention::Danger
+        );
+        let hot = GpuStats {
+            busy_percent: Some(10),
+            temp_celsius: Some(85.0),
+            ..GpuStats::default()
+        };
+        assert_eq!(gpu_attention(&hot, &settings), GaugeValueAttention::Warning);
+        assert_eq!(
+            gpu_attention(&GpuStats::default(), &settings),
+            GaugeValueAttention::Nominal
+        );
+    }
+}
@@ -0,0 +1,267 @@
+// System health gauge: one score from CPU, memory, disk, temperature, and pressure stall
+// readings, for a bar that shows a single icon instead of five. The info dialog breaks the
+// score down by factor.
+// Consumes Settings: grelier.gauge.health.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::{icon_quantity, svg_asset};
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::panels::gauges::gauge_tuning;
+use crate::panels::gauges::{cpu, disk, pressure, ram, temp};
+use crate::settings::{self, SettingSpec};
+use crate::units::Units;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Temperature at or below which the temperature factor costs nothing.
+const COOL_CELSIUS: f32 = 40.0;
+/// Temperature at which the temperature factor costs its full weight.
+const HOT_CELSIUS: f32 = 95.0;
+/// Stall share, in percent, at which the pressure factor costs its full weight.
+const FULL_STALL_PERCENT: f32 = 50.0;
+
+settings::typed_settings! {
+    struct HealthSettings {
+        cpu_weight: f32 = ("grelier.gauge.health.cpu_weight", "1", 0.0..=10.0),
+        ram_weight: f32 = ("grelier.gauge.health.ram_weight", "1", 0.0..=10.0),
+        disk_weight: f32 = ("grelier.gauge.health.disk_weight", "1", 0.0..=10.0),
+        temp_weight: f32 = ("grelier.gauge.health.temp_weight", "1", 0.0..=10.0),
+        pressure_weight: f32 = ("grelier.gauge.health.pressure_weight", "1", 0.0..=10.0),
+        disk_path: String = ("grelier.gauge.health.disk_path", "/"),
+        warning_score: u8 = ("grelier.gauge.health.warning_score", "60", 0..=100),
+        danger_score: u8 = ("grelier.gauge.health.danger_score", "35", 0..=100),
+        poll_interval_secs: u64 = ("grelier.gauge.health.poll_interval_secs", "5", 1..=3600),
+    }
+}
+
+/// One input to the score.
+#[derive(Debug, Clone, PartialEq)]
+struct Factor {
+    /// Dialog text describing the reading, such as "CPU: 35% busy".
+    label: String,
+    /// How strained the resource is, from 0 (idle) to 1 (exhausted).
+    load: f32,
+    weight: f32,
+}
+
+/// Score out of 100, where 100 is an idle machine, and the points each factor cost. Factors
+/// without a reading are left out rather than counted as healthy.
+fn score(factors: &[Factor]) -> Option<(u8, Vec<f32>)> {
+    let total_weight: f32 = factors.iter().map(|factor| factor.weight).sum();
+    if total_weight <= 0.0 {
+        return None;
+    }
+    let costs: Vec<f32> = factors
+        .iter()
+        .map(|factor| 100.0 * factor.weight * factor.load.clamp(0.0, 1.0) / total_weight)
+        .collect();
+    let score = (100.0 - costs.iter().sum::<f32>())
+        .round()
+        .clamp(0.0, 100.0) as u8;
+    Some((score, costs))
+}
+
+fn attention_for(score: u8, warning: u8, danger: u8) -> GaugeValueAttention {
+    if score <= danger {
+        GaugeValueAttention::Danger
+    } else if score <= warning {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+/// Gauge combining several system readings into one score.
+struct HealthGauge {
+    settings: HealthSettings,
+    /// Unit preferences for the temperature line.
+    units: Units,
+    /// CPU times from the previous run; utilization needs two samples.
+    previous_cpu: Option<cpu::CpuTime>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl HealthGauge {
+    fn factors(&mut self) -> Vec<Factor> {
+        let settings = &self.settings;
+        let mut factors = Vec::new();
+
+        let current_cpu = cpu::read_cpu_stat().map(|stat| stat.total);
+        if let (Some(current), Some(previous)) = (current_cpu, self.previous_cpu) {
+            let load = current.utilization_since(previous);
+            factors.push(Factor {
+                label: format!("CPU: {:.0}% busy", load * 100.0),
+                load,
+                weight: settings.cpu_weight,
+            });
+        }
+        self.previous_cpu = current_cpu;
+
+        if let Some(memory) = ram::MemorySnapshot::read().filter(|memory| memory.total > 0) {
+            let load =
+                1.0 - memory.available_bytes().min(memory.total) as f32 / memory.total as f32;
+            factors.push(Factor {
+                label: format!("Memory: {:.0}% used", load * 100.0),
+                load,
+                weight: settings.ram_weight,
+            });
+        }
+
+        if let Some(usage) = disk::disk_usage(&settings.disk_path).filter(|usage| usage.total > 0) {
+            let load = usage.used as f32 / usage.total as f32;
+            factors.push(Factor {
+                label: format!("Disk {}: {:.0}% used", settings.disk_path, load * 100.0),
+                load,
+                weight: settings.disk_weight,
+            });
+        }
+
+        let mut readings = temp::read_thermal_zones(Path::new(temp::SYS_THERMAL));
+        readings.extend(temp::read_hwmon(Path::new(temp::SYS_HWMON)));
+        if let Some(hottest) = readings
+            .iter()
+            .max_by(|a, b| a.celsius.total_cmp(&b.celsius))
+        {
+            factors.push(Factor {
+                label: format!(
+                    "Temperature: {} ({})",
+                    self.units.format_temperature(hottest.celsius, 0),
+                    hottest.name
+                ),
+                load: (hottest.celsius - COOL_CELSIUS) / (HOT_CELSIUS - COOL_CELSIUS),
+                weight: settings.temp_weight,
+            });
+        }
+
+        let stalls = pressure::read_stalls(Path::new(pressure::PROC_PRESSURE), "avg60");
+        if let Some((resource, stall)) = stalls
+            .iter()
+            .max_by(|a, b| a.1.worst().total_cmp(&b.1.worst()))
+        {
+            factors.push(Factor {
+                label: format!("Pressure: {resource} stalled {:.1}%", stall.worst()),
+                load: stall.worst() / FULL_STALL_PERCENT,
+                weight: settings.pressure_weight,
+            });
+        }
+
+        factors.retain(|factor| factor.weight > 0.0);
+        factors
+    }
+}
+
+impl Gauge for HealthGauge {
+    fn id(&self) -> &'static str {
+        "health"
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now
+            + Duration::from_secs(gauge_tuning::live_secs(
+                "health",
+                self.settings.poll_interval_secs,
+            ));
+        let factors = self.factors();
+
+        let (display, lines) = match score(&factors) {
+            Some((score, costs)) => {
+                let mut lines = vec![format!("Score: {score}/100")];
+                lines.extend(
+                    factors
+                        .iter()
+                        .zip(costs)
+                        .map(|(factor, cost)| format!("{} (-{cost:.0} points)", factor.label)),
+                );
+                (
+                    GaugeDisplay::Value {
+                        value: GaugeValue::Svg(icon_quantity(score as f32 / 100.0)),
+                        attention: attention_for(
+                            score,
+                            self.settings.warning_score,
+                            self.settings.danger_score,
+                        ),
+                    },
+                    lines,
+                )
+            }
+            None => (GaugeDisplay::Empty, vec!["Collecting readings".to_string()]),
+        };
+
+        Some(GaugeModel {
+            id: "health",
+            icon: svg_asset("health.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "System health".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    Box::new(HealthGauge {
+        settings: HealthSettings::load(),
+        units: Units::load(),
+        previous_cpu: None,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    HealthSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "health",
+        description: "System health gauge combining CPU, memory, disk, temperature, and pressure into one score.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factor(load: f32, weight: f32) -> Factor {
+        Factor {
+            label: String::new(),
+            load,
+            weight,
+        }
+    }
+
+    #[test]
+    fn weights_factors_into_one_score() {
+        let (score_value, costs) =
+            score(&[factor(0.5, 1.0), factor(1.0, 3.0), factor(2.0, 0.0)]).expect("score");
+        assert_eq!(score_value, 13);
+        assert_eq!(costs, vec![12.5, 75.0, 0.0]);
+        assert_eq!(score(&[factor(0.2, 1.0)]).map(|(score, _)| score), Some(80));
+        assert_eq!(score(&[]), None);
+        assert_eq!(score(&[factor(1.0, 0.0)]), None);
+
+        assert_eq!(attention_for(80, 60, 35), GaugeValueAttention::Nominal);
+        assert_eq!(attention_for(60, 60, 35), GaugeValueAttention::Warning);
+        assert_eq!(attention_for(12, 60, 35), GaugeValueAttention::Danger);
+    }
+}
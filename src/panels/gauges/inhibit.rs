@@ -0,0 +1,454 @@
+// Suspend inhibitor gauge: holds a logind idle/sleep inhibitor while network transfers run.
+// Consumes Settings: grelier.gauge.inhibit.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar, GaugeStop,
+};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel,
+    GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::panels::gauges::net_common::{self, NetCounters};
+use crate::settings::{self, SettingSpec, Settings};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::OwnedFd;
+
+const MODE_KEY: &str = "grelier.gauge.inhibit.mode";
+const LOGIND_SERVICE: &str = "org.freedesktop.login1";
+const LOGIND_PATH: &str = "/org/freedesktop/login1";
+const LOGIND_IFACE: &str = "org.freedesktop.login1.Manager";
+const INHIBIT_WHAT: &str = "idle:sleep";
+const INHIBIT_WHY: &str = "Network transfer in progress";
+
+settings::typed_settings! {
+    struct InhibitSettings {
+        mode: String = ("grelier.gauge.inhibit.mode", "auto"),
+        threshold_kbps: u64 = ("grelier.gauge.inhibit.threshold_kbps", "500", 1..=10_000_000),
+        sustain_secs: u64 = ("grelier.gauge.inhibit.sustain_secs", "30", 0..=3600),
+        release_secs: u64 = ("grelier.gauge.inhibit.release_secs", "60", 0..=3600),
+        poll_interval_secs: u64 = ("grelier.gauge.inhibit.poll_interval_secs", "5", 1..=600),
+    }
+}
+
+/// When the gauge holds the inhibitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InhibitMode {
+    /// Only while a sustained transfer is detected.
+    Auto,
+    /// Always, regardless of traffic.
+    Always,
+    /// Never, regardless of traffic.
+    Never,
+}
+
+impl InhibitMode {
+    const ALL: [InhibitMode; 3] = [InhibitMode::Auto, InhibitMode::Always, InhibitMode::Never];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            InhibitMode::Auto => "auto",
+            InhibitMode::Always => "always",
+            InhibitMode::Never => "never",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            InhibitMode::Auto => "Auto (during transfers)",
+            InhibitMode::Always => "Always inhibit",
+            InhibitMode::Never => "Never inhibit",
+        }
+    }
+}
+
+impl FromStr for InhibitMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(InhibitMode::Auto),
+            "always" => Ok(InhibitMode::Always),
+            "never" => Ok(InhibitMode::Never),
+            other => Err(format!(
+                "Invalid {MODE_KEY} '{other}', expected 'auto', 'always', or 'never'"
+            )),
+        }
+    }
+}
+
+/// Decides whether traffic counts as a sustained transfer.
+///
+/// The rate must stay above the threshold for `sustain` before a transfer starts, and below it
+/// for `release` before the transfer ends, so short bursts and brief stalls are ignored.
+#[derive(Debug)]
+struct TransferDetector {
+    threshold_bytes_per_sec: f64,
+    sustain: Duration,
+    release: Duration,
+    /// When the rate last crossed to the other side of the threshold.
+    crossed_at: Option<Instant>,
+    active: bool,
+}
+
+impl TransferDetector {
+    fn new(threshold_kbps: u64, sustain: Duration, release: Duration) -> Self {
+        Self {
+            threshold_bytes_per_sec: threshold_kbps as f64 * 1000.0 / 8.0,
+            sustain,
+            release,
+            crossed_at: None,
+            active: false,
+        }
+    }
+
+    /// Feed a combined rx+tx rate and return whether a transfer is in progress.
+    fn update(&mut self, now: Instant, bytes_per_sec: f64) -> bool {
+        let above = bytes_per_sec >= self.threshold_bytes_per_sec;
+        if above == self.active {
+            self.crossed_at = None;
+            return self.active;
+        }
+        let crossed_at = *self.crossed_at.get_or_insert(now);
+        let hold = if self.active {
+            self.release
+        } else {
+            self.sustain
+        };
+        if now.duration_since(crossed_at) >= hold {
+            self.active = above;
+            self.crossed_at = None;
+        }
+        self.active
+    }
+}
+
+/// State shared between the monitor thread and the gauge.
+#[derive(Debug, Clone, Copy)]
+struct InhibitState {
+    mode: InhibitMode,
+    transfer: bool,
+    bytes_per_sec: f64,
+    /// Whether logind currently holds our inhibitor.
+    held: bool,
+    /// Set when logind refused the last inhibit request.
+    failed: bool,
+}
+
+fn take_inhibitor() -> Result<OwnedFd, String> {
+    let connection =
+        Connection::system().map_err(|err| format!("failed to connect to system bus: {err}"))?;
+    let proxy = Proxy::new(&connection, LOGIND_SERVICE, LOGIND_PATH, LOGIND_IFACE)
+        .map_err(|err| format!("failed to create logind proxy: {err}"))?;
+    // logind keeps the inhibitor until every copy of the returned fd is closed.
+    proxy
+        .call("Inhibit", &(INHIBIT_WHAT, "grelier", INHIBIT_WHY, "block"))
+        .map_err(|err| format!("Inhibit failed: {err}"))
+}
+
+/// Samples interface counters and takes or releases the inhibitor to match the mode.
+struct InhibitMonitor {
+    mode_rx: mpsc::Receiver<InhibitMode>,
+    state: Arc<Mutex<InhibitState>>,
+    detector: TransferDetector,
+    poll_interval: Duration,
+}
+
+impl GaugeEventSource for InhibitMonitor {
+    fn run(mut self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
+        let mut mode = match self.state.lock() {
+            Ok(state) => state.mode,
+            Err(_) => return,
+        };
+        let mut inhibitor: Option<OwnedFd> = None;
+        let mut previous: Option<(String, NetCounters, Instant)> = None;
+        loop {
+            let now = Instant::now();
+            let bytes_per_sec = match net_common::active_interface_scan() {
+                Some(iface) => {
+                    let counters = net_common::read_counters(&iface);
+                    let rate = match (&previous, counters) {
+                        (Some((last_iface, last, at)), Some(counters)) if *last_iface == iface => {
+                            let elapsed = now.duration_since(*at).as_secs_f64();
+                            let delta = counters.rx_bytes.saturating_sub(last.rx_bytes)
+                                + counters.tx_bytes.saturating_sub(last.tx_bytes);
+                            if elapsed > 0.0 {
+                                delta as f64 / elapsed
+                            } else {
+                                0.0
+                            }
+                        }
+                        _ => 0.0,
+                    };
+                    previous = counters.map(|counters| (iface, counters, now));
+                    rate
+                }
+                None => {
+                    previous = None;
+                    0.0
+                }
+            };
+            let transfer = self.detector.update(now, bytes_per_sec);
+
+            let wanted = match mode {
+                InhibitMode::Auto => transfer,
+                InhibitMode::Always => true,
+                InhibitMode::Never => false,
+            };
+            let mut failed = false;
+            if wanted && inhibitor.is_none() {
+                match take_inhibitor() {
+                    Ok(fd) => {
+                        log::info!(
+                            "inhibit gauge: holding suspend inhibitor ({})",
+                            mode.as_str()
+                        );
+                        inhibitor = Some(fd);
+                    }
+                    Err(err) => {
+                        log::warn!("inhibit gauge: {err}");
+                        failed = true;
+                    }
+                }
+            } else if !wanted && inhibitor.take().is_some() {
+                log::info!("inhibit gauge: released suspend inhibitor");
+            }
+
+            let changed = match self.state.lock() {
+                Ok(mut state) => {
+                    let held = inhibitor.is_some();
+                    let changed = state.mode != mode
+                        || state.transfer != transfer
+                        || state.held != held
+                        || state.failed != failed;
+                    *state = InhibitState {
+                        mode,
+                        transfer,
+                        bytes_per_sec,
+                        held,
+                        failed,
+                    };
+                    changed
+                }
+                Err(_) => return,
+            };
+            if changed {
+                notify("inhibit");
+            }
+
+            match stop.recv_timeout(&self.mode_rx, self.poll_interval) {
+                Ok(next) => mode = next,
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+}
+
+fn inhibit_model(state: InhibitState, menu: GaugeMenu) -> GaugeModel {
+    let attention = if state.failed {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    };
+    let status = if state.held {
+        "Suspend: inhibited"
+    } else {
+        "Suspend: allowed"
+    };
+    let transfer = if state.transfer {
+        "Transfer: in progress"
+    } else {
+        "Transfer: idle"
+    };
+    let mut lines = vec![
+        status.to_string(),
+        format!("Mode: {}", state.mode.as_str()),
+        transfer.to_string(),
+        format!("Traffic: {:.0} kbit/s", state.bytes_per_sec * 8.0 / 1000.0),
+    ];
+    if state.failed {
+        lines.push("logind refused the inhibitor".to_string());
+    }
+
+    GaugeModel {
+        id: "inhibit",
+        icon: svg_asset("coffee.svg"),
+        display: GaugeDisplay::Value {
+            value: GaugeValue::Text(if state.held { "on" } else { "off" }.to_string()),
+            attention,
+        },
+        interactions: GaugeInteractionModel {
+            left_click: GaugePointerInteraction {
+                info: Some(InfoDialog {
+                    title: "Suspend Inhibitor".to_string(),
+                    lines,
+                    graph: None,
+                }),
+                ..GaugePointerInteraction::default()
+            },
+            right_click: GaugePointerInteraction {
+                menu: Some(menu),
+                ..GaugePointerInteraction::default()
+            },
+            ..GaugeInteractionModel::default()
+        },
+    }
+}
+
+fn mode_menu(current: InhibitMode, on_select: MenuSelectAction) -> GaugeMenu {
+    GaugeMenu {
+        title: "Suspend Inhibitor".to_string(),
+        items: InhibitMode::ALL
+            .iter()
+            .map(|mode| GaugeMenuItem {
+                id: mode.as_str().to_string(),
+                label: mode.label().to_string(),
+                selected: *mode == current,
+                exec: None,
+            })
+            .collect(),
+        on_select: Some(on_select),
+        slider: None,
+    }
+}
+
+struct InhibitGauge {
+    state: Arc<Mutex<InhibitState>>,
+    monitor: Option<InhibitMonitor>,
+    mode_tx: mpsc::Sender<InhibitMode>,
+    ready_notify: Option<GaugeReadyNotify>,
+    next_deadline: Instant,
+}
+
+impl Gauge for InhibitGauge {
+    fn id(&self) -> &'static str {
+        "inhibit"
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(monitor) = self.monitor.take() {
+            registrar.add_event_source(Box::new(monitor));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        let state = *self.state.lock().ok()?;
+        let mode_tx = self.mode_tx.clone();
+        let shared = Arc::clone(&self.state);
+        let ready_notify = self.ready_notify.clone();
+        let on_select: MenuSelectAction = Arc::new(move |id: String| {
+            let Ok(mode) = id.parse::<InhibitMode>() else {
+                return;
+            };
+            settings::settings().update(MODE_KEY, mode.as_str());
+            if let Ok(mut state) = shared.lock() {
+                state.mode = mode;
+            }
+            let _ = mode_tx.send(mode);
+            if let Some(ready_notify) = &ready_notify {
+                ready_notify("inhibit");
+            }
+        });
+
+        // State changes arrive through the monitor's notify; this is only a fallback refresh.
+        self.next_deadline = now + Duration::from_secs(60);
+        Some(inhibit_model(state, mode_menu(state.mode, on_select)))
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = InhibitSettings::load();
+    let mode = settings.mode.parse().unwrap_or(InhibitMode::Auto);
+    let state = Arc::new(Mutex::new(InhibitState {
+        mode,
+        transfer: false,
+        bytes_per_sec: 0.0,
+        held: false,
+        failed: false,
+    }));
+    let (mode_tx, mode_rx) = mpsc::channel();
+    let monitor = InhibitMonitor {
+        mode_rx,
+        state: Arc::clone(&state),
+        detector: TransferDetector::new(
+            settings.threshold_kbps,
+            Duration::from_secs(settings.sustain_secs),
+            Duration::from_secs(settings.release_secs),
+        ),
+        poll_interval: Duration::from_secs(settings.poll_interval_secs),
+    };
+
+    Box::new(InhibitGauge {
+        state,
+        monitor: Some(monitor),
+        mode_tx,
+        ready_notify: None,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    InhibitSettings::SPECS
+}
+
+fn validate(settings: &Settings) -> Result<(), String> {
+    settings
+        .get_or(MODE_KEY, "auto")
+        .parse::<InhibitMode>()
+        .map(|_| ())
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "inhibit",
+        description: "Suspend inhibitor gauge that blocks idle and sleep during sustained network transfers.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: Some(validate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_sustained_transfers_only() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        // 800 kbit/s threshold is 100 kB/s.
+        let mut detector =
+            TransferDetector::new(800, Duration::from_secs(10), Duration::from_secs(20));
+
+        assert!(!detector.update(at(0), 500_000.0));
+        // A dip before the sustain window elapses restarts it.
+        assert!(!detector.update(at(5), 1_000.0));
+        assert!(!detector.update(at(6), 500_000.0));
+        assert!(!detector.update(at(15), 500_000.0));
+        assert!(detector.update(at(16), 500_000.0));
+
+        // Brief stalls keep the transfer active until the release window passes.
+        assert!(detector.update(at(20), 0.0));
+        assert!(detector.update(at(30), 200_000.0));
+        assert!(detector.update(at(31), 0.0));
+        assert!(detector.update(at(50), 0.0));
+        assert!(!detector.update(at(51), 0.0));
+
+        assert_eq!("Always".parse::<InhibitMode>(), Ok(InhibitMode::Always));
+        assert!("sometimes".parse::<InhibitMode>().is_err());
+    }
+}
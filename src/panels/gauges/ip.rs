@@ -0,0 +1,318 @@
+// IP address gauge: primary local address, optional public address, and copy actions.
+// Consumes Settings: grelier.gauge.ip.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeDisplay, GaugeEventSource, GaugeInteractionModel, GaugeMenu, GaugeMenuItem,
+    GaugeModel, GaugePointerInteraction, GaugeReadyNotify, GaugeRegistrar, GaugeStop, GaugeValue,
+    GaugeValueAttention, MenuSelectAction,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::panels::gauges::net_common::active_interface_scan;
+use crate::settings::{self, SettingSpec};
+use serde_json::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+settings::typed_settings! {
+    struct IpSettings {
+        poll_interval_secs: u64 = ("grelier.gauge.ip.poll_interval_secs", "10", 1..=3600),
+        public_ip: bool = ("grelier.gauge.ip.public_ip", "false"),
+        public_ip_url: String = ("grelier.gauge.ip.public_ip_url", "https://ifconfig.me/ip"),
+        public_interval_secs: u64 = ("grelier.gauge.ip.public_interval_secs", "600", 30..=86_400),
+    }
+}
+
+/// One globally scoped address on an interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceAddress {
+    pub iface: String,
+    pub address: String,
+    pub ipv6: bool,
+}
+
+/// Parse `ip -j addr show` output, keeping global-scope addresses.
+fn parse_ip_addr(json: &str) -> Vec<InterfaceAddress> {
+    let Ok(Value::Array(links)) = serde_json::from_str::<Value>(json) else {
+        return Vec::new();
+    };
+    let mut addresses = Vec::new();
+    for link in &links {
+        let Some(iface) = link.get("ifname").and_then(Value::as_str) else {
+            continue;
+        };
+        let infos = link.get("addr_info").and_then(Value::as_array);
+        for info in infos.into_iter().flatten() {
+            if info.get("scope").and_then(Value::as_str) != Some("global") {
+                continue;
+            }
+            let Some(address) = info.get("local").and_then(Value::as_str) else {
+                continue;
+            };
+            addresses.push(InterfaceAddress {
+                iface: iface.to_string(),
+                address: address.to_string(),
+                ipv6: info.get("family").and_then(Value::as_str) == Some("inet6"),
+            });
+        }
+    }
+    addresses
+}
+
+/// Global addresses of every interface, from `ip -j addr show`.
+pub fn read_addresses() -> Vec<InterfaceAddress> {
+    match Command::new("ip").args(["-j", "addr", "show"]).output() {
+        Ok(output) if output.status.success() => {
+            parse_ip_addr(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Address on the default-route interface, preferring IPv4.
+fn primary_address<'a>(
+    addresses: &'a [InterfaceAddress],
+    iface: Option<&str>,
+) -> Option<&'a InterfaceAddress> {
+    let on_iface = |addr: &&InterfaceAddress| iface.is_none_or(|iface| addr.iface == iface);
+    addresses
+        .iter()
+        .filter(on_iface)
+        .min_by_key(|addr| addr.ipv6)
+        .or_else(|| addresses.iter().min_by_key(|addr| addr.ipv6))
+}
+
+/// IPv4 addresses stack one octet per line to fit the bar; IPv6 is too long to show.
+fn bar_text(address: &InterfaceAddress) -> String {
+    if address.ipv6 {
+        "v6".to_string()
+    } else {
+        address.address.replace('.', "\n")
+    }
+}
+
+fn copy_to_clipboard(text: &str) {
+    let spawned = Command::new("wl-copy")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match spawned {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            // Reap the child without blocking the UI thread.
+            thread::spawn(move || child.wait());
+        }
+        Err(err) => log::error!("ip gauge: failed to run wl-copy: {err}"),
+    }
+}
+
+fn fetch_public_ip(url: &str) -> Result<String, String> {
+    let output = Command::new("curl")
+        .args(["-fsS", "--max-time", "10", url])
+        .output()
+        .map_err(|err| format!("failed to run curl: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let address = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if address.parse::<std::net::IpAddr>().is_err() {
+        return Err(format!("unexpected response from {url}"));
+    }
+    Ok(address)
+}
+
+/// Event source that periodically looks up the public address off the gauge worker.
+struct PublicIpMonitor {
+    url: String,
+    interval: Duration,
+    public: Arc<Mutex<Option<Result<String, String>>>>,
+}
+
+impl GaugeEventSource for PublicIpMonitor {
+    fn run(self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
+        loop {
+            let next = fetch_public_ip(&self.url);
+            if let Err(err) = &next {
+                log::warn!("ip gauge: public address lookup failed: {err}");
+            }
+            match self.public.lock() {
+                Ok(mut public) => *public = Some(next),
+                Err(_) => return,
+            }
+            notify("ip");
+            if stop.sleep(self.interval) {
+                return;
+            }
+        }
+    }
+}
+
+/// Gauge showing the primary local IP address.
+struct IpGauge {
+    poll_interval: Duration,
+    /// Latest public address lookup; `None` until the first lookup or when disabled.
+    public: Arc<Mutex<Option<Result<String, String>>>>,
+    /// Lookup thread handed to the work manager on registration, if enabled.
+    public_monitor: Option<PublicIpMonitor>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for IpGauge {
+    fn id(&self) -> &'static str {
+        "ip"
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(monitor) = self.public_monitor.take() {
+            registrar.add_event_source(Box::new(monitor));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + self.poll_interval;
+
+        let addresses = read_addresses();
+        let iface = active_interface_scan();
+        let display = match primary_address(&addresses, iface.as_deref()) {
+            Some(primary) => GaugeDisplay::Value {
+                value: GaugeValue::Text(bar_text(primary)),
+                attention: GaugeValueAttention::Nominal,
+            },
+            None => GaugeDisplay::Error,
+        };
+
+        let mut lines: Vec<String> = addresses
+            .iter()
+            .map(|addr| format!("{}: {}", addr.iface, addr.address))
+            .collect();
+        if lines.is_empty() {
+            lines.push("No global addresses".to_string());
+        }
+        let mut copyable: Vec<(String, String)> = addresses
+            .iter()
+            .map(|addr| {
+                (
+                    addr.address.clone(),
+                    format!("{}  {}", addr.iface, addr.address),
+                )
+            })
+            .collect();
+        match self.public.lock().ok().and_then(|public| public.clone()) {
+            Some(Ok(public)) => {
+                lines.push(format!("public: {public}"));
+                copyable.push((public.clone(), format!("public  {public}")));
+            }
+            Some(Err(_)) => lines.push("public: lookup failed".to_string()),
+            None => {}
+        }
+
+        let on_select: MenuSelectAction = Arc::new(|address: String| copy_to_clipboard(&address));
+        let menu = (!copyable.is_empty()).then(|| GaugeMenu {
+            title: "Copy Address".to_string(),
+            items: copyable
+                .into_iter()
+                .map(|(id, label)| GaugeMenuItem {
+                    id,
+                    label,
+                    selected: false,
+                    exec: None,
+                })
+                .collect(),
+            on_select: Some(on_select),
+            slider: None,
+        });
+
+        Some(GaugeModel {
+            id: "ip",
+            icon: svg_asset("ip.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "IP Addresses".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                right_click: GaugePointerInteraction {
+                    menu,
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = IpSettings::load();
+    let public = Arc::new(Mutex::new(None));
+    let public_monitor = settings.public_ip.then(|| PublicIpMonitor {
+        url: settings.public_ip_url.clone(),
+        interval: Duration::from_secs(settings.public_interval_secs),
+        public: Arc::clone(&public),
+    });
+    Box::new(IpGauge {
+        poll_interval: Duration::from_secs(settings.poll_interval_secs),
+        public,
+        public_monitor,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    IpSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "ip",
+        description: "IP gauge showing the primary local address with copy-to-clipboard actions.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"[
+        {"ifname": "lo", "addr_info": [{"family": "inet", "local": "127.0.0.1", "scope": "host"}]},
+        {"ifname": "wlan0", "addr_info": [
+            {"family": "inet6", "local": "2001:db8::5", "scope": "global"},
+            {"family": "inet", "local": "192.168.1.20", "scope": "global"},
+            {"family": "inet6", "local": "fe80::1", "scope": "link"}
+        ]},
+        {"ifname": "docker0", "addr_info": [{"family": "inet", "local": "172.17.0.1", "scope": "global"}]}
+    ]"#;
+
+    #[test]
+    fn picks_ipv4_on_default_route_interface() {
+        let addresses = parse_ip_addr(SAMPLE);
+        assert_eq!(addresses.len(), 3);
+        let primary = primary_address(&addresses, Some("wlan0")).expect("primary");
+        assert_eq!(primary.address, "192.168.1.20");
+        assert_eq!(bar_text(primary), "192\n168\n1\n20");
+        assert_eq!(
+            primary_address(&addresses, Some("tun0")).map(|addr| addr.iface.as_str()),
+            Some("wlan0")
+        );
+        assert_eq!(bar_text(&addresses[0]), "v6");
+        assert!(parse_ip_addr("not json").is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+// Shared iwd D-Bus helpers for Wi-Fi station lookup, network listing, scanning, and connecting.
+// Used in place of NetworkManager on systems that run iwd on its own.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+
+pub const IWD_SERVICE: &str = "net.connman.iwd";
+const IWD_ROOT: &str = "/";
+const IWD_MANAGER_PATH: &str = "/net/connman/iwd";
+const OBJECT_MANAGER_IFACE: &str = "org.freedesktop.DBus.ObjectManager";
+const DEVICE_IFACE: &str = "net.connman.iwd.Device";
+const STATION_IFACE: &str = "net.connman.iwd.Station";
+const NETWORK_IFACE: &str = "net.connman.iwd.Network";
+const AGENT_MANAGER_IFACE: &str = "net.connman.iwd.AgentManager";
+/// Where the passphrase agent is served while a connect is in flight.
+const AGENT_PATH: &str = "/org/grelier/IwdAgent";
+
+/// Interfaces and properties of every object iwd exports.
+type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, HashMap<String, OwnedValue>>>;
+
+/// One network from a station's last scan.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IwdNetwork {
+    pub path: OwnedObjectPath,
+    pub ssid: String,
+    /// Security type: `open`, `psk`, `8021x`, or `wep`.
+    pub kind: String,
+    /// Whether iwd has a saved profile for it.
+    pub known: bool,
+    pub connected: bool,
+    /// Signal strength in percent.
+    pub strength: u8,
+}
+
+fn managed_objects(connection: &Connection) -> Option<ManagedObjects> {
+    Proxy::new(connection, IWD_SERVICE, IWD_ROOT, OBJECT_MANAGER_IFACE)
+        .ok()?
+        .call("GetManagedObjects", &())
+        .ok()
+}
+
+fn property<T>(properties: &HashMap<String, OwnedValue>, key: &str) -> Option<T>
+where
+    T: TryFrom<OwnedValue>,
+{
+    properties.get(key)?.try_clone().ok()?.try_into().ok()
+}
+
+/// The station object for network interface `iface`; iwd exports it on the device's path.
+fn station_for_iface(objects: &ManagedObjects, iface: &str) -> Option<OwnedObjectPath> {
+    objects.iter().find_map(|(path, interfaces)| {
+        let device = interfaces.get(DEVICE_IFACE)?;
+        (interfaces.contains_key(STATION_IFACE)
+            && property::<String>(device, "Name").as_deref() == Some(iface))
+        .then(|| path.clone())
+    })
+}
+
+pub fn station_path(connection: &Connection, iface: &str) -> Option<OwnedObjectPath> {
+    station_for_iface(&managed_objects(connection)?, iface)
+}
+
+/// iwd reports signal in hundredths of a dBm; -100 dBm and below is 0%, -50 dBm and above 100%.
+fn strength_percent(signal: i16) -> u8 {
+    (2 * (i32::from(signal) / 100 + 100)).clamp(0, 100) as u8
+}
+
+/// Networks the station sees, strongest first as iwd orders them.
+pub fn networks(connection: &Connection, station: &OwnedObjectPath) -> Vec<IwdNetwork> {
+    let Ok(station_proxy) = Proxy::new(connection, IWD_SERVICE, station, STATION_IFACE) else {
+        return Vec::new();
+    };
+    let ordered: Vec<(OwnedObjectPath, i16)> = match station_proxy.call("GetOrderedNetworks", &()) {
+        Ok(ordered) => ordered,
+        Err(_) => return Vec::new(),
+    };
+    ordered
+        .into_iter()
+        .filter_map(|(path, signal)| {
+            let proxy = Proxy::new(connection, IWD_SERVICE, path.clone(), NETWORK_IFACE).ok()?;
+            let ssid: String = proxy.get_property("Name").ok()?;
+            Some(IwdNetwork {
+                kind: proxy.get_property("Type").unwrap_or_default(),
+                // Unknown networks have no `KnownNetwork` property at all.
+                known: proxy
+                    .get_property::<OwnedObjectPath>("KnownNetwork")
+                    .is_ok(),
+                connected: proxy.get_property("Connected").unwrap_or(false),
+                strength: strength_percent(signal),
+                ssid,
+                path,
+            })
+        })
+        .collect()
+}
+
+/// SSID of the network `iface` is connected to.
+pub fn connected_ssid(connection: &Connection, iface: &str) -> Option<String> {
+    let station = station_path(connection, iface)?;
+    let network: OwnedObjectPath = Proxy::new(connection, IWD_SERVICE, &station, STATION_IFACE)
+        .ok()?
+        .get_property("ConnectedNetwork")
+        .ok()?;
+    Proxy::new(connection, IWD_SERVICE, &network, NETWORK_IFACE)
+        .ok()?
+        .get_property("Name")
+        .ok()
+}
+
+pub fn scan(connection: &Connection, station: &OwnedObjectPath) -> Result<(), String> {
+    Proxy::new(connection, IWD_SERVICE, station, STATION_IFACE)
+        .and_then(|proxy| proxy.call::<_, _, ()>("Scan", &()))
+        .map_err(|err| format!("scan failed: {err}"))
+}
+
+/// Agent answering iwd's passphrase request for the one network being joined.
+struct PassphraseAgent {
+    passphrase: Mutex<Option<String>>,
+}
+
+#[zbus::interface(name = "net.connman.iwd.Agent")]
+impl PassphraseAgent {
+    fn release(&self) {}
+
+    fn request_passphrase(&self, _network: OwnedObjectPath) -> zbus::fdo::Result<String> {
+        self.passphrase
+            .lock()
+            .ok()
+            .and_then(|mut passphrase| passphrase.take())
+            .ok_or_else(|| zbus::fdo::Error::Failed("net.connman.iwd.Agent.Error.Canceled".into()))
+    }
+
+    fn cancel(&self, _reason: String) {}
+}
+
+/// Join `network`. A passphrase is handed to iwd through a short-lived agent; iwd saves it
+/// once the connection succeeds.
+pub fn connect(
+    connection: &Connection,
+    network: &str,
+    passphrase: Option<&str>,
+) -> Result<(), String> {
+    let network = ObjectPath::try_from(network).map_err(|err| format!("invalid network: {err}"))?;
+    let Some(passphrase) = passphrase else {
+        return call_connect(connection, &network);
+    };
+
+    let agent = PassphraseAgent {
+        passphrase: Mutex::new(Some(passphrase.to_string())),
+    };
+    connection
+        .object_server()
+        .at(AGENT_PATH, agent)
+        .map_err(|err| format!("passphrase agent: {err}"))?;
+    let agent_path = ObjectPath::from_static_str_unchecked(AGENT_PATH);
+    let manager = Proxy::new(
+        connection,
+        IWD_SERVICE,
+        IWD_MANAGER_PATH,
+        AGENT_MANAGER_IFACE,
+    )
+    .map_err(|err| format!("passphrase agent: {err}"))?;
+    let result = manager
+        .call::<_, _, ()>("RegisterAgent", &(&agent_path,))
+        .map_err(|err| format!("passphrase agent: {err}"))
+        .and_then(|()| {
+            let result = call_connect(connection, &network);
+            let _ = manager.call::<_, _, ()>("UnregisterAgent", &(&agent_path,));
+            result
+        });
+    let _ = connection
+        .object_server()
+        .remove::<PassphraseAgent, _>(AGENT_PATH);
+    result
+}
+
+fn call_connect(connection: &Connection, network: &ObjectPath<'_>) -> Result<(), String> {
+    Proxy::new(connection, IWD_SERVICE, network, NETWORK_IFACE)
+        .and_then(|proxy| proxy.call::<_, _, ()>("Connect", &()))
+        .map_err(|err| format!("connect failed: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Value;
+
+    fn object(interfaces: &[(&str, Option<&str>)]) -> HashMap<String, HashMap<String, OwnedValue>> {
+        interfaces
+            .iter()
+            .map(|(interface, name)| {
+                let properties = name
+                    .map(|name| {
+                        HashMap::from([(
+                            "Name".to_string(),
+                            OwnedValue::try_from(Value::from(name)).expect("owned value"),
+                        )])
+                    })
+                    .unwrap_or_default();
+                (interface.to_string(), properties)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_stations_and_scales_signal() {
+        let station = OwnedObjectPath::try_from("/net/connman/iwd/0/4").expect("path");
+        let objects: ManagedObjects = HashMap::from([
+            (
+                OwnedObjectPath::try_from("/net/connman/iwd/0/5").expect("path"),
+                object(&[(DEVICE_IFACE, Some("wlan1"))]),
+            ),
+            (
+                station.clone(),
+                object(&[(DEVICE_IFACE, Some("wlan0")), (STATION_IFACE, None)]),
+            ),
+        ]);
+        assert_eq!(station_for_iface(&objects, "wlan0"), Some(station));
+        // A device in access point mode has no station.
+        assert_eq!(station_for_iface(&objects, "wlan1"), None);
+
+        assert_eq!(strength_percent(-4500), 100);
+        assert_eq!(strength_percent(-6400), 72);
+        assert_eq!(strength_percent(-10500), 0);
+    }
+}
@@ -0,0 +1,454 @@
+// PipeWire-JACK gauge: current quantum and sample rate, xrun counting, and quantum presets.
+// Consumes Settings: grelier.gauge.jack.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeEventSource, GaugeMenu, GaugeMenuItem, GaugeReadyNotify, GaugeRegistrar, GaugeStop,
+    MenuSelectAction,
+};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use std::collections::VecDeque;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
+
+settings::typed_settings! {
+    struct JackSettings {
+        quantum_presets: String = ("grelier.gauge.jack.quantum_presets", "64,128,256,512,1024"),
+        poll_interval_secs: u64 = ("grelier.gauge.jack.poll_interval_secs", "5", 1..=3600),
+        xrun_window_secs: u64 = ("grelier.gauge.jack.xrun_window_secs", "60", 1..=3600),
+    }
+}
+
+/// Clock settings from the PipeWire `settings` metadata object.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ClockSettings {
+    quantum: u32,
+    rate: u32,
+    /// Forced quantum; 0 means the graph picks its own.
+    force_quantum: u32,
+}
+
+impl ClockSettings {
+    fn effective_quantum(self) -> u32 {
+        if self.force_quantum > 0 {
+            self.force_quantum
+        } else {
+            self.quantum
+        }
+    }
+}
+
+/// Parse `pw-metadata -n settings` lines such as `update: id:0 key:'clock.rate' value:'48000' type:''`.
+fn parse_clock_settings(output: &str) -> ClockSettings {
+    let quoted = |line: &str, field: &str| -> Option<String> {
+        let start = line.find(field)? + field.len();
+        let rest = &line[start..];
+        Some(rest[..rest.find('\'')?].to_string())
+    };
+    let mut clock = ClockSettings::default();
+    for line in output.lines() {
+        let (Some(key), Some(value)) = (quoted(line, "key:'"), quoted(line, "value:'")) else {
+            continue;
+        };
+        let Ok(value) = value.parse::<u32>() else {
+            continue;
+        };
+        match key.as_str() {
+            "clock.quantum" => clock.quantum = value,
+            "clock.rate" => clock.rate = value,
+            "clock.force-quantum" => clock.force_quantum = value,
+            _ => {}
+        }
+    }
+    clock
+}
+
+/// Graph timing sampled from the last `pw-top` batch iteration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct GraphSample {
+    /// Quantum and rate of the first running driver, if any.
+    driver: Option<(u32, u32)>,
+    /// Sum of the per-node ERR (xrun) counters.
+    xruns: u64,
+}
+
+fn parse_pw_top(output: &str) -> GraphSample {
+    let mut sample = GraphSample::default();
+    for line in output.lines() {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        if columns.get(1) == Some(&"ID") {
+            // Each iteration starts with a header row; keep only the last one.
+            sample = GraphSample::default();
+            continue;
+        }
+        if columns.len() < 11 {
+            continue;
+        }
+        if let Ok(errors) = columns[8].parse::<u64>() {
+            sample.xruns += errors;
+        }
+        let is_driver = !line.contains(" + ");
+        if columns[0] == "R"
+            && is_driver
+            && sample.driver.is_none()
+            && let (Ok(quantum), Ok(rate)) = (columns[2].parse(), columns[3].parse())
+            && quantum > 0
+        {
+            sample.driver = Some((quantum, rate));
+        }
+    }
+    sample
+}
+
+/// Turns cumulative xrun counters into a count of recent xruns.
+#[derive(Debug, Default)]
+struct XrunTracker {
+    last_total: Option<u64>,
+    events: VecDeque<(Instant, u64)>,
+    total: u64,
+}
+
+impl XrunTracker {
+    fn record(&mut self, now: Instant, counter: u64) {
+        match self.last_total {
+            Some(last) if counter > last => {
+                self.events.push_back((now, counter - last));
+                self.total += counter - last;
+            }
+            // First sample, or nodes were recreated and counters restarted.
+            _ => {}
+        }
+        self.last_total = Some(counter);
+    }
+
+    fn recent(&mut self, now: Instant, window: Duration) -> u64 {
+        while let Some((at, _)) = self.events.front() {
+            if now.duration_since(*at) > window {
+                self.events.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.events.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// State shared between the sampler and the gauge.
+#[derive(Debug, Default)]
+struct JackState {
+    clock: ClockSettings,
+    sample: GraphSample,
+    xruns: XrunTracker,
+    error: Option<String>,
+}
+
+fn run_tool(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|err| format!("{program}: {err}"))?;
+    if !output.status.success() {
+        return Err(format!("{program} exited with {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn set_force_quantum(quantum: u32) -> Result<(), String> {
+    run_tool(
+        "pw-metadata",
+        &[
+            "-n",
+            "settings",
+            "0",
+            "clock.force-quantum",
+            &quantum.to_string(),
+        ],
+    )
+    .map(|_| ())
+}
+
+/// Event source that samples PipeWire off the gauge thread; `pw-top` takes a second per run.
+struct JackSampler {
+    interval: Duration,
+    state: Arc<Mutex<JackState>>,
+}
+
+impl GaugeEventSource for JackSampler {
+    fn run(self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
+        loop {
+            let clock =
+                run_tool("pw-metadata", &["-n", "settings"]).map(|out| parse_clock_settings(&out));
+            let sample = run_tool("pw-top", &["-b", "-n", "2"]).map(|out| parse_pw_top(&out));
+            {
+                let Ok(mut state) = self.state.lock() else {
+                    return;
+                };
+                match (clock, sample) {
+                    (Ok(clock), Ok(sample)) => {
+                        state.clock = clock;
+                        state.sample = sample;
+                        state.xruns.record(Instant::now(), sample.xruns);
+                        state.error = None;
+                    }
+                    (Err(err), _) | (_, Err(err)) => state.error = Some(err),
+                }
+            }
+            notify("jack");
+            if stop.sleep(self.interval) {
+                return;
+            }
+        }
+    }
+}
+
+fn parse_presets(raw: &str) -> Vec<u32> {
+    let mut presets: Vec<u32> = raw
+        .split(',')
+        .filter_map(|preset| preset.trim().parse().ok())
+        .filter(|preset| *preset > 0)
+        .collect();
+    presets.sort_unstable();
+    presets.dedup();
+    presets
+}
+
+fn quantum_menu(presets: &[u32], force_quantum: u32, on_select: MenuSelectAction) -> GaugeMenu {
+    let mut items = vec![GaugeMenuItem {
+        id: "0".to_string(),
+        label: "Default".to_string(),
+        selected: force_quantum == 0,
+        exec: None,
+    }];
+    items.extend(presets.iter().map(|preset| GaugeMenuItem {
+        id: preset.to_string(),
+        label: preset.to_string(),
+        selected: *preset == force_quantum,
+        exec: None,
+    }));
+    GaugeMenu {
+        title: "Quantum".to_string(),
+        items,
+        on_select: Some(on_select),
+        slider: None,
+    }
+}
+
+/// Gauge that shows the PipeWire graph quantum and flags recent xruns.
+struct JackGauge {
+    /// Quantum presets offered in the menu.
+    presets: Vec<u32>,
+    /// How far back xruns count towards danger attention.
+    xrun_window: Duration,
+    /// Latest PipeWire state written by the sampler.
+    state: Arc<Mutex<JackState>>,
+    /// Sampler handed to the work manager on registration.
+    sampler: Option<JackSampler>,
+    /// Sender used by menu callbacks to request a new forced quantum.
+    command_tx: mpsc::Sender<u32>,
+    /// Receiver drained on each run to apply requested quanta.
+    command_rx: mpsc::Receiver<u32>,
+    /// Notifier used to request an immediate scheduler wake-up after actions.
+    ready_notify: Option<GaugeReadyNotify>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for JackGauge {
+    fn id(&self) -> &'static str {
+        "jack"
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(sampler) = self.sampler.take() {
+            registrar.add_event_source(Box::new(sampler));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        // Re-run when the xrun window expires so danger attention clears on time.
+        self.next_deadline = now + self.xrun_window;
+
+        let Ok(mut state) = self.state.lock() else {
+            return None;
+        };
+        while let Ok(quantum) = self.command_rx.try_recv() {
+            match set_force_quantum(quantum) {
+                Ok(()) => state.clock.force_quantum = quantum,
+                Err(err) => log::error!("jack gauge: failed to set quantum to {quantum}: {err}"),
+            }
+        }
+
+        let recent = state.xruns.recent(now, self.xrun_window);
+        let (quantum, rate) = state
+            .sample
+            .driver
+            .unwrap_or((state.clock.effective_quantum(), state.clock.rate));
+        let (display, lines) = match &state.error {
+            Some(err) => (
+                GaugeDisplay::Error,
+                vec![format!("PipeWire unavailable: {err}")],
+            ),
+            None => {
+                let mut lines = vec![
+                    format!("Quantum: {quantum}"),
+                    format!("Sample rate: {rate} Hz"),
+                ];
+                if rate > 0 {
+                    lines.push(format!(
+                        "Latency: {:.1} ms",
+                        quantum as f32 * 1000.0 / rate as f32
+                    ));
+                }
+                lines.push(format!(
+                    "Xruns: {} ({recent} in the last {}s)",
+                    state.xruns.total,
+                    self.xrun_window.as_secs()
+                ));
+                (
+                    GaugeDisplay::Value {
+                        value: GaugeValue::Text(quantum.to_string()),
+                        attention: if recent > 0 {
+                            GaugeValueAttention::Danger
+                        } else {
+                            GaugeValueAttention::Nominal
+                        },
+                    },
+                    lines,
+                )
+            }
+        };
+
+        let command_tx = self.command_tx.clone();
+        let ready_notify = self.ready_notify.clone();
+        let on_select: MenuSelectAction = Arc::new(move |item: String| {
+            if let Ok(quantum) = item.parse::<u32>() {
+                let _ = command_tx.send(quantum);
+                if let Some(ready_notify) = &ready_notify {
+                    ready_notify("jack");
+                }
+            }
+        });
+
+        Some(GaugeModel {
+            id: "jack",
+            icon: svg_asset("waveform.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "PipeWire".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                right_click: GaugePointerInteraction {
+                    menu: Some(quantum_menu(
+                        &self.presets,
+                        state.clock.force_quantum,
+                        on_select,
+                    )),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = JackSettings::load();
+    let state = Arc::new(Mutex::new(JackState::default()));
+    let (command_tx, command_rx) = mpsc::channel();
+    Box::new(JackGauge {
+        presets: parse_presets(&settings.quantum_presets),
+        xrun_window: Duration::from_secs(settings.xrun_window_secs),
+        sampler: Some(JackSampler {
+            interval: Duration::from_secs(settings.poll_interval_secs),
+            state: Arc::clone(&state),
+        }),
+        state,
+        command_tx,
+        command_rx,
+        ready_notify: None,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    JackSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "jack",
+        description: "PipeWire-JACK gauge showing quantum, sample rate, and recent xruns.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_metadata_and_pw_top_output() {
+        let metadata = "Found \"settings\" metadata 31\n\
+            update: id:0 key:'log.level' value:'2' type:''\n\
+            update: id:0 key:'clock.rate' value:'48000' type:''\n\
+            update: id:0 key:'clock.quantum' value:'1024' type:''\n\
+            update: id:0 key:'clock.force-quantum' value:'256' type:''\n";
+        let clock = parse_clock_settings(metadata);
+        assert_eq!(clock.rate, 48000);
+        assert_eq!(clock.effective_quantum(), 256);
+
+        let top = "S   ID  QUANT   RATE    WAIT    BUSY   W/Q   B/Q  ERR FORMAT           NAME\n\
+            R   30   1024  48000  27.3us  13.1us  0.00  0.00    9    S32LE 2 48000 alsa_output\n\
+            S   ID  QUANT   RATE    WAIT    BUSY   W/Q   B/Q  ERR FORMAT           NAME\n\
+            S   28      0      0    ---     ---   ---   ---     0                  Dummy-Driver\n\
+            R   30    256  48000  27.3us  13.1us  0.00  0.00    3    S32LE 2 48000 alsa_output\n\
+            R   75    256  48000   4.0us   2.1us  0.00  0.00    2    F32LE 2 48000  + ardour\n";
+        assert_eq!(
+            parse_pw_top(top),
+            GraphSample {
+                driver: Some((256, 48000)),
+                xruns: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn counts_only_recent_xruns() {
+        let start = Instant::now();
+        let window = Duration::from_secs(60);
+        let mut tracker = XrunTracker::default();
+        tracker.record(start, 40);
+        assert_eq!(tracker.recent(start, window), 0);
+        tracker.record(start + Duration::from_secs(5), 43);
+        tracker.record(start + Duration::from_secs(10), 2);
+        tracker.record(start + Duration::from_secs(20), 3);
+        assert_eq!(tracker.recent(start + Duration::from_secs(30), window), 4);
+        assert_eq!(tracker.recent(start + Duration::from_secs(70), window), 1);
+        assert_eq!(tracker.total, 4);
+        assert_eq!(parse_presets("512, 64,x,64,0"), vec![64, 512]);
+    }
+}
@@ -0,0 +1,368 @@
+// Journal gauge: follows the systemd journal for error-priority messages and badges how many
+// arrived in the last few minutes, as an early warning for failing hardware or services.
+// Consumes Settings: grelier.gauge.journal.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    AsyncGaugeEventSource, Gauge, GaugeDisplay, GaugeFuture, GaugeInteractionModel, GaugeModel,
+    GaugePointerInteraction, GaugeRegistrar, GaugeValue, GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use async_io::Timer;
+use async_process::{Child, ChildStdout, Command, Stdio};
+use futures_lite::StreamExt;
+use futures_lite::io::{AsyncBufReadExt, BufReader, Lines};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Wait before restarting `journalctl` after it exits.
+const RESTART_DELAY: Duration = Duration::from_secs(30);
+/// How often the count is recomputed so old messages age out of the window.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+settings::typed_settings! {
+    struct JournalSettings {
+        path: String = ("grelier.gauge.journal.path", "journalctl"),
+        priority: String = ("grelier.gauge.journal.priority", "err"),
+        units: String = ("grelier.gauge.journal.units", ""),
+        window_minutes: u64 = ("grelier.gauge.journal.window_minutes", "10", 1..=1440),
+        warning_count: u64 = ("grelier.gauge.journal.warning_count", "1", 1..=10000),
+        danger_count: u64 = ("grelier.gauge.journal.danger_count", "10", 1..=10000),
+        max_messages: usize = ("grelier.gauge.journal.max_messages", "50", 1..=1000),
+    }
+}
+
+/// One journal message as shown in the dialog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct JournalEntry {
+    /// Microseconds since the Unix epoch.
+    at_usec: u64,
+    /// Unit or syslog identifier that logged it.
+    source: String,
+    message: String,
+}
+
+/// Messages read by the follower, newest last.
+#[derive(Debug, Default)]
+struct JournalState {
+    entries: VecDeque<JournalEntry>,
+    error: Option<String>,
+}
+
+/// `MESSAGE` is a string, or an array of bytes when it is not valid UTF-8.
+fn field_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                .collect();
+            Some(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        _ => None,
+    }
+}
+
+/// Parse one line of `journalctl -o json`.
+fn parse_entry(line: &str) -> Option<JournalEntry> {
+    let record: Value = serde_json::from_str(line).ok()?;
+    let at_usec = record["__REALTIME_TIMESTAMP"].as_str()?.parse().ok()?;
+    let source = ["_SYSTEMD_UNIT", "SYSLOG_IDENTIFIER", "_COMM"]
+        .iter()
+        .find_map(|key| field_text(&record[key]))
+        .unwrap_or_else(|| "kernel".to_string());
+    let message = field_text(&record["MESSAGE"])?;
+    Some(JournalEntry {
+        at_usec,
+        source,
+        message: message.trim().to_string(),
+    })
+}
+
+/// Arguments for `journalctl`, starting at the beginning of the window so the count is right
+/// from the first run.
+fn journalctl_args(priority: &str, units: &str, window_minutes: u64) -> Vec<String> {
+    let mut args = vec![
+        "--follow".to_string(),
+        "--output=json".to_string(),
+        format!("--priority={priority}"),
+        format!("--since=-{window_minutes}min"),
+    ];
+    args.extend(
+        units
+            .split(',')
+            .map(str::trim)
+            .filter(|unit| !unit.is_empty())
+            .map(|unit| format!("--unit={unit}")),
+    );
+    args
+}
+
+/// A running `journalctl` and its output.
+type Journalctl = (Child, Lines<BufReader<ChildStdout>>);
+
+/// Async event source that follows `journalctl` and wakes the gauge on each message.
+struct JournalFollower {
+    path: String,
+    args: Vec<String>,
+    max_messages: usize,
+    state: Arc<Mutex<JournalState>>,
+    /// `None` before the first start and after `journalctl` exits.
+    running: Option<Journalctl>,
+    /// Whether `journalctl` was started before, so a restart waits `RESTART_DELAY` first.
+    started: bool,
+}
+
+impl JournalFollower {
+    fn spawn(&self) -> Result<Journalctl, String> {
+        let mut child = Command::new(&self.path)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| format!("failed to run {}: {err}", self.path))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| format!("{} has no output", self.path))?;
+        // Restarting reads the whole window again, so start from an empty list.
+        if let Ok(mut state) = self.state.lock() {
+            state.entries.clear();
+            state.error = None;
+        }
+        Ok((child, BufReader::new(stdout).lines()))
+    }
+
+    /// Wait for the next message, starting `journalctl` when it is not running.
+    async fn next_message(&mut self) -> Result<(), String> {
+        let (mut child, mut lines) = match self.running.take() {
+            Some(running) => running,
+            None => {
+                if self.started {
+                    Timer::after(RESTART_DELAY).await;
+                }
+                self.started = true;
+                self.spawn()?
+            }
+        };
+        while let Some(Ok(line)) = lines.next().await {
+            let Some(entry) = parse_entry(&line) else {
+                continue;
+            };
+            if let Ok(mut state) = self.state.lock() {
+                state.entries.push_back(entry);
+                while state.entries.len() > self.max_messages {
+                    state.entries.pop_front();
+                }
+            }
+            self.running = Some((child, lines));
+            return Ok(());
+        }
+        let status = child
+            .status()
+            .await
+            .map_err(|err| format!("{} failed: {err}", self.path))?;
+        Err(format!("{} exited with {status}", self.path))
+    }
+}
+
+impl AsyncGaugeEventSource for JournalFollower {
+    fn interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Each poll waits for the next message, however long that takes.
+    fn timeout(&self) -> Duration {
+        Duration::MAX
+    }
+
+    fn poll(&mut self) -> GaugeFuture<'_, bool> {
+        Box::pin(async move {
+            if let Err(err) = self.next_message().await {
+                log::warn!("journal gauge: {err}");
+                if let Ok(mut state) = self.state.lock() {
+                    state.error = Some(err);
+                }
+            }
+            true
+        })
+    }
+}
+
+fn now_usec() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+/// Messages logged within `window` before `now_usec`.
+fn count_in_window(entries: &VecDeque<JournalEntry>, now_usec: u64, window: Duration) -> u64 {
+    let since = now_usec.saturating_sub(window.as_micros() as u64);
+    entries
+        .iter()
+        .filter(|entry| entry.at_usec >= since)
+        .count() as u64
+}
+
+/// Newest first, as "HH:MM:SS source: message".
+fn info_lines(entries: &VecDeque<JournalEntry>) -> Vec<String> {
+    if entries.is_empty() {
+        return vec!["No errors logged".to_string()];
+    }
+    entries
+        .iter()
+        .rev()
+        .map(|entry| {
+            let time = chrono::DateTime::from_timestamp_micros(entry.at_usec as i64)
+                .map(|at| {
+                    at.with_timezone(&chrono::Local)
+                        .format("%H:%M:%S")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            format!("{time} {}: {}", entry.source, entry.message)
+        })
+        .collect()
+}
+
+/// Gauge showing how many error messages the journal logged recently.
+struct JournalGauge {
+    state: Arc<Mutex<JournalState>>,
+    window: Duration,
+    warning_count: u64,
+    danger_count: u64,
+    /// Journal follower handed to the work manager on registration.
+    follower: Option<JournalFollower>,
+    next_deadline: Instant,
+}
+
+impl Gauge for JournalGauge {
+    fn id(&self) -> &'static str {
+        "journal"
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(follower) = self.follower.take() {
+            registrar.add_async_event_source(Box::new(follower));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + REFRESH_INTERVAL;
+        let state = self.state.lock().ok()?;
+
+        let (display, lines) = match &state.error {
+            Some(err) if state.entries.is_empty() => (GaugeDisplay::Error, vec![err.clone()]),
+            _ => {
+                let count = count_in_window(&state.entries, now_usec(), self.window);
+                let attention = if count >= self.danger_count {
+                    GaugeValueAttention::Danger
+                } else if count >= self.warning_count {
+                    GaugeValueAttention::Warning
+                } else {
+                    GaugeValueAttention::Nominal
+                };
+                (
+                    GaugeDisplay::Value {
+                        value: GaugeValue::Text(count.to_string()),
+                        attention,
+                    },
+                    info_lines(&state.entries),
+                )
+            }
+        };
+
+        Some(GaugeModel {
+            id: "journal",
+            icon: svg_asset("journal.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: format!("Journal errors ({} min)", self.window.as_secs() / 60),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = JournalSettings::load();
+    let state = Arc::new(Mutex::new(JournalState::default()));
+    Box::new(JournalGauge {
+        state: Arc::clone(&state),
+        window: Duration::from_secs(settings.window_minutes * 60),
+        warning_count: settings.warning_count,
+        danger_count: settings.danger_count,
+        follower: Some(JournalFollower {
+            args: journalctl_args(
+                settings.priority.trim(),
+                &settings.units,
+                settings.window_minutes,
+            ),
+            path: settings.path,
+            max_messages: settings.max_messages,
+            state,
+            running: None,
+            started: false,
+        }),
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    JournalSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "journal",
+        description: "Journal gauge counting recent error messages in the systemd journal.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_entries_and_counts_the_window() {
+        let line = r#"{"__REALTIME_TIMESTAMP":"1700000000000000","PRIORITY":"3","_SYSTEMD_UNIT":"nvme.service","MESSAGE":"I/O error"}"#;
+        let bytes = r#"{"__REALTIME_TIMESTAMP":"1700000500000000","SYSLOG_IDENTIFIER":"kernel","MESSAGE":[100,105,115,107]}"#;
+        let first = parse_entry(line).expect("entry");
+        assert_eq!(first.source, "nvme.service");
+        assert_eq!(first.message, "I/O error");
+        let second = parse_entry(bytes).expect("entry");
+        assert_eq!(second.message, "disk");
+        assert_eq!(parse_entry("not json"), None);
+
+        let entries = VecDeque::from([first, second]);
+        let now = 1_700_000_600_000_000;
+        assert_eq!(count_in_window(&entries, now, Duration::from_secs(300)), 1);
+        assert_eq!(count_in_window(&entries, now, Duration::from_secs(900)), 2);
+        assert_eq!(
+            journalctl_args("err", "nvme.service, ", 10)[3..],
+            ["--since=-10min", "--unit=nvme.service"]
+        );
+    }
+}
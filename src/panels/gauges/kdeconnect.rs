@@ -0,0 +1,432 @@
+// Companion phone gauge over KDE Connect: battery, notification count, and remote actions.
+// Consumes Settings: grelier.gauge.kdeconnect.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::{icon_quantity, svg_asset};
+use crate::panels::gauges::gauge::{Gauge, GaugeReadyNotify};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel,
+    GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
+};
+use crate::panels::gauges::gauge_dependencies;
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant};
+use zbus::blocking::{Connection, Proxy};
+
+const SERVICE: &str = "org.kde.kdeconnect";
+const DAEMON_PATH: &str = "/modules/kdeconnect";
+const DAEMON_IFACE: &str = "org.kde.kdeconnect.daemon";
+const DEVICE_IFACE: &str = "org.kde.kdeconnect.device";
+const BATTERY_IFACE: &str = "org.kde.kdeconnect.device.battery";
+const NOTIFICATIONS_IFACE: &str = "org.kde.kdeconnect.device.notifications";
+const PING_IFACE: &str = "org.kde.kdeconnect.device.ping";
+const FIND_IFACE: &str = "org.kde.kdeconnect.device.findmyphone";
+const CLIPBOARD_PLUGIN: &str = "kdeconnect_clipboard";
+
+const PING_ITEM: &str = "ping";
+const FIND_ITEM: &str = "find";
+const CLIPBOARD_ITEM: &str = "clipboard";
+
+settings::typed_settings! {
+    struct KdeConnectSettings {
+        device: String = ("grelier.gauge.kdeconnect.device", ""),
+        warning_percent: u8 = ("grelier.gauge.kdeconnect.warning_percent", "20", 0..=100),
+        danger_percent: u8 = ("grelier.gauge.kdeconnect.danger_percent", "10", 0..=100),
+        poll_interval_secs: u64 = ("grelier.gauge.kdeconnect.poll_interval_secs", "30", 1..=3600),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PhoneCommand {
+    Ping,
+    Find,
+    ToggleClipboard,
+}
+
+impl PhoneCommand {
+    fn from_item(id: &str) -> Option<Self> {
+        match id {
+            PING_ITEM => Some(PhoneCommand::Ping),
+            FIND_ITEM => Some(PhoneCommand::Find),
+            CLIPBOARD_ITEM => Some(PhoneCommand::ToggleClipboard),
+            _ => None,
+        }
+    }
+}
+
+/// State of the selected paired device; plugin-backed fields are `None` when the plugin is off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PhoneState {
+    id: String,
+    name: String,
+    charge: Option<u8>,
+    charging: bool,
+    notifications: Option<usize>,
+    clipboard_sync: bool,
+}
+
+fn device_path(id: &str, plugin: Option<&str>) -> String {
+    match plugin {
+        Some(plugin) => format!("{DAEMON_PATH}/devices/{id}/{plugin}"),
+        None => format!("{DAEMON_PATH}/devices/{id}"),
+    }
+}
+
+fn proxy<'a>(connection: &'a Connection, path: String, iface: &'a str) -> Option<Proxy<'a>> {
+    Proxy::new(connection, SERVICE, path, iface).ok()
+}
+
+/// Reachable, paired device ids with their names.
+fn reachable_devices(connection: &Connection) -> Result<Vec<(String, String)>, String> {
+    let daemon = Proxy::new(connection, SERVICE, DAEMON_PATH, DAEMON_IFACE)
+        .map_err(|err| format!("kdeconnectd proxy: {err}"))?;
+    let ids: Vec<String> = daemon
+        .call("devices", &(true, true))
+        .map_err(|err| format!("kdeconnectd not running: {err}"))?;
+    Ok(ids
+        .into_iter()
+        .map(|id| {
+            let name = proxy(connection, device_path(&id, None), DEVICE_IFACE)
+                .and_then(|device| device.get_property::<String>("name").ok())
+                .unwrap_or_else(|| id.clone());
+            (id, name)
+        })
+        .collect())
+}
+
+/// The configured device, matched by id or name, or the first one when none is configured.
+fn select_device<'a>(
+    devices: &'a [(String, String)],
+    wanted: &str,
+) -> Option<&'a (String, String)> {
+    let wanted = wanted.trim();
+    if wanted.is_empty() {
+        return devices.first();
+    }
+    devices
+        .iter()
+        .find(|(id, name)| id == wanted || name.eq_ignore_ascii_case(wanted))
+}
+
+fn read_phone(connection: &Connection, id: &str, name: &str) -> PhoneState {
+    let battery = proxy(connection, device_path(id, Some("battery")), BATTERY_IFACE);
+    let charge = battery
+        .as_ref()
+        .and_then(|battery| battery.get_property::<i32>("charge").ok())
+        // The plugin reports -1 before the phone has sent a reading.
+        .and_then(|charge| u8::try_from(charge).ok());
+    let charging = battery
+        .as_ref()
+        .and_then(|battery| battery.get_property::<bool>("isCharging").ok())
+        .unwrap_or(false);
+    let notifications = proxy(
+        connection,
+        device_path(id, Some("notifications")),
+        NOTIFICATIONS_IFACE,
+    )
+    .and_then(|notifications| {
+        notifications
+            .call::<_, _, Vec<String>>("activeNotifications", &())
+            .ok()
+    })
+    .map(|active| active.len());
+    let clipboard_sync = proxy(connection, device_path(id, None), DEVICE_IFACE)
+        .and_then(|device| {
+            device
+                .call::<_, _, bool>("isPluginEnabled", &(CLIPBOARD_PLUGIN,))
+                .ok()
+        })
+        .unwrap_or(false);
+
+    PhoneState {
+        id: id.to_string(),
+        name: name.to_string(),
+        charge,
+        charging,
+        notifications,
+        clipboard_sync,
+    }
+}
+
+fn run_command(connection: &Connection, phone: &PhoneState, command: PhoneCommand) {
+    let result = match command {
+        PhoneCommand::Ping => proxy(connection, device_path(&phone.id, Some("ping")), PING_IFACE)
+            .map(|ping| ping.call::<_, _, ()>("sendPing", &())),
+        PhoneCommand::Find => proxy(
+            connection,
+            device_path(&phone.id, Some("findmyphone")),
+            FIND_IFACE,
+        )
+        .map(|find| find.call::<_, _, ()>("ring", &())),
+        PhoneCommand::ToggleClipboard => {
+            proxy(connection, device_path(&phone.id, None), DEVICE_IFACE).map(|device| {
+                device.call::<_, _, ()>(
+                    "setPluginEnabled",
+                    &(CLIPBOARD_PLUGIN, !phone.clipboard_sync),
+                )
+            })
+        }
+    };
+    match result {
+        Some(Ok(())) => {}
+        Some(Err(err)) => log::warn!("kdeconnect gauge: {command:?} failed: {err}"),
+        None => log::warn!(
+            "kdeconnect gauge: {command:?} unavailable for {}",
+            phone.name
+        ),
+    }
+}
+
+/// Low-battery attention; a phone on the charger is never flagged.
+fn battery_attention(charge: u8, charging: bool, warning: u8, danger: u8) -> GaugeValueAttention {
+    if charging {
+        GaugeValueAttention::Nominal
+    } else if charge <= danger {
+        GaugeValueAttention::Danger
+    } else if charge <= warning {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+fn phone_lines(phone: &PhoneState) -> Vec<String> {
+    let mut lines = vec![format!("Device: {}", phone.name)];
+    lines.push(match (phone.charge, phone.charging) {
+        (Some(charge), true) => format!("Battery: {charge}% (charging)"),
+        (Some(charge), false) => format!("Battery: {charge}%"),
+        (None, _) => "Battery: unknown".to_string(),
+    });
+    if let Some(count) = phone.notifications {
+        lines.push(format!("Notifications: {count}"));
+    }
+    lines.push(format!(
+        "Clipboard sync: {}",
+        if phone.clipboard_sync { "on" } else { "off" }
+    ));
+    lines
+}
+
+fn phone_menu(phone: &PhoneState, on_select: MenuSelectAction) -> GaugeMenu {
+    let item = |id: &str, label: &str, selected: bool| GaugeMenuItem {
+        id: id.to_string(),
+        label: label.to_string(),
+        selected,
+        exec: None,
+    };
+    GaugeMenu {
+        title: phone.name.clone(),
+        items: vec![
+            item(PING_ITEM, "Ping", false),
+            item(FIND_ITEM, "Ring phone", false),
+            item(CLIPBOARD_ITEM, "Clipboard sync", phone.clipboard_sync),
+        ],
+        on_select: Some(on_select),
+        slider: None,
+    }
+}
+
+/// Gauge showing the paired phone's battery, with ping, ring, and clipboard sync in its menu.
+struct KdeConnectGauge {
+    settings: KdeConnectSettings,
+    /// Sender cloned into menu callbacks to queue phone actions.
+    command_tx: mpsc::Sender<PhoneCommand>,
+    /// Receiver drained on each run to apply queued actions.
+    command_rx: mpsc::Receiver<PhoneCommand>,
+    /// Notifier used to request an immediate scheduler wake-up after actions.
+    ready_notify: Option<GaugeReadyNotify>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl KdeConnectGauge {
+    fn menu_action(&self) -> MenuSelectAction {
+        let command_tx = self.command_tx.clone();
+        let ready_notify = self.ready_notify.clone();
+        Arc::new(move |id: String| {
+            if let Some(command) = PhoneCommand::from_item(&id) {
+                let _ = command_tx.send(command);
+                if let Some(ready_notify) = &ready_notify {
+                    ready_notify("kdeconnect");
+                }
+            }
+        })
+    }
+
+    fn phone(&mut self, connection: &Connection) -> Result<Option<PhoneState>, String> {
+        let devices = reachable_devices(connection)?;
+        let Some((id, name)) = select_device(&devices, &self.settings.device) else {
+            return Ok(None);
+        };
+        let mut phone = read_phone(connection, id, name);
+        let mut acted = false;
+        while let Ok(command) = self.command_rx.try_recv() {
+            run_command(connection, &phone, command);
+            acted = true;
+        }
+        if acted {
+            phone = read_phone(connection, id, name);
+        }
+        Ok(Some(phone))
+    }
+}
+
+impl Gauge for KdeConnectGauge {
+    fn id(&self) -> &'static str {
+        "kdeconnect"
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + Duration::from_secs(self.settings.poll_interval_secs);
+
+        let phone = Connection::session()
+            .map_err(|err| format!("session bus: {err}"))
+            .and_then(|connection| self.phone(&connection));
+        // Actions queued while no device was reachable are dropped.
+        while self.command_rx.try_recv().is_ok() {}
+
+        let (display, lines, menu) = match phone {
+            Ok(Some(phone)) => {
+                let display = match phone.charge {
+                    Some(charge) => GaugeDisplay::Value {
+                        value: GaugeValue::Svg(icon_quantity(charge as f32 / 100.0)),
+                        attention: battery_attention(
+                            charge,
+                            phone.charging,
+                            self.settings.warning_percent,
+                            self.settings.danger_percent,
+                        ),
+                    },
+                    None => GaugeDisplay::Empty,
+                };
+                let menu = phone_menu(&phone, self.menu_action());
+                (display, phone_lines(&phone), Some(menu))
+            }
+            Ok(None) => (
+                GaugeDisplay::Empty,
+                vec!["No reachable paired device".to_string()],
+                None,
+            ),
+            Err(err) => {
+                let mut lines = vec![err];
+                lines.extend(gauge_dependencies::missing_hints("kdeconnect"));
+                (GaugeDisplay::Error, lines, None)
+            }
+        };
+
+        Some(GaugeModel {
+            id: "kdeconnect",
+            icon: svg_asset("phone.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "KDE Connect".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                right_click: GaugePointerInteraction {
+                    menu,
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let (command_tx, command_rx) = mpsc::channel();
+    Box::new(KdeConnectGauge {
+        settings: KdeConnectSettings::load(),
+        command_tx,
+        command_rx,
+        ready_notify: None,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    KdeConnectSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "kdeconnect",
+        description: "KDE Connect gauge showing a paired phone's battery and notifications, with ping, ring, and clipboard sync actions.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_device_and_flags_low_battery() {
+        let devices = vec![
+            ("a1b2".to_string(), "Pixel".to_string()),
+            ("c3d4".to_string(), "Tablet".to_string()),
+        ];
+        assert_eq!(
+            select_device(&devices, "").map(|d| d.0.as_str()),
+            Some("a1b2")
+        );
+        assert_eq!(
+            select_device(&devices, "tablet").map(|d| d.0.as_str()),
+            Some("c3d4")
+        );
+        assert_eq!(select_device(&devices, "watch"), None);
+
+        assert_eq!(
+            battery_attention(8, false, 20, 10),
+            GaugeValueAttention::Danger
+        );
+        assert_eq!(
+            battery_attention(15, false, 20, 10),
+            GaugeValueAttention::Warning
+        );
+        assert_eq!(
+            battery_attention(8, true, 20, 10),
+            GaugeValueAttention::Nominal
+        );
+
+        let phone = PhoneState {
+            id: "a1b2".to_string(),
+            name: "Pixel".to_string(),
+            charge: Some(64),
+            charging: true,
+            notifications: Some(3),
+            clipboard_sync: true,
+        };
+        assert_eq!(
+            phone_lines(&phone),
+            vec![
+                "Device: Pixel",
+                "Battery: 64% (charging)",
+                "Notifications: 3",
+                "Clipboard sync: on"
+            ]
+        );
+        let menu = phone_menu(&phone, Arc::new(|_| {}));
+        assert!(menu.items[2].selected);
+        assert_eq!(
+            PhoneCommand::from_item(&menu.items[1].id),
+            Some(PhoneCommand::Find)
+        );
+    }
+}
@@ -1,19 +1,56 @@
+pub mod app_usage;
 pub mod audio_in;
 pub mod audio_out;
 pub mod battery;
+pub mod battery_profile;
 pub mod brightness;
+pub mod capture;
+pub mod certs;
+pub mod chat;
 pub mod clock;
+pub mod countdown;
 pub mod cpu;
 pub mod date;
+pub mod ddc_common;
 pub mod disk;
+pub mod display_features;
+pub mod dnd;
 pub mod gauge;
+pub mod gauge_bindings;
+pub mod gauge_dependencies;
+pub mod gauge_diagnostics;
+pub mod gauge_executor;
+pub mod gauge_hardware;
 pub mod gauge_registry;
+pub mod gauge_tuning;
 pub mod gauge_work_manager;
+pub mod gpu;
+pub mod health;
+pub mod inhibit;
+pub mod ip;
+pub mod iwd_common;
+pub mod jack;
+pub mod journal;
+pub mod kdeconnect;
 pub mod net_common;
 pub mod net_down;
 pub mod net_up;
+pub mod nm_common;
+pub mod note;
+pub mod notifications;
+pub mod plugin;
+pub mod pressure;
+pub mod proc_common;
 pub mod ram;
+pub mod run_watchdog;
+pub mod screenshare;
+pub mod script;
 pub mod session;
+pub mod tailscale;
+pub mod temp;
 #[cfg(debug_assertions)]
 pub mod test_gauge;
+pub mod vpn;
+pub mod weather_alerts;
 pub mod wifi;
+pub mod wired;
@@ -5,8 +5,28 @@ use std::path::PathBuf;
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
+use crate::panels::gauges::gauge::{GaugeDisplay, graph_samples};
+use crate::panels::gauges::gauge_tuning;
 use crate::settings;
 
+settings::typed_settings! {
+    /// Settings shared by the `net_up` and `net_down` gauges.
+    pub struct NetSettings {
+        idle_threshold_bps: f64 = ("grelier.gauge.net.idle_threshold_bps", "10240"),
+        fast_interval_secs: u64 = ("grelier.gauge.net.fast_interval_secs", "1", 1..=3600),
+        slow_interval_secs: u64 = ("grelier.gauge.net.slow_interval_secs", "3", 1..=3600),
+        calm_ticks: u8 = ("grelier.gauge.net.calm_ticks", "4"),
+        iface_cache_ttl_secs: u64 = ("grelier.gauge.net.iface_cache_ttl_secs", "10"),
+        iface_ttl_secs: u64 = ("grelier.gauge.net.iface_ttl_secs", "5"),
+        sampler_min_interval_ms: u64 = ("grelier.gauge.net.sampler_min_interval_ms", "900"),
+        sys_class_net_path: String = ("grelier.gauge.net.sys_class_net_path", "/sys/class/net"),
+        proc_net_route_path: String = ("grelier.gauge.net.proc_net_route_path", "/proc/net/route"),
+        proc_net_dev_path: String = ("grelier.gauge.net.proc_net_dev_path", "/proc/net/dev"),
+        history_samples: usize = ("grelier.gauge.net.history_samples", "30", 2..=600),
+        graph: bool = ("grelier.gauge.net.graph", "false"),
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct NetCounters {
     pub rx_bytes: u64,
@@ -59,7 +79,11 @@ impl NetIntervalState {
         if self.fast {
             self.config.fast_interval
         } else {
-            self.config.slow_interval
+            // `net_up` and `net_down` share one tunable slow interval.
+            Duration::from_secs(gauge_tuning::live_secs(
+                "net_down",
+                self.config.slow_interval.as_secs(),
+            ))
         }
     }
 }
@@ -84,29 +108,15 @@ impl Default for NetIntervalConfig {
 }
 
 pub fn net_interval_config_from_settings() -> NetIntervalConfig {
-    let idle_threshold_bps =
-        settings::settings().get_parsed_or("grelier.gauge.net.idle_threshold_bps", 10_240.0);
-    let fast_interval_secs =
-        settings::settings().get_parsed_or("grelier.gauge.net.fast_interval_secs", 1u64);
-    let slow_interval_secs =
-        settings::settings().get_parsed_or("grelier.gauge.net.slow_interval_secs", 3u64);
-    let calm_ticks = settings::settings().get_parsed_or("grelier.gauge.net.calm_ticks", 4u8);
-
+    let settings = NetSettings::load();
     NetIntervalConfig {
-        idle_threshold_bps,
-        fast_interval: Duration::from_secs(fast_interval_secs),
-        slow_interval: Duration::from_secs(slow_interval_secs),
-        calm_ticks,
+        idle_threshold_bps: settings.idle_threshold_bps,
+        fast_interval: Duration::from_secs(settings.fast_interval_secs),
+        slow_interval: Duration::from_secs(settings.slow_interval_secs),
+        calm_ticks: settings.calm_ticks,
     }
 }
 
-const DEFAULT_IFACE_CACHE_TTL_SECS: u64 = 10;
-const DEFAULT_IFACE_TTL_SECS: u64 = 5;
-const DEFAULT_SAMPLER_MIN_INTERVAL_MS: u64 = 900;
-const DEFAULT_SYS_CLASS_NET_PATH: &str = "/sys/class/net";
-const DEFAULT_PROC_NET_ROUTE_PATH: &str = "/proc/net/route";
-const DEFAULT_PROC_NET_DEV_PATH: &str = "/proc/net/dev";
-
 #[derive(Clone, Copy, Debug)]
 pub struct NetSamplerConfig {
     pub min_interval: Duration,
@@ -124,16 +134,10 @@ impl NetSamplerConfig {
 }
 
 pub fn sampler_config_from_settings() -> NetSamplerConfig {
-    let min_interval_ms = settings::settings().get_parsed_or(
-        "grelier.gauge.net.sampler_min_interval_ms",
-        DEFAULT_SAMPLER_MIN_INTERVAL_MS,
-    );
-    let iface_ttl_secs = settings::settings()
-        .get_parsed_or("grelier.gauge.net.iface_ttl_secs", DEFAULT_IFACE_TTL_SECS);
-
+    let settings = NetSettings::load();
     NetSamplerConfig {
-        min_interval: Duration::from_millis(min_interval_ms),
-        iface_ttl: Duration::from_secs(iface_ttl_secs),
+        min_interval: Duration::from_millis(settings.sampler_min_interval_ms),
+        iface_ttl: Duration::from_secs(settings.iface_ttl_secs),
     }
 }
 
@@ -295,30 +299,6 @@ pub fn shared_net_sampler() -> Arc<Mutex<NetSampler>> {
         .clone()
 }
 
-/// Format bytes/sec into a single-line KB/MB/GB per second string for info dialogs.
-pub fn format_rate_per_sec(bytes_per_sec: f64) -> String {
-    const STEP: f64 = 1024.0;
-
-    let mut value = bytes_per_sec.max(0.0) / STEP; // Start at KB.
-    let mut unit = "KB/s";
-
-    for next in ["MB/s", "GB/s"] {
-        if value < STEP {
-            break;
-        }
-        value /= STEP;
-        unit = next;
-    }
-
-    let rendered = if value < 10.0 {
-        format!("{:.1}", value)
-    } else {
-        format!("{:.0}", value)
-    };
-
-    format!("{rendered} {unit}")
-}
-
 pub struct SlidingWindow {
     samples: std::collections::VecDeque<f64>,
     min: f64,
@@ -404,6 +384,79 @@ impl SlidingWindow {
     }
 }
 
+/// Recent rates kept for the info dialog graph and the optional bar sparkline.
+pub struct RateHistory {
+    samples: std::collections::VecDeque<f64>,
+    capacity: usize,
+}
+
+impl RateHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: std::collections::VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn from_settings() -> Self {
+        Self::new(NetSettings::load().history_samples)
+    }
+
+    pub fn push(&mut self, bytes_per_sec: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(bytes_per_sec.max(0.0));
+    }
+
+    /// Highest rate still in the history.
+    pub fn peak(&self) -> f64 {
+        self.samples.iter().copied().fold(0.0, f64::max)
+    }
+
+    /// Samples oldest first, scaled against the peak so the graph always uses its full height.
+    pub fn graph(&self) -> Vec<f32> {
+        let peak = self.peak();
+        self.samples
+            .iter()
+            .map(|sample| {
+                if peak <= f64::EPSILON {
+                    0.0
+                } else {
+                    (sample / peak) as f32
+                }
+            })
+            .collect()
+    }
+
+    /// The newest `count` points of [`Self::graph`], for the bar sparkline.
+    pub fn recent_graph(&self, count: usize) -> Vec<f32> {
+        let graph = self.graph();
+        graph[graph.len().saturating_sub(count)..].to_vec()
+    }
+}
+
+/// Points for the bar sparkline, or `None` when `grelier.gauge.net.graph` is off. Read on each
+/// run so a settings profile can hide the sparkline without recreating the gauges.
+pub fn sparkline_samples() -> Option<usize> {
+    NetSettings::load().graph.then(graph_samples)
+}
+
+/// Swap a value display for a sparkline of `history` when `sparkline_samples` is set.
+pub fn rate_display(
+    display: GaugeDisplay,
+    history: &RateHistory,
+    sparkline_samples: Option<usize>,
+) -> GaugeDisplay {
+    match (sparkline_samples, display) {
+        (Some(count), GaugeDisplay::Value { attention, .. }) => GaugeDisplay::Graph {
+            samples: history.recent_graph(count),
+            attention,
+        },
+        (_, display) => display,
+    }
+}
+
 /// Attempt to find the interface that carries the default route.
 fn default_route_interface() -> Option<String> {
     let contents = fs::read_to_string(proc_net_route_path()).ok()?;
@@ -453,7 +506,8 @@ fn first_up_interface() -> Option<String> {
     None
 }
 
-fn active_interface_scan() -> Option<String> {
+/// Interface carrying the default route, falling back to the first interface that is up.
+pub fn active_interface_scan() -> Option<String> {
     default_route_interface().or_else(first_up_interface)
 }
 
@@ -506,32 +560,19 @@ fn interface_is_up(iface: &str) -> bool {
 }
 
 fn iface_cache_ttl() -> Duration {
-    let ttl_secs = settings::settings().get_parsed_or(
-        "grelier.gauge.net.iface_cache_ttl_secs",
-        DEFAULT_IFACE_CACHE_TTL_SECS,
-    );
-    Duration::from_secs(ttl_secs)
+    Duration::from_secs(NetSettings::load().iface_cache_ttl_secs)
 }
 
 fn sys_class_net_path() -> PathBuf {
-    PathBuf::from(settings::settings().get_or(
-        "grelier.gauge.net.sys_class_net_path",
-        DEFAULT_SYS_CLASS_NET_PATH,
-    ))
+    PathBuf::from(NetSettings::load().sys_class_net_path)
 }
 
 fn proc_net_route_path() -> PathBuf {
-    PathBuf::from(settings::settings().get_or(
-        "grelier.gauge.net.proc_net_route_path",
-        DEFAULT_PROC_NET_ROUTE_PATH,
-    ))
+    PathBuf::from(NetSettings::load().proc_net_route_path)
 }
 
 fn proc_net_dev_path() -> PathBuf {
-    PathBuf::from(settings::settings().get_or(
-        "grelier.gauge.net.proc_net_dev_path",
-        DEFAULT_PROC_NET_DEV_PATH,
-    ))
+    PathBuf::from(NetSettings::load().proc_net_dev_path)
 }
 
 #[cfg(test)]
@@ -542,6 +583,22 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn rate_history_keeps_last_samples_scaled_to_peak() {
+        let mut history = RateHistory::new(3);
+        assert!(history.graph().is_empty());
+        for rate in [100.0, 400.0, 200.0, 0.0] {
+            history.push(rate);
+        }
+        assert_eq!(history.peak(), 400.0);
+        assert_eq!(history.graph(), vec![1.0, 0.5, 0.0]);
+        assert_eq!(history.recent_graph(2), vec![0.5, 0.0]);
+
+        let mut idle = RateHistory::new(2);
+        idle.push(0.0);
+        assert_eq!(idle.graph(), vec![0.0]);
+    }
+
     struct FakeProvider {
         clock: Arc<Mutex<Instant>>,
         iface: String,
@@ -9,10 +9,11 @@ use crate::panels::gauges::gauge::{
 };
 use crate::panels::gauges::gauge_registry::GaugeSpec;
 use crate::panels::gauges::net_common::{
-    NetIntervalState, SlidingWindow, format_rate_per_sec, net_interval_config_from_settings,
-    shared_net_sampler,
+    NetIntervalState, RateHistory, SlidingWindow, net_interval_config_from_settings, rate_display,
+    shared_net_sampler, sparkline_samples,
 };
 use crate::settings::{NO_SETTINGS, SettingSpec};
+use crate::units::Units;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
@@ -48,6 +49,10 @@ struct NetDownGauge {
     interval_state: NetIntervalState,
     /// Sliding window used to smooth and classify sampled rates.
     rate_window: SlidingWindow,
+    /// Recent rates graphed in the info dialog.
+    history: RateHistory,
+    /// Unit preferences for the info dialog.
+    units: Units,
     /// Scheduler deadline for the next run.
     next_deadline: Instant,
 }
@@ -76,6 +81,8 @@ impl Gauge for NetDownGauge {
         let (display, bytes_per_sec) = map_rate(rate, &mut self.rate_window);
 
         self.interval_state.update(bytes_per_sec);
+        self.history.push(bytes_per_sec);
+        let display = rate_display(display, &self.history, sparkline_samples());
         self.next_deadline = now + self.interval_state.interval();
 
         Some(GaugeModel {
@@ -88,8 +95,10 @@ impl Gauge for NetDownGauge {
                         title: "Net Down".to_string(),
                         lines: vec![
                             iface.unwrap_or_else(|| "No active interface".to_string()),
-                            format_rate_per_sec(bytes_per_sec),
+                            self.units.format_rate(bytes_per_sec),
+                            format!("Peak: {}", self.units.format_rate(self.history.peak())),
                         ],
+                        graph: Some(self.history.graph()),
                     }),
                     ..GaugePointerInteraction::default()
                 },
@@ -104,6 +113,8 @@ pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
         sampler: shared_net_sampler(),
         interval_state: NetIntervalState::new(net_interval_config_from_settings()),
         rate_window: SlidingWindow::new(RATE_WINDOW_SAMPLES),
+        history: RateHistory::from_settings(),
+        units: Units::load(),
         next_deadline: now,
     })
 }
@@ -9,10 +9,11 @@ use crate::panels::gauges::gauge::{
 };
 use crate::panels::gauges::gauge_registry::GaugeSpec;
 use crate::panels::gauges::net_common::{
-    NetIntervalState, SlidingWindow, format_rate_per_sec, net_interval_config_from_settings,
-    shared_net_sampler,
+    NetIntervalState, NetSettings, RateHistory, SlidingWindow, net_interval_config_from_settings,
+    rate_display, shared_net_sampler, sparkline_samples,
 };
 use crate::settings::SettingSpec;
+use crate::units::Units;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
@@ -48,6 +49,10 @@ struct NetUpGauge {
     interval_state: NetIntervalState,
     /// Sliding window used to smooth and classify sampled rates.
     rate_window: SlidingWindow,
+    /// Recent rates graphed in the info dialog.
+    history: RateHistory,
+    /// Unit preferences for the info dialog.
+    units: Units,
     /// Scheduler deadline for the next run.
     next_deadline: Instant,
 }
@@ -76,6 +81,8 @@ impl Gauge for NetUpGauge {
         let (display, bytes_per_sec) = map_rate(rate, &mut self.rate_window);
 
         self.interval_state.update(bytes_per_sec);
+        self.history.push(bytes_per_sec);
+        let display = rate_display(display, &self.history, sparkline_samples());
         self.next_deadline = now + self.interval_state.interval();
 
         Some(GaugeModel {
@@ -88,8 +95,10 @@ impl Gauge for NetUpGauge {
                         title: "Net Up".to_string(),
                         lines: vec![
                             iface.unwrap_or_else(|| "No active interface".to_string()),
-                            format_rate_per_sec(bytes_per_sec),
+                            self.units.format_rate(bytes_per_sec),
+                            format!("Peak: {}", self.units.format_rate(self.history.peak())),
                         ],
+                        graph: Some(self.history.graph()),
                     }),
                     ..GaugePointerInteraction::default()
                 },
@@ -104,54 +113,14 @@ pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
         sampler: shared_net_sampler(),
         interval_state: NetIntervalState::new(net_interval_config_from_settings()),
         rate_window: SlidingWindow::new(RATE_WINDOW_SAMPLES),
+        history: RateHistory::from_settings(),
+        units: Units::load(),
         next_deadline: now,
     })
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[
-        SettingSpec {
-            key: "grelier.gauge.net.idle_threshold_bps",
-            default: "10240",
-        },
-        SettingSpec {
-            key: "grelier.gauge.net.fast_interval_secs",
-            default: "1",
-        },
-        SettingSpec {
-            key: "grelier.gauge.net.slow_interval_secs",
-            default: "3",
-        },
-        SettingSpec {
-            key: "grelier.gauge.net.calm_ticks",
-            default: "4",
-        },
-        SettingSpec {
-            key: "grelier.gauge.net.iface_cache_ttl_secs",
-            default: "10",
-        },
-        SettingSpec {
-            key: "grelier.gauge.net.iface_ttl_secs",
-            default: "5",
-        },
-        SettingSpec {
-            key: "grelier.gauge.net.sampler_min_interval_ms",
-            default: "900",
-        },
-        SettingSpec {
-            key: "grelier.gauge.net.sys_class_net_path",
-            default: "/sys/class/net",
-        },
-        SettingSpec {
-            key: "grelier.gauge.net.proc_net_route_path",
-            default: "/proc/net/route",
-        },
-        SettingSpec {
-            key: "grelier.gauge.net.proc_net_dev_path",
-            default: "/proc/net/dev",
-        },
-    ];
-    SETTINGS
+    NetSettings::SPECS
 }
 
 inventory::submit! {
@@ -0,0 +1,169 @@
+// Shared NetworkManager D-Bus helpers for connection listing and activation.
+use std::collections::HashMap;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+pub const NM_SERVICE: &str = "org.freedesktop.NetworkManager";
+pub const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+pub const NM_IFACE: &str = "org.freedesktop.NetworkManager";
+const NM_SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const NM_SETTINGS_IFACE: &str = "org.freedesktop.NetworkManager.Settings";
+const NM_SETTINGS_CONNECTION_IFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+const NM_ACTIVE_CONNECTION_IFACE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+
+/// Settings dictionary returned by `Settings.Connection.GetSettings`.
+pub type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+/// One saved NetworkManager connection profile.
+pub struct ConnectionEntry {
+    pub id: String,
+    pub path: OwnedObjectPath,
+    pub settings: ConnectionSettings,
+}
+
+/// One currently active NetworkManager connection.
+#[derive(Clone, Debug)]
+pub struct ActiveConnection {
+    pub id: String,
+    pub path: OwnedObjectPath,
+    pub settings_path: OwnedObjectPath,
+}
+
+/// Read a typed value from `settings[section][key]`.
+pub fn setting_value<T>(settings: &ConnectionSettings, section: &str, key: &str) -> Option<T>
+where
+    T: TryFrom<OwnedValue>,
+{
+    settings
+        .get(section)?
+        .get(key)?
+        .try_clone()
+        .ok()?
+        .try_into()
+        .ok()
+}
+
+fn non_root(path: OwnedObjectPath) -> Option<OwnedObjectPath> {
+    (path.as_str() != "/").then_some(path)
+}
+
+/// Saved connections whose `connection.type` is one of `types`, sorted by id.
+pub fn connection_entries(connection: &Connection, types: &[&str]) -> Vec<ConnectionEntry> {
+    let settings_proxy =
+        match Proxy::new(connection, NM_SERVICE, NM_SETTINGS_PATH, NM_SETTINGS_IFACE) {
+            Ok(proxy) => proxy,
+            Err(_) => return Vec::new(),
+        };
+    let paths: Vec<OwnedObjectPath> = match settings_proxy.call("ListConnections", &()) {
+        Ok(paths) => paths,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut entries = Vec::new();
+    for path in paths {
+        let conn_proxy = match Proxy::new(
+            connection,
+            NM_SERVICE,
+            path.clone(),
+            NM_SETTINGS_CONNECTION_IFACE,
+        ) {
+            Ok(proxy) => proxy,
+            Err(_) => continue,
+        };
+        let settings: ConnectionSettings = match conn_proxy.call("GetSettings", &()) {
+            Ok(settings) => settings,
+            Err(_) => continue,
+        };
+        let Some(connection_type) = setting_value::<String>(&settings, "connection", "type") else {
+            continue;
+        };
+        if !types.contains(&connection_type.as_str()) {
+            continue;
+        }
+        let id = setting_value(&settings, "connection", "id")
+            .unwrap_or_else(|| path.as_str().to_string());
+        entries.push(ConnectionEntry { id, path, settings });
+    }
+
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    entries
+}
+
+/// Settings connection path backing an active connection.
+pub fn active_settings_connection_path(
+    connection: &Connection,
+    active_path: &OwnedObjectPath,
+) -> Option<OwnedObjectPath> {
+    let active_proxy = Proxy::new(
+        connection,
+        NM_SERVICE,
+        active_path,
+        NM_ACTIVE_CONNECTION_IFACE,
+    )
+    .ok()?;
+    non_root(active_proxy.get_property("Connection").ok()?)
+}
+
+/// All active connections whose type is one of `types`.
+pub fn active_connections(connection: &Connection, types: &[&str]) -> Vec<ActiveConnection> {
+    let Ok(nm_proxy) = Proxy::new(connection, NM_SERVICE, NM_PATH, NM_IFACE) else {
+        return Vec::new();
+    };
+    let paths: Vec<OwnedObjectPath> = nm_proxy
+        .get_property("ActiveConnections")
+        .unwrap_or_default();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let proxy = Proxy::new(
+                connection,
+                NM_SERVICE,
+                path.clone(),
+                NM_ACTIVE_CONNECTION_IFACE,
+            )
+            .ok()?;
+            let connection_type: String = proxy.get_property("Type").ok()?;
+            if !types.contains(&connection_type.as_str()) {
+                return None;
+            }
+            Some(ActiveConnection {
+                id: proxy.get_property("Id").unwrap_or_default(),
+                settings_path: non_root(proxy.get_property("Connection").ok()?)?,
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Activate a saved connection; `device_path` may be `/` for VPNs.
+pub fn activate_connection(
+    connection: &Connection,
+    connection_path: &str,
+    device_path: &OwnedObjectPath,
+) -> bool {
+    let nm_proxy = match Proxy::new(connection, NM_SERVICE, NM_PATH, NM_IFACE) {
+        Ok(proxy) => proxy,
+        Err(_) => return false,
+    };
+    let connection_path = match OwnedObjectPath::try_from(connection_path) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    let specific = match OwnedObjectPath::try_from("/") {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    nm_proxy
+        .call::<_, _, OwnedObjectPath>(
+            "ActivateConnection",
+            &(connection_path, device_path, specific),
+        )
+        .is_ok()
+}
+
+/// Bring down an active connection.
+pub fn deactivate_connection(connection: &Connection, active_path: &OwnedObjectPath) -> bool {
+    Proxy::new(connection, NM_SERVICE, NM_PATH, NM_IFACE)
+        .and_then(|proxy| proxy.call::<_, _, ()>("DeactivateConnection", &(active_path,)))
+        .is_ok()
+}
@@ -0,0 +1,316 @@
+// Quick note gauge: a scratchpad kept in a text file. The bar shows the start of the first line,
+// left-click edits the whole note in a text area, and the info dialog shows it.
+// Consumes Settings: grelier.gauge.note.*.
+use crate::dialog::action;
+use crate::dialog::info::InfoDialog;
+use crate::dialog::menu;
+use crate::dialog::prompt::{self, PromptDialog, PromptSubmitAction};
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeClickAction, GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem,
+    GaugeModel, GaugePointerInteraction, GaugeReadyNotify, GaugeValue, GaugeValueAttention,
+    MenuSelectAction,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often the file is reread, to pick up edits made in the editor.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+settings::typed_settings! {
+    struct NoteSettings {
+        path: String = ("grelier.gauge.note.path", ""),
+        editor: String = ("grelier.gauge.note.editor", "xdg-open"),
+        label_chars: usize = ("grelier.gauge.note.label_chars", "3", 0..=8),
+    }
+}
+
+/// The configured note file, or `$XDG_DATA_HOME/grelier/note.txt`.
+fn note_path(configured: &str) -> PathBuf {
+    let configured = configured.trim();
+    if !configured.is_empty() {
+        return PathBuf::from(configured);
+    }
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("grelier")
+        .join("note.txt")
+}
+
+fn first_line(note: &str) -> &str {
+    note.lines().next().unwrap_or("").trim()
+}
+
+/// The note saved from the text area; `text` carries the file's final newline when unchanged.
+fn replace_note(_note: &str, text: &str) -> String {
+    format!("{}\n", text.trim_end_matches('\n'))
+}
+
+/// `note` with `line` added above its first line.
+fn push_line(note: &str, line: &str) -> String {
+    if note.trim().is_empty() {
+        return format!("{line}\n");
+    }
+    format!("{line}\n{}", note.trim_end_matches('\n')) + "\n"
+}
+
+/// Bar text: the first `chars` characters of the first line.
+fn label(line: &str, chars: usize) -> String {
+    line.chars()
+        .take(chars)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Hidden sibling the note is written to before it replaces the note.
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map_or_else(|| "note".into(), |name| name.to_string_lossy());
+    path.with_file_name(format!(".{name}.tmp"))
+}
+
+/// Replace the file at `path` with `contents`, so a crash leaves either the old or the new note.
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp = temp_path(path);
+    let mut file = File::create(&temp)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp, path)
+}
+
+/// Rewrite the note with `edit`, then refresh the gauge.
+fn edit_note(path: &Path, notify: Option<&GaugeReadyNotify>, edit: impl Fn(&str) -> String) {
+    let note = fs::read_to_string(path).unwrap_or_default();
+    if let Err(err) = write_atomically(path, &edit(&note)) {
+        log::error!("note gauge: failed to write {}: {err}", path.display());
+    }
+    if let Some(notify) = notify {
+        notify("note");
+    }
+}
+
+/// Prompt callback applying the submitted text to the note with `edit`.
+fn submit_edit(
+    path: &Path,
+    notify: Option<&GaugeReadyNotify>,
+    edit: fn(&str, &str) -> String,
+) -> PromptSubmitAction {
+    let path = path.to_path_buf();
+    let notify = notify.cloned();
+    Arc::new(move |text: String| edit_note(&path, notify.as_ref(), |note| edit(note, &text)))
+}
+
+/// Gauge showing the first line of a scratchpad file.
+struct NoteGauge {
+    settings: NoteSettings,
+    path: PathBuf,
+    ready_notify: Option<GaugeReadyNotify>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl NoteGauge {
+    fn interactions(&self, note: &str) -> GaugeInteractionModel {
+        let path = self.path.clone();
+        let notify = self.ready_notify.clone();
+        let current = note.to_string();
+        let on_click: GaugeClickAction = Arc::new(move |_| {
+            let save = submit_edit(&path, notify.as_ref(), replace_note);
+            prompt::request(
+                "note",
+                PromptDialog::multiline("Note", &current, Some(save)),
+            )
+        });
+
+        let path = self.path.clone();
+        let notify = self.ready_notify.clone();
+        let on_select: MenuSelectAction = Arc::new(move |item_id: String| match item_id.as_str() {
+            "add" => prompt::request(
+                "note",
+                PromptDialog {
+                    title: "Add a line".to_string(),
+                    value: String::new(),
+                    placeholder: "Note".to_string(),
+                    secret: false,
+                    editor: None,
+                    on_submit: Some(submit_edit(&path, notify.as_ref(), push_line)),
+                },
+            ),
+            "clear" => {
+                let path = path.clone();
+                let notify = notify.clone();
+                let clear =
+                    Arc::new(move |_: String| edit_note(&path, notify.as_ref(), |_| String::new()));
+                menu::request(
+                    "note",
+                    action::confirm_menu("Clear the note?".to_string(), item_id, clear),
+                )
+            }
+            _ => {}
+        });
+        let item = |id: &str, label: &str, exec: Option<String>| GaugeMenuItem {
+            id: id.to_string(),
+            label: label.to_string(),
+            selected: false,
+            exec,
+        };
+
+        // The editor gets an existing file even before anything was written.
+        let quoted = shell_quote(&self.path.to_string_lossy());
+        let open = format!("touch {quoted} && {} {quoted}", self.settings.editor);
+        let lines: Vec<String> = note.lines().map(ToString::to_string).collect();
+        GaugeInteractionModel {
+            left_click: GaugePointerInteraction {
+                on_input: Some(on_click),
+                ..GaugePointerInteraction::default()
+            },
+            middle_click: GaugePointerInteraction {
+                info: Some(InfoDialog {
+                    title: "Note".to_string(),
+                    lines: if lines.is_empty() {
+                        vec!["The note is empty".to_string()]
+                    } else {
+                        lines
+                    },
+                    graph: None,
+                }),
+                ..GaugePointerInteraction::default()
+            },
+            right_click: GaugePointerInteraction {
+                menu: Some(GaugeMenu {
+                    title: "Note".to_string(),
+                    items: vec![
+                        item("add", "Add a line", None),
+                        item("open", "Open in editor", Some(open)),
+                        item("clear", "Clear", None),
+                    ],
+                    on_select: Some(on_select),
+                    slider: None,
+                }),
+                ..GaugePointerInteraction::default()
+            },
+            ..GaugeInteractionModel::default()
+        }
+    }
+}
+
+impl Gauge for NoteGauge {
+    fn id(&self) -> &'static str {
+        "note"
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + REFRESH_INTERVAL;
+
+        let note = fs::read_to_string(&self.path).unwrap_or_default();
+        let label = label(first_line(&note), self.settings.label_chars);
+        let display = if label.is_empty() {
+            GaugeDisplay::Empty
+        } else {
+            GaugeDisplay::Value {
+                value: GaugeValue::Text(label),
+                attention: GaugeValueAttention::Nominal,
+            }
+        };
+
+        Some(GaugeModel {
+            id: "note",
+            icon: svg_asset("note.svg"),
+            display,
+            interactions: self.interactions(&note),
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = NoteSettings::load();
+    Box::new(NoteGauge {
+        path: note_path(&settings.path),
+        settings,
+        ready_notify: None,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    NoteSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "note",
+        description: "Quick note gauge showing the first line of a scratchpad file.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_lines_and_labels_the_first() {
+        let note = "call the bank\nbuy milk\n";
+        assert_eq!(first_line(note), "call the bank");
+        assert_eq!(
+            push_line(note, "water plants"),
+            "water plants\ncall the bank\nbuy milk\n"
+        );
+        assert_eq!(push_line("\n", "first"), "first\n");
+
+        assert_eq!(label("call the bank", 3), "cal");
+        assert_eq!(label("go to", 3), "go");
+        assert_eq!(label("anything", 0), "");
+        assert_eq!(shell_quote("/tmp/it's.txt"), r"'/tmp/it'\''s.txt'");
+        assert_eq!(note_path("/tmp/note.md"), PathBuf::from("/tmp/note.md"));
+    }
+
+    #[test]
+    fn saving_an_unchanged_note_twice_keeps_it() {
+        let note = "call the bank\nbuy milk\n";
+        let saved = replace_note(note, note);
+        assert_eq!(saved, note);
+        assert_eq!(replace_note(&saved, &saved), note);
+        assert_eq!(replace_note(note, "call the bank"), "call the bank\n");
+    }
+
+    #[test]
+    fn writes_the_note_through_a_temporary_file() {
+        let dir = std::env::temp_dir().join(format!("grelier-note-{}", std::process::id()));
+        let path = dir.join("note.txt");
+        write_atomically(&path, "first\n").expect("write note");
+        write_atomically(&path, "second\n").expect("replace note");
+        assert_eq!(fs::read_to_string(&path).expect("read note"), "second\n");
+        assert_eq!(temp_path(&path), dir.join(".note.txt.tmp"));
+        assert!(!temp_path(&path).exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,663 @@
+// Notification daemon gauge serving org.freedesktop.Notifications on the session bus.
+// Consumes Settings: grelier.gauge.notifications.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::dnd::dnd_enabled;
+use crate::panels::gauges::gauge::{
+    AsyncGaugeEventSource, Gauge, GaugeFuture, GaugeReadyNotify, GaugeRegistrar,
+};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel,
+    GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use futures_lite::{StreamExt, future};
+use iced::futures::channel::mpsc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use zbus::fdo::{DBusProxy, NameLostStream};
+use zbus::object_server::{InterfaceRef, SignalContext};
+use zbus::zvariant::OwnedValue;
+
+const BUS_NAME: &str = "org.freedesktop.Notifications";
+const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+const INFO_DIALOG_LINES: usize = 5;
+const MENU_LABEL_MAX_CHARS: usize = 60;
+const CLEAR_ALL_ITEM: &str = "clear";
+const URGENCY_CRITICAL: u8 = 2;
+/// `NotificationClosed` reasons from the notification specification.
+const CLOSE_REASON_EXPIRED: u32 = 1;
+const CLOSE_REASON_DISMISSED: u32 = 2;
+const CLOSE_REASON_CLOSED_BY_CALL: u32 = 3;
+
+settings::typed_settings! {
+    struct NotificationsSettings {
+        history_size: usize = ("grelier.gauge.notifications.history_size", "50", 1..=1000),
+    }
+}
+
+/// One notification received from a client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Notification {
+    id: u32,
+    app_name: String,
+    summary: String,
+    body: String,
+    urgency: u8,
+}
+
+impl Notification {
+    fn is_critical(&self) -> bool {
+        self.urgency >= URGENCY_CRITICAL
+    }
+
+    fn headline(&self) -> String {
+        match (self.app_name.trim(), self.summary.trim()) {
+            ("", summary) => summary.to_string(),
+            (app, "") => app.to_string(),
+            (app, summary) => format!("{app}: {summary}"),
+        }
+    }
+}
+
+/// Bounded, newest-first list of notifications that have not been dismissed.
+#[derive(Debug)]
+struct NotificationHistory {
+    entries: VecDeque<Notification>,
+    capacity: usize,
+    last_id: u32,
+    /// Set when the bus name could not be acquired.
+    error: Option<String>,
+}
+
+impl NotificationHistory {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            last_id: 0,
+            error: None,
+        }
+    }
+
+    /// Store a notification, replacing `replaces_id` in place when it is known. Returns its id
+    /// and the ids of the oldest notifications evicted to stay within capacity.
+    fn insert(&mut self, replaces_id: u32, mut notification: Notification) -> (u32, Vec<u32>) {
+        if replaces_id != 0
+            && let Some(existing) = self.entries.iter_mut().find(|n| n.id == replaces_id)
+        {
+            notification.id = replaces_id;
+            *existing = notification;
+            return (replaces_id, Vec::new());
+        }
+
+        // Id 0 is reserved by the specification to mean "no notification".
+        self.last_id = self.last_id.checked_add(1).unwrap_or(1);
+        notification.id = self.last_id;
+        self.entries.push_front(notification);
+        let evicted = self
+            .entries
+            .drain(self.capacity.min(self.entries.len())..)
+            .map(|n| n.id)
+            .collect();
+        (self.last_id, evicted)
+    }
+
+    fn remove(&mut self, id: u32) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|n| n.id != id);
+        self.entries.len() != before
+    }
+
+    fn clear(&mut self) -> Vec<u32> {
+        self.entries.drain(..).map(|n| n.id).collect()
+    }
+}
+
+/// D-Bus object implementing the notification server interface.
+struct NotificationServer {
+    history: Arc<Mutex<NotificationHistory>>,
+    ready_notify: GaugeReadyNotify,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl NotificationServer {
+    fn get_capabilities(&self) -> Vec<String> {
+        vec!["body".to_string(), "persistence".to_string()]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn notify(
+        &self,
+        app_name: String,
+        replaces_id: u32,
+        _app_icon: String,
+        summary: String,
+        body: String,
+        _actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        _expire_timeout: i32,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> u32 {
+        let urgency = hints
+            .get("urgency")
+            .and_then(|value| u8::try_from(value).ok())
+            .unwrap_or(1);
+        let notification = Notification {
+            id: 0,
+            app_name,
+            summary,
+            body,
+            urgency,
+        };
+        let (id, evicted) = match self.history.lock() {
+            Ok(mut history) => history.insert(replaces_id, notification),
+            Err(_) => return 0,
+        };
+        // Clients tracking their ids learn that notifications pushed out of the history are gone.
+        for evicted in evicted {
+            if let Err(err) = Self::notification_closed(&ctxt, evicted, CLOSE_REASON_EXPIRED).await
+            {
+                log::warn!("notifications gauge: failed to emit NotificationClosed: {err}");
+            }
+        }
+        (self.ready_notify)("notifications");
+        id
+    }
+
+    async fn close_notification(
+        &self,
+        id: u32,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> zbus::fdo::Result<()> {
+        let removed = self
+            .history
+            .lock()
+            .map(|mut history| history.remove(id))
+            .unwrap_or(false);
+        if removed {
+            Self::notification_closed(&ctxt, id, CLOSE_REASON_CLOSED_BY_CALL).await?;
+            (self.ready_notify)("notifications");
+        }
+        Ok(())
+    }
+
+    fn get_server_information(&self) -> (String, String, String, String) {
+        (
+            "grelier".to_string(),
+            "grelier".to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+            "1.2".to_string(),
+        )
+    }
+
+    #[zbus(signal)]
+    async fn notification_closed(
+        ctxt: &SignalContext<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+}
+
+/// Dismiss request sent from the menu to the server.
+#[derive(Debug)]
+enum DismissRequest {
+    One(u32),
+    All,
+}
+
+/// Bus side of the service, set up by its first poll.
+enum ServiceBus {
+    Starting,
+    Serving {
+        iface: InterfaceRef<NotificationServer>,
+        /// Reports the bus name going to another daemon; `None` once it has.
+        name_lost: Option<Box<NameLostStream<'static>>>,
+    },
+    Unavailable,
+}
+
+/// What woke the service.
+enum ServiceEvent {
+    Dismiss(Option<DismissRequest>),
+    NameLost,
+}
+
+/// Async event source that owns the bus connection and applies dismissals from the menu.
+struct NotificationService {
+    history: Arc<Mutex<NotificationHistory>>,
+    dismiss_rx: mpsc::UnboundedReceiver<DismissRequest>,
+    ready_notify: Option<GaugeReadyNotify>,
+    bus: ServiceBus,
+}
+
+impl NotificationService {
+    /// Acquire the bus name and serve the interface, or say why not.
+    async fn serve(&self, notify: GaugeReadyNotify) -> Result<ServiceBus, String> {
+        let server = NotificationServer {
+            history: Arc::clone(&self.history),
+            ready_notify: notify,
+        };
+        let connection = async {
+            zbus::connection::Builder::session()?
+                .name(BUS_NAME)?
+                .serve_at(OBJECT_PATH, server)?
+                .build()
+                .await
+        };
+        let connection = match connection.await {
+            Ok(connection) => connection,
+            Err(err) => {
+                log::error!("notifications gauge: failed to acquire {BUS_NAME}: {err}");
+                return Err(match err {
+                    zbus::Error::NameTaken => name_taken_reason().await,
+                    err => err.to_string(),
+                });
+            }
+        };
+        let name_lost = match DBusProxy::new(&connection).await {
+            Ok(proxy) => proxy.receive_name_lost().await.ok().map(Box::new),
+            Err(_) => None,
+        };
+        let iface = connection
+            .object_server()
+            .interface::<_, NotificationServer>(OBJECT_PATH)
+            .await
+            .map_err(|err| format!("server object missing: {err}"))?;
+        Ok(ServiceBus::Serving { iface, name_lost })
+    }
+
+    /// Remove the requested notifications and tell their clients they are gone.
+    async fn dismiss(&self, iface: &InterfaceRef<NotificationServer>, request: DismissRequest) {
+        let dismissed = match self.history.lock() {
+            Ok(mut history) => match request {
+                DismissRequest::One(id) => {
+                    if history.remove(id) {
+                        vec![id]
+                    } else {
+                        Vec::new()
+                    }
+                }
+                DismissRequest::All => history.clear(),
+            },
+            Err(_) => return,
+        };
+        for id in dismissed {
+            let signal = NotificationServer::notification_closed(
+                iface.signal_context(),
+                id,
+                CLOSE_REASON_DISMISSED,
+            );
+            if let Err(err) = signal.await {
+                log::warn!("notifications gauge: failed to emit NotificationClosed: {err}");
+            }
+        }
+    }
+}
+
+impl AsyncGaugeEventSource for NotificationService {
+    fn interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Each poll waits for the next dismissal or bus change, however long that takes.
+    fn timeout(&self) -> Duration {
+        Duration::MAX
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn poll(&mut self) -> GaugeFuture<'_, bool> {
+        Box::pin(async move {
+            if matches!(self.bus, ServiceBus::Starting) {
+                let Some(notify) = self.ready_notify.clone() else {
+                    return future::pending().await;
+                };
+                match self.serve(notify).await {
+                    Ok(bus) => self.bus = bus,
+                    Err(reason) => {
+                        self.bus = ServiceBus::Unavailable;
+                        set_unavailable(&self.history, reason);
+                        return true;
+                    }
+                }
+            }
+            // Without a server there is nothing left to do; idle until the task is cancelled.
+            let ServiceBus::Serving { iface, name_lost } = &mut self.bus else {
+                return future::pending().await;
+            };
+            let dismiss_rx = &mut self.dismiss_rx;
+            let event = future::or(
+                async { ServiceEvent::Dismiss(dismiss_rx.next().await) },
+                async {
+                    match name_lost {
+                        Some(name_lost) => {
+                            while let Some(signal) = name_lost.next().await {
+                                if signal
+                                    .args()
+                                    .is_ok_and(|args| args.name().as_str() == BUS_NAME)
+                                {
+                                    return ServiceEvent::NameLost;
+                                }
+                            }
+                            future::pending().await
+                        }
+                        None => future::pending().await,
+                    }
+                },
+            )
+            .await;
+            match event {
+                ServiceEvent::Dismiss(Some(request)) => {
+                    let iface = iface.clone();
+                    self.dismiss(&iface, request).await;
+                }
+                // The gauge is gone once its sender closes.
+                ServiceEvent::Dismiss(None) => return future::pending().await,
+                ServiceEvent::NameLost => {
+                    log::warn!("notifications gauge: lost {BUS_NAME} to another daemon");
+                    *name_lost = None;
+                    set_unavailable(&self.history, name_taken_reason().await);
+                }
+            }
+            true
+        })
+    }
+}
+
+/// Record why the server is not running.
+fn set_unavailable(history: &Mutex<NotificationHistory>, reason: String) {
+    if let Ok(mut history) = history.lock() {
+        history.error = Some(reason);
+    }
+}
+
+/// Name of the process that owns the notification bus name, from its `/proc` entry.
+async fn bus_name_owner() -> Option<String> {
+    let connection = zbus::Connection::session().await.ok()?;
+    let proxy = DBusProxy::new(&connection).await.ok()?;
+    let name = zbus::names::BusName::try_from(BUS_NAME).ok()?;
+    let pid = proxy.get_connection_unix_process_id(name).await.ok()?;
+    let comm = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    Some(comm.trim().to_string())
+}
+
+async fn name_taken_reason() -> String {
+    match bus_name_owner().await {
+        Some(owner) => format!("{owner} is already the notification daemon"),
+        None => "Another notification daemon is running".to_string(),
+    }
+}
+
+/// Danger while a critical notification is pending, unless Do Not Disturb is on.
+fn notification_attention(entries: &VecDeque<Notification>, dnd: bool) -> GaugeValueAttention {
+    if !dnd && entries.iter().any(Notification::is_critical) {
+        GaugeValueAttention::Danger
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+fn menu_label(notification: &Notification) -> String {
+    let headline = notification.headline();
+    if headline.chars().count() <= MENU_LABEL_MAX_CHARS {
+        return headline;
+    }
+    let mut label: String = headline.chars().take(MENU_LABEL_MAX_CHARS - 1).collect();
+    label.push('…');
+    label
+}
+
+fn info_lines(entries: &VecDeque<Notification>) -> Vec<String> {
+    if entries.is_empty() {
+        return vec!["No notifications".to_string()];
+    }
+    let mut lines = Vec::new();
+    for notification in entries.iter().take(INFO_DIALOG_LINES) {
+        lines.push(notification.headline());
+        if !notification.body.trim().is_empty() {
+            lines.push(format!("  {}", notification.body.trim()));
+        }
+    }
+    if entries.len() > INFO_DIALOG_LINES {
+        lines.push(format!("…and {} more", entries.len() - INFO_DIALOG_LINES));
+    }
+    lines
+}
+
+fn notifications_menu(
+    entries: &VecDeque<Notification>,
+    on_select: MenuSelectAction,
+) -> Option<GaugeMenu> {
+    if entries.is_empty() {
+        return None;
+    }
+    let mut items = vec![GaugeMenuItem {
+        id: CLEAR_ALL_ITEM.to_string(),
+        label: "Dismiss all".to_string(),
+        selected: false,
+        exec: None,
+    }];
+    items.extend(entries.iter().map(|notification| GaugeMenuItem {
+        id: notification.id.to_string(),
+        label: menu_label(notification),
+        selected: notification.is_critical(),
+        exec: None,
+    }));
+
+    Some(GaugeMenu {
+        title: "Notifications".to_string(),
+        items,
+        on_select: Some(on_select),
+        slider: None,
+    })
+}
+
+/// Gauge showing the count of undismissed notifications.
+struct NotificationsGauge {
+    /// Notifications shared with the D-Bus server.
+    history: Arc<Mutex<NotificationHistory>>,
+    /// Sender used by menu selections to request dismissals.
+    dismiss_tx: mpsc::UnboundedSender<DismissRequest>,
+    /// Server handed to the work manager on registration.
+    service: Option<NotificationService>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for NotificationsGauge {
+    fn id(&self) -> &'static str {
+        "notifications"
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(service) = self.service.take() {
+            registrar.add_async_event_source(Box::new(service));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        // Updates are pushed by the server; the deadline is only a fallback refresh.
+        self.next_deadline = now + Duration::from_secs(3600);
+        let history = self.history.lock().ok()?;
+
+        let icon = svg_asset("bell.svg");
+        if let Some(err) = &history.error {
+            // Warning rather than a blank error: notifications are going somewhere else.
+            return Some(GaugeModel {
+                id: "notifications",
+                icon,
+                display: GaugeDisplay::Value {
+                    value: GaugeValue::Text("off".to_string()),
+                    attention: GaugeValueAttention::Warning,
+                },
+                interactions: GaugeInteractionModel {
+                    left_click: GaugePointerInteraction {
+                        info: Some(InfoDialog {
+                            title: "Notifications".to_string(),
+                            lines: vec![
+                                format!("Notification server unavailable: {err}"),
+                                format!("Stop the other daemon to let grelier own {BUS_NAME}"),
+                            ],
+                            graph: None,
+                        }),
+                        ..GaugePointerInteraction::default()
+                    },
+                    ..GaugeInteractionModel::default()
+                },
+            });
+        }
+
+        let dnd = dnd_enabled();
+        let mut lines = info_lines(&history.entries);
+        if dnd {
+            lines.insert(0, "Do Not Disturb is on".to_string());
+        }
+
+        let on_select: MenuSelectAction = {
+            let dismiss_tx = self.dismiss_tx.clone();
+            Arc::new(move |item_id: String| {
+                let request = if item_id == CLEAR_ALL_ITEM {
+                    Some(DismissRequest::All)
+                } else {
+                    item_id.parse().ok().map(DismissRequest::One)
+                };
+                if let Some(request) = request {
+                    let _ = dismiss_tx.unbounded_send(request);
+                }
+            })
+        };
+
+        Some(GaugeModel {
+            id: "notifications",
+            icon,
+            display: GaugeDisplay::Value {
+                value: GaugeValue::Text(history.entries.len().to_string()),
+                attention: notification_attention(&history.entries, dnd),
+            },
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Notifications".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                right_click: GaugePointerInteraction {
+                    menu: notifications_menu(&history.entries, on_select),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = NotificationsSettings::load();
+    let history = Arc::new(Mutex::new(NotificationHistory::new(settings.history_size)));
+    let (dismiss_tx, dismiss_rx) = mpsc::unbounded();
+
+    Box::new(NotificationsGauge {
+        history: Arc::clone(&history),
+        dismiss_tx,
+        service: Some(NotificationService {
+            history,
+            dismiss_rx,
+            ready_notify: None,
+            bus: ServiceBus::Starting,
+        }),
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    NotificationsSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "notifications",
+        description: "Notification server gauge showing undismissed notifications.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(app_name: &str, summary: &str, urgency: u8) -> Notification {
+        Notification {
+            id: 0,
+            app_name: app_name.to_string(),
+            summary: summary.to_string(),
+            body: String::new(),
+            urgency,
+        }
+    }
+
+    #[test]
+    fn history_replaces_in_place_and_drops_oldest() {
+        let mut history = NotificationHistory::new(2);
+        let (first, _) = history.insert(0, notification("mail", "1 new", 1));
+        let (second, _) = history.insert(0, notification("chat", "hi", 1));
+        assert_eq!((first, second), (1, 2));
+
+        assert_eq!(
+            history.insert(first, notification("mail", "2 new", 1)),
+            (first, Vec::new())
+        );
+        assert_eq!(history.entries.len(), 2);
+        assert_eq!(history.entries[1].summary, "2 new");
+
+        assert_eq!(
+            history.insert(0, notification("build", "done", 1)),
+            (3, vec![first])
+        );
+        let ids: Vec<u32> = history.entries.iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec![3, 2]);
+
+        assert!(history.remove(2));
+        assert!(!history.remove(2));
+        assert_eq!(history.clear(), vec![3]);
+    }
+
+    #[test]
+    fn critical_notifications_raise_danger_and_menu_lists_entries() {
+        let mut history = NotificationHistory::new(10);
+        history.insert(0, notification("mail", "1 new", 1));
+        assert_eq!(
+            notification_attention(&history.entries, false),
+            GaugeValueAttention::Nominal
+        );
+
+        history.insert(0, notification("battery", "Battery low", URGENCY_CRITICAL));
+        assert_eq!(
+            notification_attention(&history.entries, false),
+            GaugeValueAttention::Danger
+        );
+        assert_eq!(
+            notification_attention(&history.entries, true),
+            GaugeValueAttention::Nominal
+        );
+
+        let menu = notifications_menu(&history.entries, Arc::new(|_| {})).expect("menu");
+        let ids: Vec<&str> = menu.items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec![CLEAR_ALL_ITEM, "2", "1"]);
+        assert_eq!(menu.items[1].label, "battery: Battery low");
+        assert!(notifications_menu(&VecDeque::new(), Arc::new(|_| {})).is_none());
+    }
+}
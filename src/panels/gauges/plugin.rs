@@ -0,0 +1,389 @@
+// Third-party gauges loaded at startup from shared libraries in $XDG_CONFIG_HOME/grelier/plugins.
+// Each library exports `grelier_gauge_v1`, returning a C ABI descriptor for one gauge.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::{self, GaugeFactory, GaugeSpec};
+use crate::settings::{NO_SETTINGS, SettingSpec};
+use iced::widget::svg;
+use libloading::Library;
+use std::ffi::{CStr, c_char, c_void};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// ABI version a plugin descriptor must report.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+/// Symbol every plugin library exports: `const GrelierGaugeDescriptor *grelier_gauge_v1(void)`.
+const ENTRY_SYMBOL: &[u8] = b"grelier_gauge_v1\0";
+/// Plugins beyond this count are ignored; each one occupies a registry factory slot.
+const MAX_PLUGINS: usize = 8;
+const MIN_INTERVAL_MS: u32 = 100;
+
+/// Gauge description returned by a plugin's entry point; must outlive the library.
+#[repr(C)]
+pub struct GrelierGaugeDescriptor {
+    pub abi_version: u32,
+    /// Gauge id used in `grelier.gauges`; lowercase ASCII letters, digits, and `_`.
+    pub id: *const c_char,
+    /// One-line description for `--list-gauges`; may be null.
+    pub description: *const c_char,
+    /// Absolute path of an SVG icon; null selects the default plugin icon.
+    pub icon: *const c_char,
+    /// Poll cadence in milliseconds.
+    pub interval_ms: u32,
+    /// Allocate per-gauge state; may be null when the plugin is stateless.
+    pub create: Option<unsafe extern "C" fn() -> *mut c_void>,
+    /// Fill `output` and return 0, or return non-zero to show an error.
+    /// Runs on the shared gauge worker thread, so it must not block.
+    pub poll:
+        Option<unsafe extern "C" fn(state: *mut c_void, output: *mut GrelierGaugeOutput) -> i32>,
+    /// Release state returned by `create`; may be null.
+    pub destroy: Option<unsafe extern "C" fn(state: *mut c_void)>,
+}
+
+/// Buffer a plugin fills on every poll; all strings are NUL-terminated UTF-8.
+#[repr(C)]
+pub struct GrelierGaugeOutput {
+    /// Value shown on the bar.
+    pub text: [c_char; 32],
+    /// 0 = nominal, 1 = warning, 2 = danger.
+    pub attention: u32,
+    /// Info dialog contents, one line per `\n`.
+    pub info: [c_char; 1024],
+}
+
+impl Default for GrelierGaugeOutput {
+    fn default() -> Self {
+        Self {
+            text: [0; 32],
+            attention: 0,
+            info: [0; 1024],
+        }
+    }
+}
+
+type EntryPoint = unsafe extern "C" fn() -> *const GrelierGaugeDescriptor;
+type CreateFn = unsafe extern "C" fn() -> *mut c_void;
+type PollFn = unsafe extern "C" fn(*mut c_void, *mut GrelierGaugeOutput) -> i32;
+type DestroyFn = unsafe extern "C" fn(*mut c_void);
+
+/// A validated plugin kept loaded for the life of the process.
+struct LoadedPlugin {
+    id: &'static str,
+    description: &'static str,
+    path: PathBuf,
+    icon: Option<PathBuf>,
+    interval: Duration,
+    create: Option<CreateFn>,
+    poll: PollFn,
+    destroy: Option<DestroyFn>,
+    /// Keeps the callbacks above mapped.
+    _library: Library,
+}
+
+static LOADED: OnceLock<Vec<LoadedPlugin>> = OnceLock::new();
+
+/// Default plugin directory: `$XDG_CONFIG_HOME/grelier/plugins` or `~/.config/grelier/plugins`.
+pub fn plugin_dir() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default()
+        .join("grelier")
+        .join("plugins")
+}
+
+/// Copy a NUL-terminated string out of a fixed buffer, tolerating a missing terminator.
+fn buffer_text(buffer: &[c_char]) -> String {
+    let bytes: Vec<u8> = buffer
+        .iter()
+        .take_while(|&&byte| byte != 0)
+        .map(|&byte| byte as u8)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// # Safety
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn optional_str(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    // SAFETY: non-null and NUL-terminated per the caller's contract.
+    let text = unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .trim()
+        .to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn attention_from(value: u32) -> GaugeValueAttention {
+    match value {
+        1 => GaugeValueAttention::Warning,
+        2 => GaugeValueAttention::Danger,
+        _ => GaugeValueAttention::Nominal,
+    }
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .bytes()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_')
+}
+
+fn load_plugin(path: &Path) -> Result<LoadedPlugin, String> {
+    // SAFETY: loading runs the library's initializers; plugins in the user's
+    // config directory are trusted like any other program they install.
+    let library = unsafe { Library::new(path) }.map_err(|err| err.to_string())?;
+    let descriptor = {
+        // SAFETY: the symbol type is fixed by the plugin ABI.
+        let entry =
+            unsafe { library.get::<EntryPoint>(ENTRY_SYMBOL) }.map_err(|err| err.to_string())?;
+        // SAFETY: the entry point takes no arguments and returns a static descriptor.
+        unsafe { entry().as_ref() }.ok_or("entry point returned null")?
+    };
+    if descriptor.abi_version != PLUGIN_ABI_VERSION {
+        return Err(format!(
+            "unsupported ABI version {} (expected {PLUGIN_ABI_VERSION})",
+            descriptor.abi_version
+        ));
+    }
+    // SAFETY: descriptor strings are NUL-terminated or null per the ABI.
+    let (id, description, icon) = unsafe {
+        (
+            optional_str(descriptor.id).unwrap_or_default(),
+            optional_str(descriptor.description),
+            optional_str(descriptor.icon),
+        )
+    };
+    if !valid_id(&id) {
+        return Err(format!("invalid gauge id '{id}'"));
+    }
+    let poll = descriptor.poll.ok_or("descriptor has no poll callback")?;
+    let description =
+        description.unwrap_or_else(|| format!("Plugin gauge from {}", path.display()));
+
+    Ok(LoadedPlugin {
+        id: Box::leak(id.into_boxed_str()),
+        description: Box::leak(description.into_boxed_str()),
+        path: path.to_path_buf(),
+        icon: icon.map(PathBuf::from).filter(|icon| icon.is_absolute()),
+        interval: Duration::from_millis(u64::from(descriptor.interval_ms.max(MIN_INTERVAL_MS))),
+        create: descriptor.create,
+        poll,
+        destroy: descriptor.destroy,
+        _library: library,
+    })
+}
+
+/// Load every `*.so` in `dir` and register its gauge with the gauge registry.
+///
+/// Call once at startup, before the registry is queried. Broken plugins are logged and skipped.
+pub fn load_plugins(dir: &Path) {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "so"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+
+    let mut plugins: Vec<LoadedPlugin> = Vec::new();
+    for path in paths {
+        if plugins.len() == MAX_PLUGINS {
+            log::warn!(
+                "Ignoring plugin {}: at most {MAX_PLUGINS} plugins are supported",
+                path.display()
+            );
+            continue;
+        }
+        match load_plugin(&path) {
+            Ok(plugin) if plugins.iter().any(|other| other.id == plugin.id) => {
+                log::warn!(
+                    "Ignoring plugin {}: duplicate id '{}'",
+                    path.display(),
+                    plugin.id
+                );
+            }
+            Ok(plugin) => {
+                log::info!(
+                    "Loaded plugin gauge '{}' from {}",
+                    plugin.id,
+                    path.display()
+                );
+                plugins.push(plugin);
+            }
+            Err(err) => log::warn!("Failed to load plugin {}: {err}", path.display()),
+        }
+    }
+
+    let specs = plugins
+        .iter()
+        .zip(SLOT_FACTORIES)
+        .map(|(plugin, create)| GaugeSpec {
+            id: plugin.id,
+            description: plugin.description,
+            default_enabled: false,
+            settings: plugin_settings,
+            create,
+            validate: None,
+        })
+        .collect();
+    if LOADED.set(plugins).is_err() {
+        log::warn!("Plugins were already loaded");
+        return;
+    }
+    gauge_registry::register_dynamic(specs);
+}
+
+fn plugin_settings() -> &'static [SettingSpec] {
+    NO_SETTINGS
+}
+
+/// Gauge instance driving one loaded plugin.
+struct PluginGauge {
+    plugin: &'static LoadedPlugin,
+    /// Opaque state from the plugin's `create` callback.
+    state: *mut c_void,
+    icon: svg::Handle,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+// SAFETY: the ABI requires plugin state to be usable from whichever single thread
+// owns the gauge; grelier never shares it between threads concurrently.
+unsafe impl Send for PluginGauge {}
+
+impl Drop for PluginGauge {
+    fn drop(&mut self) {
+        if let Some(destroy) = self.plugin.destroy {
+            // SAFETY: `state` came from this plugin's `create` and is released once.
+            unsafe { destroy(self.state) };
+        }
+    }
+}
+
+impl Gauge for PluginGauge {
+    fn id(&self) -> &'static str {
+        self.plugin.id
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + self.plugin.interval;
+
+        let mut output = GrelierGaugeOutput::default();
+        // SAFETY: `state` belongs to this plugin and `output` is a valid, writable buffer.
+        let status = unsafe { (self.plugin.poll)(self.state, &mut output) };
+        let info = buffer_text(&output.info);
+        let mut lines: Vec<String> = info.lines().map(ToString::to_string).collect();
+        let display = if status == 0 {
+            GaugeDisplay::Value {
+                value: GaugeValue::Text(buffer_text(&output.text)),
+                attention: attention_from(output.attention),
+            }
+        } else {
+            lines.push(format!("Plugin returned error {status}"));
+            GaugeDisplay::Error
+        };
+        if lines.is_empty() {
+            lines.push(self.plugin.description.to_string());
+        }
+        lines.push(self.plugin.path.display().to_string());
+
+        Some(GaugeModel {
+            id: self.plugin.id,
+            icon: self.icon.clone(),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: self.plugin.id.to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+fn create_gauge(slot: usize, now: Instant) -> Box<dyn Gauge> {
+    let plugin = LOADED
+        .get()
+        .and_then(|plugins| plugins.get(slot))
+        .expect("plugin slot registered without a loaded plugin");
+    let state = match plugin.create {
+        // SAFETY: `create` takes no arguments and returns plugin-owned state.
+        Some(create) => unsafe { create() },
+        None => std::ptr::null_mut(),
+    };
+    Box::new(PluginGauge {
+        plugin,
+        state,
+        icon: plugin
+            .icon
+            .as_ref()
+            .map(svg::Handle::from_path)
+            .unwrap_or_else(|| svg_asset("plugin.svg")),
+        next_deadline: now,
+    })
+}
+
+/// Registry factories are plain `fn` pointers, so each plugin gets a fixed slot.
+macro_rules! slot_factories {
+    ($($slot:literal),*) => {
+        [$({
+            fn create(now: Instant) -> Box<dyn Gauge> {
+                create_gauge($slot, now)
+            }
+            create as GaugeFactory
+        }),*]
+    };
+}
+
+const SLOT_FACTORIES: [GaugeFactory; MAX_PLUGINS] = slot_factories!(0, 1, 2, 3, 4, 5, 6, 7);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_plugin_buffers_and_ids() {
+        let mut output = GrelierGaugeOutput::default();
+        for (slot, byte) in output.text.iter_mut().zip(b"42%") {
+            *slot = *byte as c_char;
+        }
+        assert_eq!(buffer_text(&output.text), "42%");
+        // A plugin that fills the whole buffer without a terminator is still readable.
+        output.text = [b'x' as c_char; 32];
+        assert_eq!(buffer_text(&output.text).len(), 32);
+        assert_eq!(attention_from(2), GaugeValueAttention::Danger);
+        assert_eq!(attention_from(9), GaugeValueAttention::Nominal);
+        assert!(valid_id("my_gauge2"));
+        assert!(!valid_id("My-Gauge"));
+    }
+
+    #[test]
+    fn rejects_libraries_that_are_not_plugins() {
+        let path =
+            std::env::temp_dir().join(format!("grelier_plugin_test_{}.so", std::process::id()));
+        fs::write(&path, b"not a shared object").expect("write fake plugin");
+        assert!(load_plugin(&path).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,246 @@
+// Pressure stall (PSI) gauge reading /proc/pressure for cpu, memory, and io.
+// Consumes Settings: grelier.gauge.pressure.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::{icon_quantity, svg_asset};
+use crate::panels::gauges::gauge::Gauge;
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_dependencies;
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::panels::gauges::gauge_tuning;
+use crate::settings::{self, SettingSpec, Settings};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub(crate) const PROC_PRESSURE: &str = "/proc/pressure";
+const WINDOW_KEY: &str = "grelier.gauge.pressure.window";
+/// Resources reported under /proc/pressure, with their dialog labels.
+const RESOURCES: [(&str, &str); 3] = [("cpu", "CPU"), ("memory", "Memory"), ("io", "IO")];
+
+settings::typed_settings! {
+    struct PressureSettings {
+        window: String = ("grelier.gauge.pressure.window", "avg60"),
+        warning_percent: f32 = ("grelier.gauge.pressure.warning_percent", "10"),
+        danger_percent: f32 = ("grelier.gauge.pressure.danger_percent", "30"),
+        poll_interval_secs: u64 = ("grelier.gauge.pressure.poll_interval_secs", "5", 1..=3600),
+    }
+}
+
+/// Stall percentages for one resource over the configured averaging window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Stall {
+    /// Share of time at least one task was stalled.
+    some: f32,
+    /// Share of time all non-idle tasks were stalled; absent for cpu on older kernels.
+    full: Option<f32>,
+}
+
+impl Stall {
+    /// The figure thresholds apply to: `full` when tasks were fully stalled, else `some`.
+    pub(crate) fn worst(self) -> f32 {
+        self.full.map_or(self.some, |full| full.max(self.some))
+    }
+}
+
+fn parse_window(value: &str) -> Result<&'static str, String> {
+    match value.trim() {
+        "avg10" => Ok("avg10"),
+        "avg60" => Ok("avg60"),
+        "avg300" => Ok("avg300"),
+        other => Err(format!(
+            "Invalid {WINDOW_KEY} '{other}', expected 'avg10', 'avg60', or 'avg300'"
+        )),
+    }
+}
+
+/// Parse a PSI file: `some avg10=0.00 avg60=0.00 avg300=0.00 total=0`, plus an optional `full` line.
+fn parse_psi(contents: &str, window: &str) -> Option<Stall> {
+    let mut some = None;
+    let mut full = None;
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next();
+        let value = fields
+            .filter_map(|field| field.split_once('='))
+            .find(|(key, _)| *key == window)
+            .and_then(|(_, value)| value.parse::<f32>().ok());
+        match kind {
+            Some("some") => some = value,
+            Some("full") => full = value,
+            _ => {}
+        }
+    }
+    // The kernel reports a zero "full" line for cpu; it carries no information.
+    Some(Stall {
+        some: some?,
+        full: full.filter(|full| *full > 0.0),
+    })
+}
+
+pub(crate) fn read_stalls(root: &Path, window: &str) -> Vec<(&'static str, Stall)> {
+    RESOURCES
+        .iter()
+        .filter_map(|(resource, label)| {
+            let contents = fs::read_to_string(root.join(resource)).ok()?;
+            Some((*label, parse_psi(&contents, window)?))
+        })
+        .collect()
+}
+
+fn pressure_attention(percent: f32, warning: f32, danger: f32) -> GaugeValueAttention {
+    if percent >= danger {
+        GaugeValueAttention::Danger
+    } else if percent >= warning {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+fn stall_line(label: &str, stall: Stall) -> String {
+    match stall.full {
+        Some(full) => format!("{label}: some {:.1}%, full {full:.1}%", stall.some),
+        None => format!("{label}: some {:.1}%", stall.some),
+    }
+}
+
+/// Gauge that shows the most stalled resource reported by PSI.
+struct PressureGauge {
+    /// Thresholds and poll cadence.
+    settings: PressureSettings,
+    /// Averaging window read from each PSI line.
+    window: &'static str,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for PressureGauge {
+    fn id(&self) -> &'static str {
+        "pressure"
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now
+            + Duration::from_secs(gauge_tuning::live_secs(
+                "pressure",
+                self.settings.poll_interval_secs,
+            ));
+
+        let stalls = read_stalls(Path::new(PROC_PRESSURE), self.window);
+        let worst = stalls
+            .iter()
+            .map(|(_, stall)| stall.worst())
+            .fold(None, |max: Option<f32>, value| {
+                Some(max.map_or(value, |max| max.max(value)))
+            });
+
+        let settings = &self.settings;
+        let (display, mut lines) = match worst {
+            Some(worst) => (
+                GaugeDisplay::Value {
+                    // The icon fills up as the worst stall approaches the danger threshold.
+                    value: GaugeValue::Svg(icon_quantity(
+                        worst / settings.danger_percent.max(f32::EPSILON),
+                    )),
+                    attention: pressure_attention(
+                        worst,
+                        settings.warning_percent,
+                        settings.danger_percent,
+                    ),
+                },
+                stalls
+                    .iter()
+                    .map(|(label, stall)| stall_line(label, *stall))
+                    .collect(),
+            ),
+            None => (GaugeDisplay::Error, {
+                let mut lines = vec!["Pressure stall information unavailable".to_string()];
+                lines.extend(gauge_dependencies::missing_hints("pressure"));
+                lines
+            }),
+        };
+        if worst.is_some() {
+            lines.push(format!("Window: {}", self.window));
+        }
+
+        Some(GaugeModel {
+            id: "pressure",
+            icon: svg_asset("pressure.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Pressure Stalls".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = PressureSettings::load();
+    Box::new(PressureGauge {
+        window: parse_window(&settings.window).unwrap_or("avg60"),
+        settings,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    PressureSettings::SPECS
+}
+
+fn validate(settings: &Settings) -> Result<(), String> {
+    parse_window(&settings.get_or(WINDOW_KEY, "avg60")).map(|_| ())
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "pressure",
+        description: "Pressure stall gauge showing sustained cpu, memory, and io stalls from /proc/pressure.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: Some(validate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_psi_lines_for_window() {
+        let memory = "some avg10=1.50 avg60=12.25 avg300=3.00 total=28967003\n\
+                      full avg10=0.50 avg60=40.00 avg300=1.00 total=18176027\n";
+        let stall = parse_psi(memory, "avg60").expect("memory stall");
+        assert_eq!(stall.some, 12.25);
+        assert_eq!(stall.full, Some(40.0));
+        assert_eq!(stall.worst(), 40.0);
+        assert_eq!(
+            pressure_attention(stall.worst(), 10.0, 30.0),
+            GaugeValueAttention::Danger
+        );
+
+        let cpu = "some avg10=8.00 avg60=2.00 avg300=1.00 total=5\n\
+                   full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n";
+        let stall = parse_psi(cpu, "avg10").expect("cpu stall");
+        assert_eq!(stall.full, None);
+        assert_eq!(stall_line("CPU", stall), "CPU: some 8.0%");
+
+        assert_eq!(parse_psi("garbage", "avg60"), None);
+        assert!(parse_window("avg5").is_err());
+    }
+}
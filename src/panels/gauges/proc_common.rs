@@ -0,0 +1,109 @@
+// Per-process figures read from /proc/<pid>/stat, for the cpu and ram breakdown dialogs.
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const PROC_ROOT: &str = "/proc";
+
+/// One process as reported by `/proc/<pid>/stat`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessStat {
+    pub pid: u32,
+    /// Command name (`comm`), at most 15 bytes.
+    pub name: String,
+    /// User plus system time in clock ticks since the process started.
+    pub cpu_ticks: u64,
+    /// Resident set size in pages.
+    pub rss_pages: u64,
+}
+
+/// Parse a `/proc/<pid>/stat` line. The command name is parenthesised and may itself
+/// contain spaces or parentheses, so fields are counted from the last `)`.
+pub fn parse_stat(pid: u32, contents: &str) -> Option<ProcessStat> {
+    let open = contents.find('(')?;
+    let close = contents.rfind(')')?;
+    let name = contents.get(open + 1..close)?.to_string();
+    // Fields after the name start at field 3 (state).
+    let fields: Vec<&str> = contents.get(close + 1..)?.split_whitespace().collect();
+    let field = |index: usize| fields.get(index - 3)?.parse::<u64>().ok();
+    Some(ProcessStat {
+        pid,
+        name,
+        cpu_ticks: field(14)?.saturating_add(field(15)?),
+        rss_pages: field(24)?,
+    })
+}
+
+/// Every process currently under `root`; processes that exit mid-scan are skipped.
+pub fn read_processes(root: &Path) -> Vec<ProcessStat> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let contents = fs::read_to_string(entry.path().join("stat")).ok()?;
+            parse_stat(pid, &contents)
+        })
+        .collect()
+}
+
+/// CPU ticks each process used since the previous call, as `(name, ticks)` sorted busiest first.
+#[derive(Debug, Default)]
+pub struct ProcessCpuTracker {
+    previous: HashMap<u32, u64>,
+}
+
+impl ProcessCpuTracker {
+    pub fn sample(&mut self, processes: &[ProcessStat]) -> Vec<(String, u64)> {
+        let mut deltas: Vec<(String, u64)> = processes
+            .iter()
+            .filter_map(|process| {
+                // A process seen for the first time has no baseline yet.
+                let previous = self.previous.get(&process.pid)?;
+                let delta = process.cpu_ticks.saturating_sub(*previous);
+                (delta > 0).then(|| (process.name.clone(), delta))
+            })
+            .collect();
+        self.previous = processes
+            .iter()
+            .map(|process| (process.pid, process.cpu_ticks))
+            .collect();
+        deltas.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        deltas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stat_with_odd_names_and_tracks_deltas() {
+        let line = "42 (tmux: server) S 1 42 42 0 -1 4194560 1 0 0 0 \
+                    120 30 0 0 20 0 1 0 100 1000 512 18446744073709551615";
+        let stat = parse_stat(42, line).expect("stat");
+        assert_eq!(stat.name, "tmux: server");
+        assert_eq!(stat.cpu_ticks, 150);
+        assert_eq!(stat.rss_pages, 512);
+        assert_eq!(parse_stat(1, "1 (init"), None);
+
+        let mut tracker = ProcessCpuTracker::default();
+        assert!(tracker.sample(std::slice::from_ref(&stat)).is_empty());
+        let busier = ProcessStat {
+            cpu_ticks: 190,
+            ..stat.clone()
+        };
+        let newcomer = ProcessStat {
+            pid: 7,
+            name: "sh".to_string(),
+            cpu_ticks: 5,
+            rss_pages: 1,
+        };
+        assert_eq!(
+            tracker.sample(&[busier, newcomer]),
+            vec![("tmux: server".to_string(), 40)]
+        );
+    }
+}
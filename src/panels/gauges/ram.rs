@@ -1,37 +1,50 @@
 // RAM utilization gauge with adaptive polling and optional ZFS ARC accounting.
+// Swap usage raises the attention level; the info dialog lists the largest processes.
 // Consumes Settings: grelier.gauge.ram.*.
 use crate::dialog::info::InfoDialog;
 use crate::icon::{icon_quantity, svg_asset};
 use crate::panels::gauges::gauge::Gauge;
 use crate::panels::gauges::gauge::{
-    GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
-    GaugeValueAttention,
+    GaugeDisplay, GaugeHistory, GaugeInteractionModel, GaugeModel, GaugePointerInteraction,
+    GaugeValue, GaugeValueAttention,
 };
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
+use crate::panels::gauges::proc_common::{self, ProcessStat};
+use crate::settings::{self, SettingSpec};
+use crate::units::Units;
 use std::fs::{File, read_to_string};
 use std::io::{BufRead, BufReader};
+use std::path::Path;
 use std::time::{Duration, Instant};
 
-const DEFAULT_WARNING_THRESHOLD: f32 = 0.10;
-const DEFAULT_DANGER_THRESHOLD: f32 = 0.05;
-const DEFAULT_FAST_THRESHOLD: f32 = 0.70;
-const DEFAULT_FAST_INTERVAL_SECS: u64 = 1;
-const DEFAULT_SLOW_INTERVAL_SECS: u64 = 4;
-const DEFAULT_CALM_TICKS: u8 = 4;
+settings::typed_settings! {
+    struct RamSettings {
+        warning_threshold: f32 = ("grelier.gauge.ram.warning_threshold", "0.10"),
+        danger_threshold: f32 = ("grelier.gauge.ram.danger_threshold", "0.05"),
+        fast_threshold: f32 = ("grelier.gauge.ram.fast_threshold", "0.70", 0.0..=1.0),
+        calm_ticks: u8 = ("grelier.gauge.ram.calm_ticks", "4"),
+        fast_interval_secs: u64 = ("grelier.gauge.ram.fast_interval_secs", "1", 1..=3600),
+        slow_interval_secs: u64 = ("grelier.gauge.ram.slow_interval_secs", "4", 1..=3600),
+        graph: bool = ("grelier.gauge.ram.graph", "false"),
+        swap_warning_threshold: f32 = ("grelier.gauge.ram.swap_warning_threshold", "0.50", 0.0..=1.0),
+        swap_danger_threshold: f32 = ("grelier.gauge.ram.swap_danger_threshold", "0.80", 0.0..=1.0),
+        top_processes: usize = ("grelier.gauge.ram.top_processes", "5", 0..=20),
+    }
+}
 
 #[derive(Default)]
-struct MemorySnapshot {
-    total: u64,
+pub(crate) struct MemorySnapshot {
+    pub(crate) total: u64,
     available: u64,
     free: u64,
+    swap_total: u64,
+    swap_free: u64,
     zfs_arc_cache: u64,
     zfs_arc_min: u64,
 }
 
 impl MemorySnapshot {
-    fn read() -> Option<Self> {
+    pub(crate) fn read() -> Option<Self> {
         let file = File::open("/proc/meminfo").ok()?;
         let mut snapshot = MemorySnapshot::default();
 
@@ -52,6 +65,8 @@ impl MemorySnapshot {
                 "MemTotal:" => snapshot.total = value,
                 "MemAvailable:" => snapshot.available = value,
                 "MemFree:" => snapshot.free = value,
+                "SwapTotal:" => snapshot.swap_total = value,
+                "SwapFree:" => snapshot.swap_free = value,
                 _ => continue,
             }
         }
@@ -92,7 +107,16 @@ impl MemorySnapshot {
         self.zfs_arc_cache.saturating_sub(self.zfs_arc_min)
     }
 
-    fn available_bytes(&self) -> u64 {
+    fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+
+    /// Share of swap in use; `None` without swap.
+    fn swap_ratio(&self) -> Option<f32> {
+        (self.swap_total > 0).then(|| self.swap_used() as f32 / self.swap_total as f32)
+    }
+
+    pub(crate) fn available_bytes(&self) -> u64 {
         let base_available = if self.available != 0 {
             self.available.min(self.total)
         } else {
@@ -103,23 +127,6 @@ impl MemorySnapshot {
     }
 }
 
-fn format_bytes(bytes: u64) -> String {
-    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
-    let mut value = bytes as f64;
-    let mut unit = 0usize;
-    while value >= 1024.0 && unit < UNITS.len() - 1 {
-        value /= 1024.0;
-        unit += 1;
-    }
-    if unit == 0 {
-        format!("{:.0} {}", value, UNITS[unit])
-    } else if value < 10.0 {
-        format!("{:.1} {}", value, UNITS[unit])
-    } else {
-        format!("{:.0} {}", value, UNITS[unit])
-    }
-}
-
 fn attention_for_free_ratio(
     free_ratio: f32,
     warning_threshold: f32,
@@ -134,6 +141,59 @@ fn attention_for_free_ratio(
     }
 }
 
+fn attention_for_swap_ratio(
+    swap_ratio: f32,
+    warning_threshold: f32,
+    danger_threshold: f32,
+) -> GaugeValueAttention {
+    if swap_ratio >= danger_threshold {
+        GaugeValueAttention::Danger
+    } else if swap_ratio >= warning_threshold {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+/// Raise the attention of a value display to at least `floor`.
+fn raise_attention(display: GaugeDisplay, floor: GaugeValueAttention) -> GaugeDisplay {
+    match display {
+        GaugeDisplay::Value { value, attention } => GaugeDisplay::Value {
+            value,
+            attention: attention.max(floor),
+        },
+        other => other,
+    }
+}
+
+/// The `limit` processes with the largest resident set, as dialog lines.
+fn process_lines(
+    mut processes: Vec<ProcessStat>,
+    page_size: u64,
+    limit: usize,
+    units: &Units,
+) -> Vec<String> {
+    processes.sort_by(|a, b| b.rss_pages.cmp(&a.rss_pages).then(a.pid.cmp(&b.pid)));
+    processes
+        .iter()
+        .filter(|process| process.rss_pages > 0)
+        .take(limit)
+        .map(|process| {
+            format!(
+                "{}: {}",
+                process.name,
+                units.format_bytes(process.rss_pages.saturating_mul(page_size))
+            )
+        })
+        .collect()
+}
+
+fn page_size() -> u64 {
+    // SAFETY: sysconf only reads a system configuration value.
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 { size as u64 } else { 4096 }
+}
+
 fn ram_value(
     utilization: Option<f32>,
     free_ratio: Option<f32>,
@@ -165,6 +225,17 @@ struct RamState {
 }
 
 impl RamState {
+    fn new(settings: &RamSettings) -> Self {
+        Self {
+            fast_interval: false,
+            below_threshold_streak: 0,
+            fast_threshold: settings.fast_threshold,
+            calm_ticks: settings.calm_ticks,
+            fast_interval_duration: Duration::from_secs(settings.fast_interval_secs),
+            slow_interval_duration: Duration::from_secs(settings.slow_interval_secs),
+        }
+    }
+
     fn update_interval_state(&mut self, utilization: f32) {
         if utilization > self.fast_threshold {
             self.fast_interval = true;
@@ -195,6 +266,16 @@ struct RamGauge {
     warning_threshold: f32,
     /// Utilization threshold where the gauge switches to danger attention.
     danger_threshold: f32,
+    /// Swap usage share where the gauge switches to warning attention.
+    swap_warning_threshold: f32,
+    /// Swap usage share where the gauge switches to danger attention.
+    swap_danger_threshold: f32,
+    /// Number of processes listed in the info dialog.
+    top_processes: usize,
+    /// Unit preferences for the info dialog.
+    units: Units,
+    /// Recent utilization drawn as a sparkline when `grelier.gauge.ram.graph` is on.
+    history: Option<GaugeHistory>,
     /// Scheduler deadline for the next run.
     next_deadline: Instant,
 }
@@ -230,15 +311,24 @@ impl Gauge for RamGauge {
             }
         });
 
-        let lines = if let Some(snapshot) = snapshot.as_ref() {
+        let mut lines = if let Some(snapshot) = snapshot.as_ref() {
             let available = snapshot.available_bytes();
             let reserved = available.saturating_sub(snapshot.free);
             let used = snapshot.total.saturating_sub(available);
             vec![
-                format!("Total: {}", format_bytes(snapshot.total)),
-                format!("Free: {}", format_bytes(snapshot.free)),
-                format!("Reserved: {}", format_bytes(reserved)),
-                format!("Used: {}", format_bytes(used)),
+                format!("Total: {}", self.units.format_bytes(snapshot.total)),
+                format!("Free: {}", self.units.format_bytes(snapshot.free)),
+                format!("Reserved: {}", self.units.format_bytes(reserved)),
+                format!("Used: {}", self.units.format_bytes(used)),
+                if snapshot.swap_total > 0 {
+                    format!(
+                        "Swap: {} of {}",
+                        self.units.format_bytes(snapshot.swap_used()),
+                        self.units.format_bytes(snapshot.swap_total)
+                    )
+                } else {
+                    "Swap: none".to_string()
+                },
             ]
         } else {
             vec![
@@ -248,26 +338,52 @@ impl Gauge for RamGauge {
                 "Used: N/A".to_string(),
             ]
         };
+        if self.top_processes > 0 {
+            lines.extend(process_lines(
+                proc_common::read_processes(Path::new(proc_common::PROC_ROOT)),
+                page_size(),
+                self.top_processes,
+                &self.units,
+            ));
+        }
 
         if let Some(utilization) = utilization {
             self.state.update_interval_state(utilization);
         }
         self.next_deadline = now + self.state.interval();
+        let display = ram_value(
+            utilization,
+            free_ratio,
+            self.warning_threshold,
+            self.danger_threshold,
+        );
+        let swap_attention = snapshot
+            .as_ref()
+            .and_then(MemorySnapshot::swap_ratio)
+            .map(|ratio| {
+                attention_for_swap_ratio(
+                    ratio,
+                    self.swap_warning_threshold,
+                    self.swap_danger_threshold,
+                )
+            })
+            .unwrap_or_default();
+        let display = raise_attention(display, swap_attention);
+        let display = match self.history.as_mut() {
+            Some(history) => history.record(utilization, display),
+            None => display,
+        };
 
         Some(GaugeModel {
             id: "ram",
             icon: svg_asset("ram.svg"),
-            display: ram_value(
-                utilization,
-                free_ratio,
-                self.warning_threshold,
-                self.danger_threshold,
-            ),
+            display,
             interactions: GaugeInteractionModel {
                 left_click: GaugePointerInteraction {
                     info: Some(InfoDialog {
                         title: "RAM".to_string(),
                         lines,
+                        graph: None,
                     }),
                     ..GaugePointerInteraction::default()
                 },
@@ -278,14 +394,9 @@ impl Gauge for RamGauge {
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
-    let warning_threshold_raw = settings::settings().get_parsed_or(
-        "grelier.gauge.ram.warning_threshold",
-        DEFAULT_WARNING_THRESHOLD,
-    );
-    let danger_threshold_raw = settings::settings().get_parsed_or(
-        "grelier.gauge.ram.danger_threshold",
-        DEFAULT_DANGER_THRESHOLD,
-    );
+    let settings = RamSettings::load();
+    let warning_threshold_raw = settings.warning_threshold;
+    let danger_threshold_raw = settings.danger_threshold;
     let (warning_threshold, danger_threshold) = {
         let warning = if warning_threshold_raw > 0.5 {
             (1.0 - warning_threshold_raw).clamp(0.0, 1.0)
@@ -303,62 +414,22 @@ pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
             (warning, danger)
         }
     };
-    let fast_threshold = settings::settings()
-        .get_parsed_or("grelier.gauge.ram.fast_threshold", DEFAULT_FAST_THRESHOLD);
-    let calm_ticks =
-        settings::settings().get_parsed_or("grelier.gauge.ram.calm_ticks", DEFAULT_CALM_TICKS);
-    let fast_interval_secs = settings::settings().get_parsed_or(
-        "grelier.gauge.ram.fast_interval_secs",
-        DEFAULT_FAST_INTERVAL_SECS,
-    );
-    let slow_interval_secs = settings::settings().get_parsed_or(
-        "grelier.gauge.ram.slow_interval_secs",
-        DEFAULT_SLOW_INTERVAL_SECS,
-    );
 
     Box::new(RamGauge {
-        state: RamState {
-            fast_interval: false,
-            below_threshold_streak: 0,
-            fast_threshold,
-            calm_ticks,
-            fast_interval_duration: Duration::from_secs(fast_interval_secs),
-            slow_interval_duration: Duration::from_secs(slow_interval_secs),
-        },
+        state: RamState::new(&settings),
         warning_threshold,
         danger_threshold,
+        swap_warning_threshold: settings.swap_warning_threshold,
+        swap_danger_threshold: settings.swap_danger_threshold,
+        top_processes: settings.top_processes,
+        units: Units::load(),
+        history: settings.graph.then(GaugeHistory::from_settings),
         next_deadline: now,
     })
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[
-        SettingSpec {
-            key: "grelier.gauge.ram.warning_threshold",
-            default: "0.10",
-        },
-        SettingSpec {
-            key: "grelier.gauge.ram.danger_threshold",
-            default: "0.05",
-        },
-        SettingSpec {
-            key: "grelier.gauge.ram.fast_threshold",
-            default: "0.70",
-        },
-        SettingSpec {
-            key: "grelier.gauge.ram.calm_ticks",
-            default: "4",
-        },
-        SettingSpec {
-            key: "grelier.gauge.ram.fast_interval_secs",
-            default: "1",
-        },
-        SettingSpec {
-            key: "grelier.gauge.ram.slow_interval_secs",
-            default: "4",
-        },
-    ];
-    SETTINGS
+    RamSettings::SPECS
 }
 
 inventory::submit! {
@@ -378,20 +449,14 @@ mod tests {
 
     #[test]
     fn ram_interval_speeds_up_and_recovers() {
-        let mut state = RamState {
-            fast_threshold: DEFAULT_FAST_THRESHOLD,
-            calm_ticks: DEFAULT_CALM_TICKS,
-            fast_interval_duration: Duration::from_secs(DEFAULT_FAST_INTERVAL_SECS),
-            slow_interval_duration: Duration::from_secs(DEFAULT_SLOW_INTERVAL_SECS),
-            fast_interval: false,
-            below_threshold_streak: 0,
-        };
+        let defaults = RamSettings::default();
+        let mut state = RamState::new(&defaults);
 
         // Jump to fast interval when utilization crosses threshold.
         state.update_interval_state(0.8);
         assert_eq!(
             state.interval(),
-            Duration::from_secs(DEFAULT_FAST_INTERVAL_SECS)
+            Duration::from_secs(defaults.fast_interval_secs)
         );
 
         // Stay fast for several below-threshold ticks.
@@ -399,7 +464,7 @@ mod tests {
             state.update_interval_state(0.5);
             assert_eq!(
                 state.interval(),
-                Duration::from_secs(DEFAULT_FAST_INTERVAL_SECS)
+                Duration::from_secs(defaults.fast_interval_secs)
             );
         }
 
@@ -407,18 +472,59 @@ mod tests {
         state.update_interval_state(0.5);
         assert_eq!(
             state.interval(),
-            Duration::from_secs(DEFAULT_SLOW_INTERVAL_SECS)
+            Duration::from_secs(defaults.slow_interval_secs)
         );
     }
 
     #[test]
     fn returns_none_on_missing_utilization() {
+        let defaults = RamSettings::default();
         let display = ram_value(
             None,
             None,
-            DEFAULT_WARNING_THRESHOLD,
-            DEFAULT_DANGER_THRESHOLD,
+            defaults.warning_threshold,
+            defaults.danger_threshold,
         );
         assert!(matches!(display, GaugeDisplay::Error));
     }
+
+    #[test]
+    fn swap_raises_attention_and_processes_sort_by_rss() {
+        let snapshot = MemorySnapshot {
+            swap_total: 1000,
+            swap_free: 100,
+            ..MemorySnapshot::default()
+        };
+        let swap = attention_for_swap_ratio(snapshot.swap_ratio().expect("swap"), 0.5, 0.8);
+        assert_eq!(swap, GaugeValueAttention::Danger);
+        assert_eq!(MemorySnapshot::default().swap_ratio(), None);
+        let display = ram_value(Some(0.3), Some(0.7), 0.1, 0.05);
+        assert!(matches!(
+            raise_attention(display, swap),
+            GaugeDisplay::Value {
+                attention: GaugeValueAttention::Danger,
+                ..
+            }
+        ));
+
+        let process = |pid, name: &str, rss_pages| ProcessStat {
+            pid,
+            name: name.to_string(),
+            cpu_ticks: 0,
+            rss_pages,
+        };
+        let units = Units::default();
+        let lines = process_lines(
+            vec![
+                process(1, "init", 2),
+                process(2, "firefox", 512),
+                process(3, "kworker", 0),
+            ],
+            4096,
+            5,
+            &units,
+        );
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("firefox: "));
+    }
 }
@@ -0,0 +1,302 @@
+// Watchdog for the gauge worker thread: samples its stack while a gauge run overstays `max_run`.
+// The sample is kept per gauge and attached to the log entry when the gauge is marked Dead.
+use std::collections::HashMap;
+use std::ffi::{CStr, c_int, c_void};
+use std::fmt::Write as _;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Once, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MAX_FRAMES: usize = 48;
+/// How long the watchdog waits for the worker's signal handler to record its frames.
+const CAPTURE_TIMEOUT: Duration = Duration::from_millis(250);
+
+// Written by the signal handler on the worker thread, read by the watchdog after `CAPTURED`.
+static FRAMES: [AtomicUsize; MAX_FRAMES] = [const { AtomicUsize::new(0) }; MAX_FRAMES];
+static FRAME_COUNT: AtomicUsize = AtomicUsize::new(0);
+static CAPTURED: AtomicBool = AtomicBool::new(false);
+// Serializes captures so concurrent samplers never interleave frame buffers.
+static CAPTURE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Realtime signal used to interrupt the worker; handled with SA_RESTART so syscalls resume.
+fn sample_signal() -> c_int {
+    libc::SIGRTMIN() + 1
+}
+
+#[cfg(target_env = "gnu")]
+fn record_frames() {
+    let mut buf = [std::ptr::null_mut::<c_void>(); MAX_FRAMES];
+    // SAFETY: `buf` holds MAX_FRAMES pointers; backtrace writes at most that many.
+    let count = unsafe { libc::backtrace(buf.as_mut_ptr(), MAX_FRAMES as c_int) };
+    let count = usize::try_from(count).unwrap_or(0);
+    for (slot, frame) in FRAMES.iter().zip(&buf[..count]) {
+        slot.store(*frame as usize, Ordering::Relaxed);
+    }
+    FRAME_COUNT.store(count, Ordering::Relaxed);
+}
+
+#[cfg(not(target_env = "gnu"))]
+fn record_frames() {
+    FRAME_COUNT.store(0, Ordering::Relaxed);
+}
+
+extern "C" fn on_sample_signal(_signal: c_int) {
+    record_frames();
+    CAPTURED.store(true, Ordering::Release);
+}
+
+fn install_handler() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        // Unwind once up front so the unwinder library is loaded outside the signal handler.
+        record_frames();
+        // SAFETY: the handler only touches atomics and glibc's backtrace, which is safe to call
+        // from a handler once it has been initialized above.
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_sample_signal as *const () as usize;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(sample_signal(), &action, std::ptr::null_mut()) != 0 {
+                log::warn!("gauge watchdog: failed to install stack sampling handler");
+            }
+        }
+    });
+}
+
+/// Handle to a thread that can be sampled from another thread.
+#[derive(Clone, Copy)]
+struct SampledThread {
+    pthread: libc::pthread_t,
+    tid: libc::pid_t,
+}
+
+impl SampledThread {
+    fn current() -> Self {
+        // SAFETY: both calls only query the calling thread.
+        unsafe {
+            Self {
+                pthread: libc::pthread_self(),
+                tid: libc::gettid(),
+            }
+        }
+    }
+
+    /// Kernel view of the thread (state, wait channel, current syscall) plus its frames.
+    fn sample(&self) -> String {
+        let mut out = String::new();
+        let task = format!("/proc/self/task/{}", self.tid);
+        if let Ok(stat) = std::fs::read_to_string(format!("{task}/stat"))
+            && let Some(state) = stat
+                .rsplit_once(')')
+                .and_then(|(_, rest)| rest.split_whitespace().next())
+        {
+            let _ = writeln!(out, "state: {state}");
+        }
+        if let Ok(wchan) = std::fs::read_to_string(format!("{task}/wchan"))
+            && !wchan.trim().is_empty()
+            && wchan.trim() != "0"
+        {
+            let _ = writeln!(out, "wchan: {}", wchan.trim());
+        }
+        if let Ok(syscall) = std::fs::read_to_string(format!("{task}/syscall")) {
+            let _ = writeln!(out, "syscall: {}", syscall.trim());
+        }
+        match self.capture_frames() {
+            Some(frames) => {
+                for (idx, frame) in frames.into_iter().enumerate() {
+                    let _ = writeln!(out, "#{idx:<2} {}", describe_frame(frame));
+                }
+            }
+            None => out.push_str("frames: unavailable\n"),
+        }
+        out
+    }
+
+    fn capture_frames(&self) -> Option<Vec<usize>> {
+        let _guard = CAPTURE_LOCK.lock().ok()?;
+        CAPTURED.store(false, Ordering::Release);
+        // SAFETY: the handle came from pthread_self on the watched thread, and the sampler holds
+        // `WatchState::alive`, which that thread clears when it drops the watchdog, before exiting.
+        if unsafe { libc::pthread_kill(self.pthread, sample_signal()) } != 0 {
+            return None;
+        }
+        let deadline = Instant::now() + CAPTURE_TIMEOUT;
+        while !CAPTURED.load(Ordering::Acquire) {
+            if Instant::now() >= deadline {
+                return None;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        let count = FRAME_COUNT.load(Ordering::Relaxed).min(MAX_FRAMES);
+        let frames: Vec<usize> = FRAMES[..count]
+            .iter()
+            .map(|slot| slot.load(Ordering::Relaxed))
+            .collect();
+        (!frames.is_empty()).then_some(frames)
+    }
+}
+
+/// `0xaddr symbol+0xoff (object+0xoff)`; object offsets can be fed to `addr2line -e <object>`.
+fn describe_frame(addr: usize) -> String {
+    // SAFETY: dladdr only reads loader metadata; a zeroed Dl_info is a valid out-parameter.
+    let mut info: libc::Dl_info = unsafe { std::mem::zeroed() };
+    if unsafe { libc::dladdr(addr as *const c_void, &mut info) } == 0 {
+        return format!("{addr:#x}");
+    }
+    let mut out = format!("{addr:#x}");
+    if !info.dli_sname.is_null() {
+        // SAFETY: dladdr returned a NUL-terminated symbol name owned by the loader.
+        let name = unsafe { CStr::from_ptr(info.dli_sname) }.to_string_lossy();
+        let _ = write!(
+            out,
+            " {name}+{:#x}",
+            addr.saturating_sub(info.dli_saddr as usize)
+        );
+    }
+    if !info.dli_fname.is_null() {
+        // SAFETY: as above, the object path is owned by the loader.
+        let object = unsafe { CStr::from_ptr(info.dli_fname) }.to_string_lossy();
+        let _ = write!(
+            out,
+            " ({object}+{:#x})",
+            addr.saturating_sub(info.dli_fbase as usize)
+        );
+    }
+    out
+}
+
+struct ActiveRun {
+    id: &'static str,
+    started: Instant,
+    sampled: bool,
+}
+
+struct WatchState {
+    /// Cleared by the watched thread when it drops the watchdog; held while sampling it.
+    alive: Mutex<bool>,
+    current: Mutex<Option<ActiveRun>>,
+    /// Most recent stack sample per gauge, taken during its last overlong run.
+    samples: Mutex<HashMap<&'static str, String>>,
+}
+
+/// Samples the thread that created it whenever a gauge run exceeds `max_run`.
+///
+/// Must be dropped on the watched thread, which the raw pointer marker enforces by making it
+/// `!Send`; dropping it stops all sampling before that thread can exit.
+pub struct RunWatchdog {
+    state: Arc<WatchState>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl RunWatchdog {
+    /// Watch the calling thread; spawns the sampler thread.
+    pub fn spawn(max_run: Duration) -> Self {
+        install_handler();
+        let worker = SampledThread::current();
+        let state = Arc::new(WatchState {
+            alive: Mutex::new(true),
+            current: Mutex::new(None),
+            samples: Mutex::new(HashMap::new()),
+        });
+        let watched = Arc::clone(&state);
+        let interval = (max_run / 2).max(Duration::from_millis(5));
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+                // Stop once the worker has dropped its handle.
+                if !watched.alive.lock().is_ok_and(|alive| *alive) {
+                    return;
+                }
+                let overdue = match watched.current.lock() {
+                    Ok(mut current) => match current.as_mut() {
+                        Some(run) if !run.sampled && run.started.elapsed() > max_run => {
+                            run.sampled = true;
+                            Some(run.id)
+                        }
+                        _ => None,
+                    },
+                    Err(_) => return,
+                };
+                let Some(id) = overdue else {
+                    continue;
+                };
+                // Holding `alive` keeps the worker from finishing its drop, so it cannot exit
+                // while being signalled.
+                let Ok(alive) = watched.alive.lock() else {
+                    return;
+                };
+                if !*alive {
+                    return;
+                }
+                let sample = worker.sample();
+                drop(alive);
+                if let Ok(mut samples) = watched.samples.lock() {
+                    samples.insert(id, sample);
+                }
+            }
+        });
+        Self {
+            state,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Mark the start of a gauge run on the watched thread.
+    pub fn begin(&self, id: &'static str) {
+        if let Ok(mut current) = self.state.current.lock() {
+            *current = Some(ActiveRun {
+                id,
+                started: Instant::now(),
+                sampled: false,
+            });
+        }
+    }
+
+    /// Mark the end of the current gauge run.
+    pub fn end(&self) {
+        if let Ok(mut current) = self.state.current.lock() {
+            *current = None;
+        }
+    }
+
+    /// Take the latest stack sample recorded for `id`, if any run was caught overstaying.
+    pub fn take_sample(&self, id: &str) -> Option<String> {
+        self.state.samples.lock().ok()?.remove(id)
+    }
+}
+
+impl Drop for RunWatchdog {
+    fn drop(&mut self) {
+        let mut alive = self
+            .state
+            .alive
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *alive = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_a_run_that_overstays() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            let watchdog = RunWatchdog::spawn(Duration::from_millis(10));
+            watchdog.begin("slow");
+            thread::sleep(Duration::from_millis(150));
+            watchdog.end();
+            let _ = tx.send((watchdog.take_sample("slow"), watchdog.take_sample("slow")));
+        });
+        let (sample, again) = rx.recv().expect("worker finished");
+        let sample = sample.expect("sample recorded");
+        assert!(sample.contains("state: "), "{sample}");
+        assert!(sample.contains("#0 ") || sample.contains("frames: unavailable"));
+        assert!(again.is_none());
+    }
+}
@@ -0,0 +1,309 @@
+// Screen sharing gauge: turns red while any app holds an xdg-desktop-portal ScreenCast session,
+// and stops the cast on click. Sessions are read from the portal backend's object tree, since
+// the portal itself does not list them.
+// Consumes Settings: grelier.gauge.screenshare.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{Gauge, GaugeReadyNotify};
+use crate::panels::gauges::gauge::{
+    GaugeClickAction, GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel,
+    GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::panels::gauges::proc_common;
+use crate::settings::{self, SettingSpec};
+use std::fs;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use zbus::blocking::Connection;
+use zbus::blocking::fdo::{DBusProxy, IntrospectableProxy};
+use zbus::names::BusName;
+
+/// Where portal backends export sessions, as `<root>/<sender>/<token>`.
+const SESSION_ROOT: &str = "/org/freedesktop/portal/desktop/session";
+const SESSION_INTERFACE: &str = "org.freedesktop.impl.portal.Session";
+
+settings::typed_settings! {
+    struct ScreenshareSettings {
+        backend: String = ("grelier.gauge.screenshare.backend", "org.freedesktop.impl.portal.desktop.wlr"),
+        poll_interval_secs: u64 = ("grelier.gauge.screenshare.poll_interval_secs", "2", 1..=60),
+    }
+}
+
+/// One active screencast session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ShareSession {
+    /// Object path of the session on the backend.
+    path: String,
+    /// Process name of the app that started it, or its bus name when that is unknown.
+    app: String,
+}
+
+/// Names of the child nodes in an introspection document.
+fn child_nodes(xml: &str) -> Vec<String> {
+    xml.split("<node name=\"")
+        .skip(1)
+        .filter_map(|rest| rest.split('"').next())
+        .filter(|name| !name.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// The portal names session paths after the caller's unique name, `:1.42` becoming `1_42`.
+fn sender_bus_name(component: &str) -> String {
+    format!(":{}", component.replace('_', "."))
+}
+
+fn app_name(dbus: &DBusProxy<'_>, sender: &str) -> Option<String> {
+    let pid = dbus
+        .get_connection_unix_process_id(BusName::try_from(sender).ok()?)
+        .ok()?;
+    let stat = fs::read_to_string(format!("{}/{pid}/stat", proc_common::PROC_ROOT)).ok()?;
+    proc_common::parse_stat(pid, &stat).map(|process| process.name)
+}
+
+fn children(connection: &Connection, backend: &str, path: &str) -> Vec<String> {
+    IntrospectableProxy::builder(connection)
+        .destination(backend)
+        .and_then(|builder| builder.path(path))
+        .and_then(|builder| builder.build())
+        .and_then(|proxy| proxy.introspect().map_err(zbus::Error::from))
+        .map(|xml| child_nodes(&xml))
+        .unwrap_or_default()
+}
+
+fn list_sessions(connection: &Connection, backend: &str) -> Result<Vec<ShareSession>, String> {
+    let dbus = DBusProxy::new(connection).map_err(|err| format!("session bus: {err}"))?;
+    let name = BusName::try_from(backend).map_err(|err| format!("invalid backend: {err}"))?;
+    // Introspecting an activatable backend would start it, so an idle one is left alone.
+    if !dbus
+        .name_has_owner(name)
+        .map_err(|err| format!("session bus: {err}"))?
+    {
+        return Ok(Vec::new());
+    }
+
+    let mut sessions = Vec::new();
+    for sender in children(connection, backend, SESSION_ROOT) {
+        let bus_name = sender_bus_name(&sender);
+        let app = app_name(&dbus, &bus_name).unwrap_or(bus_name);
+        for token in children(connection, backend, &format!("{SESSION_ROOT}/{sender}")) {
+            sessions.push(ShareSession {
+                path: format!("{SESSION_ROOT}/{sender}/{token}"),
+                app: app.clone(),
+            });
+        }
+    }
+    Ok(sessions)
+}
+
+/// Close sessions through the backend, which tells the portal and the sharing app.
+fn close_sessions(backend: &str, paths: &[String]) {
+    let connection = match Connection::session() {
+        Ok(connection) => connection,
+        Err(err) => {
+            log::error!("screenshare gauge: failed to connect to session bus: {err}");
+            return;
+        }
+    };
+    for path in paths {
+        if let Err(err) = connection.call_method(
+            Some(backend),
+            path.as_str(),
+            Some(SESSION_INTERFACE),
+            "Close",
+            &(),
+        ) {
+            log::error!("screenshare gauge: failed to close {path}: {err}");
+        }
+    }
+}
+
+fn info_lines(sessions: &[ShareSession]) -> Vec<String> {
+    if sessions.is_empty() {
+        return vec!["Not sharing the screen".to_string()];
+    }
+    sessions
+        .iter()
+        .map(|session| format!("Sharing with {}", session.app))
+        .collect()
+}
+
+/// Gauge showing whether the screen is being shared.
+struct ScreenshareGauge {
+    settings: ScreenshareSettings,
+    ready_notify: Option<GaugeReadyNotify>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl ScreenshareGauge {
+    /// Action that closes `paths` off the gauge thread, then refreshes the gauge.
+    fn stopper(&self) -> impl Fn(Vec<String>) + Send + Sync + 'static {
+        let backend = self.settings.backend.clone();
+        let notify = self.ready_notify.clone();
+        move |paths: Vec<String>| {
+            let backend = backend.clone();
+            let notify = notify.clone();
+            thread::spawn(move || {
+                close_sessions(&backend, &paths);
+                if let Some(notify) = notify {
+                    notify("screenshare");
+                }
+            });
+        }
+    }
+}
+
+impl Gauge for ScreenshareGauge {
+    fn id(&self) -> &'static str {
+        "screenshare"
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + Duration::from_secs(self.settings.poll_interval_secs);
+
+        let sessions = Connection::session()
+            .map_err(|err| format!("session bus: {err}"))
+            .and_then(|connection| list_sessions(&connection, &self.settings.backend));
+        let sessions = match sessions {
+            Ok(sessions) => sessions,
+            Err(err) => {
+                return Some(GaugeModel {
+                    id: "screenshare",
+                    icon: svg_asset("screenshare.svg"),
+                    display: GaugeDisplay::Error,
+                    interactions: GaugeInteractionModel {
+                        left_click: GaugePointerInteraction {
+                            info: Some(InfoDialog {
+                                title: "Screen sharing".to_string(),
+                                lines: vec![err],
+                                graph: None,
+                            }),
+                            ..GaugePointerInteraction::default()
+                        },
+                        ..GaugeInteractionModel::default()
+                    },
+                });
+            }
+        };
+
+        let info = InfoDialog {
+            title: "Screen sharing".to_string(),
+            lines: info_lines(&sessions),
+            graph: None,
+        };
+        let interactions = if sessions.is_empty() {
+            GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(info),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            }
+        } else {
+            let stop = Arc::new(self.stopper());
+            let all: Vec<String> = sessions
+                .iter()
+                .map(|session| session.path.clone())
+                .collect();
+            let stop_all = Arc::clone(&stop);
+            let on_click: GaugeClickAction = Arc::new(move |_| stop_all(all.clone()));
+            let on_select: MenuSelectAction = Arc::new(move |path: String| stop(vec![path]));
+            GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    on_input: Some(on_click),
+                    ..GaugePointerInteraction::default()
+                },
+                middle_click: GaugePointerInteraction {
+                    info: Some(info),
+                    ..GaugePointerInteraction::default()
+                },
+                right_click: GaugePointerInteraction {
+                    menu: Some(GaugeMenu {
+                        title: "Stop sharing".to_string(),
+                        items: sessions
+                            .iter()
+                            .map(|session| GaugeMenuItem {
+                                id: session.path.clone(),
+                                label: session.app.clone(),
+                                selected: false,
+                                exec: None,
+                            })
+                            .collect(),
+                        on_select: Some(on_select),
+                        slider: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            }
+        };
+
+        let display = if sessions.is_empty() {
+            GaugeDisplay::Empty
+        } else {
+            GaugeDisplay::Value {
+                value: GaugeValue::Text(sessions.len().to_string()),
+                attention: GaugeValueAttention::Danger,
+            }
+        };
+
+        Some(GaugeModel {
+            id: "screenshare",
+            icon: svg_asset("screenshare.svg"),
+            display,
+            interactions,
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    Box::new(ScreenshareGauge {
+        settings: ScreenshareSettings::load(),
+        ready_notify: None,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    ScreenshareSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "screenshare",
+        description: "Screen sharing indicator that turns red while an app casts the screen and stops it on click.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_session_nodes_and_senders() {
+        let xml = r#"<node>
+  <interface name="org.freedesktop.DBus.Introspectable"/>
+  <node name="1_42"/>
+  <node name="1_97"/>
+</node>"#;
+        assert_eq!(child_nodes(xml), vec!["1_42", "1_97"]);
+        assert_eq!(child_nodes("<node></node>"), Vec::<String>::new());
+        assert_eq!(sender_bus_name("1_42"), ":1.42");
+        assert_eq!(info_lines(&[]), vec!["Not sharing the screen"]);
+    }
+}
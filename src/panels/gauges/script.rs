@@ -0,0 +1,469 @@
+// User script gauges: run a shell command and render its output like a built-in gauge.
+// Consumes Settings: grelier.gauge.script.<N>.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeClick, GaugeClickAction, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar,
+    GaugeStop,
+};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_bindings;
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use iced::widget::svg;
+use serde_json::Value;
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Scripts still running after this long are killed and reported as failed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+const WAIT_POLL: Duration = Duration::from_millis(50);
+/// How long output may keep flowing after the script exits before its leftover jobs are killed.
+const EXIT_GRACE: Duration = Duration::from_millis(200);
+
+/// Parsed script output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ScriptOutput {
+    text: String,
+    attention: GaugeValueAttention,
+    tooltip: Vec<String>,
+}
+
+fn parse_attention(value: &str) -> GaugeValueAttention {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "warning" => GaugeValueAttention::Warning,
+        "danger" | "critical" => GaugeValueAttention::Danger,
+        _ => GaugeValueAttention::Nominal,
+    }
+}
+
+/// Parse either `{"text": .., "attention": .., "tooltip": ..}` or plain text, where the
+/// first line is the value and any further lines become the tooltip.
+fn parse_output(stdout: &str) -> ScriptOutput {
+    let trimmed = stdout.trim();
+    if trimmed.starts_with('{')
+        && let Ok(Value::Object(object)) = serde_json::from_str::<Value>(trimmed)
+    {
+        let tooltip = match object.get("tooltip") {
+            Some(Value::String(text)) => text.lines().map(ToString::to_string).collect(),
+            Some(Value::Array(lines)) => lines
+                .iter()
+                .filter_map(|line| line.as_str().map(ToString::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        return ScriptOutput {
+            text: object
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            attention: object
+                .get("attention")
+                .and_then(Value::as_str)
+                .map(parse_attention)
+                .unwrap_or_default(),
+            tooltip,
+        };
+    }
+
+    let mut lines = trimmed.lines();
+    ScriptOutput {
+        text: lines.next().unwrap_or_default().trim().to_string(),
+        attention: GaugeValueAttention::Nominal,
+        tooltip: lines.map(ToString::to_string).collect(),
+    }
+}
+
+/// Read `pipe` to the end on its own thread, so a chatty child never blocks on a full pipe.
+/// Output read so far from one pipe, and a channel that disconnects at end of file.
+struct Drain {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    done: mpsc::Receiver<()>,
+}
+
+impl Drain {
+    /// Wait up to `limit` for the pipe to close; returns whether it did.
+    fn wait(&self, limit: Duration) -> bool {
+        matches!(
+            self.done.recv_timeout(limit),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        )
+    }
+
+    fn take(self) -> Vec<u8> {
+        self.buffer
+            .lock()
+            .map(|mut buffer| std::mem::take(&mut *buffer))
+            .unwrap_or_default()
+    }
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> Drain {
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let (done_tx, done) = mpsc::channel::<()>();
+    let shared = Arc::clone(&buffer);
+    thread::spawn(move || {
+        let _done = done_tx;
+        let Some(mut pipe) = pipe else {
+            return;
+        };
+        let mut chunk = [0u8; 4096];
+        loop {
+            match pipe.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    if let Ok(mut buffer) = shared.lock() {
+                        buffer.extend_from_slice(&chunk[..read]);
+                    }
+                }
+            }
+        }
+    });
+    Drain { buffer, done }
+}
+
+/// Kill the child and everything it started; it leads its own process group.
+fn kill_group(child: &mut Child) {
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill has no memory-safety preconditions.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn wait_with_timeout(mut child: Child, timeout: Duration) -> Result<Output, String> {
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                kill_group(&mut child);
+                return Err(format!("timed out after {}s", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(WAIT_POLL),
+            Err(err) => {
+                kill_group(&mut child);
+                return Err(err.to_string());
+            }
+        }
+    };
+    // Background jobs inherit the pipes and can hold them open long after the script exits.
+    let closed = stdout.wait(EXIT_GRACE) && stderr.wait(EXIT_GRACE);
+    if !closed {
+        kill_group(&mut child);
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        // Jobs that left the group may still hold a pipe; keep what was read by the deadline.
+        let _ = stdout.wait(remaining) && stderr.wait(remaining);
+    }
+    Ok(Output {
+        status,
+        stdout: stdout.take(),
+        stderr: stderr.take(),
+    })
+}
+
+fn run_script(command: &str) -> Result<ScriptOutput, String> {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .spawn()
+        .map_err(|err| format!("failed to run: {err}"))?;
+    let output = wait_with_timeout(child, COMMAND_TIMEOUT)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(if stderr.is_empty() {
+            format!("exited with {}", output.status)
+        } else {
+            stderr
+        });
+    }
+    Ok(parse_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Event source that runs the script on its interval or when a click asks for a refresh.
+struct ScriptRunner {
+    id: &'static str,
+    command: String,
+    interval: Duration,
+    rerun_rx: mpsc::Receiver<()>,
+    result: Arc<Mutex<Option<Result<ScriptOutput, String>>>>,
+}
+
+impl GaugeEventSource for ScriptRunner {
+    fn run(self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
+        loop {
+            let next = run_script(&self.command);
+            if let Err(err) = &next {
+                log::warn!("{} gauge: `{}` failed: {err}", self.id, self.command);
+            }
+            match self.result.lock() {
+                Ok(mut result) => *result = Some(next),
+                Err(_) => return,
+            }
+            notify(self.id);
+            if let Err(mpsc::RecvTimeoutError::Disconnected) =
+                stop.recv_timeout(&self.rerun_rx, self.interval)
+            {
+                return;
+            }
+        }
+    }
+}
+
+/// Resolve the icon setting: empty for the default, a bundled asset name, or an SVG path.
+fn script_icon(setting: &str) -> svg::Handle {
+    let setting = setting.trim();
+    if setting.is_empty() {
+        svg_asset("script.svg")
+    } else if Path::new(setting).is_absolute() {
+        svg::Handle::from_path(setting)
+    } else {
+        svg_asset(setting)
+    }
+}
+
+/// Gauge rendering the latest output of one user script.
+struct ScriptGauge {
+    id: &'static str,
+    icon: svg::Handle,
+    click_command: String,
+    /// Whether a command is set; without one the gauge only shows an error.
+    configured: bool,
+    result: Arc<Mutex<Option<Result<ScriptOutput, String>>>>,
+    rerun_tx: mpsc::Sender<()>,
+    /// Runner handed to the work manager on registration, if a command is set.
+    runner: Option<ScriptRunner>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for ScriptGauge {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(runner) = self.runner.take() {
+            registrar.add_event_source(Box::new(runner));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        // Updates are pushed by the runner thread; the deadline is only a fallback refresh.
+        self.next_deadline = now + Duration::from_secs(3600);
+        let result = self.result.lock().ok()?.clone();
+
+        let (display, tooltip) = match result {
+            None if !self.configured => (
+                GaugeDisplay::Error,
+                vec!["No command configured".to_string()],
+            ),
+            None => (GaugeDisplay::Empty, Vec::new()),
+            Some(Ok(output)) => (
+                GaugeDisplay::Value {
+                    value: GaugeValue::Text(output.text),
+                    attention: output.attention,
+                },
+                output.tooltip,
+            ),
+            Some(Err(err)) => (GaugeDisplay::Error, vec![err]),
+        };
+        let info = (!tooltip.is_empty()).then(|| InfoDialog {
+            title: self.id.to_string(),
+            lines: tooltip,
+            graph: None,
+        });
+
+        let mut interactions = GaugeInteractionModel::default();
+        if self.click_command.is_empty() {
+            interactions.left_click.info = info;
+        } else {
+            let click_command = self.click_command.clone();
+            let rerun_tx = self.rerun_tx.clone();
+            let on_click: GaugeClickAction = Arc::new(move |_click: GaugeClick| {
+                gauge_bindings::spawn_command(&click_command);
+                let _ = rerun_tx.send(());
+            });
+            interactions.left_click.on_input = Some(on_click);
+            interactions.right_click = GaugePointerInteraction {
+                info,
+                ..GaugePointerInteraction::default()
+            };
+        }
+
+        Some(GaugeModel {
+            id: self.id,
+            icon: self.icon.clone(),
+            display,
+            interactions,
+        })
+    }
+}
+
+fn create_gauge(id: &'static str, slot: u8, now: Instant) -> Box<dyn Gauge> {
+    let key = |name: &str| format!("grelier.gauge.script.{slot}.{name}");
+    let settings = settings::settings();
+    let command = settings.get_or(&key("command"), "").trim().to_string();
+    let interval = settings
+        .get_parsed_or(&key("interval_secs"), 10u64)
+        .clamp(1, 86_400);
+    let result = Arc::new(Mutex::new(None));
+    let (rerun_tx, rerun_rx) = mpsc::channel();
+    let runner = (!command.is_empty()).then(|| ScriptRunner {
+        id,
+        command,
+        interval: Duration::from_secs(interval),
+        rerun_rx,
+        result: Arc::clone(&result),
+    });
+
+    Box::new(ScriptGauge {
+        id,
+        icon: script_icon(&settings.get_or(&key("icon"), "")),
+        click_command: settings
+            .get_or(&key("click_command"), "")
+            .trim()
+            .to_string(),
+        configured: runner.is_some(),
+        result,
+        rerun_tx,
+        runner,
+        next_deadline: now,
+    })
+}
+
+/// Register gauge `script<N>` with its `grelier.gauge.script.<N>.*` settings.
+macro_rules! script_slot {
+    ($module:ident, $slot:literal) => {
+        mod $module {
+            use super::*;
+
+            const SPECS: &[SettingSpec] = &[
+                SettingSpec {
+                    key: concat!("grelier.gauge.script.", $slot, ".command"),
+                    default: "",
+                },
+                SettingSpec {
+                    key: concat!("grelier.gauge.script.", $slot, ".interval_secs"),
+                    default: "10",
+                },
+                SettingSpec {
+                    key: concat!("grelier.gauge.script.", $slot, ".click_command"),
+                    default: "",
+                },
+                SettingSpec {
+                    key: concat!("grelier.gauge.script.", $slot, ".icon"),
+                    default: "",
+                },
+            ];
+
+            fn settings() -> &'static [SettingSpec] {
+                SPECS
+            }
+
+            fn create(now: Instant) -> Box<dyn Gauge> {
+                create_gauge(concat!("script", $slot), $slot, now)
+            }
+
+            inventory::submit! {
+                GaugeSpec {
+                    id: concat!("script", $slot),
+                    description: concat!(
+                        "User script gauge #", $slot,
+                        " showing the output of grelier.gauge.script.", $slot, ".command."
+                    ),
+                    default_enabled: false,
+                    settings,
+                    create,
+                    validate: None,
+                }
+            }
+        }
+    };
+}
+
+script_slot!(slot1, 1);
+script_slot!(slot2, 2);
+script_slot!(slot3, 3);
+script_slot!(slot4, 4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_and_plain_text_output() {
+        assert_eq!(
+            parse_output(r#"{"text": "12", "attention": "warning", "tooltip": ["a", "b"]}"#),
+            ScriptOutput {
+                text: "12".to_string(),
+                attention: GaugeValueAttention::Warning,
+                tooltip: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+        assert_eq!(
+            parse_output("3\nupdates pending\nfirefox\n"),
+            ScriptOutput {
+                text: "3".to_string(),
+                attention: GaugeValueAttention::Nominal,
+                tooltip: vec!["updates pending".to_string(), "firefox".to_string()],
+            }
+        );
+        assert_eq!(parse_output("{not json").text, "{not json");
+        assert_eq!(parse_attention("Critical"), GaugeValueAttention::Danger);
+    }
+
+    #[test]
+    fn runs_commands_and_reports_failures() {
+        assert_eq!(
+            run_script("echo ok").map(|output| output.text),
+            Ok("ok".to_string())
+        );
+        assert_eq!(
+            run_script("echo broke >&2; exit 3"),
+            Err("broke".to_string())
+        );
+        // More than a pipe buffer of output must not stall the script.
+        assert_eq!(
+            run_script("head -c 200000 /dev/zero | tr '\\0' x; echo; echo done")
+                .map(|output| output.tooltip),
+            Ok(vec!["done".to_string()])
+        );
+        // A background job holding stdout must not keep the gauge waiting.
+        let started = Instant::now();
+        assert_eq!(
+            run_script("sleep 20 & echo hi").map(|output| output.text),
+            Ok("hi".to_string())
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(
+            wait_with_timeout(
+                Command::new("sleep").arg("5").spawn().expect("spawn sleep"),
+                Duration::from_millis(100)
+            )
+            .is_err()
+        );
+    }
+}
@@ -1,14 +1,19 @@
 // Desktop session actions gauge with uptime info and session controls.
+// Consumes Settings: grelier.gauge.session.*.
 use crate::dialog::info::InfoDialog;
 use crate::icon::svg_asset;
 use crate::panels::gauges::gauge::Gauge;
 use crate::panels::gauges::gauge::{
-    ActionSelectAction, GaugeActionDialog, GaugeActionItem, GaugeDisplay, GaugeInteractionModel,
-    GaugeModel, GaugePointerInteraction,
+    ActionConfirmPrompt, ActionSelectAction, GaugeActionDialog, GaugeActionItem, GaugeDisplay,
+    GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel, GaugePointerInteraction,
+    MenuSelectAction,
 };
+use crate::panels::gauges::gauge_dependencies;
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings::SettingSpec;
+use crate::settings::{self, SettingSpec};
+use std::collections::HashMap;
 use std::fs;
+use std::process::{Command, Stdio};
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
@@ -19,24 +24,95 @@ const LOGIND_PATH: &str = "/org/freedesktop/login1";
 const LOGIND_IFACE: &str = "org.freedesktop.login1.Manager";
 const DEFAULT_REFRESH_INTERVAL_SECS: u64 = 60;
 
-#[derive(Debug, Clone, Copy)]
+settings::typed_settings! {
+    struct SessionSettings {
+        grub_cfg: String = ("grelier.gauge.session.grub_cfg", "/boot/grub/grub.cfg"),
+        grub_reboot_command: String = ("grelier.gauge.session.grub_reboot_command", "pkexec grub-reboot"),
+        hidden_actions: String = ("grelier.gauge.session.hidden_actions", ""),
+        confirm: bool = ("grelier.gauge.session.confirm", "true"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SessionAction {
+    Lock,
+    Logout,
     Sleep,
+    Hibernate,
     Reboot,
     Shutdown,
 }
 
 impl SessionAction {
+    const ALL: [SessionAction; 6] = [
+        Self::Lock,
+        Self::Logout,
+        Self::Sleep,
+        Self::Hibernate,
+        Self::Reboot,
+        Self::Shutdown,
+    ];
+
+    fn item_id(self) -> &'static str {
+        match self {
+            Self::Lock => "lock",
+            Self::Logout => "logout",
+            Self::Sleep => "sleep",
+            Self::Hibernate => "hibernate",
+            Self::Reboot => "reboot",
+            Self::Shutdown => "shutdown",
+        }
+    }
+
     fn from_item_id(item_id: &str) -> Option<Self> {
-        match item_id {
-            "sleep" => Some(Self::Sleep),
-            "reboot" => Some(Self::Reboot),
-            "shutdown" => Some(Self::Shutdown),
-            _ => None,
+        Self::ALL
+            .into_iter()
+            .find(|action| action.item_id() == item_id)
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Lock => "lock.svg",
+            Self::Logout => "logout.svg",
+            Self::Sleep => "sleep.svg",
+            Self::Hibernate => "hibernate.svg",
+            Self::Reboot => "reboot.svg",
+            Self::Shutdown => "shutdown.svg",
         }
     }
 }
 
+/// Confirmation prompt for a session action; locking is harmless and runs immediately.
+fn confirm_prompt(item_id: &str) -> Option<String> {
+    let prompt = match SessionAction::from_item_id(item_id)? {
+        SessionAction::Lock => return None,
+        SessionAction::Logout => "Log out?",
+        SessionAction::Sleep => "Suspend?",
+        SessionAction::Hibernate => "Hibernate?",
+        SessionAction::Reboot => "Reboot?",
+        SessionAction::Shutdown => "Power off?",
+    };
+    Some(prompt.to_string())
+}
+
+/// Actions shown in the dialog, minus those listed in `hidden_actions`.
+fn visible_actions(hidden_actions: &str) -> Vec<SessionAction> {
+    let hidden: Vec<&str> = hidden_actions
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .collect();
+    SessionAction::ALL
+        .into_iter()
+        .filter(|action| !hidden.contains(&action.item_id()))
+        .collect()
+}
+
+fn logind_proxy(connection: &Connection) -> Result<Proxy<'_>, String> {
+    Proxy::new(connection, LOGIND_SERVICE, LOGIND_PATH, LOGIND_IFACE)
+        .map_err(|err| format!("failed to create logind proxy: {err}"))
+}
+
 fn perform_session_action(action: SessionAction) {
     let connection = match Connection::system() {
         Ok(connection) => connection,
@@ -46,16 +122,20 @@ fn perform_session_action(action: SessionAction) {
         }
     };
 
-    let proxy = match Proxy::new(&connection, LOGIND_SERVICE, LOGIND_PATH, LOGIND_IFACE) {
+    let proxy = match logind_proxy(&connection) {
         Ok(proxy) => proxy,
         Err(err) => {
-            log::error!("session gauge: failed to create logind proxy: {err}");
+            log::error!("session gauge: {err}");
             return;
         }
     };
 
+    // "auto" resolves to the caller's session, or the user's graphical session.
     let result = match action {
+        SessionAction::Lock => proxy.call_method("LockSession", &("auto",)),
+        SessionAction::Logout => proxy.call_method("TerminateSession", &("auto",)),
         SessionAction::Sleep => proxy.call_method("Suspend", &(false,)),
+        SessionAction::Hibernate => proxy.call_method("Hibernate", &(false,)),
         SessionAction::Reboot => proxy.call_method("Reboot", &(false,)),
         SessionAction::Shutdown => proxy.call_method("PowerOff", &(false,)),
     };
@@ -65,6 +145,177 @@ fn perform_session_action(action: SessionAction) {
     }
 }
 
+/// One-shot boot target offered in the "Reboot Into" menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BootTarget {
+    /// Boot Loader Specification entry id, set through logind (systemd-boot and compatible).
+    LoaderEntry(String),
+    /// Top-level GRUB menu entry title, set through `grub-reboot`.
+    Grub(String),
+}
+
+impl BootTarget {
+    fn item_id(&self) -> String {
+        match self {
+            BootTarget::LoaderEntry(id) => format!("loader:{id}"),
+            BootTarget::Grub(title) => format!("grub:{title}"),
+        }
+    }
+
+    fn from_item_id(item_id: &str) -> Option<Self> {
+        if let Some(id) = item_id.strip_prefix("loader:") {
+            Some(BootTarget::LoaderEntry(id.to_string()))
+        } else {
+            item_id
+                .strip_prefix("grub:")
+                .map(|title| BootTarget::Grub(title.to_string()))
+        }
+    }
+}
+
+/// Titles for loader entries from `bootctl list --json=short`, keyed by entry id.
+fn bootctl_titles() -> HashMap<String, String> {
+    let Ok(output) = Command::new("bootctl")
+        .args(["list", "--json=short", "--no-pager"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return HashMap::new();
+    };
+    let Ok(entries) = serde_json::from_slice::<Vec<serde_json::Value>>(&output.stdout) else {
+        return HashMap::new();
+    };
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let id = entry.get("id")?.as_str()?;
+            let title = entry
+                .get("showTitle")
+                .or_else(|| entry.get("title"))?
+                .as_str()?;
+            Some((id.to_string(), title.to_string()))
+        })
+        .collect()
+}
+
+fn loader_entries() -> Vec<String> {
+    let Ok(connection) = Connection::system() else {
+        return Vec::new();
+    };
+    logind_proxy(&connection)
+        .ok()
+        .and_then(|proxy| proxy.get_property::<Vec<String>>("BootLoaderEntries").ok())
+        .unwrap_or_default()
+}
+
+/// Titles of top-level `menuentry` blocks; entries inside submenus are skipped.
+fn parse_grub_entries(cfg: &str) -> Vec<String> {
+    let mut depth = 0usize;
+    let mut entries = Vec::new();
+    for line in cfg.lines() {
+        let trimmed = line.trim_start();
+        if depth == 0
+            && let Some(rest) = trimmed.strip_prefix("menuentry ")
+        {
+            let rest = rest.trim_start();
+            let title = match rest.chars().next() {
+                Some(quote @ ('\'' | '"')) => rest[1..].split(quote).next(),
+                _ => rest.split_whitespace().next(),
+            };
+            if let Some(title) = title.filter(|title| !title.is_empty()) {
+                entries.push(title.to_string());
+            }
+        }
+        depth += trimmed.matches('{').count();
+        depth = depth.saturating_sub(trimmed.matches('}').count());
+    }
+    entries
+}
+
+/// Loader entries when logind knows any, otherwise GRUB menu entries.
+fn boot_targets(settings: &SessionSettings) -> Vec<(BootTarget, String)> {
+    let entries = loader_entries();
+    if !entries.is_empty() {
+        let titles = bootctl_titles();
+        return entries
+            .into_iter()
+            .map(|id| {
+                let label = titles
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or_else(|| id.trim_end_matches(".conf").to_string());
+                (BootTarget::LoaderEntry(id), label)
+            })
+            .collect();
+    }
+    fs::read_to_string(&settings.grub_cfg)
+        .map(|cfg| parse_grub_entries(&cfg))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|title| (BootTarget::Grub(title.clone()), title))
+        .collect()
+}
+
+fn set_boot_target(target: &BootTarget, grub_reboot_command: &str) -> Result<(), String> {
+    match target {
+        BootTarget::LoaderEntry(id) => {
+            let connection = Connection::system()
+                .map_err(|err| format!("failed to connect to system bus: {err}"))?;
+            logind_proxy(&connection)?
+                .call_method("SetRebootToBootLoaderEntry", &(id.as_str(),))
+                .map(|_| ())
+                .map_err(|err| format!("failed to set boot loader entry '{id}': {err}"))
+        }
+        BootTarget::Grub(title) => {
+            let mut parts = grub_reboot_command.split_whitespace();
+            let program = parts
+                .next()
+                .ok_or_else(|| "grub reboot command is empty".to_string())?;
+            let status = Command::new(program)
+                .args(parts)
+                .arg(title)
+                .stdin(Stdio::null())
+                .status()
+                .map_err(|err| format!("failed to run {program}: {err}"))?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(format!("{program} exited with {status}"))
+            }
+        }
+    }
+}
+
+fn reboot_into_menu(targets: &[(BootTarget, String)], grub_reboot_command: String) -> GaugeMenu {
+    let on_select: MenuSelectAction = Arc::new(move |item_id: String| {
+        let Some(target) = BootTarget::from_item_id(&item_id) else {
+            log::warn!("session gauge: unknown boot target '{item_id}'");
+            return;
+        };
+        let command = grub_reboot_command.clone();
+        thread::spawn(move || match set_boot_target(&target, &command) {
+            Ok(()) => perform_session_action(SessionAction::Reboot),
+            Err(err) => log::error!("session gauge: {err}"),
+        });
+    });
+
+    GaugeMenu {
+        title: "Reboot Into".to_string(),
+        items: targets
+            .iter()
+            .map(|(target, label)| GaugeMenuItem {
+                id: target.item_id(),
+                label: label.clone(),
+                selected: false,
+                exec: None,
+            })
+            .collect(),
+        on_select: Some(on_select),
+        slider: None,
+    }
+}
+
 fn read_uptime_seconds() -> Option<u64> {
     let uptime = fs::read_to_string("/proc/uptime").ok()?;
     let first = uptime.split_whitespace().next()?;
@@ -84,7 +335,7 @@ fn format_uptime(seconds: u64) -> String {
     }
 }
 
-fn session_action_dialog() -> GaugeActionDialog {
+fn session_action_dialog(actions: &[SessionAction], confirm: bool) -> GaugeActionDialog {
     let on_select: ActionSelectAction = Arc::new(|item_id: String| {
         let Some(action) = SessionAction::from_item_id(&item_id) else {
             log::warn!("session gauge: unknown action '{item_id}'");
@@ -95,28 +346,25 @@ fn session_action_dialog() -> GaugeActionDialog {
 
     GaugeActionDialog {
         title: "Session".to_string(),
-        items: vec![
-            GaugeActionItem {
-                id: "sleep".to_string(),
-                icon: svg_asset("sleep.svg"),
-            },
-            GaugeActionItem {
-                id: "reboot".to_string(),
-                icon: svg_asset("reboot.svg"),
-            },
-            GaugeActionItem {
-                id: "shutdown".to_string(),
-                icon: svg_asset("shutdown.svg"),
-            },
-        ],
+        items: actions
+            .iter()
+            .map(|action| GaugeActionItem {
+                id: action.item_id().to_string(),
+                icon: svg_asset(action.icon()),
+                exec: None,
+            })
+            .collect(),
         on_select: Some(on_select),
+        confirm: confirm.then_some(confirm_prompt as ActionConfirmPrompt),
     }
 }
 
 /// Gauge that exposes session-level actions such as logout and power controls.
 struct SessionGauge {
-    /// Prebuilt action dialog with session management actions.
-    action_dialog: GaugeActionDialog,
+    /// Prebuilt action dialog with session management actions; absent when all are hidden.
+    action_dialog: Option<GaugeActionDialog>,
+    /// "Reboot Into" menu on middle click; absent when no boot entries were found.
+    reboot_into_menu: Option<GaugeMenu>,
     /// Scheduler deadline for the next run.
     next_deadline: Instant,
 }
@@ -132,6 +380,11 @@ impl Gauge for SessionGauge {
 
     fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
         self.next_deadline = now + Duration::from_secs(DEFAULT_REFRESH_INTERVAL_SECS);
+        let mut lines = vec![match read_uptime_seconds() {
+            Some(seconds) => format!("Uptime: {}", format_uptime(seconds)),
+            None => "Uptime: Unknown".to_string(),
+        }];
+        lines.extend(gauge_dependencies::missing_hints("session"));
         Some(GaugeModel {
             id: "session",
             icon: svg_asset("shutdown.svg"),
@@ -140,15 +393,17 @@ impl Gauge for SessionGauge {
                 left_click: GaugePointerInteraction {
                     info: Some(InfoDialog {
                         title: "Session".to_string(),
-                        lines: vec![match read_uptime_seconds() {
-                            Some(seconds) => format!("Uptime: {}", format_uptime(seconds)),
-                            None => "Uptime: Unknown".to_string(),
-                        }],
+                        lines,
+                        graph: None,
                     }),
                     ..GaugePointerInteraction::default()
                 },
+                middle_click: GaugePointerInteraction {
+                    menu: self.reboot_into_menu.clone(),
+                    ..GaugePointerInteraction::default()
+                },
                 right_click: GaugePointerInteraction {
-                    action_dialog: Some(self.action_dialog.clone()),
+                    action_dialog: self.action_dialog.clone(),
                     ..GaugePointerInteraction::default()
                 },
                 ..GaugeInteractionModel::default()
@@ -158,24 +413,78 @@ impl Gauge for SessionGauge {
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = SessionSettings::load();
+    let targets = boot_targets(&settings);
+    let actions = visible_actions(&settings.hidden_actions);
     Box::new(SessionGauge {
-        action_dialog: session_action_dialog(),
+        action_dialog: (!actions.is_empty())
+            .then(|| session_action_dialog(&actions, settings.confirm)),
+        reboot_into_menu: (!targets.is_empty())
+            .then(|| reboot_into_menu(&targets, settings.grub_reboot_command.clone())),
         next_deadline: now,
     })
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[];
-    SETTINGS
+    SessionSettings::SPECS
 }
 
 inventory::submit! {
     GaugeSpec {
         id: "session",
-        description: "Desktop session actions with uptime info.",
+        description: "Desktop session actions (lock, logout, suspend, hibernate, reboot, power off) with uptime info.",
         default_enabled: false,
         settings,
         create: create_gauge,
         validate: None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_top_level_grub_entries() {
+        let cfg = r#"
+menuentry 'Arch Linux' --class arch {
+    linux /vmlinuz-linux
+}
+submenu 'Advanced options' {
+    menuentry 'Arch Linux (fallback)' {
+        linux /vmlinuz-linux
+    }
+}
+menuentry "Windows Boot Manager (on /dev/nvme0n1p1)" {
+    chainloader /EFI/Microsoft/Boot/bootmgfw.efi
+}
+"#;
+        assert_eq!(
+            parse_grub_entries(cfg),
+            vec!["Arch Linux", "Windows Boot Manager (on /dev/nvme0n1p1)"]
+        );
+        let target = BootTarget::Grub("Arch Linux".to_string());
+        assert_eq!(BootTarget::from_item_id(&target.item_id()), Some(target));
+        assert_eq!(
+            BootTarget::from_item_id("loader:windows.conf"),
+            Some(BootTarget::LoaderEntry("windows.conf".to_string()))
+        );
+    }
+
+    #[test]
+    fn hides_configured_actions_and_confirms_destructive_ones() {
+        assert_eq!(
+            visible_actions(" hibernate, logout ,bogus"),
+            vec![
+                SessionAction::Lock,
+                SessionAction::Sleep,
+                SessionAction::Reboot,
+                SessionAction::Shutdown,
+            ]
+        );
+        assert_eq!(visible_actions("").len(), SessionAction::ALL.len());
+        assert_eq!(confirm_prompt("lock"), None);
+        assert_eq!(confirm_prompt("shutdown").as_deref(), Some("Power off?"));
+        assert_eq!(confirm_prompt("unknown"), None);
+    }
+}
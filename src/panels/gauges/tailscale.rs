@@ -0,0 +1,572 @@
+// Tailscale gauge: connection state and exit node from `tailscale status --json`, with a menu
+// to switch exit nodes or disconnect and per-peer latency in the info dialog.
+// Consumes Settings: grelier.gauge.tailscale.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeEventSource, GaugeReadyNotify, GaugeRegistrar, GaugeStop, MenuSelectAction,
+};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel,
+    GaugePointerInteraction, GaugeValue, GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_dependencies;
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
+
+/// Peers listed in the info dialog; the rest are summarized.
+const INFO_DIALOG_PEERS: usize = 10;
+/// Peers pinged per latency round, so a large tailnet does not flood the network.
+const MAX_PINGED_PEERS: usize = 8;
+const EXIT_ITEM_PREFIX: &str = "exit:";
+const CONNECTION_ITEM: &str = "connection";
+
+settings::typed_settings! {
+    struct TailscaleSettings {
+        path: String = ("grelier.gauge.tailscale.path", "tailscale"),
+        poll_interval_secs: u64 = ("grelier.gauge.tailscale.poll_interval_secs", "10", 1..=3600),
+        ping_interval_secs: u64 = ("grelier.gauge.tailscale.ping_interval_secs", "60", 10..=3600),
+    }
+}
+
+/// How traffic currently reaches a peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PeerPath {
+    /// Direct UDP to this endpoint.
+    Direct(String),
+    /// Through the named DERP relay region.
+    Relay(String),
+    /// No recent traffic.
+    Idle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Peer {
+    name: String,
+    /// First Tailscale address, used for pings and `--exit-node`.
+    ip: String,
+    online: bool,
+    /// Currently used as this device's exit node.
+    exit_node: bool,
+    /// Offers itself as an exit node.
+    exit_node_option: bool,
+    path: PeerPath,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TailscaleStatus {
+    /// `Running`, `Stopped`, `NeedsLogin`, and so on.
+    backend_state: String,
+    self_name: String,
+    self_ip: Option<String>,
+    /// Peers sorted by name.
+    peers: Vec<Peer>,
+}
+
+impl TailscaleStatus {
+    fn running(&self) -> bool {
+        self.backend_state == "Running"
+    }
+
+    fn exit_node(&self) -> Option<&Peer> {
+        self.peers.iter().find(|peer| peer.exit_node)
+    }
+}
+
+/// Short host name: `HostName`, falling back to the first label of `DNSName`.
+fn node_name(node: &Value) -> String {
+    node["HostName"]
+        .as_str()
+        .filter(|name| !name.is_empty())
+        .or_else(|| node["DNSName"].as_str()?.split('.').next())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+fn first_ip(node: &Value) -> Option<String> {
+    node["TailscaleIPs"][0].as_str().map(ToString::to_string)
+}
+
+fn parse_peer(node: &Value) -> Option<Peer> {
+    let text = |key: &str| node[key].as_str().filter(|value| !value.is_empty());
+    let path = match (text("CurAddr"), text("Relay")) {
+        (Some(addr), _) => PeerPath::Direct(addr.to_string()),
+        (None, Some(relay)) if node["Active"].as_bool() == Some(true) => {
+            PeerPath::Relay(relay.to_string())
+        }
+        _ => PeerPath::Idle,
+    };
+    Some(Peer {
+        name: node_name(node),
+        ip: first_ip(node)?,
+        online: node["Online"].as_bool().unwrap_or(false),
+        exit_node: node["ExitNode"].as_bool().unwrap_or(false),
+        exit_node_option: node["ExitNodeOption"].as_bool().unwrap_or(false),
+        path,
+    })
+}
+
+/// Parse the output of `tailscale status --json`.
+fn parse_status(json: &str) -> Result<TailscaleStatus, String> {
+    let status: Value =
+        serde_json::from_str(json).map_err(|err| format!("invalid tailscale status: {err}"))?;
+    let backend_state = status["BackendState"]
+        .as_str()
+        .ok_or_else(|| "tailscale status has no BackendState".to_string())?
+        .to_string();
+    let mut peers: Vec<Peer> = status["Peer"]
+        .as_object()
+        .map(|peers| peers.values().filter_map(parse_peer).collect())
+        .unwrap_or_default();
+    peers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(TailscaleStatus {
+        backend_state,
+        self_name: node_name(&status["Self"]),
+        self_ip: first_ip(&status["Self"]),
+        peers,
+    })
+}
+
+/// Round-trip time from `tailscale ping` output: `pong from host (100.x) via ... in 23ms`.
+fn parse_ping_ms(output: &str) -> Option<f64> {
+    output.lines().find_map(|line| {
+        let rest = line.strip_prefix("pong from ")?;
+        let (_, latency) = rest.rsplit_once(" in ")?;
+        latency.trim().strip_suffix("ms")?.parse().ok()
+    })
+}
+
+fn run_tailscale(path: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(path)
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run {path}: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(if stderr.is_empty() {
+            format!("{path} {} failed: {}", args.join(" "), output.status)
+        } else {
+            stderr
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn fetch_status(path: &str) -> Result<TailscaleStatus, String> {
+    parse_status(&run_tailscale(path, &["status", "--json"])?)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TailscaleCommand {
+    /// Route through the peer with this address; empty clears the exit node.
+    SetExitNode(String),
+    /// Bring the connection up when down, or down when up.
+    ToggleConnection,
+}
+
+fn parse_item(id: &str) -> Option<TailscaleCommand> {
+    if id == CONNECTION_ITEM {
+        return Some(TailscaleCommand::ToggleConnection);
+    }
+    id.strip_prefix(EXIT_ITEM_PREFIX)
+        .map(|ip| TailscaleCommand::SetExitNode(ip.to_string()))
+}
+
+fn tailscale_menu(status: &TailscaleStatus, on_select: MenuSelectAction) -> GaugeMenu {
+    let mut items = vec![GaugeMenuItem {
+        id: CONNECTION_ITEM.to_string(),
+        label: "Connected".to_string(),
+        selected: status.running(),
+        exec: None,
+    }];
+    if status.running() {
+        items.push(GaugeMenuItem {
+            id: EXIT_ITEM_PREFIX.to_string(),
+            label: "Exit node: none".to_string(),
+            selected: status.exit_node().is_none(),
+            exec: None,
+        });
+        items.extend(
+            status
+                .peers
+                .iter()
+                .filter(|peer| peer.exit_node_option && (peer.online || peer.exit_node))
+                .map(|peer| GaugeMenuItem {
+                    id: format!("{EXIT_ITEM_PREFIX}{}", peer.ip),
+                    label: format!("Exit node: {}", peer.name),
+                    selected: peer.exit_node,
+                    exec: None,
+                }),
+        );
+    }
+    GaugeMenu {
+        title: "Tailscale".to_string(),
+        items,
+        on_select: Some(on_select),
+        slider: None,
+    }
+}
+
+fn peer_line(peer: &Peer, latency: Option<f64>) -> String {
+    let path = match &peer.path {
+        PeerPath::Direct(addr) => format!("direct {addr}"),
+        PeerPath::Relay(region) => format!("relay {region}"),
+        PeerPath::Idle => "idle".to_string(),
+    };
+    match latency {
+        Some(ms) => format!("{}: {path}, {ms:.0} ms", peer.name),
+        None => format!("{}: {path}", peer.name),
+    }
+}
+
+fn info_lines(status: &TailscaleStatus, latencies: &HashMap<String, f64>) -> Vec<String> {
+    let mut lines = vec![format!("State: {}", status.backend_state)];
+    match &status.self_ip {
+        Some(ip) => lines.push(format!("This device: {} ({ip})", status.self_name)),
+        None => lines.push(format!("This device: {}", status.self_name)),
+    }
+    lines.push(format!(
+        "Exit node: {}",
+        status.exit_node().map_or("none", |peer| peer.name.as_str())
+    ));
+    let online: Vec<&Peer> = status.peers.iter().filter(|peer| peer.online).collect();
+    lines.push(format!(
+        "Peers: {} online of {}",
+        online.len(),
+        status.peers.len()
+    ));
+    lines.extend(
+        online
+            .iter()
+            .take(INFO_DIALOG_PEERS)
+            .map(|peer| peer_line(peer, latencies.get(&peer.ip).copied())),
+    );
+    if online.len() > INFO_DIALOG_PEERS {
+        lines.push(format!("…and {} more", online.len() - INFO_DIALOG_PEERS));
+    }
+    lines
+}
+
+/// Event source that periodically pings online peers with recent traffic.
+struct LatencyMonitor {
+    path: String,
+    interval: Duration,
+    latencies: Arc<Mutex<HashMap<String, f64>>>,
+}
+
+impl LatencyMonitor {
+    fn measure(&self) -> HashMap<String, f64> {
+        let Ok(status) = fetch_status(&self.path) else {
+            return HashMap::new();
+        };
+        if !status.running() {
+            return HashMap::new();
+        }
+        status
+            .peers
+            .iter()
+            .filter(|peer| peer.online && peer.path != PeerPath::Idle)
+            .take(MAX_PINGED_PEERS)
+            .filter_map(|peer| {
+                // A relayed pong still carries a latency, but `tailscale ping` exits non-zero
+                // when no direct path comes up, so read stdout either way.
+                let output = Command::new(&self.path)
+                    .args(["ping", "--c", "1", "--timeout", "2s", &peer.ip])
+                    .output()
+                    .ok()?;
+                let ms = parse_ping_ms(&String::from_utf8_lossy(&output.stdout))?;
+                Some((peer.ip.clone(), ms))
+            })
+            .collect()
+    }
+}
+
+impl GaugeEventSource for LatencyMonitor {
+    fn run(self: Box<Self>, notify: GaugeReadyNotify, stop: GaugeStop) {
+        loop {
+            let measured = self.measure();
+            let changed = match self.latencies.lock() {
+                Ok(mut latencies) => {
+                    let changed = *latencies != measured;
+                    *latencies = measured;
+                    changed
+                }
+                Err(_) => return,
+            };
+            if changed {
+                notify("tailscale");
+            }
+            if stop.sleep(self.interval) {
+                return;
+            }
+        }
+    }
+}
+
+/// Gauge showing whether Tailscale is connected and which exit node is in use.
+struct TailscaleGauge {
+    path: String,
+    poll_interval: Duration,
+    /// Last ping round, by peer address.
+    latencies: Arc<Mutex<HashMap<String, f64>>>,
+    /// Pinger handed to the work manager on registration.
+    monitor: Option<LatencyMonitor>,
+    /// Error from the last menu action, shown until the next one succeeds.
+    last_error: Option<String>,
+    /// Sender cloned into menu callbacks to queue actions.
+    command_tx: mpsc::Sender<TailscaleCommand>,
+    /// Receiver drained on each run to apply queued actions.
+    command_rx: mpsc::Receiver<TailscaleCommand>,
+    /// Notifier used to request an immediate scheduler wake-up after actions.
+    ready_notify: Option<GaugeReadyNotify>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl TailscaleGauge {
+    fn apply(&mut self, command: TailscaleCommand, status: Option<&TailscaleStatus>) {
+        let result = match &command {
+            TailscaleCommand::SetExitNode(ip) => {
+                run_tailscale(&self.path, &["set", &format!("--exit-node={ip}")])
+            }
+            TailscaleCommand::ToggleConnection => {
+                let action = if status.is_some_and(TailscaleStatus::running) {
+                    "down"
+                } else {
+                    "up"
+                };
+                run_tailscale(&self.path, &[action])
+            }
+        };
+        match result {
+            Ok(_) => self.last_error = None,
+            Err(err) => {
+                log::warn!("tailscale gauge: {command:?} failed: {err}");
+                self.last_error = Some(err);
+            }
+        }
+    }
+
+    fn menu_action(&self) -> MenuSelectAction {
+        let command_tx = self.command_tx.clone();
+        let ready_notify = self.ready_notify.clone();
+        Arc::new(move |id: String| {
+            if let Some(command) = parse_item(&id) {
+                let _ = command_tx.send(command);
+                if let Some(ready_notify) = &ready_notify {
+                    ready_notify("tailscale");
+                }
+            }
+        })
+    }
+}
+
+impl Gauge for TailscaleGauge {
+    fn id(&self) -> &'static str {
+        "tailscale"
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(monitor) = self.monitor.take() {
+            registrar.add_event_source(Box::new(monitor));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + self.poll_interval;
+
+        let mut status = fetch_status(&self.path);
+        let mut acted = false;
+        while let Ok(command) = self.command_rx.try_recv() {
+            let current = status.as_ref().ok().cloned();
+            self.apply(command, current.as_ref());
+            acted = true;
+        }
+        if acted {
+            status = fetch_status(&self.path);
+        }
+
+        let (icon, display, mut lines, menu) = match status {
+            Ok(status) => {
+                let latencies = self
+                    .latencies
+                    .lock()
+                    .map(|latencies| latencies.clone())
+                    .unwrap_or_default();
+                let running = status.running();
+                (
+                    if running {
+                        "tailscale.svg"
+                    } else {
+                        "tailscale-off.svg"
+                    },
+                    GaugeDisplay::Value {
+                        value: GaugeValue::Text(if running { "on" } else { "off" }.to_string()),
+                        // Logged out or expired: the tailnet is unreachable until re-login.
+                        attention: if status.backend_state == "NeedsLogin" {
+                            GaugeValueAttention::Warning
+                        } else {
+                            GaugeValueAttention::Nominal
+                        },
+                    },
+                    info_lines(&status, &latencies),
+                    Some(tailscale_menu(&status, self.menu_action())),
+                )
+            }
+            Err(err) => {
+                let mut lines = vec![err];
+                lines.extend(gauge_dependencies::missing_hints("tailscale"));
+                ("tailscale-off.svg", GaugeDisplay::Error, lines, None)
+            }
+        };
+        if let Some(err) = &self.last_error {
+            lines.push(format!("Last action failed: {err}"));
+        }
+
+        Some(GaugeModel {
+            id: "tailscale",
+            icon: svg_asset(icon),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Tailscale".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                right_click: GaugePointerInteraction {
+                    menu,
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = TailscaleSettings::load();
+    let latencies = Arc::new(Mutex::new(HashMap::new()));
+    let (command_tx, command_rx) = mpsc::channel();
+    Box::new(TailscaleGauge {
+        path: settings.path.clone(),
+        poll_interval: Duration::from_secs(settings.poll_interval_secs),
+        latencies: Arc::clone(&latencies),
+        monitor: Some(LatencyMonitor {
+            path: settings.path,
+            interval: Duration::from_secs(settings.ping_interval_secs),
+            latencies,
+        }),
+        last_error: None,
+        command_tx,
+        command_rx,
+        ready_notify: None,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    TailscaleSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "tailscale",
+        description: "Tailscale gauge showing connection state and exit node, with peer latency details.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = r#"{
+        "BackendState": "Running",
+        "Self": {"HostName": "laptop", "TailscaleIPs": ["100.64.0.1", "fd7a::1"]},
+        "Peer": {
+            "nodekey:b": {"HostName": "", "DNSName": "nas.tail1234.ts.net.",
+                "TailscaleIPs": ["100.64.0.3"], "Online": true, "ExitNode": false,
+                "ExitNodeOption": false, "CurAddr": "192.168.1.5:41641", "Relay": "fra",
+                "Active": true},
+            "nodekey:a": {"HostName": "exit-fra", "TailscaleIPs": ["100.64.0.2"],
+                "Online": true, "ExitNode": true, "ExitNodeOption": true, "CurAddr": "",
+                "Relay": "fra", "Active": true},
+            "nodekey:c": {"HostName": "phone", "TailscaleIPs": ["100.64.0.4"],
+                "Online": false, "ExitNodeOption": true, "CurAddr": "", "Relay": "ams"}
+        }
+    }"#;
+
+    #[test]
+    fn parses_status_into_menu_and_peer_lines() {
+        let status = parse_status(STATUS).expect("status");
+        assert!(status.running());
+        assert_eq!(
+            status.exit_node().map(|peer| peer.name.as_str()),
+            Some("exit-fra")
+        );
+        assert_eq!(
+            status
+                .peers
+                .iter()
+                .map(|peer| &peer.path)
+                .collect::<Vec<_>>(),
+            vec![
+                &PeerPath::Relay("fra".to_string()),
+                &PeerPath::Direct("192.168.1.5:41641".to_string()),
+                &PeerPath::Idle,
+            ]
+        );
+
+        let menu = tailscale_menu(&status, Arc::new(|_| {}));
+        assert_eq!(
+            menu.items
+                .iter()
+                .map(|item| (item.label.as_str(), item.selected))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Connected", true),
+                ("Exit node: none", false),
+                ("Exit node: exit-fra", true),
+            ]
+        );
+        assert_eq!(
+            parse_item(&menu.items[1].id),
+            Some(TailscaleCommand::SetExitNode(String::new()))
+        );
+        assert_eq!(
+            parse_item(&menu.items[2].id),
+            Some(TailscaleCommand::SetExitNode("100.64.0.2".to_string()))
+        );
+
+        let latencies = HashMap::from([("100.64.0.3".to_string(), 3.2)]);
+        let lines = info_lines(&status, &latencies);
+        assert_eq!(lines[1], "This device: laptop (100.64.0.1)");
+        assert_eq!(lines[3], "Peers: 2 online of 3");
+        assert_eq!(lines[5], "nas: direct 192.168.1.5:41641, 3 ms");
+
+        assert_eq!(
+            parse_ping_ms("pong from nas (100.64.0.3) via DERP(fra) in 41ms\n"),
+            Some(41.0)
+        );
+        assert_eq!(parse_ping_ms("timeout waiting for pong"), None);
+    }
+}
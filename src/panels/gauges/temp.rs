@@ -0,0 +1,327 @@
+// Temperature gauge reading thermal zones and hwmon sensors from sysfs.
+// Consumes Settings: grelier.gauge.temp.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::{icon_quantity, svg_asset};
+use crate::panels::gauges::gauge::Gauge;
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::panels::gauges::gauge_tuning;
+use crate::settings::{self, SettingSpec};
+use crate::units::Units;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub(crate) const SYS_THERMAL: &str = "/sys/class/thermal";
+pub(crate) const SYS_HWMON: &str = "/sys/class/hwmon";
+
+settings::typed_settings! {
+    struct TempSettings {
+        warning_celsius: f32 = ("grelier.gauge.temp.warning_celsius", "75"),
+        danger_celsius: f32 = ("grelier.gauge.temp.danger_celsius", "90"),
+        scale_min_celsius: f32 = ("grelier.gauge.temp.scale_min_celsius", "30"),
+        scale_max_celsius: f32 = ("grelier.gauge.temp.scale_max_celsius", "100"),
+        sensors: String = ("grelier.gauge.temp.sensors", ""),
+        poll_interval_secs: u64 = ("grelier.gauge.temp.poll_interval_secs", "5", 1..=3600),
+    }
+}
+
+/// One temperature reading from a thermal zone or hwmon channel.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TempReading {
+    /// Display name, e.g. `x86_pkg_temp` or `coretemp/Package id 0`.
+    pub(crate) name: String,
+    /// Zone type or hwmon chip name, so a whitelist can select a whole chip.
+    source: String,
+    pub(crate) celsius: f32,
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Sysfs reports temperatures in millidegrees Celsius.
+fn read_millidegrees(path: &Path) -> Option<f32> {
+    read_trimmed(path)?
+        .parse::<i64>()
+        .ok()
+        .map(|value| value as f32 / 1000.0)
+}
+
+pub(crate) fn read_thermal_zones(root: &Path) -> Vec<TempReading> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|entry| {
+            let path = entry.path();
+            let zone_type = read_trimmed(&path.join("type"))
+                .unwrap_or_else(|| entry.file_name().to_string_lossy().into_owned());
+            Some(TempReading {
+                name: zone_type.clone(),
+                source: zone_type,
+                celsius: read_millidegrees(&path.join("temp"))?,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn read_hwmon(root: &Path) -> Vec<TempReading> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut readings = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let chip = read_trimmed(&path.join("name"))
+            .unwrap_or_else(|| entry.file_name().to_string_lossy().into_owned());
+        let Ok(files) = fs::read_dir(&path) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let file_name = file.file_name().to_string_lossy().into_owned();
+            let Some(channel) = file_name
+                .strip_prefix("temp")
+                .and_then(|rest| rest.strip_suffix("_input"))
+            else {
+                continue;
+            };
+            let Some(celsius) = read_millidegrees(&file.path()) else {
+                continue;
+            };
+            let label = read_trimmed(&path.join(format!("temp{channel}_label")))
+                .unwrap_or_else(|| format!("temp{channel}"));
+            readings.push(TempReading {
+                name: format!("{chip}/{label}"),
+                source: chip.clone(),
+                celsius,
+            });
+        }
+    }
+    readings
+}
+
+fn parse_sensor_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Keep readings named in `whitelist` (by full name or source); an empty list keeps all.
+fn filter_readings(mut readings: Vec<TempReading>, whitelist: &[String]) -> Vec<TempReading> {
+    if !whitelist.is_empty() {
+        readings.retain(|reading| {
+            whitelist
+                .iter()
+                .any(|entry| *entry == reading.name || *entry == reading.source)
+        });
+    }
+    readings.sort_by(|a, b| {
+        b.celsius
+            .total_cmp(&a.celsius)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    readings
+}
+
+fn temp_attention(celsius: f32, warning: f32, danger: f32) -> GaugeValueAttention {
+    if celsius >= danger {
+        GaugeValueAttention::Danger
+    } else if celsius >= warning {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+fn scale_fraction(celsius: f32, min: f32, max: f32) -> f32 {
+    if max <= min {
+        return 0.0;
+    }
+    ((celsius - min) / (max - min)).clamp(0.0, 1.0)
+}
+
+/// Gauge that shows the hottest selected temperature sensor.
+struct TempGauge {
+    /// Thresholds, icon scale, and poll cadence.
+    settings: TempSettings,
+    /// Parsed sensor whitelist; empty means every sensor.
+    whitelist: Vec<String>,
+    /// Unit preferences for the info dialog.
+    units: Units,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for TempGauge {
+    fn id(&self) -> &'static str {
+        "temp"
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now
+            + Duration::from_secs(gauge_tuning::live_secs(
+                "temp",
+                self.settings.poll_interval_secs,
+            ));
+
+        let mut readings = read_thermal_zones(Path::new(SYS_THERMAL));
+        readings.extend(read_hwmon(Path::new(SYS_HWMON)));
+        let readings = filter_readings(readings, &self.whitelist);
+
+        let settings = &self.settings;
+        let (display, lines) = match readings.first() {
+            Some(hottest) => (
+                GaugeDisplay::Value {
+                    value: GaugeValue::Svg(icon_quantity(scale_fraction(
+                        hottest.celsius,
+                        settings.scale_min_celsius,
+                        settings.scale_max_celsius,
+                    ))),
+                    attention: temp_attention(
+                        hottest.celsius,
+                        settings.warning_celsius,
+                        settings.danger_celsius,
+                    ),
+                },
+                readings
+                    .iter()
+                    .map(|reading| {
+                        format!(
+                            "{}: {}",
+                            reading.name,
+                            self.units.format_temperature(reading.celsius, 1)
+                        )
+                    })
+                    .collect(),
+            ),
+            None => (
+                GaugeDisplay::Error,
+                vec!["No temperature sensors found".to_string()],
+            ),
+        };
+
+        Some(GaugeModel {
+            id: "temp",
+            icon: svg_asset("thermometer.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Temperature".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = TempSettings::load();
+    Box::new(TempGauge {
+        whitelist: parse_sensor_list(&settings.sensors),
+        settings,
+        units: Units::load(),
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    TempSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "temp",
+        description: "Temperature gauge showing the hottest thermal zone or hwmon sensor.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_sysfs(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("grelier_temp_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+        fs::write(path, contents).expect("write file");
+    }
+
+    #[test]
+    fn reads_zones_and_hwmon_and_applies_whitelist() {
+        let root = temp_sysfs("read");
+        let thermal = root.join("thermal");
+        let hwmon = root.join("hwmon");
+        write(&thermal.join("thermal_zone0/type"), "acpitz\n");
+        write(&thermal.join("thermal_zone0/temp"), "41000\n");
+        write(&thermal.join("cooling_device0/type"), "Processor\n");
+        write(&hwmon.join("hwmon1/name"), "coretemp\n");
+        write(&hwmon.join("hwmon1/temp1_input"), "63500\n");
+        write(&hwmon.join("hwmon1/temp1_label"), "Package id 0\n");
+        write(&hwmon.join("hwmon1/temp2_input"), "58000\n");
+
+        let mut readings = read_thermal_zones(&thermal);
+        readings.extend(read_hwmon(&hwmon));
+        let all = filter_readings(readings.clone(), &[]);
+        let names: Vec<&str> = all.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["coretemp/Package id 0", "coretemp/temp2", "acpitz"]
+        );
+        assert_eq!(all[0].celsius, 63.5);
+
+        let only_acpi = filter_readings(readings.clone(), &parse_sensor_list("acpitz"));
+        assert_eq!(only_acpi.len(), 1);
+        let chip = filter_readings(readings, &parse_sensor_list(" coretemp/temp2 ,nope"));
+        assert_eq!(chip[0].name, "coretemp/temp2");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn thresholds_and_scale_follow_settings() {
+        let defaults = TempSettings::default();
+        let attention =
+            |celsius| temp_attention(celsius, defaults.warning_celsius, defaults.danger_celsius);
+        assert_eq!(attention(50.0), GaugeValueAttention::Nominal);
+        assert_eq!(attention(80.0), GaugeValueAttention::Warning);
+        assert_eq!(attention(95.0), GaugeValueAttention::Danger);
+        assert_eq!(scale_fraction(65.0, 30.0, 100.0), 0.5);
+        assert_eq!(scale_fraction(10.0, 30.0, 100.0), 0.0);
+        assert_eq!(scale_fraction(50.0, 30.0, 30.0), 0.0);
+    }
+}
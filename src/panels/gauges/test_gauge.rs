@@ -101,19 +101,23 @@ fn action_dialog() -> GaugeActionDialog {
             GaugeActionItem {
                 id: "ram.svg".to_string(),
                 icon: svg_asset("ram.svg"),
+                exec: None,
             },
             GaugeActionItem {
                 id: "disk.svg".to_string(),
                 icon: svg_asset("disk.svg"),
+                exec: None,
             },
             GaugeActionItem {
                 id: "microchip.svg".to_string(),
                 icon: svg_asset("microchip.svg"),
+                exec: None,
             },
         ],
         on_select: Some(Arc::new(|item: String| {
             println!("{item}");
         }) as ActionSelectAction),
+        confirm: None,
     }
 }
 
@@ -0,0 +1,490 @@
+// VPN tunnel gauge with NetworkManager VPN toggling and optional kill-switch (firewall) detection.
+// Consumes Settings: grelier.gauge.vpn.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    AsyncGaugeEventSource, Gauge, GaugeFuture, GaugeReadyNotify, GaugeRegistrar,
+};
+use crate::panels::gauges::gauge::{
+    GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel,
+    GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
+};
+use crate::panels::gauges::gauge_dependencies;
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::panels::gauges::nm_common::{self, ActiveConnection};
+use crate::settings::{self, SettingSpec};
+use async_process::{Command, Stdio};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedObjectPath;
+
+const SYS_NET: &str = "/sys/class/net";
+const IFF_UP: u32 = 0x1;
+/// NetworkManager connection types shown in the VPN menu.
+const NM_VPN_TYPES: [&str; 2] = ["vpn", "wireguard"];
+const MENU_REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+/// Longest a kill-switch check may run before it is killed.
+const KILLSWITCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+settings::typed_settings! {
+    struct VpnSettings {
+        interface_prefixes: String = ("grelier.gauge.vpn.interface_prefixes", "tun,tap,wg,ppp"),
+        poll_interval_secs: u64 = ("grelier.gauge.vpn.poll_interval_secs", "5", 1..=3600),
+        killswitch_command: String = ("grelier.gauge.vpn.killswitch_command", ""),
+        killswitch_interval_secs: u64 = ("grelier.gauge.vpn.killswitch_interval_secs", "30", 1..=86400),
+    }
+}
+
+/// Result of the most recent kill-switch check command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KillSwitchState {
+    /// No check command is configured.
+    NotConfigured,
+    /// The check has not completed yet or the command could not be run.
+    Unknown,
+    /// The check command exited successfully.
+    Active,
+    /// The check command exited with a failure status.
+    Inactive,
+}
+
+impl KillSwitchState {
+    fn label(self) -> &'static str {
+        match self {
+            KillSwitchState::NotConfigured => "Kill switch: not configured",
+            KillSwitchState::Unknown => "Kill switch: unknown",
+            KillSwitchState::Active => "Kill switch: active",
+            KillSwitchState::Inactive => "Kill switch: inactive",
+        }
+    }
+}
+
+/// Async event source that periodically runs the kill-switch check command.
+struct KillSwitchMonitor {
+    command: String,
+    interval: Duration,
+    state: Arc<Mutex<KillSwitchState>>,
+}
+
+impl AsyncGaugeEventSource for KillSwitchMonitor {
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn timeout(&self) -> Duration {
+        KILLSWITCH_TIMEOUT
+    }
+
+    fn poll(&mut self) -> GaugeFuture<'_, bool> {
+        Box::pin(async move {
+            let next = run_killswitch_check(&self.command).await;
+            match self.state.lock() {
+                Ok(mut state) => {
+                    let changed = *state != next;
+                    *state = next;
+                    changed
+                }
+                Err(_) => false,
+            }
+        })
+    }
+}
+
+async fn run_killswitch_check(command: &str) -> KillSwitchState {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await;
+    match status {
+        Ok(status) if status.success() => KillSwitchState::Active,
+        Ok(_) => KillSwitchState::Inactive,
+        Err(err) => {
+            log::error!("vpn gauge: failed to run kill-switch check: {err}");
+            KillSwitchState::Unknown
+        }
+    }
+}
+
+fn parse_prefixes(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|prefix| !prefix.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn interface_up(path: &Path) -> bool {
+    if let Ok(flags) = fs::read_to_string(path.join("flags"))
+        && let Ok(flags) = u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16)
+    {
+        return flags & IFF_UP != 0;
+    }
+
+    // Point-to-point tunnels usually report "unknown" rather than "up".
+    fs::read_to_string(path.join("operstate"))
+        .map(|state| matches!(state.trim(), "up" | "unknown"))
+        .unwrap_or(false)
+}
+
+fn active_tunnels_at(sys_net: &Path, prefixes: &[String]) -> Vec<String> {
+    let entries = match fs::read_dir(sys_net) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut tunnels: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let matches = prefixes.iter().any(|prefix| name.starts_with(prefix));
+            (matches && interface_up(&entry.path())).then_some(name)
+        })
+        .collect();
+    tunnels.sort();
+    tunnels
+}
+
+/// Saved NetworkManager VPN profile as `(id, settings path)`.
+type SavedVpn = (String, String);
+
+#[derive(Debug)]
+enum VpnCommand {
+    /// Bring the saved connection at this settings path up, or down if it is active.
+    Toggle(String),
+}
+
+fn saved_vpns(connection: &Connection) -> Vec<SavedVpn> {
+    nm_common::connection_entries(connection, &NM_VPN_TYPES)
+        .into_iter()
+        .map(|entry| (entry.id, entry.path.as_str().to_string()))
+        .collect()
+}
+
+fn toggle_vpn(connection: &Connection, active: &[ActiveConnection], settings_path: &str) {
+    let ok = match active
+        .iter()
+        .find(|conn| conn.settings_path.as_str() == settings_path)
+    {
+        Some(conn) => nm_common::deactivate_connection(connection, &conn.path),
+        None => OwnedObjectPath::try_from("/").is_ok_and(|any_device| {
+            nm_common::activate_connection(connection, settings_path, &any_device)
+        }),
+    };
+    if !ok {
+        log::warn!("vpn gauge: failed to toggle connection {settings_path}");
+    }
+}
+
+fn vpn_menu_items(saved: &[SavedVpn], active: &[ActiveConnection]) -> Vec<GaugeMenuItem> {
+    saved
+        .iter()
+        .map(|(id, path)| GaugeMenuItem {
+            id: path.clone(),
+            label: id.clone(),
+            selected: active
+                .iter()
+                .any(|conn| conn.settings_path.as_str() == path),
+            exec: None,
+        })
+        .collect()
+}
+
+fn vpn_icon(connected: bool, killswitch: KillSwitchState) -> &'static str {
+    match (connected, killswitch) {
+        (_, KillSwitchState::Active) => "vpn-shield.svg",
+        (true, _) => "vpn.svg",
+        (false, _) => "vpn-off.svg",
+    }
+}
+
+fn vpn_attention(connected: bool, killswitch: KillSwitchState) -> GaugeValueAttention {
+    // A tunnel without an engaged kill switch can leak traffic if the tunnel drops.
+    if connected && killswitch == KillSwitchState::Inactive {
+        GaugeValueAttention::Warning
+    } else {
+        GaugeValueAttention::Nominal
+    }
+}
+
+fn vpn_model(
+    tunnels: &[String],
+    nm_active: &[String],
+    killswitch: KillSwitchState,
+    menu: Option<GaugeMenu>,
+) -> GaugeModel {
+    let connected = !tunnels.is_empty() || !nm_active.is_empty();
+    let mut lines = Vec::new();
+    if !tunnels.is_empty() {
+        lines.push(format!("Tunnel: {}", tunnels.join(", ")));
+    }
+    if !nm_active.is_empty() {
+        lines.push(format!("NetworkManager: {}", nm_active.join(", ")));
+    }
+    if !connected {
+        lines.push("No active tunnel".to_string());
+    }
+    lines.push(killswitch.label().to_string());
+    if menu.is_none() {
+        lines.extend(gauge_dependencies::missing_hints("vpn"));
+    }
+
+    GaugeModel {
+        id: "vpn",
+        icon: svg_asset(vpn_icon(connected, killswitch)),
+        display: GaugeDisplay::Value {
+            value: GaugeValue::Text(if connected { "on" } else { "off" }.to_string()),
+            attention: vpn_attention(connected, killswitch),
+        },
+        interactions: GaugeInteractionModel {
+            left_click: GaugePointerInteraction {
+                info: Some(InfoDialog {
+                    title: "VPN".to_string(),
+                    lines,
+                    graph: None,
+                }),
+                ..GaugePointerInteraction::default()
+            },
+            right_click: GaugePointerInteraction {
+                menu,
+                ..GaugePointerInteraction::default()
+            },
+            ..GaugeInteractionModel::default()
+        },
+    }
+}
+
+/// Gauge that reports active VPN tunnels and whether a kill switch is engaged.
+struct VpnGauge {
+    /// Interface name prefixes treated as VPN tunnels.
+    prefixes: Vec<String>,
+    /// Poll cadence for tunnel interface sampling.
+    poll_interval: Duration,
+    /// Latest kill-switch state written by the monitor event source.
+    killswitch: Arc<Mutex<KillSwitchState>>,
+    /// Monitor handed to the work manager on registration, if a check command is set.
+    monitor: Option<KillSwitchMonitor>,
+    /// Sender cloned into menu callbacks to queue connection toggles.
+    command_tx: mpsc::Sender<VpnCommand>,
+    /// Receiver drained on each run to apply queued toggles.
+    command_rx: mpsc::Receiver<VpnCommand>,
+    /// Notifier used to request an immediate scheduler wake-up after actions.
+    ready_notify: Option<GaugeReadyNotify>,
+    /// Saved NetworkManager VPN profiles reused between menu refreshes.
+    cached_saved: Vec<SavedVpn>,
+    /// Absolute deadline for re-listing saved profiles.
+    menu_refresh_deadline: Instant,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for VpnGauge {
+    fn id(&self) -> &'static str {
+        "vpn"
+    }
+
+    fn bind_ready_notify(&mut self, notify: GaugeReadyNotify) {
+        self.ready_notify = Some(notify);
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(monitor) = self.monitor.take() {
+            registrar.add_async_event_source(Box::new(monitor));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        let tunnels = active_tunnels_at(Path::new(SYS_NET), &self.prefixes);
+        let killswitch = self
+            .killswitch
+            .lock()
+            .map(|state| *state)
+            .unwrap_or(KillSwitchState::Unknown);
+
+        let nm_connection = Connection::system().ok();
+        let mut active = nm_connection
+            .as_ref()
+            .map(|connection| nm_common::active_connections(connection, &NM_VPN_TYPES))
+            .unwrap_or_default();
+        let mut toggled = false;
+        while let Ok(VpnCommand::Toggle(path)) = self.command_rx.try_recv() {
+            if let Some(connection) = nm_connection.as_ref() {
+                toggle_vpn(connection, &active, &path);
+                toggled = true;
+            }
+        }
+        if toggled && let Some(connection) = nm_connection.as_ref() {
+            active = nm_common::active_connections(connection, &NM_VPN_TYPES);
+        }
+
+        if now >= self.menu_refresh_deadline {
+            self.cached_saved = nm_connection.as_ref().map(saved_vpns).unwrap_or_default();
+            self.menu_refresh_deadline = now + MENU_REFRESH_INTERVAL;
+        }
+        let menu = (!self.cached_saved.is_empty()).then(|| {
+            let command_tx = self.command_tx.clone();
+            let ready_notify = self.ready_notify.clone();
+            let on_select: MenuSelectAction = Arc::new(move |path: String| {
+                let _ = command_tx.send(VpnCommand::Toggle(path));
+                if let Some(ready_notify) = &ready_notify {
+                    ready_notify("vpn");
+                }
+            });
+            GaugeMenu {
+                title: "VPN Connections".to_string(),
+                items: vpn_menu_items(&self.cached_saved, &active),
+                on_select: Some(on_select),
+                slider: None,
+            }
+        });
+        let nm_active: Vec<String> = active.into_iter().map(|conn| conn.id).collect();
+
+        self.next_deadline = now + self.poll_interval;
+        Some(vpn_model(&tunnels, &nm_active, killswitch, menu))
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = VpnSettings::load();
+    let command = settings.killswitch_command.trim().to_string();
+    let initial = if command.is_empty() {
+        KillSwitchState::NotConfigured
+    } else {
+        KillSwitchState::Unknown
+    };
+    let killswitch = Arc::new(Mutex::new(initial));
+    let monitor = (!command.is_empty()).then(|| KillSwitchMonitor {
+        command,
+        interval: Duration::from_secs(settings.killswitch_interval_secs),
+        state: Arc::clone(&killswitch),
+    });
+
+    let (command_tx, command_rx) = mpsc::channel();
+
+    Box::new(VpnGauge {
+        prefixes: parse_prefixes(&settings.interface_prefixes),
+        poll_interval: Duration::from_secs(settings.poll_interval_secs),
+        killswitch,
+        monitor,
+        command_tx,
+        command_rx,
+        ready_notify: None,
+        cached_saved: Vec::new(),
+        menu_refresh_deadline: now,
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    VpnSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "vpn",
+        description: "VPN gauge with NetworkManager VPN toggling and optional kill-switch detection.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_sys_net(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("grelier_vpn_test_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn write_iface(sys_net: &Path, iface: &str, flags: &str) {
+        let iface_dir = sys_net.join(iface);
+        fs::create_dir_all(&iface_dir).expect("create iface dir");
+        fs::write(iface_dir.join("flags"), flags).expect("write flags");
+    }
+
+    #[test]
+    fn detects_up_tunnels_matching_prefixes() {
+        let sys_net = temp_sys_net("detect");
+        write_iface(&sys_net, "wg0", "0x1091\n");
+        write_iface(&sys_net, "tun1", "0x1090\n");
+        write_iface(&sys_net, "eth0", "0x1003\n");
+
+        let prefixes = parse_prefixes(&VpnSettings::default().interface_prefixes);
+        assert_eq!(active_tunnels_at(&sys_net, &prefixes), vec!["wg0"]);
+
+        let _ = fs::remove_dir_all(&sys_net);
+    }
+
+    #[test]
+    fn warns_only_when_tunnel_is_up_without_killswitch() {
+        assert_eq!(
+            vpn_attention(true, KillSwitchState::Inactive),
+            GaugeValueAttention::Warning
+        );
+        assert_eq!(
+            vpn_attention(true, KillSwitchState::Active),
+            GaugeValueAttention::Nominal
+        );
+        assert_eq!(
+            vpn_attention(false, KillSwitchState::Inactive),
+            GaugeValueAttention::Nominal
+        );
+        assert_eq!(vpn_icon(true, KillSwitchState::Active), "vpn-shield.svg");
+        assert_eq!(
+            vpn_icon(false, KillSwitchState::NotConfigured),
+            "vpn-off.svg"
+        );
+    }
+
+    #[test]
+    fn menu_marks_active_networkmanager_vpns() {
+        let path = |p: &str| OwnedObjectPath::try_from(p).expect("object path");
+        let saved = vec![
+            ("office".to_string(), "/settings/1".to_string()),
+            ("home-wg".to_string(), "/settings/2".to_string()),
+        ];
+        let active = vec![ActiveConnection {
+            id: "home-wg".to_string(),
+            path: path("/active/7"),
+            settings_path: path("/settings/2"),
+        }];
+        let items = vpn_menu_items(&saved, &active);
+        assert_eq!(
+            items
+                .iter()
+                .map(|item| (item.id.as_str(), item.label.as_str(), item.selected))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/settings/1", "office", false),
+                ("/settings/2", "home-wg", true)
+            ]
+        );
+
+        let model = vpn_model(
+            &[],
+            &["home-wg".to_string()],
+            KillSwitchState::Unknown,
+            None,
+        );
+        let info = model.interactions.left_click.info.expect("info dialog");
+        assert_eq!(info.lines[0], "NetworkManager: home-wg");
+        assert_eq!(model.icon, svg_asset("vpn.svg"));
+    }
+}
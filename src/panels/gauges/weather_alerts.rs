@@ -0,0 +1,476 @@
+// Weather alerts gauge: active severe-weather alerts from NWS or MeteoAlarm for one location.
+// Consumes Settings: grelier.gauge.weather_alerts.*.
+use crate::dialog::info::InfoDialog;
+use crate::dialog::toast::{self, Toast};
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    AsyncGaugeEventSource, Gauge, GaugeDisplay, GaugeFuture, GaugeInteractionModel, GaugeModel,
+    GaugePointerInteraction, GaugeRegistrar, GaugeValue, GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::settings::{self, SettingSpec, Settings};
+use async_process::Command;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const PROVIDER_KEY: &str = "grelier.gauge.weather_alerts.provider";
+const USER_AGENT: &str = "grelier (https://github.com/kgilmer/grelier)";
+/// Push-driven gauge; the poller wakes it when alerts change.
+const FALLBACK_INTERVAL: Duration = Duration::from_secs(3600);
+/// A feed request still running after this long is cancelled; curl itself gives up at 20s.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+settings::typed_settings! {
+    struct WeatherAlertSettings {
+        provider: String = ("grelier.gauge.weather_alerts.provider", "nws"),
+        latitude: String = ("grelier.gauge.weather_alerts.latitude", ""),
+        longitude: String = ("grelier.gauge.weather_alerts.longitude", ""),
+        country: String = ("grelier.gauge.weather_alerts.country", ""),
+        region: String = ("grelier.gauge.weather_alerts.region", ""),
+        poll_interval_secs: u64 = ("grelier.gauge.weather_alerts.poll_interval_secs", "600", 60..=86_400),
+    }
+}
+
+/// CAP severity levels, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Unknown,
+    Minor,
+    Moderate,
+    Severe,
+    Extreme,
+}
+
+impl Severity {
+    fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "minor" => Severity::Minor,
+            "moderate" => Severity::Moderate,
+            "severe" => Severity::Severe,
+            "extreme" => Severity::Extreme,
+            _ => Severity::Unknown,
+        }
+    }
+
+    fn attention(self) -> GaugeValueAttention {
+        match self {
+            Severity::Severe | Severity::Extreme => GaugeValueAttention::Danger,
+            Severity::Moderate => GaugeValueAttention::Warning,
+            Severity::Minor | Severity::Unknown => GaugeValueAttention::Nominal,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Unknown => "Unknown",
+            Severity::Minor => "Minor",
+            Severity::Moderate => "Moderate",
+            Severity::Severe => "Severe",
+            Severity::Extreme => "Extreme",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WeatherAlert {
+    id: String,
+    event: String,
+    severity: Severity,
+    headline: String,
+    area: String,
+}
+
+/// Where alerts come from, resolved from settings.
+#[derive(Debug, Clone, PartialEq)]
+enum AlertFeed {
+    /// US National Weather Service alerts for a point.
+    Nws { latitude: f64, longitude: f64 },
+    /// MeteoAlarm country feed, filtered to areas containing `region`.
+    MeteoAlarm { country: String, region: String },
+}
+
+impl AlertFeed {
+    fn from_settings(settings: &WeatherAlertSettings) -> Result<Self, String> {
+        match settings.provider.trim().to_ascii_lowercase().as_str() {
+            "nws" => {
+                let coordinate = |key: &str, value: &str| {
+                    value.trim().parse::<f64>().map_err(|_| {
+                        format!("grelier.gauge.weather_alerts.{key} must be set to a number for the nws provider")
+                    })
+                };
+                Ok(AlertFeed::Nws {
+                    latitude: coordinate("latitude", &settings.latitude)?,
+                    longitude: coordinate("longitude", &settings.longitude)?,
+                })
+            }
+            "meteoalarm" => {
+                let country = settings.country.trim().to_ascii_lowercase();
+                if country.is_empty() {
+                    return Err(
+                        "grelier.gauge.weather_alerts.country must be set for the meteoalarm provider"
+                            .to_string(),
+                    );
+                }
+                Ok(AlertFeed::MeteoAlarm {
+                    country,
+                    region: settings.region.trim().to_string(),
+                })
+            }
+            other => Err(format!(
+                "Invalid {PROVIDER_KEY} '{other}', expected 'nws' or 'meteoalarm'"
+            )),
+        }
+    }
+
+    fn url(&self) -> String {
+        match self {
+            AlertFeed::Nws {
+                latitude,
+                longitude,
+            } => {
+                format!("https://api.weather.gov/alerts/active?point={latitude:.4},{longitude:.4}")
+            }
+            AlertFeed::MeteoAlarm { country, .. } => {
+                format!("https://feeds.meteoalarm.org/api/v1/warnings/feeds-{country}")
+            }
+        }
+    }
+
+    fn parse(&self, json: &str) -> Result<Vec<WeatherAlert>, String> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|err| format!("invalid alert feed: {err}"))?;
+        let mut alerts = match self {
+            AlertFeed::Nws { .. } => parse_nws(&value),
+            AlertFeed::MeteoAlarm { region, .. } => parse_meteoalarm(&value, region),
+        };
+        alerts.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.event.cmp(&b.event)));
+        Ok(alerts)
+    }
+}
+
+fn str_field(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// Parse the GeoJSON returned by `api.weather.gov/alerts/active`.
+fn parse_nws(value: &Value) -> Vec<WeatherAlert> {
+    let features = value.get("features").and_then(Value::as_array);
+    features
+        .into_iter()
+        .flatten()
+        .filter_map(|feature| feature.get("properties"))
+        .map(|props| WeatherAlert {
+            id: str_field(props, "id"),
+            event: str_field(props, "event"),
+            severity: Severity::parse(&str_field(props, "severity")),
+            headline: str_field(props, "headline"),
+            area: str_field(props, "areaDesc"),
+        })
+        .collect()
+}
+
+/// Parse a MeteoAlarm country feed, keeping alerts with an area matching `region`.
+fn parse_meteoalarm(value: &Value, region: &str) -> Vec<WeatherAlert> {
+    let region = region.to_lowercase();
+    let warnings = value.get("warnings").and_then(Value::as_array);
+    warnings
+        .into_iter()
+        .flatten()
+        .filter_map(|warning| warning.get("alert"))
+        .filter_map(|alert| {
+            let infos = alert.get("info").and_then(Value::as_array)?;
+            // Feeds repeat each alert per language; prefer English.
+            let info = infos
+                .iter()
+                .find(|info| str_field(info, "language").starts_with("en"))
+                .or_else(|| infos.first())?;
+            let areas: Vec<String> = info
+                .get("area")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|area| str_field(area, "areaDesc"))
+                .collect();
+            if !region.is_empty()
+                && !areas
+                    .iter()
+                    .any(|area| area.to_lowercase().contains(&region))
+            {
+                return None;
+            }
+            Some(WeatherAlert {
+                id: str_field(alert, "identifier"),
+                event: str_field(info, "event"),
+                severity: Severity::parse(&str_field(info, "severity")),
+                headline: str_field(info, "headline"),
+                area: areas.join(", "),
+            })
+        })
+        .collect()
+}
+
+async fn fetch_alerts(feed: &AlertFeed) -> Result<Vec<WeatherAlert>, String> {
+    let url = feed.url();
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", "20", "-A", USER_AGENT, &url])
+        // A cancelled fetch must not leave curl running.
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|err| format!("failed to run curl: {err}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    feed.parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+type SharedAlerts = Arc<Mutex<Option<Result<Vec<WeatherAlert>, String>>>>;
+
+/// Async event source polling the alert feed on the shared gauge executor.
+struct AlertPoller {
+    feed: AlertFeed,
+    interval: Duration,
+    alerts: SharedAlerts,
+}
+
+impl AsyncGaugeEventSource for AlertPoller {
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn timeout(&self) -> Duration {
+        FETCH_TIMEOUT
+    }
+
+    fn poll(&mut self) -> GaugeFuture<'_, bool> {
+        Box::pin(async move {
+            let next = fetch_alerts(&self.feed).await;
+            if let Err(err) = &next {
+                log::warn!("weather_alerts gauge: {err}");
+            }
+            match self.alerts.lock() {
+                Ok(mut alerts) => *alerts = Some(next),
+                Err(_) => return false,
+            }
+            true
+        })
+    }
+}
+
+/// Gauge showing the number of active alerts, colored by the most severe one.
+struct WeatherAlertsGauge {
+    /// Latest feed result; `None` until the first poll completes.
+    alerts: SharedAlerts,
+    /// Poller handed to the work manager on registration; `None` when misconfigured.
+    poller: Option<AlertPoller>,
+    /// Configuration problem shown instead of alerts.
+    config_error: Option<String>,
+    /// Severe alerts already announced with a toast.
+    announced: HashSet<String>,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl WeatherAlertsGauge {
+    /// Raise a toast for each severe alert not seen before.
+    fn announce(&mut self, alerts: &[WeatherAlert]) {
+        for alert in alerts {
+            if alert.severity.attention() == GaugeValueAttention::Danger
+                && self.announced.insert(alert.id.clone())
+            {
+                toast::show(
+                    Toast::new(alert.event.clone(), alert.headline.clone())
+                        .with_attention(GaugeValueAttention::Danger)
+                        .with_timeout(Duration::from_secs(15)),
+                );
+            }
+        }
+    }
+}
+
+fn alert_lines(alerts: &[WeatherAlert]) -> Vec<String> {
+    if alerts.is_empty() {
+        return vec!["No active alerts".to_string()];
+    }
+    alerts
+        .iter()
+        .flat_map(|alert| {
+            let mut lines = vec![format!("{} ({})", alert.event, alert.severity.label())];
+            if !alert.headline.is_empty() {
+                lines.push(alert.headline.clone());
+            }
+            if !alert.area.is_empty() {
+                lines.push(alert.area.clone());
+            }
+            lines
+        })
+        .collect()
+}
+
+impl Gauge for WeatherAlertsGauge {
+    fn id(&self) -> &'static str {
+        "weather_alerts"
+    }
+
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some(poller) = self.poller.take() {
+            registrar.add_async_event_source(Box::new(poller));
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + FALLBACK_INTERVAL;
+
+        let latest = self.alerts.lock().ok().and_then(|alerts| alerts.clone());
+        let (display, lines) = match (&self.config_error, latest) {
+            (Some(err), _) => (GaugeDisplay::Error, vec![err.clone()]),
+            (None, None) => (
+                GaugeDisplay::Empty,
+                vec!["Checking for alerts…".to_string()],
+            ),
+            (None, Some(Err(err))) => (GaugeDisplay::Error, vec![format!("Lookup failed: {err}")]),
+            (None, Some(Ok(alerts))) => {
+                self.announce(&alerts);
+                let display = match alerts.iter().map(|alert| alert.severity).max() {
+                    Some(severity) => GaugeDisplay::Value {
+                        value: GaugeValue::Text(alerts.len().to_string()),
+                        attention: severity.attention(),
+                    },
+                    None => GaugeDisplay::Empty,
+                };
+                (display, alert_lines(&alerts))
+            }
+        };
+
+        Some(GaugeModel {
+            id: "weather_alerts",
+            icon: svg_asset("weather-alert.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Weather Alerts".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    let settings = WeatherAlertSettings::load();
+    let alerts: SharedAlerts = Arc::new(Mutex::new(None));
+    let (poller, config_error) = match AlertFeed::from_settings(&settings) {
+        Ok(feed) => (
+            Some(AlertPoller {
+                feed,
+                interval: Duration::from_secs(settings.poll_interval_secs),
+                alerts: Arc::clone(&alerts),
+            }),
+            None,
+        ),
+        Err(err) => {
+            log::warn!("weather_alerts gauge: {err}");
+            (None, Some(err))
+        }
+    };
+    Box::new(WeatherAlertsGauge {
+        alerts,
+        poller,
+        config_error,
+        announced: HashSet::new(),
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    WeatherAlertSettings::SPECS
+}
+
+fn validate(settings: &Settings) -> Result<(), String> {
+    let provider = settings.get_or(PROVIDER_KEY, "nws");
+    match provider.trim().to_ascii_lowercase().as_str() {
+        "nws" | "meteoalarm" => Ok(()),
+        other => Err(format!(
+            "Invalid {PROVIDER_KEY} '{other}', expected 'nws' or 'meteoalarm'"
+        )),
+    }
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "weather_alerts",
+        description: "Weather alerts gauge showing active NWS or MeteoAlarm severe-weather alerts for a location.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: Some(validate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nws_alerts_most_severe_first() {
+        let json = r#"{"features": [
+            {"properties": {"id": "a", "event": "Wind Advisory", "severity": "Moderate",
+                            "headline": "Wind Advisory until 6 PM", "areaDesc": "King, WA"}},
+            {"properties": {"id": "b", "event": "Tornado Warning", "severity": "Extreme",
+                            "headline": "Take shelter now", "areaDesc": "King, WA"}}
+        ]}"#;
+        let feed = AlertFeed::Nws {
+            latitude: 47.6,
+            longitude: -122.3,
+        };
+        let alerts = feed.parse(json).expect("parse");
+        assert_eq!(alerts[0].event, "Tornado Warning");
+        assert_eq!(alerts[0].severity.attention(), GaugeValueAttention::Danger);
+        assert_eq!(alerts[1].severity, Severity::Moderate);
+        assert_eq!(
+            feed.url(),
+            "https://api.weather.gov/alerts/active?point=47.6000,-122.3000"
+        );
+        assert!(feed.parse("<html>").is_err());
+    }
+
+    #[test]
+    fn filters_meteoalarm_alerts_by_region() {
+        let json = r#"{"warnings": [
+            {"alert": {"identifier": "x", "info": [
+                {"language": "de-DE", "event": "Sturm", "severity": "Severe", "area": [{"areaDesc": "Berlin"}]},
+                {"language": "en-GB", "event": "Storm", "severity": "Severe", "headline": "Gale force winds",
+                 "area": [{"areaDesc": "Berlin"}]}]}},
+            {"alert": {"identifier": "y", "info": [
+                {"language": "en-GB", "event": "Heat", "severity": "Minor", "area": [{"areaDesc": "Bayern"}]}]}}
+        ]}"#;
+        let feed = AlertFeed::MeteoAlarm {
+            country: "germany".to_string(),
+            region: "berlin".to_string(),
+        };
+        let alerts = feed.parse(json).expect("parse");
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].event, "Storm");
+        assert_eq!(alerts[0].area, "Berlin");
+        assert_eq!(
+            alert_lines(&alerts),
+            vec!["Storm (Severe)", "Gale force winds", "Berlin"]
+        );
+    }
+}
@@ -1,41 +1,87 @@
-// Wi-Fi signal/connection gauge that polls sysfs and /proc.
+// Wi-Fi signal/connection gauge that reads sysfs and /proc when woken by rtnetlink link messages
+// or NetworkManager property changes, polling only when NetworkManager is not there to report
+// signal changes. The right-click menu lists saved and newly visible networks from
+// NetworkManager, or from iwd when NetworkManager is not running, scans on request, and asks for
+// a passphrase when joining a new secured network.
 // Consumes Settings: grelier.gauge.wifi.*.
 use crate::dialog::info::InfoDialog;
+use crate::dialog::prompt::{self, PromptDialog};
+use crate::dialog::toast::{self, Toast};
 use crate::icon::{icon_quantity, svg_asset};
-use crate::panels::gauges::gauge::{Gauge, GaugeReadyNotify};
+use crate::panels::gauges::gauge::{
+    AsyncGaugeEventSource, Gauge, GaugeFuture, GaugeReadyNotify, GaugeRegistrar,
+};
 use crate::panels::gauges::gauge::{
     GaugeDisplay, GaugeInteractionModel, GaugeMenu, GaugeMenuItem, GaugeModel,
     GaugePointerInteraction, GaugeValue, GaugeValueAttention, MenuSelectAction,
 };
+use crate::panels::gauges::gauge_dependencies;
 use crate::panels::gauges::gauge_registry::GaugeSpec;
-use crate::settings;
-use crate::settings::SettingSpec;
+use crate::panels::gauges::gauge_tuning;
+use crate::panels::gauges::iwd_common::{self, IwdNetwork};
+use crate::panels::gauges::nm_common::{
+    self, NM_IFACE, NM_PATH, NM_SERVICE, activate_connection, active_settings_connection_path,
+};
+use crate::settings::{self, SettingSpec};
+use async_io::{Async, Timer};
+use futures_lite::{StreamExt, future};
 use std::collections::{HashMap, HashSet};
 use std::fs;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
 use std::os::unix::net::UnixDatagram;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use zbus::blocking::{Connection, Proxy};
-use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+use zbus::fdo::DBusProxy;
+use zbus::message::Type as MessageType;
+use zbus::names::BusName;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{MatchRule, MessageStream};
 
 const SYS_NET: &str = "/sys/class/net";
 const PROC_NET_WIRELESS: &str = "/proc/net/wireless";
 const WPA_CTRL_DIRS: [&str; 2] = ["/run/wpa_supplicant", "/var/run/wpa_supplicant"];
 const WPA_CTRL_READ_TIMEOUT: Duration = Duration::from_millis(30);
-const DEFAULT_QUALITY_MAX: f32 = 70.0;
-const DEFAULT_POLL_INTERVAL_SECS: u64 = 3;
 const MENU_REFRESH_INTERVAL_SECS: u64 = 15;
-const NM_SERVICE: &str = "org.freedesktop.NetworkManager";
-const NM_PATH: &str = "/org/freedesktop/NetworkManager";
-const NM_SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
-const NM_IFACE: &str = "org.freedesktop.NetworkManager";
-const NM_SETTINGS_IFACE: &str = "org.freedesktop.NetworkManager.Settings";
-const NM_SETTINGS_CONNECTION_IFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
 const NM_DEVICE_IFACE: &str = "org.freedesktop.NetworkManager.Device";
 const NM_DEVICE_WIRELESS_IFACE: &str = "org.freedesktop.NetworkManager.Device.Wireless";
 const NM_ACCESS_POINT_IFACE: &str = "org.freedesktop.NetworkManager.AccessPoint";
+/// `NM80211ApFlags` bit set when the network needs any key.
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+/// `NM80211ApSecurityFlags` key management bits.
+const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+/// How long after a scan request the menu is rebuilt, giving NetworkManager time to report.
+const SCAN_SETTLE: Duration = Duration::from_secs(4);
+const SCAN_ITEM_ID: &str = "scan";
+/// Prefix of menu ids for visible networks without a saved connection.
+const NEW_NETWORK_PREFIX: &str = "new:";
+const PROPERTIES_IFACE: &str = "org.freedesktop.DBus.Properties";
+/// Wireless device properties that change what the gauge or its menu shows.
+const WATCHED_WIRELESS_PROPERTIES: [&str; 3] = ["ActiveAccessPoint", "AccessPoints", "LastScan"];
+/// Safety refresh while events are flowing, in case one was missed.
+const EVENT_REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
+/// Wait before reconnecting a dropped event stream or retrying a NetworkManager that failed.
+const EVENT_RESTART_DELAY: Duration = Duration::from_secs(30);
+/// rtnetlink message types and attribute for link changes, from `linux/rtnetlink.h`.
+const RTM_NEWLINK: u16 = 16;
+const RTM_DELLINK: u16 = 17;
+const IFLA_IFNAME: u16 = 3;
+/// Sizes of `struct nlmsghdr`, `struct ifinfomsg`, and `struct rtattr`.
+const NLMSG_HEADER_LEN: usize = 16;
+const IFINFO_LEN: usize = 16;
+const RTATTR_HEADER_LEN: usize = 4;
+
+settings::typed_settings! {
+    struct WifiSettings {
+        quality_max: f32 = ("grelier.gauge.wifi.quality_max", "70"),
+        poll_interval_secs: u64 = ("grelier.gauge.wifi.poll_interval_secs", "3", 1..=3600),
+    }
+}
 
 #[derive(Clone, Copy, Debug)]
 enum WifiState {
@@ -59,9 +105,121 @@ struct WifiMenuEntry {
     ssid: Option<String>,
 }
 
+/// How a network authenticates, from its access point's flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum KeyMgmt {
+    Open,
+    /// WPA/WPA2 personal.
+    Psk,
+    /// WPA3 personal.
+    Sae,
+    /// 802.1X, which needs more than a passphrase.
+    Enterprise,
+}
+
+impl KeyMgmt {
+    fn from_flags(flags: u32, wpa_flags: u32, rsn_flags: u32) -> Self {
+        let security = wpa_flags | rsn_flags;
+        if security & AP_SEC_KEY_MGMT_802_1X != 0 {
+            Self::Enterprise
+        } else if security & AP_SEC_KEY_MGMT_PSK != 0 {
+            Self::Psk
+        } else if security & AP_SEC_KEY_MGMT_SAE != 0 {
+            Self::Sae
+        } else if flags & AP_FLAGS_PRIVACY != 0 {
+            // WEP; NetworkManager takes the key as a PSK-style passphrase too.
+            Self::Psk
+        } else {
+            Self::Open
+        }
+    }
+
+    /// `802-11-wireless-security.key-mgmt` value, or `None` for open networks.
+    fn setting(self) -> Option<&'static str> {
+        match self {
+            Self::Open => None,
+            Self::Psk => Some("wpa-psk"),
+            Self::Sae => Some("sae"),
+            Self::Enterprise => Some("wpa-eap"),
+        }
+    }
+
+    /// From the `Type` of an iwd network.
+    fn from_iwd_type(kind: &str) -> Self {
+        match kind {
+            "open" => Self::Open,
+            "8021x" => Self::Enterprise,
+            _ => Self::Psk,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(Self::Open),
+            "wpa-psk" => Some(Self::Psk),
+            "sae" => Some(Self::Sae),
+            "wpa-eap" => Some(Self::Enterprise),
+            _ => None,
+        }
+    }
+}
+
+/// One visible network, keeping the strongest access point per SSID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct AccessPoint {
+    path: String,
+    ssid: String,
+    /// Signal strength in percent.
+    strength: u8,
+    key_mgmt: KeyMgmt,
+}
+
+impl AccessPoint {
+    /// Menu id carrying what joining the network needs, as `new:<key-mgmt>:<path>:<ssid>`.
+    fn item_id(&self) -> String {
+        format!(
+            "{NEW_NETWORK_PREFIX}{}:{}:{}",
+            self.key_mgmt.setting().unwrap_or("none"),
+            self.path,
+            self.ssid
+        )
+    }
+
+    fn from_item_id(item_id: &str) -> Option<Self> {
+        let mut parts = item_id.strip_prefix(NEW_NETWORK_PREFIX)?.splitn(3, ':');
+        let key_mgmt = KeyMgmt::parse(parts.next()?)?;
+        let path = parts.next()?.to_string();
+        let ssid = parts.next()?.to_string();
+        Some(Self {
+            path,
+            ssid,
+            strength: 0,
+            key_mgmt,
+        })
+    }
+}
+
+/// Service managing the Wi-Fi device, with its object for the device.
+#[derive(Clone, Debug)]
+enum WifiBackend {
+    /// NetworkManager device path.
+    NetworkManager(OwnedObjectPath),
+    /// iwd station path, used when NetworkManager does not manage the device.
+    Iwd(OwnedObjectPath),
+}
+
+fn wifi_backend(connection: &Connection, iface: &str) -> Option<WifiBackend> {
+    nm_device_path(connection, iface)
+        .map(WifiBackend::NetworkManager)
+        .or_else(|| iwd_common::station_path(connection, iface).map(WifiBackend::Iwd))
+}
+
 #[derive(Debug)]
 enum WifiCommand {
     Connect(String),
+    /// Join a network without a saved connection, with its passphrase when it is secured.
+    ConnectNew(AccessPoint, Option<String>),
+    Scan,
 }
 
 fn wifi_interfaces_at(sys_net: &Path) -> Vec<String> {
@@ -121,6 +279,13 @@ fn read_ssid(iface: &str) -> Option<String> {
     if let Some(ssid) = read_ssid_network_manager(iface) {
         return Some(ssid);
     }
+    if let Some(ssid) = Connection::system()
+        .ok()
+        .and_then(|connection| iwd_common::connected_ssid(&connection, iface))
+        .and_then(|ssid| normalize_ssid(&ssid))
+    {
+        return Some(ssid);
+    }
 
     for dir in WPA_CTRL_DIRS {
         let path = Path::new(dir).join(iface);
@@ -238,99 +403,32 @@ fn active_connection_path(
     }
 }
 
-fn active_settings_connection_path(
-    connection: &Connection,
-    active_path: &OwnedObjectPath,
-) -> Option<OwnedObjectPath> {
-    let active_proxy = Proxy::new(
-        connection,
-        NM_SERVICE,
-        active_path,
-        "org.freedesktop.NetworkManager.Connection.Active",
-    )
-    .ok()?;
-    let settings_path: OwnedObjectPath = active_proxy.get_property("Connection").ok()?;
-    if settings_path.as_str() == "/" {
-        None
-    } else {
-        Some(settings_path)
-    }
-}
-
 fn wifi_connection_entries(
     connection: &Connection,
     available_ssids: Option<&HashSet<String>>,
 ) -> Vec<WifiMenuEntry> {
-    let settings_proxy =
-        match Proxy::new(connection, NM_SERVICE, NM_SETTINGS_PATH, NM_SETTINGS_IFACE) {
-            Ok(proxy) => proxy,
-            Err(_) => return Vec::new(),
-        };
-    let paths: Vec<OwnedObjectPath> = match settings_proxy.call("ListConnections", &()) {
-        Ok(paths) => paths,
-        Err(_) => return Vec::new(),
-    };
-
-    let mut entries = Vec::new();
-    for path in paths {
-        let conn_proxy = match Proxy::new(
-            connection,
-            NM_SERVICE,
-            path.clone(),
-            NM_SETTINGS_CONNECTION_IFACE,
-        ) {
-            Ok(proxy) => proxy,
-            Err(_) => continue,
-        };
-        let settings: HashMap<String, HashMap<String, OwnedValue>> =
-            match conn_proxy.call("GetSettings", &()) {
-                Ok(settings) => settings,
-                Err(_) => continue,
-            };
-        let Some(connection_section) = settings.get("connection") else {
-            continue;
-        };
-        let connection_type: Option<String> = connection_section
-            .get("type")
-            .and_then(|value| value.try_clone().ok())
-            .and_then(|value| value.try_into().ok());
-        if connection_type.as_deref() != Some("802-11-wireless") {
-            continue;
-        }
-        let id = connection_section
-            .get("id")
-            .and_then(|value| value.try_clone().ok())
-            .and_then(|value| value.try_into().ok())
-            .unwrap_or_else(|| path.as_str().to_string());
-        let timestamp: Option<u64> = connection_section
-            .get("timestamp")
-            .and_then(|value| value.try_clone().ok())
-            .and_then(|value| value.try_into().ok());
-        if let Some(last_seen) = timestamp
-            && last_seen == 0
-        {
-            continue;
-        }
-        let ssid = settings
-            .get("802-11-wireless")
-            .and_then(|section| section.get("ssid"))
-            .and_then(|value| value.try_clone().ok())
-            .and_then(|value| value.try_into().ok())
-            .and_then(|bytes: Vec<u8>| normalize_ssid_bytes(&bytes));
-        if let Some(available) = available_ssids {
-            if let Some(ssid) = ssid.as_deref() {
-                if !available.contains(ssid) {
-                    continue;
-                }
-            } else {
-                continue;
+    nm_common::connection_entries(connection, &["802-11-wireless"])
+        .into_iter()
+        .filter_map(|entry| {
+            let timestamp: Option<u64> =
+                nm_common::setting_value(&entry.settings, "connection", "timestamp");
+            if timestamp == Some(0) {
+                return None;
             }
-        }
-        entries.push(WifiMenuEntry { id, path, ssid });
-    }
-
-    entries.sort_by(|a, b| a.id.cmp(&b.id));
-    entries
+            let ssid = nm_common::setting_value(&entry.settings, "802-11-wireless", "ssid")
+                .and_then(|bytes: Vec<u8>| normalize_ssid_bytes(&bytes));
+            if let Some(available) = available_ssids
+                && !ssid.as_deref().is_some_and(|ssid| available.contains(ssid))
+            {
+                return None;
+            }
+            Some(WifiMenuEntry {
+                id: entry.id,
+                path: entry.path,
+                ssid,
+            })
+        })
+        .collect()
 }
 
 fn connection_label(entry: &WifiMenuEntry) -> String {
@@ -352,6 +450,7 @@ fn wifi_menu_items(
     entries: &[WifiMenuEntry],
     active_connection: Option<&OwnedObjectPath>,
     active_ssid: Option<&str>,
+    aps: &[AccessPoint],
 ) -> Vec<GaugeMenuItem> {
     let mut items: Vec<GaugeMenuItem> = entries
         .iter()
@@ -359,11 +458,18 @@ fn wifi_menu_items(
             let selected = active_connection.is_some_and(|path| path == &entry.path)
                 || (active_connection.is_none()
                     && active_ssid.is_some_and(|ssid| entry.ssid.as_deref() == Some(ssid)));
-            let label = connection_label(entry);
+            let mut label = connection_label(entry);
+            if let Some(ap) = aps
+                .iter()
+                .find(|ap| entry.ssid.as_deref() == Some(ap.ssid.as_str()))
+            {
+                label = format!("{label} ({}%)", ap.strength);
+            }
             GaugeMenuItem {
                 id: entry.path.as_str().to_string(),
                 label,
                 selected,
+                exec: None,
             }
         })
         .collect();
@@ -371,23 +477,63 @@ fn wifi_menu_items(
     items
 }
 
-fn refresh_wifi_menu_items(connection: &Connection, snapshot: &WifiSnapshot) -> Vec<GaugeMenuItem> {
-    let Some(iface) = snapshot.iface.as_deref() else {
-        return Vec::new();
-    };
-    let Some(device_path) = nm_device_path(connection, iface) else {
-        return Vec::new();
-    };
+/// Known iwd networks connect by their network path; the rest are offered as new networks.
+fn iwd_menu_items(networks: &[IwdNetwork]) -> Vec<GaugeMenuItem> {
+    let mut items: Vec<GaugeMenuItem> = networks
+        .iter()
+        .filter(|network| network.known)
+        .map(|network| GaugeMenuItem {
+            id: network.path.as_str().to_string(),
+            label: format!("{} ({}%)", network.ssid, network.strength),
+            selected: network.connected,
+            exec: None,
+        })
+        .collect();
+    items.sort_by(|a, b| a.label.cmp(&b.label));
+    let unknown: Vec<AccessPoint> = networks
+        .iter()
+        .filter(|network| !network.known)
+        .map(|network| AccessPoint {
+            path: network.path.as_str().to_string(),
+            ssid: network.ssid.clone(),
+            strength: network.strength,
+            key_mgmt: KeyMgmt::from_iwd_type(&network.kind),
+        })
+        .collect();
+    items.extend(new_network_items(&unknown, &[]));
+    items
+}
 
-    let available = available_ssids(connection, &device_path);
-    let entries = wifi_connection_entries(connection, Some(&available));
-    let active_connection = active_connection_path(connection, &device_path)
-        .and_then(|path| active_settings_connection_path(connection, &path));
-    wifi_menu_items(
-        &entries,
-        active_connection.as_ref(),
-        snapshot.ssid.as_deref(),
-    )
+fn refresh_wifi_menu_items(
+    connection: &Connection,
+    backend: &WifiBackend,
+    snapshot: &WifiSnapshot,
+) -> Vec<GaugeMenuItem> {
+    let mut items = match backend {
+        WifiBackend::NetworkManager(device_path) => {
+            let aps = visible_access_points(connection, device_path);
+            let available: HashSet<String> = aps.iter().map(|ap| ap.ssid.clone()).collect();
+            let entries = wifi_connection_entries(connection, Some(&available));
+            let active_connection = active_connection_path(connection, device_path)
+                .and_then(|path| active_settings_connection_path(connection, &path));
+            let mut items = wifi_menu_items(
+                &entries,
+                active_connection.as_ref(),
+                snapshot.ssid.as_deref(),
+                &aps,
+            );
+            items.extend(new_network_items(&aps, &entries));
+            items
+        }
+        WifiBackend::Iwd(station) => iwd_menu_items(&iwd_common::networks(connection, station)),
+    };
+    items.push(GaugeMenuItem {
+        id: SCAN_ITEM_ID.to_string(),
+        label: "Scan for networks".to_string(),
+        selected: false,
+        exec: None,
+    });
+    items
 }
 
 fn should_refresh_menu(
@@ -399,33 +545,11 @@ fn should_refresh_menu(
     now >= menu_refresh_deadline || cached_iface != current_iface
 }
 
-fn activate_connection(
+/// Networks in range from the device's last scan, strongest first. Hidden networks are skipped.
+fn visible_access_points(
     connection: &Connection,
-    connection_path: &str,
     device_path: &OwnedObjectPath,
-) -> bool {
-    let nm_proxy = match Proxy::new(connection, NM_SERVICE, NM_PATH, NM_IFACE) {
-        Ok(proxy) => proxy,
-        Err(_) => return false,
-    };
-    let connection_path = match OwnedObjectPath::try_from(connection_path) {
-        Ok(path) => path,
-        Err(_) => return false,
-    };
-    let specific = match OwnedObjectPath::try_from("/") {
-        Ok(path) => path,
-        Err(_) => return false,
-    };
-    nm_proxy
-        .call::<_, _, OwnedObjectPath>(
-            "ActivateConnection",
-            &(connection_path, device_path, specific),
-        )
-        .is_ok()
-}
-
-fn available_ssids(connection: &Connection, device_path: &OwnedObjectPath) -> HashSet<String> {
-    let mut ssids = HashSet::new();
+) -> Vec<AccessPoint> {
     let device_proxy = match Proxy::new(
         connection,
         NM_SERVICE,
@@ -433,26 +557,111 @@ fn available_ssids(connection: &Connection, device_path: &OwnedObjectPath) -> Ha
         NM_DEVICE_WIRELESS_IFACE,
     ) {
         Ok(proxy) => proxy,
-        Err(_) => return ssids,
+        Err(_) => return Vec::new(),
     };
     let ap_paths: Vec<OwnedObjectPath> = match device_proxy.call("GetAllAccessPoints", &()) {
         Ok(paths) => paths,
-        Err(_) => return ssids,
+        Err(_) => return Vec::new(),
     };
+    let mut strongest: HashMap<String, AccessPoint> = HashMap::new();
     for ap_path in ap_paths {
-        let ap_proxy = match Proxy::new(connection, NM_SERVICE, ap_path, NM_ACCESS_POINT_IFACE) {
-            Ok(proxy) => proxy,
-            Err(_) => continue,
+        let Ok(ap_proxy) = Proxy::new(connection, NM_SERVICE, &ap_path, NM_ACCESS_POINT_IFACE)
+        else {
+            continue;
+        };
+        let Some(ssid) = ap_proxy
+            .get_property::<Vec<u8>>("Ssid")
+            .ok()
+            .and_then(|bytes| normalize_ssid_bytes(&bytes))
+        else {
+            continue;
         };
-        let ssid_bytes: Vec<u8> = match ap_proxy.get_property("Ssid") {
-            Ok(bytes) => bytes,
-            Err(_) => continue,
+        let flag = |name: &str| ap_proxy.get_property::<u32>(name).unwrap_or(0);
+        let ap = AccessPoint {
+            path: ap_path.as_str().to_string(),
+            strength: ap_proxy.get_property("Strength").unwrap_or(0),
+            key_mgmt: KeyMgmt::from_flags(flag("Flags"), flag("WpaFlags"), flag("RsnFlags")),
+            ssid,
         };
-        if let Some(ssid) = normalize_ssid_bytes(&ssid_bytes) {
-            ssids.insert(ssid);
+        if strongest
+            .get(&ap.ssid)
+            .is_none_or(|known| known.strength < ap.strength)
+        {
+            strongest.insert(ap.ssid.clone(), ap);
         }
     }
-    ssids
+    let mut aps: Vec<AccessPoint> = strongest.into_values().collect();
+    aps.sort_by(|a, b| {
+        b.strength
+            .cmp(&a.strength)
+            .then_with(|| a.ssid.cmp(&b.ssid))
+    });
+    aps
+}
+
+/// Menu items for visible networks that no saved connection covers.
+fn new_network_items(aps: &[AccessPoint], entries: &[WifiMenuEntry]) -> Vec<GaugeMenuItem> {
+    aps.iter()
+        .filter(|ap| {
+            !entries
+                .iter()
+                .any(|entry| entry.ssid.as_deref() == Some(ap.ssid.as_str()))
+        })
+        .map(|ap| GaugeMenuItem {
+            id: ap.item_id(),
+            label: match ap.key_mgmt {
+                KeyMgmt::Open => format!("{} ({}%, open)", ap.ssid, ap.strength),
+                _ => format!("{} ({}%, secured)", ap.ssid, ap.strength),
+            },
+            selected: false,
+            exec: None,
+        })
+        .collect()
+}
+
+/// Ask NetworkManager to scan; results show up as the access point list changes.
+fn request_scan(connection: &Connection, device_path: &OwnedObjectPath) -> Result<(), String> {
+    let options: HashMap<&str, Value> = HashMap::new();
+    Proxy::new(
+        connection,
+        NM_SERVICE,
+        device_path,
+        NM_DEVICE_WIRELESS_IFACE,
+    )
+    .and_then(|proxy| proxy.call::<_, _, ()>("RequestScan", &(options,)))
+    .map_err(|err| format!("scan failed: {err}"))
+}
+
+/// Save a connection for `ap` and join it.
+fn add_and_activate(
+    connection: &Connection,
+    device_path: &OwnedObjectPath,
+    ap: &AccessPoint,
+    passphrase: Option<&str>,
+) -> Result<(), String> {
+    let mut settings: HashMap<&str, HashMap<&str, Value>> = HashMap::new();
+    settings.insert(
+        "802-11-wireless",
+        HashMap::from([("ssid", Value::from(ap.ssid.as_bytes().to_vec()))]),
+    );
+    if let Some(key_mgmt) = ap.key_mgmt.setting() {
+        let mut security = HashMap::from([("key-mgmt", Value::from(key_mgmt))]);
+        if let Some(passphrase) = passphrase {
+            security.insert("psk", Value::from(passphrase));
+        }
+        settings.insert("802-11-wireless-security", security);
+    }
+    let specific = OwnedObjectPath::try_from(ap.path.as_str())
+        .map_err(|err| format!("invalid access point path: {err}"))?;
+    Proxy::new(connection, NM_SERVICE, NM_PATH, NM_IFACE)
+        .and_then(|proxy| {
+            proxy.call::<_, _, (OwnedObjectPath, OwnedObjectPath)>(
+                "AddAndActivateConnection",
+                &(settings, device_path, specific),
+            )
+        })
+        .map(|_| ())
+        .map_err(|err| format!("could not join {}: {err}", ap.ssid))
 }
 
 fn interface_connected(path: &Path, quality: Option<f32>) -> bool {
@@ -549,10 +758,15 @@ fn wifi_info_dialog(snapshot: &WifiSnapshot) -> InfoDialog {
     InfoDialog {
         title: "Wi-Fi".to_string(),
         lines: vec![device_line, ssid_line, signal_line],
+        graph: None,
     }
 }
 
 fn wifi_gauge(snapshot: WifiSnapshot, menu: Option<GaugeMenu>) -> GaugeModel {
+    let mut info = wifi_info_dialog(&snapshot);
+    if menu.is_none() || matches!(snapshot.state, WifiState::NoDevice) {
+        info.lines.extend(gauge_dependencies::missing_hints("wifi"));
+    }
     let (icon, attention) = match snapshot.state {
         WifiState::Connected => ("wifi.svg", GaugeValueAttention::Nominal),
         WifiState::NotConnected => ("wifi-off.svg", GaugeValueAttention::Warning),
@@ -571,7 +785,7 @@ fn wifi_gauge(snapshot: WifiSnapshot, menu: Option<GaugeMenu>) -> GaugeModel {
         },
         interactions: GaugeInteractionModel {
             left_click: GaugePointerInteraction {
-                info: Some(wifi_info_dialog(&snapshot)),
+                info: Some(info),
                 ..GaugePointerInteraction::default()
             },
             right_click: GaugePointerInteraction {
@@ -583,6 +797,358 @@ fn wifi_gauge(snapshot: WifiSnapshot, menu: Option<GaugeMenu>) -> GaugeModel {
     }
 }
 
+/// Netlink attributes and messages are padded to four bytes.
+fn nl_align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Interface names in the `RTM_NEWLINK`/`RTM_DELLINK` messages of one netlink datagram, with
+/// whether the link went away.
+fn link_changes(buf: &[u8]) -> Vec<(String, bool)> {
+    let mut changes = Vec::new();
+    let mut offset = 0;
+    while offset + NLMSG_HEADER_LEN <= buf.len() {
+        let header = &buf[offset..];
+        let len = u32::from_ne_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if len < NLMSG_HEADER_LEN || offset + len > buf.len() {
+            break;
+        }
+        let kind = u16::from_ne_bytes([header[4], header[5]]);
+        if kind == RTM_NEWLINK || kind == RTM_DELLINK {
+            let mut attr = offset + NLMSG_HEADER_LEN + IFINFO_LEN;
+            while attr + RTATTR_HEADER_LEN <= offset + len {
+                let attr_len = u16::from_ne_bytes([buf[attr], buf[attr + 1]]) as usize;
+                let attr_kind = u16::from_ne_bytes([buf[attr + 2], buf[attr + 3]]);
+                if attr_len < RTATTR_HEADER_LEN || attr + attr_len > offset + len {
+                    break;
+                }
+                if attr_kind == IFLA_IFNAME {
+                    let name = &buf[attr + RTATTR_HEADER_LEN..attr + attr_len];
+                    let name = name.split(|byte| *byte == 0).next().unwrap_or_default();
+                    changes.push((
+                        String::from_utf8_lossy(name).into_owned(),
+                        kind == RTM_DELLINK,
+                    ));
+                    break;
+                }
+                attr += nl_align(attr_len);
+            }
+        }
+        offset += nl_align(len);
+    }
+    changes
+}
+
+/// Socket receiving rtnetlink link messages, which cover carrier, operstate, and association
+/// changes of every interface.
+fn link_socket() -> io::Result<OwnedFd> {
+    // SAFETY: plain socket(2) call; the returned descriptor is owned below.
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            libc::NETLINK_ROUTE,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` is a fresh descriptor nothing else owns.
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    // SAFETY: an all-zero `sockaddr_nl` is valid; the fields that matter are set next.
+    let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    addr.nl_groups = libc::RTMGRP_LINK as u32;
+    // SAFETY: `addr` is a valid `sockaddr_nl` and the length matches it.
+    let bound = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            (&addr as *const libc::sockaddr_nl).cast(),
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if bound < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(socket)
+}
+
+/// Async event source waking the gauge on link changes of wireless interfaces, or of ones
+/// that went away.
+struct LinkEvents {
+    /// Opened by the first poll; `None` before then and after it failed.
+    socket: Option<Async<OwnedFd>>,
+    /// Whether the socket could not be opened or read, which ends the source.
+    failed: bool,
+    buf: Vec<u8>,
+}
+
+impl LinkEvents {
+    fn new() -> Self {
+        Self {
+            socket: None,
+            failed: false,
+            buf: vec![0u8; 16 * 1024],
+        }
+    }
+
+    /// Wait for the next datagram; `Ok(true)` when it concerns a wireless interface.
+    async fn next_change(&mut self) -> Result<bool, String> {
+        if self.socket.is_none() {
+            let socket = link_socket()
+                .and_then(Async::new)
+                .map_err(|err| format!("netlink socket: {err}"))?;
+            self.socket = Some(socket);
+        }
+        let Some(socket) = &self.socket else {
+            return Ok(false);
+        };
+        let buf = &mut self.buf;
+        let received = socket
+            .read_with(|socket| {
+                // SAFETY: `buf` is valid for writes of its length.
+                let received = unsafe {
+                    libc::recv(socket.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0)
+                };
+                if received < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(received as usize)
+                }
+            })
+            .await;
+        let received = match received {
+            Ok(received) => received,
+            // Overruns drop messages; rereading the state covers whatever was lost.
+            Err(err) if err.raw_os_error() == Some(libc::ENOBUFS) => return Ok(true),
+            Err(err) => return Err(format!("netlink receive: {err}")),
+        };
+        let sys_net = Path::new(SYS_NET);
+        Ok(link_changes(&self.buf[..received])
+            .iter()
+            .any(|(name, removed)| *removed || is_wifi_iface(&sys_net.join(name))))
+    }
+}
+
+impl AsyncGaugeEventSource for LinkEvents {
+    fn interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Each poll waits for the next link message, however long that takes.
+    fn timeout(&self) -> Duration {
+        Duration::MAX
+    }
+
+    fn poll(&mut self) -> GaugeFuture<'_, bool> {
+        Box::pin(async move {
+            if self.failed {
+                return future::pending().await;
+            }
+            match self.next_change().await {
+                Ok(changed) => changed,
+                Err(err) => {
+                    log::warn!("wifi gauge: {err}");
+                    self.failed = true;
+                    false
+                }
+            }
+        })
+    }
+}
+
+/// Whether a NetworkManager `PropertiesChanged` signal affects the gauge or its menu.
+fn nm_change_matters(
+    path: &str,
+    interface: &str,
+    changed: &HashMap<String, OwnedValue>,
+    active_ap: Option<&str>,
+) -> bool {
+    match interface {
+        NM_DEVICE_IFACE => changed.contains_key("State"),
+        NM_DEVICE_WIRELESS_IFACE => WATCHED_WIRELESS_PROPERTIES
+            .iter()
+            .any(|property| changed.contains_key(*property)),
+        NM_ACCESS_POINT_IFACE => active_ap == Some(path) && changed.contains_key("Strength"),
+        _ => false,
+    }
+}
+
+/// Whether a signal from the NetworkManager match rule affects the gauge or its menu.
+fn nm_signal_matters(message: &zbus::Message, active_ap: &Mutex<Option<String>>) -> bool {
+    let header = message.header();
+    let Some(path) = header.path() else {
+        return false;
+    };
+    let Ok((interface, changed, _)) =
+        message
+            .body()
+            .deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>()
+    else {
+        return false;
+    };
+    let active_ap = active_ap.lock().ok().and_then(|active| active.clone());
+    nm_change_matters(path.as_str(), &interface, &changed, active_ap.as_deref())
+}
+
+/// Subscribe to NetworkManager property changes, failing when NetworkManager is not running.
+async fn subscribe_network_manager(connection: &zbus::Connection) -> Result<MessageStream, String> {
+    let rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface(PROPERTIES_IFACE)
+        .and_then(|builder| builder.member("PropertiesChanged"))
+        .and_then(|builder| builder.path_namespace(NM_PATH))
+        .map_err(|err| format!("match rule: {err}"))?
+        .build();
+    let messages = MessageStream::for_match_rule(rule, connection, Some(64))
+        .await
+        .map_err(|err| format!("signal subscription: {err}"))?;
+    // Without NetworkManager nothing reports signal changes, so the gauge keeps polling.
+    let proxy = zbus::Proxy::new(connection, NM_SERVICE, NM_PATH, NM_IFACE)
+        .await
+        .map_err(|err| format!("NetworkManager proxy: {err}"))?;
+    if proxy.get_property::<String>("Version").await.is_err() {
+        return Err("NetworkManager is not running".to_string());
+    }
+    Ok(messages)
+}
+
+/// Wait until NetworkManager takes its bus name. Returns `Ok(false)` if it is already running,
+/// so the caller backs off instead of retrying a watch that just failed.
+async fn wait_for_network_manager(connection: &zbus::Connection) -> Result<bool, String> {
+    let bus = DBusProxy::new(connection)
+        .await
+        .map_err(|err| format!("bus proxy: {err}"))?;
+    // Subscribe before checking, so a start in between is not missed.
+    let mut owner_changes = bus
+        .receive_name_owner_changed_with_args(&[(0, NM_SERVICE)])
+        .await
+        .map_err(|err| format!("name owner subscription: {err}"))?;
+    let name = BusName::try_from(NM_SERVICE).map_err(|err| err.to_string())?;
+    if bus.name_has_owner(name).await.unwrap_or(false) {
+        return Ok(false);
+    }
+    while let Some(signal) = owner_changes.next().await {
+        if signal.args().is_ok_and(|args| args.new_owner().is_some()) {
+            return Ok(true);
+        }
+    }
+    Err("name owner stream ended".to_string())
+}
+
+/// Where `NetworkManagerEvents` is in following NetworkManager.
+enum NmStage {
+    Subscribe,
+    Watch(Box<MessageStream>),
+    /// On iwd-only systems this waits on the bus until NetworkManager ever starts.
+    WaitForService,
+}
+
+/// Async event source waking the gauge when NetworkManager state changes.
+struct NetworkManagerEvents {
+    /// Set while NetworkManager signals are flowing, which lets the gauge stop polling.
+    live: Arc<AtomicBool>,
+    /// Active access point path, so strength changes of other networks are ignored.
+    active_ap: Arc<Mutex<Option<String>>>,
+    /// System bus connection, opened by the first poll.
+    connection: Option<zbus::Connection>,
+    stage: NmStage,
+}
+
+impl NetworkManagerEvents {
+    async fn connection(&mut self) -> zbus::Connection {
+        loop {
+            if let Some(connection) = &self.connection {
+                return connection.clone();
+            }
+            match zbus::Connection::system().await {
+                Ok(connection) => self.connection = Some(connection),
+                Err(err) => {
+                    log::debug!("wifi gauge: system bus: {err}");
+                    Timer::after(EVENT_RESTART_DELAY).await;
+                }
+            }
+        }
+    }
+
+    /// Wait for the next change the gauge should see.
+    async fn next_change(&mut self) {
+        loop {
+            let connection = self.connection().await;
+            let lost = match &mut self.stage {
+                NmStage::Subscribe => match subscribe_network_manager(&connection).await {
+                    Ok(messages) => {
+                        self.stage = NmStage::Watch(Box::new(messages));
+                        self.live.store(true, Ordering::Relaxed);
+                        return;
+                    }
+                    Err(err) => err,
+                },
+                NmStage::Watch(messages) => match messages.next().await {
+                    Some(Ok(message)) => {
+                        if nm_signal_matters(&message, &self.active_ap) {
+                            return;
+                        }
+                        continue;
+                    }
+                    _ => "NetworkManager signal stream ended".to_string(),
+                },
+                NmStage::WaitForService => {
+                    match wait_for_network_manager(&connection).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            Timer::after(EVENT_RESTART_DELAY).await;
+                        }
+                        Err(err) => {
+                            log::debug!("wifi gauge: {err}");
+                            Timer::after(EVENT_RESTART_DELAY).await;
+                        }
+                    }
+                    self.stage = NmStage::Subscribe;
+                    continue;
+                }
+            };
+            log::debug!("wifi gauge: {lost}");
+            self.stage = NmStage::WaitForService;
+            // Wake the gauge so it polls again until the stream is back.
+            if self.live.swap(false, Ordering::Relaxed) {
+                return;
+            }
+        }
+    }
+}
+
+impl AsyncGaugeEventSource for NetworkManagerEvents {
+    fn interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Each poll waits for the next change, however long that takes.
+    fn timeout(&self) -> Duration {
+        Duration::MAX
+    }
+
+    fn poll(&mut self) -> GaugeFuture<'_, bool> {
+        Box::pin(async move {
+            self.next_change().await;
+            true
+        })
+    }
+}
+
+fn active_access_point(connection: &Connection, device_path: &OwnedObjectPath) -> Option<String> {
+    let path: OwnedObjectPath = Proxy::new(
+        connection,
+        NM_SERVICE,
+        device_path,
+        NM_DEVICE_WIRELESS_IFACE,
+    )
+    .ok()?
+    .get_property("ActiveAccessPoint")
+    .ok()?;
+    (path.as_str() != "/").then(|| path.as_str().to_string())
+}
+
 /// Gauge that monitors Wi-Fi signal quality and provides network actions.
 struct WifiGauge {
     /// Upper bound used to normalize signal quality into a percentage.
@@ -603,6 +1169,14 @@ struct WifiGauge {
     cached_menu_iface: Option<String>,
     /// Absolute deadline for refreshing cached menu data.
     menu_refresh_deadline: Instant,
+    /// Set after a scan request until the menu picks up its results.
+    scan_pending: bool,
+    /// Event sources handed to the work manager on registration.
+    events: Option<(LinkEvents, NetworkManagerEvents)>,
+    /// Whether NetworkManager signals are flowing.
+    events_live: Arc<AtomicBool>,
+    /// Shared with the event sources to filter access point strength changes.
+    active_ap: Arc<Mutex<Option<String>>>,
     /// Scheduler deadline for the next run.
     next_deadline: Instant,
 }
@@ -616,36 +1190,120 @@ impl Gauge for WifiGauge {
         self.ready_notify = Some(notify);
     }
 
+    fn register(&mut self, registrar: &mut dyn GaugeRegistrar) {
+        if let Some((links, network_manager)) = self.events.take() {
+            registrar.add_async_event_source(Box::new(links));
+            registrar.add_async_event_source(Box::new(network_manager));
+        }
+    }
+
     fn next_deadline(&self) -> Instant {
         self.next_deadline
     }
 
     fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
         let snapshot = wifi_snapshot(self.quality_max);
-        let nm_connection = Connection::system().ok();
-        let device_path = nm_connection.as_ref().and_then(|connection| {
+        let system_bus = Connection::system().ok();
+        let backend = system_bus.as_ref().and_then(|connection| {
             snapshot
                 .iface
                 .as_deref()
-                .and_then(|iface| nm_device_path(connection, iface))
+                .and_then(|iface| wifi_backend(connection, iface))
         });
 
         while let Ok(command) = self.command_rx.try_recv() {
-            if let (Some(connection), Some(device_path)) =
-                (nm_connection.as_ref(), device_path.as_ref())
-            {
-                let WifiCommand::Connect(connection_path) = command;
-                let _ = activate_connection(connection, &connection_path, device_path);
+            let (Some(connection), Some(backend)) = (system_bus.as_ref(), backend.as_ref()) else {
+                continue;
+            };
+            match (command, backend) {
+                (WifiCommand::Connect(path), WifiBackend::NetworkManager(device_path)) => {
+                    let _ = activate_connection(connection, &path, device_path);
+                }
+                (WifiCommand::Connect(path), WifiBackend::Iwd(_)) => {
+                    if let Err(err) = iwd_common::connect(connection, &path, None) {
+                        log::warn!("wifi gauge: {err}");
+                    }
+                }
+                (WifiCommand::ConnectNew(ap, passphrase), backend) => {
+                    let result = match backend {
+                        WifiBackend::NetworkManager(device_path) => {
+                            add_and_activate(connection, device_path, &ap, passphrase.as_deref())
+                        }
+                        WifiBackend::Iwd(_) => {
+                            iwd_common::connect(connection, &ap.path, passphrase.as_deref())
+                                .map_err(|err| format!("could not join {}: {err}", ap.ssid))
+                        }
+                    };
+                    if let Err(err) = result {
+                        log::warn!("wifi gauge: {err}");
+                        toast::show(
+                            Toast::new("Wi-Fi", err).with_attention(GaugeValueAttention::Warning),
+                        );
+                    }
+                    self.menu_refresh_deadline = now;
+                }
+                (WifiCommand::Scan, backend) => {
+                    let result = match backend {
+                        WifiBackend::NetworkManager(device_path) => {
+                            request_scan(connection, device_path)
+                        }
+                        WifiBackend::Iwd(station) => iwd_common::scan(connection, station),
+                    };
+                    match result {
+                        Ok(()) => {
+                            self.menu_refresh_deadline = now + SCAN_SETTLE;
+                            self.scan_pending = true;
+                        }
+                        Err(err) => log::warn!("wifi gauge: {err}"),
+                    }
+                }
             }
         }
 
         let menu_select: MenuSelectAction = {
             let command_tx = self.command_tx.clone();
             let ready_notify = self.ready_notify.clone();
-            Arc::new(move |connection_path: String| {
-                let _ = command_tx.send(WifiCommand::Connect(connection_path));
-                if let Some(ready_notify) = &ready_notify {
-                    ready_notify("wifi");
+            Arc::new(move |item_id: String| {
+                let notify = ready_notify.clone();
+                let send = {
+                    let command_tx = command_tx.clone();
+                    move |command: WifiCommand| {
+                        let _ = command_tx.send(command);
+                        if let Some(notify) = &notify {
+                            notify("wifi");
+                        }
+                    }
+                };
+                if item_id == SCAN_ITEM_ID {
+                    send(WifiCommand::Scan);
+                    return;
+                }
+                let Some(ap) = AccessPoint::from_item_id(&item_id) else {
+                    send(WifiCommand::Connect(item_id));
+                    return;
+                };
+                match ap.key_mgmt {
+                    KeyMgmt::Open => send(WifiCommand::ConnectNew(ap, None)),
+                    KeyMgmt::Psk | KeyMgmt::Sae => prompt::request(
+                        "wifi",
+                        PromptDialog {
+                            title: format!("Join {}", ap.ssid),
+                            value: String::new(),
+                            placeholder: "Passphrase".to_string(),
+                            secret: true,
+                            editor: None,
+                            on_submit: Some(Arc::new(move |passphrase: String| {
+                                send(WifiCommand::ConnectNew(ap.clone(), Some(passphrase)))
+                            })),
+                        },
+                    ),
+                    KeyMgmt::Enterprise => toast::show(Toast::new(
+                        "Wi-Fi",
+                        format!(
+                            "{} uses enterprise sign-in; add it with nm-connection-editor.",
+                            ap.ssid
+                        ),
+                    )),
                 }
             })
         };
@@ -657,15 +1315,27 @@ impl Gauge for WifiGauge {
             self.cached_menu_iface.as_deref(),
             current_iface,
         ) {
-            self.cached_menu_items = nm_connection
+            self.cached_menu_items = system_bus
                 .as_ref()
-                .map(|connection| refresh_wifi_menu_items(connection, &snapshot))
+                .zip(backend.as_ref())
+                .map(|(connection, backend)| {
+                    refresh_wifi_menu_items(connection, backend, &snapshot)
+                })
                 .unwrap_or_default();
             self.cached_menu_iface = current_iface.map(ToString::to_string);
             self.menu_refresh_deadline = now + self.menu_refresh_interval;
+            self.scan_pending = false;
+        }
+        if let Ok(mut active_ap) = self.active_ap.lock() {
+            *active_ap = match (system_bus.as_ref(), backend.as_ref()) {
+                (Some(connection), Some(WifiBackend::NetworkManager(device_path))) => {
+                    active_access_point(connection, device_path)
+                }
+                _ => None,
+            };
         }
 
-        let menu = if nm_connection.is_some() && snapshot.iface.is_some() {
+        let menu = if backend.is_some() {
             Some(GaugeMenu {
                 title: "Wi-Fi Networks".to_string(),
                 items: self.cached_menu_items.clone(),
@@ -679,26 +1349,36 @@ impl Gauge for WifiGauge {
             None
         };
 
-        self.next_deadline = now + self.poll_interval;
+        let poll_deadline = if self.events_live.load(Ordering::Relaxed) {
+            now + EVENT_REFRESH_INTERVAL
+        } else {
+            now + Duration::from_secs(gauge_tuning::live_secs(
+                "wifi",
+                self.poll_interval.as_secs(),
+            ))
+        };
+        // Wake in time to pick up scan results.
+        self.next_deadline = if self.scan_pending {
+            poll_deadline.min(self.menu_refresh_deadline)
+        } else {
+            poll_deadline
+        };
         Some(wifi_gauge(snapshot, menu))
     }
 }
 
 pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
-    let mut quality_max =
-        settings::settings().get_parsed_or("grelier.gauge.wifi.quality_max", DEFAULT_QUALITY_MAX);
-    if quality_max <= 0.0 {
-        quality_max = DEFAULT_QUALITY_MAX;
-    }
-    let poll_interval_secs = settings::settings().get_parsed_or(
-        "grelier.gauge.wifi.poll_interval_secs",
-        DEFAULT_POLL_INTERVAL_SECS,
-    );
+    let mut settings = WifiSettings::load();
+    if settings.quality_max <= 0.0 {
+        settings.quality_max = WifiSettings::default().quality_max;
+    }
     let (command_tx, command_rx) = mpsc::channel::<WifiCommand>();
+    let events_live = Arc::new(AtomicBool::new(false));
+    let active_ap = Arc::new(Mutex::new(None));
 
     Box::new(WifiGauge {
-        quality_max,
-        poll_interval: Duration::from_secs(poll_interval_secs),
+        quality_max: settings.quality_max,
+        poll_interval: Duration::from_secs(settings.poll_interval_secs),
         menu_refresh_interval: Duration::from_secs(MENU_REFRESH_INTERVAL_SECS),
         command_tx,
         command_rx,
@@ -706,22 +1386,24 @@ pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
         cached_menu_items: Vec::new(),
         cached_menu_iface: None,
         menu_refresh_deadline: now,
+        scan_pending: false,
+        events: Some((
+            LinkEvents::new(),
+            NetworkManagerEvents {
+                live: Arc::clone(&events_live),
+                active_ap: Arc::clone(&active_ap),
+                connection: None,
+                stage: NmStage::Subscribe,
+            },
+        )),
+        events_live,
+        active_ap,
         next_deadline: now,
     })
 }
 
 pub fn settings() -> &'static [SettingSpec] {
-    const SETTINGS: &[SettingSpec] = &[
-        SettingSpec {
-            key: "grelier.gauge.wifi.quality_max",
-            default: "70",
-        },
-        SettingSpec {
-            key: "grelier.gauge.wifi.poll_interval_secs",
-            default: "3",
-        },
-    ];
-    SETTINGS
+    WifiSettings::SPECS
 }
 
 inventory::submit! {
@@ -856,4 +1538,71 @@ mod tests {
             Some("wlan0")
         ));
     }
+
+    #[test]
+    fn classifies_access_points_and_round_trips_menu_ids() {
+        assert_eq!(KeyMgmt::from_flags(0, 0, 0), KeyMgmt::Open);
+        assert_eq!(KeyMgmt::from_flags(1, 0, 0x188), KeyMgmt::Psk);
+        assert_eq!(KeyMgmt::from_flags(1, 0, 0x400), KeyMgmt::Sae);
+        assert_eq!(KeyMgmt::from_flags(1, 0x200, 0x188), KeyMgmt::Enterprise);
+
+        let ap = AccessPoint {
+            path: "/org/freedesktop/NetworkManager/AccessPoint/7".to_string(),
+            ssid: "Cafe: upstairs".to_string(),
+            strength: 0,
+            key_mgmt: KeyMgmt::Sae,
+        };
+        assert_eq!(AccessPoint::from_item_id(&ap.item_id()), Some(ap.clone()));
+        assert_eq!(
+            AccessPoint::from_item_id("/org/freedesktop/NetworkManager/Settings/3"),
+            None
+        );
+
+        let items = new_network_items(&[AccessPoint { strength: 72, ..ap }], &[]);
+        assert_eq!(items[0].label, "Cafe: upstairs (72%, secured)");
+    }
+
+    #[test]
+    fn reads_link_names_from_netlink_messages() {
+        fn link_message(kind: u16, name: &str) -> Vec<u8> {
+            let mut attr = Vec::new();
+            let attr_len = RTATTR_HEADER_LEN + name.len() + 1;
+            attr.extend_from_slice(&(attr_len as u16).to_ne_bytes());
+            attr.extend_from_slice(&IFLA_IFNAME.to_ne_bytes());
+            attr.extend_from_slice(name.as_bytes());
+            attr.resize(nl_align(attr_len), 0);
+            let len = NLMSG_HEADER_LEN + IFINFO_LEN + attr.len();
+            let mut message = Vec::new();
+            message.extend_from_slice(&(len as u32).to_ne_bytes());
+            message.extend_from_slice(&kind.to_ne_bytes());
+            message.resize(NLMSG_HEADER_LEN + IFINFO_LEN, 0);
+            message.extend_from_slice(&attr);
+            message
+        }
+
+        let mut datagram = link_message(RTM_NEWLINK, "wlan0");
+        datagram.extend(link_message(20, "ignored"));
+        datagram.extend(link_message(RTM_DELLINK, "wlp3s0"));
+        assert_eq!(
+            link_changes(&datagram),
+            vec![("wlan0".to_string(), false), ("wlp3s0".to_string(), true)]
+        );
+        assert!(link_changes(&datagram[..10]).is_empty());
+
+        let strength = HashMap::from([("Strength".to_string(), OwnedValue::from(70u8))]);
+        let ap = "/org/freedesktop/NetworkManager/AccessPoint/3";
+        assert!(nm_change_matters(
+            ap,
+            NM_ACCESS_POINT_IFACE,
+            &strength,
+            Some(ap)
+        ));
+        assert!(!nm_change_matters(
+            ap,
+            NM_ACCESS_POINT_IFACE,
+            &strength,
+            None
+        ));
+        assert!(!nm_change_matters(ap, NM_DEVICE_IFACE, &strength, Some(ap)));
+    }
 }
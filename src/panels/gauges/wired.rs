@@ -0,0 +1,343 @@
+// Wired network gauge: link state and negotiated speed of an Ethernet interface from sysfs, with
+// its addresses, gateway, and DNS servers in the info dialog.
+// Consumes Settings: grelier.gauge.wired.*.
+use crate::dialog::info::InfoDialog;
+use crate::icon::svg_asset;
+use crate::panels::gauges::gauge::{
+    Gauge, GaugeDisplay, GaugeInteractionModel, GaugeModel, GaugePointerInteraction, GaugeValue,
+    GaugeValueAttention,
+};
+use crate::panels::gauges::gauge_registry::GaugeSpec;
+use crate::panels::gauges::ip;
+use crate::settings::{self, SettingSpec};
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const SYS_NET: &str = "/sys/class/net";
+const PROC_NET_ROUTE: &str = "/proc/net/route";
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+/// `ARPHRD_ETHER` from `linux/if_arp.h`, as sysfs prints it in `type`.
+const ARPHRD_ETHER: &str = "1";
+
+settings::typed_settings! {
+    struct WiredSettings {
+        interface: String = ("grelier.gauge.wired.interface", ""),
+        poll_interval_secs: u64 = ("grelier.gauge.wired.poll_interval_secs", "5", 1..=3600),
+    }
+}
+
+/// Link state of one wired interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WiredLink {
+    iface: String,
+    carrier: bool,
+    /// Negotiated speed in Mb/s; unknown while the link is down.
+    speed_mbps: Option<u32>,
+    duplex: Option<String>,
+    mac: Option<String>,
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Physical Ethernet interfaces, sorted by name. Bridges, tunnels, and veth pairs have no
+/// backing device and are skipped, as are wireless interfaces, which also report Ethernet.
+pub fn wired_interfaces(sys_net: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(sys_net) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| {
+            let path = entry.path();
+            read_trimmed(&path.join("type")).as_deref() == Some(ARPHRD_ETHER)
+                && path.join("device").exists()
+                && !path.join("wireless").exists()
+                && !path.join("phy80211").exists()
+        })
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+fn read_link(sys_net: &Path, iface: &str) -> WiredLink {
+    let path = sys_net.join(iface);
+    // Reading `carrier` or `speed` fails while the interface is administratively down.
+    let carrier = read_trimmed(&path.join("carrier")).as_deref() == Some("1");
+    WiredLink {
+        iface: iface.to_string(),
+        carrier,
+        speed_mbps: read_trimmed(&path.join("speed"))
+            .and_then(|speed| speed.parse::<i64>().ok())
+            .filter(|speed| carrier && *speed > 0)
+            .and_then(|speed| u32::try_from(speed).ok()),
+        duplex: read_trimmed(&path.join("duplex")).filter(|duplex| carrier && duplex != "unknown"),
+        mac: read_trimmed(&path.join("address")),
+    }
+}
+
+/// The configured interface, or else the first wired one with a cable, or else the first one.
+fn pick_link(sys_net: &Path, configured: &str) -> Option<WiredLink> {
+    let configured = configured.trim();
+    if !configured.is_empty() {
+        return sys_net
+            .join(configured)
+            .exists()
+            .then(|| read_link(sys_net, configured));
+    }
+    let links: Vec<WiredLink> = wired_interfaces(sys_net)
+        .iter()
+        .map(|iface| read_link(sys_net, iface))
+        .collect();
+    links
+        .iter()
+        .find(|link| link.carrier)
+        .or_else(|| links.first())
+        .cloned()
+}
+
+/// Bar text for a speed, such as "100M", "1G", or "2.5G".
+fn speed_label(mbps: u32) -> String {
+    if mbps < 1000 {
+        format!("{mbps}M")
+    } else if mbps.is_multiple_of(1000) {
+        format!("{}G", mbps / 1000)
+    } else {
+        format!("{:.1}G", mbps as f32 / 1000.0)
+    }
+}
+
+/// Default gateway of `iface` from `/proc/net/route`.
+fn gateway(route: &str, iface: &str) -> Option<Ipv4Addr> {
+    route.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields[0] != iface || fields[1] != "00000000" {
+            return None;
+        }
+        // The kernel prints the address as a host-order number of network-order bytes.
+        let raw = u32::from_str_radix(fields[2], 16).ok()?;
+        (raw != 0).then(|| Ipv4Addr::from(raw.to_ne_bytes()))
+    })
+}
+
+fn nameservers(resolv_conf: &str) -> Vec<String> {
+    resolv_conf
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            (fields.next() == Some("nameserver"))
+                .then(|| fields.next())
+                .flatten()
+                .map(ToString::to_string)
+        })
+        .collect()
+}
+
+fn link_line(link: &WiredLink) -> String {
+    if !link.carrier {
+        return format!("{}: no cable", link.iface);
+    }
+    match (link.speed_mbps, link.duplex.as_deref()) {
+        (Some(speed), Some(duplex)) => {
+            format!("{}: connected, {speed} Mb/s {duplex} duplex", link.iface)
+        }
+        (Some(speed), None) => format!("{}: connected, {speed} Mb/s", link.iface),
+        _ => format!("{}: connected", link.iface),
+    }
+}
+
+fn info_lines(link: &WiredLink) -> Vec<String> {
+    let mut lines = vec![link_line(link)];
+    if let Some(mac) = &link.mac {
+        lines.push(format!("MAC: {mac}"));
+    }
+    lines.extend(
+        ip::read_addresses()
+            .into_iter()
+            .filter(|address| address.iface == link.iface)
+            .map(|address| {
+                let family = if address.ipv6 { "IPv6" } else { "IPv4" };
+                format!("{family}: {}", address.address)
+            }),
+    );
+    if let Some(gateway) = fs::read_to_string(PROC_NET_ROUTE)
+        .ok()
+        .and_then(|route| gateway(&route, &link.iface))
+    {
+        lines.push(format!("Gateway: {gateway}"));
+    }
+    let dns = nameservers(&fs::read_to_string(RESOLV_CONF).unwrap_or_default());
+    if !dns.is_empty() {
+        lines.push(format!("DNS: {}", dns.join(", ")));
+    }
+    lines
+}
+
+/// Gauge showing the state of a wired network link.
+struct WiredGauge {
+    settings: WiredSettings,
+    /// Scheduler deadline for the next run.
+    next_deadline: Instant,
+}
+
+impl Gauge for WiredGauge {
+    fn id(&self) -> &'static str {
+        "wired"
+    }
+
+    fn next_deadline(&self) -> Instant {
+        self.next_deadline
+    }
+
+    fn run_once(&mut self, now: Instant) -> Option<GaugeModel> {
+        self.next_deadline = now + Duration::from_secs(self.settings.poll_interval_secs);
+
+        let (display, lines) = match pick_link(Path::new(SYS_NET), &self.settings.interface) {
+            Some(link) => {
+                let display = if link.carrier {
+                    GaugeDisplay::Value {
+                        value: GaugeValue::Text(
+                            link.speed_mbps
+                                .map(speed_label)
+                                .unwrap_or_else(|| "up".to_string()),
+                        ),
+                        attention: GaugeValueAttention::Nominal,
+                    }
+                } else {
+                    GaugeDisplay::Value {
+                        value: GaugeValue::Text("off".to_string()),
+                        attention: GaugeValueAttention::Warning,
+                    }
+                };
+                (display, info_lines(&link))
+            }
+            None => (
+                GaugeDisplay::Error,
+                vec![match self.settings.interface.trim() {
+                    "" => "No wired interface".to_string(),
+                    iface => format!("No interface named {iface}"),
+                }],
+            ),
+        };
+
+        Some(GaugeModel {
+            id: "wired",
+            icon: svg_asset("ethernet.svg"),
+            display,
+            interactions: GaugeInteractionModel {
+                left_click: GaugePointerInteraction {
+                    info: Some(InfoDialog {
+                        title: "Wired".to_string(),
+                        lines,
+                        graph: None,
+                    }),
+                    ..GaugePointerInteraction::default()
+                },
+                ..GaugeInteractionModel::default()
+            },
+        })
+    }
+}
+
+pub fn create_gauge(now: Instant) -> Box<dyn Gauge> {
+    Box::new(WiredGauge {
+        settings: WiredSettings::load(),
+        next_deadline: now,
+    })
+}
+
+pub fn settings() -> &'static [SettingSpec] {
+    WiredSettings::SPECS
+}
+
+inventory::submit! {
+    GaugeSpec {
+        id: "wired",
+        description: "Wired network gauge showing Ethernet link state and speed.",
+        default_enabled: false,
+        settings,
+        create: create_gauge,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn write_iface(sys_net: &Path, iface: &str, files: &[(&str, &str)], device: bool) {
+        let dir = sys_net.join(iface);
+        fs::create_dir_all(&dir).expect("create iface dir");
+        for (name, value) in files {
+            fs::write(dir.join(name), format!("{value}\n")).expect("write iface file");
+        }
+        if device {
+            fs::create_dir_all(dir.join("device")).expect("create device dir");
+        }
+    }
+
+    #[test]
+    fn picks_physical_links_and_reads_routing() {
+        let sys_net: PathBuf = std::env::temp_dir().join("grelier_wired_test_sys_net");
+        let _ = fs::remove_dir_all(&sys_net);
+        write_iface(
+            &sys_net,
+            "docker0",
+            &[("type", "1"), ("carrier", "1")],
+            false,
+        );
+        write_iface(
+            &sys_net,
+            "enp0s25",
+            &[("type", "1"), ("carrier", "0")],
+            true,
+        );
+        write_iface(
+            &sys_net,
+            "enp3s0",
+            &[
+                ("type", "1"),
+                ("carrier", "1"),
+                ("speed", "2500"),
+                ("duplex", "full"),
+            ],
+            true,
+        );
+        write_iface(&sys_net, "wlan0", &[("type", "1"), ("carrier", "1")], true);
+        fs::create_dir_all(sys_net.join("wlan0/wireless")).expect("create wireless dir");
+
+        assert_eq!(wired_interfaces(&sys_net), vec!["enp0s25", "enp3s0"]);
+        let link = pick_link(&sys_net, "").expect("link");
+        assert_eq!(link.iface, "enp3s0");
+        assert_eq!(link.speed_mbps.map(speed_label).as_deref(), Some("2.5G"));
+        assert_eq!(link_line(&link), "enp3s0: connected, 2500 Mb/s full duplex");
+        let unplugged = pick_link(&sys_net, "enp0s25").expect("configured link");
+        assert_eq!(link_line(&unplugged), "enp0s25: no cable");
+        assert_eq!(pick_link(&sys_net, "eth9"), None);
+        let _ = fs::remove_dir_all(&sys_net);
+
+        assert_eq!(speed_label(100), "100M");
+        assert_eq!(speed_label(1000), "1G");
+        let route = "Iface\tDestination\tGateway \tFlags\n\
+                     enp3s0\t00000000\t0101A8C0\t0003\n\
+                     enp3s0\t0001A8C0\t00000000\t0001\n";
+        if cfg!(target_endian = "little") {
+            assert_eq!(
+                gateway(route, "enp3s0"),
+                Some(Ipv4Addr::new(192, 168, 1, 1))
+            );
+        }
+        assert_eq!(gateway(route, "wlan0"), None);
+        assert_eq!(
+            nameservers("# generated\nnameserver 1.1.1.1\nsearch lan\nnameserver fe80::1%enp3s0\n"),
+            vec!["1.1.1.1", "fe80::1%enp3s0"]
+        );
+    }
+}
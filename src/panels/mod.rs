@@ -1,5 +1,8 @@
+pub mod focused_window_panel;
 pub mod gauge_panel;
 pub mod gauges;
+pub mod overview_panel;
 pub mod panel_registry;
+pub mod taskbar_panel;
 pub mod top_apps_panel;
 pub mod ws_panel;
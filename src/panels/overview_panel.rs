@@ -0,0 +1,50 @@
+// Overview panel: a single button that opens the full-screen workspace overview.
+use crate::bar::{HoverTarget, Message, Panel, hoverable};
+use crate::panels::panel_registry::{PanelSpec, PanelViewContext};
+use crate::settings;
+use iced::alignment;
+use iced::widget::{Svg, container, mouse_area, svg};
+use iced::{Color, Length, Theme, mouse};
+
+pub fn view<'a>(context: PanelViewContext<'a>) -> Panel<'a> {
+    let state = context.state;
+    let icon_size = settings::settings().get_parsed_or(crate::overview::BUTTON_SIZE_KEY, 20.0_f32);
+    let icon = Svg::new(crate::icon::svg_asset("overview.svg"))
+        .width(Length::Fixed(icon_size))
+        .height(Length::Fixed(icon_size))
+        .style(|theme: &Theme, _status| svg::Style {
+            color: Some(Color {
+                a: 0.8,
+                ..theme.palette().text
+            }),
+        });
+    let target = HoverTarget::Overview;
+    let hovered = state.is_hovered(&target);
+    let button = mouse_area(hoverable(icon, target, hovered))
+        .on_press(Message::OverviewToggled)
+        .interaction(mouse::Interaction::Pointer);
+
+    Panel::new(
+        container(button)
+            .padding([2, 2])
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Center),
+    )
+}
+
+fn panel_settings() -> &'static [crate::settings::SettingSpec] {
+    crate::settings::NO_SETTINGS
+}
+
+inventory::submit! {
+    PanelSpec {
+        id: "overview",
+        description: "Button that opens a full-screen overview of all workspaces and their windows.",
+        default_enabled: false,
+        settings: panel_settings,
+        view,
+        subscription: None,
+        bootstrap: None,
+        validate: None,
+    }
+}
@@ -5,7 +5,7 @@ use std::collections::HashSet;
 use std::sync::OnceLock;
 
 pub type PanelValidator = fn(&Settings) -> Result<(), String>;
-pub type PanelView = for<'a> fn(&'a BarState) -> Panel<'a>;
+pub type PanelView = for<'a> fn(PanelViewContext<'a>) -> Panel<'a>;
 pub type PanelSubscriptionFactory =
     for<'a> fn(PanelSubscriptionContext<'a>) -> Option<Subscription<Message>>;
 pub type PanelBootstrapFactory = for<'a> fn(PanelBootstrapContext<'a>, &mut PanelBootstrapConfig);
@@ -20,6 +20,15 @@ pub enum PanelActivation {
 pub struct PanelSubscriptionContext<'a> {
     pub activation: PanelActivation,
     pub gauges: &'a [String],
+    /// Restart counter; subscriptions keyed on it are recreated when it changes.
+    pub generation: u64,
+}
+
+#[derive(Clone, Copy)]
+pub struct PanelViewContext<'a> {
+    pub state: &'a BarState,
+    /// Device pixel scale of the bar window being drawn, for snapping borders and paddings.
+    pub scale: f32,
 }
 
 #[derive(Clone, Copy)]
@@ -124,7 +133,11 @@ pub fn default_panels() -> &'static str {
     })
 }
 
-pub fn subscriptions_for_setting(setting: &str, gauges: &[String]) -> Vec<Subscription<Message>> {
+pub fn subscriptions_for_setting(
+    setting: &str,
+    gauges: &[String],
+    generation: u64,
+) -> Vec<Subscription<Message>> {
     let active: HashSet<&'static str> = panel_order_from_setting(setting).into_iter().collect();
     let mut subs = Vec::new();
     let mut panels: Vec<&'static PanelSpec> = all().collect();
@@ -138,7 +151,11 @@ pub fn subscriptions_for_setting(setting: &str, gauges: &[String]) -> Vec<Subscr
         } else {
             PanelActivation::Inactive
         };
-        if let Some(sub) = factory(PanelSubscriptionContext { activation, gauges }) {
+        if let Some(sub) = factory(PanelSubscriptionContext {
+            activation,
+            gauges,
+            generation,
+        }) {
             subs.push(sub);
         }
     }
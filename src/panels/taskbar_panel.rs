@@ -0,0 +1,174 @@
+// Taskbar panel: one icon per window on the focused workspace.
+// Left-click focuses, middle-click closes, right-click opens a move-to-workspace menu.
+use crate::bar::{HoverTarget, Message, Panel, app_icon_view, hoverable};
+use crate::compositor::{WorkspaceApp, WorkspaceInfo};
+use crate::panels::gauges::gauge::{GaugeMenu, GaugeMenuItem};
+use crate::panels::panel_registry::{
+    PanelActivation, PanelSpec, PanelSubscriptionContext, PanelViewContext,
+};
+use crate::settings;
+use elbey_cache::FALLBACK_ICON_HANDLE;
+use iced::alignment;
+use iced::widget::{Column, container, mouse_area};
+use iced::{Length, mouse};
+use std::collections::HashMap;
+
+/// Dialog owner id used for the move-to-workspace menu.
+pub const MENU_ID: &str = "taskbar";
+
+/// Windows on the focused workspace, in the order the compositor reports them.
+pub fn focused_workspace_apps<'a>(
+    workspaces: &[WorkspaceInfo],
+    workspace_apps: &'a HashMap<String, Vec<WorkspaceApp>>,
+) -> &'a [WorkspaceApp] {
+    workspaces
+        .iter()
+        .find(|ws| ws.focused)
+        .and_then(|ws| workspace_apps.get(&ws.name))
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+/// Menu of the other workspaces the window can be sent to.
+pub fn move_menu(workspaces: &[WorkspaceInfo], app_id: &str) -> GaugeMenu {
+    GaugeMenu {
+        title: format!("Move {app_id} to"),
+        items: workspaces
+            .iter()
+            .filter(|ws| !ws.focused)
+            .map(|ws| GaugeMenuItem {
+                id: ws.name.clone(),
+                label: ws.label.clone(),
+                selected: false,
+                exec: None,
+            })
+            .collect(),
+        on_select: None,
+        slider: None,
+    }
+}
+
+pub fn view<'a>(context: PanelViewContext<'a>) -> Panel<'a> {
+    let state = context.state;
+    let settings = settings::settings();
+    let icon_size = settings.get_parsed_or("grelier.taskbar.icon_size", 20.0_f32);
+    let spacing = settings.get_parsed_or("grelier.taskbar.spacing", 6u32);
+
+    let apps = focused_workspace_apps(&state.workspaces, &state.workspace_apps);
+    let column = apps.iter().fold(
+        Column::new()
+            .spacing(spacing)
+            .align_x(alignment::Horizontal::Center)
+            .width(Length::Fill),
+        |col, app| {
+            let handle = state
+                .app_icons
+                .icon_for(&app.app_id)
+                .unwrap_or(&FALLBACK_ICON_HANDLE);
+            let target = HoverTarget::WorkspaceApp(app.con_id);
+            let hovered = state.is_hovered(&target);
+            let icon = mouse_area(hoverable(app_icon_view(handle, icon_size), target, hovered))
+                .on_press(Message::WorkspaceAppClicked {
+                    con_id: app.con_id,
+                    app_id: app.app_id.clone(),
+                })
+                .on_middle_press(Message::TaskbarCloseRequested(app.con_id))
+                .on_right_press(Message::TaskbarMenuRequested {
+                    con_id: app.con_id,
+                    app_id: app.app_id.clone(),
+                })
+                .interaction(mouse::Interaction::Pointer);
+            col.push(icon)
+        },
+    );
+
+    Panel::new(
+        container(column)
+            .padding([2, 2])
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Center),
+    )
+}
+
+fn panel_settings() -> &'static [crate::settings::SettingSpec] {
+    crate::settings::NO_SETTINGS
+}
+
+fn panel_subscription(
+    context: PanelSubscriptionContext<'_>,
+) -> Option<iced::Subscription<Message>> {
+    // Shares the workspace panel's stream; identical subscriptions run once.
+    (context.activation == PanelActivation::Active)
+        .then(|| crate::compositor::workspace_subscription(context.generation))
+}
+
+inventory::submit! {
+    PanelSpec {
+        id: "taskbar",
+        description: "Taskbar panel with an icon for each window on the focused workspace.",
+        default_enabled: false,
+        settings: panel_settings,
+        view,
+        subscription: Some(panel_subscription),
+        bootstrap: None,
+        validate: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compositor::Rect;
+
+    fn workspace(name: &str, focused: bool) -> WorkspaceInfo {
+        WorkspaceInfo {
+            num: name.parse().unwrap_or(0),
+            name: name.to_string(),
+            label: name.to_string(),
+            focused,
+            urgent: false,
+            rect: Rect { y: 0, height: 0 },
+        }
+    }
+
+    #[test]
+    fn lists_focused_workspace_windows_and_move_targets() {
+        let workspaces = vec![
+            workspace("1", false),
+            workspace("2", true),
+            workspace("3", false),
+        ];
+        let apps = HashMap::from([
+            (
+                "1".to_string(),
+                vec![WorkspaceApp {
+                    app_id: "foot".to_string(),
+                    con_id: 4,
+                }],
+            ),
+            (
+                "2".to_string(),
+                vec![
+                    WorkspaceApp {
+                        app_id: "firefox".to_string(),
+                        con_id: 7,
+                    },
+                    WorkspaceApp {
+                        app_id: "foot".to_string(),
+                        con_id: 9,
+                    },
+                ],
+            ),
+        ]);
+        let shown: Vec<i64> = focused_workspace_apps(&workspaces, &apps)
+            .iter()
+            .map(|app| app.con_id)
+            .collect();
+        assert_eq!(shown, vec![7, 9]);
+
+        let menu = move_menu(&workspaces, "firefox");
+        let targets: Vec<&str> = menu.items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(targets, vec!["1", "3"]);
+        assert!(focused_workspace_apps(&[], &apps).is_empty());
+    }
+}
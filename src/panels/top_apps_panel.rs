@@ -1,14 +1,96 @@
-use crate::bar::{BarState, Message, Panel, app_icon_view};
+// Top launched apps panel, with a busy marker on apps that are still starting.
+// Consumes Settings: grelier.app.top_apps.*.
+use crate::apps::{self, MAIN_CATEGORIES, TOP_APPS_CATEGORIES_KEY};
+use crate::bar::{self, BarState, HoverTarget, Message, Panel, app_icon_view, hoverable};
+use crate::compositor::WorkspaceApp;
+use crate::panels::gauges::gauge::{GaugeMenu, GaugeMenuItem};
 use crate::panels::panel_registry::{
-    PanelActivation, PanelBootstrapConfig, PanelBootstrapContext, PanelSpec,
+    PanelActivation, PanelBootstrapConfig, PanelBootstrapContext, PanelSpec, PanelViewContext,
 };
 use crate::settings;
-use elbey_cache::{FALLBACK_ICON_HANDLE, IconHandle};
+use elbey_cache::{AppDescriptor, Cache, FALLBACK_ICON_HANDLE, IconHandle};
 use iced::alignment;
-use iced::widget::{Column, container, mouse_area};
-use iced::{Element, Length, mouse};
+use iced::border;
+use iced::widget::{Column, Space, Stack, container, mouse_area};
+use iced::{Border, Element, Length, Task, Theme, mouse};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
+const LAUNCH_TIMEOUT_KEY: &str = "grelier.app.top_apps.launch_timeout_ms";
+const BUSY_DOT_SIZE: f32 = 5.0;
+
+/// An app launched from the panel that has not opened a window yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingLaunch {
+    /// Windows the app already had when it was launched.
+    pub windows: usize,
+    pub started: Instant,
+}
+
+/// Open windows whose app id matches the desktop app id, ignoring case and a `.desktop` suffix.
+fn window_count(apps: &HashMap<String, Vec<WorkspaceApp>>, app_id: &str) -> usize {
+    let app_id = app_id.strip_suffix(".desktop").unwrap_or(app_id);
+    apps.values()
+        .flatten()
+        .filter(|app| app.app_id.eq_ignore_ascii_case(app_id))
+        .count()
+}
+
+/// Mark `app_id` busy until one of its windows appears or the launch timeout runs out.
+pub fn begin_launch(state: &mut BarState, app_id: &str) -> Task<Message> {
+    let started = Instant::now();
+    let windows = window_count(&state.workspace_apps, app_id);
+    state
+        .launching
+        .insert(app_id.to_string(), PendingLaunch { windows, started });
+    let timeout = settings::settings().get_parsed_or(LAUNCH_TIMEOUT_KEY, 10_000u64);
+    bar::delayed_task(
+        Duration::from_millis(timeout),
+        Message::TopAppLaunchTimeout {
+            app_id: app_id.to_string(),
+            started,
+        },
+    )
+}
+
+/// Clear the busy marker of every launch that gained a window.
+pub fn settle_launches(state: &mut BarState) {
+    let apps = &state.workspace_apps;
+    state
+        .launching
+        .retain(|app_id, launch| window_count(apps, app_id) <= launch.windows);
+}
+
+/// Clear the busy marker when the timeout for that launch expires; later launches are kept.
+pub fn expire_launch(state: &mut BarState, app_id: &str, started: Instant) {
+    if state
+        .launching
+        .get(app_id)
+        .is_some_and(|launch| launch.started == started)
+    {
+        state.launching.remove(app_id);
+    }
+}
+
+fn busy_marker<'a>() -> Element<'a, Message> {
+    container(
+        container(Space::new().width(BUSY_DOT_SIZE).height(BUSY_DOT_SIZE)).style(
+            |theme: &Theme| container::Style {
+                background: Some(theme.palette().primary.into()),
+                border: Border::default().rounded(border::Radius::new(BUSY_DOT_SIZE / 2.0)),
+                ..container::Style::default()
+            },
+        ),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .align_x(alignment::Horizontal::Right)
+    .align_y(alignment::Vertical::Bottom)
+    .into()
+}
+
+pub fn view<'a>(context: PanelViewContext<'a>) -> Panel<'a> {
+    let state = context.state;
     let settings = settings::settings();
     let top_apps_icon_size = settings.get_parsed_or("grelier.app.top_apps.icon_size", 20.0);
     let workspace_icon_spacing = settings
@@ -33,22 +115,112 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                     .unwrap_or(&FALLBACK_ICON_HANDLE),
                 handle => handle,
             };
-            let icon = mouse_area(app_icon_view(handle, top_apps_icon_size))
+            let target = HoverTarget::TopApp(app_id.clone());
+            let hovered = state.is_hovered(&target);
+            let mut icon = Stack::new().push(app_icon_view(handle, top_apps_icon_size));
+            if state.launching.contains_key(&app_id) {
+                icon = icon.push(busy_marker());
+            }
+            let icon = mouse_area(hoverable(icon, target, hovered))
                 .on_press(Message::TopAppClicked { app_id })
                 .interaction(mouse::Interaction::Pointer);
             col.push(icon)
         },
     );
 
-    let top_apps_section: Element<'_, Message> = container(top_apps)
-        .padding([workspace_icon_padding_y, workspace_icon_padding_x])
-        .width(Length::Fill)
-        .align_x(alignment::Horizontal::Center)
-        .into();
+    let top_apps_section: Element<'_, Message> = mouse_area(
+        container(top_apps)
+            .padding([workspace_icon_padding_y, workspace_icon_padding_x])
+            .width(Length::Fill)
+            .align_x(alignment::Horizontal::Center),
+    )
+    .on_right_press(Message::TopAppsMenuRequested)
+    .into();
 
     Panel::new(top_apps_section)
 }
 
+/// Dialog owner id used for the category filter menu.
+pub const MENU_ID: &str = "top_apps";
+const MENU_ALL: &str = "all";
+
+fn category_filter() -> Vec<String> {
+    apps::parse_categories(&settings::settings().get_or(TOP_APPS_CATEGORIES_KEY, ""))
+}
+
+fn top_apps_count() -> usize {
+    settings::settings().get_parsed_or("grelier.app.top_apps.count", 6usize)
+}
+
+/// How many ranked apps to request from the cache for the configured count and filter.
+pub fn pool_size(count: usize) -> usize {
+    apps::top_apps_pool_size(count, &category_filter())
+}
+
+/// Replace the shown apps with `ranked`, applying the configured category filter.
+pub fn set_top_apps(state: &mut BarState, ranked: Vec<AppDescriptor>) {
+    let filter = category_filter();
+    if !filter.is_empty() && state.app_categories.is_empty() {
+        state.app_categories = apps::load_app_categories();
+    }
+    state.top_apps =
+        apps::filter_top_apps(ranked, &filter, &state.app_categories, top_apps_count());
+}
+
+/// Re-read the ranked apps from the launch cache and apply the filter.
+pub fn reload_top_apps(state: &mut BarState, cache: &mut Cache) {
+    let ranked = cache
+        .top_apps(pool_size(top_apps_count()))
+        .unwrap_or_default();
+    set_top_apps(state, ranked);
+}
+
+/// Menu listing the main categories of installed apps, plus an entry to clear the filter.
+pub fn category_menu(state: &mut BarState) -> GaugeMenu {
+    if state.app_categories.is_empty() {
+        state.app_categories = apps::load_app_categories();
+    }
+    let filter = category_filter();
+    let is_selected = |category: &str| filter.iter().any(|f| f.eq_ignore_ascii_case(category));
+    let mut items = vec![GaugeMenuItem {
+        id: MENU_ALL.to_string(),
+        label: "All apps".to_string(),
+        selected: filter.is_empty(),
+        exec: None,
+    }];
+    items.extend(
+        MAIN_CATEGORIES
+            .iter()
+            .filter(|category| {
+                is_selected(category)
+                    || state
+                        .app_categories
+                        .values()
+                        .any(|categories| categories.iter().any(|c| c == *category))
+            })
+            .map(|category| GaugeMenuItem {
+                id: category.to_string(),
+                label: category.to_string(),
+                selected: is_selected(category),
+                exec: None,
+            }),
+    );
+    GaugeMenu {
+        title: "App Category".to_string(),
+        items,
+        on_select: None,
+        slider: None,
+    }
+}
+
+/// Persist the category chosen from the menu and refresh the shown apps.
+pub fn select_category(state: &mut BarState, item_id: &str) {
+    let value = if item_id == MENU_ALL { "" } else { item_id };
+    settings::settings().update(TOP_APPS_CATEGORIES_KEY, value);
+    let mut cache = Cache::new(apps::load_desktop_apps);
+    reload_top_apps(state, &mut cache);
+}
+
 fn panel_settings() -> &'static [crate::settings::SettingSpec] {
     crate::settings::NO_SETTINGS
 }
@@ -73,3 +245,23 @@ inventory::submit! {
         validate: None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_windows_matching_desktop_app_id() {
+        let app = |app_id: &str, con_id| WorkspaceApp {
+            app_id: app_id.to_string(),
+            con_id,
+        };
+        let apps = HashMap::from([
+            ("1".to_string(), vec![app("firefox", 1), app("foot", 2)]),
+            ("2".to_string(), vec![app("Firefox", 3)]),
+        ]);
+        assert_eq!(window_count(&apps, "firefox.desktop"), 2);
+        assert_eq!(window_count(&apps, "foot"), 1);
+        assert_eq!(window_count(&apps, "org.gnome.Nautilus"), 0);
+    }
+}
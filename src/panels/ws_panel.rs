@@ -1,20 +1,281 @@
-use crate::bar::{BarState, Message, Panel, app_icon_view, lerp_color};
+use crate::bar::{
+    BarState, HoverTarget, Message, Panel, WorkspaceDrag, app_icon_view, bar_easing, hoverable,
+    lerp_color,
+};
+use crate::compositor::{WorkspaceApp, WorkspaceInfo};
+use crate::ipc::{self, EventTopic};
 use crate::panels::panel_registry::{
     PanelActivation, PanelBootstrapConfig, PanelBootstrapContext, PanelSpec,
-    PanelSubscriptionContext,
+    PanelSubscriptionContext, PanelViewContext,
 };
 use crate::settings;
-use crate::sway_workspace::WorkspaceInfo;
+use crate::style::StyleSheet;
 use elbey_cache::FALLBACK_ICON_HANDLE;
 use iced::alignment;
 use iced::border;
 use iced::font::Weight;
 use iced::gradient::Linear;
 use iced::widget::text;
-use iced::widget::{Column, Text, button, container, mouse_area};
-use iced::{Border, Degrees, Element, Font, Gradient, Length, Theme, mouse};
+use iced::widget::{Column, Space, Text, container, mouse_area};
+use iced::{Border, Degrees, Element, Font, Gradient, Length, Theme, keyboard, mouse};
 use iced_anim::animation_builder::AnimationBuilder;
 use iced_anim::transition::Easing;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const STYLE_KEY: &str = "grelier.ws.style";
+const DROP_INDICATOR_HEIGHT: f32 = 2.0;
+pub const SCROLL_WRAP_KEY: &str = "grelier.ws.scroll_wrap";
+pub const AUTO_NAME_KEY: &str = "grelier.ws.auto_name";
+pub const AUTO_NAME_DELAY_KEY: &str = "grelier.ws.auto_name.delay_ms";
+pub const AUTO_NAME_BLOCKLIST_KEY: &str = "grelier.ws.auto_name.blocklist";
+pub const DOUBLE_CLICK_MS_KEY: &str = "grelier.ws.double_click_ms";
+const HISTORY_MODIFIER_KEY: &str = "grelier.ws.history_modifier";
+/// Most workspaces remembered in the focus history.
+const HISTORY_LIMIT: usize = 20;
+const ON_MIDDLE_CLICK_KEY: &str = "grelier.ws.on_middle_click";
+const ON_RIGHT_CLICK_KEY: &str = "grelier.ws.on_right_click";
+const ON_DOUBLE_CLICK_KEY: &str = "grelier.ws.on_double_click";
+
+/// Workspace button inputs that can be mapped to a [`WorkspaceAction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceInput {
+    /// Middle-button click.
+    Middle,
+    /// Right-button click.
+    Right,
+    /// Two left clicks on the same workspace within `grelier.ws.double_click_ms`.
+    Double,
+}
+
+impl WorkspaceInput {
+    fn key(self) -> &'static str {
+        match self {
+            Self::Middle => ON_MIDDLE_CLICK_KEY,
+            Self::Right => ON_RIGHT_CLICK_KEY,
+            Self::Double => ON_DOUBLE_CLICK_KEY,
+        }
+    }
+}
+
+/// What a mapped workspace input does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceAction {
+    /// Focus the workspace, like a left click.
+    Focus,
+    /// Move the focused window to the workspace.
+    MoveFocusedHere,
+    /// Prompt for a new workspace name.
+    Rename,
+    /// Ignore the input.
+    None,
+}
+
+impl WorkspaceAction {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "focus" => Some(Self::Focus),
+            "move-focused-here" => Some(Self::MoveFocusedHere),
+            "rename" => Some(Self::Rename),
+            "none" | "" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+/// Configured action for `input`; unset or invalid values do nothing.
+pub fn action_for(input: WorkspaceInput) -> WorkspaceAction {
+    settings::settings()
+        .get(input.key())
+        .and_then(|value| WorkspaceAction::parse(&value))
+        .unwrap_or(WorkspaceAction::None)
+}
+
+/// Modifier that turns workspace scrolling into focus-history navigation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryModifier {
+    Alt,
+    Ctrl,
+    Shift,
+    Super,
+    /// History navigation is off.
+    None,
+}
+
+impl HistoryModifier {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "alt" => Some(Self::Alt),
+            "ctrl" => Some(Self::Ctrl),
+            "shift" => Some(Self::Shift),
+            "super" => Some(Self::Super),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    pub fn held(self, modifiers: keyboard::Modifiers) -> bool {
+        match self {
+            Self::Alt => modifiers.alt(),
+            Self::Ctrl => modifiers.control(),
+            Self::Shift => modifiers.shift(),
+            Self::Super => modifiers.logo(),
+            Self::None => false,
+        }
+    }
+}
+
+/// Configured history modifier; invalid values turn history navigation off.
+pub fn history_modifier() -> HistoryModifier {
+    HistoryModifier::parse(&settings::settings().get_or(HISTORY_MODIFIER_KEY, "alt"))
+        .unwrap_or(HistoryModifier::None)
+}
+
+/// Move `focused` to the front of the focus history.
+fn record_history(history: &mut Vec<String>, focused: &str) {
+    history.retain(|name| name != focused);
+    history.insert(0, focused.to_string());
+    history.truncate(HISTORY_LIMIT);
+}
+
+/// Next history position to preview from `current`: `older` walks back in time.
+/// Position 0 is the focused workspace.
+pub fn history_step(len: usize, current: Option<usize>, older: bool) -> Option<usize> {
+    if len < 2 {
+        return None;
+    }
+    let current = current.unwrap_or(0);
+    Some(if older {
+        (current + 1).min(len - 1)
+    } else {
+        current.saturating_sub(1)
+    })
+}
+
+/// End a history preview, returning the workspace to focus when it differs from the current one.
+pub fn commit_history_preview(state: &mut BarState) -> Option<String> {
+    let position = state.workspace_history_preview.take()?;
+    (position > 0)
+        .then(|| state.workspace_history.get(position).cloned())
+        .flatten()
+}
+
+/// Whether a release on `name` at `now` completes a double click after `last`.
+pub fn is_double_click(
+    last: Option<&(String, Instant)>,
+    name: &str,
+    now: Instant,
+    window: Duration,
+) -> bool {
+    last.is_some_and(|(last_name, at)| last_name == name && now.duration_since(*at) <= window)
+}
+
+/// How each workspace is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WorkspaceStyle {
+    /// Numbered buttons, optionally followed by the workspace's app icons.
+    Numbers,
+    /// Small dots; the focused workspace is stretched into a pill.
+    Dots,
+}
+
+impl WorkspaceStyle {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "numbers" => Some(Self::Numbers),
+            "dots" => Some(Self::Dots),
+            _ => None,
+        }
+    }
+}
+
+/// Dot width and height; focus stretches the dot along the bar.
+fn dot_dimensions(size: f32, focus: f32) -> (f32, f32) {
+    (size, size * (1.0 + 1.5 * focus))
+}
+
+fn dot_view<'a>(
+    name: String,
+    focus: f32,
+    urgent: f32,
+    is_previous: bool,
+    hovered: bool,
+    size: f32,
+) -> Element<'a, Message> {
+    let (width, height) = dot_dimensions(size, focus);
+    let style = WorkspaceColors::from_style(&crate::style::current());
+    let dot = container(Space::new().width(width).height(height)).style(move |theme: &Theme| {
+        let ((_, color), _) = styled_workspace_colors(focus, urgent, is_previous, theme, style);
+        container::Style {
+            background: Some(color.into()),
+            border: Border::default().rounded(border::Radius::new(size / 2.0)),
+            ..container::Style::default()
+        }
+    });
+
+    let target = container(dot)
+        .width(Length::Fill)
+        .align_x(alignment::Horizontal::Center);
+    workspace_target(target, name, hovered)
+}
+
+/// Make a workspace clickable and draggable: press starts a drag, release clicks or drops.
+fn workspace_target<'a>(
+    content: impl Into<Element<'a, Message>>,
+    name: String,
+    hovered: bool,
+) -> Element<'a, Message> {
+    mouse_area(hoverable(
+        content,
+        HoverTarget::Workspace(name.clone()),
+        hovered,
+    ))
+    .on_press(Message::WorkspacePressed(name.clone()))
+    .on_release(Message::WorkspaceReleased(name.clone()))
+    .on_middle_press(Message::WorkspaceInput {
+        name: name.clone(),
+        input: WorkspaceInput::Middle,
+    })
+    .on_right_press(Message::WorkspaceInput {
+        name,
+        input: WorkspaceInput::Right,
+    })
+    .into()
+}
+
+/// Where the drop indicator goes relative to a workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DropSide {
+    Before,
+    After,
+}
+
+/// Indicator placement for `name` while `drag` is over it: dragging up drops before the
+/// target, dragging down drops after it.
+fn drop_side(workspaces: &[WorkspaceInfo], drag: &WorkspaceDrag, name: &str) -> Option<DropSide> {
+    if drag.target.as_deref() != Some(name) {
+        return None;
+    }
+    let position = |name: &str| workspaces.iter().position(|ws| ws.name == name);
+    let (source, target) = (position(&drag.source)?, position(name)?);
+    Some(if target < source {
+        DropSide::Before
+    } else {
+        DropSide::After
+    })
+}
+
+fn drop_indicator<'a>() -> Element<'a, Message> {
+    container(Space::new())
+        .width(Length::Fill)
+        .height(Length::Fixed(DROP_INDICATOR_HEIGHT))
+        .style(|theme: &Theme| container::Style {
+            background: Some(theme.palette().primary.into()),
+            ..container::Style::default()
+        })
+        .into()
+}
 
 fn workspace_gradient(start: iced::Color, end: iced::Color) -> Gradient {
     Gradient::Linear(
@@ -24,6 +285,74 @@ fn workspace_gradient(start: iced::Color, end: iced::Color) -> Gradient {
     )
 }
 
+/// Workspace colors from the style file, copied out so view closures stay `Copy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct WorkspaceColors {
+    background: Option<iced::Color>,
+    text: Option<iced::Color>,
+    focused: Option<iced::Color>,
+    focused_text: Option<iced::Color>,
+}
+
+impl WorkspaceColors {
+    fn from_style(style: &StyleSheet) -> Self {
+        Self {
+            background: style.color("workspace.background"),
+            text: style.color("workspace.text"),
+            focused: style.color("workspace.focused"),
+            focused_text: style.color("workspace.focused_text"),
+        }
+    }
+}
+
+/// Gradient and text colors for a workspace button, with style file overrides for
+/// unfocused and focused buttons; urgent and previous workspaces keep the theme colors.
+fn styled_workspace_colors(
+    focus: f32,
+    urgent: f32,
+    is_previous: bool,
+    theme: &Theme,
+    style: WorkspaceColors,
+) -> ((iced::Color, iced::Color), iced::Color) {
+    let palette = theme.extended_palette();
+    let themed_text = |focus: f32| {
+        if is_previous {
+            palette.background.base.color
+        } else {
+            lerp_color(
+                theme.palette().text,
+                palette.background.base.color,
+                focus.max(urgent),
+            )
+        }
+    };
+    let themed = (
+        workspace_gradient_colors(focus, urgent, is_previous, palette),
+        themed_text(focus),
+    );
+    if urgent > 0.0 || is_previous || style == WorkspaceColors::default() {
+        return themed;
+    }
+    let pair = |color: iced::Color| (color, color);
+    let idle = style
+        .background
+        .map(pair)
+        .unwrap_or_else(|| workspace_gradient_colors(0.0, 0.0, false, palette));
+    let focused = style
+        .focused
+        .map(pair)
+        .unwrap_or_else(|| workspace_gradient_colors(1.0, 0.0, false, palette));
+    let idle_text = style.text.unwrap_or_else(|| themed_text(0.0));
+    let focused_text = style.focused_text.unwrap_or_else(|| themed_text(1.0));
+    (
+        (
+            lerp_color(idle.0, focused.0, focus),
+            lerp_color(idle.1, focused.1, focus),
+        ),
+        lerp_color(idle_text, focused_text, focus),
+    )
+}
+
 fn workspace_gradient_colors(
     focus: f32,
     urgent: f32,
@@ -86,6 +415,87 @@ fn workspace_levels(ws: &WorkspaceInfo) -> (f32, f32) {
     )
 }
 
+/// Short label for an app id: the last reverse-DNS segment, lowercased.
+fn app_label(app_id: &str) -> String {
+    app_id
+        .rsplit('.')
+        .next()
+        .unwrap_or(app_id)
+        .trim()
+        .to_lowercase()
+}
+
+/// Most common non-blocked app on a workspace; ties go to the app listed first.
+fn dominant_app<'a>(apps: &'a [WorkspaceApp], blocklist: &[String]) -> Option<&'a str> {
+    let allowed: Vec<&str> = apps
+        .iter()
+        .map(|app| app.app_id.as_str())
+        .filter(|app_id| {
+            !app_id.is_empty()
+                && !blocklist
+                    .iter()
+                    .any(|blocked| blocked.eq_ignore_ascii_case(app_id))
+        })
+        .collect();
+    allowed.iter().copied().max_by_key(|candidate| {
+        let count = allowed.iter().filter(|app_id| *app_id == candidate).count();
+        let first = allowed.iter().position(|app_id| app_id == candidate);
+        (count, std::cmp::Reverse(first))
+    })
+}
+
+/// Renames giving each numbered workspace a `num:app` name after its dominant app, or a
+/// bare `num` when it has none. Renames that would collide with another workspace are skipped.
+pub fn auto_name_renames(
+    workspaces: &[WorkspaceInfo],
+    apps: &HashMap<String, Vec<WorkspaceApp>>,
+    blocklist: &[String],
+) -> Vec<(String, String)> {
+    workspaces
+        .iter()
+        .filter(|ws| ws.num >= 0)
+        .filter_map(|ws| {
+            let app = apps
+                .get(&ws.name)
+                .and_then(|apps| dominant_app(apps, blocklist));
+            let name = match app {
+                Some(app_id) => format!("{}:{}", ws.num, app_label(app_id)),
+                None => ws.num.to_string(),
+            };
+            let taken = workspaces.iter().any(|other| other.name == name);
+            (!taken).then(|| (ws.name.clone(), name))
+        })
+        .collect()
+}
+
+/// Workspace to focus when scrolling from the focused one, in panel order.
+pub fn scroll_target(workspaces: &[WorkspaceInfo], next: bool, wrap: bool) -> Option<&str> {
+    let count = workspaces.len();
+    let focused = workspaces.iter().position(|ws| ws.focused)?;
+    let target = match (next, wrap) {
+        (true, _) if focused + 1 < count => focused + 1,
+        (false, _) if focused > 0 => focused - 1,
+        (true, true) => 0,
+        (false, true) => count - 1,
+        (_, false) => return None,
+    };
+    (target != focused).then(|| workspaces[target].name.as_str())
+}
+
+fn scroll_message(delta: mouse::ScrollDelta) -> Message {
+    match delta {
+        mouse::ScrollDelta::Lines { y, .. } | mouse::ScrollDelta::Pixels { y, .. } => {
+            if y < 0.0 {
+                Message::WorkspaceScrolled { next: true }
+            } else if y > 0.0 {
+                Message::WorkspaceScrolled { next: false }
+            } else {
+                Message::Noop
+            }
+        }
+    }
+}
+
 pub fn update_workspace_focus(state: &mut BarState, workspaces: &[WorkspaceInfo]) {
     let workspace_count = workspaces.len();
 
@@ -99,6 +509,17 @@ pub fn update_workspace_focus(state: &mut BarState, workspaces: &[WorkspaceInfo]
         state.previous_workspace = None;
     }
 
+    // Forget workspaces that are gone; a preview that pointed at one ends.
+    state
+        .workspace_history
+        .retain(|name| workspaces.iter().any(|ws| ws.name == *name));
+    if state
+        .workspace_history_preview
+        .is_some_and(|position| position >= state.workspace_history.len())
+    {
+        state.workspace_history_preview = None;
+    }
+
     let focused_workspace = workspaces
         .iter()
         .find(|ws| ws.focused)
@@ -106,6 +527,10 @@ pub fn update_workspace_focus(state: &mut BarState, workspaces: &[WorkspaceInfo]
 
     match focused_workspace {
         Some(focused) if Some(focused.as_str()) != state.current_workspace.as_deref() => {
+            ipc::publish(
+                EventTopic::Workspace,
+                || json!({ "focused": focused, "previous": state.current_workspace }),
+            );
             if workspace_count > 1 {
                 if let Some(current) = state.current_workspace.take() {
                     state.previous_workspace = Some(current);
@@ -114,6 +539,7 @@ pub fn update_workspace_focus(state: &mut BarState, workspaces: &[WorkspaceInfo]
                 state.previous_workspace = None;
             }
 
+            record_history(&mut state.workspace_history, &focused);
             state.current_workspace = Some(focused);
         }
         Some(_) => {}
@@ -121,18 +547,31 @@ pub fn update_workspace_focus(state: &mut BarState, workspaces: &[WorkspaceInfo]
     }
 }
 
-pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
+pub fn view<'a>(context: PanelViewContext<'a>) -> Panel<'a> {
+    let state = context.state;
     let settings = settings::settings();
+    let style = crate::style::current();
     let workspace_padding_x = settings.get_parsed_or("grelier.app.workspace.padding_x", 4u16);
     let workspace_padding_y = settings.get_parsed_or("grelier.app.workspace.padding_y", 2u16);
     let workspace_spacing = settings.get_parsed_or("grelier.ws.spacing", 2u32);
-    let workspace_button_padding_x =
-        settings.get_parsed_or("grelier.app.workspace.button_padding_x", 4u16);
-    let workspace_button_padding_y =
-        settings.get_parsed_or("grelier.app.workspace.button_padding_y", 4u16);
-    let workspace_corner_radius = settings.get_parsed_or("grelier.ws.corner_radius", 5.0_f32);
+    let workspace_button_padding_x = style
+        .number("workspace.padding_x")
+        .map(|padding| padding as u16)
+        .unwrap_or_else(|| settings.get_parsed_or("grelier.app.workspace.button_padding_x", 4u16));
+    let workspace_button_padding_y = style
+        .number("workspace.padding_y")
+        .map(|padding| padding as u16)
+        .unwrap_or_else(|| settings.get_parsed_or("grelier.app.workspace.button_padding_y", 4u16));
+    let workspace_corner_radius = style
+        .number("workspace.radius")
+        .unwrap_or_else(|| settings.get_parsed_or("grelier.ws.corner_radius", 5.0_f32));
     let workspace_transitions = settings.get_bool_or("grelier.ws.transitions", false);
-    let workspace_label_size = settings.get_parsed_or("grelier.app.workspace.label_size", 14u32);
+    let workspace_label_size = style
+        .number("workspace.font_size")
+        .map(|size| size as u32)
+        .unwrap_or_else(|| settings.get_parsed_or("grelier.app.workspace.label_size", 14u32));
+    let workspace_font = style.font("workspace.font");
+    let workspace_colors = WorkspaceColors::from_style(&style);
     let workspace_icon_size = settings.get_parsed_or("grelier.app.workspace.icon_size", 22.0);
     let workspace_icon_spacing = settings
         .get_parsed_or("grelier.app.workspace.icon_spacing", 6u32)
@@ -142,7 +581,16 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
     let workspace_icon_padding_y =
         settings.get_parsed_or("grelier.app.workspace.icon_padding_y", 2u16);
     let workspace_app_icons = settings.get_bool_or("grelier.app.workspace.app_icons", true);
+    let workspace_style = WorkspaceStyle::parse(&settings.get_or(STYLE_KEY, "numbers"))
+        .unwrap_or(WorkspaceStyle::Numbers);
+    let workspace_dot_size = settings
+        .get_parsed_or("grelier.ws.dot_size", 8.0_f32)
+        .max(2.0);
 
+    let previewed_workspace = state
+        .workspace_history_preview
+        .and_then(|position| state.workspace_history.get(position))
+        .map(String::as_str);
     let previous_workspace = state.previous_workspace.as_deref();
     let highlight_previous = previous_workspace.is_some() && state.workspaces.len() > 1;
 
@@ -159,6 +607,12 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                 .map(|apps| apps.as_slice())
                 .unwrap_or(&[]);
             let (focus_level, urgent_level) = workspace_levels(ws);
+            let drop = state
+                .workspace_drag
+                .as_ref()
+                .and_then(|drag| drop_side(&state.workspaces, drag, &ws.name));
+            let hovered = state.is_hovered(&HoverTarget::Workspace(ws.name.clone()))
+                || previewed_workspace == Some(ws.name.as_str());
             let is_previous =
                 highlight_previous && !ws.focused && previous_workspace == Some(ws.name.as_str());
 
@@ -171,8 +625,10 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                 if focus > 0.0 {
                     label = label.font(Font {
                         weight: Weight::Bold,
-                        ..Font::DEFAULT
+                        ..workspace_font.unwrap_or(Font::DEFAULT)
                     });
+                } else if let Some(font) = workspace_font {
+                    label = label.font(font);
                 }
 
                 let mut icons_column = Column::new()
@@ -186,7 +642,10 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                             .unwrap_or(&FALLBACK_ICON_HANDLE);
                         let app_id = app.app_id.clone();
                         let con_id = app.con_id;
-                        let icon = mouse_area(app_icon_view(handle, workspace_icon_size))
+                        let target = HoverTarget::WorkspaceApp(con_id);
+                        let hovered = state.is_hovered(&target);
+                        let icon = app_icon_view(handle, workspace_icon_size);
+                        let icon = mouse_area(hoverable(icon, target, hovered))
                             .on_press(Message::WorkspaceAppClicked { con_id, app_id })
                             .interaction(mouse::Interaction::Pointer);
                         icons_column = icons_column.push(icon);
@@ -197,19 +656,13 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                     .padding([workspace_button_padding_y, workspace_button_padding_x])
                     .width(Length::Fill)
                     .style(move |theme: &Theme| {
-                        let palette = theme.extended_palette();
-                        let (gradient_start, gradient_end) =
-                            workspace_gradient_colors(focus, urgent, is_previous, palette);
-                        let text_color = if is_previous {
-                            palette.background.base.color
-                        } else {
-                            let emphasis = focus.max(urgent);
-                            lerp_color(
-                                theme.palette().text,
-                                palette.background.base.color,
-                                emphasis,
-                            )
-                        };
+                        let ((gradient_start, gradient_end), text_color) = styled_workspace_colors(
+                            focus,
+                            urgent,
+                            is_previous,
+                            theme,
+                            workspace_colors,
+                        );
                         let border =
                             Border::default().rounded(border::Radius::new(workspace_corner_radius));
 
@@ -223,16 +676,7 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                         }
                     });
 
-                let label_button: Element<'_, Message> = button(label_content)
-                    .style(|theme: &Theme, _status| button::Style {
-                        background: None,
-                        text_color: theme.palette().text,
-                        ..button::Style::default()
-                    })
-                    .padding(0)
-                    .width(Length::Fill)
-                    .on_press(Message::WorkspaceClicked(name))
-                    .into();
+                let label_button = workspace_target(label_content, name, hovered);
 
                 let mut layout = Column::new()
                     .spacing(2)
@@ -256,42 +700,126 @@ pub fn view<'a>(state: &'a BarState) -> Panel<'a> {
                 layout.into()
             };
 
-            let workspace: Element<'_, Message> = if workspace_transitions {
-                AnimationBuilder::new((focus_level, urgent_level), move |(focus, urgent)| {
-                    build_workspace(focus, urgent)
-                })
-                .animation(Easing::EASE_IN_OUT.very_quick())
-                .into()
-            } else {
-                build_workspace(focus_level, urgent_level)
+            let dot_name = ws.name.clone();
+            let build_dot = move |focus: f32, urgent: f32| -> Element<'_, Message> {
+                dot_view(
+                    dot_name.clone(),
+                    focus,
+                    urgent,
+                    is_previous,
+                    hovered,
+                    workspace_dot_size,
+                )
             };
 
-            col.push(workspace)
+            let workspace: Element<'_, Message> =
+                match (workspace_style, workspace_transitions) {
+                    (WorkspaceStyle::Numbers, true) => AnimationBuilder::new(
+                        (focus_level, urgent_level),
+                        move |(focus, urgent)| build_workspace(focus, urgent),
+                    )
+                    .animation(bar_easing(Easing::EASE_IN_OUT.very_quick()))
+                    .into(),
+                    (WorkspaceStyle::Numbers, false) => build_workspace(focus_level, urgent_level),
+                    (WorkspaceStyle::Dots, true) => AnimationBuilder::new(
+                        (focus_level, urgent_level),
+                        move |(focus, urgent)| build_dot(focus, urgent),
+                    )
+                    .animation(bar_easing(Easing::EASE_IN_OUT.very_quick()))
+                    .into(),
+                    (WorkspaceStyle::Dots, false) => build_dot(focus_level, urgent_level),
+                };
+
+            let col = if drop == Some(DropSide::Before) {
+                col.push(drop_indicator())
+            } else {
+                col
+            };
+            let col = col.push(container(workspace).id(crate::a11y::workspace_id(ws)));
+            if drop == Some(DropSide::After) {
+                col.push(drop_indicator())
+            } else {
+                col
+            }
         },
     );
 
-    Panel::new(workspaces)
+    let content: Element<'_, Message> = match previewed_workspace {
+        Some(name) => Column::new()
+            .push(
+                container(
+                    Text::new(name.to_string())
+                        .size(workspace_label_size.saturating_sub(2).max(8))
+                        .width(Length::Fill)
+                        .align_x(text::Alignment::Center),
+                )
+                .padding([workspace_padding_y, workspace_padding_x]),
+            )
+            .push(workspaces)
+            .into(),
+        None => workspaces.into(),
+    };
+
+    Panel::new(
+        mouse_area(content)
+            .on_scroll(scroll_message)
+            .on_exit(Message::WorkspaceHistoryCancelled),
+    )
 }
 
 fn panel_settings() -> &'static [crate::settings::SettingSpec] {
     crate::settings::NO_SETTINGS
 }
 
+fn validate(settings: &crate::settings::Settings) -> Result<(), String> {
+    if let Some(style) = settings.get(STYLE_KEY)
+        && WorkspaceStyle::parse(&style).is_none()
+    {
+        return Err(format!(
+            "Invalid {STYLE_KEY} '{}', expected 'numbers' or 'dots'",
+            style.trim()
+        ));
+    }
+    if let Some(modifier) = settings.get(HISTORY_MODIFIER_KEY)
+        && HistoryModifier::parse(&modifier).is_none()
+    {
+        return Err(format!(
+            "Invalid {HISTORY_MODIFIER_KEY} '{}', expected 'alt', 'ctrl', 'shift', 'super', or 'none'",
+            modifier.trim()
+        ));
+    }
+    for key in [ON_MIDDLE_CLICK_KEY, ON_RIGHT_CLICK_KEY, ON_DOUBLE_CLICK_KEY] {
+        if let Some(value) = settings.get(key)
+            && WorkspaceAction::parse(&value).is_none()
+        {
+            return Err(format!(
+                "Invalid {key} '{}', expected 'focus', 'move-focused-here', 'rename', or 'none'",
+                value.trim()
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn panel_subscription(
     context: PanelSubscriptionContext<'_>,
 ) -> Option<iced::Subscription<Message>> {
     Some(if context.activation == PanelActivation::Active {
-        crate::sway_workspace::workspace_subscription()
+        crate::compositor::workspace_subscription(context.generation)
     } else {
-        crate::sway_workspace::output_subscription()
+        crate::compositor::output_subscription(context.generation)
     })
 }
 
 fn panel_bootstrap(context: PanelBootstrapContext<'_>, out: &mut PanelBootstrapConfig) {
     if context.activation == PanelActivation::Active {
-        out.workspace_app_icons = context
-            .settings
-            .get_bool_or("grelier.app.workspace.app_icons", true);
+        // Dots mode never shows app icons, so skip fetching them.
+        let dots = WorkspaceStyle::parse(&context.settings.get_or(STYLE_KEY, "numbers"))
+            == Some(WorkspaceStyle::Dots);
+        out.workspace_app_icons = !dots
+            && context
+                .settings
+                .get_bool_or("grelier.app.workspace.app_icons", true);
     }
 }
 
@@ -304,7 +832,7 @@ inventory::submit! {
         view,
         subscription: Some(panel_subscription),
         bootstrap: Some(panel_bootstrap),
-        validate: None,
+        validate: Some(validate),
     }
 }
 
@@ -323,9 +851,10 @@ mod tests {
         WorkspaceInfo {
             num,
             name: num.to_string(),
+            label: num.to_string(),
             focused,
             urgent: false,
-            rect: crate::sway_workspace::Rect { y: 0, height: 0 },
+            rect: crate::compositor::Rect { y: 0, height: 0 },
         }
     }
 
@@ -382,6 +911,140 @@ mod tests {
         );
     }
 
+    #[test]
+    fn auto_names_follow_dominant_unblocked_app() {
+        let app = |app_id: &str| WorkspaceApp {
+            app_id: app_id.to_string(),
+            con_id: 0,
+        };
+        let mut named = workspace(2, false);
+        named.name = "2:old".to_string();
+        let workspaces = [workspace(1, true), named, workspace(3, false)];
+        let apps = HashMap::from([
+            (
+                "1".to_string(),
+                vec![
+                    app("foot"),
+                    app("org.mozilla.firefox"),
+                    app("org.mozilla.firefox"),
+                ],
+            ),
+            ("2:old".to_string(), vec![app("foot")]),
+            ("3".to_string(), vec![app("foot"), app("Slack")]),
+        ]);
+        let renames = auto_name_renames(&workspaces, &apps, &["foot".to_string()]);
+        assert_eq!(
+            renames,
+            vec![
+                ("1".to_string(), "1:firefox".to_string()),
+                ("2:old".to_string(), "2".to_string()),
+                ("3".to_string(), "3:slack".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn drop_indicator_follows_drag_direction() {
+        let workspaces = [workspace(1, true), workspace(2, false), workspace(3, false)];
+        let drag = |source: &str, target: &str| WorkspaceDrag {
+            source: source.to_string(),
+            target: Some(target.to_string()),
+        };
+        assert_eq!(
+            drop_side(&workspaces, &drag("1", "3"), "3"),
+            Some(DropSide::After)
+        );
+        assert_eq!(
+            drop_side(&workspaces, &drag("3", "2"), "2"),
+            Some(DropSide::Before)
+        );
+        assert_eq!(drop_side(&workspaces, &drag("3", "2"), "1"), None);
+    }
+
+    #[test]
+    fn scroll_moves_focus_with_optional_wrap() {
+        let workspaces = [workspace(1, false), workspace(2, false), workspace(3, true)];
+        assert_eq!(scroll_target(&workspaces, false, false), Some("2"));
+        assert_eq!(scroll_target(&workspaces, true, false), None);
+        assert_eq!(scroll_target(&workspaces, true, true), Some("1"));
+        assert_eq!(scroll_target(&[workspace(1, true)], true, true), None);
+        assert_eq!(scroll_target(&[workspace(1, false)], true, true), None);
+    }
+
+    #[test]
+    fn walks_focus_history_and_commits_on_release() {
+        let mut state = BarState::default();
+        let all = [
+            workspace(1, false),
+            workspace(2, false),
+            workspace(3, false),
+        ];
+        for focused in [1, 2, 3, 1] {
+            let mut workspaces = all.clone();
+            workspaces[focused - 1].focused = true;
+            update_workspace_focus(&mut state, &workspaces);
+        }
+        assert_eq!(state.workspace_history, vec!["1", "3", "2"]);
+
+        assert_eq!(history_step(3, None, true), Some(1));
+        assert_eq!(history_step(3, Some(2), true), Some(2));
+        assert_eq!(history_step(3, Some(1), false), Some(0));
+        assert_eq!(history_step(1, None, true), None);
+
+        state.workspace_history_preview = Some(2);
+        assert_eq!(commit_history_preview(&mut state).as_deref(), Some("2"));
+        assert_eq!(state.workspace_history_preview, None);
+        state.workspace_history_preview = Some(0);
+        assert_eq!(commit_history_preview(&mut state), None);
+
+        update_workspace_focus(&mut state, &[workspace(1, false), workspace(2, true)]);
+        assert_eq!(state.workspace_history, vec!["2", "1"]);
+
+        let alt = keyboard::Modifiers::ALT;
+        assert!(HistoryModifier::parse("alt").unwrap().held(alt));
+        assert!(!HistoryModifier::parse("none").unwrap().held(alt));
+        assert_eq!(HistoryModifier::parse("hyper"), None);
+    }
+
+    #[test]
+    fn maps_workspace_actions_and_double_clicks() {
+        assert_eq!(
+            WorkspaceAction::parse(" move-focused-here "),
+            Some(WorkspaceAction::MoveFocusedHere)
+        );
+        assert_eq!(
+            WorkspaceAction::parse("rename"),
+            Some(WorkspaceAction::Rename)
+        );
+        assert_eq!(WorkspaceAction::parse("close"), None);
+
+        let window = Duration::from_millis(400);
+        let start = Instant::now();
+        let last = ("2".to_string(), start);
+        let soon = start + Duration::from_millis(200);
+        assert!(is_double_click(Some(&last), "2", soon, window));
+        assert!(!is_double_click(Some(&last), "3", soon, window));
+        assert!(!is_double_click(
+            Some(&last),
+            "2",
+            start + Duration::from_millis(500),
+            window
+        ));
+        assert!(!is_double_click(None, "2", soon, window));
+    }
+
+    #[test]
+    fn dots_style_parses_and_stretches_focused_dot() {
+        assert_eq!(WorkspaceStyle::parse("dots"), Some(WorkspaceStyle::Dots));
+        assert_eq!(
+            WorkspaceStyle::parse(" numbers "),
+            Some(WorkspaceStyle::Numbers)
+        );
+        assert_eq!(WorkspaceStyle::parse("pills"), None);
+        assert_eq!(dot_dimensions(8.0, 0.0), (8.0, 8.0));
+        assert_eq!(dot_dimensions(8.0, 1.0), (8.0, 20.0));
+    }
+
     #[test]
     fn workspace_gradient_colors_handle_previous_fade() {
         let theme = Theme::Nord;
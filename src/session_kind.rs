@@ -0,0 +1,182 @@
+// Session detection: whether the bar runs on the machine's own seat or in a nested, headless,
+// remote, or secondary-seat session, and which hardware access that rules out.
+// Consumes Settings: grelier.session.
+use crate::bar::OutputSnapshot;
+use crate::settings::Settings;
+use std::sync::OnceLock;
+
+pub const SESSION_KEY: &str = "grelier.session";
+
+/// Gauges that act on the displays the compositor drives: backlight, VRR/HDR, privacy screen.
+const OUTPUT_GAUGES: &[&str] = &["brightness", "display_features"];
+/// Gauges reading the local machine's sensors, meaningless in a VM or from another host.
+const HARDWARE_GAUGES: &[&str] = &["battery", "gpu", "temp"];
+
+static KIND: OnceLock<SessionKind> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionKind {
+    /// The compositor runs on seat0 with real outputs.
+    Local,
+    /// The compositor owns a seat other than seat0 on a multi-seat machine.
+    Seat,
+    /// A compositor running as a window inside another session.
+    Nested,
+    /// A compositor without outputs, such as in CI or a VM.
+    Headless,
+    /// Started over SSH.
+    Remote,
+}
+
+impl SessionKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "local" => Some(Self::Local),
+            "seat" => Some(Self::Seat),
+            "nested" => Some(Self::Nested),
+            "headless" => Some(Self::Headless),
+            "remote" => Some(Self::Remote),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Local => "local",
+            Self::Seat => "seat",
+            Self::Nested => "nested",
+            Self::Headless => "headless",
+            Self::Remote => "remote",
+        }
+    }
+
+    /// Whether `gauge_id` cannot work in this session and is left out.
+    pub fn skips(self, gauge_id: &str) -> bool {
+        match self {
+            Self::Local => false,
+            Self::Seat | Self::Nested => OUTPUT_GAUGES.contains(&gauge_id),
+            Self::Headless | Self::Remote => {
+                OUTPUT_GAUGES.contains(&gauge_id) || HARDWARE_GAUGES.contains(&gauge_id)
+            }
+        }
+    }
+
+    /// udev events and the lid switch belong to the session on the machine's own seat.
+    pub fn owns_hardware(self) -> bool {
+        self == Self::Local
+    }
+
+    /// Layer-shell namespace, distinct outside a local session so compositor rules and
+    /// screenshot tools can tell a nested bar from the host's.
+    pub fn namespace(self, base: &str) -> String {
+        match self {
+            Self::Local => base.to_string(),
+            other => format!("{base}-{}", other.name()),
+        }
+    }
+}
+
+/// Classify the session from its environment and the compositor's output names. Nested wlroots
+/// compositors name outputs `WL-1` or `X11-1`, headless ones `HEADLESS-1`.
+fn detect_from(env: impl Fn(&str) -> Option<String>, outputs: &[OutputSnapshot]) -> SessionKind {
+    let backends = env("WLR_BACKENDS").unwrap_or_default();
+    let has_backend = |name: &str| backends.split(',').any(|backend| backend.trim() == name);
+    let all_named = |prefixes: &[&str]| {
+        !outputs.is_empty()
+            && outputs.iter().all(|output| {
+                prefixes
+                    .iter()
+                    .any(|prefix| output.name.starts_with(prefix))
+            })
+    };
+
+    if has_backend("wayland") || has_backend("x11") || all_named(&["WL-", "X11-"]) {
+        SessionKind::Nested
+    } else if has_backend("headless") || all_named(&["HEADLESS-"]) {
+        SessionKind::Headless
+    } else if env("SSH_CONNECTION").is_some_and(|value| !value.is_empty()) {
+        SessionKind::Remote
+    } else if env("XDG_SEAT").is_some_and(|seat| !seat.is_empty() && seat != "seat0") {
+        SessionKind::Seat
+    } else {
+        SessionKind::Local
+    }
+}
+
+/// Detect the session, or use `grelier.session` when it names a kind, and remember it.
+pub fn init(settings: &Settings, outputs: &[OutputSnapshot]) -> Result<SessionKind, String> {
+    let configured = settings.get_or(SESSION_KEY, "auto");
+    let kind = match configured.as_str() {
+        "auto" => detect_from(|key| std::env::var(key).ok(), outputs),
+        name => SessionKind::from_name(name).ok_or_else(|| {
+            format!(
+                "Invalid {SESSION_KEY} '{name}'; expected auto, local, seat, nested, headless, \
+                 or remote"
+            )
+        })?,
+    };
+    let _ = KIND.set(kind);
+    Ok(kind)
+}
+
+/// The session detected at startup; local before detection has run.
+pub fn kind() -> SessionKind {
+    KIND.get().copied().unwrap_or(SessionKind::Local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn output(name: &str) -> OutputSnapshot {
+        OutputSnapshot {
+            name: name.to_string(),
+            active: true,
+            rect: (0, 0, 1280, 720),
+            scale: 1.0,
+        }
+    }
+
+    fn detect(vars: &[(&str, &str)], outputs: &[&str]) -> SessionKind {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let outputs: Vec<OutputSnapshot> = outputs.iter().map(|name| output(name)).collect();
+        detect_from(|key| vars.get(key).cloned(), &outputs)
+    }
+
+    #[test]
+    fn detects_nested_headless_remote_and_seat_sessions() {
+        assert_eq!(detect(&[], &["eDP-1", "DP-2"]), SessionKind::Local);
+        assert_eq!(
+            detect(&[("XDG_SEAT", "seat0")], &["eDP-1"]),
+            SessionKind::Local
+        );
+        assert_eq!(detect(&[], &["WL-1"]), SessionKind::Nested);
+        assert_eq!(
+            detect(&[("WLR_BACKENDS", "x11,libinput")], &[]),
+            SessionKind::Nested
+        );
+        assert_eq!(detect(&[], &["HEADLESS-1"]), SessionKind::Headless);
+        assert_eq!(
+            detect(
+                &[("SSH_CONNECTION", "10.0.0.2 51000 10.0.0.1 22")],
+                &["eDP-1"]
+            ),
+            SessionKind::Remote
+        );
+        assert_eq!(
+            detect(&[("XDG_SEAT", "seat1")], &["DP-1"]),
+            SessionKind::Seat
+        );
+
+        assert!(SessionKind::Nested.skips("brightness"));
+        assert!(!SessionKind::Nested.skips("battery"));
+        assert!(SessionKind::Headless.skips("battery"));
+        assert!(!SessionKind::Headless.skips("clock"));
+        assert_eq!(SessionKind::Local.namespace("grelier"), "grelier");
+        assert_eq!(SessionKind::Nested.namespace("grelier"), "grelier-nested");
+    }
+}
@@ -1,8 +1,11 @@
 // Global settings store with parsing helpers and runtime updates persisted to storage.
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 use std::sync::{OnceLock, RwLock};
 
+use crate::panels::gauges::gauge::GRAPH_SAMPLES_KEY;
 use crate::settings_storage::SettingsStorage;
 
 /// Static settings metadata for defaults and help output.
@@ -14,6 +17,86 @@ pub struct SettingSpec {
 
 pub const NO_SETTINGS: &[SettingSpec] = &[];
 
+/// Namespace for per-output settings, as `grelier.output.<output>.<key>`.
+pub const OUTPUT_PREFIX: &str = "grelier.output.";
+
+/// Namespace for settings profiles, as `grelier.profile.<profile>.<key>`.
+pub const PROFILE_PREFIX: &str = "grelier.profile.";
+
+/// Declare a typed settings struct together with the `SettingSpec` table for its keys.
+///
+/// Each field names its key, its default, and optionally an inclusive range that parsed
+/// values are clamped to. The generated `SPECS` table and `load()` share the same default
+/// string, so help output and runtime behavior cannot drift apart.
+///
+/// ```ignore
+/// typed_settings! {
+///     struct ExampleSettings {
+///         poll_interval_secs: u64 = ("grelier.gauge.example.poll_interval_secs", "5", 1..=3600),
+///     }
+/// }
+/// ```
+macro_rules! typed_settings {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident: $ty:ty = ($key:literal, $default:literal $(, $range:expr)?)
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl $name {
+            pub const SPECS: &'static [$crate::settings::SettingSpec] = &[
+                $(
+                    $crate::settings::SettingSpec {
+                        key: $key,
+                        default: $default,
+                    },
+                )*
+            ];
+
+            /// Read every field from the global settings store, falling back to the defaults.
+            pub fn load() -> Self {
+                Self::from_lookup(|key| $crate::settings::settings().get(key))
+            }
+
+            fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+                Self {
+                    $(
+                        $field: {
+                            let raw = lookup($key);
+                            let value: $ty = $crate::settings::parse_or_exit(
+                                $key,
+                                raw.as_deref().unwrap_or($default),
+                            );
+                            $(let value = $crate::settings::clamp_setting($key, value, $range);)?
+                            value
+                        },
+                    )*
+                }
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::from_lookup(|_| None)
+            }
+        }
+    };
+}
+
+pub(crate) use typed_settings;
+
 /// Base settings shared by the bar regardless of which gauges are enabled.
 pub fn base_setting_specs(
     default_gauges: &'static str,
@@ -30,6 +113,14 @@ pub fn base_setting_specs(
             key: "grelier.panels",
             default: default_panels,
         },
+        SettingSpec {
+            key: "grelier.compositor",
+            default: "auto",
+        },
+        SettingSpec {
+            key: "grelier.session",
+            default: "auto",
+        },
         SettingSpec {
             key: "grelier.bar.orientation",
             default: default_orientation,
@@ -62,10 +153,70 @@ pub fn base_setting_specs(
             key: "grelier.bar.theme.danger",
             default: "",
         },
+        SettingSpec {
+            key: crate::theme::PYWAL_COLORS_KEY,
+            default: crate::theme::DEFAULT_PYWAL_COLORS,
+        },
         SettingSpec {
             key: "grelier.bar.width",
             default: "28",
         },
+        SettingSpec {
+            key: crate::bar::ALIGN_KEY,
+            default: "space-between",
+        },
+        SettingSpec {
+            key: crate::bar::HOVER_HIGHLIGHT_KEY,
+            default: "true",
+        },
+        SettingSpec {
+            key: crate::bar::ANIMATIONS_KEY,
+            default: "true",
+        },
+        SettingSpec {
+            key: crate::bar::SCALE_KEY,
+            default: "auto",
+        },
+        SettingSpec {
+            key: "grelier.bar.lock.locked",
+            default: "false",
+        },
+        SettingSpec {
+            key: "grelier.bar.lock.long_press_ms",
+            default: "1500",
+        },
+        SettingSpec {
+            key: crate::bar::MARGIN_TOP_KEY,
+            default: "0",
+        },
+        SettingSpec {
+            key: crate::bar::MARGIN_RIGHT_KEY,
+            default: "0",
+        },
+        SettingSpec {
+            key: crate::bar::MARGIN_BOTTOM_KEY,
+            default: "0",
+        },
+        SettingSpec {
+            key: crate::bar::MARGIN_LEFT_KEY,
+            default: "0",
+        },
+        SettingSpec {
+            key: crate::bar::RADIUS_KEY,
+            default: "0",
+        },
+        SettingSpec {
+            key: crate::autohide::AUTOHIDE_KEY,
+            default: "false",
+        },
+        SettingSpec {
+            key: crate::autohide::PEEK_KEY,
+            default: "2",
+        },
+        SettingSpec {
+            key: crate::autohide::DELAY_KEY,
+            default: "500",
+        },
         SettingSpec {
             key: "grelier.bar.border.blend",
             default: "true",
@@ -182,6 +333,10 @@ pub fn base_setting_specs(
             key: "grelier.app.top_apps.icon_size",
             default: "20.0",
         },
+        SettingSpec {
+            key: "grelier.app.top_apps.categories",
+            default: "",
+        },
         SettingSpec {
             key: "grelier.gauge.ui.padding_x",
             default: "2",
@@ -206,26 +361,125 @@ pub fn base_setting_specs(
             key: "grelier.gauge.ui.icon_value_spacing",
             default: "0.0",
         },
+        SettingSpec {
+            key: GRAPH_SAMPLES_KEY,
+            default: "10",
+        },
+        SettingSpec {
+            key: crate::gauge_loading::LOADING_TIMEOUT_KEY,
+            default: "15",
+        },
+        SettingSpec {
+            key: crate::panels::gauges::gauge_tuning::TUNE_MODIFIER_KEY,
+            default: "ctrl",
+        },
+        SettingSpec {
+            key: crate::overview::COLUMNS_KEY,
+            default: "4",
+        },
+        SettingSpec {
+            key: crate::overview::CARD_WIDTH_KEY,
+            default: "260",
+        },
+        SettingSpec {
+            key: crate::overview::ICON_SIZE_KEY,
+            default: "24",
+        },
+        SettingSpec {
+            key: crate::overview::BUTTON_SIZE_KEY,
+            default: "20",
+        },
+        SettingSpec {
+            key: "grelier.units.temperature",
+            default: "c",
+        },
+        SettingSpec {
+            key: "grelier.units.data_rate",
+            default: "bytes",
+        },
+        SettingSpec {
+            key: "grelier.units.prefix",
+            default: "iec",
+        },
+        SettingSpec {
+            key: crate::settings_storage::INCLUDE_KEY,
+            default: "",
+        },
+        SettingSpec {
+            key: crate::panels::gauges::gauge_hardware::HIDE_MISSING_KEY,
+            default: "true",
+        },
+        SettingSpec {
+            key: crate::panels::gauges::battery_profile::ON_BATTERY_KEY,
+            default: "battery",
+        },
+        SettingSpec {
+            key: "grelier.profile.battery.bar.animations",
+            default: "false",
+        },
+        SettingSpec {
+            key: "grelier.profile.battery.gauge.net.graph",
+            default: "false",
+        },
+        SettingSpec {
+            key: "grelier.profile.battery.gauge.net.slow_interval_secs",
+            default: "10",
+        },
+        SettingSpec {
+            key: "grelier.profile.battery.gauge.temp.poll_interval_secs",
+            default: "15",
+        },
+        SettingSpec {
+            key: "grelier.profile.battery.gauge.gpu.poll_interval_secs",
+            default: "10",
+        },
+        SettingSpec {
+            key: "grelier.profile.battery.gauge.wifi.poll_interval_secs",
+            default: "10",
+        },
     ]
 }
 
 #[derive(Debug)]
 pub struct Settings {
+    /// Effective settings: the main file with includes merged over it, plus runtime updates.
     map: RwLock<HashMap<String, String>>,
+    /// Main-file layer written back on update, so included values are never copied into it.
+    stored: RwLock<HashMap<String, String>>,
+    /// Keys overridden by an output or a profile, mapped to the prefixed key updates go to.
+    override_keys: RwLock<HashMap<String, String>>,
+    /// The settings profile switched on, if any.
+    profile: RwLock<Option<ActiveProfile>>,
     storage: SettingsStorage,
 }
 
+/// A switched-on profile and what it replaced, so switching it off restores the previous values.
+#[derive(Debug)]
+struct ActiveProfile {
+    name: String,
+    /// Replaced keys with their previous value and previous override key.
+    replaced: Vec<(String, Option<String>, Option<String>)>,
+}
+
 impl Settings {
     pub fn new(storage: SettingsStorage) -> Self {
-        let map = match storage.load() {
+        let stored = match storage.load() {
             Ok(map) => map,
             Err(err) => {
                 log::error!("Failed to load settings storage: {err}");
                 HashMap::new()
             }
         };
+        let mut map = stored.clone();
+        match storage.load_includes(&stored) {
+            Ok(included) => map.extend(included),
+            Err(err) => log::error!("Failed to load settings includes: {err}"),
+        }
         Self {
             map: RwLock::new(map),
+            stored: RwLock::new(stored),
+            override_keys: RwLock::new(HashMap::new()),
+            profile: RwLock::new(None),
             storage,
         }
     }
@@ -238,6 +492,16 @@ impl Settings {
             .cloned()
     }
 
+    /// Snapshot of every stored setting, sorted by key.
+    pub fn all(&self) -> BTreeMap<String, String> {
+        self.map
+            .read()
+            .expect("settings read lock poisoned")
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
     pub fn get_or(&self, key: &str, default: &str) -> String {
         self.get(key).unwrap_or_else(|| default.to_string())
     }
@@ -266,26 +530,103 @@ impl Settings {
             return;
         }
         map.insert(key.to_string(), value.to_string());
-        let snapshot = map.clone();
+        // Changes to an overridden key stay with the output or profile that overrides it.
+        let stored_key = self
+            .override_keys
+            .read()
+            .expect("settings read lock poisoned")
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_string());
+        if stored_key != key {
+            map.insert(stored_key.clone(), value.to_string());
+        }
         drop(map);
+        let mut stored = self.stored.write().expect("settings write lock poisoned");
+        stored.insert(stored_key, value.to_string());
+        let snapshot = stored.clone();
+        drop(stored);
         if let Err(err) = self.storage.save(&snapshot) {
             log::error!("Failed to save settings storage: {err}");
         }
     }
 
+    /// Let `grelier.output.<output>.<key>` values stand in for `grelier.<key>` in this process,
+    /// so a bar bound to one output can differ from the others. Overrides stay in memory and are
+    /// never written back to storage. Returns how many keys were overridden.
+    pub fn apply_output_overrides(&self, output: &str) -> usize {
+        let mut map = self.map.write().expect("settings write lock poisoned");
+        let overrides = output_overrides(&map, output);
+        let count = overrides.len();
+        let mut override_keys = self
+            .override_keys
+            .write()
+            .expect("settings write lock poisoned");
+        for (key, output_key, value) in overrides {
+            override_keys.insert(key.clone(), output_key);
+            map.insert(key, value);
+        }
+        count
+    }
+
+    /// Switch profile `name` on, replacing any other, or switch profiles off with `None`. While
+    /// a profile is on, its `grelier.profile.<name>.<key>` values stand in for `grelier.<key>`
+    /// like output overrides do. Returns whether the active profile changed.
+    pub fn set_profile(&self, name: Option<&str>) -> bool {
+        let mut active = self.profile.write().expect("settings write lock poisoned");
+        if active.as_ref().map(|profile| profile.name.as_str()) == name {
+            return false;
+        }
+        let mut map = self.map.write().expect("settings write lock poisoned");
+        let mut override_keys = self
+            .override_keys
+            .write()
+            .expect("settings write lock poisoned");
+        if let Some(previous) = active.take() {
+            for (key, value, override_key) in previous.replaced {
+                match value {
+                    Some(value) => map.insert(key.clone(), value),
+                    None => map.remove(&key),
+                };
+                match override_key {
+                    Some(override_key) => override_keys.insert(key, override_key),
+                    None => override_keys.remove(&key),
+                };
+            }
+        }
+        if let Some(name) = name {
+            let replaced = prefixed_overrides(&map, &format!("{PROFILE_PREFIX}{name}."))
+                .into_iter()
+                .map(|(key, profile_key, value)| {
+                    let previous_value = map.insert(key.clone(), value);
+                    let previous_key = override_keys.insert(key.clone(), profile_key);
+                    (key, previous_value, previous_key)
+                })
+                .collect();
+            *active = Some(ActiveProfile {
+                name: name.to_string(),
+                replaced,
+            });
+        }
+        true
+    }
+
     pub fn ensure_defaults(&self, specs: &[SettingSpec]) {
         let mut map = self.map.write().expect("settings write lock poisoned");
+        let mut stored = self.stored.write().expect("settings write lock poisoned");
         let mut changed = false;
         for spec in specs {
             if !map.contains_key(spec.key) {
                 map.insert(spec.key.to_string(), spec.default.to_string());
+                stored.insert(spec.key.to_string(), spec.default.to_string());
                 changed = true;
             }
         }
         if !changed {
             return;
         }
-        let snapshot = map.clone();
+        let snapshot = stored.clone();
+        drop(stored);
         drop(map);
         if let Err(err) = self.storage.save(&snapshot) {
             log::error!("Failed to save settings storage: {err}");
@@ -293,6 +634,32 @@ impl Settings {
     }
 }
 
+/// The `grelier.<key>` settings that `grelier.output.<output>.<key>` entries replace, as
+/// (key, per-output key, value).
+fn output_overrides(map: &HashMap<String, String>, output: &str) -> Vec<(String, String, String)> {
+    prefixed_overrides(map, &format!("{OUTPUT_PREFIX}{output}."))
+}
+
+/// The `grelier.<key>` settings that `<prefix><key>` entries replace, as
+/// (key, prefixed key, value).
+fn prefixed_overrides(
+    map: &HashMap<String, String>,
+    prefix: &str,
+) -> Vec<(String, String, String)> {
+    map.iter()
+        .filter_map(|(prefixed_key, value)| {
+            let key = prefixed_key.strip_prefix(prefix)?;
+            (!key.is_empty()).then(|| {
+                (
+                    format!("grelier.{key}"),
+                    prefixed_key.clone(),
+                    value.clone(),
+                )
+            })
+        })
+        .collect()
+}
+
 static SETTINGS: OnceLock<Settings> = OnceLock::new();
 
 pub fn init_settings(settings: Settings) -> &'static Settings {
@@ -328,7 +695,7 @@ pub fn parse_settings_arg(arg: &str) -> Result<HashMap<String, String>, String>
     Ok(map)
 }
 
-fn parse_or_exit<T: FromStr>(key: &str, value: &str) -> T {
+pub(crate) fn parse_or_exit<T: FromStr>(key: &str, value: &str) -> T {
     value.parse::<T>().unwrap_or_else(|_| {
         let message = format!(
             "Invalid setting '{key}': cannot parse '{value}' as {}",
@@ -339,6 +706,24 @@ fn parse_or_exit<T: FromStr>(key: &str, value: &str) -> T {
     })
 }
 
+/// Clamp a parsed setting into `range`, logging when the configured value was out of bounds.
+pub(crate) fn clamp_setting<T: PartialOrd + Debug>(
+    key: &str,
+    value: T,
+    range: RangeInclusive<T>,
+) -> T {
+    let (start, end) = range.into_inner();
+    if value < start {
+        log::warn!("Setting '{key}' value {value:?} is below {start:?}; using {start:?}");
+        start
+    } else if value > end {
+        log::warn!("Setting '{key}' value {value:?} is above {end:?}; using {end:?}");
+        end
+    } else {
+        value
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -351,6 +736,119 @@ mod tests {
         SettingsStorage::new(path)
     }
 
+    #[test]
+    fn updates_do_not_persist_included_values() {
+        let storage = temp_storage_path("include_layers");
+        let dir = std::env::temp_dir().join("grelier_settings_test_include_layers");
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        std::fs::write(dir.join("host.conf"), "grelier.bar.width: 40\n").expect("write include");
+        let mut map = HashMap::new();
+        map.insert("grelier.include".to_string(), "host.conf".to_string());
+        map.insert("grelier.bar.width".to_string(), "28".to_string());
+        storage.save(&map).expect("save settings storage");
+
+        let settings = Settings::new(storage.clone());
+        assert_eq!(settings.get("grelier.bar.width"), Some("40".to_string()));
+        settings.update("grelier.bar.theme", "Light");
+        let stored = storage.load().expect("reload settings storage");
+        assert_eq!(stored.get("grelier.bar.width"), Some(&"28".to_string()));
+        assert_eq!(stored.get("grelier.bar.theme"), Some(&"Light".to_string()));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn output_overrides_replace_only_that_outputs_keys() {
+        let map = HashMap::from([
+            ("grelier.bar.width".to_string(), "28".to_string()),
+            (
+                "grelier.output.HDMI-A-1.bar.width".to_string(),
+                "40".to_string(),
+            ),
+            (
+                "grelier.output.HDMI-A-1.panels".to_string(),
+                "gauges".to_string(),
+            ),
+            (
+                "grelier.output.eDP-1.bar.theme".to_string(),
+                "Light".to_string(),
+            ),
+            (
+                "grelier.output.HDMI-A-1.".to_string(),
+                "ignored".to_string(),
+            ),
+        ]);
+        let mut overrides = output_overrides(&map, "HDMI-A-1");
+        overrides.sort();
+        assert_eq!(
+            overrides,
+            vec![
+                (
+                    "grelier.bar.width".to_string(),
+                    "grelier.output.HDMI-A-1.bar.width".to_string(),
+                    "40".to_string()
+                ),
+                (
+                    "grelier.panels".to_string(),
+                    "grelier.output.HDMI-A-1.panels".to_string(),
+                    "gauges".to_string()
+                ),
+            ]
+        );
+        assert!(output_overrides(&map, "DP-2").is_empty());
+    }
+
+    #[test]
+    fn profiles_replace_keys_until_switched_off() {
+        let storage = temp_storage_path("profiles");
+        let map = HashMap::from([
+            ("grelier.bar.animations".to_string(), "true".to_string()),
+            ("grelier.bar.width".to_string(), "28".to_string()),
+            (
+                "grelier.profile.battery.bar.animations".to_string(),
+                "false".to_string(),
+            ),
+            (
+                "grelier.profile.battery.gauge.net.graph".to_string(),
+                "false".to_string(),
+            ),
+        ]);
+        storage.save(&map).expect("save settings storage");
+        let settings = Settings::new(storage.clone());
+
+        assert!(settings.set_profile(Some("battery")));
+        assert!(!settings.set_profile(Some("battery")));
+        assert_eq!(
+            settings.get("grelier.bar.animations").as_deref(),
+            Some("false")
+        );
+        assert_eq!(
+            settings.get("grelier.gauge.net.graph").as_deref(),
+            Some("false")
+        );
+        settings.update("grelier.bar.animations", "true");
+        settings.update("grelier.bar.width", "30");
+
+        assert!(settings.set_profile(None));
+        assert_eq!(
+            settings.get("grelier.bar.animations").as_deref(),
+            Some("true")
+        );
+        assert_eq!(settings.get("grelier.gauge.net.graph"), None);
+        assert_eq!(settings.get("grelier.bar.width").as_deref(), Some("30"));
+        let stored = storage.load().expect("reload settings storage");
+        assert_eq!(
+            stored
+                .get("grelier.profile.battery.bar.animations")
+                .map(String::as_str),
+            Some("true")
+        );
+        assert_eq!(
+            stored.get("grelier.bar.animations").map(String::as_str),
+            Some("true")
+        );
+    }
+
     #[test]
     fn parse_settings_rejects_missing_separator() {
         let err = parse_settings_arg("grelier.bar.theme").unwrap_err();
@@ -395,6 +893,43 @@ mod tests {
         );
     }
 
+    // `load()` reads the global store, which tests never initialize.
+    #[allow(dead_code)]
+    mod example {
+        typed_settings! {
+            pub struct ExampleSettings {
+                label: String = ("grelier.example.label", "hello"),
+                ratio: f32 = ("grelier.example.ratio", "0.5", 0.0..=1.0),
+                ticks: u8 = ("grelier.example.ticks", "4", 1..=10),
+            }
+        }
+
+        pub fn configured(lookup: impl Fn(&str) -> Option<String>) -> ExampleSettings {
+            ExampleSettings::from_lookup(lookup)
+        }
+    }
+
+    #[test]
+    fn typed_settings_share_defaults_with_specs_and_clamp() {
+        use example::ExampleSettings;
+
+        let defaults = ExampleSettings::default();
+        assert_eq!(defaults.label, "hello");
+        assert_eq!(defaults.ratio, 0.5);
+        assert_eq!(ExampleSettings::SPECS.len(), 3);
+        assert_eq!(ExampleSettings::SPECS[1].key, "grelier.example.ratio");
+        assert_eq!(ExampleSettings::SPECS[1].default, "0.5");
+
+        let configured = example::configured(|key| match key {
+            "grelier.example.ratio" => Some("1.5".to_string()),
+            "grelier.example.ticks" => Some("7".to_string()),
+            _ => None,
+        });
+        assert_eq!(configured.ratio, 1.0);
+        assert_eq!(configured.ticks, 7);
+        assert_eq!(configured.label, "hello");
+    }
+
     #[test]
     fn get_parsed_panics_on_invalid_value() {
         let storage = temp_storage_path("parse_invalid");
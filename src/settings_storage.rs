@@ -1,9 +1,15 @@
 // Load/save settings in an Xresources-style file under the grelier config directory.
 // The filename includes the grelier version (Settings-<version>.xresources).
+// `grelier.include` pulls in override files (e.g. per-host) that are merged over it at load time.
 use std::collections::HashMap;
 use std::fs;
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Comma-separated list of files whose settings override the file that names them.
+pub const INCLUDE_KEY: &str = "grelier.include";
+/// How deep included files may themselves include further files.
+const MAX_INCLUDE_DEPTH: usize = 4;
 
 #[derive(Clone, Debug)]
 pub struct SettingsStorage {
@@ -30,69 +36,24 @@ impl SettingsStorage {
         path
     }
 
+    /// Settings stored in the main file, without includes applied.
     pub fn load(&self) -> Result<HashMap<String, String>, String> {
-        let file = match fs::File::open(&self.path) {
-            Ok(file) => file,
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                return Ok(HashMap::new());
-            }
-            Err(err) => {
-                return Err(format!(
-                    "unable to open settings storage {}: {err}",
-                    self.path.display()
-                ));
-            }
-        };
-        let reader = BufReader::new(file);
-        let mut map = HashMap::new();
-        let mut pending = String::new();
-        let mut pending_line = 0usize;
-        let mut continuation = false;
-
-        for (index, line) in reader.lines().enumerate() {
-            let line = line.map_err(|err| {
-                format!(
-                    "unable to read settings storage {}: {err}",
-                    self.path.display()
-                )
-            })?;
-            let line_number = index + 1;
-            if !continuation && pending.is_empty() {
-                let trimmed = line.trim();
-                if trimmed.is_empty() || trimmed.starts_with('!') || trimmed.starts_with('#') {
-                    continue;
-                }
-                pending_line = line_number;
-            } else if pending.is_empty() {
-                pending_line = line_number;
-            }
-
-            let mut fragment = line;
-            if continuation {
-                fragment = fragment.trim_start().to_string();
-            }
-
-            let (segment, has_continuation) = split_continuation(&fragment);
-            pending.push_str(&segment);
-            continuation = has_continuation;
-
-            if continuation {
-                continue;
-            }
-
-            if let Some((key, value)) = parse_line(&pending, pending_line)? {
-                map.insert(key, value);
-            }
-            pending.clear();
-        }
-
-        if continuation {
-            return Err(format!(
-                "line {pending_line}: trailing line continuation without content"
-            ));
-        }
+        load_file(&self.path)
+    }
 
-        Ok(map)
+    /// Settings from the files named by `grelier.include` in `base`, merged in order.
+    ///
+    /// Later includes override earlier ones, and an included file's own includes override it.
+    /// Missing include files are skipped so per-host overrides can be optional.
+    pub fn load_includes(
+        &self,
+        base: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, String> {
+        let mut merged = HashMap::new();
+        let mut visited = vec![self.path.clone()];
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        merge_includes(base, dir, 1, &mut visited, &mut merged)?;
+        Ok(merged)
     }
 
     pub fn save(&self, map: &HashMap<String, String>) -> Result<(), String> {
@@ -127,6 +88,133 @@ impl SettingsStorage {
     }
 }
 
+fn load_file(path: &Path) -> Result<HashMap<String, String>, String> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(HashMap::new());
+        }
+        Err(err) => {
+            return Err(format!(
+                "unable to open settings storage {}: {err}",
+                path.display()
+            ));
+        }
+    };
+    let reader = BufReader::new(file);
+    let mut map = HashMap::new();
+    let mut pending = String::new();
+    let mut pending_line = 0usize;
+    let mut continuation = false;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line
+            .map_err(|err| format!("unable to read settings storage {}: {err}", path.display()))?;
+        let line_number = index + 1;
+        if !continuation && pending.is_empty() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('!') || trimmed.starts_with('#') {
+                continue;
+            }
+            pending_line = line_number;
+        } else if pending.is_empty() {
+            pending_line = line_number;
+        }
+
+        let mut fragment = line;
+        if continuation {
+            fragment = fragment.trim_start().to_string();
+        }
+
+        let (segment, has_continuation) = split_continuation(&fragment);
+        pending.push_str(&segment);
+        continuation = has_continuation;
+
+        if continuation {
+            continue;
+        }
+
+        if let Some((key, value)) = parse_line(&pending, pending_line)? {
+            map.insert(key, value);
+        }
+        pending.clear();
+    }
+
+    if continuation {
+        return Err(format!(
+            "line {pending_line}: trailing line continuation without content"
+        ));
+    }
+
+    Ok(map)
+}
+
+fn merge_includes(
+    map: &HashMap<String, String>,
+    dir: &Path,
+    depth: usize,
+    visited: &mut Vec<PathBuf>,
+    merged: &mut HashMap<String, String>,
+) -> Result<(), String> {
+    let Some(includes) = map.get(INCLUDE_KEY) else {
+        return Ok(());
+    };
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err(format!(
+            "{INCLUDE_KEY} nested more than {MAX_INCLUDE_DEPTH} levels deep"
+        ));
+    }
+    let hostname = hostname();
+    for include in includes.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let path = include_path(include, dir, &hostname);
+        if visited.contains(&path) {
+            return Err(format!("{INCLUDE_KEY} cycle at {}", path.display()));
+        }
+        if !path.is_file() {
+            log::debug!("Skipping missing settings include {}", path.display());
+            continue;
+        }
+        let included = load_file(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+        visited.push(path.clone());
+        for (key, value) in &included {
+            if key != INCLUDE_KEY {
+                merged.insert(key.clone(), value.clone());
+            }
+        }
+        let include_dir = path.parent().unwrap_or(dir).to_path_buf();
+        merge_includes(&included, &include_dir, depth + 1, visited, merged)?;
+        visited.pop();
+    }
+    Ok(())
+}
+
+/// Expand `~`, `$HOSTNAME`, `${HOSTNAME}`, and `$(hostname)`; relative paths resolve against `dir`.
+fn include_path(include: &str, dir: &Path, hostname: &str) -> PathBuf {
+    let expanded = include
+        .replace("$(hostname)", hostname)
+        .replace("${HOSTNAME}", hostname)
+        .replace("$HOSTNAME", hostname);
+    let path = match expanded.strip_prefix("~/") {
+        Some(rest) => match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(rest),
+            None => PathBuf::from(rest),
+        },
+        None => PathBuf::from(expanded),
+    };
+    if path.is_absolute() {
+        path
+    } else {
+        dir.join(path)
+    }
+}
+
+fn hostname() -> String {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
 fn parse_line(line: &str, line_number: usize) -> Result<Option<(String, String)>, String> {
     let trimmed = line.trim();
     if trimmed.is_empty() || trimmed.starts_with('!') || trimmed.starts_with('#') {
@@ -287,6 +375,37 @@ mod tests {
         let _ = fs::remove_dir_all(dir);
     }
 
+    #[test]
+    fn includes_merge_in_order_and_skip_missing_files() {
+        let (storage, dir) = temp_storage("include");
+        fs::create_dir_all(dir.join("hosts")).expect("create hosts dir");
+        fs::write(
+            dir.join("hosts/common.conf"),
+            "grelier.gauges: clock\ngrelier.bar.width: 30\ngrelier.include: laptop.conf\n",
+        )
+        .expect("write common include");
+        fs::write(dir.join("hosts/laptop.conf"), "grelier.bar.width: 40\n")
+            .expect("write nested include");
+        fs::write(
+            &storage.path,
+            "grelier.gauges: cpu\ngrelier.bar.theme: Nord\ngrelier.include: hosts/common.conf, hosts/absent.conf\n",
+        )
+        .expect("write settings storage");
+
+        let base = storage.load().expect("load base settings");
+        let included = storage.load_includes(&base).expect("load includes");
+        assert_eq!(included.get("grelier.gauges"), Some(&"clock".to_string()));
+        assert_eq!(included.get("grelier.bar.width"), Some(&"40".to_string()));
+        assert!(!included.contains_key("grelier.bar.theme"));
+        assert!(!included.contains_key(INCLUDE_KEY));
+        assert_eq!(
+            include_path("~/h/$(hostname).conf", &dir, "box"),
+            PathBuf::from(std::env::var("HOME").unwrap_or_default()).join("h/box.conf")
+        );
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
     #[test]
     fn load_parses_line_continuations() {
         let (storage, dir) = temp_storage("continuation");
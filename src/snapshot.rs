@@ -0,0 +1,209 @@
+// Bug-report snapshots: window screenshots plus a JSON dump of the bar state.
+use crate::a11y;
+use crate::bar::{BarState, GaugeDialog, Message};
+use crate::compositor;
+use crate::ipc::IpcReply;
+use crate::panels::gauges::gauge::{GaugeDisplay, GaugeValue};
+use crate::settings;
+use iced::Task;
+use iced::widget::svg;
+use iced::window::{self, Screenshot};
+use serde_json::{Value, json};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn svg_name(handle: &svg::Handle) -> String {
+    match handle.data() {
+        iced_core::svg::Data::Path(path) => path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        iced_core::svg::Data::Bytes(_) => "<generated>".to_string(),
+    }
+}
+
+pub(crate) fn display_json(display: &GaugeDisplay) -> Value {
+    match display {
+        GaugeDisplay::Value { value, attention } => {
+            let value = match value {
+                GaugeValue::Text(text) => json!({ "text": text }),
+                GaugeValue::Svg(handle) => json!({ "svg": svg_name(handle) }),
+            };
+            json!({ "value": value, "attention": format!("{attention:?}") })
+        }
+        GaugeDisplay::Graph { samples, attention } => {
+            json!({ "graph": samples, "attention": format!("{attention:?}") })
+        }
+        GaugeDisplay::Empty => json!("empty"),
+        GaugeDisplay::Error => json!("error"),
+    }
+}
+
+fn a11y_json((role, label): (a11y::Role, String)) -> Value {
+    json!({ "role": role.name(), "label": label })
+}
+
+pub(crate) fn dialog_json(gauge_id: &str, dialog: &GaugeDialog) -> Value {
+    let (kind, title, lines): (&str, &str, Vec<&str>) = match dialog {
+        GaugeDialog::Menu(menu) => (
+            "menu",
+            &menu.title,
+            menu.items.iter().map(|item| item.label.as_str()).collect(),
+        ),
+        GaugeDialog::Action(action) => (
+            "action",
+            &action.title,
+            action.items.iter().map(|item| item.id.as_str()).collect(),
+        ),
+        GaugeDialog::Info(info) => (
+            "info",
+            &info.title,
+            info.lines.iter().map(String::as_str).collect(),
+        ),
+        GaugeDialog::Prompt(prompt) => ("prompt", &prompt.title, Vec::new()),
+    };
+    let mut value = json!({ "gauge": gauge_id, "kind": kind, "title": title, "lines": lines });
+    if let GaugeDialog::Menu(menu) = dialog {
+        value["labels"] = menu
+            .items
+            .iter()
+            .map(|item| a11y_json(a11y::menu_item_label(item)))
+            .collect();
+    }
+    value
+}
+
+/// Everything a bug report needs to reproduce what the bar is showing.
+pub fn state_json(state: &BarState) -> Value {
+    let mut dialogs: Vec<Value> = state
+        .dialog_windows
+        .values()
+        .chain(state.pinned_dialogs.values())
+        .map(|window| dialog_json(&window.gauge_id, &window.dialog))
+        .collect();
+    dialogs.sort_by_key(|dialog| dialog["gauge"].to_string());
+
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "compositor": compositor::backend().name(),
+        "theme": state.bar_theme.to_string(),
+        "current_workspace": state.current_workspace,
+        "workspaces": state.workspaces.iter().map(|ws| json!({
+            "num": ws.num,
+            "name": ws.name,
+            "focused": ws.focused,
+            "urgent": ws.urgent,
+            "a11y": a11y_json(a11y::workspace_label(ws)),
+        })).collect::<Vec<_>>(),
+        "gauges": state.gauges.iter().map(|gauge| json!({
+            "id": gauge.id,
+            "icon": svg_name(&gauge.icon),
+            "display": display_json(&gauge.display),
+            "a11y": a11y_json(a11y::gauge_label(gauge)),
+        })).collect::<Vec<_>>(),
+        "dialogs": dialogs,
+        "degraded": state.degraded,
+        "locked": state.locked,
+        "settings": settings::settings().all(),
+    })
+}
+
+fn write_png(path: &Path, screenshot: &Screenshot) -> Result<(), String> {
+    let file =
+        File::create(path).map_err(|err| format!("failed to create {}: {err}", path.display()))?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        screenshot.size.width,
+        screenshot.size.height,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&screenshot.rgba))
+        .map_err(|err| format!("failed to encode {}: {err}", path.display()))
+}
+
+/// Write the state dump now and screenshot every bar and dialog window, replying when done.
+pub fn snapshot_task(state: &BarState, dir: PathBuf, reply: IpcReply) -> Task<Message> {
+    let send_error = |reply: &IpcReply, message: String| {
+        let _ = reply.send(json!({ "ok": false, "error": message }).to_string());
+    };
+    if let Err(err) = fs::create_dir_all(&dir) {
+        send_error(&reply, format!("failed to create {}: {err}", dir.display()));
+        return Task::none();
+    }
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let prefix = format!("grelier-{}-{stamp}", std::process::id());
+    let state_path = dir.join(format!("{prefix}-state.json"));
+    let state_text = serde_json::to_string_pretty(&state_json(state)).unwrap_or_default();
+    if let Err(err) = fs::write(&state_path, state_text) {
+        send_error(
+            &reply,
+            format!("failed to write {}: {err}", state_path.display()),
+        );
+        return Task::none();
+    }
+
+    let mut windows: Vec<(String, window::Id)> = state
+        .windows
+        .bars()
+        .enumerate()
+        .map(|(index, id)| (format!("bar{index}"), id))
+        .collect();
+    windows.extend(
+        state
+            .dialog_windows
+            .iter()
+            .map(|(id, dialog)| (format!("dialog-{}", dialog.gauge_id), *id)),
+    );
+    windows.extend(
+        state
+            .pinned_dialogs
+            .iter()
+            .map(|(id, dialog)| (format!("pinned-{}", dialog.gauge_id), *id)),
+    );
+
+    let screenshots = windows.into_iter().map(|(label, id)| {
+        let path = dir.join(format!("{prefix}-{label}.png"));
+        window::screenshot(id).map(move |shot| write_png(&path, &shot).map(|()| path.clone()))
+    });
+    Task::batch(screenshots).collect().map(move |results| {
+        let mut files = vec![state_path.display().to_string()];
+        let mut errors = Vec::new();
+        for result in results {
+            match result {
+                Ok(path) => files.push(path.display().to_string()),
+                Err(err) => errors.push(err),
+            }
+        }
+        let _ = reply
+            .send(json!({ "ok": errors.is_empty(), "files": files, "errors": errors }).to_string());
+        Message::Noop
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::panels::gauges::gauge::GaugeValueAttention;
+
+    #[test]
+    fn display_json_reports_value_and_attention() {
+        let display = GaugeDisplay::Value {
+            value: GaugeValue::Svg(svg::Handle::from_path("/x/assets/ratio-3.svg")),
+            attention: GaugeValueAttention::Warning,
+        };
+        assert_eq!(
+            display_json(&display),
+            json!({ "value": { "svg": "ratio-3.svg" }, "attention": "Warning" })
+        );
+        assert_eq!(display_json(&GaugeDisplay::Error), json!("error"));
+    }
+}
@@ -0,0 +1,261 @@
+// Optional per-component style overrides read from ~/.config/grelier/style.toml.
+// Values here win over the theme palette and the matching grelier.* settings; the file is
+// reloaded when it changes.
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use iced::futures::SinkExt;
+use iced::futures::channel::mpsc;
+use iced::futures::executor;
+use iced::{Color, Font, Subscription};
+use toml_edit::{DocumentMut, Item, Value};
+
+use crate::bar::Message;
+use crate::theme::parse_hex_color;
+
+const STYLE_FILENAME: &str = "style.toml";
+/// How often the style file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Color,
+    Number,
+    Font,
+}
+
+/// Every key the style file understands, as `section.name`.
+const KEYS: &[(&str, Kind)] = &[
+    ("workspace.background", Kind::Color),
+    ("workspace.text", Kind::Color),
+    ("workspace.focused", Kind::Color),
+    ("workspace.focused_text", Kind::Color),
+    ("workspace.radius", Kind::Number),
+    ("workspace.padding_x", Kind::Number),
+    ("workspace.padding_y", Kind::Number),
+    ("workspace.font_size", Kind::Number),
+    ("workspace.font", Kind::Font),
+    ("gauge.icon", Kind::Color),
+    ("gauge.text", Kind::Color),
+    ("gauge.icon_size", Kind::Number),
+    ("gauge.padding_x", Kind::Number),
+    ("gauge.padding_y", Kind::Number),
+    ("gauge.spacing", Kind::Number),
+    ("gauge.font_size", Kind::Number),
+    ("gauge.font", Kind::Font),
+    ("dialog.background", Kind::Color),
+    ("dialog.text", Kind::Color),
+    ("dialog.title_background", Kind::Color),
+    ("dialog.title_text", Kind::Color),
+    ("dialog.font", Kind::Font),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum StyleValue {
+    Color(Color),
+    Number(f32),
+    Font(Font),
+}
+
+/// Parsed style overrides; empty when there is no style file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StyleSheet {
+    values: HashMap<&'static str, StyleValue>,
+}
+
+impl StyleSheet {
+    pub fn color(&self, key: &str) -> Option<Color> {
+        match self.values.get(key) {
+            Some(StyleValue::Color(color)) => Some(*color),
+            _ => None,
+        }
+    }
+
+    pub fn number(&self, key: &str) -> Option<f32> {
+        match self.values.get(key) {
+            Some(StyleValue::Number(number)) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn font(&self, key: &str) -> Option<Font> {
+        match self.values.get(key) {
+            Some(StyleValue::Font(font)) => Some(*font),
+            _ => None,
+        }
+    }
+}
+
+/// Font families have to outlive every frame; each distinct name is leaked once.
+fn intern(name: &str) -> &'static str {
+    static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut names = NAMES
+        .get_or_init(|| Mutex::new(HashSet::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(existing) = names.get(name) {
+        return existing;
+    }
+    let leaked: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.insert(leaked);
+    leaked
+}
+
+fn parse_value(key: &str, kind: Kind, value: &Value) -> Result<StyleValue, String> {
+    match (kind, value) {
+        (Kind::Color, Value::String(color)) => parse_hex_color(color.value())
+            .map(StyleValue::Color)
+            .map_err(|err| format!("{key}: {err}")),
+        (Kind::Number, Value::Integer(number)) => Ok(StyleValue::Number(*number.value() as f32)),
+        (Kind::Number, Value::Float(number)) => Ok(StyleValue::Number(*number.value() as f32)),
+        (Kind::Font, Value::String(name)) if !name.value().trim().is_empty() => Ok(
+            StyleValue::Font(Font::with_name(intern(name.value().trim()))),
+        ),
+        (Kind::Color, _) => Err(format!("{key}: expected a \"#rrggbb\" color string")),
+        (Kind::Number, _) => Err(format!("{key}: expected a number")),
+        (Kind::Font, _) => Err(format!("{key}: expected a font family name")),
+    }
+}
+
+/// Parse a style file. Unknown keys are reported in the returned warnings and skipped;
+/// malformed TOML or values of the wrong type are errors.
+pub fn parse(contents: &str) -> Result<(StyleSheet, Vec<String>), String> {
+    let document: DocumentMut = contents
+        .parse()
+        .map_err(|err| format!("invalid style file: {err}"))?;
+    let mut sheet = StyleSheet::default();
+    let mut warnings = Vec::new();
+    for (section, item) in document.iter() {
+        let Item::Table(table) = item else {
+            warnings.push(format!("{section}: expected a [{section}] table"));
+            continue;
+        };
+        for (name, item) in table.iter() {
+            let key = format!("{section}.{name}");
+            let Some((known, kind)) = KEYS.iter().find(|(known, _)| *known == key) else {
+                warnings.push(format!("{key}: unknown style key"));
+                continue;
+            };
+            let Some(value) = item.as_value() else {
+                return Err(format!("{key}: expected a value"));
+            };
+            sheet.values.insert(known, parse_value(&key, *kind, value)?);
+        }
+    }
+    Ok((sheet, warnings))
+}
+
+/// `$HOME/.config/grelier/style.toml`.
+pub fn style_path() -> PathBuf {
+    let mut path = match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home),
+        None => PathBuf::from("."),
+    };
+    path.push(".config");
+    path.push("grelier");
+    path.push(STYLE_FILENAME);
+    path
+}
+
+fn load_from(path: &Path) -> Result<StyleSheet, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(StyleSheet::default());
+        }
+        Err(err) => return Err(format!("{}: {err}", path.display())),
+    };
+    let (sheet, warnings) = parse(&contents).map_err(|err| format!("{}: {err}", path.display()))?;
+    for warning in warnings {
+        log::warn!("{}: {warning}", path.display());
+    }
+    Ok(sheet)
+}
+
+fn current_lock() -> &'static RwLock<Arc<StyleSheet>> {
+    static CURRENT: OnceLock<RwLock<Arc<StyleSheet>>> = OnceLock::new();
+    CURRENT.get_or_init(|| RwLock::new(Arc::new(StyleSheet::default())))
+}
+
+/// Style overrides currently in effect.
+pub fn current() -> Arc<StyleSheet> {
+    current_lock()
+        .read()
+        .map(|sheet| sheet.clone())
+        .unwrap_or_default()
+}
+
+/// Read the style file again; on error the previous overrides stay in effect.
+pub fn reload() -> Result<(), String> {
+    let sheet = load_from(&style_path())?;
+    if let Ok(mut current) = current_lock().write() {
+        *current = Arc::new(sheet);
+    }
+    Ok(())
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn style_watch_stream() -> impl iced::futures::Stream<Item = Message> {
+    let (mut sender, receiver) = mpsc::channel(1);
+    let path = style_path();
+    std::thread::spawn(move || {
+        let mut last = modified_at(&path);
+        while !sender.is_closed() {
+            std::thread::sleep(POLL_INTERVAL);
+            let current = modified_at(&path);
+            // Deleting the file also counts, so its overrides are dropped.
+            if current != last {
+                last = current;
+                if executor::block_on(sender.send(Message::StyleFileChanged)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    receiver
+}
+
+/// Watch the style file for edits.
+pub fn subscription() -> Subscription<Message> {
+    Subscription::run(style_watch_stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_keys_and_reports_unknown_ones() {
+        let (sheet, warnings) = parse(
+            "[workspace]\nfocused = \"#88c0d0\"\nradius = 3\n\n\
+             [gauge]\nicon_size = 18.5\nsparkle = true\n\n\
+             [dialog]\nfont = \"Fira Sans\"\n",
+        )
+        .expect("valid style");
+        assert_eq!(
+            sheet.color("workspace.focused"),
+            Some(Color::from_rgb8(0x88, 0xc0, 0xd0))
+        );
+        assert_eq!(sheet.number("workspace.radius"), Some(3.0));
+        assert_eq!(sheet.number("gauge.icon_size"), Some(18.5));
+        assert_eq!(
+            sheet.font("dialog.font"),
+            Some(Font::with_name("Fira Sans"))
+        );
+        assert_eq!(sheet.color("dialog.background"), None);
+        assert_eq!(
+            warnings,
+            vec!["gauge.sparkle: unknown style key".to_string()]
+        );
+
+        assert!(parse("[workspace]\nradius = \"big\"\n").is_err());
+        assert!(parse("[workspace]\nfocused = \"teal\"\n").is_err());
+        assert!(parse("[workspace\n").is_err());
+    }
+}
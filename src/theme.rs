@@ -1,12 +1,27 @@
 // Theme parsing and custom palette definitions for the UI.
-use std::sync::Arc;
+// Consumes Settings: grelier.bar.theme.*.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
 
+use iced::futures::SinkExt;
+use iced::futures::channel::mpsc;
+use iced::futures::executor;
 use iced::{
-    Color, Theme,
+    Color, Subscription, Theme,
     theme::{Custom, Palette},
 };
+use serde_json::Value;
 
+use crate::bar::Message;
+use crate::panels::gauges::gauge::{GaugeMenu, GaugeMenuItem};
 use crate::settings::Settings;
+use crate::settings_storage::SettingsStorage;
+
+/// Dialog id for the bar-level theme menu opened by right-clicking the background.
+pub const MENU_ID: &str = "bar_theme";
+pub const THEME_KEY: &str = "grelier.bar.theme";
 
 pub const DEFAULT_THEME: Theme = Theme::Nord;
 pub const VALID_THEME_NAMES: &[&str] = &[
@@ -32,9 +47,25 @@ pub const VALID_THEME_NAMES: &[&str] = &[
     "TokyoNightStorm",
     "AyuMirage",
     "Custom",
+    "Pywal",
 ];
 
 pub const CUSTOM_THEME_NAME: &str = "Custom";
+/// Theme whose palette is read from a pywal/wallust `colors.json` and reloaded on change.
+pub const PYWAL_THEME_NAME: &str = "Pywal";
+pub const PYWAL_COLORS_KEY: &str = "grelier.bar.theme.wal_colors";
+pub const DEFAULT_PYWAL_COLORS: &str = "~/.cache/wal/colors.json";
+/// How often the pywal colors file is checked for changes.
+const PYWAL_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Color keys read from user palette files.
+const USER_THEME_KEYS: [&str; 6] = [
+    "background",
+    "text",
+    "primary",
+    "success",
+    "warning",
+    "danger",
+];
 pub const CUSTOM_THEME_SETTING_KEYS: [&str; 6] = [
     "grelier.bar.theme.background",
     "grelier.bar.theme.text",
@@ -44,8 +75,48 @@ pub const CUSTOM_THEME_SETTING_KEYS: [&str; 6] = [
     "grelier.bar.theme.danger",
 ];
 
+/// Resolve a theme name, reading the palette from settings for `Custom`.
+pub fn theme_for_name(name: &str, settings: &Settings) -> Result<Theme, String> {
+    if is_custom_theme_name(name) {
+        return custom_theme_from_settings(settings);
+    }
+    if is_pywal_theme_name(name) {
+        // The colors file may not exist until wal first runs; the watcher picks it up later.
+        return Ok(
+            pywal_theme(&pywal_colors_path(settings)).unwrap_or_else(|err| {
+                log::warn!("Pywal theme unavailable, using {DEFAULT_THEME}: {err}");
+                DEFAULT_THEME
+            }),
+        );
+    }
+    parse_theme(name).ok_or_else(|| {
+        format!(
+            "Unknown theme '{name}'. Valid themes: {}",
+            theme_names().join(", ")
+        )
+    })
+}
+
+/// Menu listing every theme, with `current` checked.
+pub fn theme_menu(current: &str) -> GaugeMenu {
+    GaugeMenu {
+        title: "Theme".to_string(),
+        items: theme_names()
+            .into_iter()
+            .map(|name| GaugeMenuItem {
+                selected: name.eq_ignore_ascii_case(current.trim()),
+                label: name.clone(),
+                id: name,
+                exec: None,
+            })
+            .collect(),
+        on_select: None,
+        slider: None,
+    }
+}
+
 pub fn list_themes() {
-    for name in VALID_THEME_NAMES {
+    for name in theme_names() {
         println!("{name}");
     }
 }
@@ -55,62 +126,208 @@ pub fn is_custom_theme_name(name: &str) -> bool {
 }
 
 pub fn custom_theme_from_settings(settings: &Settings) -> Result<Theme, String> {
-    let mut missing = Vec::new();
-    let mut values: [Option<String>; 6] = [None, None, None, None, None, None];
+    let palette = palette_from(&CUSTOM_THEME_SETTING_KEYS, |key| settings.get(key))
+        .map_err(|err| format!("Custom theme: {err}"))?;
+    Ok(Theme::Custom(Arc::new(Custom::new(
+        CUSTOM_THEME_NAME.to_string(),
+        palette,
+    ))))
+}
+
+pub fn is_pywal_theme_name(name: &str) -> bool {
+    name.trim().eq_ignore_ascii_case(PYWAL_THEME_NAME)
+}
+
+/// Location of the pywal colors file, with a leading `~` expanded.
+pub fn pywal_colors_path(settings: &Settings) -> PathBuf {
+    let path = settings.get_or(PYWAL_COLORS_KEY, DEFAULT_PYWAL_COLORS);
+    match path.trim().strip_prefix("~/") {
+        Some(rest) => std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_default()
+            .join(rest),
+        None => PathBuf::from(path.trim()),
+    }
+}
+
+/// Map a pywal `colors.json` onto a palette: special background/foreground, then
+/// color4 (blue) as primary, color2 success, color3 warning, and color1 danger.
+fn pywal_palette(json: &str) -> Result<Palette, String> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|err| format!("invalid colors.json: {err}"))?;
+    let lookup = |section: &str, key: &str| {
+        value
+            .get(section)
+            .and_then(|section| section.get(key))
+            .and_then(Value::as_str)
+            .map(ToString::to_string)
+    };
+    palette_from(
+        &[
+            "special.background",
+            "special.foreground",
+            "colors.color4",
+            "colors.color2",
+            "colors.color3",
+            "colors.color1",
+        ],
+        |key| {
+            key.split_once('.')
+                .and_then(|(section, key)| lookup(section, key))
+        },
+    )
+}
+
+pub fn pywal_theme(path: &Path) -> Result<Theme, String> {
+    let json = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let palette = pywal_palette(&json).map_err(|err| format!("{}: {err}", path.display()))?;
+    Ok(Theme::Custom(Arc::new(Custom::new(
+        PYWAL_THEME_NAME.to_string(),
+        palette,
+    ))))
+}
 
-    for (index, key) in CUSTOM_THEME_SETTING_KEYS.iter().enumerate() {
-        match settings.get(key) {
-            Some(value) if !value.trim().is_empty() => values[index] = Some(value),
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn pywal_watch_stream() -> impl iced::futures::Stream<Item = Message> {
+    let (mut sender, receiver) = mpsc::channel(1);
+    let path = pywal_colors_path(crate::settings::settings());
+    std::thread::spawn(move || {
+        let mut last = modified_at(&path);
+        while !sender.is_closed() {
+            std::thread::sleep(PYWAL_POLL_INTERVAL);
+            let current = modified_at(&path);
+            if current.is_some() && current != last {
+                last = current;
+                if executor::block_on(sender.send(Message::ThemeFileChanged)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    receiver
+}
+
+/// Watch the pywal colors file while the Pywal theme is active.
+pub fn pywal_subscription() -> Subscription<Message> {
+    Subscription::run(pywal_watch_stream)
+}
+
+/// Build a palette from six color keys in `background, text, primary, success, warning,
+/// danger` order, reporting every missing key at once.
+fn palette_from(keys: &[&str; 6], get: impl Fn(&str) -> Option<String>) -> Result<Palette, String> {
+    let mut missing = Vec::new();
+    let mut colors = [Color::BLACK; 6];
+    for (color, key) in colors.iter_mut().zip(keys) {
+        match get(key) {
+            Some(value) if !value.trim().is_empty() => *color = parse_color_setting(key, &value)?,
             _ => missing.push(*key),
         }
     }
-
     if !missing.is_empty() {
-        return Err(format!(
-            "Custom theme requires settings for: {}",
-            missing.join(", ")
-        ));
+        return Err(format!("missing colors: {}", missing.join(", ")));
     }
+    let [background, text, primary, success, warning, danger] = colors;
+    Ok(Palette {
+        background,
+        text,
+        primary,
+        success,
+        warning,
+        danger,
+    })
+}
 
-    let background = parse_color_setting(
-        CUSTOM_THEME_SETTING_KEYS[0],
-        values[0].as_deref().unwrap_or(""),
-    )?;
-    let text = parse_color_setting(
-        CUSTOM_THEME_SETTING_KEYS[1],
-        values[1].as_deref().unwrap_or(""),
-    )?;
-    let primary = parse_color_setting(
-        CUSTOM_THEME_SETTING_KEYS[2],
-        values[2].as_deref().unwrap_or(""),
-    )?;
-    let success = parse_color_setting(
-        CUSTOM_THEME_SETTING_KEYS[3],
-        values[3].as_deref().unwrap_or(""),
-    )?;
-    let warning = parse_color_setting(
-        CUSTOM_THEME_SETTING_KEYS[4],
-        values[4].as_deref().unwrap_or(""),
-    )?;
-    let danger = parse_color_setting(
-        CUSTOM_THEME_SETTING_KEYS[5],
-        values[5].as_deref().unwrap_or(""),
-    )?;
+/// Directory scanned for user palette files: `$XDG_CONFIG_HOME/grelier/themes`.
+pub fn user_theme_dir() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_default()
+        .join("grelier")
+        .join("themes")
+}
 
-    Ok(Theme::Custom(Arc::new(Custom::new(
-        CUSTOM_THEME_NAME.to_string(),
-        Palette {
-            background,
-            text,
-            primary,
-            success,
-            warning,
-            danger,
-        },
-    ))))
+/// Load a palette file; the theme is named after the file stem.
+fn load_theme_file(path: &Path) -> Result<Theme, String> {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| format!("{}: missing theme name", path.display()))?;
+    let values = SettingsStorage::new(path.to_path_buf()).load()?;
+    let palette = palette_from(&USER_THEME_KEYS, |key| values.get(key).cloned())
+        .map_err(|err| format!("{}: {err}", path.display()))?;
+    Ok(Theme::Custom(Arc::new(Custom::new(name, palette))))
+}
+
+/// Every `*.xresources` palette in `dir`, sorted by name. Files that shadow a built-in
+/// theme or fail to parse are logged and skipped.
+fn load_user_themes(dir: &Path) -> Vec<Theme> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "xresources"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+
+    let mut themes: Vec<Theme> = Vec::new();
+    for path in paths {
+        match load_theme_file(&path) {
+            Ok(theme) => {
+                let name = theme.to_string();
+                let taken = builtin_theme(&name).is_some()
+                    || is_custom_theme_name(&name)
+                    || themes
+                        .iter()
+                        .any(|other| other.to_string().eq_ignore_ascii_case(&name));
+                if taken {
+                    log::warn!(
+                        "Ignoring theme file {}: '{name}' already exists",
+                        path.display()
+                    );
+                } else {
+                    themes.push(theme);
+                }
+            }
+            Err(err) => log::warn!("Ignoring theme file: {err}"),
+        }
+    }
+    themes
 }
 
+/// User themes from [`user_theme_dir`], loaded once per process.
+fn user_themes() -> &'static [Theme] {
+    static USER_THEMES: OnceLock<Vec<Theme>> = OnceLock::new();
+    USER_THEMES.get_or_init(|| load_user_themes(&user_theme_dir()))
+}
+
+/// Built-in theme names followed by user theme names.
+pub fn theme_names() -> Vec<String> {
+    VALID_THEME_NAMES
+        .iter()
+        .map(ToString::to_string)
+        .chain(user_themes().iter().map(ToString::to_string))
+        .collect()
+}
+
+/// Resolve a built-in or user theme by case-insensitive name.
 pub fn parse_theme(name: &str) -> Option<Theme> {
+    builtin_theme(name).or_else(|| {
+        user_themes()
+            .iter()
+            .find(|theme| theme.to_string().eq_ignore_ascii_case(name.trim()))
+            .cloned()
+    })
+}
+
+fn builtin_theme(name: &str) -> Option<Theme> {
     match name.trim().to_ascii_lowercase().as_str() {
         "catppuccinfrappe" => Some(Theme::CatppuccinFrappe),
         "catppuccinlatte" => Some(Theme::CatppuccinLatte),
@@ -151,7 +368,7 @@ fn parse_color_setting(key: &str, value: &str) -> Result<Color, String> {
     parse_hex_color(value).map_err(|err| format!("Invalid setting '{key}': {err}"))
 }
 
-fn parse_hex_color(value: &str) -> Result<Color, String> {
+pub(crate) fn parse_hex_color(value: &str) -> Result<Color, String> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
         return Err("empty color value".to_string());
@@ -249,4 +466,65 @@ mod tests {
 
         let _ = fs::remove_dir_all(dir);
     }
+
+    #[test]
+    fn theme_menu_checks_current_and_names_resolve() {
+        let menu = theme_menu("nord");
+        assert_eq!(menu.items.len(), theme_names().len());
+        let selected: Vec<&str> = menu
+            .items
+            .iter()
+            .filter(|item| item.selected)
+            .map(|item| item.id.as_str())
+            .collect();
+        assert_eq!(selected, vec!["Nord"]);
+
+        let (settings, dir) = build_settings(HashMap::new(), "menu");
+        assert_eq!(
+            theme_for_name("Dracula", &settings).expect("built-in theme"),
+            Theme::Dracula
+        );
+        assert!(theme_for_name("Nope", &settings).is_err());
+        assert!(theme_for_name("Custom", &settings).is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn maps_pywal_colors_onto_palette() {
+        let json = r##"{
+            "special": {"background": "#1d1f21", "foreground": "#c5c8c6", "cursor": "#c5c8c6"},
+            "colors": {"color0": "#1d1f21", "color1": "#cc6666", "color2": "#b5bd68",
+                       "color3": "#f0c674", "color4": "#81a2be", "color5": "#b294bb"}
+        }"##;
+        let palette = pywal_palette(json).expect("palette");
+        assert_eq!(palette.background, Color::from_rgb8(0x1d, 0x1f, 0x21));
+        assert_eq!(palette.primary, Color::from_rgb8(0x81, 0xa2, 0xbe));
+        assert_eq!(palette.danger, Color::from_rgb8(0xcc, 0x66, 0x66));
+        let err = pywal_palette(r#"{"special": {}}"#).unwrap_err();
+        assert!(err.contains("special.background"), "{err}");
+    }
+
+    #[test]
+    fn loads_user_palette_files() {
+        let (_, dir) = temp_storage_path("user_themes");
+        let palette = "background: #002B36\ntext: #839496\nprimary: #268BD2\n\
+                       success: #859900\nwarning: #B58900\ndanger: #DC322F\n";
+        fs::write(dir.join("Solarized.xresources"), palette).expect("write theme");
+        fs::write(dir.join("Nord.xresources"), palette).expect("write theme");
+        fs::write(dir.join("Broken.xresources"), "background: #000000\n").expect("write theme");
+        fs::write(dir.join("notes.txt"), palette).expect("write notes");
+
+        let themes = load_user_themes(&dir);
+        let names: Vec<String> = themes.iter().map(ToString::to_string).collect();
+        assert_eq!(names, vec!["Solarized"]);
+        assert_eq!(
+            themes[0].palette().primary,
+            Color::from_rgb8(0x26, 0x8B, 0xD2)
+        );
+        let err = load_theme_file(&dir.join("Broken.xresources")).unwrap_err();
+        assert!(err.contains("missing colors: text"), "{err}");
+
+        let _ = fs::remove_dir_all(dir);
+    }
 }
@@ -0,0 +1,226 @@
+// Shared unit preferences and formatting so gauges agree on temperature and data units.
+// Consumes Settings: grelier.units.*.
+use crate::settings::{self, Settings};
+use std::str::FromStr;
+
+const TEMPERATURE_KEY: &str = "grelier.units.temperature";
+const DATA_RATE_KEY: &str = "grelier.units.data_rate";
+const PREFIX_KEY: &str = "grelier.units.prefix";
+
+/// Unit used to show temperatures; readings are always collected in Celsius.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl FromStr for TemperatureUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "c" => Ok(TemperatureUnit::Celsius),
+            "f" => Ok(TemperatureUnit::Fahrenheit),
+            other => Err(format!(
+                "Invalid {TEMPERATURE_KEY} '{other}', expected 'c' or 'f'"
+            )),
+        }
+    }
+}
+
+/// Whether transfer rates are shown in bytes or bits per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataRateUnit {
+    #[default]
+    Bytes,
+    Bits,
+}
+
+impl FromStr for DataRateUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "bytes" => Ok(DataRateUnit::Bytes),
+            "bits" => Ok(DataRateUnit::Bits),
+            other => Err(format!(
+                "Invalid {DATA_RATE_KEY} '{other}', expected 'bytes' or 'bits'"
+            )),
+        }
+    }
+}
+
+/// Binary (1024, KiB) or decimal (1000, kB) multiples for sizes and rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnitPrefix {
+    #[default]
+    Iec,
+    Si,
+}
+
+impl FromStr for UnitPrefix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "iec" => Ok(UnitPrefix::Iec),
+            "si" => Ok(UnitPrefix::Si),
+            other => Err(format!(
+                "Invalid {PREFIX_KEY} '{other}', expected 'iec' or 'si'"
+            )),
+        }
+    }
+}
+
+impl UnitPrefix {
+    fn step(self) -> f64 {
+        match self {
+            UnitPrefix::Iec => 1024.0,
+            UnitPrefix::Si => 1000.0,
+        }
+    }
+
+    /// Multiple prefixes from kilo up to tera.
+    fn prefixes(self) -> [&'static str; 4] {
+        match self {
+            UnitPrefix::Iec => ["Ki", "Mi", "Gi", "Ti"],
+            UnitPrefix::Si => ["k", "M", "G", "T"],
+        }
+    }
+}
+
+/// Global unit preferences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Units {
+    pub temperature: TemperatureUnit,
+    pub data_rate: DataRateUnit,
+    pub prefix: UnitPrefix,
+}
+
+fn parse_setting<T: FromStr<Err = String> + Default>(settings: &Settings, key: &str) -> T {
+    match settings.get(key).map(|value| value.parse::<T>()) {
+        Some(Ok(value)) => value,
+        Some(Err(err)) => {
+            log::warn!("{err}; using the default");
+            T::default()
+        }
+        None => T::default(),
+    }
+}
+
+impl Units {
+    /// Read the unit preferences from the global settings store.
+    pub fn load() -> Self {
+        let settings = settings::settings();
+        Self {
+            temperature: parse_setting(settings, TEMPERATURE_KEY),
+            data_rate: parse_setting(settings, DATA_RATE_KEY),
+            prefix: parse_setting(settings, PREFIX_KEY),
+        }
+    }
+
+    /// Format a Celsius reading in the preferred unit with `decimals` fractional digits.
+    pub fn format_temperature(&self, celsius: f32, decimals: usize) -> String {
+        match self.temperature {
+            TemperatureUnit::Celsius => format!("{celsius:.decimals$}°C"),
+            TemperatureUnit::Fahrenheit => {
+                format!("{:.decimals$}°F", celsius * 9.0 / 5.0 + 32.0)
+            }
+        }
+    }
+
+    /// Format a byte count, e.g. `512 B`, `1.5 GiB`, or `1.6 GB`.
+    pub fn format_bytes(&self, bytes: u64) -> String {
+        let step = self.prefix.step();
+        let mut value = bytes as f64;
+        if value < step {
+            return format!("{value:.0} B");
+        }
+        let mut prefix = "";
+        for next in self.prefix.prefixes() {
+            if value < step {
+                break;
+            }
+            value /= step;
+            prefix = next;
+        }
+        format!("{} {prefix}B", format_scaled(value))
+    }
+
+    /// Format a transfer rate, starting at the kilo multiple, e.g. `3.2 MiB/s` or `27 Mbit/s`.
+    pub fn format_rate(&self, bytes_per_sec: f64) -> String {
+        let (mut value, suffix) = match self.data_rate {
+            DataRateUnit::Bytes => (bytes_per_sec.max(0.0), "B/s"),
+            DataRateUnit::Bits => (bytes_per_sec.max(0.0) * 8.0, "bit/s"),
+        };
+        let step = self.prefix.step();
+        let prefixes = self.prefix.prefixes();
+        value /= step;
+        let mut prefix = prefixes[0];
+        for next in &prefixes[1..3] {
+            if value < step {
+                break;
+            }
+            value /= step;
+            prefix = next;
+        }
+        format!("{} {prefix}{suffix}", format_scaled(value))
+    }
+}
+
+fn format_scaled(value: f64) -> String {
+    if value < 10.0 {
+        format!("{value:.1}")
+    } else {
+        format!("{value:.0}")
+    }
+}
+
+/// Reject unknown unit names at startup instead of silently falling back.
+pub fn validate_settings(settings: &Settings) -> Result<(), String> {
+    if let Some(value) = settings.get(TEMPERATURE_KEY) {
+        value.parse::<TemperatureUnit>()?;
+    }
+    if let Some(value) = settings.get(DATA_RATE_KEY) {
+        value.parse::<DataRateUnit>()?;
+    }
+    if let Some(value) = settings.get(PREFIX_KEY) {
+        value.parse::<UnitPrefix>()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_sizes_and_rates_per_preference() {
+        let iec = Units::default();
+        assert_eq!(iec.format_bytes(512), "512 B");
+        assert_eq!(iec.format_bytes(3 * 1024 * 1024 / 2), "1.5 MiB");
+        assert_eq!(iec.format_rate(2048.0), "2.0 KiB/s");
+
+        let si_bits = Units {
+            data_rate: DataRateUnit::Bits,
+            prefix: UnitPrefix::Si,
+            ..Units::default()
+        };
+        assert_eq!(si_bits.format_bytes(1_600_000_000), "1.6 GB");
+        assert_eq!(si_bits.format_rate(3_375_000.0), "27 Mbit/s");
+    }
+
+    #[test]
+    fn converts_temperatures_and_parses_names() {
+        let fahrenheit = Units {
+            temperature: TemperatureUnit::Fahrenheit,
+            ..Units::default()
+        };
+        assert_eq!(fahrenheit.format_temperature(100.0, 0), "212°F");
+        assert_eq!(Units::default().format_temperature(41.25, 1), "41.2°C");
+        assert_eq!("F".parse(), Ok(TemperatureUnit::Fahrenheit));
+        assert!("kelvin".parse::<TemperatureUnit>().is_err());
+        assert_eq!(" SI ".parse(), Ok(UnitPrefix::Si));
+    }
+}
@@ -0,0 +1,314 @@
+// Bar window bookkeeping: which layer surfaces are bars, which one is primary, which are
+// closing, and when an output change should recreate the bar.
+// After resume or hotplug the existing surface can go blank, so an output change recreates the
+// primary bar; the suppression rules here keep that from stacking up duplicate bars.
+use crate::bar::OutputSnapshot;
+use crate::monitor;
+use iced::window;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Output events this soon after a bar opened, or after the last handled change, are echoes
+/// of that change and do not recreate the bar again.
+const REOPEN_SUPPRESSION_WINDOW: Duration = Duration::from_millis(750);
+
+/// Lifecycle of the primary bar window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primary {
+    /// No bar is open and none was requested: before startup, or closed for clamshell mode.
+    Absent,
+    /// A new bar was requested and has not opened yet.
+    Pending,
+    /// The bar that dialogs and snapshots belong to.
+    Open(window::Id),
+}
+
+/// What a closed window meant for the bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarClosed {
+    /// Not the primary bar: a dialog, a secondary bar, or a bar already being replaced.
+    Other,
+    /// The primary bar closed and another open bar took over.
+    Promoted(window::Id),
+    /// The last bar closed on its own; the caller opens a new one.
+    Reopen,
+}
+
+/// Explicit state for the bar's windows; every transition goes through a method here.
+#[derive(Debug, Clone)]
+pub struct WindowTracker {
+    primary: Primary,
+    bars: HashSet<window::Id>,
+    /// Windows asked to close whose close event has not arrived; they render empty and are
+    /// never adopted as bars.
+    closing: HashSet<window::Id>,
+    last_bar_opened_at: Option<Instant>,
+    last_output_change_at: Option<Instant>,
+    last_outputs: Option<Vec<OutputSnapshot>>,
+}
+
+impl Default for WindowTracker {
+    fn default() -> Self {
+        Self {
+            primary: Primary::Absent,
+            bars: HashSet::new(),
+            closing: HashSet::new(),
+            last_bar_opened_at: None,
+            last_output_change_at: None,
+            last_outputs: None,
+        }
+    }
+}
+
+impl WindowTracker {
+    #[cfg(test)]
+    pub fn primary(&self) -> Primary {
+        self.primary
+    }
+
+    pub fn bars(&self) -> impl Iterator<Item = window::Id> + '_ {
+        self.bars.iter().copied()
+    }
+
+    pub fn is_closing(&self, window: window::Id) -> bool {
+        self.closing.contains(&window)
+    }
+
+    pub fn mark_closing(&mut self, windows: impl IntoIterator<Item = window::Id>) {
+        self.closing.extend(windows);
+    }
+
+    /// Stop treating `window` as closing, once it is gone or closed directly.
+    pub fn forget_closing(&mut self, window: window::Id) {
+        self.closing.remove(&window);
+    }
+
+    /// Outputs seen at the last output change.
+    pub fn last_outputs(&self) -> Option<&[OutputSnapshot]> {
+        self.last_outputs.as_deref()
+    }
+
+    pub fn record_outputs(&mut self, outputs: Vec<OutputSnapshot>) {
+        self.last_outputs = Some(outputs);
+    }
+
+    /// A surface that is not a dialog opened or changed; closing windows are ignored.
+    pub fn bar_opened(&mut self, window: window::Id, now: Instant) {
+        if self.closing.contains(&window) {
+            return;
+        }
+        if self.bars.insert(window) {
+            self.last_bar_opened_at = Some(now);
+        }
+        if !matches!(self.primary, Primary::Open(_)) {
+            self.primary = Primary::Open(window);
+        }
+    }
+
+    pub fn window_closed(&mut self, window: window::Id) -> BarClosed {
+        self.closing.remove(&window);
+        self.bars.remove(&window);
+        if self.primary != Primary::Open(window) {
+            return BarClosed::Other;
+        }
+        match self.bars.iter().next() {
+            Some(&next) => {
+                self.primary = Primary::Open(next);
+                BarClosed::Promoted(next)
+            }
+            None => {
+                self.primary = Primary::Pending;
+                BarClosed::Reopen
+            }
+        }
+    }
+
+    /// No bar is open and no surface is still waiting for its close event.
+    pub fn all_closed(&self) -> bool {
+        self.bars.is_empty() && self.closing.is_empty()
+    }
+
+    /// Mark every bar closing and return them for the caller to close. With `reopen` a
+    /// replacement is expected; without it the bar stays away until [`Self::request_bar`].
+    pub fn close_bars(&mut self, reopen: bool) -> Vec<window::Id> {
+        self.primary = if reopen {
+            Primary::Pending
+        } else {
+            Primary::Absent
+        };
+        let bars: Vec<window::Id> = self.bars.drain().collect();
+        self.closing.extend(bars.iter().copied());
+        bars
+    }
+
+    /// A new bar was requested while none is open.
+    pub fn request_bar(&mut self) {
+        self.primary = Primary::Pending;
+    }
+
+    /// Whether an output change should recreate the primary bar. `snapshot` is `None` when
+    /// outputs could not be read, which skips the comparison but not the suppression rules.
+    pub fn output_changed(&mut self, snapshot: Option<Vec<OutputSnapshot>>, now: Instant) -> bool {
+        if let Some(snapshot) = snapshot {
+            let active = monitor::has_active_outputs(&snapshot);
+            let previous = self.last_outputs.replace(snapshot);
+            // Nothing to draw on while every output is off, and nothing to compare at first.
+            let Some(previous) = previous else {
+                return false;
+            };
+            if !active
+                || !monitor::has_active_outputs(&previous)
+                || self
+                    .last_outputs
+                    .as_deref()
+                    .is_some_and(|current| monitor::outputs_equal(&previous, current))
+            {
+                return false;
+            }
+        }
+
+        let reopened_since_last_change = self
+            .last_output_change_at
+            .zip(self.last_bar_opened_at)
+            .is_some_and(|(changed, opened)| opened > changed);
+        if reopened_since_last_change {
+            self.last_output_change_at = Some(now);
+            return false;
+        }
+        let recent = |at: Option<Instant>| {
+            at.and_then(|at| now.checked_duration_since(at))
+                .is_some_and(|elapsed| elapsed < REOPEN_SUPPRESSION_WINDOW)
+        };
+        if recent(self.last_bar_opened_at) || recent(self.last_output_change_at) {
+            return false;
+        }
+        // One bar per output: those surfaces follow their outputs on their own.
+        if self.bars.len() > 1 {
+            self.last_output_change_at = Some(now);
+            return false;
+        }
+        if !matches!(self.primary, Primary::Open(_)) {
+            return false;
+        }
+        self.last_output_change_at = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(name: &str, active: bool, width: i32) -> OutputSnapshot {
+        OutputSnapshot {
+            name: name.to_string(),
+            active,
+            rect: (0, 0, width, 1080),
+            scale: 1.0,
+        }
+    }
+
+    fn opened(now: Instant) -> (WindowTracker, window::Id) {
+        let mut tracker = WindowTracker::default();
+        let bar = window::Id::unique();
+        tracker.bar_opened(bar, now);
+        tracker.record_outputs(vec![output("eDP-1", true, 1920)]);
+        (tracker, bar)
+    }
+
+    #[test]
+    fn hotplug_recreates_a_single_bar_once_and_ignores_echoes() {
+        let start = Instant::now();
+        let (mut tracker, bar) = opened(start);
+        assert_eq!(tracker.primary(), Primary::Open(bar));
+        let later = start + Duration::from_secs(5);
+        let docked = || {
+            Some(vec![
+                output("eDP-1", true, 1920),
+                output("DP-1", true, 2560),
+            ])
+        };
+
+        // Unchanged outputs and changes right after the bar opened do nothing.
+        assert!(!tracker.output_changed(Some(vec![output("eDP-1", true, 1920)]), later));
+        assert!(!tracker.output_changed(docked(), start + Duration::from_millis(100)));
+
+        tracker.record_outputs(vec![output("eDP-1", true, 1920)]);
+        assert!(tracker.output_changed(docked(), later));
+        let closing = tracker.close_bars(true);
+        assert_eq!(closing, vec![bar]);
+        assert_eq!(tracker.primary(), Primary::Pending);
+        assert!(tracker.is_closing(bar));
+
+        // While the replacement is pending, further changes do not pile up more bars.
+        let undocked = || Some(vec![output("eDP-1", true, 1280)]);
+        assert!(!tracker.output_changed(undocked(), later + Duration::from_secs(5)));
+
+        // A late event from the old surface does not make it a bar again.
+        tracker.bar_opened(bar, later);
+        assert_eq!(tracker.primary(), Primary::Pending);
+        let replacement = window::Id::unique();
+        tracker.bar_opened(replacement, later + Duration::from_secs(6));
+        assert_eq!(tracker.primary(), Primary::Open(replacement));
+        assert_eq!(tracker.window_closed(bar), BarClosed::Other);
+        assert!(!tracker.is_closing(bar));
+
+        // The echo of the change that caused the reopen is suppressed.
+        assert!(!tracker.output_changed(docked(), later + Duration::from_secs(6)));
+        assert_eq!(tracker.bars().collect::<Vec<_>>(), vec![replacement]);
+    }
+
+    #[test]
+    fn resume_waits_for_active_outputs_before_recreating() {
+        let start = Instant::now();
+        let (mut tracker, bar) = opened(start);
+        let asleep = start + Duration::from_secs(60);
+        assert!(!tracker.output_changed(Some(vec![output("eDP-1", false, 1920)]), asleep));
+        // The first change after outputs come back has nothing active to compare against.
+        let resumed = asleep + Duration::from_secs(30);
+        assert!(!tracker.output_changed(Some(vec![output("eDP-1", true, 1920)]), resumed));
+        assert_eq!(tracker.primary(), Primary::Open(bar));
+
+        // A resume that changed the mode recreates the bar, even if outputs could not be read
+        // later on.
+        let later = resumed + Duration::from_secs(30);
+        assert!(tracker.output_changed(Some(vec![output("eDP-1", true, 2880)]), later));
+        tracker.close_bars(true);
+        assert!(!tracker.output_changed(None, later + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn bars_and_dialogs_interleave_without_losing_the_primary() {
+        let start = Instant::now();
+        let (mut tracker, bar) = opened(start);
+        let second = window::Id::unique();
+        tracker.bar_opened(second, start);
+        assert_eq!(tracker.primary(), Primary::Open(bar));
+        assert!(
+            !tracker.output_changed(
+                Some(vec![output("eDP-1", true, 2560)]),
+                start + Duration::from_secs(5)
+            ),
+            "per-output bars are never recreated"
+        );
+
+        let dialog = window::Id::unique();
+        tracker.mark_closing([dialog]);
+        tracker.bar_opened(dialog, start);
+        assert_eq!(tracker.bars().count(), 2, "closing dialogs are not bars");
+        assert_eq!(tracker.window_closed(dialog), BarClosed::Other);
+
+        assert_eq!(tracker.window_closed(bar), BarClosed::Promoted(second));
+        assert_eq!(tracker.window_closed(second), BarClosed::Reopen);
+        assert_eq!(tracker.primary(), Primary::Pending);
+
+        // Clamshell: bars close without a replacement until one is requested.
+        let third = window::Id::unique();
+        tracker.bar_opened(third, start);
+        assert_eq!(tracker.close_bars(false), vec![third]);
+        assert_eq!(tracker.primary(), Primary::Absent);
+        assert_eq!(tracker.window_closed(third), BarClosed::Other);
+        tracker.request_bar();
+        assert_eq!(tracker.primary(), Primary::Pending);
+    }
+}
@@ -0,0 +1,341 @@
+// End-to-end tests against a headless sway: each test starts its own sway with two virtual
+// outputs, runs the bar on the first one, drives the pointer with `swaymsg seat ... cursor`, and
+// checks the bar's state dump over the control socket.
+//
+// Needs `sway` and `swaymsg` on PATH. Run with:
+//
+//     cargo test --features sway-integration --test sway_headless
+#![cfg(feature = "sway-integration")]
+
+use serde_json::Value;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long sway and the bar get to come up.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(20);
+/// How long the bar gets to reflect an input or compositor change.
+const STATE_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Two outputs keep both workspaces alive without any client windows.
+const SWAY_CONFIG: &str = "\
+output HEADLESS-1 resolution 1280x720 position 0 0
+output HEADLESS-2 resolution 1280x720 position 1280 0
+workspace 1 output HEADLESS-1
+workspace 2 output HEADLESS-2
+";
+
+/// Bar layout pinned so the click targets below are stable.
+const BAR_SETTINGS: &[&str] = &[
+    "grelier.bar.orientation=left",
+    "grelier.bar.width=28",
+    "grelier.panels=workspaces,gauges",
+    "grelier.gauges=clock",
+    "grelier.app.workspace.padding_y=2",
+    "grelier.app.workspace.button_padding_y=4",
+];
+/// Inside the first workspace button on the HEADLESS-1 bar.
+const FIRST_WORKSPACE: (i32, i32) = (14, 12);
+/// Empty bar background between the workspace buttons and the gauges.
+const BACKGROUND: (i32, i32) = (14, 360);
+
+/// Poll `check` until it returns a value, failing the test after `timeout`.
+fn wait_for<T>(what: &str, timeout: Duration, mut check: impl FnMut() -> Option<T>) -> T {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(value) = check() {
+            return value;
+        }
+        if Instant::now() >= deadline {
+            panic!("timed out waiting for {what}");
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// First entry of `dir` whose file name satisfies `matches`.
+fn find_entry(dir: &Path, matches: impl Fn(&str) -> bool) -> Option<PathBuf> {
+    fs::read_dir(dir).ok()?.flatten().find_map(|entry| {
+        let name = entry.file_name();
+        matches(&name.to_string_lossy()).then(|| entry.path())
+    })
+}
+
+/// A headless sway and, once started, a bar running inside it. Both are killed on drop.
+struct Session {
+    dir: PathBuf,
+    sway: Child,
+    sway_socket: PathBuf,
+    wayland_display: String,
+    bar: Option<Child>,
+    bar_socket: PathBuf,
+    snapshots: usize,
+}
+
+impl Session {
+    fn start(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("grelier-sway-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("create runtime dir");
+        // Wayland refuses runtime directories other users can read.
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700)).expect("restrict runtime dir");
+        let config = dir.join("sway.conf");
+        fs::write(&config, SWAY_CONFIG).expect("write sway config");
+        let log = fs::File::create(dir.join("sway.log")).expect("create sway log");
+
+        let sway = Command::new("sway")
+            .arg("-c")
+            .arg(&config)
+            .env("XDG_RUNTIME_DIR", &dir)
+            .env("WLR_BACKENDS", "headless")
+            .env("WLR_HEADLESS_OUTPUTS", "2")
+            .env("WLR_LIBINPUT_NO_DEVICES", "1")
+            .env("WLR_RENDERER", "pixman")
+            .env_remove("WAYLAND_DISPLAY")
+            .env_remove("DISPLAY")
+            .env_remove("SWAYSOCK")
+            .stdout(Stdio::null())
+            .stderr(log)
+            .spawn()
+            .expect("sway must be on PATH to run the sway integration tests");
+
+        let sway_socket = wait_for("the sway IPC socket", STARTUP_TIMEOUT, || {
+            find_entry(&dir, |name| {
+                name.starts_with("sway-ipc.") && name.ends_with(".sock")
+            })
+        });
+        let wayland_display = wait_for("the Wayland socket", STARTUP_TIMEOUT, || {
+            find_entry(&dir, |name| {
+                name.starts_with("wayland-") && !name.ends_with(".lock")
+            })
+        })
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .expect("socket name");
+
+        Self {
+            dir,
+            sway,
+            sway_socket,
+            wayland_display,
+            bar: None,
+            bar_socket: PathBuf::new(),
+            snapshots: 0,
+        }
+    }
+
+    /// Run `swaymsg` against this sway and return its JSON reply.
+    fn swaymsg(&self, args: &[&str]) -> Value {
+        let output = Command::new("swaymsg")
+            .arg("-s")
+            .arg(&self.sway_socket)
+            .arg("-r")
+            .args(args)
+            .output()
+            .expect("swaymsg must be on PATH");
+        assert!(
+            output.status.success(),
+            "swaymsg {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        serde_json::from_slice(&output.stdout).expect("swaymsg reply is JSON")
+    }
+
+    /// Name of the workspace sway reports as focused.
+    fn sway_focused_workspace(&self) -> Option<String> {
+        self.swaymsg(&["-t", "get_workspaces"])
+            .as_array()?
+            .iter()
+            .find(|workspace| workspace["focused"].as_bool() == Some(true))
+            .and_then(|workspace| workspace["name"].as_str())
+            .map(ToString::to_string)
+    }
+
+    /// Press and release `button` (1 = left, 3 = right) at `position` in layout coordinates.
+    fn click(&self, (x, y): (i32, i32), button: u8) {
+        let (x, y, button) = (x.to_string(), y.to_string(), format!("button{button}"));
+        self.swaymsg(&["seat", "seat0", "cursor", "set", &x, &y]);
+        self.swaymsg(&["seat", "seat0", "cursor", "press", &button]);
+        self.swaymsg(&["seat", "seat0", "cursor", "release", &button]);
+    }
+
+    fn start_bar(&mut self) {
+        let config_home = self.dir.join("config");
+        let mut command = Command::new(env!("CARGO_BIN_EXE_grelier"));
+        command
+            .arg("-c")
+            .arg(self.dir.join("settings"))
+            .args(["--on-monitor", "HEADLESS-1"]);
+        for setting in BAR_SETTINGS {
+            command.args(["-s", setting]);
+        }
+        let log = fs::File::create(self.dir.join("grelier.log")).expect("create bar log");
+        let bar = command
+            .env("XDG_RUNTIME_DIR", &self.dir)
+            .env("WAYLAND_DISPLAY", &self.wayland_display)
+            .env("SWAYSOCK", &self.sway_socket)
+            // Keep the user's plugins, themes, and caches out of the test.
+            .env("XDG_CONFIG_HOME", &config_home)
+            .env("XDG_DATA_HOME", self.dir.join("data"))
+            .env("XDG_CACHE_HOME", self.dir.join("cache"))
+            .env_remove("NIRI_SOCKET")
+            .env_remove("HYPRLAND_INSTANCE_SIGNATURE")
+            .stdout(Stdio::null())
+            .stderr(log)
+            .spawn()
+            .expect("spawn grelier");
+        self.bar_socket = self.dir.join(format!("grelier-{}.sock", bar.id()));
+        self.bar = Some(bar);
+        let socket = self.bar_socket.clone();
+        wait_for("the bar's control socket", STARTUP_TIMEOUT, || {
+            UnixStream::connect(&socket).ok()
+        });
+    }
+
+    /// Send one control-socket command to the bar and return its JSON reply.
+    fn bar_command(&self, line: &str) -> Value {
+        let mut stream = UnixStream::connect(&self.bar_socket).expect("connect to the bar");
+        stream
+            .set_read_timeout(Some(STATE_TIMEOUT))
+            .expect("set read timeout");
+        stream
+            .write_all(format!("{line}\n").as_bytes())
+            .expect("send command");
+        let mut reply = String::new();
+        BufReader::new(stream)
+            .read_line(&mut reply)
+            .expect("read reply");
+        serde_json::from_str(&reply).expect("reply is JSON")
+    }
+
+    /// Take a snapshot and return the reply along with the parsed state dump.
+    fn snapshot(&mut self) -> (Value, Value) {
+        self.snapshots += 1;
+        let dir = self.dir.join(format!("snapshot-{}", self.snapshots));
+        let reply = self.bar_command(&format!("snapshot {}", dir.display()));
+        let state_path = reply["files"][0]
+            .as_str()
+            .unwrap_or_else(|| panic!("snapshot failed: {reply}"));
+        let state = fs::read_to_string(state_path).expect("read state dump");
+        (
+            reply.clone(),
+            serde_json::from_str(&state).expect("state is JSON"),
+        )
+    }
+
+    /// Poll the bar's state until `check` accepts it.
+    fn wait_for_state(&mut self, what: &str, check: impl Fn(&Value) -> bool) -> Value {
+        wait_for(what, STATE_TIMEOUT, || {
+            let (_, state) = self.snapshot();
+            check(&state).then_some(state)
+        })
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        for child in self.bar.iter_mut().chain(std::iter::once(&mut self.sway)) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        // Logs are kept when a test fails, to see what the bar and sway printed.
+        if !thread::panicking() {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+fn workspace_names(state: &Value) -> Vec<String> {
+    state["workspaces"]
+        .as_array()
+        .map(|workspaces| {
+            workspaces
+                .iter()
+                .filter_map(|workspace| workspace["name"].as_str().map(ToString::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn dialog_gauges(state: &Value) -> Vec<String> {
+    state["dialogs"]
+        .as_array()
+        .map(|dialogs| {
+            dialogs
+                .iter()
+                .filter_map(|dialog| dialog["gauge"].as_str().map(ToString::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[test]
+fn workspaces_render() {
+    let mut session = Session::start("render");
+    session.start_bar();
+
+    let state = session.wait_for_state("both workspaces on the bar", |state| {
+        workspace_names(state) == ["1", "2"]
+    });
+    assert_eq!(state["compositor"], "sway");
+
+    let (reply, _) = session.snapshot();
+    assert_eq!(reply["ok"], true, "snapshot reported errors: {reply}");
+    let screenshot = reply["files"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .find(|file| file.ends_with("-bar0.png"))
+        .expect("a screenshot of the bar");
+    assert!(fs::metadata(screenshot).expect("screenshot file").len() > 0);
+}
+
+#[test]
+fn clicking_a_workspace_focuses_it() {
+    let mut session = Session::start("focus");
+    session.start_bar();
+    session.swaymsg(&["workspace", "2"]);
+    session.wait_for_state("workspace 2 focused", |state| {
+        state["current_workspace"] == "2"
+    });
+
+    session.click(FIRST_WORKSPACE, 1);
+
+    session.wait_for_state("workspace 1 focused", |state| {
+        state["current_workspace"] == "1"
+    });
+    assert_eq!(session.sway_focused_workspace().as_deref(), Some("1"));
+}
+
+#[test]
+fn context_menu_opens_and_closes() {
+    let mut session = Session::start("dialogs");
+    session.start_bar();
+    session.wait_for_state("the bar to load", |state| {
+        !workspace_names(state).is_empty()
+    });
+
+    session.click(BACKGROUND, 3);
+    let state = session.wait_for_state("the theme menu to open", |state| {
+        dialog_gauges(state) == ["bar_theme"]
+    });
+    let items = &state["dialogs"][0]["lines"];
+    assert!(
+        items
+            .as_array()
+            .is_some_and(|items| items.iter().any(|item| item == "Gauge diagnostics")),
+        "menu items: {items}"
+    );
+
+    session.click(BACKGROUND, 1);
+    session.wait_for_state("the theme menu to close", |state| {
+        dialog_gauges(state).is_empty()
+    });
+}